@@ -0,0 +1,10 @@
+fn main() {
+    // The gRPC code is only generated when the server is built with it
+    #[cfg(feature = "grpc")]
+    {
+        std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path().unwrap());
+        tonic_build::compile_protos("proto/game.proto").unwrap();
+        println!("cargo:rerun-if-changed=proto/game.proto");
+    }
+    println!("cargo:rerun-if-changed=build.rs");
+}
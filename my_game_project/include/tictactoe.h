@@ -0,0 +1,151 @@
+#ifndef TICTACTOE_H
+#define TICTACTOE_H
+
+/* Generated from src/ffi.rs by `cbindgen --config cbindgen.toml --output include/tictactoe.h src/ffi.rs`. Don't edit it by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// What is in a cell, and also how players are passed to C
+typedef enum TttCell {
+  // No mark, or no player
+  TttCell_Empty = 0,
+  // X's mark
+  TttCell_X = 1,
+  // O's mark
+  TttCell_O = 2,
+} TttCell;
+
+// Whether a game is still going and how it ended, as in [`GameStatus`]
+typedef enum TttStatus {
+  // The game is still being played
+  TttStatus_InProgress = 0,
+  // A player completed a line
+  TttStatus_Won = 1,
+  // The board filled up, or the players agreed to a draw
+  TttStatus_Draw = 2,
+  // A player resigned
+  TttStatus_Resigned = 3,
+  // A player ran out of time
+  TttStatus_TimedOut = 4,
+} TttStatus;
+
+// How well the AI plays, as in [`Difficulty`]
+//
+// Functions take it as a plain `uint32_t`, since a value outside the enum
+// would be undefined behaviour in Rust.
+typedef enum TttDifficulty {
+  // Random moves
+  TttDifficulty_Easy = 0,
+  // A shallow search
+  TttDifficulty_Medium = 1,
+  // Perfect play where the board allows it
+  TttDifficulty_Hard = 2,
+} TttDifficulty;
+
+// A game played through the C functions, along with its history
+typedef struct TttGame TttGame;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Creates a standard 3x3 game, to be released with [`ttt_game_free`]
+struct TttGame *ttt_game_new(void);
+
+// Creates a game on a `size` x `size` board won by `win_length` marks in a row
+//
+// Returns null if there's no such game, such as when `win_length` is longer
+// than the board.
+struct TttGame *ttt_game_with_size(size_t size, size_t win_length);
+
+// Releases a game
+//
+// # Safety
+//
+// `game` must be null or a game from [`ttt_game_new`] or [`ttt_game_with_size`]
+// that hasn't been released yet.
+void ttt_game_free(struct TttGame *game);
+
+// Places a mark at (`row`, `col`) for the player whose turn it is
+//
+// # Safety
+//
+// `game` must be a live game from [`ttt_game_new`] or [`ttt_game_with_size`].
+bool ttt_game_make_move(struct TttGame *game, size_t row, size_t col);
+
+// Lets the AI move for the player whose turn it is
+//
+// `difficulty` is one of the [`TttDifficulty`] values. Where the mark landed
+// is written to `row` and `col`, unless they are null.
+//
+// # Safety
+//
+// `game` must be a live game from [`ttt_game_new`] or [`ttt_game_with_size`],
+// and `row` and `col` must each be null or valid to write to.
+bool ttt_game_ai_move(struct TttGame *game, uint32_t difficulty, size_t *row, size_t *col);
+
+// Returns the number of rows on the board
+//
+// # Safety
+//
+// `game` must be a live game from [`ttt_game_new`] or [`ttt_game_with_size`].
+size_t ttt_game_rows(const struct TttGame *game);
+
+// Returns the number of columns on the board
+//
+// # Safety
+//
+// `game` must be a live game from [`ttt_game_new`] or [`ttt_game_with_size`].
+size_t ttt_game_cols(const struct TttGame *game);
+
+// Copies the board into `cells` a row at a time, returning how many cells it has
+//
+// Nothing is copied if `len` is less than that, so passing a null `cells`
+// and a `len` of 0 finds out how big a buffer is needed.
+//
+// # Safety
+//
+// `game` must be a live game from [`ttt_game_new`] or [`ttt_game_with_size`],
+// and `cells` must be valid for writing `len` cells.
+size_t ttt_game_board(const struct TttGame *game, enum TttCell *cells, size_t len);
+
+// Returns the player whose turn it is
+//
+// # Safety
+//
+// `game` must be a live game from [`ttt_game_new`] or [`ttt_game_with_size`].
+enum TttCell ttt_game_current_turn(const struct TttGame *game);
+
+// Returns whether the game is still going, and how it ended if not
+//
+// # Safety
+//
+// `game` must be a live game from [`ttt_game_new`] or [`ttt_game_with_size`].
+enum TttStatus ttt_game_status(const struct TttGame *game);
+
+// Returns the winner, or [`TttCell::Empty`] if there isn't one
+//
+// # Safety
+//
+// `game` must be a live game from [`ttt_game_new`] or [`ttt_game_with_size`].
+enum TttCell ttt_game_winner(const struct TttGame *game);
+
+// Returns a description of the last error on this thread, or null if there hasn't been one
+//
+// The string stays valid until the next error on the same thread.
+const char *ttt_last_error(void);
+
+// Returns the code of the last error on this thread, like `CELL_OCCUPIED`, or null if there hasn't been one
+//
+// The string stays valid until the next error on the same thread.
+const char *ttt_last_error_code(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* TICTACTOE_H */
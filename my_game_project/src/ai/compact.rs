@@ -0,0 +1,186 @@
+use serde::{Deserialize, Serialize};
+
+use crate::game::{Cell, GameState};
+use crate::player::Player;
+use crate::rules::Variant;
+use crate::symmetry::Symmetry;
+
+/// Number of cells on a compact board
+const CELLS: usize = 9;
+
+/// Every line of three on a 3x3 board, as a mask of its cells
+const WIN_MASKS: [u16; 8] = [
+    0b000_000_111, // Rows
+    0b000_111_000,
+    0b111_000_000,
+    0b001_001_001, // Columns
+    0b010_010_010,
+    0b100_100_100,
+    0b100_010_001, // Diagonals
+    0b001_010_100,
+];
+
+/// Mask of every cell on the board
+const FULL: u16 = (1 << CELLS) - 1;
+
+/// A standard 3x3 position packed into a bitmask per player
+///
+/// Cell (`row`, `col`) is bit `3 * row + col`. Boards are `Copy` and
+/// hashable, so they make cheap keys for tables of solved positions, and
+/// checking for a win is an AND against each of the eight lines. They
+/// serialize as the two masks and the player to move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CompactBoard {
+    /// Cells holding an X
+    x: u16,
+    /// Cells holding an O
+    o: u16,
+    /// The player to move
+    turn: Player,
+}
+
+impl CompactBoard {
+    /// An empty board with `first` to move
+    pub fn new(first: Player) -> Self {
+        Self { x: 0, o: 0, turn: first }
+    }
+
+    /// Pack a game's board, if it's standard 3x3 tic-tac-toe
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::ai::CompactBoard;
+    /// use my_game_project::game::GameState;
+    /// use my_game_project::player::Player;
+    ///
+    /// let mut game = GameState::new();
+    /// game.make_move((1, 1)).unwrap();
+    ///
+    /// let board = CompactBoard::from_game(&game).unwrap();
+    /// assert_eq!(board, CompactBoard::new(Player::X).play((1, 1)));
+    /// assert!(CompactBoard::from_game(&GameState::with_size(4, 3).unwrap()).is_none());
+    /// ```
+    pub fn from_game(game: &GameState) -> Option<Self> {
+        let standard = game.variant == Variant::Standard && game.win_length == 3;
+        if !standard || game.rows() != 3 || game.cols() != 3 {
+            return None;
+        }
+
+        let mut board = Self::new(game.current_turn);
+        for (bit, cell) in game.board.iter().flatten().enumerate() {
+            match cell {
+                Cell::Occupied(Player::X) => board.x |= 1 << bit,
+                Cell::Occupied(Player::O) => board.o |= 1 << bit,
+                Cell::Empty => {}
+            }
+        }
+        Some(board)
+    }
+
+    /// The player to move
+    pub fn turn(&self) -> Player {
+        self.turn
+    }
+
+    /// The board after the player to move plays at `cell`, which must be empty
+    pub fn play(self, (row, col): (usize, usize)) -> Self {
+        let bit = 1 << (3 * row + col);
+        match self.turn {
+            Player::X => Self { x: self.x | bit, turn: Player::O, ..self },
+            Player::O => Self { o: self.o | bit, turn: Player::X, ..self },
+        }
+    }
+
+    /// The player with three in a row, if either has one
+    pub fn winner(&self) -> Option<Player> {
+        let has_line = |marks: u16| WIN_MASKS.iter().any(|&mask| mask & !marks == 0);
+        if has_line(self.x) {
+            Some(Player::X)
+        } else if has_line(self.o) {
+            Some(Player::O)
+        } else {
+            None
+        }
+    }
+
+    /// Returns true if every cell is occupied
+    pub fn is_full(&self) -> bool {
+        self.x | self.o == FULL
+    }
+
+    /// Returns true if the game is won or drawn
+    pub fn is_over(&self) -> bool {
+        self.is_full() || self.winner().is_some()
+    }
+
+    /// Number of empty cells
+    pub fn empty_count(&self) -> usize {
+        CELLS - (self.x | self.o).count_ones() as usize
+    }
+
+    /// The board turned or mirrored by `symmetry`
+    pub fn transformed(self, symmetry: Symmetry) -> Self {
+        let mut image = Self { x: 0, o: 0, ..self };
+        for bit in 0..CELLS {
+            let (row, col) = symmetry.apply((bit / 3, bit % 3), 3, 3);
+            let image_bit = 3 * row + col;
+            image.x |= (self.x >> bit & 1) << image_bit;
+            image.o |= (self.o >> bit & 1) << image_bit;
+        }
+        image
+    }
+
+    /// The board's canonical form, and the symmetry that turns the board into it
+    ///
+    /// Boards that are rotations or reflections of each other share a
+    /// canonical form, so tables keyed by it store each position once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::ai::CompactBoard;
+    /// use my_game_project::player::Player;
+    ///
+    /// let corner = CompactBoard::new(Player::X).play((0, 0));
+    /// let other_corner = CompactBoard::new(Player::X).play((2, 2));
+    /// assert_eq!(corner.canonical().0, other_corner.canonical().0);
+    /// ```
+    pub fn canonical(self) -> (Self, Symmetry) {
+        Symmetry::ALL
+            .into_iter()
+            .map(|symmetry| (self.transformed(symmetry), symmetry))
+            .min_by_key(|(image, _)| image.x as u32 | (image.o as u32) << CELLS)
+            .expect("there is always the identity")
+    }
+
+    /// The empty cells in row-major order, or none once the game is over
+    pub fn legal_moves(&self) -> impl Iterator<Item = (usize, usize)> {
+        let empty = if self.is_over() { 0 } else { !(self.x | self.o) & FULL };
+        (0..CELLS).filter(move |bit| empty & (1 << bit) != 0).map(|bit| (bit / 3, bit % 3))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameStatus;
+
+    #[test]
+    fn test_agrees_with_game_state() {
+        let mut game = GameState::new();
+        let mut board = CompactBoard::new(Player::X);
+
+        for cell in [(0, 0), (1, 1), (0, 1), (2, 2), (0, 2)] {
+            assert_eq!(board.legal_moves().collect::<Vec<_>>(), game.legal_moves().collect::<Vec<_>>());
+            game.make_move(cell).unwrap();
+            board = board.play(cell);
+        }
+
+        assert_eq!(game.status, GameStatus::Won(Player::X));
+        assert_eq!(board.winner(), Some(Player::X));
+        assert!(board.is_over());
+        assert_eq!(board.legal_moves().count(), 0);
+        assert_eq!(board.empty_count(), 4);
+    }
+}
@@ -0,0 +1,199 @@
+use std::cmp::Reverse;
+use std::sync::Mutex;
+
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use super::minimax::MinimaxAI;
+use crate::error::GameResult;
+use crate::game::GameState;
+use crate::player::{GamePlayer, Player};
+use crate::position::Position;
+
+/// How a [`FallibleAI`] picks among the worse moves when it blunders
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Blunders {
+    /// Every worse move is equally likely
+    Uniform,
+    /// Each worse move is `ratio` times as likely as the one ranked just above it
+    ///
+    /// Small ratios make near misses far more common than throwing the game
+    /// away, and a ratio of 0 always plays the second best move.
+    Geometric(f64),
+}
+
+impl Default for Blunders {
+    fn default() -> Self {
+        Blunders::Geometric(0.5)
+    }
+}
+
+impl Blunders {
+    /// Pick one of `count` moves, ranked from best to worst
+    fn pick(self, count: usize, rng: &mut StdRng) -> usize {
+        match self {
+            Blunders::Uniform => rng.gen_range(0..count),
+            Blunders::Geometric(ratio) => {
+                let weights = (0..count).map(|rank| ratio.powi(rank as i32));
+                WeightedIndex::new(weights).map(|index| index.sample(rng)).unwrap_or(0)
+            }
+        }
+    }
+}
+
+/// An AI that plays like a person: usually well, but not always
+///
+/// Each turn it plays the move the wrapped [`MinimaxAI`] would with
+/// probability `accuracy`. Otherwise it plays one of the moves that AI would
+/// have considered but scores lower, chosen by its [`Blunders`]. Unlike an
+/// easy AI it never plays a move far from the action on a large board, so its
+/// mistakes look like a human's.
+///
+/// # Examples
+///
+/// ```
+/// use my_game_project::ai::{Blunders, Difficulty, FallibleAI, MinimaxAI};
+/// use my_game_project::game::GameState;
+/// use my_game_project::player::{GamePlayer, Player};
+///
+/// let ai = FallibleAI::new(MinimaxAI::new(Player::X, Difficulty::Hard), 0.8)
+///     .with_blunders(Blunders::Uniform);
+///
+/// assert_eq!(ai.get_name(), "AI (80% accurate)");
+/// assert!(ai.get_move(&GameState::new()).is_ok());
+/// ```
+pub struct FallibleAI {
+    /// The AI whose moves are played when it doesn't blunder
+    ai: MinimaxAI,
+    /// The chance of playing the AI's move, between 0 and 1
+    accuracy: f64,
+    /// How the move is picked when it blunders
+    blunders: Blunders,
+    /// The random number generator deciding when and how to blunder
+    rng: Mutex<StdRng>,
+}
+
+impl FallibleAI {
+    /// Create an AI that plays `ai`'s move with probability `accuracy`
+    ///
+    /// `accuracy` is clamped to between 0 and 1.
+    pub fn new(ai: MinimaxAI, accuracy: f64) -> Self {
+        Self::with_rng(ai, accuracy, StdRng::from_entropy())
+    }
+
+    /// Create an AI whose blunders are reproducible
+    ///
+    /// Give `ai` a seed too for its own random choices to be reproducible.
+    pub fn new_with_seed(ai: MinimaxAI, accuracy: f64, seed: u64) -> Self {
+        Self::with_rng(ai, accuracy, StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(ai: MinimaxAI, accuracy: f64, rng: StdRng) -> Self {
+        Self {
+            ai,
+            accuracy: accuracy.clamp(0.0, 1.0),
+            blunders: Blunders::default(),
+            rng: Mutex::new(rng),
+        }
+    }
+
+    /// Pick worse moves with `blunders` instead of the default
+    pub fn with_blunders(mut self, blunders: Blunders) -> Self {
+        self.blunders = blunders;
+        self
+    }
+
+    /// Choose a move, which is the wrapped AI's unless this turn is a blunder
+    pub fn choose_move(&self, game: &GameState) -> GameResult<Position> {
+        if let Some(cell) = self.choose_blunder(game)? {
+            return Ok(Position::from(cell));
+        }
+        self.ai.choose_move(game).map(|(position, _)| position)
+    }
+
+    /// Decide whether to blunder and pick the worse move to play if so
+    ///
+    /// Returns `None` to play the best move, which is also the case when every
+    /// move is equally good.
+    fn choose_blunder(&self, game: &GameState) -> GameResult<Option<(usize, usize)>> {
+        let mut rng = self.rng.lock().unwrap_or_else(|e| e.into_inner());
+        if rng.gen_bool(self.accuracy) {
+            return Ok(None);
+        }
+
+        let mut moves = self.ai.score_moves(game)?;
+        let Some(best) = moves.iter().map(|&(_, score)| score).max() else {
+            return Ok(None);
+        };
+        moves.retain(|&(_, score)| score < best);
+        if moves.is_empty() {
+            return Ok(None);
+        }
+
+        // Rank from best to worst, keeping the search order between equal scores
+        moves.sort_by_key(|&(_, score)| Reverse(score));
+        let (cell, _) = moves[self.blunders.pick(moves.len(), &mut rng)];
+        Ok(Some(cell))
+    }
+}
+
+impl GamePlayer for FallibleAI {
+    fn get_move(&self, game: &GameState) -> GameResult<Position> {
+        self.choose_move(game)
+    }
+
+    fn respond_to_draw_offer(&self, game: &GameState) -> GameResult<bool> {
+        Ok(self.ai.accepts_draw(game))
+    }
+
+    fn get_player_type(&self) -> Player {
+        self.ai.get_player_type()
+    }
+
+    fn get_name(&self) -> String {
+        format!("AI ({:.0}% accurate)", self.accuracy * 100.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::Difficulty;
+
+    /// A position where O can win at (1, 2), block X at (0, 2), or lose
+    fn winning_chance() -> GameState {
+        let mut game = GameState::new();
+        for cell in [(0, 0), (1, 0), (0, 1), (1, 1), (2, 2)] {
+            game.make_move(cell).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn test_accurate_ai_plays_the_best_move() {
+        let ai = FallibleAI::new_with_seed(MinimaxAI::new(Player::O, Difficulty::Hard), 1.0, 7);
+
+        for _ in 0..10 {
+            assert_eq!(ai.get_move(&winning_chance()).unwrap(), Position::new(1, 2));
+        }
+    }
+
+    #[test]
+    fn test_blunders_are_worse_moves() {
+        let game = winning_chance();
+        let ai = FallibleAI::new_with_seed(MinimaxAI::new(Player::O, Difficulty::Hard), 0.0, 7)
+            .with_blunders(Blunders::Uniform);
+
+        for _ in 0..10 {
+            assert_ne!(ai.get_move(&game).unwrap(), Position::new(1, 2));
+        }
+
+        // Only ever missing by as little as possible means blocking instead of winning
+        let ai = ai.with_blunders(Blunders::Geometric(0.0));
+        for _ in 0..10 {
+            assert_eq!(ai.get_move(&game).unwrap(), Position::new(0, 2));
+        }
+    }
+}
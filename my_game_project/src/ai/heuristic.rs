@@ -0,0 +1,180 @@
+use crate::error::{GameError, GameResult};
+use crate::game::{Cell, GameState};
+use crate::player::{GamePlayer, Player};
+use crate::position::Position;
+
+/// An AI player that follows the classic tic-tac-toe strategy rules instead of searching
+///
+/// On each turn it takes the first of these moves that applies: win, block
+/// the opponent's win, fork (threaten two wins at once), block the opponent's
+/// fork, take the center, take the corner opposite the opponent, take a
+/// corner, take a side. It never loses standard tic-tac-toe, and decides
+/// instantly on any board.
+pub struct HeuristicAI {
+    /// The player type (X or O)
+    player_type: Player,
+}
+
+impl HeuristicAI {
+    /// Create a new rule-based AI player
+    pub fn new(player_type: Player) -> Self {
+        Self { player_type }
+    }
+
+    /// Pick a move by the first strategy rule that applies
+    fn choose_move(&self, game: &GameState) -> GameResult<(usize, usize)> {
+        let (me, them) = (self.player_type, self.player_type.opponent());
+        let legal: Vec<_> = game.legal_moves().collect();
+        let (last_row, last_col) = (game.rows() - 1, game.cols() - 1);
+        let corners = [(0, 0), (0, last_col), (last_row, 0), (last_row, last_col)];
+
+        // Win, block, fork, then block a fork
+        if let Some(&cell) = winning_cells(game, me).first() {
+            return Ok(cell);
+        }
+        if let Some(&cell) = winning_cells(game, them).first() {
+            return Ok(cell);
+        }
+        if let Some(&cell) = fork_cells(game, me).first() {
+            return Ok(cell);
+        }
+        if let Some(cell) = self.block_fork(game, &legal) {
+            return Ok(cell);
+        }
+
+        // Center, the corner opposite the opponent, any corner
+        let center = (game.rows() / 2, game.cols() / 2);
+        if legal.contains(&center) {
+            return Ok(center);
+        }
+        let opposite_corner = corners
+            .iter()
+            .filter(|&&(row, col)| game.board[row][col] == Cell::Occupied(them))
+            .map(|&(row, col)| (last_row - row, last_col - col))
+            .find(|cell| legal.contains(cell));
+        if let Some(cell) = opposite_corner {
+            return Ok(cell);
+        }
+        if let Some(&cell) = corners.iter().find(|cell| legal.contains(cell)) {
+            return Ok(cell);
+        }
+
+        // A side, or failing that any cell
+        legal
+            .iter()
+            .find(|&&(row, col)| row == 0 || col == 0 || row == last_row || col == last_col)
+            .or(legal.first())
+            .copied()
+            .ok_or(GameError::NoValidMoves)
+    }
+
+    /// Find a move that stops the opponent forking, if they can
+    ///
+    /// A single fork is blocked by taking its cell. Against several, the AI
+    /// threatens a win of its own so the opponent must block it somewhere
+    /// that doesn't give them a fork.
+    fn block_fork(&self, game: &GameState, legal: &[(usize, usize)]) -> Option<(usize, usize)> {
+        let (me, them) = (self.player_type, self.player_type.opponent());
+        let forks = fork_cells(game, them);
+
+        match forks.as_slice() {
+            [] => None,
+            [cell] => Some(*cell),
+            _ => legal
+                .iter()
+                .copied()
+                .find(|&cell| {
+                    let Some(next) = after_move(game, me, cell) else {
+                        return false;
+                    };
+                    match winning_cells(&next, me).as_slice() {
+                        [block] => after_move(&next, them, *block)
+                            .is_some_and(|after| winning_cells(&after, them).len() < 2),
+                        _ => false,
+                    }
+                })
+                .or(forks.first().copied()),
+        }
+    }
+}
+
+/// Returns the game after `player` plays at `cell`, whoever's turn it is
+fn after_move(game: &GameState, player: Player, cell: (usize, usize)) -> Option<GameState> {
+    let mut next = game.clone();
+    next.current_turn = player;
+    next.make_move(cell).ok()?;
+    Some(next)
+}
+
+/// Returns the cells where `player` would win by playing next
+fn winning_cells(game: &GameState, player: Player) -> Vec<(usize, usize)> {
+    game.legal_moves()
+        .filter(|&cell| {
+            after_move(game, player, cell).is_some_and(|next| next.status.winner() == Some(player))
+        })
+        .collect()
+}
+
+/// Returns the cells where `player` would threaten two wins at once
+fn fork_cells(game: &GameState, player: Player) -> Vec<(usize, usize)> {
+    game.legal_moves()
+        .filter(|&cell| {
+            after_move(game, player, cell)
+                .is_some_and(|next| !next.is_over() && winning_cells(&next, player).len() >= 2)
+        })
+        .collect()
+}
+
+impl GamePlayer for HeuristicAI {
+    fn get_move(&self, game: &GameState) -> GameResult<Position> {
+        self.choose_move(game).map(Position::from)
+    }
+
+    fn get_player_type(&self) -> Player {
+        self.player_type
+    }
+
+    fn get_name(&self) -> String {
+        "AI (Rules)".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::{Difficulty, MinimaxAI};
+    use crate::game::GameStatus;
+
+    #[test]
+    fn test_blocks_fork_with_a_side() {
+        let mut game = GameState::new();
+        for position in [(0, 0), (1, 1), (2, 2)] {
+            game.make_move(position).unwrap();
+        }
+
+        // A corner would let X fork; O has to force X to block on a side
+        let (row, col) = HeuristicAI::new(Player::O).choose_move(&game).unwrap();
+        assert!(row == 1 || col == 1);
+        assert_ne!((row, col), (1, 1));
+    }
+
+    #[test]
+    fn test_draws_against_perfect_play() {
+        for heuristic_player in [Player::X, Player::O] {
+            let heuristic = HeuristicAI::new(heuristic_player);
+            let minimax = MinimaxAI::new(heuristic_player.opponent(), Difficulty::Hard);
+            let mut game = GameState::new();
+
+            while !game.is_over() {
+                let position = if game.current_turn == heuristic_player {
+                    heuristic.get_move(&game).unwrap()
+                } else {
+                    minimax.get_move(&game).unwrap()
+                };
+                game.make_move(position).unwrap();
+            }
+
+            assert_eq!(game.status, GameStatus::Draw);
+        }
+    }
+}
@@ -1,178 +1,628 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Mutex;
 use std::time::Instant;
 
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
 use serde::{Deserialize, Serialize};
+use tracing::{debug, instrument};
 
+use super::opening_book::OpeningBook;
+use super::perfect;
+use super::stats::SearchStats;
+use super::transposition::TranspositionTable;
+use super::weights::HeuristicWeights;
 use crate::error::{GameError, GameResult};
-use crate::game::{Cell, GameState, GameStatus};
+use crate::game::{Cell, GameState};
 use crate::player::{GamePlayer, Player};
+use crate::position::Position;
+use crate::rules::LINE_DIRECTIONS;
+
+/// Score of a won position, before adjusting for how many moves it takes
+pub(crate) const WIN_SCORE: i32 = 1_000_000;
+
+/// Boards with at most this many cells are searched to the end on Hard
+const FULL_SEARCH_CELLS: usize = 16;
+
+/// Search depth on Hard for boards too big to search to the end
+const HARD_DEPTH: usize = 6;
+
+/// Boards with more than this many cells only search moves next to existing marks
+const LARGE_BOARD_CELLS: usize = 100;
+
+/// Search depth on Hard for large boards
+const LARGE_BOARD_DEPTH: usize = 3;
 
 /// Difficulty levels for the AI
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Difficulty {
     /// Easy difficulty - makes random valid moves
+    #[serde(alias = "Easy")]
     Easy,
     /// Medium difficulty - uses minimax but with limited depth
+    #[serde(alias = "Medium")]
     Medium,
-    /// Hard difficulty - uses full minimax algorithm
+    /// Hard difficulty - uses full minimax algorithm on small boards
+    #[serde(alias = "Hard")]
     Hard,
 }
 
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Difficulty::Easy => write!(f, "easy"),
+            Difficulty::Medium => write!(f, "medium"),
+            Difficulty::Hard => write!(f, "hard"),
+        }
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = GameError;
+
+    /// Parses a difficulty by name, in any case
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::ai::Difficulty;
+    ///
+    /// assert_eq!("hard".parse::<Difficulty>().unwrap(), Difficulty::Hard);
+    /// assert_eq!("Medium".parse::<Difficulty>().unwrap(), Difficulty::Medium);
+    /// assert!("impossible".parse::<Difficulty>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "easy" => Ok(Difficulty::Easy),
+            "medium" => Ok(Difficulty::Medium),
+            "hard" => Ok(Difficulty::Hard),
+            _ => Err(GameError::InvalidPlayerType(format!("unknown difficulty {}", s))),
+        }
+    }
+}
+
+/// Which legal moves are worth searching in a position
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Candidates {
+    /// Every legal move
+    All,
+    /// Moves next to an existing mark
+    NearMarks,
+    /// Only the center, when there are no moves near a mark
+    Center,
+}
+
+impl Candidates {
+    /// Decide which moves to search in a position
+    fn for_game(game: &GameState) -> Self {
+        if game.rows() * game.cols() <= LARGE_BOARD_CELLS {
+            Candidates::All
+        } else if game.legal_moves().any(|(row, col)| near_mark(game, row, col)) {
+            Candidates::NearMarks
+        } else {
+            Candidates::Center
+        }
+    }
+
+    /// Returns true if the legal move at (`row`, `col`) should be searched
+    fn includes(self, game: &GameState, row: usize, col: usize) -> bool {
+        match self {
+            Candidates::All => true,
+            Candidates::NearMarks => near_mark(game, row, col),
+            Candidates::Center => (row, col) == (game.rows() / 2, game.cols() / 2),
+        }
+    }
+}
+
+/// The cells where a player threatens to complete a run, counted up to two
+#[derive(Debug, Default)]
+struct Threats {
+    /// The first threatened cell found
+    first: Option<(usize, usize)>,
+    /// Whether a second, different cell is threatened too
+    several: bool,
+}
+
+impl Threats {
+    /// Record a threatened cell
+    fn add(&mut self, cell: (usize, usize)) {
+        match self.first {
+            None => self.first = Some(cell),
+            Some(first) if first != cell => self.several = true,
+            Some(_) => {}
+        }
+    }
+}
+
+/// Counts the runs of `win_length` cells on the board that pass through (`row`, `col`)
+fn runs_through(game: &GameState, row: usize, col: usize) -> i32 {
+    let (rows, cols) = (game.rows() as isize, game.cols() as isize);
+    let length = game.win_length as isize;
+    let on_board = |r: isize, c: isize| (0..rows).contains(&r) && (0..cols).contains(&c);
+
+    let mut runs = 0;
+    for &(dr, dc) in &LINE_DIRECTIONS {
+        // Runs starting up to `length - 1` cells back along the direction
+        for back in 0..length {
+            let (start_row, start_col) = (row as isize - dr * back, col as isize - dc * back);
+            let (end_row, end_col) = (start_row + dr * (length - 1), start_col + dc * (length - 1));
+            if on_board(start_row, start_col) && on_board(end_row, end_col) {
+                runs += 1;
+            }
+        }
+    }
+    runs
+}
+
+/// Returns true if any cell next to (`row`, `col`) holds a mark
+fn near_mark(game: &GameState, row: usize, col: usize) -> bool {
+    (row.saturating_sub(1)..=row + 1).any(|r| {
+        (col.saturating_sub(1)..=col + 1).any(|c| {
+            matches!(game.board.get(r).and_then(|cells| cells.get(c)), Some(Cell::Occupied(_)))
+        })
+    })
+}
+
 /// An AI player that uses the minimax algorithm
 pub struct MinimaxAI {
     /// The player type (X or O)
     player_type: Player,
     /// The difficulty level
     difficulty: Difficulty,
+    /// The weights positions that aren't over are scored with
+    weights: HeuristicWeights,
+    /// The random number generator used for random moves and openings
+    rng: Mutex<StdRng>,
+    /// Statistics from the search for the last move played
+    last_search: Mutex<Option<SearchStats>>,
 }
 
 impl MinimaxAI {
     /// Create a new AI player
     pub fn new(player_type: Player, difficulty: Difficulty) -> Self {
-        Self { player_type, difficulty }
+        Self {
+            player_type,
+            difficulty,
+            weights: HeuristicWeights::default(),
+            rng: Mutex::new(StdRng::from_entropy()),
+            last_search: Mutex::new(None),
+        }
+    }
+
+    /// Create a new AI player whose random choices are reproducible
+    ///
+    /// Two AIs created with the same seed make the same moves for the same positions.
+    pub fn new_with_seed(player_type: Player, difficulty: Difficulty, seed: u64) -> Self {
+        Self {
+            player_type,
+            difficulty,
+            weights: HeuristicWeights::default(),
+            rng: Mutex::new(StdRng::seed_from_u64(seed)),
+            last_search: Mutex::new(None),
+        }
+    }
+
+    /// Score unfinished positions with `weights` instead of the defaults
+    pub fn with_weights(mut self, weights: HeuristicWeights) -> Self {
+        self.weights = weights;
+        self
     }
 
-    /// Get the maximum search depth based on difficulty
-    fn get_max_depth(&self) -> usize {
+    /// Get the maximum search depth based on difficulty and board size
+    fn get_max_depth(&self, game: &GameState) -> usize {
+        let cells = game.rows() * game.cols();
         match self.difficulty {
             Difficulty::Easy => 1,
+            Difficulty::Medium if cells > LARGE_BOARD_CELLS => 2,
             Difficulty::Medium => 3,
-            Difficulty::Hard => 9, // Full search for tic-tac-toe
+            Difficulty::Hard if cells <= FULL_SEARCH_CELLS => cells, // Full search
+            Difficulty::Hard if cells > LARGE_BOARD_CELLS => LARGE_BOARD_DEPTH,
+            Difficulty::Hard => HARD_DEPTH,
         }
     }
 
-    /// Evaluate the board state for the minimax algorithm
-    fn evaluate(&self, game: &GameState) -> i32 {
-        match game.status {
-            GameStatus::Won(player) => {
-                if player == self.player_type {
-                    10 // AI wins
-                } else {
-                    -10 // AI loses
+    /// Get the moves worth searching in a position
+    ///
+    /// On large boards only cells next to an existing mark are considered (or
+    /// the center of an empty board), since moves far from the action are
+    /// rarely good and searching them all would be far too slow.
+    fn candidate_moves(&self, game: &GameState) -> Vec<(usize, usize)> {
+        let candidates = Candidates::for_game(game);
+        game.legal_moves().filter(|&(row, col)| candidates.includes(game, row, col)).collect()
+    }
+
+    /// Evaluate the board state `depth` moves into the search
+    ///
+    /// Wins and losses dominate every other score, preferring shorter paths
+    /// to victory and longer paths to defeat. Positions that aren't over are
+    /// scored with a heuristic.
+    fn evaluate(&self, game: &GameState, depth: usize) -> i32 {
+        match game.status.winner() {
+            Some(player) if player == self.player_type => WIN_SCORE - depth as i32, // AI wins
+            Some(_) => depth as i32 - WIN_SCORE, // AI loses
+            None if game.is_over() => 0, // Draw
+            None => self.heuristic(game),
+        }
+    }
+
+    /// Score a position that is still in progress by the lines each player could complete
+    ///
+    /// Every run of `win_length` cells containing marks of only one player
+    /// counts for that player, with runs closer to completion weighted more.
+    /// Marks also count for the number of runs through their cell, favouring
+    /// the center and corners, and a player threatening to complete two lines
+    /// at once (or one on their own turn) gets a bonus, as the threat can't be
+    /// stopped. When completing a line loses, the score counts against the
+    /// player instead. How much each of these counts is set by the AI's
+    /// [`HeuristicWeights`].
+    fn heuristic(&self, game: &GameState) -> i32 {
+        let (rows, cols) = (game.rows() as isize, game.cols() as isize);
+        let length = game.win_length as isize;
+        let mut score = 0;
+
+        // The cells completing a run for each side
+        let (mut my_threats, mut their_threats) = (Threats::default(), Threats::default());
+
+        for row in 0..rows {
+            for col in 0..cols {
+                for &(dr, dc) in &LINE_DIRECTIONS {
+                    // Skip runs that would leave the board
+                    let (end_row, end_col) = (row + dr * (length - 1), col + dc * (length - 1));
+                    if end_row >= rows || end_col < 0 || end_col >= cols {
+                        continue;
+                    }
+
+                    let (mut mine, mut theirs, mut empty) = (0, 0, None);
+                    for i in 0..length {
+                        let (r, c) = ((row + dr * i) as usize, (col + dc * i) as usize);
+                        match game.board[r][c] {
+                            Cell::Occupied(player) if player == self.player_type => mine += 1,
+                            Cell::Occupied(_) => theirs += 1,
+                            Cell::Empty => empty = Some((r, c)),
+                        }
+                    }
+
+                    match (mine, theirs) {
+                        (0, 0) => {}
+                        (mine, 0) => score += self.weights.run_worth(mine),
+                        (0, theirs) => score -= self.weights.run_worth(theirs),
+                        _ => {} // Blocked for both players
+                    }
+
+                    // A run one mark short of complete is a threat to win on its empty cell
+                    match (mine, theirs, empty) {
+                        (mine, 0, Some(cell)) if mine == length - 1 => my_threats.add(cell),
+                        (0, theirs, Some(cell)) if theirs == length - 1 => their_threats.add(cell),
+                        _ => {}
+                    }
                 }
             }
-            GameStatus::Draw => 0, // Draw
-            GameStatus::InProgress => 0, // Game still in progress
         }
+
+        // Marks on cells with more runs through them, like the center, are worth more
+        for (row, cells) in game.board.iter().enumerate() {
+            for (col, cell) in cells.iter().enumerate() {
+                match cell {
+                    Cell::Occupied(player) if *player == self.player_type => {
+                        score += self.weights.position * runs_through(game, row, col)
+                    }
+                    Cell::Occupied(_) => score -= self.weights.position * runs_through(game, row, col),
+                    Cell::Empty => {}
+                }
+            }
+        }
+
+        // Worth more than any number of runs one mark short of complete
+        let unstoppable = self.weights.threat.saturating_mul(self.weights.run_worth(length));
+        let my_turn = game.current_turn == self.player_type;
+        if my_threats.several || (my_turn && my_threats.first.is_some()) {
+            score += unstoppable;
+        }
+        if their_threats.several || (!my_turn && their_threats.first.is_some()) {
+            score -= unstoppable;
+        }
+
+        if !game.rules().lines_win() {
+            score = -score;
+        }
+        score.clamp(-WIN_SCORE / 2, WIN_SCORE / 2)
     }
 
     /// Find the best move using the minimax algorithm
+    #[cfg(test)]
     fn find_best_move(&self, game: &GameState) -> GameResult<(usize, usize)> {
+        self.search(game).map(|(cell, _)| cell)
+    }
+
+    /// Find the best move, along with statistics about the search for it
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::ai::{Difficulty, MinimaxAI};
+    /// use my_game_project::game::GameState;
+    /// use my_game_project::player::Player;
+    ///
+    /// let game = GameState::with_size(4, 3).unwrap();
+    /// let ai = MinimaxAI::new(Player::X, Difficulty::Medium);
+    /// let (position, stats) = ai.choose_move(&game).unwrap();
+    ///
+    /// assert_eq!(stats.principal_variation[0], position);
+    /// assert!(stats.nodes > 0);
+    /// ```
+    #[instrument(
+        level = "debug",
+        skip_all,
+        fields(game_id = %game.id, player = ?self.player_type, difficulty = ?self.difficulty),
+    )]
+    pub fn choose_move(&self, game: &GameState) -> GameResult<(Position, SearchStats)> {
+        let (cell, stats) = self.search(game)?;
+        debug!(nodes = stats.nodes, depth = stats.depth, elapsed = ?stats.elapsed, "search finished");
+        Ok((Position::from(cell), stats))
+    }
+
+    /// Find the best move and the statistics of the search for it
+    fn search(&self, game: &GameState) -> GameResult<((usize, usize), SearchStats)> {
+        let started = Instant::now();
+
         // If it's easy difficulty, just make a random valid move
         if self.difficulty == Difficulty::Easy {
-            return self.find_random_move(game);
+            let cell = self.find_random_move(game)?;
+            return Ok((cell, SearchStats::new(0, vec![cell], started)));
+        }
+
+        // Standard 3x3 games are already solved, with a choice of equally good openings
+        if self.difficulty == Difficulty::Hard {
+            let mut rng = self.rng.lock().unwrap_or_else(|e| e.into_inner());
+            let book_move = OpeningBook::get().choose(game, &mut *rng);
+            if let Some(cell) = book_move.or_else(|| perfect::best_move(game)) {
+                let (nodes, line) = perfect::principal_variation(game, cell);
+                return Ok((cell, SearchStats::new(nodes, line, started)));
+            }
         }
 
-        let max_depth = self.get_max_depth();
+        let max_depth = self.get_max_depth(game);
+        let mut table = TranspositionTable::new();
         let mut best_score = i32::MIN;
         let mut best_move = None;
 
-        // Try each empty cell
-        for row in 0..3 {
-            for col in 0..3 {
-                if let Cell::Empty = game.board[row][col] {
-                    // Make a temporary move
-                    let mut game_copy = game.clone();
-                    game_copy.make_move(row, col)?;
-
-                    // Calculate score for this move
-                    let score = self.minimax(&game_copy, 0, max_depth, false);
-
-                    // Update best move if this is better
-                    if score > best_score {
-                        best_score = score;
-                        best_move = Some((row, col));
-                    }
-                }
+        // Search on a single copy of the game, making and taking back each move
+        let mut board = game.clone();
+
+        // Try each candidate move
+        for (row, col) in self.candidate_moves(game) {
+            // Calculate score for this move, only searching for lines that beat the best so far
+            let undo = board.make_move_unchecked(row, col);
+            let score = self.minimax(&mut board, 0, max_depth, best_score, i32::MAX, false, &mut table);
+            board.unmake_move(undo);
+
+            // Update best move if this is better
+            if score > best_score {
+                best_score = score;
+                best_move = Some((row, col));
             }
         }
 
-        best_move.ok_or_else(|| GameError::NoValidMoves)
+        let cell = best_move.ok_or(GameError::NoValidMoves)?;
+        let mut stats = SearchStats::new(table.nodes(), principal_variation(game, cell, &table), started);
+        stats.depth = stats.depth.max(table.depth());
+        Ok((cell, stats))
+    }
+
+    /// Score every move worth searching for the player to move
+    ///
+    /// Each move is searched with a full window, so unlike the scores used to
+    /// pick a move, these are exact. Positive scores are good for this AI.
+    pub(crate) fn score_moves(&self, game: &GameState) -> GameResult<Vec<((usize, usize), i32)>> {
+        let mut table = TranspositionTable::new();
+
+        self.candidate_moves(game)
+            .into_iter()
+            .map(|(row, col)| Ok(((row, col), self.search_move(game, row, col, &mut table)?)))
+            .collect()
+    }
+
+    /// Score every legal move for the player to move, keyed by where it's played
+    ///
+    /// Unlike [`score_moves`](Self::score_moves), moves the AI wouldn't
+    /// consider on a large board are scored too. Positive scores are good for
+    /// this AI, so create it for the player to move to rate that player's options.
+    pub fn evaluate_all(&self, game: &GameState) -> HashMap<Position, i32> {
+        let mut table = TranspositionTable::new();
+        let moves: Vec<_> = game.legal_moves().collect();
+
+        moves
+            .into_iter()
+            .filter_map(|(row, col)| {
+                let score = self.search_move(game, row, col, &mut table).ok()?;
+                Some((Position::new(row, col), score))
+            })
+            .collect()
+    }
+
+    /// Score a single move for the player to move, searched with a full window
+    pub(crate) fn score_move(&self, game: &GameState, row: usize, col: usize) -> GameResult<i32> {
+        self.search_move(game, row, col, &mut TranspositionTable::new())
+    }
+
+    /// Score a move with a full window, sharing `table` with other searches of the same position
+    fn search_move(
+        &self,
+        game: &GameState,
+        row: usize,
+        col: usize,
+        table: &mut TranspositionTable,
+    ) -> GameResult<i32> {
+        let max_depth = self.get_max_depth(game);
+        let is_maximizing = game.current_turn != self.player_type;
+
+        let mut game_copy = game.clone();
+        game_copy.make_move((row, col))?;
+        Ok(self.minimax(&mut game_copy, 0, max_depth, i32::MIN, i32::MAX, is_maximizing, table))
+    }
+
+    /// Decide whether to accept a draw, which the AI does unless it expects to win
+    pub fn accepts_draw(&self, game: &GameState) -> bool {
+        let max_depth = self.get_max_depth(game);
+        let is_maximizing = game.current_turn == self.player_type;
+        let mut table = TranspositionTable::new();
+        self.minimax(&mut game.clone(), 0, max_depth, i32::MIN, i32::MAX, is_maximizing, &mut table) <= 0
     }
 
     /// Find a random valid move
     fn find_random_move(&self, game: &GameState) -> GameResult<(usize, usize)> {
-        let mut empty_cells = Vec::new();
-
         // Find all empty cells
-        for row in 0..3 {
-            for col in 0..3 {
-                if let Cell::Empty = game.board[row][col] {
-                    empty_cells.push((row, col));
-                }
-            }
-        }
+        let empty_cells: Vec<(usize, usize)> = game.legal_moves().collect();
 
         // Pick a random empty cell
-        if empty_cells.is_empty() {
-            return Err(GameError::NoValidMoves);
-        }
-
-        let random_index = (Instant::now().elapsed().as_nanos() % empty_cells.len() as u128) as usize;
-        Ok(empty_cells[random_index])
+        let mut rng = self.rng.lock().unwrap_or_else(|e| e.into_inner());
+        empty_cells
+            .choose(&mut *rng)
+            .copied()
+            .ok_or(GameError::NoValidMoves)
     }
 
-    /// The minimax algorithm implementation
-    fn minimax(&self, game: &GameState, depth: usize, max_depth: usize, is_maximizing: bool) -> i32 {
+    /// The minimax algorithm implementation with alpha-beta pruning
+    ///
+    /// `alpha` is the score the maximizing player is already assured of and
+    /// `beta` the score the minimizing player is already assured of. Once
+    /// they cross, the remaining moves cannot affect the result and are skipped.
+    ///
+    /// Positions reached by transposed moves are looked up in `table` rather
+    /// than searched again. Moves are made and taken back on `game` itself,
+    /// which is left as it was found.
+    #[allow(clippy::too_many_arguments)]
+    fn minimax(
+        &self,
+        game: &mut GameState,
+        depth: usize,
+        max_depth: usize,
+        mut alpha: i32,
+        mut beta: i32,
+        is_maximizing: bool,
+        table: &mut TranspositionTable,
+    ) -> i32 {
+        table.visit(depth + 1);
+
         // Base cases: terminal state or maximum depth reached
-        if game.status != GameStatus::InProgress || depth == max_depth {
-            return self.evaluate(game) - depth as i32; // Prefer shorter paths to victory
+        if game.is_over() || depth == max_depth {
+            return self.evaluate(game, depth);
+        }
+
+        // Reuse the result of searching this position, or a rotation or reflection of it, before
+        let (hash, symmetry) = game.canonical_hash();
+        if let Some(score) = table.probe(hash, &mut alpha, &mut beta) {
+            return score;
         }
+        let (window_alpha, window_beta) = (alpha, beta);
+
+        // Candidate cells in row-major order, checked as they come up rather than collected
+        let candidates = Candidates::for_game(game);
+        let (rows, cols) = (game.rows(), game.cols());
+        let cells = (0..rows * cols).map(|index| (index / cols, index % cols));
 
         if is_maximizing {
             // Maximizing player (AI)
             let mut best_score = i32::MIN;
+            let mut best_move = None;
 
-            // Try each empty cell
-            for row in 0..3 {
-                for col in 0..3 {
-                    if let Cell::Empty = game.board[row][col] {
-                        // Make a temporary move
-                        let mut game_copy = game.clone();
-                        if game_copy.make_move(row, col).is_ok() {
-                            // Calculate score for this move
-                            let score = self.minimax(&game_copy, depth + 1, max_depth, false);
-                            best_score = best_score.max(score);
-                        }
-                    }
+            // Try each candidate move
+            for (row, col) in cells {
+                if !game.is_legal_move(row, col) || !candidates.includes(game, row, col) {
+                    continue;
+                }
+
+                // Calculate score for this move
+                let undo = game.make_move_unchecked(row, col);
+                let score = self.minimax(game, depth + 1, max_depth, alpha, beta, false, table);
+                game.unmake_move(undo);
+                if score > best_score {
+                    best_score = score;
+                    best_move = Some((row, col));
+                }
+                alpha = alpha.max(score);
+
+                // The opponent will never allow this line
+                if alpha >= beta {
+                    break;
                 }
             }
 
+            let best_move = best_move.map(|cell| symmetry.apply(cell, rows, cols));
+            table.store(hash, best_score, window_alpha, window_beta, best_move);
             best_score
         } else {
             // Minimizing player (opponent)
             let mut best_score = i32::MAX;
+            let mut best_move = None;
 
-            // Try each empty cell
-            for row in 0..3 {
-                for col in 0..3 {
-                    if let Cell::Empty = game.board[row][col] {
-                        // Make a temporary move
-                        let mut game_copy = game.clone();
-                        if game_copy.make_move(row, col).is_ok() {
-                            // Calculate score for this move
-                            let score = self.minimax(&game_copy, depth + 1, max_depth, true);
-                            best_score = best_score.min(score);
-                        }
-                    }
+            // Try each candidate move
+            for (row, col) in cells {
+                if !game.is_legal_move(row, col) || !candidates.includes(game, row, col) {
+                    continue;
+                }
+
+                // Calculate score for this move
+                let undo = game.make_move_unchecked(row, col);
+                let score = self.minimax(game, depth + 1, max_depth, alpha, beta, true, table);
+                game.unmake_move(undo);
+                if score < best_score {
+                    best_score = score;
+                    best_move = Some((row, col));
+                }
+                beta = beta.min(score);
+
+                // The AI already has a better alternative elsewhere
+                if alpha >= beta {
+                    break;
                 }
             }
 
+            let best_move = best_move.map(|cell| symmetry.apply(cell, rows, cols));
+            table.store(hash, best_score, window_alpha, window_beta, best_move);
             best_score
         }
     }
 }
 
-impl GamePlayer for MinimaxAI {
-    fn get_move(&self, game: &GameState) -> GameResult<(usize, usize)> {
-        println!("AI is thinking...");
-        let start = Instant::now();
+/// Follow the best moves stored in `table` from the position after `first`
+fn principal_variation(game: &GameState, first: (usize, usize), table: &TranspositionTable) -> Vec<(usize, usize)> {
+    let mut line = vec![first];
+    let mut next = game.clone();
+    let mut cell = Some(first);
+
+    while let Some(current) = cell {
+        if next.make_move(current).is_err() || next.is_over() {
+            break;
+        }
+        // The table holds moves for the canonical form, so turn them back
+        let (hash, symmetry) = next.canonical_hash();
+        cell = table
+            .best_move(hash)
+            .map(|cell| symmetry.inverse().apply(cell, next.rows(), next.cols()));
+        line.extend(cell);
+    }
+
+    line
+}
 
-        let result = self.find_best_move(game);
+impl GamePlayer for MinimaxAI {
+    fn get_move(&self, game: &GameState) -> GameResult<Position> {
+        let (position, stats) = self.choose_move(game)?;
+        *self.last_search.lock().unwrap_or_else(|e| e.into_inner()) = Some(stats);
+        Ok(position)
+    }
 
-        let duration = start.elapsed();
-        println!("AI decided in {:.2?}", duration);
+    fn search_stats(&self) -> Option<SearchStats> {
+        self.last_search.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
 
-        result
+    fn respond_to_draw_offer(&self, game: &GameState) -> GameResult<bool> {
+        Ok(self.accepts_draw(game))
     }
 
     fn get_player_type(&self) -> Player {
@@ -183,3 +633,189 @@ impl GamePlayer for MinimaxAI {
         format!("AI ({:?})", self.difficulty)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameStatus;
+    use crate::rules::Variant;
+
+    #[test]
+    fn test_hard_ai_takes_win() {
+        let mut game = GameState::new();
+        game.make_move((0, 0)).unwrap(); // X
+        game.make_move((1, 0)).unwrap(); // O
+        game.make_move((0, 1)).unwrap(); // X
+        game.make_move((1, 1)).unwrap(); // O
+        game.make_move((2, 2)).unwrap(); // X
+
+        // O can win immediately at (1, 2) instead of blocking X at (0, 2)
+        let ai = MinimaxAI::new(Player::O, Difficulty::Hard);
+        assert_eq!(ai.find_best_move(&game).unwrap(), (1, 2));
+    }
+
+    #[test]
+    fn test_hard_ai_blocks_loss() {
+        let mut game = GameState::new();
+        game.make_move((0, 0)).unwrap(); // X
+        game.make_move((1, 1)).unwrap(); // O
+        game.make_move((0, 1)).unwrap(); // X threatens (0, 2)
+
+        let ai = MinimaxAI::new(Player::O, Difficulty::Hard);
+        assert_eq!(ai.find_best_move(&game).unwrap(), (0, 2));
+    }
+
+    #[test]
+    fn test_hard_ai_never_loses_to_itself() {
+        let x = MinimaxAI::new(Player::X, Difficulty::Hard);
+        let o = MinimaxAI::new(Player::O, Difficulty::Hard);
+        let mut game = GameState::new();
+
+        while !game.is_over() {
+            let ai = if game.current_turn == Player::X { &x } else { &o };
+            let (row, col) = ai.find_best_move(&game).unwrap();
+            game.make_move((row, col)).unwrap();
+        }
+
+        assert_eq!(game.status, GameStatus::Draw);
+    }
+
+    #[test]
+    fn test_evaluate_all_scores_every_legal_move() {
+        let mut game = GameState::new();
+        game.make_move((0, 0)).unwrap(); // X
+        game.make_move((1, 1)).unwrap(); // O
+        game.make_move((0, 1)).unwrap(); // X threatens (0, 2)
+
+        let ai = MinimaxAI::new(Player::O, Difficulty::Hard);
+        let scores = ai.evaluate_all(&game);
+        assert_eq!(scores.len(), game.legal_moves().count());
+
+        // Only blocking holds the draw; every other move loses
+        assert_eq!(scores[&Position::new(0, 2)], 0);
+        assert!(scores.iter().all(|(&position, &score)| position == Position::new(0, 2) || score < 0));
+    }
+
+    #[test]
+    fn test_heuristic_favours_center_and_forks() {
+        let ai = MinimaxAI::new(Player::X, Difficulty::Medium);
+
+        // A lone mark is worth most in the center, then the corners
+        let score_alone = |row, col| {
+            let mut game = GameState::new();
+            game.make_move((row, col)).unwrap();
+            ai.heuristic(&game)
+        };
+        assert!(score_alone(1, 1) > score_alone(0, 0));
+        assert!(score_alone(0, 0) > score_alone(0, 1));
+
+        // X threatens both (0, 1) and (1, 0), which O can't both block
+        let mut fork = GameState::new();
+        for position in [(0, 0), (1, 1), (0, 2), (2, 2), (2, 0)] {
+            fork.make_move(position).unwrap();
+        }
+        let mut single = GameState::new();
+        for position in [(0, 0), (1, 1), (0, 2), (2, 2), (2, 1)] {
+            single.make_move(position).unwrap();
+        }
+        assert!(ai.heuristic(&fork) > ai.heuristic(&single) + 16);
+
+        // With depth cut short, Medium still opens in the center
+        assert_eq!(ai.find_best_move(&GameState::new()).unwrap(), (1, 1));
+    }
+
+    #[test]
+    fn test_transposition_table_reuses_positions() {
+        let ai = MinimaxAI::new(Player::X, Difficulty::Hard);
+        let mut game = GameState::new();
+        let mut table = TranspositionTable::new();
+
+        // An empty board is a draw with perfect play
+        assert_eq!(ai.minimax(&mut game, 0, 9, i32::MIN, i32::MAX, true, &mut table), 0);
+        assert!(table.hits() > 0);
+
+        // Every move searched was taken back
+        assert_eq!(game.zobrist_hash(), GameState::new().zobrist_hash());
+    }
+
+    #[test]
+    fn test_connect_four_ai_blocks_and_wins() {
+        let ai = MinimaxAI::new(Player::O, Difficulty::Hard);
+
+        // X threatens to complete four along the bottom row at column 3
+        let mut game = GameState::connect_four();
+        for col in [0, 0, 1, 1, 2] {
+            game.make_move((0, col)).unwrap();
+        }
+        assert_eq!(ai.find_best_move(&game).unwrap(), (5, 3));
+
+        // With three of its own stacked in column 0, O completes four instead
+        let mut game = GameState::connect_four();
+        for col in [1, 0, 2, 0, 4, 0, 6] {
+            game.make_move((0, col)).unwrap();
+        }
+        assert_eq!(ai.find_best_move(&game).unwrap(), (2, 0));
+    }
+
+    #[test]
+    fn test_gomoku_ai_blocks_four() {
+        let ai = MinimaxAI::new(Player::O, Difficulty::Medium);
+
+        // X has four in a row on the middle row with one end already blocked
+        let mut game = GameState::gomoku();
+        for (row, col) in [(7, 5), (7, 4), (7, 6), (0, 0), (7, 7), (0, 14), (7, 8)] {
+            game.make_move((row, col)).unwrap();
+        }
+        assert_eq!(ai.find_best_move(&game).unwrap(), (7, 9));
+
+        // The first move of an empty board goes in the center
+        assert_eq!(ai.find_best_move(&GameState::gomoku()).unwrap(), (7, 7));
+    }
+
+    #[test]
+    fn test_misere_ai_avoids_completing_a_line() {
+        let mut game = GameState::with_size(3, 3).unwrap();
+        game.variant = Variant::Misere;
+        game.make_move((0, 0)).unwrap(); // X
+        game.make_move((1, 0)).unwrap(); // O
+        game.make_move((0, 1)).unwrap(); // X
+        game.make_move((1, 1)).unwrap(); // O
+
+        // X must not complete the top row, whatever difficulty
+        for difficulty in [Difficulty::Medium, Difficulty::Hard] {
+            let ai = MinimaxAI::new(Player::X, difficulty);
+            assert_ne!(ai.find_best_move(&game).unwrap(), (0, 2));
+        }
+
+        // Completing a line hands the game to the opponent
+        game.make_move((0, 2)).unwrap();
+        assert_eq!(game.status, GameStatus::Won(Player::O));
+        assert_eq!(game.winning_line, Some(vec![(0, 0), (0, 1), (0, 2)]));
+    }
+
+    #[test]
+    fn test_seeded_easy_ai_is_reproducible() {
+        let game = GameState::new();
+        let moves = |seed| {
+            let ai = MinimaxAI::new_with_seed(Player::X, Difficulty::Easy, seed);
+            (0..20).map(|_| ai.find_best_move(&game).unwrap()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(moves(7), moves(7));
+
+        // Random moves should not always be the first empty cell
+        assert!(moves(7).iter().any(|&position| position != (0, 0)));
+    }
+
+    #[test]
+    fn test_hard_ai_varies_its_openings() {
+        let game = GameState::new();
+        let openings = |seed| {
+            let ai = MinimaxAI::new_with_seed(Player::X, Difficulty::Hard, seed);
+            (0..20).map(|_| ai.find_best_move(&game).unwrap()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(openings(3), openings(3));
+        assert!(openings(3).iter().any(|&position| position != openings(3)[0]));
+    }
+}
@@ -1,3 +1,22 @@
+mod compact;
+mod fallible;
+mod heuristic;
 mod minimax;
+mod opening_book;
+mod perfect;
+mod qlearning;
+mod stats;
+mod transposition;
+mod tuning;
+mod weights;
 
+pub use compact::CompactBoard;
+pub use fallible::{Blunders, FallibleAI};
+pub use heuristic::HeuristicAI;
 pub use minimax::{MinimaxAI, Difficulty};
+pub(crate) use minimax::WIN_SCORE;
+pub use perfect::PerfectAI;
+pub use qlearning::{QLearningAI, QTable, Training};
+pub use stats::SearchStats;
+pub use tuning::{Tuner, TuningResult};
+pub use weights::HeuristicWeights;
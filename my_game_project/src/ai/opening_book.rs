@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use super::compact::CompactBoard;
+use super::perfect;
+use crate::game::GameState;
+use crate::player::Player;
+
+/// Positions with fewer marks than this are in the book
+const BOOK_PLIES: usize = 4;
+
+/// Every equally good move for the early positions of standard 3x3 games
+///
+/// The book is keyed by compact board, which covers the player to move as
+/// well as the marks.
+pub(crate) struct OpeningBook {
+    moves: HashMap<CompactBoard, Vec<(usize, usize)>>,
+}
+
+impl OpeningBook {
+    /// Returns the book, filling it from the solution table the first time it's needed
+    pub(crate) fn get() -> &'static OpeningBook {
+        static BOOK: OnceLock<OpeningBook> = OnceLock::new();
+        BOOK.get_or_init(|| {
+            let mut book = OpeningBook { moves: HashMap::new() };
+            for first in [Player::X, Player::O] {
+                let mut game = GameState::new();
+                game.current_turn = first;
+                book.add(&game, 0);
+            }
+            book
+        })
+    }
+
+    /// Add a position and every position up to `BOOK_PLIES` marks after it
+    fn add(&mut self, game: &GameState, plies: usize) {
+        let Some(board) = CompactBoard::from_game(game) else {
+            return;
+        };
+        if plies >= BOOK_PLIES || self.moves.contains_key(&board) {
+            return;
+        }
+        let Some(moves) = perfect::best_moves(game) else {
+            return;
+        };
+
+        for cell in game.legal_moves() {
+            let mut next = game.clone();
+            if next.make_move(cell).is_ok() && !next.is_over() {
+                self.add(&next, plies + 1);
+            }
+        }
+        self.moves.insert(board, moves);
+    }
+
+    /// Returns every equally good move for a position, if it's in the book
+    pub(crate) fn moves(&self, game: &GameState) -> Option<&[(usize, usize)]> {
+        self.moves.get(&CompactBoard::from_game(game)?).map(Vec::as_slice)
+    }
+
+    /// Pick one of the equally good moves for a position at random, if it's in the book
+    pub(crate) fn choose(&self, game: &GameState, rng: &mut impl Rng) -> Option<(usize, usize)> {
+        self.moves(game)?.choose(rng).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_book_holds_every_best_opening() {
+        let book = OpeningBook::get();
+
+        // Every first move draws with perfect play
+        assert_eq!(book.moves(&GameState::new()).unwrap().len(), 9);
+
+        // Against a corner opening, only the center holds the draw
+        let mut game = GameState::new();
+        game.make_move((0, 0)).unwrap();
+        assert_eq!(book.moves(&game).unwrap(), [(1, 1)]);
+
+        // Past the opening, and on other boards, the book has nothing
+        for cell in [(1, 1), (2, 2), (0, 2)] {
+            game.make_move(cell).unwrap();
+        }
+        assert!(book.moves(&game).is_none());
+        assert!(book.moves(&GameState::with_size(4, 3).unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_choice_is_reproducible_with_a_seed() {
+        let book = OpeningBook::get();
+        let game = GameState::new();
+        let choices = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (0..10).map(|_| book.choose(&game, &mut rng).unwrap()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(choices(7), choices(7));
+        assert!(choices(7).iter().any(|&cell| cell != choices(7)[0]));
+    }
+}
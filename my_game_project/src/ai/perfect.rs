@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use super::compact::CompactBoard;
+use super::minimax::{Difficulty, MinimaxAI};
+use crate::error::{GameError, GameResult};
+use crate::game::GameState;
+use crate::player::{GamePlayer, Player};
+use crate::position::Position;
+
+/// Scores of every reachable standard 3x3 position, keyed by its canonical form
+///
+/// Rotations and reflections of a position score the same, so only one of
+/// them is stored. Each score is for the player to move: positive when they can force a win,
+/// negative when their opponent can, and zero for a draw. Wins that leave
+/// more of the board empty, so are reached sooner, score higher.
+type SolutionTable = HashMap<CompactBoard, i8>;
+
+/// Returns the solution table, solving every position the first time it's needed
+fn solution_table() -> &'static SolutionTable {
+    static TABLE: OnceLock<SolutionTable> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut table = HashMap::new();
+        for first in [Player::X, Player::O] {
+            solve(CompactBoard::new(first), &mut table);
+        }
+        table
+    })
+}
+
+/// Score a position for the player to move, storing it and every position after it
+fn solve(board: CompactBoard, table: &mut SolutionTable) -> i8 {
+    let (board, _) = board.canonical();
+    if let Some(&score) = table.get(&board) {
+        return score;
+    }
+
+    let score = if board.winner().is_some() {
+        // The player who just moved won
+        -(board.empty_count() as i8 + 1)
+    } else {
+        board
+            .legal_moves()
+            .map(|cell| -solve(board.play(cell), table))
+            .max()
+            .unwrap_or(0)
+    };
+
+    table.insert(board, score);
+    score
+}
+
+/// Returns true if the table covers games like `game`
+pub(crate) fn is_covered(game: &GameState) -> bool {
+    CompactBoard::from_game(game).is_some()
+}
+
+/// Look up the best move for the player to move, if the table covers the game
+///
+/// Among equally good moves the first in board order is chosen, as a full
+/// minimax search would.
+pub(crate) fn best_move(game: &GameState) -> Option<(usize, usize)> {
+    best_moves(game)?.first().copied()
+}
+
+/// Look up every move tied for best for the player to move, in board order
+pub(crate) fn best_moves(game: &GameState) -> Option<Vec<(usize, usize)>> {
+    if game.is_over() {
+        return None;
+    }
+    best_board_moves(CompactBoard::from_game(game)?)
+}
+
+/// Look up every move tied for best on a compact board, in board order
+fn best_board_moves(board: CompactBoard) -> Option<Vec<(usize, usize)>> {
+    let table = solution_table();
+    let scores = board
+        .legal_moves()
+        .map(|cell| Some((cell, -*table.get(&board.play(cell).canonical().0)?)))
+        .collect::<Option<Vec<_>>>()?;
+
+    let best = scores.iter().map(|&(_, score)| score).max()?;
+    Some(scores.into_iter().filter(|&(_, score)| score == best).map(|(cell, _)| cell).collect())
+}
+
+/// Follow the best moves in the table from the position after `first`
+///
+/// Returns the number of positions looked up along with the line.
+pub(crate) fn principal_variation(game: &GameState, first: (usize, usize)) -> (usize, Vec<(usize, usize)>) {
+    let (mut lookups, mut line) = (game.legal_moves().count(), vec![first]);
+    let Some(mut board) = CompactBoard::from_game(game) else {
+        return (0, line);
+    };
+
+    board = board.play(first);
+    while !board.is_over() {
+        lookups += board.legal_moves().count();
+        let Some(&cell) = best_board_moves(board).as_deref().and_then(<[_]>::first) else {
+            break;
+        };
+        line.push(cell);
+        board = board.play(cell);
+    }
+
+    (lookups, line)
+}
+
+/// Score a position for the player to move, if the table covers the game
+fn position_score(game: &GameState) -> Option<i8> {
+    let (board, _) = CompactBoard::from_game(game)?.canonical();
+    solution_table().get(&board).copied()
+}
+
+/// An AI player that plays standard 3x3 tic-tac-toe perfectly by table lookup
+///
+/// Every reachable position is solved once, the first time any game needs
+/// the table, after which each move is a lookup. Other boards and variants
+/// fall back to a Hard [`MinimaxAI`].
+pub struct PerfectAI {
+    /// The player type (X or O)
+    player_type: Player,
+    /// Plays the games the table doesn't cover
+    fallback: MinimaxAI,
+}
+
+impl PerfectAI {
+    /// Create a new perfect-play AI player
+    pub fn new(player_type: Player) -> Self {
+        Self {
+            player_type,
+            fallback: MinimaxAI::new(player_type, Difficulty::Hard),
+        }
+    }
+}
+
+impl GamePlayer for PerfectAI {
+    fn get_move(&self, game: &GameState) -> GameResult<Position> {
+        if !is_covered(game) {
+            return self.fallback.get_move(game);
+        }
+        best_move(game).map(Position::from).ok_or(GameError::NoValidMoves)
+    }
+
+    fn respond_to_draw_offer(&self, game: &GameState) -> GameResult<bool> {
+        // Accept unless the AI can force a win
+        match position_score(game) {
+            Some(score) if game.current_turn == self.player_type => Ok(score <= 0),
+            Some(score) => Ok(score >= 0),
+            None => self.fallback.respond_to_draw_offer(game),
+        }
+    }
+
+    fn get_player_type(&self) -> Player {
+        self.player_type
+    }
+
+    fn get_name(&self) -> String {
+        "AI (Perfect)".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_covers_every_reachable_position() {
+        // 5,478 positions are reachable with X moving first, 765 of them
+        // different up to rotation and reflection, and as many with O first
+        assert_eq!(solution_table().len(), 2 * 765);
+        assert_eq!(position_score(&GameState::new()), Some(0));
+    }
+
+    #[test]
+    fn test_matches_full_search() {
+        let mut game = GameState::new();
+        for cell in [(0, 0), (1, 1), (2, 2)] {
+            game.make_move(cell).unwrap();
+        }
+
+        let perfect = PerfectAI::new(Player::O);
+        let minimax = MinimaxAI::new(Player::O, Difficulty::Hard);
+        let scores = minimax.evaluate_all(&game);
+        let best = scores.values().max().unwrap();
+
+        let chosen = perfect.get_move(&game).unwrap();
+        assert_eq!(scores[&chosen], *best);
+        assert!(perfect.respond_to_draw_offer(&game).unwrap());
+
+        // Other boards are searched instead
+        let big = GameState::with_size(4, 3).unwrap();
+        assert!(best_move(&big).is_none());
+        assert!(perfect.get_move(&big).is_ok());
+    }
+}
@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::compact::CompactBoard;
+use super::minimax::{Difficulty, MinimaxAI};
+use crate::error::{GameError, GameResult};
+use crate::game::GameState;
+use crate::player::{GamePlayer, Player};
+use crate::position::Position;
+#[cfg(feature = "files")]
+use crate::save;
+use crate::save::{Migration, Saved};
+
+/// The learned value of playing in each cell, indexed by `3 * row + col`
+type ActionValues = [f64; 9];
+
+/// How a [`QTable`] learns from its games
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Training {
+    /// How far each value moves toward what a game just showed, between 0 and 1
+    pub learning_rate: f64,
+    /// How much a win is worth for each move it is away, between 0 and 1
+    pub discount: f64,
+    /// The chance of trying a random move instead of the best known one
+    pub exploration: f64,
+}
+
+impl Default for Training {
+    fn default() -> Self {
+        Self { learning_rate: 0.3, discount: 0.95, exploration: 0.2 }
+    }
+}
+
+/// The values a Q-learning AI has learned for each move in standard 3x3 positions
+///
+/// Values are for the player to move: 1 for a move that wins, 0 for one that
+/// draws and negative for one that lets the opponent win, discounted for each
+/// move it takes. Both sides share the table, so self-play teaches X and O at once.
+///
+/// # Examples
+///
+/// ```
+/// use my_game_project::ai::QTable;
+/// use my_game_project::game::GameState;
+///
+/// let mut table = QTable::new();
+/// table.train(1000);
+///
+/// assert_eq!(table.games(), 1000);
+/// assert!(table.best_move(&GameState::new()).is_some());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QTable {
+    /// Number of games it has been trained on
+    games: usize,
+    /// Values of the moves in each position seen in training
+    #[serde(serialize_with = "serialize_values", deserialize_with = "deserialize_values")]
+    values: HashMap<CompactBoard, ActionValues>,
+}
+
+impl QTable {
+    /// Create an untrained table, which values every move equally
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of games the table has been trained on
+    pub fn games(&self) -> usize {
+        self.games
+    }
+
+    /// Number of positions the table has values for
+    pub fn positions(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Train on `games` games of self-play with the default [`Training`]
+    pub fn train(&mut self, games: usize) {
+        self.train_with(games, Training::default(), &mut StdRng::from_entropy());
+    }
+
+    /// Train on `games` games of self-play, making random choices with `rng`
+    ///
+    /// Games alternate between X and O moving first. After each move, its
+    /// value is moved toward the result if it ended the game, or otherwise
+    /// toward the negated value of the opponent's best reply.
+    pub fn train_with(&mut self, games: usize, training: Training, rng: &mut impl Rng) {
+        for _ in 0..games {
+            let first = if self.games.is_multiple_of(2) { Player::X } else { Player::O };
+            let mut board = CompactBoard::new(first);
+
+            while !board.is_over() {
+                let moves: Vec<_> = board.legal_moves().collect();
+                let cell = if rng.gen_bool(training.exploration) {
+                    *moves.choose(rng).expect("a game that isn't over has a legal move")
+                } else {
+                    *self.best_moves(board).choose(rng).expect("a game that isn't over has a legal move")
+                };
+
+                let next = board.play(cell);
+                let target = if next.winner().is_some() {
+                    1.0
+                } else if next.is_over() {
+                    0.0
+                } else {
+                    -training.discount * self.position_value(next)
+                };
+
+                let value = &mut self.values.entry(board).or_insert([0.0; 9])[index(cell)];
+                *value += training.learning_rate * (target - *value);
+                board = next;
+            }
+
+            self.games += 1;
+        }
+    }
+
+    /// The best move for the player to move, if the table covers the game and it isn't over
+    ///
+    /// Among equally valued moves the first in board order is chosen.
+    pub fn best_move(&self, game: &GameState) -> Option<Position> {
+        let board = CompactBoard::from_game(game)?;
+        self.best_moves(board).first().map(|&cell| Position::from(cell))
+    }
+
+    /// The learned value of a position for the player to move, if the table covers the game
+    pub fn value(&self, game: &GameState) -> Option<f64> {
+        CompactBoard::from_game(game).map(|board| self.position_value(board))
+    }
+
+    /// Every legal move tied for the highest value, in board order
+    fn best_moves(&self, board: CompactBoard) -> Vec<(usize, usize)> {
+        let Some(values) = self.values.get(&board) else {
+            return board.legal_moves().collect();
+        };
+        let best = self.position_value(board);
+        board.legal_moves().filter(|&cell| values[index(cell)] == best).collect()
+    }
+
+    /// The value of the best legal move, or 0 once the game is over
+    fn position_value(&self, board: CompactBoard) -> f64 {
+        let values = self.values.get(&board).unwrap_or(&[0.0; 9]);
+        board.legal_moves().map(|cell| values[index(cell)]).reduce(f64::max).unwrap_or(0.0)
+    }
+
+    /// Saves the table to a file, in JSON unless the file name calls
+    /// for another [`SaveFormat`](crate::save::SaveFormat)
+    #[cfg(feature = "files")]
+    pub fn save_to_file(&self, filename: &str) -> GameResult<()> {
+        save::save_to_file(self, filename)
+    }
+
+    /// Loads a table from a file saved in any format
+    #[cfg(feature = "files")]
+    pub fn load_from_file(filename: &str) -> GameResult<Self> {
+        save::load_from_file(filename)
+    }
+}
+
+impl Saved for QTable {
+    const MIGRATIONS: &'static [Migration] = &[];
+}
+
+/// The index of `cell` in a position's [`ActionValues`]
+fn index((row, col): (usize, usize)) -> usize {
+    3 * row + col
+}
+
+/// Writes the values as a list of positions and their values, since JSON keys must be strings
+fn serialize_values<S: Serializer>(
+    values: &HashMap<CompactBoard, ActionValues>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(values)
+}
+
+/// Reads the values written by [`serialize_values`]
+fn deserialize_values<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HashMap<CompactBoard, ActionValues>, D::Error> {
+    Ok(Vec::<(CompactBoard, ActionValues)>::deserialize(deserializer)?.into_iter().collect())
+}
+
+/// An AI player that plays the moves a trained [`QTable`] values most
+///
+/// The table only covers standard 3x3 games, so others are played by a Hard
+/// [`MinimaxAI`] instead. Tables are shared, so many players can be created
+/// from one that was loaded once.
+pub struct QLearningAI {
+    /// The player type (X or O)
+    player_type: Player,
+    /// The learned values of each move
+    table: Arc<QTable>,
+    /// Plays the games the table doesn't cover
+    fallback: MinimaxAI,
+}
+
+impl QLearningAI {
+    /// Create a new AI player that plays by `table`
+    pub fn new(player_type: Player, table: Arc<QTable>) -> Self {
+        Self {
+            player_type,
+            table,
+            fallback: MinimaxAI::new(player_type, Difficulty::Hard),
+        }
+    }
+}
+
+impl GamePlayer for QLearningAI {
+    fn get_move(&self, game: &GameState) -> GameResult<Position> {
+        if CompactBoard::from_game(game).is_none() {
+            return self.fallback.get_move(game);
+        }
+        self.table.best_move(game).ok_or(GameError::NoValidMoves)
+    }
+
+    fn respond_to_draw_offer(&self, game: &GameState) -> GameResult<bool> {
+        // Accept unless the AI expects to win
+        match self.table.value(game) {
+            Some(value) if game.current_turn == self.player_type => Ok(value <= 0.0),
+            Some(value) => Ok(value >= 0.0),
+            None => self.fallback.respond_to_draw_offer(game),
+        }
+    }
+
+    fn get_player_type(&self) -> Player {
+        self.player_type
+    }
+
+    fn get_name(&self) -> String {
+        "AI (Q-learning)".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trained() -> QTable {
+        let mut table = QTable::new();
+        table.train_with(20_000, Training::default(), &mut StdRng::seed_from_u64(7));
+        table
+    }
+
+    #[test]
+    fn test_self_play_learns_to_win_and_block() {
+        let table = Arc::new(trained());
+        let ai = QLearningAI::new(Player::O, Arc::clone(&table));
+
+        // O can win at (1, 2) instead of blocking X at (0, 2)
+        let mut game = GameState::new();
+        for cell in [(0, 0), (1, 0), (0, 1), (1, 1), (2, 2)] {
+            game.make_move(cell).unwrap();
+        }
+        assert_eq!(ai.get_move(&game).unwrap(), Position::new(1, 2));
+
+        // X threatens (0, 2)
+        let mut game = GameState::new();
+        for cell in [(0, 0), (1, 1), (0, 1)] {
+            game.make_move(cell).unwrap();
+        }
+        assert_eq!(ai.get_move(&game).unwrap(), Position::new(0, 2));
+
+        // Other boards are searched instead
+        assert!(ai.get_move(&GameState::with_size(4, 3).unwrap()).is_ok());
+    }
+
+    #[test]
+    #[cfg(feature = "files")]
+    fn test_table_round_trips_through_a_save() {
+        let mut table = QTable::new();
+        table.train_with(500, Training::default(), &mut StdRng::seed_from_u64(7));
+
+        let path = std::env::temp_dir().join(format!("qtable_{}.msgpack.gz", std::process::id()));
+        let path = path.to_str().unwrap();
+        table.save_to_file(path).unwrap();
+        let loaded = QTable::load_from_file(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(loaded, table);
+        assert_eq!(loaded.games(), 500);
+        assert!(loaded.positions() > 0);
+    }
+}
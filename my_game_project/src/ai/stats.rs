@@ -0,0 +1,77 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use serde::{Serialize, Serializer};
+
+use crate::position::Position;
+
+/// Diagnostics from the AI's search for a move
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchStats {
+    /// Number of positions searched, or looked up in the solution table
+    pub nodes: usize,
+    /// Deepest ply reached, counting the chosen move as the first
+    pub depth: usize,
+    /// Time spent choosing the move
+    #[serde(rename = "time_ms", serialize_with = "serialize_millis")]
+    pub elapsed: Duration,
+    /// The line of play the AI expects, starting with the chosen move
+    pub principal_variation: Vec<Position>,
+}
+
+impl SearchStats {
+    /// Statistics for a search started at `started` that expects `line`
+    pub(crate) fn new(nodes: usize, line: Vec<(usize, usize)>, started: Instant) -> Self {
+        Self {
+            nodes,
+            depth: line.len(),
+            elapsed: started.elapsed(),
+            principal_variation: line.into_iter().map(Position::from).collect(),
+        }
+    }
+}
+
+/// Serialize a duration as fractional milliseconds
+fn serialize_millis<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64() * 1000.0)
+}
+
+/// Format a count with commas between groups of three digits
+fn with_commas(count: usize) -> String {
+    let digits = count.to_string();
+    let mut formatted = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            formatted.push(',');
+        }
+        formatted.push(digit);
+    }
+    formatted
+}
+
+impl fmt::Display for SearchStats {
+    /// Formats as e.g. `PV=(1, 1) (0, 0), 5,432 nodes in 3.02ms`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let line: Vec<String> = self.principal_variation.iter().map(Position::to_string).collect();
+        write!(f, "PV={}, {} nodes in {:.2?}", line.join(" "), with_commas(self.nodes), self.elapsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let stats = SearchStats {
+            nodes: 5432,
+            depth: 2,
+            elapsed: Duration::from_millis(3),
+            principal_variation: vec![Position::new(1, 1), Position::new(0, 0)],
+        };
+
+        assert_eq!(stats.to_string(), "PV=(1, 1) (0, 0), 5,432 nodes in 3.00ms");
+        assert_eq!(with_commas(1_234_567), "1,234,567");
+        assert_eq!(serde_json::to_value(&stats).unwrap()["time_ms"], 3.0);
+    }
+}
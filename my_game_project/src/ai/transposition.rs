@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+
+/// How a stored score relates to the true minimax value of a position
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Bound {
+    /// The score is the exact value
+    Exact,
+    /// The search failed high: the value is at least the score
+    Lower,
+    /// The search failed low: the value is at most the score
+    Upper,
+}
+
+/// A previously searched position
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    score: i32,
+    bound: Bound,
+    /// The move that gave the score, if any moves were searched
+    best_move: Option<(usize, usize)>,
+}
+
+/// Scores of positions already searched, keyed by Zobrist hash
+///
+/// A table is only valid for a single search: within one search a position is
+/// always reached at the same depth and with the same player to maximize, so
+/// stored scores can be reused as they are. The table also counts the
+/// positions the search visits.
+#[derive(Debug, Default)]
+pub(crate) struct TranspositionTable {
+    entries: HashMap<u64, Entry>,
+    /// Number of lookups that found a stored position
+    hits: usize,
+    /// Number of positions visited
+    nodes: usize,
+    /// Deepest ply visited
+    depth: usize,
+}
+
+impl TranspositionTable {
+    /// Create an empty table
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Look up a position, narrowing the alpha-beta window with any stored bound
+    ///
+    /// Returns the stored score if it settles the position outright.
+    pub(crate) fn probe(&mut self, hash: u64, alpha: &mut i32, beta: &mut i32) -> Option<i32> {
+        let entry = self.entries.get(&hash)?;
+        self.hits += 1;
+
+        match entry.bound {
+            Bound::Exact => return Some(entry.score),
+            Bound::Lower => *alpha = (*alpha).max(entry.score),
+            Bound::Upper => *beta = (*beta).min(entry.score),
+        }
+
+        (*alpha >= *beta).then_some(entry.score)
+    }
+
+    /// Store the score found for a position searched with the window (`alpha`, `beta`)
+    pub(crate) fn store(&mut self, hash: u64, score: i32, alpha: i32, beta: i32, best_move: Option<(usize, usize)>) {
+        let bound = if score <= alpha {
+            Bound::Upper
+        } else if score >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+
+        self.entries.insert(hash, Entry { score, bound, best_move });
+    }
+
+    /// Returns the best move stored for a position, if any
+    pub(crate) fn best_move(&self, hash: u64) -> Option<(usize, usize)> {
+        self.entries.get(&hash)?.best_move
+    }
+
+    /// Count a position visited `depth` plies into the search
+    pub(crate) fn visit(&mut self, depth: usize) {
+        self.nodes += 1;
+        self.depth = self.depth.max(depth);
+    }
+
+    /// Number of positions visited
+    pub(crate) fn nodes(&self) -> usize {
+        self.nodes
+    }
+
+    /// Deepest ply visited
+    pub(crate) fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Number of lookups that found a stored position
+    #[cfg(test)]
+    pub(crate) fn hits(&self) -> usize {
+        self.hits
+    }
+}
@@ -0,0 +1,227 @@
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use tracing::debug;
+
+use super::minimax::{Difficulty, MinimaxAI};
+use super::weights::HeuristicWeights;
+use crate::error::{GameError, GameResult};
+use crate::game::GameState;
+use crate::tournament::Tournament;
+
+/// The largest value a tuned weight can take
+const MAX_WEIGHT: i32 = 8;
+
+/// How many children to try breeding for each place in the population before giving up
+const BREEDING_ATTEMPTS: usize = 10;
+
+/// The outcome of tuning
+#[derive(Debug, Clone, PartialEq)]
+pub struct TuningResult {
+    /// The weights that won the last generation's tournament
+    pub best: HeuristicWeights,
+    /// The winning weights of each generation, in order
+    pub generations: Vec<HeuristicWeights>,
+}
+
+/// Tunes the weights [`MinimaxAI`] scores positions with by self-play
+///
+/// A simple genetic algorithm: each generation, a population of candidate
+/// weights plays a round-robin [`Tournament`] from the starting position.
+/// The better half survives, and the rest of the next generation is bred from
+/// the survivors by mixing their weights and nudging some of them up or down.
+/// The population starts from the default weights and variations on them.
+///
+/// # Examples
+///
+/// ```
+/// use my_game_project::ai::Tuner;
+/// use my_game_project::game::GameState;
+///
+/// let result = Tuner::new(GameState::new()).population(3).generations(1).seed(7).run().unwrap();
+/// assert_eq!(result.generations, vec![result.best]);
+/// ```
+pub struct Tuner {
+    /// The position every game starts from
+    start: GameState,
+    /// The difficulty the candidates search at
+    difficulty: Difficulty,
+    /// Number of candidates in each generation
+    population: usize,
+    /// Number of generations to play
+    generations: usize,
+    /// Number of games each pair of candidates plays
+    games_per_pairing: usize,
+    /// Seed for reproducible tuning, if any
+    seed: Option<u64>,
+}
+
+impl Tuner {
+    /// Tune weights for games starting from `start`, searched at Medium
+    ///
+    /// By default eight candidates play five generations, two games per pairing.
+    pub fn new(start: GameState) -> Self {
+        Self {
+            start,
+            difficulty: Difficulty::Medium,
+            population: 8,
+            generations: 5,
+            games_per_pairing: 2,
+            seed: None,
+        }
+    }
+
+    /// Set the difficulty the candidates search at
+    ///
+    /// Only positions the search doesn't see the end of are scored with the
+    /// weights, so a deeper search can leave less to tune.
+    pub fn difficulty(mut self, difficulty: Difficulty) -> Self {
+        self.difficulty = difficulty;
+        self
+    }
+
+    /// Set the number of candidates in each generation, which must be at least two
+    pub fn population(mut self, population: usize) -> Self {
+        self.population = population;
+        self
+    }
+
+    /// Set the number of generations to play
+    pub fn generations(mut self, generations: usize) -> Self {
+        self.generations = generations;
+        self
+    }
+
+    /// Set the number of games each pair of candidates plays
+    pub fn games_per_pairing(mut self, games: usize) -> Self {
+        self.games_per_pairing = games;
+        self
+    }
+
+    /// Make the candidates bred reproducible
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Play every generation and return the best weights found
+    pub fn run(&self) -> GameResult<TuningResult> {
+        if self.population < 2 {
+            return Err(GameError::InvalidConfig("tuning needs a population of at least two".to_string()));
+        }
+        let mut rng = match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        let mut population = vec![HeuristicWeights::default()];
+        breed(&mut population, self.population, &mut rng);
+
+        let mut result = TuningResult { best: HeuristicWeights::default(), generations: Vec::new() };
+        for generation in 0..self.generations {
+            let mut ranked = self.rank(&population)?;
+            result.best = ranked[0];
+            result.generations.push(ranked[0]);
+            debug!(generation, best = ?ranked[0], "generation played");
+
+            ranked.truncate(self.population.div_ceil(2));
+            population = ranked;
+            breed(&mut population, self.population, &mut rng);
+        }
+
+        Ok(result)
+    }
+
+    /// Play a round robin between `candidates` and return them from best to worst
+    fn rank(&self, candidates: &[HeuristicWeights]) -> GameResult<Vec<HeuristicWeights>> {
+        let mut tournament = Tournament::new(self.start.clone()).games_per_pairing(self.games_per_pairing);
+        for (index, &weights) in candidates.iter().enumerate() {
+            let difficulty = self.difficulty;
+            tournament.register(index.to_string(), move |player| {
+                Box::new(MinimaxAI::new(player, difficulty).with_weights(weights))
+            });
+        }
+
+        tournament
+            .round_robin()?
+            .standings
+            .iter()
+            .map(|standing| {
+                let index: usize = standing.rating.player_id.parse().map_err(|_| {
+                    GameError::InvalidPlayerType(format!("unknown candidate {}", standing.rating.player_id))
+                })?;
+                Ok(candidates[index])
+            })
+            .collect()
+    }
+}
+
+/// Fill `population` up to `size` with children of its members, skipping duplicates
+fn breed(population: &mut Vec<HeuristicWeights>, size: usize, rng: &mut StdRng) {
+    let parents = population.clone();
+    for _ in 0..size * BREEDING_ATTEMPTS {
+        if population.len() >= size {
+            return;
+        }
+        let (Some(&mother), Some(&father)) = (parents.choose(rng), parents.choose(rng)) else {
+            return;
+        };
+        let child = mutate(crossover(mother, father, rng), rng);
+        if !population.contains(&child) {
+            population.push(child);
+        }
+    }
+}
+
+/// Take each weight from one parent or the other
+fn crossover(mother: HeuristicWeights, father: HeuristicWeights, rng: &mut StdRng) -> HeuristicWeights {
+    let mut pick = |a: i32, b: i32| if rng.gen_bool(0.5) { a } else { b };
+    HeuristicWeights {
+        run_growth: pick(mother.run_growth, father.run_growth),
+        position: pick(mother.position, father.position),
+        threat: pick(mother.threat, father.threat),
+    }
+}
+
+/// Nudge each weight up or down by one with even odds, keeping runs worth more as they grow
+fn mutate(weights: HeuristicWeights, rng: &mut StdRng) -> HeuristicWeights {
+    let mut nudge = |weight: i32, min: i32| {
+        let step = *[-1, 0, 0, 1].choose(rng).unwrap_or(&0);
+        (weight + step).clamp(min, MAX_WEIGHT)
+    };
+    HeuristicWeights {
+        run_growth: nudge(weights.run_growth, 2),
+        position: nudge(weights.position, 0),
+        threat: nudge(weights.threat, 0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tuning_keeps_the_winners() {
+        let start = GameState::with_size(4, 3).unwrap();
+        let result = Tuner::new(start).population(4).generations(2).seed(7).run().unwrap();
+
+        assert_eq!(result.generations.len(), 2);
+        assert_eq!(result.generations.last(), Some(&result.best));
+        assert!((2..=MAX_WEIGHT).contains(&result.best.run_growth));
+
+        assert!(Tuner::new(GameState::new()).population(1).run().is_err());
+    }
+
+    #[test]
+    fn test_breeding_fills_the_population_with_new_weights() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut population = vec![HeuristicWeights::default()];
+        breed(&mut population, 6, &mut rng);
+
+        assert_eq!(population.len(), 6);
+        assert_eq!(population[0], HeuristicWeights::default());
+        for (index, weights) in population.iter().enumerate() {
+            assert!(!population[index + 1..].contains(weights));
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "files")]
+use crate::error::{GameError, GameResult};
+
+/// The most a single run is worth, however close it is to complete
+const MAX_RUN_WORTH: i32 = 1 << 16;
+
+/// The weights [`MinimaxAI`](super::MinimaxAI) scores unfinished positions with
+///
+/// The defaults are the hand-picked weights the AI has always used, and
+/// [`Tuner`](super::Tuner) finds better ones for a board by self-play. Weights
+/// are saved as TOML, with missing weights left at their defaults:
+///
+/// ```toml
+/// run_growth = 4
+/// position = 1
+/// threat = 1
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HeuristicWeights {
+    /// How many times more a run open to one player is worth for each extra mark in it
+    pub run_growth: i32,
+    /// How much each mark is worth for every run passing through its cell
+    pub position: i32,
+    /// The bonus for a threat that can't be stopped, as a multiple of a complete run's worth
+    pub threat: i32,
+}
+
+impl Default for HeuristicWeights {
+    fn default() -> Self {
+        Self { run_growth: 4, position: 1, threat: 1 }
+    }
+}
+
+impl HeuristicWeights {
+    /// The worth of a run holding `marks` marks of only one player
+    pub(crate) fn run_worth(&self, marks: isize) -> i32 {
+        let exponent = u32::try_from(marks - 1).unwrap_or(0);
+        self.run_growth.saturating_pow(exponent).min(MAX_RUN_WORTH)
+    }
+
+    /// Loads weights from a TOML file
+    #[cfg(feature = "files")]
+    pub fn load_from_file(filename: &str) -> GameResult<Self> {
+        let text = std::fs::read_to_string(filename)?;
+        toml::from_str(&text).map_err(|e| GameError::InvalidConfig(e.to_string()))
+    }
+
+    /// Saves the weights to a TOML file
+    #[cfg(feature = "files")]
+    pub fn save_to_file(&self, filename: &str) -> GameResult<()> {
+        let text = toml::to_string(self).map_err(|e| GameError::SerializationError(e.to_string()))?;
+        std::fs::write(filename, text).map_err(GameError::from)
+    }
+}
@@ -0,0 +1,288 @@
+//! Post-game analysis
+//!
+//! Replays a game's history and grades every move against the full-strength
+//! minimax search, pointing out where a better move was available.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::ai::{Difficulty, MinimaxAI, WIN_SCORE};
+use crate::error::GameResult;
+use crate::history::GameHistory;
+use crate::player::Player;
+use crate::position::Position;
+
+/// How far a move may fall short of the best move's estimate and still count as best
+pub const INACCURACY_DROP: i32 = 10;
+
+/// How far a move must fall short of the best move's estimate to count as a blunder
+pub const BLUNDER_DROP: i32 = 100;
+
+/// How a move compares with the best move available
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MoveQuality {
+    /// The move kept the best outcome available (win, draw or loss), or came
+    /// within [`INACCURACY_DROP`] of the best move's estimate
+    #[serde(alias = "Best")]
+    Best,
+    /// The move gave up a win but still avoids losing, or fell noticeably
+    /// short of the best move's estimate
+    #[serde(alias = "Inaccuracy")]
+    Inaccuracy,
+    /// The move turned a position that wasn't lost into a lost one, or fell
+    /// at least [`BLUNDER_DROP`] short of the best move's estimate
+    #[serde(alias = "Blunder")]
+    Blunder,
+}
+
+/// The analysis of a single move
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MoveAnalysis {
+    /// The index of the move in the game, starting at 0
+    pub ply: usize,
+    /// The player who made the move
+    pub player: Player,
+    /// The move that was played
+    pub played: (usize, usize),
+    /// The engine's score for the move that was played, from the mover's point of view
+    pub score: i32,
+    /// The best move available
+    pub best_move: (usize, usize),
+    /// The engine's score for the best move, from the mover's point of view
+    pub best_score: i32,
+    /// How the played move compares with the best move
+    pub quality: MoveQuality,
+}
+
+/// The analysis of a whole game
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameAnalysis {
+    /// The unique identifier of the game
+    pub game_id: Uuid,
+    /// The analysis of each move in order
+    pub moves: Vec<MoveAnalysis>,
+}
+
+impl GameAnalysis {
+    /// Returns the first move graded a blunder, such as one that turned the
+    /// game into a loss for the player who made it
+    pub fn first_blunder(&self) -> Option<&MoveAnalysis> {
+        self.moves.iter().find(|analysis| analysis.quality == MoveQuality::Blunder)
+    }
+}
+
+/// A coach's review of one player's moves in a game
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Review {
+    /// The player whose moves are reviewed
+    pub player: Player,
+    /// The analysis of each of the player's moves in order
+    pub moves: Vec<MoveAnalysis>,
+    /// The player's first blunder, if they made one
+    pub mistake: Option<MoveAnalysis>,
+    /// How the opponent wins after the mistake, starting with their reply
+    pub refutation: Vec<Position>,
+}
+
+/// Describes a score from the mover's point of view: a forced win or loss, or the engine's estimate
+///
+/// # Examples
+///
+/// ```
+/// use my_game_project::analysis::describe_score;
+///
+/// assert_eq!(describe_score(999_995), "winning");
+/// assert_eq!(describe_score(-999_995), "losing");
+/// assert_eq!(describe_score(0), "even");
+/// assert_eq!(describe_score(12), "+12");
+/// ```
+pub fn describe_score(score: i32) -> String {
+    // Estimates of unfinished positions never reach half a win
+    match score {
+        _ if score > WIN_SCORE / 2 => "winning".to_string(),
+        _ if score < -WIN_SCORE / 2 => "losing".to_string(),
+        0 => "even".to_string(),
+        _ => format!("{:+}", score),
+    }
+}
+
+/// The outcome a score promises: 1 for a forced win, -1 for a forced loss,
+/// and 0 for an estimate of an unfinished position
+fn outcome(score: i32) -> i32 {
+    match score {
+        _ if score > WIN_SCORE / 2 => 1,
+        _ if score < -WIN_SCORE / 2 => -1,
+        _ => 0,
+    }
+}
+
+/// Grade a move's score against the best score available
+///
+/// When either score is a forced win or loss, the move is graded by the
+/// outcome it gives up. Otherwise both are estimates, and the move is graded
+/// by how far it falls short of the best one.
+fn classify(score: i32, best_score: i32) -> MoveQuality {
+    let (played, best) = (outcome(score), outcome(best_score));
+    if played != 0 || best != 0 {
+        return match played {
+            _ if played == best => MoveQuality::Best,
+            -1 => MoveQuality::Blunder,
+            _ => MoveQuality::Inaccuracy,
+        };
+    }
+
+    match best_score.saturating_sub(score) {
+        drop if drop >= BLUNDER_DROP => MoveQuality::Blunder,
+        drop if drop > INACCURACY_DROP => MoveQuality::Inaccuracy,
+        _ => MoveQuality::Best,
+    }
+}
+
+/// Analyze every move in a game's history
+///
+/// # Examples
+///
+/// ```
+/// use my_game_project::analysis::{analyze_history, MoveQuality};
+/// use my_game_project::game::GameState;
+/// use my_game_project::player::Player;
+///
+/// let mut history = GameState::new().create_history();
+/// history.add_move(Player::X, (0, 0));
+/// history.add_move(Player::O, (0, 1)); // Only the center holds the draw
+///
+/// let analysis = analyze_history(&history).unwrap();
+/// assert_eq!(analysis.moves[0].quality, MoveQuality::Best);
+/// assert_eq!(analysis.moves[1].quality, MoveQuality::Blunder);
+/// assert_eq!(analysis.moves[1].best_move, (1, 1));
+/// ```
+pub fn analyze_history(history: &GameHistory) -> GameResult<GameAnalysis> {
+    // Report where the game goes wrong before spending any time analyzing it
+    history.validate()?;
+    let mut moves = Vec::new();
+
+    for (ply, (game, game_move)) in history.replay().zip(&history.moves).enumerate() {
+        let game = game?;
+        let ai = MinimaxAI::new(game_move.player, Difficulty::Hard);
+        let scores = ai.score_moves(&game)?;
+
+        // Marks may land elsewhere than where they were played, e.g. with gravity
+        let played = game.rules().landing_cell(&game, game_move.position.row, game_move.position.col);
+        // Prefer the first of several equally good moves
+        let best = scores.iter().fold(None, |best: Option<&((usize, usize), i32)>, candidate| {
+            match best {
+                Some(best) if best.1 >= candidate.1 => Some(best),
+                _ => Some(candidate),
+            }
+        });
+        let Some(&(best_move, best_score)) = best else {
+            // The game was already over, so replaying the move will fail below
+            continue;
+        };
+        // Moves the AI doesn't consider worth searching are scored separately
+        let score = match scores.iter().find(|(position, _)| *position == played) {
+            Some(&(_, score)) => score,
+            None => ai.score_move(&game, played.0, played.1).unwrap_or(i32::MIN),
+        };
+
+        moves.push(MoveAnalysis {
+            ply,
+            player: game_move.player,
+            played,
+            score,
+            best_move,
+            best_score,
+            quality: classify(score, best_score),
+        });
+    }
+
+    Ok(GameAnalysis {
+        game_id: history.game_id,
+        moves,
+    })
+}
+
+/// Review `player`'s moves in a game, as a coach would after it
+///
+/// Every move the player made is graded by [`analyze_history`]. If one of
+/// them was a blunder, the first is pointed out along with the engine's line
+/// refuting it.
+///
+/// # Examples
+///
+/// ```
+/// use my_game_project::analysis::review_history;
+/// use my_game_project::game::{GameState, GameStatus};
+/// use my_game_project::player::Player;
+///
+/// let mut history = GameState::new().create_history();
+/// history.add_move(Player::X, (0, 0));
+/// history.add_move(Player::O, (0, 1)); // Only the center holds the draw
+///
+/// let review = review_history(&history, Player::O).unwrap();
+/// assert_eq!(review.moves.len(), 1);
+/// assert_eq!(review.mistake.unwrap().ply, 1);
+///
+/// // Playing out the refutation wins for X
+/// let mut game = history.replay().last().unwrap().unwrap();
+/// for position in review.refutation {
+///     game.make_move(position).unwrap();
+/// }
+/// assert_eq!(game.status, GameStatus::Won(Player::X));
+/// ```
+pub fn review_history(history: &GameHistory, player: Player) -> GameResult<Review> {
+    let analysis = analyze_history(history)?;
+    let moves: Vec<MoveAnalysis> = analysis.moves.into_iter().filter(|analysis| analysis.player == player).collect();
+    let mistake = moves.iter().find(|analysis| analysis.quality == MoveQuality::Blunder).cloned();
+
+    let mut refutation = Vec::new();
+    if let Some(mistake) = &mistake {
+        // The position just after the mistake, with the opponent to move
+        if let Some(after) = history.replay().nth(mistake.ply + 1) {
+            let after = after?;
+            if !after.is_over() {
+                let ai = MinimaxAI::new(player.opponent(), Difficulty::Hard);
+                refutation = ai.choose_move(&after)?.1.principal_variation;
+            }
+        }
+    }
+
+    Ok(Review { player, moves, mistake, refutation })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+
+    /// Grade the moves of a game on a 5x5 board needing `win_length` in a row
+    fn grades(win_length: usize, moves: &[(usize, usize)]) -> Vec<MoveQuality> {
+        let mut history = GameState::with_size(5, win_length).unwrap().create_history();
+        let mut player = Player::X;
+        for &position in moves {
+            history.add_move(player, position);
+            player = player.opponent();
+        }
+        analyze_history(&history).unwrap().moves.iter().map(|analysis| analysis.quality).collect()
+    }
+
+    #[test]
+    fn test_moves_are_graded_by_how_much_they_give_up() {
+        // A corner instead of the center is worse, though nothing is lost yet
+        assert_eq!(grades(4, &[(0, 0)]), [MoveQuality::Inaccuracy]);
+
+        // X gives up a forced win, then O walks into a forced loss it could have avoided
+        assert_eq!(grades(3, &[(0, 0), (2, 2)]), [MoveQuality::Inaccuracy, MoveQuality::Blunder]);
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify(2, 30), MoveQuality::Inaccuracy);
+        assert_eq!(classify(-2, 5), MoveQuality::Best);
+        assert_eq!(classify(10, 300), MoveQuality::Blunder);
+        assert_eq!(classify(-WIN_SCORE + 3, -WIN_SCORE + 5), MoveQuality::Best);
+        assert_eq!(classify(-WIN_SCORE + 3, -80), MoveQuality::Blunder);
+        assert_eq!(classify(i32::MIN, 0), MoveQuality::Blunder);
+    }
+}
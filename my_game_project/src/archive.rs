@@ -0,0 +1,467 @@
+//! Archive of finished games in a SQLite database
+//!
+//! Keeps each finished game in `games`, `moves` and `players` tables, so
+//! games can be looked up by when they were played, who played them and how
+//! they ended without reading every saved file. The CLI archives the games
+//! it finishes, and the server archives its games when configured to.
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions, SqliteRow};
+use sqlx::Row;
+use uuid::Uuid;
+
+use crate::error::{GameError, GameResult};
+use crate::game::GameStatus;
+use crate::history::{GameHistory, GameMove};
+use crate::player::Player;
+use crate::position::Position;
+use crate::save::PlayerInfo;
+use crate::stats::GameStats;
+
+/// How a finished game ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Outcome {
+    /// X won, whether by making a line or by O resigning or running out of time
+    #[serde(rename = "x")]
+    XWon,
+    /// O won
+    #[serde(rename = "o")]
+    OWon,
+    /// The game was drawn
+    #[serde(rename = "draw")]
+    Draw,
+}
+
+impl Outcome {
+    /// Returns how a game with this status ended, or `None` if it hasn't
+    pub fn of(status: GameStatus) -> Option<Self> {
+        match (status, status.winner()) {
+            (GameStatus::InProgress, _) => None,
+            (_, Some(Player::X)) => Some(Outcome::XWon),
+            (_, Some(Player::O)) => Some(Outcome::OWon),
+            (_, None) => Some(Outcome::Draw),
+        }
+    }
+
+    /// Returns the name the outcome is stored and queried by: `x`, `o` or `draw`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Outcome::XWon => "x",
+            Outcome::OWon => "o",
+            Outcome::Draw => "draw",
+        }
+    }
+}
+
+/// A finished game read back from a [`HistoryStore`]
+#[derive(Debug, Clone)]
+pub struct ArchivedGame {
+    /// The moves and result of the game
+    pub history: GameHistory,
+    /// Who played the game, if known
+    pub players: Vec<PlayerInfo>,
+}
+
+impl ArchivedGame {
+    /// Returns the name of whoever played `player`, or their mark if it isn't known
+    pub fn name_of(&self, player: Player) -> String {
+        self.players
+            .iter()
+            .find(|info| info.player == player)
+            .map(|info| info.name.clone())
+            .unwrap_or_else(|| player.to_string())
+    }
+}
+
+/// Which archived games to look up
+///
+/// Conditions left unset match every game. Deserializes from query strings
+/// such as `?player=alice&result=draw&from=2024-01-01T00:00:00Z`.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct HistoryQuery {
+    /// Only games started at or after this time
+    pub from: Option<DateTime<Utc>>,
+    /// Only games started before this time
+    pub to: Option<DateTime<Utc>>,
+    /// Only games one of the players played under this name
+    pub player: Option<String>,
+    /// Only games that ended this way
+    pub result: Option<Outcome>,
+}
+
+impl HistoryQuery {
+    /// Returns true if the game with this history, played by `players`, matches
+    ///
+    /// Games that haven't finished never match.
+    pub fn matches(&self, history: &GameHistory, players: &[PlayerInfo]) -> bool {
+        let Some(outcome) = history.final_status.and_then(Outcome::of) else {
+            return false;
+        };
+        self.from.is_none_or(|from| history.started_at >= from)
+            && self.to.is_none_or(|to| history.started_at < to)
+            && self.player.as_ref().is_none_or(|name| players.iter().any(|info| &info.name == name))
+            && self.result.is_none_or(|result| result == outcome)
+    }
+}
+
+/// Finished games kept in a SQLite database
+///
+/// # Examples
+///
+/// ```no_run
+/// # async fn example() -> my_game_project::error::GameResult<()> {
+/// use my_game_project::archive::{HistoryStore, Outcome};
+///
+/// let archive = HistoryStore::connect("sqlite://history.db").await?;
+/// let draws = archive.games_with_result(Outcome::Draw).await?;
+/// println!("{} games were drawn", draws.len());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct HistoryStore {
+    /// The connection pool
+    pool: SqlitePool,
+}
+
+impl HistoryStore {
+    /// Open (creating if necessary) the database at `url` and set up its tables
+    pub async fn connect(url: &str) -> GameResult<Self> {
+        let options = url
+            .parse::<SqliteConnectOptions>()
+            .map_err(storage_error)?
+            .create_if_missing(true);
+
+        let pool = SqlitePoolOptions::new()
+            .connect_with(options)
+            .await
+            .map_err(storage_error)?;
+
+        let tables = [
+            "CREATE TABLE IF NOT EXISTS games (\
+             id TEXT PRIMARY KEY, started_at TEXT NOT NULL, ended_at TEXT, result TEXT NOT NULL, \
+             status TEXT NOT NULL, board_size INTEGER NOT NULL, board_cols INTEGER, \
+             win_length INTEGER NOT NULL, starting_player TEXT NOT NULL, variant TEXT NOT NULL, \
+             variations TEXT NOT NULL, chat TEXT NOT NULL)",
+            "CREATE TABLE IF NOT EXISTS moves (\
+             game_id TEXT NOT NULL, ply INTEGER NOT NULL, player TEXT NOT NULL, \
+             row INTEGER NOT NULL, col INTEGER NOT NULL, played_at TEXT NOT NULL, \
+             comments TEXT NOT NULL, tag TEXT, PRIMARY KEY (game_id, ply))",
+            "CREATE TABLE IF NOT EXISTS players (\
+             game_id TEXT NOT NULL, player TEXT NOT NULL, name TEXT NOT NULL, \
+             human INTEGER NOT NULL, PRIMARY KEY (game_id, player))",
+            "CREATE INDEX IF NOT EXISTS games_started_at ON games (started_at)",
+            "CREATE INDEX IF NOT EXISTS players_name ON players (name)",
+        ];
+        for statement in tables {
+            sqlx::query(statement).execute(&pool).await.map_err(storage_error)?;
+        }
+
+        Ok(Self { pool })
+    }
+
+    /// Archive a finished game played by `players`, replacing any earlier copy of it
+    ///
+    /// Games that haven't finished are skipped.
+    pub async fn record(&self, history: &GameHistory, players: &[PlayerInfo]) -> GameResult<()> {
+        let Some(outcome) = history.final_status.and_then(Outcome::of) else {
+            return Ok(());
+        };
+        let id = history.game_id.to_string();
+        let mut transaction = self.pool.begin().await.map_err(storage_error)?;
+
+        sqlx::query(
+            "INSERT OR REPLACE INTO games (id, started_at, ended_at, result, status, board_size, \
+             board_cols, win_length, starting_player, variant, variations, chat) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&id)
+        .bind(timestamp(history.started_at))
+        .bind(history.ended_at.map(timestamp))
+        .bind(outcome.as_str())
+        .bind(to_json(&history.final_status)?)
+        .bind(history.board_size as i64)
+        .bind(history.board_cols.map(|cols| cols as i64))
+        .bind(history.win_length as i64)
+        .bind(history.starting_player.to_string())
+        .bind(to_json(&history.variant)?)
+        .bind(to_json(&history.variations)?)
+        .bind(to_json(&history.chat)?)
+        .execute(&mut *transaction)
+        .await
+        .map_err(storage_error)?;
+
+        for statement in ["DELETE FROM moves WHERE game_id = ?", "DELETE FROM players WHERE game_id = ?"] {
+            sqlx::query(statement)
+                .bind(&id)
+                .execute(&mut *transaction)
+                .await
+                .map_err(storage_error)?;
+        }
+
+        for (ply, game_move) in history.moves.iter().enumerate() {
+            sqlx::query(
+                "INSERT INTO moves (game_id, ply, player, row, col, played_at, comments, tag) \
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(&id)
+            .bind(ply as i64)
+            .bind(game_move.player.to_string())
+            .bind(game_move.position.row as i64)
+            .bind(game_move.position.col as i64)
+            .bind(timestamp(game_move.timestamp))
+            .bind(to_json(&game_move.comments)?)
+            .bind(game_move.tag.map(|tag| to_json(&tag)).transpose()?)
+            .execute(&mut *transaction)
+            .await
+            .map_err(storage_error)?;
+        }
+
+        for info in players {
+            sqlx::query("INSERT OR REPLACE INTO players (game_id, player, name, human) VALUES (?, ?, ?, ?)")
+                .bind(&id)
+                .bind(info.player.to_string())
+                .bind(&info.name)
+                .bind(info.human)
+                .execute(&mut *transaction)
+                .await
+                .map_err(storage_error)?;
+        }
+
+        transaction.commit().await.map_err(storage_error)
+    }
+
+    /// Load an archived game by ID
+    pub async fn load(&self, id: Uuid) -> GameResult<Option<ArchivedGame>> {
+        let row = sqlx::query("SELECT * FROM games WHERE id = ?")
+            .bind(id.to_string())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(storage_error)?;
+
+        match row {
+            Some(row) => Ok(Some(self.read_game(&row).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Load the archived games that match `query`, oldest first
+    pub async fn find(&self, query: &HistoryQuery) -> GameResult<Vec<ArchivedGame>> {
+        let mut sql = "SELECT * FROM games WHERE 1 = 1".to_string();
+        if query.from.is_some() {
+            sql.push_str(" AND started_at >= ?");
+        }
+        if query.to.is_some() {
+            sql.push_str(" AND started_at < ?");
+        }
+        if query.player.is_some() {
+            sql.push_str(" AND id IN (SELECT game_id FROM players WHERE name = ?)");
+        }
+        if query.result.is_some() {
+            sql.push_str(" AND result = ?");
+        }
+        sql.push_str(" ORDER BY started_at, id");
+
+        let mut statement = sqlx::query(&sql);
+        if let Some(from) = query.from {
+            statement = statement.bind(timestamp(from));
+        }
+        if let Some(to) = query.to {
+            statement = statement.bind(timestamp(to));
+        }
+        if let Some(player) = &query.player {
+            statement = statement.bind(player);
+        }
+        if let Some(result) = query.result {
+            statement = statement.bind(result.as_str());
+        }
+        let rows = statement.fetch_all(&self.pool).await.map_err(storage_error)?;
+
+        let mut games = Vec::with_capacity(rows.len());
+        for row in &rows {
+            games.push(self.read_game(row).await?);
+        }
+        Ok(games)
+    }
+
+    /// Load the games started from `from` up to, but not including, `to`
+    pub async fn games_between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> GameResult<Vec<ArchivedGame>> {
+        self.find(&HistoryQuery { from: Some(from), to: Some(to), ..Default::default() }).await
+    }
+
+    /// Load the games a player played under `name`
+    pub async fn games_of(&self, name: &str) -> GameResult<Vec<ArchivedGame>> {
+        self.find(&HistoryQuery { player: Some(name.to_string()), ..Default::default() }).await
+    }
+
+    /// Load the games that ended as `result`
+    pub async fn games_with_result(&self, result: Outcome) -> GameResult<Vec<ArchivedGame>> {
+        self.find(&HistoryQuery { result: Some(result), ..Default::default() }).await
+    }
+
+    /// Collects statistics over the archived games that match `query`
+    ///
+    /// Players are named as they were archived, and by their mark otherwise.
+    pub async fn stats(&self, query: &HistoryQuery) -> GameResult<GameStats> {
+        let mut stats = GameStats::default();
+        for game in self.find(query).await? {
+            stats.add_with_players(&game.history, &game.name_of(Player::X), &game.name_of(Player::O));
+        }
+        Ok(stats)
+    }
+
+    /// Read a game from its row in `games` along with its moves and players
+    async fn read_game(&self, row: &SqliteRow) -> GameResult<ArchivedGame> {
+        let id: String = row.get("id");
+        let game_id = Uuid::parse_str(&id).map_err(|e| GameError::DeserializationError(e.to_string()))?;
+
+        let moves = sqlx::query("SELECT * FROM moves WHERE game_id = ? ORDER BY ply")
+            .bind(&id)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(storage_error)?
+            .iter()
+            .map(|row| {
+                Ok(GameMove {
+                    player: parse_mark(row.get("player"))?,
+                    position: Position::new(row.get::<i64, _>("row") as usize, row.get::<i64, _>("col") as usize),
+                    timestamp: parse_timestamp(row.get("played_at"))?,
+                    comments: from_json(row.get("comments"))?,
+                    tag: row.get::<Option<&str>, _>("tag").map(from_json).transpose()?,
+                })
+            })
+            .collect::<GameResult<Vec<_>>>()?;
+
+        let players = sqlx::query("SELECT * FROM players WHERE game_id = ? ORDER BY rowid")
+            .bind(&id)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(storage_error)?
+            .iter()
+            .map(|row| {
+                Ok(PlayerInfo {
+                    player: parse_mark(row.get("player"))?,
+                    name: row.get("name"),
+                    human: row.get("human"),
+                })
+            })
+            .collect::<GameResult<Vec<_>>>()?;
+
+        let history = GameHistory {
+            game_id,
+            moves,
+            started_at: parse_timestamp(row.get("started_at"))?,
+            ended_at: row.get::<Option<&str>, _>("ended_at").map(parse_timestamp).transpose()?,
+            final_status: from_json(row.get("status"))?,
+            board_size: row.get::<i64, _>("board_size") as usize,
+            board_cols: row.get::<Option<i64>, _>("board_cols").map(|cols| cols as usize),
+            win_length: row.get::<i64, _>("win_length") as usize,
+            starting_player: parse_mark(row.get("starting_player"))?,
+            variant: from_json(row.get("variant"))?,
+            variations: from_json(row.get("variations"))?,
+            chat: from_json(row.get("chat"))?,
+        };
+
+        Ok(ArchivedGame { history, players })
+    }
+}
+
+/// Reads a player back from their stored mark
+fn parse_mark(mark: &str) -> GameResult<Player> {
+    mark.parse()
+        .map_err(|_| GameError::DeserializationError(format!("unknown player '{}'", mark)))
+}
+
+/// Formats a time so that stored times sort in the order they happened
+fn timestamp(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Nanos, true)
+}
+
+/// Reads back a time stored by [`timestamp`]
+fn parse_timestamp(text: &str) -> GameResult<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(text)
+        .map(|time| time.with_timezone(&Utc))
+        .map_err(|e| GameError::DeserializationError(e.to_string()))
+}
+
+/// Convert a database error into a `GameError`
+fn storage_error(error: sqlx::Error) -> GameError {
+    GameError::StorageError(error.to_string())
+}
+
+/// Serialize a value stored as a JSON document
+fn to_json<T: Serialize>(value: &T) -> GameResult<String> {
+    serde_json::to_string(value).map_err(GameError::from)
+}
+
+/// Deserialize a JSON document read from the database
+fn from_json<T: serde::de::DeserializeOwned>(json: &str) -> GameResult<T> {
+    serde_json::from_str(json).map_err(GameError::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+    use crate::history::MoveTag;
+
+    fn finished_game(moves: &[(usize, usize)]) -> GameHistory {
+        let mut game = GameState::new();
+        let mut history = game.create_history();
+        for &position in moves {
+            history.add_move(game.current_turn, position);
+            game.make_move(position).unwrap();
+        }
+        history.finish(game.status);
+        history
+    }
+
+    #[tokio::test]
+    async fn test_history_store() {
+        let path = std::env::temp_dir().join(format!("history_{}.db", Uuid::new_v4()));
+        let archive = HistoryStore::connect(&format!("sqlite://{}", path.display())).await.unwrap();
+
+        let mut x_wins = finished_game(&[(1, 1), (0, 0), (0, 1), (2, 2), (2, 1)]);
+        x_wins.annotate(0, "the centre").unwrap();
+        x_wins.tag(4, MoveTag::Good).unwrap();
+        let players = [
+            PlayerInfo { player: Player::X, name: "alice".to_string(), human: true },
+            PlayerInfo { player: Player::O, name: "AI (Hard)".to_string(), human: false },
+        ];
+        archive.record(&x_wins, &players).await.unwrap();
+        archive.record(&x_wins, &players).await.unwrap();
+
+        let mut draw = finished_game(&[(1, 1), (0, 0), (2, 2), (0, 2), (0, 1), (2, 1), (1, 0), (1, 2), (2, 0)]);
+        draw.started_at = x_wins.started_at + chrono::Duration::hours(1);
+        archive.record(&draw, &[]).await.unwrap();
+
+        // Unfinished games aren't archived
+        archive.record(&GameState::new().create_history(), &players).await.unwrap();
+
+        let loaded = archive.load(x_wins.game_id).await.unwrap().unwrap();
+        assert_eq!(loaded.history.moves, x_wins.moves);
+        assert_eq!(loaded.history.final_status, Some(GameStatus::Won(Player::X)));
+        assert_eq!(loaded.players, players);
+        assert_eq!(loaded.name_of(Player::O), "AI (Hard)");
+
+        assert_eq!(archive.find(&HistoryQuery::default()).await.unwrap().len(), 2);
+        assert_eq!(archive.games_of("alice").await.unwrap().len(), 1);
+        let draws = archive.games_with_result(Outcome::Draw).await.unwrap();
+        assert_eq!(draws[0].history.game_id, draw.game_id);
+        assert_eq!(draws[0].name_of(Player::X), "X");
+        let later = archive.games_between(draw.started_at, draw.started_at + chrono::Duration::hours(1));
+        assert_eq!(later.await.unwrap().len(), 1);
+
+        let stats = archive.stats(&HistoryQuery::default()).await.unwrap();
+        assert_eq!((stats.games, stats.x_wins, stats.draws), (2, 1, 1));
+        assert_eq!(stats.players["alice"].wins, 1);
+
+        let query = HistoryQuery { player: Some("alice".to_string()), ..Default::default() };
+        assert!(query.matches(&x_wins, &players));
+        assert!(!query.matches(&draw, &[]));
+
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,187 @@
+//! Benchmarks of the AI's search, for measuring the effect of optimizations
+//!
+//! For each difficulty and board size, the AI plays itself from the empty
+//! board, and every move it chooses adds to a [`Benchmark`] of the positions
+//! searched and the time taken. The AIs are seeded, so every run searches the
+//! same positions and runs before and after a change can be compared.
+
+use std::fmt;
+use std::time::Duration;
+
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+use crate::ai::{Difficulty, MinimaxAI};
+use crate::error::GameResult;
+use crate::game::GameState;
+use crate::player::Player;
+
+/// Board sizes benchmarked unless others are asked for
+pub const DEFAULT_SIZES: &[usize] = &[3, 4, 5];
+
+/// Marks in a row needed to win unless another number is asked for, or the board is smaller
+pub const DEFAULT_WIN_LENGTH: usize = 3;
+
+/// How one difficulty searched on one board size
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Benchmark {
+    /// The difficulty of the AI
+    pub difficulty: Difficulty,
+    /// The width and height of the board
+    pub size: usize,
+    /// The number of marks in a row needed to win
+    pub win_length: usize,
+    /// The number of moves chosen
+    pub moves: usize,
+    /// The number of positions searched over all the moves
+    pub nodes: usize,
+    /// Time spent choosing all the moves
+    pub elapsed: Duration,
+    /// Time spent choosing the slowest move
+    pub slowest_move: Duration,
+}
+
+impl Benchmark {
+    /// Positions searched per second
+    pub fn nodes_per_second(&self) -> f64 {
+        match self.elapsed.is_zero() {
+            true => 0.0,
+            false => self.nodes as f64 / self.elapsed.as_secs_f64(),
+        }
+    }
+
+    /// Average time spent choosing a move
+    pub fn average_move_time(&self) -> Duration {
+        match self.moves {
+            0 => Duration::ZERO,
+            moves => self.elapsed / moves as u32,
+        }
+    }
+}
+
+impl Serialize for Benchmark {
+    /// Serializes with the rates worked out, and times as fractional milliseconds
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let millis = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        let mut benchmark = serializer.serialize_struct("Benchmark", 9)?;
+        benchmark.serialize_field("difficulty", &self.difficulty)?;
+        benchmark.serialize_field("size", &self.size)?;
+        benchmark.serialize_field("win_length", &self.win_length)?;
+        benchmark.serialize_field("moves", &self.moves)?;
+        benchmark.serialize_field("nodes", &self.nodes)?;
+        benchmark.serialize_field("nodes_per_second", &self.nodes_per_second())?;
+        benchmark.serialize_field("time_ms", &millis(self.elapsed))?;
+        benchmark.serialize_field("average_move_ms", &millis(self.average_move_time()))?;
+        benchmark.serialize_field("slowest_move_ms", &millis(self.slowest_move))?;
+        benchmark.end()
+    }
+}
+
+/// Benchmarks for several difficulties and board sizes
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct BenchmarkReport {
+    /// One benchmark for each difficulty on each board size, sizes first
+    pub benchmarks: Vec<Benchmark>,
+}
+
+/// Benchmark the AI at `difficulty` on an `size`x`size` board, playing itself `games` times
+///
+/// # Examples
+///
+/// ```
+/// use my_game_project::ai::Difficulty;
+/// use my_game_project::bench;
+///
+/// let benchmark = bench::run(Difficulty::Medium, 3, 3, 1).unwrap();
+/// assert!(benchmark.moves >= 5);
+/// assert!(benchmark.nodes > 0);
+/// ```
+pub fn run(difficulty: Difficulty, size: usize, win_length: usize, games: usize) -> GameResult<Benchmark> {
+    let start = GameState::with_size(size, win_length)?;
+    let mut benchmark = Benchmark {
+        difficulty,
+        size,
+        win_length,
+        moves: 0,
+        nodes: 0,
+        elapsed: Duration::ZERO,
+        slowest_move: Duration::ZERO,
+    };
+
+    for seed in 0..games as u64 {
+        let x = MinimaxAI::new_with_seed(Player::X, difficulty, seed);
+        let o = MinimaxAI::new_with_seed(Player::O, difficulty, seed);
+        let mut game = start.clone();
+        while !game.is_over() {
+            let ai = if game.current_turn == Player::X { &x } else { &o };
+            let (position, stats) = ai.choose_move(&game)?;
+            game.make_move(position)?;
+
+            benchmark.moves += 1;
+            benchmark.nodes += stats.nodes;
+            benchmark.elapsed += stats.elapsed;
+            benchmark.slowest_move = benchmark.slowest_move.max(stats.elapsed);
+        }
+    }
+    Ok(benchmark)
+}
+
+/// Benchmark each difficulty on each board size, with at most `win_length` in a row to win
+pub fn run_all(difficulties: &[Difficulty], sizes: &[usize], win_length: usize, games: usize) -> GameResult<BenchmarkReport> {
+    let mut report = BenchmarkReport::default();
+    for &size in sizes {
+        for &difficulty in difficulties {
+            report.benchmarks.push(run(difficulty, size, win_length.min(size), games)?);
+        }
+    }
+    Ok(report)
+}
+
+impl fmt::Display for BenchmarkReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<10} {:<9} {:>6} {:>12} {:>14} {:>12} {:>12}",
+            "Difficulty", "Board", "Moves", "Nodes", "Nodes/sec", "Avg move", "Slowest"
+        )?;
+        for benchmark in &self.benchmarks {
+            let board = format!("{0}x{0} ({1})", benchmark.size, benchmark.win_length);
+            write!(
+                f,
+                "\n{:<10} {:<9} {:>6} {:>12} {:>14.0} {:>12} {:>12}",
+                format!("{:?}", benchmark.difficulty),
+                board,
+                benchmark.moves,
+                benchmark.nodes,
+                benchmark.nodes_per_second(),
+                format!("{:.2?}", benchmark.average_move_time()),
+                format!("{:.2?}", benchmark.slowest_move),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runs_are_repeatable() {
+        let report = run_all(&[Difficulty::Easy, Difficulty::Hard], &[3], 3, 2).unwrap();
+        assert_eq!(report.benchmarks.len(), 2);
+
+        // The same seeds play the same games, so search the same positions
+        let again = run(Difficulty::Hard, 3, 3, 2).unwrap();
+        assert_eq!((again.moves, again.nodes), (report.benchmarks[1].moves, report.benchmarks[1].nodes));
+
+        // Easy picks moves at random without searching
+        assert_eq!(report.benchmarks[0].nodes, 0);
+        assert_eq!(report.benchmarks[0].nodes_per_second(), 0.0);
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["benchmarks"][1]["size"], 3);
+        assert_eq!(json["benchmarks"][0]["nodes_per_second"], 0.0);
+        assert!(report.to_string().lines().nth(2).unwrap().starts_with("Hard"));
+    }
+}
@@ -0,0 +1,238 @@
+//! Typed client for the game server's REST API
+//!
+//! [`GameClient`] wraps the routes used to play a game, sending the request
+//! types defined here and decoding responses into the library's own types.
+//! Errors reported by the server come back as the matching [`GameError`].
+
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::ai::Difficulty;
+use crate::error::{GameError, GameResult};
+use crate::game::GameState;
+use crate::player::Player;
+use crate::position::Position;
+use crate::rules::Variant;
+use crate::server::{CreatorTokens, Opponent};
+
+/// Request for creating a game; unset fields take the server's defaults
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CreateGame {
+    /// The variant to play (standard tic-tac-toe by default)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variant: Option<Variant>,
+    /// The player who moves first (X by default)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub starting_player: Option<Player>,
+    /// Board size for variants that don't fix it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<usize>,
+    /// Marks in a row needed to win (the board size by default)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub win_length: Option<usize>,
+    /// Who the game is played against
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub opponent: Option<Opponent>,
+    /// Difficulty of the AI opponent
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub difficulty: Option<Difficulty>,
+    /// The seat the AI opponent plays (O by default)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ai_player: Option<Player>,
+    /// Seconds each player has per move
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub move_time_limit: Option<u64>,
+    /// A seat to leave open for another client to join
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub open_seat: Option<Player>,
+}
+
+/// A newly created game, with the tokens for the creator's seats
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreatedGame {
+    /// The game as created
+    #[serde(flatten)]
+    pub game: GameState,
+    /// The secret tokens for each seat not left open or played by the AI
+    pub tokens: CreatorTokens,
+}
+
+/// Request for making a move
+#[derive(Debug, Clone, Serialize)]
+pub struct MakeMove {
+    /// The cell to play
+    #[serde(flatten)]
+    pub position: Position,
+    /// The player making the move
+    pub player: Player,
+    /// The player's seat token
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// The game version the move was chosen against, to refuse it if the game has moved on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected_version: Option<u64>,
+}
+
+/// Request for having the AI make the current player's move
+#[derive(Debug, Clone, Serialize)]
+pub struct AiMove {
+    /// How strongly the AI plays
+    pub difficulty: Difficulty,
+    /// The current player's seat token
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// Whether the server should report how the AI searched
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub include_stats: bool,
+}
+
+/// An error response from the server
+#[derive(Debug, Deserialize)]
+struct ErrorBody {
+    code: String,
+    message: String,
+    position: Option<(usize, usize)>,
+    versions: Option<(u64, u64)>,
+}
+
+/// An async client for a game server
+#[derive(Debug, Clone)]
+pub struct GameClient {
+    /// The HTTP client used for every request
+    http: Client,
+    /// Base URL of the server, e.g. `http://localhost:3000`
+    server: String,
+}
+
+impl GameClient {
+    /// Create a client for the server at `server`
+    pub fn new(server: &str) -> Self {
+        Self {
+            http: Client::new(),
+            server: server.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Create a game
+    pub async fn create_game(&self, request: &CreateGame) -> GameResult<CreatedGame> {
+        send(self.http.post(format!("{}/games", self.server)).json(request)).await
+    }
+
+    /// Get a game by ID
+    pub async fn get_game(&self, id: Uuid) -> GameResult<GameState> {
+        send(self.http.get(self.url(id, ""))).await
+    }
+
+    /// Make a move, returning the game after it (and after the AI's reply, if it has one)
+    pub async fn make_move(&self, id: Uuid, request: &MakeMove) -> GameResult<GameState> {
+        send(self.http.post(self.url(id, "/move")).json(request)).await
+    }
+
+    /// Have the AI make the current player's move
+    pub async fn ai_move(&self, id: Uuid, request: &AiMove) -> GameResult<GameState> {
+        send(self.http.post(self.url(id, "/ai-move")).json(request)).await
+    }
+
+    /// Returns the URL of a route under the game with ID `id`
+    fn url(&self, id: Uuid, path: &str) -> String {
+        format!("{}/games/{}{}", self.server, id, path)
+    }
+}
+
+/// Send a request and decode its JSON response
+async fn send<T: DeserializeOwned>(request: RequestBuilder) -> GameResult<T> {
+    let response = request.send().await.map_err(network_error)?;
+    let status = response.status();
+    let body = response.bytes().await.map_err(network_error)?;
+
+    decode_response(status, &body)
+}
+
+/// Decode a response body, turning server errors into `GameError`s
+pub(crate) fn decode_response<T: DeserializeOwned>(status: StatusCode, body: &[u8]) -> GameResult<T> {
+    if status.is_success() {
+        return serde_json::from_slice(body).map_err(GameError::from);
+    }
+
+    let error: ErrorBody = serde_json::from_slice(body)
+        .map_err(|_| GameError::NetworkError(format!("server responded with {}", status)))?;
+
+    Err(match (error.code.as_str(), error.position) {
+        ("CELL_OCCUPIED", Some((row, col))) => GameError::CellOccupied(row, col),
+        ("INVALID_POSITION", Some((row, col))) => GameError::InvalidPosition(row, col),
+        ("GAME_FINISHED", _) => GameError::GameAlreadyFinished,
+        ("NOT_YOUR_TURN", _) => GameError::NotPlayerTurn,
+        ("VERSION_CONFLICT", _) => match error.versions {
+            Some((expected, actual)) => GameError::VersionConflict { expected, actual },
+            None => GameError::NetworkError(error.message),
+        },
+        ("NO_DRAW_OFFER", _) => GameError::NoDrawOffer,
+        ("NO_OPEN_SEAT", _) => GameError::NoOpenSeat,
+        ("INVALID_TOKEN", _) => GameError::InvalidToken(error.message),
+        ("GAME_NOT_FOUND", _) => GameError::GameNotFound(error.message),
+        ("PLAYER_NOT_FOUND", _) => GameError::PlayerNotFound(error.message),
+        _ => GameError::NetworkError(error.message),
+    })
+}
+
+/// Convert a transport error into a `GameError`
+pub(crate) fn network_error(error: reqwest::Error) -> GameError {
+    GameError::NetworkError(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::{create_router, AppState};
+    use std::net::TcpListener;
+
+    /// Start a server on a free local port, returning its base URL
+    fn spawn_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = create_router().with_state(AppState::new());
+        tokio::spawn(axum::Server::from_tcp(listener).unwrap().serve(app.into_make_service()));
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_play_through_client() {
+        let client = GameClient::new(&spawn_server());
+
+        let created = client.create_game(&CreateGame::default()).await.unwrap();
+        let id = created.game.id;
+        let x_move = MakeMove {
+            position: Position::new(1, 1),
+            player: Player::X,
+            token: created.tokens.x.clone(),
+            expected_version: Some(0),
+        };
+        let game = client.make_move(id, &x_move).await.unwrap();
+        assert_eq!((game.current_turn, game.version), (Player::O, 1));
+
+        // Server errors come back as the matching GameError
+        let o_move = MakeMove {
+            position: Position::new(1, 1),
+            player: Player::O,
+            token: created.tokens.o.clone(),
+            expected_version: None,
+        };
+        assert!(matches!(client.make_move(id, &o_move).await, Err(GameError::CellOccupied(1, 1))));
+        let wrong_token = MakeMove { position: Position::new(0, 0), token: None, ..o_move.clone() };
+        assert!(matches!(client.make_move(id, &wrong_token).await, Err(GameError::InvalidToken(_))));
+        let stale = MakeMove { position: Position::new(0, 0), expected_version: Some(0), ..o_move.clone() };
+        assert!(matches!(
+            client.make_move(id, &stale).await,
+            Err(GameError::VersionConflict { expected: 0, actual: 1 })
+        ));
+
+        let ai_move = AiMove { difficulty: Difficulty::Easy, token: created.tokens.o, include_stats: false };
+        let game = client.ai_move(id, &ai_move).await.unwrap();
+        assert_eq!(client.get_game(id).await.unwrap().board, game.board);
+        assert_eq!(game.current_turn, Player::X);
+
+        assert!(matches!(client.get_game(Uuid::new_v4()).await, Err(GameError::GameNotFound(_))));
+    }
+}
@@ -0,0 +1,290 @@
+//! Training data for machine learning, exported from finished games
+//!
+//! Every move of a finished game becomes a [`TrainingRecord`]: the board the
+//! move was played on as a flat tensor, the move, and how the game turned out
+//! for the player who made it. A [`DatasetWriter`] writes the records as
+//! newline-delimited JSON or CSV. Each record can also be written once for
+//! every [`Symmetry`] of the board, multiplying the data by up to eight.
+
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::error::{GameError, GameResult};
+use crate::game::{Cell, GameState, GameStatus};
+use crate::history::GameHistory;
+use crate::player::Player;
+use crate::symmetry::Symmetry;
+
+/// The header row of a CSV dataset
+const CSV_HEADER: &str = "game_id,ply,symmetry,rows,cols,player,board,move,row,col,outcome";
+
+/// How a dataset is written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DatasetFormat {
+    /// One JSON object per line
+    #[default]
+    Ndjson,
+    /// Comma-separated values with a header row, the board's cells separated by spaces
+    Csv,
+}
+
+impl FromStr for DatasetFormat {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "ndjson" | "jsonl" => Ok(DatasetFormat::Ndjson),
+            "csv" => Ok(DatasetFormat::Csv),
+            _ => Err(GameError::InvalidConfig(format!("unknown dataset format {}", s))),
+        }
+    }
+}
+
+impl fmt::Display for DatasetFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DatasetFormat::Ndjson => write!(f, "ndjson"),
+            DatasetFormat::Csv => write!(f, "csv"),
+        }
+    }
+}
+
+/// One move of a finished game, as a training example
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrainingRecord {
+    /// The game the move was played in
+    pub game_id: Uuid,
+    /// The number of moves played before this one
+    pub ply: usize,
+    /// How the board was turned or mirrored from the game as played
+    pub symmetry: Symmetry,
+    /// Number of rows on the board
+    pub rows: usize,
+    /// Number of columns on the board
+    pub cols: usize,
+    /// The player who made the move
+    pub player: Player,
+    /// The board before the move, row by row: 1 for the player's marks, -1 for the opponent's and 0 for empty cells
+    pub board: Vec<i8>,
+    /// The index of the move's cell in `board`
+    #[serde(rename = "move")]
+    pub move_index: usize,
+    /// The row of the move
+    pub row: usize,
+    /// The column of the move
+    pub col: usize,
+    /// How the game ended for the player: 1 for a win, 0 for a draw and -1 for a loss
+    pub outcome: i8,
+}
+
+impl TrainingRecord {
+    /// The record as a CSV row, without a line ending
+    fn to_csv(&self) -> String {
+        let board: Vec<String> = self.board.iter().map(i8::to_string).collect();
+        format!(
+            "{},{},{},{},{},{:?},{},{},{},{},{}",
+            self.game_id,
+            self.ply,
+            self.symmetry,
+            self.rows,
+            self.cols,
+            self.player,
+            board.join(" "),
+            self.move_index,
+            self.row,
+            self.col,
+            self.outcome
+        )
+    }
+}
+
+/// Turns every move of a finished game into training records
+///
+/// With `augment`, each move is recorded once for every symmetry of the
+/// board, starting with the game as played. Games that aren't finished have
+/// no outcome to learn from, so give no records.
+///
+/// # Examples
+///
+/// ```
+/// use my_game_project::dataset::records;
+/// use my_game_project::game::{Game, GameState};
+///
+/// let mut game = Game::new(GameState::new()).unwrap();
+/// for cell in [(0, 0), (1, 0), (0, 1), (1, 1), (0, 2)] {
+///     game.make_move(cell).unwrap();
+/// }
+///
+/// let plain = records(game.history(), false).unwrap();
+/// assert_eq!(plain.len(), 5);
+/// assert_eq!(plain[0].board, vec![0; 9]);
+/// assert_eq!(plain[4].outcome, 1);
+///
+/// assert_eq!(records(game.history(), true).unwrap().len(), 5 * 8);
+/// ```
+pub fn records(history: &GameHistory, augment: bool) -> GameResult<Vec<TrainingRecord>> {
+    let winner = match history.final_status {
+        None | Some(GameStatus::InProgress) => return Ok(Vec::new()),
+        Some(status) => status.winner(),
+    };
+
+    let mut records = Vec::new();
+    for (ply, (game, game_move)) in history.replay().zip(&history.moves).enumerate() {
+        let game = game?;
+        let (rows, cols) = (game.rows(), game.cols());
+        let symmetries = match augment {
+            true => Symmetry::of_board(rows, cols, game.variant),
+            false => vec![Symmetry::Identity],
+        };
+        let outcome = match winner {
+            Some(player) if player == game_move.player => 1,
+            Some(_) => -1,
+            None => 0,
+        };
+
+        for symmetry in symmetries {
+            let (row, col) = symmetry.apply((game_move.position.row, game_move.position.col), rows, cols);
+            records.push(TrainingRecord {
+                game_id: history.game_id,
+                ply,
+                symmetry,
+                rows,
+                cols,
+                player: game_move.player,
+                board: board_tensor(&game, game_move.player, symmetry),
+                move_index: row * cols + col,
+                row,
+                col,
+                outcome,
+            });
+        }
+    }
+    Ok(records)
+}
+
+/// The board as seen by `player`, after applying `symmetry`, row by row
+fn board_tensor(game: &GameState, player: Player, symmetry: Symmetry) -> Vec<i8> {
+    let (rows, cols) = (game.rows(), game.cols());
+    let mut tensor = vec![0; rows * cols];
+    for (row, cells) in game.board.iter().enumerate() {
+        for (col, cell) in cells.iter().enumerate() {
+            let (image_row, image_col) = symmetry.apply((row, col), rows, cols);
+            tensor[image_row * cols + image_col] = match cell {
+                Cell::Occupied(mark) if *mark == player => 1,
+                Cell::Occupied(_) => -1,
+                Cell::Empty => 0,
+            };
+        }
+    }
+    tensor
+}
+
+/// Writes training records to a file or other output
+///
+/// # Examples
+///
+/// ```
+/// use my_game_project::dataset::{DatasetFormat, DatasetWriter};
+/// use my_game_project::game::{Game, GameState};
+///
+/// let mut game = Game::new(GameState::new()).unwrap();
+/// for cell in [(0, 0), (1, 0), (0, 1), (1, 1), (0, 2)] {
+///     game.make_move(cell).unwrap();
+/// }
+///
+/// let mut writer = DatasetWriter::new(Vec::new(), DatasetFormat::Csv);
+/// assert_eq!(writer.write_history(game.history(), false).unwrap(), 5);
+///
+/// let csv = String::from_utf8(writer.finish().unwrap()).unwrap();
+/// assert!(csv.starts_with("game_id,ply,symmetry"));
+/// assert_eq!(csv.lines().count(), 6);
+/// ```
+pub struct DatasetWriter<W: Write> {
+    /// Where the records are written
+    out: W,
+    /// How the records are written
+    format: DatasetFormat,
+    /// Whether the CSV header has been written yet
+    started: bool,
+}
+
+impl<W: Write> DatasetWriter<W> {
+    /// Create a writer that writes records to `out` in `format`
+    pub fn new(out: W, format: DatasetFormat) -> Self {
+        Self { out, format, started: false }
+    }
+
+    /// Write a single record
+    pub fn write(&mut self, record: &TrainingRecord) -> GameResult<()> {
+        let line = match self.format {
+            DatasetFormat::Ndjson => {
+                serde_json::to_string(record)?
+            }
+            DatasetFormat::Csv => record.to_csv(),
+        };
+        self.start()?;
+        writeln!(self.out, "{}", line).map_err(GameError::from)
+    }
+
+    /// Write the records of every move of a finished game, returning how many were written
+    pub fn write_history(&mut self, history: &GameHistory, augment: bool) -> GameResult<usize> {
+        let records = records(history, augment)?;
+        for record in &records {
+            self.write(record)?;
+        }
+        Ok(records.len())
+    }
+
+    /// Flush the output and return it, writing the CSV header if no records were written
+    pub fn finish(mut self) -> GameResult<W> {
+        self.start()?;
+        self.out.flush()?;
+        Ok(self.out)
+    }
+
+    /// Write the CSV header before the first record
+    fn start(&mut self) -> GameResult<()> {
+        if !self.started && self.format == DatasetFormat::Csv {
+            writeln!(self.out, "{}", CSV_HEADER)?;
+        }
+        self.started = true;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+
+    #[test]
+    fn test_augmented_records_are_turned_consistently() {
+        let mut game = Game::new(GameState::new()).unwrap();
+        for cell in [(0, 0), (1, 1), (0, 1)] {
+            game.make_move(cell).unwrap();
+        }
+        game.resign(Player::X).unwrap();
+
+        let records = records(game.history(), true).unwrap();
+        assert_eq!(records.len(), 3 * 8);
+
+        // O's reply to X in the corner, turned a quarter clockwise
+        let turned = records
+            .iter()
+            .find(|record| record.ply == 1 && record.symmetry == Symmetry::Rotate90)
+            .unwrap();
+        assert_eq!(turned.player, Player::O);
+        assert_eq!(turned.board, vec![0, 0, -1, 0, 0, 0, 0, 0, 0]);
+        assert_eq!((turned.row, turned.col, turned.move_index), (1, 1, 4));
+        // O won when X resigned
+        assert_eq!(turned.outcome, 1);
+
+        let json = serde_json::to_value(turned).unwrap();
+        assert_eq!(json["symmetry"], "rotate90");
+        assert_eq!(json["move"], 4);
+    }
+}
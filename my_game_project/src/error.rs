@@ -1,7 +1,12 @@
+use std::io;
+
 use thiserror::Error;
 
 /// Represents errors that can occur during game operations
-#[derive(Error, Debug, Clone)]
+///
+/// IO and JSON errors keep the error they came from as their source, so
+/// callers can still match on things like [`io::ErrorKind::NotFound`].
+#[derive(Error, Debug)]
 pub enum GameError {
     /// The cell at the specified position is already occupied
     #[error("Cell at position ({0}, {1}) is already occupied")]
@@ -19,9 +24,66 @@ pub enum GameError {
     #[error("Not player's turn")]
     NotPlayerTurn,
 
+    /// The token presented doesn't match the player's seat
+    #[error("Invalid or missing token for player {0}")]
+    InvalidToken(String),
+
+    /// The game has no seat left for another player to join
+    #[error("Game has no open seat to join")]
+    NoOpenSeat,
+
+    /// The client acted on a version of the game that has since changed
+    #[error("Game is at version {actual}, not the expected version {expected}")]
+    VersionConflict {
+        /// The version the client expected
+        expected: u64,
+        /// The game's current version
+        actual: u64,
+    },
+
+    /// A request needed a logged-in user but had no valid bearer token
+    #[error("Not logged in: {0}")]
+    Unauthenticated(String),
+
+    /// The username and password don't match a registered user
+    #[error("Invalid username or password")]
+    InvalidCredentials,
+
+    /// Another user already registered the username
+    #[error("Username '{0}' is already taken")]
+    UsernameTaken(String),
+
+    /// A username or password that can't be registered
+    #[error("Invalid account details: {0}")]
+    InvalidAccount(String),
+
+    /// There is no draw offer from the opponent to answer
+    #[error("No draw offer to respond to")]
+    NoDrawOffer,
+
+    /// There are no moves to take back
+    #[error("No moves to undo")]
+    NothingToUndo,
+
+    /// Attempted to answer an undo request that wasn't made
+    #[error("No undo request to respond to")]
+    NoUndoRequest,
+
+    /// A webhook URL that events can't be sent to
+    #[error("Invalid webhook: {0}")]
+    InvalidWebhook(String),
+
+    /// A chat message that is empty or too long
+    #[error("Invalid chat message: {0}")]
+    InvalidChatMessage(String),
+
     /// IO error occurred
     #[error("IO error: {0}")]
-    IoError(String),
+    IoError(#[from] io::Error),
+
+    /// JSON that couldn't be read or written
+    #[error("JSON error: {0}")]
+    JsonError(#[from] serde_json::Error),
 
     /// Error during serialization
     #[error("Serialization error: {0}")]
@@ -31,17 +93,176 @@ pub enum GameError {
     #[error("Deserialization error: {0}")]
     DeserializationError(String),
 
+    /// Error talking to a game server
+    #[error("Network error: {0}")]
+    NetworkError(String),
+
+    /// Error reading from or writing to the storage backend
+    #[error("Storage error: {0}")]
+    StorageError(String),
+
     /// Game not found
     #[error("Game with ID {0} not found")]
     GameNotFound(String),
 
+    /// No rated games have been played by the player
+    #[error("Player {0} not found")]
+    PlayerNotFound(String),
+
     /// No valid moves available
     #[error("No valid moves available")]
     NoValidMoves,
 
+    /// Coordinates that couldn't be parsed into a position
+    #[error("Invalid coordinates: '{0}'")]
+    InvalidCoordinates(String),
+
+    /// The requested board size or win length is not playable
+    #[error("Invalid board: size {0} with win length {1}")]
+    InvalidBoardSize(usize, usize),
+
+    /// The board can't be reached by legal play
+    #[error("Unreachable position: {0}")]
+    UnreachablePosition(String),
+
+    /// The move index is past the end of the game's history
+    #[error("Move index {0} is out of range")]
+    InvalidMoveIndex(usize),
+
+    /// No variation with this index exists in the history
+    #[error("Variation {0} not found")]
+    VariationNotFound(usize),
+
     /// Invalid player type
     #[error("Invalid player type: {0}")]
     InvalidPlayerType(String),
+
+    /// A page cursor that wasn't issued by the server
+    #[error("Invalid cursor: {0}")]
+    InvalidCursor(String),
+
+    /// A server setting with a missing or unusable value
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+
+    /// A match must last at least one game
+    #[error("Invalid match length: best of {0}")]
+    InvalidMatchLength(usize),
+
+    /// The match with this id was not found
+    #[error("Match not found: {0}")]
+    MatchNotFound(String),
+
+    /// Attempted to play on in a match that has already been decided
+    #[error("Match is already finished")]
+    MatchAlreadyFinished,
+
+    /// Another server changed the game while this one was changing it
+    #[error("Game {0} was changed by another server")]
+    ConcurrentUpdate(String),
+
+    /// The AI didn't choose a move within the time it was given
+    #[error("The AI took longer than {0} ms to choose a move")]
+    AiTimedOut(u64),
+
+    /// The AI's search stopped without choosing a move
+    #[error("The AI failed to choose a move: {0}")]
+    AiFailed(String),
+
+    /// A game with this ID is already hosted
+    #[error("Game with ID {0} already exists")]
+    GameAlreadyExists(String),
+
+    /// The user isn't allowed to do this
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    /// A game written in the text notation that can't be read
+    #[error("Invalid notation: {0}")]
+    InvalidNotation(String),
+
+    /// The move at this ply of a history, counting from 0, can't be played
+    #[error("Illegal move at ply {0}: {1}")]
+    IllegalHistoryMove(usize, #[source] Box<GameError>),
+
+    /// A save file written by a newer release, with a schema this one doesn't know
+    #[error("Save file schema version {0} is newer than this release supports")]
+    UnsupportedSchemaVersion(u32),
+
+    /// A save file whose contents don't match the checksum or signature saved with them
+    #[error("Corrupt save: {0}")]
+    CorruptSave(String),
+
+    /// A saved game whose state and history don't agree
+    #[error("Invalid import: {0}")]
+    InvalidImport(String),
+}
+
+impl GameError {
+    /// A stable, machine-readable code for the error, like `CELL_OCCUPIED`
+    ///
+    /// Codes don't change between releases, unlike the messages, so clients
+    /// can branch on them. The server sends them in its error responses.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::error::GameError;
+    ///
+    /// assert_eq!(GameError::CellOccupied(1, 1).error_code(), "CELL_OCCUPIED");
+    /// assert_eq!(GameError::NotPlayerTurn.error_code(), "NOT_YOUR_TURN");
+    /// ```
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            GameError::CellOccupied(_, _) => "CELL_OCCUPIED",
+            GameError::InvalidPosition(_, _) => "INVALID_POSITION",
+            GameError::GameAlreadyFinished => "GAME_FINISHED",
+            GameError::NotPlayerTurn => "NOT_YOUR_TURN",
+            GameError::InvalidToken(_) => "INVALID_TOKEN",
+            GameError::NoOpenSeat => "NO_OPEN_SEAT",
+            GameError::VersionConflict { .. } => "VERSION_CONFLICT",
+            GameError::Unauthenticated(_) => "UNAUTHENTICATED",
+            GameError::InvalidCredentials => "INVALID_CREDENTIALS",
+            GameError::UsernameTaken(_) => "USERNAME_TAKEN",
+            GameError::InvalidAccount(_) => "INVALID_ACCOUNT",
+            GameError::NoDrawOffer => "NO_DRAW_OFFER",
+            GameError::NothingToUndo => "NOTHING_TO_UNDO",
+            GameError::NoUndoRequest => "NO_UNDO_REQUEST",
+            GameError::InvalidWebhook(_) => "INVALID_WEBHOOK",
+            GameError::InvalidChatMessage(_) => "INVALID_CHAT_MESSAGE",
+            GameError::IoError(_) => "IO_ERROR",
+            GameError::JsonError(e) if e.is_io() => "IO_ERROR",
+            GameError::SerializationError(_) => "SERIALIZATION_ERROR",
+            // What a client sent couldn't be read
+            GameError::DeserializationError(_) | GameError::JsonError(_) => "INVALID_REQUEST",
+            GameError::NetworkError(_) => "NETWORK_ERROR",
+            GameError::StorageError(_) => "STORAGE_ERROR",
+            GameError::GameNotFound(_) => "GAME_NOT_FOUND",
+            GameError::PlayerNotFound(_) => "PLAYER_NOT_FOUND",
+            GameError::NoValidMoves => "NO_VALID_MOVES",
+            GameError::InvalidCoordinates(_) => "INVALID_COORDINATES",
+            GameError::InvalidBoardSize(_, _) => "INVALID_BOARD_SIZE",
+            GameError::UnreachablePosition(_) => "UNREACHABLE_POSITION",
+            GameError::InvalidMoveIndex(_) => "INVALID_MOVE_INDEX",
+            GameError::VariationNotFound(_) => "VARIATION_NOT_FOUND",
+            GameError::InvalidPlayerType(_) => "INVALID_PLAYER_TYPE",
+            GameError::InvalidCursor(_) => "INVALID_CURSOR",
+            GameError::InvalidConfig(_) => "INVALID_CONFIG",
+            GameError::InvalidMatchLength(_) => "INVALID_MATCH_LENGTH",
+            GameError::MatchNotFound(_) => "MATCH_NOT_FOUND",
+            GameError::MatchAlreadyFinished => "MATCH_FINISHED",
+            GameError::ConcurrentUpdate(_) => "CONCURRENT_UPDATE",
+            GameError::AiTimedOut(_) => "AI_TIMEOUT",
+            GameError::AiFailed(_) => "AI_FAILED",
+            GameError::GameAlreadyExists(_) => "GAME_EXISTS",
+            GameError::Forbidden(_) => "FORBIDDEN",
+            GameError::InvalidNotation(_) => "INVALID_NOTATION",
+            GameError::IllegalHistoryMove(_, _) => "ILLEGAL_HISTORY_MOVE",
+            GameError::UnsupportedSchemaVersion(_) => "UNSUPPORTED_SCHEMA_VERSION",
+            GameError::CorruptSave(_) => "CORRUPT_SAVE",
+            GameError::InvalidImport(_) => "INVALID_IMPORT",
+        }
+    }
 }
 
 /// A specialized Result type for game operations
@@ -0,0 +1,62 @@
+//! Events describing what happened in a game
+//!
+//! Every change made to a [`GameState`](crate::game::GameState) through its
+//! methods is queued as [`GameEvent`]s, which are taken with
+//! [`GameState::take_events`](crate::game::GameState::take_events). Whatever
+//! reacts to a game, like its history, the server's subscribers or its
+//! metrics, reads the events instead of comparing the game before and after.
+//!
+//! # Examples
+//!
+//! ```
+//! use my_game_project::events::GameEvent;
+//! use my_game_project::game::{GameState, GameStatus};
+//! use my_game_project::player::Player;
+//! use my_game_project::position::Position;
+//!
+//! let mut game = GameState::new();
+//! game.make_move((1, 1)).unwrap();
+//! game.resign(Player::O).unwrap();
+//!
+//! assert_eq!(
+//!     game.take_events(),
+//!     [
+//!         GameEvent::MoveMade { player: Player::X, position: Position::new(1, 1) },
+//!         GameEvent::TurnChanged { player: Player::O },
+//!         GameEvent::GameFinished { status: GameStatus::Resigned(Player::O) },
+//!     ]
+//! );
+//! assert!(game.take_events().is_empty());
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::GameStatus;
+use crate::player::Player;
+use crate::position::Position;
+
+/// Something that happened in a game
+///
+/// Serialized with the kind of event in an `event` field, like
+/// `{"event": "move_made", "player": "x", "position": {"row": 1, "col": 1}}`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum GameEvent {
+    /// `player` put their mark on the board
+    MoveMade {
+        /// The player who moved
+        player: Player,
+        /// Where the mark landed, which in Connect Four is the lowest empty cell of the column played
+        position: Position,
+    },
+    /// It became `player`'s turn, after a move or after moves were taken back
+    TurnChanged {
+        /// The player to move
+        player: Player,
+    },
+    /// The game ended, by a move, a resignation, an agreed draw or running out of time
+    GameFinished {
+        /// How the game ended
+        status: GameStatus,
+    },
+}
@@ -0,0 +1,413 @@
+//! C functions for playing games from other languages
+//!
+//! Built with the `ffi` feature and declared in `include/tictactoe.h`, so a C,
+//! C++ or Unity client can use the engine's rules and AI instead of
+//! reimplementing them. Build the library for linking with:
+//!
+//! ```bash
+//! cargo rustc --lib --release --no-default-features --features ffi --crate-type cdylib
+//! ```
+//!
+//! A game is created with [`ttt_game_new`] or [`ttt_game_with_size`], played
+//! with [`ttt_game_make_move`] and [`ttt_game_ai_move`], and released with
+//! [`ttt_game_free`]. Functions that can fail return `false`, after which
+//! [`ttt_last_error`] says what went wrong and [`ttt_last_error_code`] gives
+//! the [`error_code`](GameError::error_code) the REST API would answer with.
+//! Errors are kept per thread. A panic is caught before it reaches C and
+//! reported the same way, with the code `PANIC`.
+//!
+//! ```c
+//! TttGame *game = ttt_game_new();
+//! if (!ttt_game_make_move(game, 1, 1)) {
+//!     fprintf(stderr, "%s\n", ttt_last_error());
+//! }
+//! ttt_game_ai_move(game, TttDifficulty_Hard, NULL, NULL);
+//! ttt_game_free(game);
+//! ```
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::ffi::{c_char, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use crate::ai::{Difficulty, MinimaxAI};
+use crate::error::{GameError, GameResult};
+use crate::events::GameEvent;
+use crate::game::{Cell, Game, GameState, GameStatus};
+use crate::player::{GamePlayer, Player};
+
+thread_local! {
+    /// The code and message of the last error on this thread
+    static LAST_ERROR: RefCell<Option<(CString, CString)>> = const { RefCell::new(None) };
+}
+
+/// A game played through the C functions, along with its history
+pub struct TttGame {
+    game: Game,
+}
+
+/// What is in a cell, and also how players are passed to C
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TttCell {
+    /// No mark, or no player
+    Empty = 0,
+    /// X's mark
+    X = 1,
+    /// O's mark
+    O = 2,
+}
+
+impl From<Player> for TttCell {
+    fn from(player: Player) -> Self {
+        match player {
+            Player::X => TttCell::X,
+            Player::O => TttCell::O,
+        }
+    }
+}
+
+impl From<Cell> for TttCell {
+    fn from(cell: Cell) -> Self {
+        match cell {
+            Cell::Empty => TttCell::Empty,
+            Cell::Occupied(player) => player.into(),
+        }
+    }
+}
+
+/// Whether a game is still going and how it ended, as in [`GameStatus`]
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TttStatus {
+    /// The game is still being played
+    InProgress = 0,
+    /// A player completed a line
+    Won = 1,
+    /// The board filled up, or the players agreed to a draw
+    Draw = 2,
+    /// A player resigned
+    Resigned = 3,
+    /// A player ran out of time
+    TimedOut = 4,
+}
+
+impl From<GameStatus> for TttStatus {
+    fn from(status: GameStatus) -> Self {
+        match status {
+            GameStatus::InProgress => TttStatus::InProgress,
+            GameStatus::Won(_) => TttStatus::Won,
+            GameStatus::Draw => TttStatus::Draw,
+            GameStatus::Resigned(_) => TttStatus::Resigned,
+            GameStatus::TimedOut(_) => TttStatus::TimedOut,
+        }
+    }
+}
+
+/// How well the AI plays, as in [`Difficulty`]
+///
+/// Functions take it as a plain `uint32_t`, since a value outside the enum
+/// would be undefined behaviour in Rust.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TttDifficulty {
+    /// Random moves
+    Easy = 0,
+    /// A shallow search
+    Medium = 1,
+    /// Perfect play where the board allows it
+    Hard = 2,
+}
+
+/// Reads a difficulty passed from C as one of the [`TttDifficulty`] values
+fn difficulty(value: u32) -> GameResult<Difficulty> {
+    match value {
+        v if v == TttDifficulty::Easy as u32 => Ok(Difficulty::Easy),
+        v if v == TttDifficulty::Medium as u32 => Ok(Difficulty::Medium),
+        v if v == TttDifficulty::Hard as u32 => Ok(Difficulty::Hard),
+        _ => Err(GameError::InvalidPlayerType(format!("unknown difficulty {}", value))),
+    }
+}
+
+/// Keeps `code` and `message` for [`ttt_last_error`] and [`ttt_last_error_code`]
+fn keep_last_error(code: &str, message: &str) {
+    let code = CString::new(code).unwrap_or_default();
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some((code, message)));
+}
+
+/// Keeps `error` for [`ttt_last_error`] and [`ttt_last_error_code`]
+fn set_last_error(error: &GameError) {
+    keep_last_error(error.error_code(), &error.to_string());
+}
+
+/// Runs the body of a C function, returning `fallback` if it panics
+///
+/// Unwinding into C is undefined behaviour, so the panic stops here and is
+/// kept as the last error instead.
+fn guard<T>(fallback: T, body: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or_else(|payload: Box<dyn Any + Send>| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unknown panic");
+        keep_last_error("PANIC", &format!("The engine panicked: {}", message));
+        fallback
+    })
+}
+
+/// Returns whether `result` succeeded, keeping its error if it didn't
+fn succeeded(result: GameResult<()>) -> bool {
+    result.map_err(|e| set_last_error(&e)).is_ok()
+}
+
+/// Moves a new game onto the heap for C to hold, or returns null if it can't be recorded
+fn into_handle(state: GameResult<GameState>) -> *mut TttGame {
+    match state.and_then(Game::new) {
+        Ok(game) => Box::into_raw(Box::new(TttGame { game })),
+        Err(e) => {
+            set_last_error(&e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Creates a standard 3x3 game, to be released with [`ttt_game_free`]
+#[no_mangle]
+pub extern "C" fn ttt_game_new() -> *mut TttGame {
+    guard(ptr::null_mut(), || into_handle(Ok(GameState::new())))
+}
+
+/// Creates a game on a `size` x `size` board won by `win_length` marks in a row
+///
+/// Returns null if there's no such game, such as when `win_length` is longer
+/// than the board.
+#[no_mangle]
+pub extern "C" fn ttt_game_with_size(size: usize, win_length: usize) -> *mut TttGame {
+    guard(ptr::null_mut(), || into_handle(GameState::with_size(size, win_length)))
+}
+
+/// Releases a game
+///
+/// # Safety
+///
+/// `game` must be null or a game from [`ttt_game_new`] or [`ttt_game_with_size`]
+/// that hasn't been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn ttt_game_free(game: *mut TttGame) {
+    guard((), || {
+        if !game.is_null() {
+            drop(Box::from_raw(game));
+        }
+    })
+}
+
+/// Places a mark at (`row`, `col`) for the player whose turn it is
+///
+/// # Safety
+///
+/// `game` must be a live game from [`ttt_game_new`] or [`ttt_game_with_size`].
+#[no_mangle]
+pub unsafe extern "C" fn ttt_game_make_move(game: *mut TttGame, row: usize, col: usize) -> bool {
+    guard(false, || {
+        let game = &mut (*game).game;
+        succeeded(game.make_move((row, col)))
+    })
+}
+
+/// Lets the AI move for the player whose turn it is
+///
+/// `difficulty` is one of the [`TttDifficulty`] values. Where the mark landed
+/// is written to `row` and `col`, unless they are null.
+///
+/// # Safety
+///
+/// `game` must be a live game from [`ttt_game_new`] or [`ttt_game_with_size`],
+/// and `row` and `col` must each be null or valid to write to.
+#[no_mangle]
+pub unsafe extern "C" fn ttt_game_ai_move(
+    game: *mut TttGame,
+    difficulty: u32,
+    row: *mut usize,
+    col: *mut usize,
+) -> bool {
+    guard(false, || {
+        let game = &mut (*game).game;
+        let moved = self::difficulty(difficulty).and_then(|difficulty| {
+            let ai = MinimaxAI::new(game.current_turn, difficulty);
+            ai.get_move(game).and_then(|position| game.make_move(position))
+        });
+        if !succeeded(moved) {
+            return false;
+        }
+
+        for event in game.take_events() {
+            if let GameEvent::MoveMade { position, .. } = event {
+                if !row.is_null() {
+                    *row = position.row;
+                }
+                if !col.is_null() {
+                    *col = position.col;
+                }
+            }
+        }
+        true
+    })
+}
+
+/// Returns the number of rows on the board
+///
+/// # Safety
+///
+/// `game` must be a live game from [`ttt_game_new`] or [`ttt_game_with_size`].
+#[no_mangle]
+pub unsafe extern "C" fn ttt_game_rows(game: *const TttGame) -> usize {
+    guard(0, || (*game).game.rows())
+}
+
+/// Returns the number of columns on the board
+///
+/// # Safety
+///
+/// `game` must be a live game from [`ttt_game_new`] or [`ttt_game_with_size`].
+#[no_mangle]
+pub unsafe extern "C" fn ttt_game_cols(game: *const TttGame) -> usize {
+    guard(0, || (*game).game.cols())
+}
+
+/// Copies the board into `cells` a row at a time, returning how many cells it has
+///
+/// Nothing is copied if `len` is less than that, so passing a null `cells`
+/// and a `len` of 0 finds out how big a buffer is needed.
+///
+/// # Safety
+///
+/// `game` must be a live game from [`ttt_game_new`] or [`ttt_game_with_size`],
+/// and `cells` must be valid for writing `len` cells.
+#[no_mangle]
+pub unsafe extern "C" fn ttt_game_board(game: *const TttGame, cells: *mut TttCell, len: usize) -> usize {
+    guard(0, || {
+        let game = &(*game).game;
+        let count = game.rows() * game.cols();
+        if cells.is_null() || len < count {
+            return count;
+        }
+
+        let board = game.board.iter().flatten().map(|&cell| TttCell::from(cell));
+        for (i, cell) in board.enumerate() {
+            *cells.add(i) = cell;
+        }
+        count
+    })
+}
+
+/// Returns the player whose turn it is
+///
+/// # Safety
+///
+/// `game` must be a live game from [`ttt_game_new`] or [`ttt_game_with_size`].
+#[no_mangle]
+pub unsafe extern "C" fn ttt_game_current_turn(game: *const TttGame) -> TttCell {
+    guard(TttCell::Empty, || {
+        let game = &(*game).game;
+        game.current_turn.into()
+    })
+}
+
+/// Returns whether the game is still going, and how it ended if not
+///
+/// # Safety
+///
+/// `game` must be a live game from [`ttt_game_new`] or [`ttt_game_with_size`].
+#[no_mangle]
+pub unsafe extern "C" fn ttt_game_status(game: *const TttGame) -> TttStatus {
+    guard(TttStatus::InProgress, || {
+        let game = &(*game).game;
+        game.status.into()
+    })
+}
+
+/// Returns the winner, or [`TttCell::Empty`] if there isn't one
+///
+/// # Safety
+///
+/// `game` must be a live game from [`ttt_game_new`] or [`ttt_game_with_size`].
+#[no_mangle]
+pub unsafe extern "C" fn ttt_game_winner(game: *const TttGame) -> TttCell {
+    guard(TttCell::Empty, || {
+        let game = &(*game).game;
+        game.status.winner().map_or(TttCell::Empty, TttCell::from)
+    })
+}
+
+/// Returns a description of the last error on this thread, or null if there hasn't been one
+///
+/// The string stays valid until the next error on the same thread.
+#[no_mangle]
+pub extern "C" fn ttt_last_error() -> *const c_char {
+    guard(ptr::null(), || {
+        LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |(_, message)| message.as_ptr()))
+    })
+}
+
+/// Returns the code of the last error on this thread, like `CELL_OCCUPIED`, or null if there hasn't been one
+///
+/// The string stays valid until the next error on the same thread.
+#[no_mangle]
+pub extern "C" fn ttt_last_error_code() -> *const c_char {
+    guard(ptr::null(), || {
+        LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |(code, _)| code.as_ptr()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    #[test]
+    fn test_play_through_ffi() {
+        unsafe {
+            let game = ttt_game_new();
+            assert!(ttt_game_make_move(game, 1, 1));
+            assert_eq!(ttt_game_current_turn(game), TttCell::O);
+
+            // A refused move is explained by the last error
+            assert!(!ttt_game_make_move(game, 1, 1));
+            assert_eq!(CStr::from_ptr(ttt_last_error_code()).to_str(), Ok("CELL_OCCUPIED"));
+            assert!(!CStr::from_ptr(ttt_last_error()).to_bytes().is_empty());
+
+            // A difficulty C made up is refused rather than trusted
+            assert!(!ttt_game_ai_move(game, 7, ptr::null_mut(), ptr::null_mut()));
+            assert_eq!(CStr::from_ptr(ttt_last_error_code()).to_str(), Ok("INVALID_PLAYER_TYPE"));
+
+            let (mut row, mut col) = (usize::MAX, usize::MAX);
+            assert!(ttt_game_ai_move(game, TttDifficulty::Hard as u32, &mut row, &mut col));
+            assert!(row < 3 && col < 3);
+
+            let mut cells = [TttCell::Empty; 9];
+            assert_eq!(ttt_game_board(game, ptr::null_mut(), 0), 9);
+            assert_eq!(ttt_game_board(game, cells.as_mut_ptr(), cells.len()), 9);
+            assert_eq!(cells[4], TttCell::X);
+            assert_eq!(cells[row * 3 + col], TttCell::O);
+
+            // Hard never loses, so letting it finish the game against itself draws
+            while ttt_game_status(game) == TttStatus::InProgress {
+                assert!(ttt_game_ai_move(game, TttDifficulty::Hard as u32, ptr::null_mut(), ptr::null_mut()));
+            }
+            assert_eq!(ttt_game_status(game), TttStatus::Draw);
+            assert_eq!(ttt_game_winner(game), TttCell::Empty);
+            ttt_game_free(game);
+        }
+
+        assert!(ttt_game_with_size(3, 4).is_null());
+        assert_eq!(unsafe { CStr::from_ptr(ttt_last_error_code()) }.to_str(), Ok("INVALID_BOARD_SIZE"));
+
+        // A panic stops at the C boundary and becomes the last error
+        assert!(guard(ptr::null_mut::<TttGame>(), || panic!("lost the board")).is_null());
+        assert_eq!(unsafe { CStr::from_ptr(ttt_last_error_code()) }.to_str(), Ok("PANIC"));
+        assert!(unsafe { CStr::from_ptr(ttt_last_error()) }.to_str().unwrap().contains("lost the board"));
+    }
+}
@@ -1,12 +1,30 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::Deref;
+
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "tracing")]
+use tracing::{field, instrument, Span};
 use uuid::Uuid;
 
 use crate::error::{GameError, GameResult};
+use crate::events::GameEvent;
 use crate::history::GameHistory;
 use crate::player::Player;
+use crate::position::Position;
+use crate::render::BoardRenderer;
+use crate::rules::{GameRules, Variant, LINE_DIRECTIONS};
+#[cfg(feature = "files")]
+use crate::save::{Footer, PlayerInfo, SaveBundle, SaveFormat};
+use crate::save::{self, Migration, Saved};
+use crate::symmetry::Symmetry;
 
 /// Represents a cell on the game board
+///
+/// Serialized as the mark in it, `"x"` or `"o"`, or `null` if it's empty.
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(into = "Option<Player>", from = "SerializedCell")]
 pub enum Cell {
     /// An empty cell
     Empty,
@@ -14,8 +32,59 @@ pub enum Cell {
     Occupied(Player),
 }
 
+impl From<Cell> for Option<Player> {
+    fn from(cell: Cell) -> Self {
+        match cell {
+            Cell::Empty => None,
+            Cell::Occupied(player) => Some(player),
+        }
+    }
+}
+
+/// The ways a [`Cell`] has been serialized
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SerializedCell {
+    /// The mark in the cell, or `null` if it's empty
+    Mark(Option<Player>),
+    /// `"Empty"` or `{"Occupied": "X"}`, as older releases wrote cells
+    Legacy(LegacyCell),
+}
+
+/// A [`Cell`] as older releases serialized it
+#[derive(Deserialize)]
+enum LegacyCell {
+    Empty,
+    Occupied(Player),
+}
+
+impl From<SerializedCell> for Cell {
+    fn from(cell: SerializedCell) -> Self {
+        match cell {
+            SerializedCell::Mark(None) | SerializedCell::Legacy(LegacyCell::Empty) => Cell::Empty,
+            SerializedCell::Mark(Some(player)) | SerializedCell::Legacy(LegacyCell::Occupied(player)) => {
+                Cell::Occupied(player)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Cell {
+    /// Writes the mark in the cell, or `.` if it's empty
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cell::Empty => write!(f, "."),
+            Cell::Occupied(player) => write!(f, "{}", player),
+        }
+    }
+}
+
 /// Represents the current status of the game
+///
+/// Serialized as what happened and who won, if anyone did, like
+/// `{"status": "resigned", "winner": "o"}` or `{"status": "draw"}`.
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(into = "StatusFields", try_from = "SerializedStatus")]
 pub enum GameStatus {
     /// The game is still in progress
     InProgress,
@@ -23,6 +92,168 @@ pub enum GameStatus {
     Won(Player),
     /// The game ended in a draw
     Draw,
+    /// The specified player resigned, conceding the game to their opponent
+    Resigned(Player),
+    /// The specified player ran out of time, forfeiting the game to their opponent
+    TimedOut(Player),
+}
+
+impl GameStatus {
+    /// Returns the player who won the game, if any
+    pub fn winner(&self) -> Option<Player> {
+        match self {
+            GameStatus::Won(player) => Some(*player),
+            GameStatus::Resigned(player) | GameStatus::TimedOut(player) => Some(player.opponent()),
+            GameStatus::InProgress | GameStatus::Draw => None,
+        }
+    }
+}
+
+/// What happened in a game, as serialized in a [`GameStatus`]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum StatusKind {
+    InProgress,
+    Won,
+    Draw,
+    Resigned,
+    TimedOut,
+}
+
+/// The fields a [`GameStatus`] is serialized as
+#[derive(Serialize, Deserialize)]
+struct StatusFields {
+    status: StatusKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    winner: Option<Player>,
+}
+
+/// The ways a [`GameStatus`] has been serialized
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SerializedStatus {
+    Fields(StatusFields),
+    /// `"InProgress"` or `{"Won": "X"}`, as older releases wrote statuses
+    Legacy(LegacyStatus),
+}
+
+/// A [`GameStatus`] as older releases serialized it
+#[derive(Deserialize)]
+enum LegacyStatus {
+    InProgress,
+    Won(Player),
+    Draw,
+    Resigned(Player),
+    TimedOut(Player),
+}
+
+impl From<GameStatus> for StatusFields {
+    fn from(status: GameStatus) -> Self {
+        let status_kind = match status {
+            GameStatus::InProgress => StatusKind::InProgress,
+            GameStatus::Won(_) => StatusKind::Won,
+            GameStatus::Draw => StatusKind::Draw,
+            GameStatus::Resigned(_) => StatusKind::Resigned,
+            GameStatus::TimedOut(_) => StatusKind::TimedOut,
+        };
+        StatusFields { status: status_kind, winner: status.winner() }
+    }
+}
+
+impl TryFrom<SerializedStatus> for GameStatus {
+    type Error = GameError;
+
+    fn try_from(status: SerializedStatus) -> GameResult<Self> {
+        let StatusFields { status, winner } = match status {
+            SerializedStatus::Fields(fields) => fields,
+            SerializedStatus::Legacy(legacy) => {
+                return Ok(match legacy {
+                    LegacyStatus::InProgress => GameStatus::InProgress,
+                    LegacyStatus::Won(player) => GameStatus::Won(player),
+                    LegacyStatus::Draw => GameStatus::Draw,
+                    LegacyStatus::Resigned(player) => GameStatus::Resigned(player),
+                    LegacyStatus::TimedOut(player) => GameStatus::TimedOut(player),
+                })
+            }
+        };
+        match (status, winner) {
+            (StatusKind::InProgress, None) => Ok(GameStatus::InProgress),
+            (StatusKind::Draw, None) => Ok(GameStatus::Draw),
+            (StatusKind::Won, Some(winner)) => Ok(GameStatus::Won(winner)),
+            (StatusKind::Resigned, Some(winner)) => Ok(GameStatus::Resigned(winner.opponent())),
+            (StatusKind::TimedOut, Some(winner)) => Ok(GameStatus::TimedOut(winner.opponent())),
+            (status, winner) => Err(GameError::DeserializationError(format!(
+                "a game with status {:?} can't have winner {:?}",
+                status, winner
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for GameStatus {
+    /// Describes the status in a few words, such as `X wins` or `draw`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameStatus::InProgress => write!(f, "in progress"),
+            GameStatus::Won(player) => write!(f, "{} wins", player),
+            GameStatus::Draw => write!(f, "draw"),
+            GameStatus::Resigned(player) => write!(f, "{} resigns", player),
+            GameStatus::TimedOut(player) => write!(f, "{} times out", player),
+        }
+    }
+}
+
+/// The default board size (classic 3x3 tic-tac-toe)
+pub const DEFAULT_SIZE: usize = 3;
+
+/// The default number of marks in a row needed to win
+pub const DEFAULT_WIN_LENGTH: usize = 3;
+
+/// The number of rows on a Connect Four board
+pub const CONNECT_FOUR_ROWS: usize = 6;
+
+/// The number of columns on a Connect Four board
+pub const CONNECT_FOUR_COLS: usize = 7;
+
+/// The number of marks in a row needed to win Connect Four
+pub const CONNECT_FOUR_WIN_LENGTH: usize = 4;
+
+/// The width and height of a Gomoku board
+pub const GOMOKU_SIZE: usize = 15;
+
+/// The number of marks in a row needed to win Gomoku
+pub const GOMOKU_WIN_LENGTH: usize = 5;
+
+/// The most rows or columns a board can have, which keeps games from
+/// clients and imported histories from allocating huge boards
+pub const MAX_BOARD_SIZE: usize = 25;
+
+fn default_win_length() -> usize {
+    DEFAULT_WIN_LENGTH
+}
+
+/// When a move was made and how long its player took over it
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MoveTiming {
+    /// The player who made the move
+    pub player: Player,
+    /// When the move was made
+    pub made_at: DateTime<Utc>,
+    /// How long the player took, from the start of their turn, in milliseconds
+    pub duration_ms: u64,
+}
+
+/// What [`GameState::unmake_move`] needs to take back a move
+#[cfg(feature = "ai")]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct UnmakeInfo {
+    /// Where the mark landed
+    row: usize,
+    col: usize,
+    /// The status before the move
+    status: GameStatus,
+    /// The player who made the move
+    current_turn: Player,
 }
 
 /// Represents the complete state of a tic-tac-toe game
@@ -30,60 +261,881 @@ pub enum GameStatus {
 pub struct GameState {
     /// Unique identifier for the game
     pub id: Uuid,
-    /// The 3x3 game board
-    pub board: [[Cell; 3]; 3],
+    /// The game board, indexed as `board[row][col]`
+    pub board: Vec<Vec<Cell>>,
+    /// The number of marks in a row needed to win
+    #[serde(default = "default_win_length")]
+    pub win_length: usize,
     /// The player whose turn it is
     pub current_turn: Player,
     /// The current status of the game
     pub status: GameStatus,
+    /// The cells forming the winning line, if the game has been won
+    #[serde(default)]
+    pub winning_line: Option<Vec<(usize, usize)>>,
+    /// The player who has offered a draw that hasn't been answered yet
+    #[serde(default)]
+    pub draw_offer: Option<Player>,
+    /// The time each player has to make a move, in seconds, if moves are timed
+    #[serde(default)]
+    pub move_time_limit: Option<u64>,
+    /// When the current player's turn started, which the move time limit is counted from
+    #[serde(default = "Utc::now")]
+    pub turn_started_at: DateTime<Utc>,
+    /// The number of moves on the board
+    #[serde(default)]
+    pub move_count: usize,
+    /// When the last move was made, if any have been
+    #[serde(default)]
+    pub last_move_at: Option<DateTime<Utc>>,
+    /// When each move on the board was made and how long it took, in order
+    ///
+    /// Games set up from a board with [`GameState::from_board`] have no timings
+    /// for the moves already on it.
+    #[serde(default)]
+    pub move_timings: Vec<MoveTiming>,
+    /// The variant of the game, whose rules decide when it is won or drawn
+    #[serde(default)]
+    pub variant: Variant,
+    /// Counts moves made and taken back, so clients can tell whether their copy is stale
+    #[serde(default)]
+    pub version: u64,
+    /// What has happened since the events were last taken, oldest first
+    #[serde(skip)]
+    events: Vec<GameEvent>,
+}
+
+/// Pseudo-random Zobrist key number `index`, derived with SplitMix64
+///
+/// Keys are computed rather than stored so boards of any size are supported.
+fn zobrist_key(index: usize) -> u64 {
+    let mut z = (index as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
 }
 
-impl GameState {
-    /// Creates a new game with an empty board and a new UUID
+/// The Zobrist key for `player`'s mark on the cell at `index` in row-major order
+fn mark_key(index: usize, player: Player) -> u64 {
+    zobrist_key(2 * index + 1 + (player == Player::O) as usize)
+}
+
+impl Default for GameState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GameState {
+    /// Creates a new game with an empty board and a new UUID
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::game::GameState;
+    /// use my_game_project::player::Player;
+    /// use my_game_project::game::GameStatus;
+    ///
+    /// let game = GameState::new();
+    /// assert_eq!(game.current_turn, Player::X);
+    /// assert_eq!(game.status, GameStatus::InProgress);
+    /// ```
+    pub fn new() -> Self {
+        Self::new_with_id(Uuid::new_v4())
+    }
+
+    /// Creates a new game with a specific UUID
+    ///
+    /// This is useful for reconstructing games from history
+    pub fn new_with_id(id: Uuid) -> Self {
+        Self {
+            id,
+            board: vec![vec![Cell::Empty; DEFAULT_SIZE]; DEFAULT_SIZE],
+            win_length: DEFAULT_WIN_LENGTH,
+            current_turn: Player::X,
+            status: GameStatus::InProgress,
+            winning_line: None,
+            draw_offer: None,
+            move_time_limit: None,
+            turn_started_at: Utc::now(),
+            move_count: 0,
+            last_move_at: None,
+            move_timings: Vec::new(),
+            variant: Variant::Standard,
+            version: 0,
+            events: Vec::new(),
+        }
+    }
+
+    /// Creates a new game on an `n`x`n` board where `k` marks in a row win
+    ///
+    /// # Arguments
+    ///
+    /// * `n` - The width and height of the board
+    /// * `k` - The number of marks in a row needed to win (at most `n`)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::game::GameState;
+    ///
+    /// let game = GameState::with_size(5, 4).unwrap();
+    /// assert_eq!(game.size(), 5);
+    /// assert_eq!(game.win_length, 4);
+    /// assert!(GameState::with_size(3, 4).is_err());
+    /// ```
+    pub fn with_size(n: usize, k: usize) -> GameResult<Self> {
+        Self::with_dimensions(n, n, k)
+    }
+
+    /// Creates a new game on a board `rows` high and `cols` wide where `k` marks in a row win
+    ///
+    /// Neither side may be longer than [`MAX_BOARD_SIZE`].
+    pub fn with_dimensions(rows: usize, cols: usize, k: usize) -> GameResult<Self> {
+        if rows == 0 || cols == 0 || k == 0 || k > rows.max(cols) || rows.max(cols) > MAX_BOARD_SIZE {
+            return Err(GameError::InvalidBoardSize(rows.max(cols), k));
+        }
+
+        let mut game = Self::new();
+        game.board = vec![vec![Cell::Empty; cols]; rows];
+        game.win_length = k;
+        Ok(game)
+    }
+
+    /// Creates a new game of Connect Four
+    ///
+    /// The board is 7 columns wide and 6 rows high, marks fall to the lowest
+    /// empty row of their column and four in a row wins.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::game::{Cell, GameState};
+    /// use my_game_project::player::Player;
+    ///
+    /// let mut game = GameState::connect_four();
+    /// game.make_move((0, 3)).unwrap();
+    /// assert_eq!(game.board[5][3], Cell::Occupied(Player::X));
+    /// assert_eq!(game.legal_moves().count(), 7);
+    /// ```
+    pub fn connect_four() -> Self {
+        let mut game = Self::new();
+        game.board = vec![vec![Cell::Empty; CONNECT_FOUR_COLS]; CONNECT_FOUR_ROWS];
+        game.win_length = CONNECT_FOUR_WIN_LENGTH;
+        game.variant = Variant::ConnectFour;
+        game
+    }
+
+    /// Creates a new game of Gomoku: five in a row wins on a 15x15 board
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::game::GameState;
+    ///
+    /// let game = GameState::gomoku();
+    /// assert_eq!(game.size(), 15);
+    /// assert_eq!(game.win_length, 5);
+    /// ```
+    pub fn gomoku() -> Self {
+        let mut game = Self::new();
+        game.board = vec![vec![Cell::Empty; GOMOKU_SIZE]; GOMOKU_SIZE];
+        game.win_length = GOMOKU_WIN_LENGTH;
+        game.variant = Variant::Gomoku;
+        game
+    }
+
+    /// Creates a standard game from an arbitrary board, deriving its status
+    ///
+    /// The win length is the length of the board's shorter side. The position
+    /// must be reachable by legal play: neither player can have more than one
+    /// mark more than the other, and at most one player can have completed a
+    /// line. `current_turn` is the player to move, and must be the player with
+    /// fewer marks when the counts differ; in a won game it becomes the winner,
+    /// who made the last move.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::game::{Cell, GameState, GameStatus};
+    /// use my_game_project::player::Player;
+    ///
+    /// let x = Cell::Occupied(Player::X);
+    /// let o = Cell::Occupied(Player::O);
+    /// let e = Cell::Empty;
+    ///
+    /// let board = vec![vec![x, x, x], vec![o, o, e], vec![e, e, e]];
+    /// let game = GameState::from_board(board, Player::O).unwrap();
+    /// assert_eq!(game.status, GameStatus::Won(Player::X));
+    ///
+    /// let board = vec![vec![x, x, e], vec![e, e, e], vec![e, e, e]];
+    /// assert!(GameState::from_board(board, Player::O).is_err());
+    /// ```
+    pub fn from_board(board: Vec<Vec<Cell>>, current_turn: Player) -> GameResult<Self> {
+        let rows = board.len();
+        let cols = board.first().map_or(0, Vec::len);
+        if board.iter().any(|row| row.len() != cols) {
+            return Err(GameError::UnreachablePosition("rows differ in length".to_string()));
+        }
+
+        let mut game = Self::with_dimensions(rows, cols, rows.min(cols))?;
+        game.board = board;
+        game.current_turn = current_turn;
+
+        // Players alternate, so their mark counts differ by at most one
+        let count = |player| {
+            game.board.iter().flatten().filter(|&&cell| cell == Cell::Occupied(player)).count()
+        };
+        let (x_count, o_count) = (count(Player::X), count(Player::O));
+        game.version = (x_count + o_count) as u64;
+        game.move_count = x_count + o_count;
+        if x_count.abs_diff(o_count) > 1 {
+            return Err(GameError::UnreachablePosition(format!(
+                "X has {} marks and O has {}",
+                x_count, o_count
+            )));
+        }
+        let fewer = match x_count.cmp(&o_count) {
+            Ordering::Less => Some(Player::X),
+            Ordering::Greater => Some(Player::O),
+            Ordering::Equal => None,
+        };
+
+        // The game ends as soon as a line is completed, so only one player can have one
+        let winners: Vec<_> = [Player::X, Player::O]
+            .into_iter()
+            .filter_map(|player| game.completed_line(player).map(|line| (player, line)))
+            .collect();
+
+        match winners.as_slice() {
+            [] => {
+                if fewer.is_some_and(|player| player != current_turn) {
+                    return Err(GameError::UnreachablePosition(format!(
+                        "{} can't move with more marks than {}",
+                        current_turn,
+                        current_turn.opponent()
+                    )));
+                }
+                if game.is_full() {
+                    game.status = GameStatus::Draw;
+                }
+            }
+            [(winner, line)] => {
+                if fewer == Some(*winner) {
+                    return Err(GameError::UnreachablePosition(format!(
+                        "{} won with fewer marks than {}",
+                        winner,
+                        winner.opponent()
+                    )));
+                }
+                game.status = GameStatus::Won(*winner);
+                game.winning_line = Some(line.clone());
+                game.current_turn = *winner;
+            }
+            _ => {
+                return Err(GameError::UnreachablePosition("both players completed a line".to_string()));
+            }
+        }
+
+        Ok(game)
+    }
+
+    /// Returns a line of at least `win_length` marks completed by `player`, if there is one
+    fn completed_line(&self, player: Player) -> Option<Vec<(usize, usize)>> {
+        (0..self.rows())
+            .flat_map(|row| (0..self.cols()).map(move |col| (row, col)))
+            .filter(|&(row, col)| self.board[row][col] == Cell::Occupied(player))
+            .flat_map(|(row, col)| {
+                LINE_DIRECTIONS
+                    .iter()
+                    .map(move |&(dr, dc)| self.line_through(row, col, dr, dc, player))
+            })
+            .find(|line| line.len() >= self.win_length)
+    }
+
+    /// Returns the height of the board, which is also its width on square boards
+    pub fn size(&self) -> usize {
+        self.rows()
+    }
+
+    /// Returns the number of rows on the board
+    pub fn rows(&self) -> usize {
+        self.board.len()
+    }
+
+    /// Returns the number of columns on the board
+    pub fn cols(&self) -> usize {
+        self.board.first().map_or(0, Vec::len)
+    }
+
+    /// Returns an iterator over every cell a mark can be placed in, in row-major order
+    ///
+    /// These are the empty cells, limited by the rules of the variant (with
+    /// gravity, only the lowest empty cell of each column). No moves are
+    /// returned once the game is over.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::game::GameState;
+    ///
+    /// let mut game = GameState::new();
+    /// game.make_move((0, 0)).unwrap();
+    /// assert_eq!(game.legal_moves().count(), 8);
+    /// assert_eq!(game.legal_moves().next(), Some((0, 1)));
+    /// ```
+    pub fn legal_moves(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let rows = if self.is_over() { 0 } else { self.rows() };
+        let cols = self.cols();
+
+        (0..rows)
+            .flat_map(move |row| (0..cols).map(move |col| (row, col)))
+            .filter(move |&(row, col)| self.is_legal_move(row, col))
+    }
+
+    /// Returns true if a mark can be played at (`row`, `col`) and stay there
+    ///
+    /// Doesn't check whether the game is over.
+    pub(crate) fn is_legal_move(&self, row: usize, col: usize) -> bool {
+        self.board[row][col] == Cell::Empty && self.rules().landing_cell(self, row, col) == (row, col)
+    }
+
+    /// Returns true if every cell on the board is occupied
+    pub fn is_full(&self) -> bool {
+        self.board.iter().all(|row| row.iter().all(|cell| *cell != Cell::Empty))
+    }
+
+    /// Returns the rules the game is played by
+    pub fn rules(&self) -> &'static dyn GameRules {
+        self.variant.rules()
+    }
+
+    /// Returns true if the game has been won or drawn
+    pub fn is_over(&self) -> bool {
+        self.status != GameStatus::InProgress
+    }
+
+    /// Returns a Zobrist hash of the position: the marks on the board and the player to move
+    ///
+    /// Positions reached through different move orders hash the same, which lets
+    /// the AI reuse work when moves transpose.
+    pub fn zobrist_hash(&self) -> u64 {
+        let cols = self.cols();
+        let mut hash = self.turn_key();
+
+        for (row, cells) in self.board.iter().enumerate() {
+            for (col, cell) in cells.iter().enumerate() {
+                if let Cell::Occupied(player) = cell {
+                    hash ^= mark_key(row * cols + col, *player);
+                }
+            }
+        }
+
+        hash
+    }
+
+    /// The part of the Zobrist hash for the player to move
+    fn turn_key(&self) -> u64 {
+        match self.current_turn {
+            Player::X => 0,
+            Player::O => zobrist_key(0),
+        }
+    }
+
+    /// Returns the rotations and reflections of the board that don't change how the game plays
+    ///
+    /// The identity comes first. Square boards have eight, other boards
+    /// four, and Connect Four only the identity and a left-right mirror.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::game::GameState;
+    ///
+    /// assert_eq!(GameState::new().symmetries().len(), 8);
+    /// assert_eq!(GameState::connect_four().symmetries().len(), 2);
+    /// ```
+    pub fn symmetries(&self) -> Vec<Symmetry> {
+        Symmetry::of_board(self.rows(), self.cols(), self.variant)
+    }
+
+    /// Returns the position with its board turned or mirrored by `symmetry`
+    ///
+    /// `symmetry` must be one of the board's [`symmetries`](Self::symmetries).
+    /// The winning line is moved with the marks, and everything else is kept.
+    pub fn transformed(&self, symmetry: Symmetry) -> GameState {
+        let (rows, cols) = (self.rows(), self.cols());
+        let mut image = self.clone();
+        for (row, cells) in self.board.iter().enumerate() {
+            for (col, &cell) in cells.iter().enumerate() {
+                let (image_row, image_col) = symmetry.apply((row, col), rows, cols);
+                image.board[image_row][image_col] = cell;
+            }
+        }
+        image.winning_line = self
+            .winning_line
+            .as_ref()
+            .map(|line| line.iter().map(|&cell| symmetry.apply(cell, rows, cols)).collect());
+        image
+    }
+
+    /// Returns the canonical form of the position, and the symmetry that turns the position into it
+    ///
+    /// Positions that are rotations or reflections of each other have the
+    /// same canonical form: the image with the lowest
+    /// [`canonical_hash`](Self::canonical_hash).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::game::GameState;
+    ///
+    /// let mut corner = GameState::new();
+    /// corner.make_move((0, 0)).unwrap();
+    /// let mut other_corner = GameState::new();
+    /// other_corner.make_move((2, 2)).unwrap();
+    ///
+    /// let (canonical, symmetry) = other_corner.canonical_form();
+    /// assert_eq!(canonical.board, corner.canonical_form().0.board);
+    /// assert_eq!(other_corner.transformed(symmetry).board, canonical.board);
+    /// ```
+    pub fn canonical_form(&self) -> (GameState, Symmetry) {
+        let (_, symmetry) = self.canonical_hash();
+        (self.transformed(symmetry), symmetry)
+    }
+
+    /// Returns the lowest Zobrist hash among the images of the position, and the symmetry giving it
+    ///
+    /// Unlike [`canonical_form`](Self::canonical_form), no board is copied, so
+    /// the AI's search keys its transposition table by this hash to share
+    /// work between symmetric positions. The identity wins ties, so a
+    /// symmetric position is its own canonical form.
+    pub fn canonical_hash(&self) -> (u64, Symmetry) {
+        let (rows, cols) = (self.rows(), self.cols());
+        let fits = Symmetry::ALL.map(|symmetry| symmetry.fits(rows, cols, self.variant));
+        let mut hashes = [self.turn_key(); Symmetry::ALL.len()];
+
+        for (row, cells) in self.board.iter().enumerate() {
+            for (col, cell) in cells.iter().enumerate() {
+                if let Cell::Occupied(player) = cell {
+                    for (index, symmetry) in Symmetry::ALL.into_iter().enumerate().filter(|&(index, _)| fits[index]) {
+                        let (image_row, image_col) = symmetry.apply((row, col), rows, cols);
+                        hashes[index] ^= mark_key(image_row * cols + image_col, *player);
+                    }
+                }
+            }
+        }
+
+        Symmetry::ALL
+            .into_iter()
+            .zip(hashes)
+            .zip(fits)
+            .filter(|&(_, fits)| fits)
+            .map(|((symmetry, hash), _)| (hash, symmetry))
+            .min_by_key(|&(hash, _)| hash)
+            .unwrap_or((hashes[0], Symmetry::Identity))
+    }
+
+    /// Makes a move at the specified position for the player whose turn it is
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The cell to play, as a [`Position`] or a `(row, col)` pair
+    ///
+    /// The rules of the variant may move the mark elsewhere, e.g. with gravity
+    /// it falls to the lowest empty row of the column. Use
+    /// [`make_move_as`](Self::make_move_as) when the move comes from a
+    /// particular player, so a move out of turn is refused.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the move was successful
+    /// * `Err(GameError)` if the move was invalid
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::game::GameState;
+    ///
+    /// let mut game = GameState::new();
+    /// assert!(game.make_move((0, 0)).is_ok());
+    /// assert!(game.make_move((0, 0)).is_err()); // Cell already occupied
+    /// ```
+    pub fn make_move(&mut self, position: impl Into<Position>) -> GameResult<()> {
+        self.make_move_as(self.current_turn, position)
+    }
+
+    /// Makes a move at the specified position on behalf of `player`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GameError::NotPlayerTurn`] if it isn't `player`'s turn, as
+    /// well as the errors of [`make_move`](Self::make_move).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::error::GameError;
+    /// use my_game_project::game::GameState;
+    /// use my_game_project::player::Player;
+    ///
+    /// let mut game = GameState::new();
+    /// assert!(matches!(game.make_move_as(Player::O, (0, 0)), Err(GameError::NotPlayerTurn)));
+    /// assert!(game.make_move_as(Player::X, (0, 0)).is_ok());
+    /// ```
+    pub fn make_move_as(&mut self, player: Player, position: impl Into<Position>) -> GameResult<()> {
+        self.make_move_at(player, position, Utc::now())
+    }
+
+    /// Makes a move at `position` on behalf of `player` as if it were made at `now`
+    ///
+    /// The move is timed from the start of the current player's turn. If the
+    /// player's time ran out before `now`, they forfeit the game instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chrono::{Duration, Utc};
+    /// use my_game_project::game::GameState;
+    /// use my_game_project::player::Player;
+    ///
+    /// let mut game = GameState::new();
+    /// let started = game.turn_started_at;
+    /// game.make_move_at(Player::X, (1, 1), started + Duration::seconds(3)).unwrap();
+    /// assert_eq!(game.move_count, 1);
+    /// assert_eq!(game.move_timings[0].duration_ms, 3000);
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        instrument(
+            level = "debug",
+            skip_all,
+            fields(game_id = %self.id, ?player, position = field::Empty),
+            err(level = "debug"),
+        )
+    )]
+    pub fn make_move_at(&mut self, player: Player, position: impl Into<Position>, now: DateTime<Utc>) -> GameResult<()> {
+        let Position { row, col } = position.into();
+        #[cfg(feature = "tracing")]
+        Span::current().record("position", field::display(Position { row, col }));
+
+        // Check if the game is already finished, or the player has run out of time
+        if self.is_over() || self.check_timeout(now) {
+            return Err(GameError::GameAlreadyFinished);
+        }
+
+        // Check it's the player's turn
+        if player != self.current_turn {
+            return Err(GameError::NotPlayerTurn);
+        }
+
+        // Check if the position is valid
+        if row >= self.rows() || col >= self.cols() {
+            return Err(GameError::InvalidPosition(row, col));
+        }
+        let (row, col) = self.rules().landing_cell(self, row, col);
+
+        // Check if the cell is empty
+        match self.board[row][col] {
+            Cell::Empty => {
+                // Make the move, which lets any pending draw offer lapse
+                self.board[row][col] = Cell::Occupied(self.current_turn);
+                self.draw_offer = None;
+                self.version += 1;
+
+                // Time the move, and restart the clock for the next player
+                let duration = (now - self.turn_started_at).num_milliseconds().max(0) as u64;
+                self.move_timings.push(MoveTiming {
+                    player: self.current_turn,
+                    made_at: now,
+                    duration_ms: duration,
+                });
+                self.move_count += 1;
+                self.last_move_at = Some(now);
+                self.turn_started_at = now;
+
+                // Check for win or draw
+                self.update_game_status(row, col);
+
+                // Switch turns if the game is still in progress
+                self.events.push(GameEvent::MoveMade { player, position: Position { row, col } });
+                if self.status == GameStatus::InProgress {
+                    self.current_turn = self.current_turn.opponent();
+                    self.events.push(GameEvent::TurnChanged { player: self.current_turn });
+                } else {
+                    self.events.push(GameEvent::GameFinished { status: self.status });
+                }
+
+                Ok(())
+            }
+            Cell::Occupied(_) => Err(GameError::CellOccupied(row, col)),
+        }
+    }
+
+    /// Makes a move without checking it, for searching ahead
+    ///
+    /// The move must be legal. Only the board, status and turn change: clocks,
+    /// draw offers and the winning line are left alone, and nothing is
+    /// allocated. Take the move back with [`unmake_move`](Self::unmake_move).
+    #[cfg(feature = "ai")]
+    pub(crate) fn make_move_unchecked(&mut self, row: usize, col: usize) -> UnmakeInfo {
+        let (row, col) = self.rules().landing_cell(self, row, col);
+        let undo = UnmakeInfo {
+            row,
+            col,
+            status: self.status,
+            current_turn: self.current_turn,
+        };
+
+        self.board[row][col] = Cell::Occupied(self.current_turn);
+        self.status = self.rules().status(self, row, col);
+        if self.status == GameStatus::InProgress {
+            self.current_turn = self.current_turn.opponent();
+        }
+
+        undo
+    }
+
+    /// Takes back a move made with [`make_move_unchecked`](Self::make_move_unchecked)
+    #[cfg(feature = "ai")]
+    pub(crate) fn unmake_move(&mut self, undo: UnmakeInfo) {
+        self.board[undo.row][undo.col] = Cell::Empty;
+        self.status = undo.status;
+        self.current_turn = undo.current_turn;
+    }
+
+    /// Gives each player `seconds` to make every move, starting the clock now
+    pub fn set_move_time_limit(&mut self, seconds: u64) {
+        self.move_time_limit = Some(seconds);
+        self.turn_started_at = Utc::now();
+    }
+
+    /// Returns the time by which the current player must move, if moves are timed
+    pub fn turn_deadline(&self) -> Option<DateTime<Utc>> {
+        let limit = self.move_time_limit?;
+        Some(self.turn_started_at + Duration::seconds(limit as i64))
+    }
+
+    /// Forfeits the game for the current player if their time ran out before `now`
+    ///
+    /// Returns true if the game was forfeited.
+    pub fn check_timeout(&mut self, now: DateTime<Utc>) -> bool {
+        match self.turn_deadline() {
+            Some(deadline) if !self.is_over() && now > deadline => {
+                self.finish(GameStatus::TimedOut(self.current_turn));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Resigns the game on behalf of `player`, conceding it to their opponent
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::game::{GameState, GameStatus};
+    /// use my_game_project::player::Player;
+    ///
+    /// let mut game = GameState::new();
+    /// game.resign(Player::O).unwrap();
+    /// assert_eq!(game.status, GameStatus::Resigned(Player::O));
+    /// assert_eq!(game.status.winner(), Some(Player::X));
+    /// ```
+    pub fn resign(&mut self, player: Player) -> GameResult<()> {
+        if self.is_over() {
+            return Err(GameError::GameAlreadyFinished);
+        }
+
+        self.finish(GameStatus::Resigned(player));
+        Ok(())
+    }
+
+    /// Offers a draw on behalf of `player`
+    ///
+    /// The offer stands until the opponent accepts or declines it, or until
+    /// the next move is made.
+    pub fn offer_draw(&mut self, player: Player) -> GameResult<()> {
+        if self.is_over() {
+            return Err(GameError::GameAlreadyFinished);
+        }
+
+        self.draw_offer = Some(player);
+        Ok(())
+    }
+
+    /// Accepts the opponent's draw offer on behalf of `player`, ending the game in a draw
+    pub fn accept_draw(&mut self, player: Player) -> GameResult<()> {
+        if self.is_over() {
+            return Err(GameError::GameAlreadyFinished);
+        }
+        if self.draw_offer != Some(player.opponent()) {
+            return Err(GameError::NoDrawOffer);
+        }
+
+        self.finish(GameStatus::Draw);
+        Ok(())
+    }
+
+    /// Ends the game with `status`, letting any draw offer lapse
+    pub(crate) fn finish(&mut self, status: GameStatus) {
+        self.status = status;
+        self.draw_offer = None;
+        self.events.push(GameEvent::GameFinished { status });
+    }
+
+    /// Returns what has happened since the events were last taken, oldest first
+    pub fn events(&self) -> &[GameEvent] {
+        &self.events
+    }
+
+    /// Takes the events of everything that has happened since they were last taken
+    ///
+    /// See [`events`](crate::events) for an example.
+    pub fn take_events(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Declines the opponent's draw offer on behalf of `player`
+    pub fn decline_draw(&mut self, player: Player) -> GameResult<()> {
+        if self.draw_offer != Some(player.opponent()) {
+            return Err(GameError::NoDrawOffer);
+        }
+
+        self.draw_offer = None;
+        Ok(())
+    }
+
+    /// Takes back the last `plies` moves recorded in `history`, this game's history
+    ///
+    /// The moves are removed from the history too, and any draw offer lapses.
+    /// Finished games can't be taken back.
     ///
     /// # Examples
     ///
     /// ```
     /// use my_game_project::game::GameState;
     /// use my_game_project::player::Player;
-    /// use my_game_project::game::GameStatus;
     ///
-    /// let game = GameState::new();
-    /// assert_eq!(game.current_turn, Player::X);
-    /// assert_eq!(game.status, GameStatus::InProgress);
+    /// let mut game = GameState::new();
+    /// let mut history = game.create_history();
+    /// for position in [(1, 1), (0, 0)] {
+    ///     let player = game.current_turn;
+    ///     game.make_move(position).unwrap();
+    ///     history.add_move(player, position);
+    /// }
+    ///
+    /// game.undo(&mut history, 1).unwrap();
+    /// assert_eq!(game.current_turn, Player::O);
+    /// assert_eq!(history.moves.len(), 1);
+    /// assert!(game.undo(&mut history, 2).is_err());
     /// ```
-    pub fn new() -> Self {
-        Self {
-            id: Uuid::new_v4(),
-            board: [[Cell::Empty; 3]; 3],
-            current_turn: Player::X,
-            status: GameStatus::InProgress,
+    pub fn undo(&mut self, history: &mut GameHistory, plies: usize) -> GameResult<()> {
+        if self.is_over() {
+            return Err(GameError::GameAlreadyFinished);
+        }
+        if plies == 0 || plies > history.moves.len() {
+            return Err(GameError::NothingToUndo);
         }
-    }
 
-    /// Creates a new game with a specific UUID
-    ///
-    /// This is useful for reconstructing games from history
-    pub fn new_with_id(id: Uuid) -> Self {
-        Self {
-            id,
-            board: [[Cell::Empty; 3]; 3],
-            current_turn: Player::X,
-            status: GameStatus::InProgress,
+        let remaining = history.moves.len() - plies;
+        let previous = history.reconstruct_game_at(remaining)?;
+        history.moves.truncate(remaining);
+
+        if previous.current_turn != self.current_turn {
+            self.events.push(GameEvent::TurnChanged { player: previous.current_turn });
         }
+        self.board = previous.board;
+        self.current_turn = previous.current_turn;
+        self.status = previous.status;
+        self.winning_line = previous.winning_line;
+        self.move_count = previous.move_count;
+        self.last_move_at = previous.last_move_at;
+        self.move_timings = previous.move_timings;
+        self.draw_offer = None;
+        self.version += 1;
+
+        // Restart the clock for the player who moves again
+        self.turn_started_at = Utc::now();
+        Ok(())
     }
 
-    /// Makes a move at the specified position
+    /// Updates the game status after a move
+    ///
+    /// This method asks the game's rules whether the last move resulted in a
+    /// win or a draw and updates the game status accordingly.
     ///
     /// # Arguments
     ///
-    /// * `row` - The row index (0-2)
-    /// * `col` - The column index (0-2)
+    /// * `last_row` - The row of the last move
+    /// * `last_col` - The column of the last move
+    fn update_game_status(&mut self, last_row: usize, last_col: usize) {
+        let outcome = self.rules().outcome(self, last_row, last_col);
+        self.status = outcome.status;
+        self.winning_line = outcome.winning_line;
+    }
+
+    /// Collects the unbroken run of `player` marks through a cell along a direction
     ///
-    /// # Returns
+    /// The run is followed both backwards and forwards from the cell, so the
+    /// result includes the cell itself and is ordered along the direction.
+    pub fn line_through(
+        &self,
+        row: usize,
+        col: usize,
+        dr: isize,
+        dc: isize,
+        player: Player,
+    ) -> Vec<(usize, usize)> {
+        // Walk backwards to the start of the run
+        let (mut r, mut c) = (row as isize, col as isize);
+        while self.is_player_at(r - dr, c - dc, player) {
+            r -= dr;
+            c -= dc;
+        }
+
+        // Walk forwards collecting every cell in the run
+        let mut line = Vec::new();
+        while self.is_player_at(r, c, player) {
+            line.push((r as usize, c as usize));
+            r += dr;
+            c += dc;
+        }
+        line
+    }
+
+    /// Counts the unbroken run of `player` marks through a cell along a direction
     ///
-    /// * `Ok(())` if the move was successful
-    /// * `Err(GameError)` if the move was invalid
+    /// Like [`line_through`](Self::line_through), but without collecting the cells.
+    pub fn run_length(&self, row: usize, col: usize, dr: isize, dc: isize, player: Player) -> usize {
+        let count = |dr: isize, dc: isize| {
+            (1..)
+                .take_while(|&i| self.is_player_at(row as isize + dr * i, col as isize + dc * i, player))
+                .count()
+        };
+
+        usize::from(self.is_player_at(row as isize, col as isize, player)) + count(dr, dc) + count(-dr, -dc)
+    }
+
+    /// Returns true if the (possibly out-of-bounds) cell is occupied by `player`
+    fn is_player_at(&self, row: isize, col: isize, player: Player) -> bool {
+        if row < 0 || col < 0 {
+            return false;
+        }
+        matches!(
+            self.board.get(row as usize).and_then(|r| r.get(col as usize)),
+            Some(Cell::Occupied(p)) if *p == player
+        )
+    }
+
+    /// Draws the board as text in `style`
+    ///
+    /// See [`BoardRenderer`] for the styles.
+    pub fn render(&self, style: &BoardRenderer) -> String {
+        style.render(self)
+    }
+
+    /// Draws the board as ASCII art, one line per row between separators
     ///
     /// # Examples
     ///
@@ -91,130 +1143,223 @@ impl GameState {
     /// use my_game_project::game::GameState;
     ///
     /// let mut game = GameState::new();
-    /// assert!(game.make_move(0, 0).is_ok());
-    /// assert!(game.make_move(0, 0).is_err()); // Cell already occupied
+    /// game.make_move((0, 0)).unwrap();
+    /// assert!(game.render_board().starts_with("-------------\n| X |   |   |\n"));
     /// ```
-    pub fn make_move(&mut self, row: usize, col: usize) -> GameResult<()> {
-        // Check if the game is already finished
-        if self.status != GameStatus::InProgress {
-            return Err(GameError::GameAlreadyFinished);
-        }
+    pub fn render_board(&self) -> String {
+        self.render(&BoardRenderer::default())
+    }
 
-        // Check if the position is valid
-        if row >= 3 || col >= 3 {
-            return Err(GameError::InvalidPosition(row, col));
+    /// Creates a new game history for this game
+    pub fn create_history(&self) -> GameHistory {
+        GameHistory::for_game(self)
+    }
+
+    /// Saves the game state to a file, in JSON unless the file name calls for
+    /// another [`SaveFormat`](crate::save::SaveFormat)
+    #[cfg(feature = "files")]
+    pub fn save_to_file(&self, filename: &str) -> GameResult<()> {
+        save::save_to_file(self, filename)
+    }
+
+    /// Loads a game state from a file saved in any format, or from a bundle
+    #[cfg(feature = "files")]
+    pub fn load_from_file(filename: &str) -> GameResult<Self> {
+        if SaveFormat::is_bundle(filename) {
+            return save::load_from_file::<SaveBundle>(filename).map(|bundle| bundle.game);
         }
+        save::load_from_file(filename)
+    }
+}
 
-        // Check if the cell is empty
-        match self.board[row][col] {
-            Cell::Empty => {
-                // Make the move
-                self.board[row][col] = Cell::Occupied(self.current_turn);
+impl fmt::Display for GameState {
+    /// Draws the board the same way as [`GameState::render_board`]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render_board())
+    }
+}
 
-                // Check for win or draw
-                self.update_game_status(row, col);
+impl Saved for GameState {
+    const MIGRATIONS: &'static [Migration] = &[save::unversioned, save::lowercase_enums];
+}
 
-                // Switch turns if the game is still in progress
-                if self.status == GameStatus::InProgress {
-                    self.current_turn = self.current_turn.opponent();
-                }
+/// A game together with the history of how it was played
+///
+/// Every action taken through a `Game` is recorded in its history, with the
+/// player who took it, and the history is finished when the game ends. The
+/// state can be read through `Deref`, but only changed through `Game`'s own
+/// methods so the two can't fall out of step.
+///
+/// # Examples
+///
+/// ```
+/// use my_game_project::game::{Game, GameState};
+/// use my_game_project::player::Player;
+///
+/// let mut game = Game::new(GameState::new()).unwrap();
+/// game.make_move((1, 1)).unwrap();
+/// game.resign(Player::O).unwrap();
+///
+/// assert_eq!(game.history().moves[0].player, Player::X);
+/// assert_eq!(game.history().final_status, Some(game.status));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Game {
+    state: GameState,
+    history: GameHistory,
+}
 
-                Ok(())
-            }
-            Cell::Occupied(_) => Err(GameError::CellOccupied(row, col)),
+impl Game {
+    /// Starts recording a game that no marks have been placed in yet
+    ///
+    /// A history only records the moves made from an empty board, so a game
+    /// already under way is refused; carry on recording one with
+    /// [`Game::with_history`] instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::game::{Game, GameState};
+    ///
+    /// let mut state = GameState::new();
+    /// assert!(Game::new(state.clone()).is_ok());
+    ///
+    /// state.make_move((1, 1)).unwrap();
+    /// assert!(Game::new(state).is_err());
+    /// ```
+    pub fn new(state: GameState) -> GameResult<Self> {
+        if state.board.iter().flatten().any(|&cell| cell != Cell::Empty) {
+            return Err(GameError::InvalidImport("the game has marks on the board but no history".to_string()));
         }
+        let history = state.create_history();
+        Ok(Self { state, history })
     }
 
-    /// Updates the game status after a move
+    /// Carries on recording a game that was played as `history` describes
     ///
-    /// This method checks if the last move resulted in a win or a draw
-    /// and updates the game status accordingly.
+    /// The history must be legal and lead to the game's board.
+    pub fn with_history(state: GameState, history: GameHistory) -> GameResult<Self> {
+        if history.game_id != state.id || history.validate()?.board != state.board {
+            return Err(GameError::InvalidImport("the history doesn't lead to the game's board".to_string()));
+        }
+        Ok(Self { state, history })
+    }
+
+    /// Saves the game, its history and its players together in one bundle
     ///
-    /// # Arguments
+    /// The bundle is checksummed, so a damaged file is caught when it loads.
     ///
-    /// * `last_row` - The row of the last move
-    /// * `last_col` - The column of the last move
-    fn update_game_status(&mut self, last_row: usize, last_col: usize) {
-        // Get the player who just made a move
-        let player = self.current_turn;
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use my_game_project::game::{Game, GameState};
+    ///
+    /// let mut game = Game::new(GameState::new()).unwrap();
+    /// game.make_move((1, 1)).unwrap();
+    /// game.save_bundle("game.ttt", &[]).unwrap();
+    ///
+    /// let (loaded, _players) = Game::load_bundle("game.ttt").unwrap();
+    /// assert_eq!(loaded.history().moves.len(), 1);
+    /// ```
+    #[cfg(feature = "files")]
+    pub fn save_bundle(&self, filename: &str, players: &[PlayerInfo]) -> GameResult<()> {
+        let bundle = SaveBundle {
+            game: self.state.clone(),
+            history: self.history.clone(),
+            players: players.to_vec(),
+        };
+        save::save_to_file_with_footer(&bundle, filename, Footer::Checksum)
+    }
 
-        // Check row
-        if (0..3).all(|col| matches!(self.board[last_row][col], Cell::Occupied(p) if p == player)) {
-            self.status = GameStatus::Won(player);
-            return;
-        }
+    /// Loads a game saved with [`Game::save_bundle`], along with its players
+    #[cfg(feature = "files")]
+    pub fn load_bundle(filename: &str) -> GameResult<(Self, Vec<PlayerInfo>)> {
+        let bundle: SaveBundle = save::load_from_file(filename)?;
+        Ok((Self::with_history(bundle.game, bundle.history)?, bundle.players))
+    }
 
-        // Check column
-        if (0..3).all(|row| matches!(self.board[row][last_col], Cell::Occupied(p) if p == player)) {
-            self.status = GameStatus::Won(player);
-            return;
-        }
+    /// Returns the current state of the game
+    pub fn state(&self) -> &GameState {
+        &self.state
+    }
 
-        // Check diagonal (top-left to bottom-right)
-        if last_row == last_col &&
-           (0..3).all(|i| matches!(self.board[i][i], Cell::Occupied(p) if p == player)) {
-            self.status = GameStatus::Won(player);
-            return;
-        }
+    /// Returns the moves made so far
+    pub fn history(&self) -> &GameHistory {
+        &self.history
+    }
 
-        // Check diagonal (top-right to bottom-left)
-        if last_row + last_col == 2 &&
-           (0..3).all(|i| matches!(self.board[i][2-i], Cell::Occupied(p) if p == player)) {
-            self.status = GameStatus::Won(player);
-            return;
-        }
+    /// Makes a move for the current player and records it
+    pub fn make_move(&mut self, position: impl Into<Position>) -> GameResult<()> {
+        self.make_move_as(self.state.current_turn, position)
+    }
 
-        // Check for draw (all cells filled)
-        if self.board.iter().all(|row| row.iter().all(|cell| !matches!(cell, Cell::Empty))) {
-            self.status = GameStatus::Draw;
-            return;
-        }
+    /// Makes a move on behalf of `player` and records it, refusing it if it isn't their turn
+    pub fn make_move_as(&mut self, player: Player, position: impl Into<Position>) -> GameResult<()> {
+        self.record(|state| state.make_move_as(player, position))
     }
 
-    /// Prints the current board state to the console
-    pub fn print_board(&self) {
-        println!("Current board:");
-        println!("-------------");
+    /// Resigns the game on behalf of `player`
+    pub fn resign(&mut self, player: Player) -> GameResult<()> {
+        self.record(|state| state.resign(player))
+    }
 
-        for row in &self.board {
-            print!("|");
-            for cell in row {
-                match cell {
-                    Cell::Empty => print!("   |"),
-                    Cell::Occupied(Player::X) => print!(" X |"),
-                    Cell::Occupied(Player::O) => print!(" O |"),
-                }
-            }
-            println!("");
-            println!("-------------");
-        }
+    /// Offers a draw on behalf of `player`
+    pub fn offer_draw(&mut self, player: Player) -> GameResult<()> {
+        self.state.offer_draw(player)
     }
 
-    /// Creates a new game history for this game
-    pub fn create_history(&self) -> GameHistory {
-        GameHistory::new(self.id)
+    /// Accepts the pending draw offer on behalf of `player`
+    pub fn accept_draw(&mut self, player: Player) -> GameResult<()> {
+        self.record(|state| state.accept_draw(player))
     }
 
-    /// Saves the game state to a file in JSON format
-    pub fn save_to_file(&self, filename: &str) -> GameResult<()> {
-        let json = serde_json::to_string_pretty(self)
-            .map_err(|e| GameError::SerializationError(e.to_string()))?;
+    /// Declines the pending draw offer on behalf of `player`
+    pub fn decline_draw(&mut self, player: Player) -> GameResult<()> {
+        self.state.decline_draw(player)
+    }
 
-        std::fs::write(filename, json)
-            .map_err(|e| GameError::IoError(e.to_string()))?;
+    /// Takes back the last `plies` moves
+    pub fn undo(&mut self, plies: usize) -> GameResult<()> {
+        self.state.undo(&mut self.history, plies)
+    }
 
-        Ok(())
+    /// Forfeits the game if the current player has run out of time at `now`
+    pub fn check_timeout(&mut self, now: DateTime<Utc>) -> bool {
+        self.record(|state| Ok(state.check_timeout(now))).unwrap_or(false)
     }
 
-    /// Loads a game state from a file
-    pub fn load_from_file(filename: &str) -> GameResult<Self> {
-        let json = std::fs::read_to_string(filename)
-            .map_err(|e| GameError::IoError(e.to_string()))?;
+    /// Takes the events of everything that has happened since they were last taken
+    pub fn take_events(&mut self) -> Vec<GameEvent> {
+        self.state.take_events()
+    }
+
+    /// Runs `action` on the state and records what happened in the history
+    ///
+    /// Failed actions are recorded too, since a move made too late still
+    /// forfeits the game. The events are left for [`take_events`](Self::take_events).
+    fn record<T>(&mut self, action: impl FnOnce(&mut GameState) -> GameResult<T>) -> GameResult<T> {
+        let seen = self.state.events.len();
+        let result = action(&mut self.state);
+        for event in &self.state.events[seen..] {
+            self.history.record(event);
+        }
+        result
+    }
+}
 
-        let game = serde_json::from_str(&json)
-            .map_err(|e| GameError::DeserializationError(e.to_string()))?;
+impl TryFrom<GameState> for Game {
+    type Error = GameError;
 
-        Ok(game)
+    fn try_from(state: GameState) -> GameResult<Self> {
+        Self::new(state)
+    }
+}
+
+impl Deref for Game {
+    type Target = GameState;
+
+    fn deref(&self) -> &GameState {
+        &self.state
     }
 }
 
@@ -241,54 +1386,56 @@ mod tests {
         let mut game = GameState::new();
 
         // Make a valid move
-        assert!(game.make_move(0, 0).is_ok());
+        assert!(game.make_move((0, 0)).is_ok());
         assert_eq!(game.board[0][0], Cell::Occupied(Player::X));
         assert_eq!(game.current_turn, Player::O); // Turn should switch
 
         // Try to make a move on an occupied cell
-        assert!(game.make_move(0, 0).is_err());
+        assert!(game.make_move((0, 0)).is_err());
 
         // Try to make a move out of bounds
-        assert!(game.make_move(3, 0).is_err());
+        assert!(game.make_move((3, 0)).is_err());
     }
 
     #[test]
     fn test_win_conditions() {
         // Test row win
         let mut game = GameState::new();
-        game.make_move(0, 0).unwrap(); // X at (0,0)
-        game.make_move(1, 0).unwrap(); // O at (1,0)
-        game.make_move(0, 1).unwrap(); // X at (0,1)
-        game.make_move(1, 1).unwrap(); // O at (1,1)
-        game.make_move(0, 2).unwrap(); // X at (0,2)
+        game.make_move((0, 0)).unwrap(); // X at (0,0)
+        game.make_move((1, 0)).unwrap(); // O at (1,0)
+        game.make_move((0, 1)).unwrap(); // X at (0,1)
+        game.make_move((1, 1)).unwrap(); // O at (1,1)
+        game.make_move((0, 2)).unwrap(); // X at (0,2)
         assert_eq!(game.status, GameStatus::Won(Player::X));
+        assert_eq!(game.winning_line, Some(vec![(0, 0), (0, 1), (0, 2)]));
 
         // Test column win
         let mut game = GameState::new();
-        game.make_move(0, 0).unwrap(); // X at (0,0)
-        game.make_move(0, 1).unwrap(); // O at (0,1)
-        game.make_move(1, 0).unwrap(); // X at (1,0)
-        game.make_move(1, 1).unwrap(); // O at (1,1)
-        game.make_move(2, 0).unwrap(); // X at (2,0)
+        game.make_move((0, 0)).unwrap(); // X at (0,0)
+        game.make_move((0, 1)).unwrap(); // O at (0,1)
+        game.make_move((1, 0)).unwrap(); // X at (1,0)
+        game.make_move((1, 1)).unwrap(); // O at (1,1)
+        game.make_move((2, 0)).unwrap(); // X at (2,0)
         assert_eq!(game.status, GameStatus::Won(Player::X));
 
         // Test diagonal win (top-left to bottom-right)
         let mut game = GameState::new();
-        game.make_move(0, 0).unwrap(); // X at (0,0)
-        game.make_move(0, 1).unwrap(); // O at (0,1)
-        game.make_move(1, 1).unwrap(); // X at (1,1)
-        game.make_move(0, 2).unwrap(); // O at (0,2)
-        game.make_move(2, 2).unwrap(); // X at (2,2)
+        game.make_move((0, 0)).unwrap(); // X at (0,0)
+        game.make_move((0, 1)).unwrap(); // O at (0,1)
+        game.make_move((1, 1)).unwrap(); // X at (1,1)
+        game.make_move((0, 2)).unwrap(); // O at (0,2)
+        game.make_move((2, 2)).unwrap(); // X at (2,2)
         assert_eq!(game.status, GameStatus::Won(Player::X));
 
         // Test diagonal win (top-right to bottom-left)
         let mut game = GameState::new();
-        game.make_move(0, 2).unwrap(); // X at (0,2)
-        game.make_move(0, 0).unwrap(); // O at (0,0)
-        game.make_move(1, 1).unwrap(); // X at (1,1)
-        game.make_move(0, 1).unwrap(); // O at (0,1)
-        game.make_move(2, 0).unwrap(); // X at (2,0)
+        game.make_move((0, 2)).unwrap(); // X at (0,2)
+        game.make_move((0, 0)).unwrap(); // O at (0,0)
+        game.make_move((1, 1)).unwrap(); // X at (1,1)
+        game.make_move((0, 1)).unwrap(); // O at (0,1)
+        game.make_move((2, 0)).unwrap(); // X at (2,0)
         assert_eq!(game.status, GameStatus::Won(Player::X));
+        assert_eq!(game.winning_line, Some(vec![(0, 2), (1, 1), (2, 0)]));
     }
 
     #[test]
@@ -298,36 +1445,412 @@ mod tests {
         // X | O | X
         // O | O | X
         // X | X | O
-        game.make_move(0, 0).unwrap(); // X at (0,0)
-        game.make_move(0, 1).unwrap(); // O at (0,1)
-        game.make_move(0, 2).unwrap(); // X at (0,2)
-        game.make_move(1, 0).unwrap(); // O at (1,0)
-        game.make_move(1, 2).unwrap(); // X at (1,2)
-        game.make_move(1, 1).unwrap(); // O at (1,1)
-        game.make_move(2, 0).unwrap(); // X at (2,0)
-        game.make_move(2, 2).unwrap(); // O at (2,2)
-        game.make_move(2, 1).unwrap(); // X at (2,1)
+        game.make_move((0, 0)).unwrap(); // X at (0,0)
+        game.make_move((0, 1)).unwrap(); // O at (0,1)
+        game.make_move((0, 2)).unwrap(); // X at (0,2)
+        game.make_move((1, 0)).unwrap(); // O at (1,0)
+        game.make_move((1, 2)).unwrap(); // X at (1,2)
+        game.make_move((1, 1)).unwrap(); // O at (1,1)
+        game.make_move((2, 0)).unwrap(); // X at (2,0)
+        game.make_move((2, 2)).unwrap(); // O at (2,2)
+        game.make_move((2, 1)).unwrap(); // X at (2,1)
         assert_eq!(game.status, GameStatus::Draw);
+        assert_eq!(game.winning_line, None);
     }
 
     #[test]
     fn test_game_already_finished() {
         let mut game = GameState::new();
         // Create a winning condition
-        game.make_move(0, 0).unwrap();
-        game.make_move(1, 0).unwrap();
-        game.make_move(0, 1).unwrap();
-        game.make_move(1, 1).unwrap();
-        game.make_move(0, 2).unwrap();
+        game.make_move((0, 0)).unwrap();
+        game.make_move((1, 0)).unwrap();
+        game.make_move((0, 1)).unwrap();
+        game.make_move((1, 1)).unwrap();
+        game.make_move((0, 2)).unwrap();
         // Game should be won by X now
         assert_eq!(game.status, GameStatus::Won(Player::X));
 
         // Try to make another move
-        let result = game.make_move(2, 2);
+        let result = game.make_move((2, 2));
         assert!(result.is_err());
         match result {
             Err(GameError::GameAlreadyFinished) => {}, // Expected
             _ => panic!("Expected GameAlreadyFinished error"),
         }
     }
+
+    #[test]
+    fn test_with_size() {
+        let game = GameState::with_size(4, 3).unwrap();
+        assert_eq!(game.size(), 4);
+        assert_eq!(game.win_length, 3);
+        assert!(game.board.iter().all(|row| row.len() == 4));
+
+        assert!(matches!(GameState::with_size(0, 0), Err(GameError::InvalidBoardSize(0, 0))));
+        assert!(matches!(GameState::with_size(3, 4), Err(GameError::InvalidBoardSize(3, 4))));
+
+        // Boards are capped, however they are asked for
+        assert!(GameState::with_size(MAX_BOARD_SIZE, 5).is_ok());
+        let huge = 1_000_000_000;
+        assert!(matches!(GameState::with_size(huge, 5), Err(GameError::InvalidBoardSize(size, 5)) if size == huge));
+        assert!(GameState::with_dimensions(3, MAX_BOARD_SIZE + 1, 3).is_err());
+    }
+
+    #[test]
+    fn test_larger_board_win_length() {
+        // 5x5 board with four in a row needed
+        let mut game = GameState::with_size(5, 4).unwrap();
+        game.make_move((0, 1)).unwrap(); // X
+        game.make_move((4, 4)).unwrap(); // O
+        game.make_move((1, 2)).unwrap(); // X
+        game.make_move((4, 3)).unwrap(); // O
+        game.make_move((2, 3)).unwrap(); // X - three on the diagonal is not enough
+        assert_eq!(game.status, GameStatus::InProgress);
+        game.make_move((0, 0)).unwrap(); // O
+        game.make_move((3, 4)).unwrap(); // X
+        assert_eq!(game.status, GameStatus::Won(Player::X));
+        assert_eq!(game.winning_line, Some(vec![(0, 1), (1, 2), (2, 3), (3, 4)]));
+
+        // Moves outside the larger board are still rejected
+        let mut game = GameState::with_size(4, 4).unwrap();
+        assert!(game.make_move((3, 3)).is_ok());
+        assert!(matches!(game.make_move((4, 0)), Err(GameError::InvalidPosition(4, 0))));
+    }
+
+    #[test]
+    fn test_serialization_round_trip() {
+        let mut game = GameState::with_size(4, 3).unwrap();
+        game.make_move((3, 2)).unwrap();
+
+        let json = serde_json::to_string(&game).unwrap();
+        let loaded: GameState = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.size(), 4);
+        assert_eq!(loaded.win_length, 3);
+        assert_eq!(loaded.board[3][2], Cell::Occupied(Player::X));
+    }
+
+    #[test]
+    fn test_legal_moves() {
+        let mut game = GameState::new();
+        assert_eq!(game.legal_moves().count(), 9);
+        assert!(!game.is_full());
+        assert!(!game.is_over());
+
+        game.make_move((1, 1)).unwrap();
+        assert!(!game.legal_moves().any(|position| position == (1, 1)));
+        assert_eq!(game.legal_moves().count(), 8);
+
+        // A finished game has no legal moves even with empty cells left
+        game.make_move((0, 0)).unwrap(); // O
+        game.make_move((0, 1)).unwrap(); // X
+        game.make_move((0, 2)).unwrap(); // O
+        game.make_move((2, 1)).unwrap(); // X wins down the middle column
+        assert!(game.is_over());
+        assert!(!game.is_full());
+        assert_eq!(game.legal_moves().count(), 0);
+    }
+
+    #[test]
+    fn test_resign() {
+        let mut game = GameState::new();
+        game.make_move((1, 1)).unwrap();
+        game.resign(Player::O).unwrap();
+
+        assert_eq!(game.status, GameStatus::Resigned(Player::O));
+        assert_eq!(game.status.winner(), Some(Player::X));
+        assert!(game.is_over());
+        assert!(matches!(game.make_move((0, 0)), Err(GameError::GameAlreadyFinished)));
+        assert!(matches!(game.resign(Player::X), Err(GameError::GameAlreadyFinished)));
+    }
+
+    #[test]
+    fn test_draw_offer() {
+        let mut game = GameState::new();
+
+        // A player can't accept their own offer
+        game.offer_draw(Player::X).unwrap();
+        assert!(matches!(game.accept_draw(Player::X), Err(GameError::NoDrawOffer)));
+
+        // Declining clears the offer
+        game.decline_draw(Player::O).unwrap();
+        assert_eq!(game.draw_offer, None);
+        assert!(matches!(game.accept_draw(Player::O), Err(GameError::NoDrawOffer)));
+
+        // Making a move lets the offer lapse
+        game.offer_draw(Player::X).unwrap();
+        game.make_move((0, 0)).unwrap();
+        assert!(matches!(game.accept_draw(Player::O), Err(GameError::NoDrawOffer)));
+
+        // Accepting ends the game in a draw
+        game.offer_draw(Player::O).unwrap();
+        game.accept_draw(Player::X).unwrap();
+        assert_eq!(game.status, GameStatus::Draw);
+        assert_eq!(game.status.winner(), None);
+    }
+
+    #[test]
+    fn test_move_time_limit() {
+        let mut game = GameState::new();
+        assert_eq!(game.turn_deadline(), None);
+        assert!(!game.check_timeout(Utc::now() + Duration::days(1)));
+
+        game.set_move_time_limit(30);
+        let deadline = game.turn_deadline().unwrap();
+        assert!(!game.check_timeout(deadline));
+
+        // Moving restarts the clock for the opponent
+        game.make_move((0, 0)).unwrap();
+        assert!(game.turn_deadline().unwrap() >= deadline);
+
+        let late = game.turn_deadline().unwrap() + Duration::seconds(1);
+        assert!(game.check_timeout(late));
+        assert_eq!(game.status, GameStatus::TimedOut(Player::O));
+        assert_eq!(game.status.winner(), Some(Player::X));
+        assert!(!game.check_timeout(late));
+    }
+
+    #[test]
+    fn test_move_timings() {
+        let mut game = GameState::new();
+        let start = game.turn_started_at;
+        game.make_move_at(Player::X, (0, 0), start + Duration::seconds(3)).unwrap();
+        game.make_move_at(Player::O, (1, 1), start + Duration::seconds(4)).unwrap();
+        assert_eq!(game.move_count, 2);
+        assert_eq!(game.last_move_at, Some(start + Duration::seconds(4)));
+        let durations: Vec<_> = game.move_timings.iter().map(|timing| timing.duration_ms).collect();
+        assert_eq!(durations, [3000, 1000]);
+
+        // A move made after the deadline forfeits the game instead
+        game.set_move_time_limit(30);
+        let late = start + Duration::seconds(40);
+        assert!(game.make_move_at(Player::X, (2, 2), late).is_err());
+        assert_eq!(game.status, GameStatus::TimedOut(Player::X));
+        assert_eq!(game.move_count, 2);
+    }
+
+    #[test]
+    fn test_connect_four_gravity() {
+        let mut game = GameState::connect_four();
+        assert_eq!((game.rows(), game.cols()), (6, 7));
+
+        // Marks stack up from the bottom of the column, wherever they are played
+        game.make_move((0, 0)).unwrap(); // X
+        game.make_move((5, 0)).unwrap(); // O
+        assert_eq!(game.board[5][0], Cell::Occupied(Player::X));
+        assert_eq!(game.board[4][0], Cell::Occupied(Player::O));
+        assert!(game.legal_moves().any(|cell| cell == (3, 0)));
+        assert!(!game.legal_moves().any(|cell| cell == (2, 0)));
+
+        // Four in a row along the bottom wins
+        for col in 1..4 {
+            game.make_move((0, col)).unwrap(); // X
+            if col < 3 {
+                game.make_move((0, col)).unwrap(); // O stacks on top
+            }
+        }
+        assert_eq!(game.status, GameStatus::Won(Player::X));
+        assert_eq!(game.winning_line, Some(vec![(5, 0), (5, 1), (5, 2), (5, 3)]));
+
+        // A full column can't take another mark
+        let mut game = GameState::connect_four();
+        for _ in 0..6 {
+            game.make_move((0, 6)).unwrap();
+        }
+        assert!(matches!(game.make_move((0, 6)), Err(GameError::CellOccupied(0, 6))));
+    }
+
+    #[test]
+    fn test_moves_out_of_turn() {
+        let mut game = Game::new(GameState::new()).unwrap();
+        game.make_move_as(Player::X, (1, 1)).unwrap();
+
+        // A refused move leaves the board and the history as they were
+        assert!(matches!(game.make_move_as(Player::X, (0, 0)), Err(GameError::NotPlayerTurn)));
+        assert_eq!(game.state().move_count, 1);
+        assert_eq!(game.history().moves.len(), 1);
+
+        game.make_move_as(Player::O, (0, 0)).unwrap();
+        assert_eq!(game.history().moves[1].player, Player::O);
+    }
+
+    #[test]
+    fn test_events() {
+        let mut game = Game::new(GameState::connect_four()).unwrap();
+        game.make_move_as(Player::X, (0, 3)).unwrap();
+        assert_eq!(
+            game.take_events(),
+            [
+                GameEvent::MoveMade { player: Player::X, position: Position::new(5, 3) },
+                GameEvent::TurnChanged { player: Player::O },
+            ]
+        );
+        assert_eq!(game.history().moves[0].position, Position::new(5, 3));
+
+        // A move made too late is refused, but the forfeit still reaches the history
+        let mut state = GameState::new();
+        state.set_move_time_limit(30);
+        state.turn_started_at -= Duration::minutes(1);
+        let mut game = Game::new(state).unwrap();
+        assert!(game.make_move_as(Player::X, (0, 0)).is_err());
+        let finished = GameEvent::GameFinished { status: GameStatus::TimedOut(Player::X) };
+        assert_eq!(game.take_events(), [finished]);
+        assert_eq!(game.history().final_status, Some(GameStatus::TimedOut(Player::X)));
+    }
+
+    #[test]
+    fn test_serialized_enums() {
+        let mut game = GameState::new();
+        game.make_move((0, 0)).unwrap();
+        game.status = GameStatus::TimedOut(Player::O);
+
+        let json = serde_json::to_value(&game).unwrap();
+        assert_eq!(json["board"][0], serde_json::json!(["x", null, null]));
+        assert_eq!(json["current_turn"], "o");
+        assert_eq!(json["status"], serde_json::json!({"status": "timed_out", "winner": "x"}));
+        assert_eq!(serde_json::to_value(GameStatus::Draw).unwrap(), serde_json::json!({"status": "draw"}));
+
+        let loaded: GameState = serde_json::from_value(json).unwrap();
+        assert_eq!((loaded.board, loaded.status), (game.board, game.status));
+
+        // A status can't have a winner that doesn't fit it
+        let draw_won_by_x = serde_json::json!({"status": "draw", "winner": "x"});
+        assert!(serde_json::from_value::<GameStatus>(draw_won_by_x).is_err());
+    }
+
+    #[test]
+    fn test_display() {
+        let mut game = GameState::new();
+        game.make_move((0, 0)).unwrap();
+
+        assert_eq!(game.to_string(), game.render_board());
+        assert_eq!(game.board[0][0].to_string(), "X");
+        assert_eq!(game.board[0][1].to_string(), ".");
+        assert_eq!(game.status.to_string(), "in progress");
+        assert_eq!(GameStatus::Won(Player::O).to_string(), "O wins");
+        assert_eq!(GameStatus::TimedOut(Player::X).to_string(), "X times out");
+    }
+
+    #[cfg(feature = "ai")]
+    #[test]
+    fn test_make_and_unmake_move() {
+        let mut game = GameState::new();
+        for cell in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            game.make_move(cell).unwrap();
+        }
+        let before = game.zobrist_hash();
+
+        // Winning unchecked sets the status, and unmaking restores everything
+        let undo = game.make_move_unchecked(0, 2);
+        assert_eq!(game.status, GameStatus::Won(Player::X));
+        assert_eq!(game.current_turn, Player::X);
+        game.unmake_move(undo);
+        assert_eq!(game.status, GameStatus::InProgress);
+        assert_eq!(game.board[0][2], Cell::Empty);
+        assert_eq!(game.zobrist_hash(), before);
+
+        // Gravity still applies
+        let mut game = GameState::connect_four();
+        let undo = game.make_move_unchecked(0, 3);
+        assert_eq!(game.board[5][3], Cell::Occupied(Player::X));
+        assert_eq!(game.current_turn, Player::O);
+        game.unmake_move(undo);
+        assert_eq!(game.board[5][3], Cell::Empty);
+    }
+
+    #[test]
+    fn test_zobrist_hash_transpositions() {
+        let mut a = GameState::new();
+        a.make_move((0, 0)).unwrap(); // X
+        a.make_move((1, 1)).unwrap(); // O
+        a.make_move((2, 2)).unwrap(); // X
+
+        let mut b = GameState::new();
+        b.make_move((2, 2)).unwrap(); // X
+        b.make_move((1, 1)).unwrap(); // O
+        b.make_move((0, 0)).unwrap(); // X
+
+        // The same position reached in a different order hashes the same
+        assert_eq!(a.zobrist_hash(), b.zobrist_hash());
+
+        // The side to move and the marks themselves are part of the hash
+        let mut swapped = a.clone();
+        swapped.current_turn = Player::X;
+        assert_ne!(a.zobrist_hash(), swapped.zobrist_hash());
+        assert_ne!(a.zobrist_hash(), GameState::new().zobrist_hash());
+    }
+
+    #[test]
+    fn test_canonical_form() {
+        let mut game = GameState::new();
+        for cell in [(0, 1), (1, 1), (0, 2)] {
+            game.make_move(cell).unwrap();
+        }
+
+        // Every rotation and reflection has the same canonical form
+        let (canonical, _) = game.canonical_form();
+        for symmetry in game.symmetries() {
+            let image = game.transformed(symmetry);
+            assert_eq!(image.canonical_hash().0, game.canonical_hash().0);
+            assert_eq!(image.canonical_form().0.board, canonical.board);
+        }
+        assert_eq!(game.transformed(Symmetry::Rotate90).board[1][2], Cell::Occupied(Player::X));
+
+        // A different position doesn't
+        let mut other = GameState::new();
+        for cell in [(0, 0), (1, 1), (0, 2)] {
+            other.make_move(cell).unwrap();
+        }
+        assert_ne!(other.canonical_hash().0, game.canonical_hash().0);
+
+        // A symmetric position is already canonical
+        let mut centre = GameState::new();
+        centre.make_move((1, 1)).unwrap();
+        assert_eq!(centre.canonical_form().1, Symmetry::Identity);
+
+        // Connect Four boards can only be mirrored left to right
+        let mut four = GameState::connect_four();
+        four.make_move((5, 0)).unwrap();
+        let (mirrored, symmetry) = four.canonical_form();
+        assert!(matches!(symmetry, Symmetry::Identity | Symmetry::FlipHorizontal));
+        assert_eq!(mirrored.board[5].iter().filter(|cell| **cell != Cell::Empty).count(), 1);
+    }
+
+    #[test]
+    fn test_from_board() {
+        let (x, o, e) = (Cell::Occupied(Player::X), Cell::Occupied(Player::O), Cell::Empty);
+
+        // An unfinished position keeps the player to move
+        let game = GameState::from_board(vec![vec![x, e, e], vec![e, o, e], vec![e, e, x]], Player::O).unwrap();
+        assert_eq!(game.status, GameStatus::InProgress);
+        assert_eq!(game.current_turn, Player::O);
+        assert_eq!(game.legal_moves().count(), 6);
+
+        // O started, so X is to move with equal counts too
+        assert!(GameState::from_board(vec![vec![x, e, e], vec![e, o, e], vec![e, e, o]], Player::X).is_ok());
+
+        // A won position becomes finished, with the winner as the last player to move
+        let game = GameState::from_board(vec![vec![o, x, x], vec![x, o, e], vec![e, e, o]], Player::X).unwrap();
+        assert_eq!(game.status, GameStatus::Won(Player::O));
+        assert_eq!(game.winning_line, Some(vec![(0, 0), (1, 1), (2, 2)]));
+        assert_eq!(game.current_turn, Player::O);
+
+        // A full board without a line is a draw
+        let game = GameState::from_board(vec![vec![x, o, x], vec![x, o, o], vec![o, x, x]], Player::O).unwrap();
+        assert_eq!(game.status, GameStatus::Draw);
+
+        // Unreachable positions are rejected
+        let unreachable = [
+            (vec![vec![x, x, e], vec![e, e, e], vec![e, e, e]], Player::O),
+            (vec![vec![x, e, e], vec![e, e, e], vec![e, e, e]], Player::X),
+            (vec![vec![x, x, x], vec![o, o, o], vec![e, e, e]], Player::X),
+            (vec![vec![x, x, x], vec![o, o, e], vec![o, e, o]], Player::X),
+            (vec![vec![x, e], vec![e]], Player::O),
+        ];
+        for (board, current_turn) in unreachable {
+            assert!(matches!(
+                GameState::from_board(board, current_turn),
+                Err(GameError::UnreachablePosition(_))
+            ));
+        }
+    }
 }
@@ -1,36 +1,125 @@
+use std::borrow::Cow;
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use crate::error::{GameError, GameResult};
-use crate::game::{GameState, GameStatus};
+use crate::events::GameEvent;
+use crate::game::{GameState, GameStatus, DEFAULT_SIZE, DEFAULT_WIN_LENGTH};
 use crate::player::Player;
+use crate::position::Position;
+use crate::rules::Variant;
+#[cfg(feature = "files")]
+use crate::save::{SaveBundle, SaveFormat};
+use crate::save::{self, Migration, Saved};
+
+/// A judgement of a move, written after it like in chess annotation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MoveTag {
+    /// A brilliant move (`!!`)
+    Brilliant,
+    /// A good move (`!`)
+    Good,
+    /// An interesting move (`!?`)
+    Interesting,
+    /// A dubious move (`?!`)
+    Dubious,
+    /// A mistake (`?`)
+    Mistake,
+    /// A blunder (`??`)
+    Blunder,
+}
+
+impl MoveTag {
+    /// Returns the symbol written after a move with this tag
+    pub fn symbol(self) -> &'static str {
+        match self {
+            MoveTag::Brilliant => "!!",
+            MoveTag::Good => "!",
+            MoveTag::Interesting => "!?",
+            MoveTag::Dubious => "?!",
+            MoveTag::Mistake => "?",
+            MoveTag::Blunder => "??",
+        }
+    }
+}
 
 /// Represents a single move in the game
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct GameMove {
     /// The player who made the move
     pub player: Player,
-    /// The row where the move was made (0-2)
-    pub row: usize,
-    /// The column where the move was made (0-2)
-    pub col: usize,
+    /// The cell where the move was made
+    #[serde(flatten)]
+    pub position: Position,
     /// The timestamp when the move was made
     pub timestamp: DateTime<Utc>,
+    /// Comments added while reviewing the game, in the order they were added
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub comments: Vec<String>,
+    /// A judgement of the move, if one has been given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<MoveTag>,
 }
 
 impl GameMove {
     /// Creates a new game move
-    pub fn new(player: Player, row: usize, col: usize) -> Self {
+    pub fn new(player: Player, position: impl Into<Position>) -> Self {
         Self {
             player,
-            row,
-            col,
+            position: position.into(),
             timestamp: Utc::now(),
+            comments: Vec::new(),
+            tag: None,
         }
     }
 }
 
+/// A message one player sent the other during a game
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    /// The player who sent the message
+    pub player: Player,
+    /// What the player said
+    pub text: String,
+    /// The timestamp when the message was sent
+    pub sent_at: DateTime<Utc>,
+}
+
+impl ChatMessage {
+    /// Creates a message sent by `player` just now
+    pub fn new(player: Player, text: impl Into<String>) -> Self {
+        Self {
+            player,
+            text: text.into(),
+            sent_at: Utc::now(),
+        }
+    }
+}
+
+/// A line of play in a history: the moves actually played, or a variation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Line {
+    /// The moves actually played in the game
+    Main,
+    /// The variation with this index in [`GameHistory::variations`]
+    Variation(usize),
+}
+
+/// An alternative line of play branching off from another line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Variation {
+    /// The line this variation branches off from
+    pub parent: Line,
+    /// Number of moves of the parent line played before the variation starts
+    pub at_ply: usize,
+    /// The moves played in the variation instead of the parent's
+    pub moves: Vec<GameMove>,
+}
+
 /// Represents the complete history of a game
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameHistory {
@@ -44,6 +133,39 @@ pub struct GameHistory {
     pub ended_at: Option<DateTime<Utc>>,
     /// The final status of the game (if it has ended)
     pub final_status: Option<GameStatus>,
+    /// The height of the board, which is also its width unless `board_cols` is set
+    #[serde(default = "default_board_size")]
+    pub board_size: usize,
+    /// The width of the board, if it isn't square
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub board_cols: Option<usize>,
+    /// The number of marks in a row needed to win
+    #[serde(default = "default_win_length")]
+    pub win_length: usize,
+    /// The player who made the first move
+    #[serde(default = "default_starting_player")]
+    pub starting_player: Player,
+    /// The variant of the game being played
+    #[serde(default)]
+    pub variant: Variant,
+    /// Alternative lines explored alongside the game, indexed by [`Line::Variation`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub variations: Vec<Variation>,
+    /// Messages the players sent each other, in the order they were sent
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chat: Vec<ChatMessage>,
+}
+
+fn default_board_size() -> usize {
+    DEFAULT_SIZE
+}
+
+fn default_win_length() -> usize {
+    DEFAULT_WIN_LENGTH
+}
+
+fn default_starting_player() -> Player {
+    Player::X
 }
 
 impl GameHistory {
@@ -55,57 +177,495 @@ impl GameHistory {
             started_at: Utc::now(),
             ended_at: None,
             final_status: None,
+            board_size: DEFAULT_SIZE,
+            board_cols: None,
+            win_length: DEFAULT_WIN_LENGTH,
+            starting_player: Player::X,
+            variant: Variant::Standard,
+            variations: Vec::new(),
+            chat: Vec::new(),
+        }
+    }
+
+    /// Creates an empty history for a game that starts from `game`'s board settings, variant and turn
+    pub fn for_game(game: &GameState) -> Self {
+        Self {
+            board_size: game.rows(),
+            board_cols: (game.cols() != game.rows()).then_some(game.cols()),
+            win_length: game.win_length,
+            starting_player: game.current_turn,
+            variant: game.variant,
+            ..Self::new(game.id)
         }
     }
 
     /// Adds a move to the history
-    pub fn add_move(&mut self, player: Player, row: usize, col: usize) {
-        let game_move = GameMove::new(player, row, col);
+    pub fn add_move(&mut self, player: Player, position: impl Into<Position>) {
+        let game_move = GameMove::new(player, position);
         self.moves.push(game_move);
     }
 
+    /// Adds a comment to the move at `ply`, counting from 0
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::game::GameState;
+    /// use my_game_project::history::MoveTag;
+    /// use my_game_project::player::Player;
+    ///
+    /// let mut history = GameState::new().create_history();
+    /// history.add_move(Player::X, (0, 0));
+    /// history.annotate(0, "a corner is as good as the centre").unwrap();
+    /// history.tag(0, MoveTag::Good).unwrap();
+    ///
+    /// assert_eq!(history.moves[0].comments, ["a corner is as good as the centre"]);
+    /// assert!(history.annotate(1, "no such move").is_err());
+    /// ```
+    pub fn annotate(&mut self, ply: usize, comment: impl Into<String>) -> GameResult<()> {
+        let game_move = self.moves.get_mut(ply).ok_or(GameError::InvalidMoveIndex(ply))?;
+        game_move.comments.push(comment.into());
+        Ok(())
+    }
+
+    /// Tags the move at `ply`, counting from 0, replacing any tag it already has
+    pub fn tag(&mut self, ply: usize, tag: MoveTag) -> GameResult<()> {
+        let game_move = self.moves.get_mut(ply).ok_or(GameError::InvalidMoveIndex(ply))?;
+        game_move.tag = Some(tag);
+        Ok(())
+    }
+
     /// Marks the game as finished
     pub fn finish(&mut self, status: GameStatus) {
         self.ended_at = Some(Utc::now());
         self.final_status = Some(status);
     }
 
-    /// Saves the game history to a file
+    /// Records an event from the game: moves are added and the end of the game marks it finished
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::game::GameState;
+    /// use my_game_project::player::Player;
+    ///
+    /// let mut game = GameState::new();
+    /// let mut history = game.create_history();
+    /// game.make_move((1, 1)).unwrap();
+    /// game.resign(Player::O).unwrap();
+    ///
+    /// game.take_events().iter().for_each(|event| history.record(event));
+    /// assert_eq!(history.moves.len(), 1);
+    /// assert_eq!(history.final_status, Some(game.status));
+    /// ```
+    pub fn record(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::MoveMade { player, position } => self.add_move(player, position),
+            GameEvent::GameFinished { status } => self.finish(status),
+            GameEvent::TurnChanged { .. } => {}
+        }
+    }
+
+    /// Saves the game history to a file, in JSON unless the file name calls
+    /// for another [`SaveFormat`](crate::save::SaveFormat)
+    #[cfg(feature = "files")]
     pub fn save_to_file(&self, filename: &str) -> GameResult<()> {
-        let json = serde_json::to_string_pretty(self)
-            .map_err(|e| GameError::SerializationError(e.to_string()))?;
-        
-        std::fs::write(filename, json)
-            .map_err(|e| GameError::IoError(e.to_string()))?;
-        
-        Ok(())
+        save::save_to_file(self, filename)
     }
 
-    /// Loads a game history from a file
+    /// Loads a game history from a file saved in any format, or from a bundle
+    #[cfg(feature = "files")]
     pub fn load_from_file(filename: &str) -> GameResult<Self> {
-        let json = std::fs::read_to_string(filename)
-            .map_err(|e| GameError::IoError(e.to_string()))?;
-        
-        let history = serde_json::from_str(&json)
-            .map_err(|e| GameError::DeserializationError(e.to_string()))?;
-        
-        Ok(history)
+        if SaveFormat::is_bundle(filename) {
+            return save::load_from_file::<SaveBundle>(filename).map(|bundle| bundle.history);
+        }
+        save::load_from_file(filename)
+    }
+
+    /// Returns the position before any moves were made
+    pub fn initial_state(&self) -> GameResult<GameState> {
+        let cols = self.board_cols.unwrap_or(self.board_size);
+        let mut game = GameState::with_dimensions(self.board_size, cols, self.win_length)?;
+        game.id = self.game_id;
+        game.current_turn = self.starting_player;
+        game.variant = self.variant;
+        game.turn_started_at = self.started_at;
+        Ok(game)
+    }
+
+    /// Returns an iterator over every position in the game
+    ///
+    /// The first item is the position before any moves were made, followed
+    /// by the position after each move. Iteration stops after the first
+    /// move that can't be applied, which is yielded as an error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::game::GameState;
+    /// use my_game_project::player::Player;
+    ///
+    /// let game = GameState::new();
+    /// let mut history = game.create_history();
+    /// history.add_move(Player::X, (1, 1));
+    /// history.add_move(Player::O, (0, 0));
+    ///
+    /// let positions: Vec<GameState> = history.replay().collect::<Result<_, _>>().unwrap();
+    /// assert_eq!(positions.len(), 3);
+    /// assert_eq!(positions[2].current_turn, Player::X);
+    /// ```
+    pub fn replay(&self) -> Replay<'_> {
+        self.replay_moves(Cow::Borrowed(&self.moves))
+    }
+
+    /// Returns an iterator over every position along a line, from the start of the game
+    pub fn replay_line(&self, line: Line) -> GameResult<Replay<'_>> {
+        Ok(self.replay_moves(self.line_moves(line)?))
+    }
+
+    /// Returns a replay of `moves` played from the initial position
+    fn replay_moves<'a>(&'a self, moves: Cow<'a, [GameMove]>) -> Replay<'a> {
+        Replay {
+            history: self,
+            moves,
+            game: None,
+            next_move: 0,
+            done: false,
+        }
+    }
+
+    /// Returns every move along a line, from the start of the game
+    ///
+    /// A variation's moves follow the moves of its parent line up to where it branches off.
+    pub fn line_moves(&self, line: Line) -> GameResult<Cow<'_, [GameMove]>> {
+        let index = match line {
+            Line::Main => return Ok(Cow::Borrowed(&self.moves)),
+            Line::Variation(index) => index,
+        };
+        let variation = self.variations.get(index).ok_or(GameError::VariationNotFound(index))?;
+
+        let mut moves = self.line_moves(variation.parent)?.into_owned();
+        moves.truncate(variation.at_ply);
+        moves.extend(variation.moves.iter().cloned());
+        Ok(Cow::Owned(moves))
+    }
+
+    /// Adds a variation branching off the game after its first `at_ply` moves
+    ///
+    /// Each move is made by whichever player's turn it is. Returns the index
+    /// of the new variation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::game::GameState;
+    /// use my_game_project::history::Line;
+    /// use my_game_project::player::Player;
+    ///
+    /// let mut history = GameState::new().create_history();
+    /// history.add_move(Player::X, (1, 1));
+    /// history.add_move(Player::O, (0, 0));
+    ///
+    /// // What if O had answered in the corner opposite instead?
+    /// let variation = history.add_variation(1, &[(2, 2), (0, 0)]).unwrap();
+    /// assert_eq!(history.branches_at(Line::Main, 1), vec![variation]);
+    /// assert_eq!(history.line_moves(Line::Variation(variation)).unwrap().len(), 3);
+    /// ```
+    pub fn add_variation(&mut self, at_ply: usize, moves: &[(usize, usize)]) -> GameResult<usize> {
+        self.add_variation_to(Line::Main, at_ply, moves)
+    }
+
+    /// Adds a variation branching off `parent` after its first `at_ply` moves
+    ///
+    /// Every move must be legal. Returns the index of the new variation.
+    pub fn add_variation_to(
+        &mut self,
+        parent: Line,
+        at_ply: usize,
+        moves: &[(usize, usize)],
+    ) -> GameResult<usize> {
+        let mut game = self.reconstruct_line_at(parent, at_ply)?;
+
+        let mut variation_moves = Vec::with_capacity(moves.len());
+        for &(row, col) in moves {
+            let player = game.current_turn;
+            game.make_move((row, col))?;
+            variation_moves.push(GameMove::new(player, (row, col)));
+        }
+
+        self.variations.push(Variation {
+            parent,
+            at_ply,
+            moves: variation_moves,
+        });
+        Ok(self.variations.len() - 1)
+    }
+
+    /// Returns the indexes of the variations that branch off `line` after `at_ply` moves
+    pub fn branches_at(&self, line: Line, at_ply: usize) -> Vec<usize> {
+        self.variations
+            .iter()
+            .enumerate()
+            .filter(|(_, variation)| variation.parent == line && variation.at_ply == at_ply)
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Reconstructs the game state after the first `move_index` moves along a line
+    pub fn reconstruct_line_at(&self, line: Line, move_index: usize) -> GameResult<GameState> {
+        let moves = self.line_moves(line)?;
+        if move_index > moves.len() {
+            return Err(GameError::InvalidMoveIndex(move_index));
+        }
+
+        let mut positions = self.replay_moves(moves).take(move_index + 1);
+        let mut game = positions.next().unwrap_or(Err(GameError::InvalidMoveIndex(move_index)))?;
+        for position in positions {
+            game = position?;
+        }
+        Ok(game)
+    }
+
+    /// Reconstructs the game state after the first `move_index` moves
+    ///
+    /// A `move_index` of 0 gives the starting position and `moves.len()` the
+    /// final position.
+    pub fn reconstruct_game_at(&self, move_index: usize) -> GameResult<GameState> {
+        self.reconstruct_line_at(Line::Main, move_index)
     }
 
     /// Reconstructs a game state from the history
     pub fn reconstruct_game(&self) -> GameResult<GameState> {
-        let mut game = GameState::new_with_id(self.game_id);
-        
-        for game_move in &self.moves {
-            // Verify that it's the correct player's turn
-            if game.current_turn != game_move.player {
-                return Err(GameError::NotPlayerTurn);
+        self.reconstruct_game_at(self.moves.len())
+    }
+
+    /// Checks that every move in the history is legal, returning the final position
+    ///
+    /// The first illegal move is reported as [`GameError::IllegalHistoryMove`]
+    /// with its ply and the reason it can't be played: it's the other
+    /// player's turn, the cell is taken or off the board, or the game is over.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::error::GameError;
+    /// use my_game_project::game::GameState;
+    /// use my_game_project::player::Player;
+    ///
+    /// let mut history = GameState::new().create_history();
+    /// history.add_move(Player::X, (1, 1));
+    /// history.add_move(Player::O, (1, 1));
+    ///
+    /// match history.validate() {
+    ///     Err(GameError::IllegalHistoryMove(ply, reason)) => {
+    ///         assert_eq!(ply, 1);
+    ///         assert!(matches!(*reason, GameError::CellOccupied(1, 1)));
+    ///     }
+    ///     other => panic!("expected an illegal move, got {:?}", other),
+    /// }
+    /// ```
+    pub fn validate(&self) -> GameResult<GameState> {
+        let mut last = None;
+        // The first position is the one before any moves, so each later one follows a move
+        for (index, position) in self.replay().enumerate() {
+            match position {
+                Ok(game) => last = Some(game),
+                Err(e) if index == 0 => return Err(e),
+                Err(e) => return Err(GameError::IllegalHistoryMove(index - 1, Box::new(e))),
             }
-            
-            // Apply the move
-            game.make_move(game_move.row, game_move.col)?;
         }
-        
-        Ok(game)
+        last.ok_or(GameError::InvalidMoveIndex(0))
+    }
+}
+
+impl Saved for GameHistory {
+    const MIGRATIONS: &'static [Migration] = &[save::unversioned, save::lowercase_enums];
+}
+
+/// An iterator over the positions of a game, created by [`GameHistory::replay`]
+#[derive(Debug, Clone)]
+pub struct Replay<'a> {
+    /// The history being replayed
+    history: &'a GameHistory,
+    /// The moves being replayed
+    moves: Cow<'a, [GameMove]>,
+    /// The position reached so far, once the replay has started
+    game: Option<GameState>,
+    /// The index of the next move to apply
+    next_move: usize,
+    /// Whether the replay has finished or failed
+    done: bool,
+}
+
+impl Iterator for Replay<'_> {
+    type Item = GameResult<GameState>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let game = match self.game.as_mut() {
+            // Start from the initial position
+            None => match self.history.initial_state() {
+                Ok(game) => self.game.insert(game),
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            },
+            Some(game) => {
+                let game_move = match self.moves.get(self.next_move) {
+                    Some(game_move) => game_move,
+                    None => {
+                        self.done = true;
+                        return None;
+                    }
+                };
+                self.next_move += 1;
+
+                // Apply the move, which checks that the game goes on and it's the player's turn
+                if let Err(e) = game.make_move_at(game_move.player, game_move.position, game_move.timestamp) {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+                game
+            }
+        };
+
+        Some(Ok(game.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_history() -> GameHistory {
+        let mut game = GameState::with_size(4, 3).unwrap();
+        game.current_turn = Player::O;
+
+        let mut history = game.create_history();
+        history.add_move(Player::O, (0, 0));
+        history.add_move(Player::X, (3, 3));
+        history.add_move(Player::O, (0, 1));
+        history
+    }
+
+    #[test]
+    fn test_replay() {
+        let history = sample_history();
+        let positions: Vec<GameState> = history.replay().collect::<GameResult<_>>().unwrap();
+
+        assert_eq!(positions.len(), 4);
+        assert_eq!(positions[0].size(), 4);
+        assert_eq!(positions[0].legal_moves().count(), 16);
+        assert_eq!(positions[0].current_turn, Player::O);
+        assert_eq!(positions[3].legal_moves().count(), 13);
+        assert!(positions.iter().all(|game| game.id == history.game_id));
+    }
+
+    #[test]
+    fn test_reconstruct_game_at() {
+        let history = sample_history();
+
+        let game = history.reconstruct_game_at(2).unwrap();
+        assert_eq!(game.legal_moves().count(), 14);
+        assert_eq!(game.current_turn, Player::O);
+
+        assert_eq!(history.reconstruct_game().unwrap().current_turn, Player::X);
+        assert!(matches!(history.reconstruct_game_at(4), Err(GameError::InvalidMoveIndex(4))));
+    }
+
+    #[test]
+    fn test_replay_stops_at_illegal_move() {
+        let mut history = sample_history();
+        history.add_move(Player::X, (0, 0));
+        history.add_move(Player::O, (2, 2));
+
+        let results: Vec<GameResult<GameState>> = history.replay().collect();
+        assert_eq!(results.len(), 5);
+        assert!(matches!(results[4], Err(GameError::CellOccupied(0, 0))));
+        assert!(matches!(history.reconstruct_game(), Err(GameError::CellOccupied(0, 0))));
+    }
+
+    #[test]
+    fn test_validate() {
+        let history = sample_history();
+        assert_eq!(history.validate().unwrap().legal_moves().count(), 13);
+
+        let mut wrong_turn = sample_history();
+        wrong_turn.add_move(Player::O, (2, 2));
+        assert!(matches!(
+            wrong_turn.validate(),
+            Err(GameError::IllegalHistoryMove(3, reason)) if matches!(*reason, GameError::NotPlayerTurn)
+        ));
+
+        let mut off_board = sample_history();
+        off_board.add_move(Player::X, (4, 0));
+        assert!(matches!(
+            off_board.validate(),
+            Err(GameError::IllegalHistoryMove(3, reason)) if matches!(*reason, GameError::InvalidPosition(4, 0))
+        ));
+
+        // O has three in a row after its fifth move, so nothing can follow it
+        let mut after_end = sample_history();
+        after_end.add_move(Player::X, (3, 2));
+        after_end.add_move(Player::O, (0, 2));
+        after_end.add_move(Player::X, (3, 1));
+        assert!(matches!(
+            after_end.validate(),
+            Err(GameError::IllegalHistoryMove(5, reason)) if matches!(*reason, GameError::GameAlreadyFinished)
+        ));
+    }
+
+    #[test]
+    fn test_variations() {
+        let mut history = sample_history();
+
+        // Branch off the game after O's first move, then off that variation
+        let first = history.add_variation(1, &[(1, 1), (0, 1)]).unwrap();
+        let nested = history.add_variation_to(Line::Variation(first), 2, &[(2, 2)]).unwrap();
+
+        assert_eq!(history.branches_at(Line::Main, 1), vec![first]);
+        assert_eq!(history.branches_at(Line::Variation(first), 2), vec![nested]);
+        assert!(history.branches_at(Line::Main, 2).is_empty());
+
+        let moves = history.line_moves(Line::Variation(nested)).unwrap();
+        let cells: Vec<(usize, usize)> = moves.iter().map(|m| m.position.into()).collect();
+        assert_eq!(cells, vec![(0, 0), (1, 1), (2, 2)]);
+        assert_eq!(moves[2].player, Player::O);
+
+        let game = history.reconstruct_line_at(Line::Variation(first), 3).unwrap();
+        assert_eq!(game.legal_moves().count(), 13);
+        assert_eq!(history.replay_line(Line::Variation(nested)).unwrap().count(), 4);
+
+        // The game itself is untouched
+        assert_eq!(history.moves.len(), 3);
+
+        // Variations must be legal and branch off existing lines
+        assert!(matches!(history.add_variation(1, &[(0, 0)]), Err(GameError::CellOccupied(0, 0))));
+        assert!(matches!(history.add_variation(4, &[]), Err(GameError::InvalidMoveIndex(4))));
+        assert!(matches!(
+            history.add_variation_to(Line::Variation(9), 0, &[]),
+            Err(GameError::VariationNotFound(9))
+        ));
+        assert_eq!(history.variations.len(), 2);
+    }
+
+    #[test]
+    fn test_annotations_round_trip() {
+        let mut history = sample_history();
+        history.annotate(1, "missed fork at (2,0)").unwrap();
+        history.annotate(1, "O was winning before this").unwrap();
+        history.tag(1, MoveTag::Blunder).unwrap();
+        assert!(matches!(history.tag(3, MoveTag::Good), Err(GameError::InvalidMoveIndex(3))));
+
+        let json = serde_json::to_string(&history).unwrap();
+        let loaded: GameHistory = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.moves, history.moves);
+        assert_eq!(loaded.moves[1].comments.len(), 2);
+        assert_eq!(loaded.moves[1].tag, Some(MoveTag::Blunder));
+        assert!(loaded.moves[0].comments.is_empty());
     }
 }
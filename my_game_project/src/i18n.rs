@@ -0,0 +1,558 @@
+//! Translations of the text players see while playing
+//!
+//! Every prompt and status message of a game, and every [`GameError`]
+//! message, is a [`Text`] in the catalog below, written out in each
+//! [`Locale`] side by side. Templates take their arguments as `{0}`, `{1}`
+//! and so on, so a translation can put them in whatever order its language
+//! needs. [`tr`] fills in a template in the current locale, which the CLI
+//! sets from its config file or the environment with [`Locale::set_current`].
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use serde::Deserialize;
+
+use crate::error::GameError;
+
+/// Environment variables naming the locale, checked in order
+const LOCALE_VARS: [&str; 4] = ["TICTACTOE_LANG", "LC_ALL", "LC_MESSAGES", "LANG"];
+
+/// The current locale, as its index in [`Locale::ALL`]
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+/// A language the game's text is translated into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub enum Locale {
+    /// English
+    #[default]
+    #[serde(rename = "en")]
+    English,
+    /// Spanish
+    #[serde(rename = "es")]
+    Spanish,
+    /// French
+    #[serde(rename = "fr")]
+    French,
+}
+
+impl Locale {
+    /// Every locale, in the order of the catalog's translations
+    pub const ALL: [Locale; 3] = [Locale::English, Locale::Spanish, Locale::French];
+
+    /// The locale's ISO 639-1 language code
+    pub fn code(self) -> &'static str {
+        match self {
+            Locale::English => "en",
+            Locale::Spanish => "es",
+            Locale::French => "fr",
+        }
+    }
+
+    /// The locale named by `TICTACTOE_LANG`, `LC_ALL`, `LC_MESSAGES` or `LANG`
+    ///
+    /// The first of them that is set decides, falling back to English for a
+    /// language there are no translations for.
+    pub fn from_env() -> Locale {
+        LOCALE_VARS
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_default()
+    }
+
+    /// The locale text is translated into, English unless set otherwise
+    pub fn current() -> Locale {
+        Self::ALL[CURRENT.load(Ordering::Relaxed) as usize]
+    }
+
+    /// Translate text into this locale from now on
+    pub fn set_current(self) {
+        CURRENT.store(self.index() as u8, Ordering::Relaxed);
+    }
+
+    /// Returns true if `answer` means yes, in English or this locale
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::i18n::Locale;
+    ///
+    /// assert!(Locale::Spanish.is_yes("Sí\n"));
+    /// assert!(Locale::French.is_yes("y"));
+    /// assert!(!Locale::English.is_yes("o"));
+    /// ```
+    pub fn is_yes(self, answer: &str) -> bool {
+        let answer = answer.trim().to_lowercase();
+        let localized: &[&str] = match self {
+            Locale::English => &[],
+            Locale::Spanish => &["s", "si", "sí"],
+            Locale::French => &["o", "oui"],
+        };
+        ["y", "yes"].contains(&answer.as_str()) || localized.contains(&answer.as_str())
+    }
+
+    /// The locale's position in [`Locale::ALL`]
+    fn index(self) -> usize {
+        match self {
+            Locale::English => 0,
+            Locale::Spanish => 1,
+            Locale::French => 2,
+        }
+    }
+}
+
+impl FromStr for Locale {
+    type Err = GameError;
+
+    /// Parses a language code, or a POSIX locale like `es_ES.UTF-8`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let language = s.split(['_', '-', '.', '@']).next().unwrap_or_default().to_lowercase();
+        match language.as_str() {
+            "en" | "c" | "posix" => Ok(Locale::English),
+            "es" => Ok(Locale::Spanish),
+            "fr" => Ok(Locale::French),
+            _ => Err(GameError::InvalidConfig(format!("no translations for locale {}", s))),
+        }
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// Declares the [`Text`] keys along with their translations, in the order of [`Locale::ALL`]
+macro_rules! catalog {
+    ($($key:ident => [$en:expr, $es:expr, $fr:expr $(,)?],)*) => {
+        /// A piece of text in the catalog
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        #[allow(missing_docs)]
+        pub enum Text {
+            $($key,)*
+        }
+
+        impl Text {
+            /// Every piece of text in the catalog
+            pub const ALL: &'static [Text] = &[$(Text::$key,)*];
+
+            /// The text's template in each locale, in the order of [`Locale::ALL`]
+            fn templates(self) -> [&'static str; 3] {
+                match self {
+                    $(Text::$key => [$en, $es, $fr],)*
+                }
+            }
+        }
+    };
+}
+
+catalog! {
+    // Starting a game
+    Welcome => ["Welcome to Tic-Tac-Toe in Rust!", "¡Bienvenido al tres en raya en Rust!", "Bienvenue au morpion en Rust !"],
+    GameCreated => ["Game created with ID: {0}", "Partida creada con ID: {0}", "Partie créée avec l'ID : {0}"],
+    XGoesFirst => ["Player X goes first", "El jugador X empieza", "Le joueur X commence"],
+    PlayerNumber => ["Player {0}: {1}", "Jugador {0}: {1}", "Joueur {0} : {1}"],
+    PlayerNumberSide => ["Player {0} ({1}): {2}", "Jugador {0} ({1}): {2}", "Joueur {0} ({1}) : {2}"],
+    SelectVariant => ["Select variant:", "Elige la variante:", "Choisissez la variante :"],
+    VariantStandard => ["Tic-tac-toe", "Tres en raya", "Morpion"],
+    VariantConnectFour => ["Connect Four", "Conecta cuatro", "Puissance 4"],
+    VariantGomoku => ["Gomoku (five in a row)", "Gomoku (cinco en línea)", "Gomoku (cinq alignés)"],
+    VariantMisere => [
+        "Misère tic-tac-toe (three in a row loses)",
+        "Tres en raya misère (hacer tres en línea pierde)",
+        "Morpion misère (aligner trois fait perdre)",
+    ],
+    ChoiceDefaultFirst => [
+        "Enter your choice (press Enter for 1): ",
+        "Elige una opción (pulsa Intro para 1): ",
+        "Votre choix (Entrée pour 1) : ",
+    ],
+    BoardSizePrompt => [
+        "Enter board size (press Enter for {0}): ",
+        "Tamaño del tablero (pulsa Intro para {0}): ",
+        "Taille du plateau (Entrée pour {0}) : ",
+    ],
+    InvalidSize => [
+        "Invalid size. Defaulting to {0}x{0}.",
+        "Tamaño no válido. Se usará {0}x{0}.",
+        "Taille invalide. Plateau {0}x{0} par défaut.",
+    ],
+    WinLengthPrompt => [
+        "Enter marks in a row needed to win (press Enter for {0}): ",
+        "Marcas en línea necesarias para ganar (pulsa Intro para {0}): ",
+        "Nombre de marques alignées pour gagner (Entrée pour {0}) : ",
+    ],
+    SelectGameMode => ["Select game mode:", "Elige el modo de juego:", "Choisissez le mode de jeu :"],
+    ModeHumanVsHuman => ["Human vs Human", "Humano contra humano", "Humain contre humain"],
+    ModeEasy => ["Human vs AI (Easy)", "Humano contra IA (fácil)", "Humain contre IA (facile)"],
+    ModeMedium => ["Human vs AI (Medium)", "Humano contra IA (media)", "Humain contre IA (moyen)"],
+    ModeHard => ["Human vs AI (Hard)", "Humano contra IA (difícil)", "Humain contre IA (difficile)"],
+    ModeRules => ["Human vs AI (Rule-based)", "Humano contra IA (por reglas)", "Humain contre IA (à règles)"],
+    ChoiceOneToFive => ["Enter your choice (1-5): ", "Elige una opción (1-5): ", "Votre choix (1-5) : "],
+    InvalidModeChoice => [
+        "Invalid choice. Defaulting to Human vs Human.",
+        "Opción no válida. Se jugará humano contra humano.",
+        "Choix invalide. Humain contre humain par défaut.",
+    ],
+
+    // Saved games
+    SavedGamesIn => ["Saved games in {0}:", "Partidas guardadas en {0}:", "Parties sauvegardées dans {0} :"],
+    SavedGamePrompt => [
+        "Enter a number to load, d <number> to delete, or press Enter for a new game: ",
+        "Escribe un número para cargarla, d <número> para borrarla, o pulsa Intro para una partida nueva: ",
+        "Entrez un numéro pour charger, d <numéro> pour supprimer, ou Entrée pour une nouvelle partie : ",
+    ],
+    DeletedGame => ["Deleted game {0}.", "Partida {0} borrada.", "Partie {0} supprimée."],
+    NoSavedGame => ["No saved game {0}.", "No hay ninguna partida guardada {0}.", "Aucune partie sauvegardée {0}."],
+    PlayedBy => ["{0} was played by {1}", "{1} jugaba con {0}", "{1} jouait les {0}"],
+    GameLoaded => ["Game loaded successfully!", "¡Partida cargada!", "Partie chargée !"],
+    InterruptedSaved => [
+        "Game saved. To carry on, run: {0}",
+        "Partida guardada. Para continuar, ejecuta: {0}",
+        "Partie sauvegardée. Pour la reprendre, lancez : {0}",
+    ],
+    InvalidChoice => ["Invalid choice.", "Opción no válida.", "Choix invalide."],
+    SlotInProgress => ["in progress", "en curso", "en cours"],
+    SlotWon => ["{0} won", "ganó {0}", "{0} a gagné"],
+    SlotDrawn => ["drawn", "empate", "nulle"],
+    SlotResigned => ["{0} resigned", "{0} se rindió", "{0} a abandonné"],
+    SlotTimedOut => ["{0} ran out of time", "a {0} se le acabó el tiempo", "{0} n'avait plus de temps"],
+    SlotSummary => [
+        "{0} - {1}, {2} moves, saved {3}",
+        "{0} - {1}, {2} movimientos, guardada el {3}",
+        "{0} - {1}, {2} coups, sauvegardée le {3}",
+    ],
+
+    // Playing
+    CurrentBoard => ["Current board:", "Tablero actual:", "Plateau actuel :"],
+    FinalBoard => ["Final board state:", "Estado final del tablero:", "Plateau final :"],
+    PlayerTurn => ["Player {0}'s turn", "Turno del jugador {0}", "Au tour du joueur {0}"],
+    NamedTurn => ["{0}'s turn", "Turno de {0}", "Au tour de {0}"],
+    ChoseMove => ["{0} chose {1}: {2}", "{0} eligió {1}: {2}", "{0} a choisi {1} : {2}"],
+    DecidedIn => ["{0} decided in {1}", "{0} decidió en {1}", "{0} a décidé en {1}"],
+    MoveSuccessful => ["Move successful!", "¡Jugada hecha!", "Coup joué !"],
+    Played => ["{0} played {1}", "{0} jugó {1}", "{0} a joué {1}"],
+    MovePrompt => [
+        "Enter your move as 'row col' (0-{0}, 0-{1}) or like 'b2', 'resign' or 'draw': ",
+        "Escribe tu jugada como 'fila columna' (0-{0}, 0-{1}) o como 'b2', 'resign' para rendirte o 'draw' para ofrecer empate: ",
+        "Entrez votre coup en 'ligne colonne' (0-{0}, 0-{1}) ou comme 'b2', 'resign' pour abandonner ou 'draw' pour proposer la nulle : ",
+    ],
+    MoveOutOfRange => [
+        "Row must be between 0 and {0} and column between 0 and {1}.",
+        "La fila debe estar entre 0 y {0} y la columna entre 0 y {1}.",
+        "La ligne doit être entre 0 et {0} et la colonne entre 0 et {1}.",
+    ],
+    EnterAMove => ["Please enter a move.", "Escribe una jugada.", "Veuillez entrer un coup."],
+    ErrorMessage => ["Error: {0}", "Error: {0}", "Erreur : {0}"],
+    ErrorTryAgain => ["{0}. Please try again.", "{0}. Inténtalo de nuevo.", "{0}. Veuillez réessayer."],
+    TryAgain => ["Please try again.", "Inténtalo de nuevo.", "Veuillez réessayer."],
+    WaitingForOpponent => ["Waiting for the opponent...", "Esperando al rival...", "En attente de l'adversaire..."],
+
+    // Draw offers
+    OffersDraw => ["{0} offers a draw.", "{0} ofrece un empate.", "{0} propose la nulle."],
+    DeclinedDraw => ["{0} declined the draw.", "{0} rechazó el empate.", "{0} a refusé la nulle."],
+    AcceptDrawPrompt => [
+        "{0}, do you accept the draw? (y/n): ",
+        "{0}, ¿aceptas el empate? (s/n): ",
+        "{0}, acceptez-vous la nulle ? (o/n) : ",
+    ],
+    OffersDrawAccept => [
+        "{0} offers a draw. {1}, accept? (y/n)",
+        "{0} ofrece un empate. {1}, ¿aceptas? (s/n)",
+        "{0} propose la nulle. {1}, acceptez-vous ? (o/n)",
+    ],
+    WaitingForDrawAnswer => [
+        "Waiting for the opponent to answer the draw offer...",
+        "Esperando a que el rival responda a la oferta de empate...",
+        "En attente de la réponse de l'adversaire à la proposition de nulle...",
+    ],
+
+    // Results
+    PlayerWins => ["Player {0} wins!", "¡Gana el jugador {0}!", "Le joueur {0} gagne !"],
+    Draw => ["It's a draw!", "¡Empate!", "Match nul !"],
+    PlayerResigns => [
+        "Player {0} resigns. Player {1} wins!",
+        "El jugador {0} se rinde. ¡Gana el jugador {1}!",
+        "Le joueur {0} abandonne. Le joueur {1} gagne !",
+    ],
+    PlayerTimedOut => [
+        "Player {0} ran out of time. Player {1} wins!",
+        "Al jugador {0} se le acabó el tiempo. ¡Gana el jugador {1}!",
+        "Le joueur {0} n'a plus de temps. Le joueur {1} gagne !",
+    ],
+    YouWin => ["You win!", "¡Has ganado!", "Vous avez gagné !"],
+    YouLose => ["You lose.", "Has perdido.", "Vous avez perdu."],
+    PressAnyKey => ["Press any key to exit.", "Pulsa cualquier tecla para salir.", "Appuyez sur une touche pour quitter."],
+    ReviewPrompt => [
+        "Review your moves with the coach? [y/N]: ",
+        "¿Repasar tus jugadas con el entrenador? [s/N]: ",
+        "Revoir vos coups avec l'entraîneur ? [o/N] : ",
+    ],
+    ThanksForPlaying => ["Thanks for playing!", "¡Gracias por jugar!", "Merci d'avoir joué !"],
+
+    // Matches and network games
+    BestOf => ["Best of {0}", "Al mejor de {0}", "Au meilleur des {0}"],
+    MatchGameStarts => ["Game {0}: Player {1} goes first", "Partida {0}: empieza el jugador {1}", "Partie {0} : le joueur {1} commence"],
+    MatchAbandoned => ["Match abandoned at {0}", "Encuentro abandonado con {0}", "Match abandonné à {0}"],
+    AfterGame => ["After game {0}: {1}", "Tras la partida {0}: {1}", "Après la partie {0} : {1}"],
+    WinsMatch => ["{0} wins the match!", "¡{0} gana el encuentro!", "{0} remporte le match !"],
+    MatchDrawn => ["The match is drawn!", "¡El encuentro termina en empate!", "Le match est nul !"],
+    ShareGame => ["Share it with your opponent: {0}", "Compártela con tu rival: {0}", "Partagez-la avec votre adversaire : {0}"],
+    YouArePlaying => ["You are playing {0}", "Juegas con {0}", "Vous jouez les {0}"],
+    TerminalHelp => [
+        "←↑↓→ move  Enter place  r resign  d offer draw  q save & quit",
+        "←↑↓→ mover  Intro colocar  r rendirse  d ofrecer empate  q guardar y salir",
+        "←↑↓→ déplacer  Entrée placer  r abandonner  d proposer la nulle  q sauver et quitter",
+    ],
+
+    // Errors, in the order of `GameError`'s variants
+    CellOccupied => [
+        "Cell at position ({0}, {1}) is already occupied",
+        "La casilla ({0}, {1}) ya está ocupada",
+        "La case ({0}, {1}) est déjà occupée",
+    ],
+    InvalidPosition => [
+        "Invalid position: ({0}, {1}) is out of bounds",
+        "Posición no válida: ({0}, {1}) está fuera del tablero",
+        "Position invalide : ({0}, {1}) est hors du plateau",
+    ],
+    GameAlreadyFinished => ["Game is already finished", "La partida ya ha terminado", "La partie est déjà terminée"],
+    NotPlayerTurn => ["Not player's turn", "No es el turno del jugador", "Ce n'est pas le tour du joueur"],
+    InvalidToken => [
+        "Invalid or missing token for player {0}",
+        "Token no válido o ausente para el jugador {0}",
+        "Jeton invalide ou manquant pour le joueur {0}",
+    ],
+    NoOpenSeat => [
+        "Game has no open seat to join",
+        "La partida no tiene ningún puesto libre",
+        "La partie n'a plus de place libre",
+    ],
+    VersionConflict => [
+        "Game is at version {0}, not the expected version {1}",
+        "La partida está en la versión {0}, no en la versión esperada {1}",
+        "La partie est à la version {0}, et non à la version attendue {1}",
+    ],
+    Unauthenticated => ["Not logged in: {0}", "No has iniciado sesión: {0}", "Non connecté : {0}"],
+    InvalidCredentials => [
+        "Invalid username or password",
+        "Usuario o contraseña incorrectos",
+        "Nom d'utilisateur ou mot de passe incorrect",
+    ],
+    UsernameTaken => [
+        "Username '{0}' is already taken",
+        "El nombre de usuario '{0}' ya está en uso",
+        "Le nom d'utilisateur '{0}' est déjà pris",
+    ],
+    InvalidAccount => ["Invalid account details: {0}", "Datos de cuenta no válidos: {0}", "Informations de compte invalides : {0}"],
+    NoDrawOffer => [
+        "No draw offer to respond to",
+        "No hay ninguna oferta de empate que responder",
+        "Aucune proposition de nulle à laquelle répondre",
+    ],
+    NothingToUndo => ["No moves to undo", "No hay jugadas que deshacer", "Aucun coup à annuler"],
+    NoUndoRequest => [
+        "No undo request to respond to",
+        "No hay ninguna petición de deshacer que responder",
+        "Aucune demande d'annulation à laquelle répondre",
+    ],
+    InvalidWebhook => ["Invalid webhook: {0}", "Webhook no válido: {0}", "Webhook invalide : {0}"],
+    InvalidChatMessage => ["Invalid chat message: {0}", "Mensaje de chat no válido: {0}", "Message de discussion invalide : {0}"],
+    IoError => ["IO error: {0}", "Error de E/S: {0}", "Erreur d'E/S : {0}"],
+    JsonError => ["JSON error: {0}", "Error de JSON: {0}", "Erreur JSON : {0}"],
+    SerializationError => ["Serialization error: {0}", "Error de serialización: {0}", "Erreur de sérialisation : {0}"],
+    DeserializationError => ["Deserialization error: {0}", "Error de deserialización: {0}", "Erreur de désérialisation : {0}"],
+    NetworkError => ["Network error: {0}", "Error de red: {0}", "Erreur réseau : {0}"],
+    StorageError => ["Storage error: {0}", "Error de almacenamiento: {0}", "Erreur de stockage : {0}"],
+    GameNotFound => ["Game with ID {0} not found", "No se encontró la partida con ID {0}", "Partie avec l'ID {0} introuvable"],
+    PlayerNotFound => ["Player {0} not found", "No se encontró al jugador {0}", "Joueur {0} introuvable"],
+    NoValidMoves => ["No valid moves available", "No hay jugadas válidas", "Aucun coup valide possible"],
+    InvalidCoordinates => ["Invalid coordinates: '{0}'", "Coordenadas no válidas: '{0}'", "Coordonnées invalides : '{0}'"],
+    InvalidBoardSize => [
+        "Invalid board: size {0} with win length {1}",
+        "Tablero no válido: tamaño {0} con {1} en línea para ganar",
+        "Plateau invalide : taille {0} avec {1} alignés pour gagner",
+    ],
+    UnreachablePosition => ["Unreachable position: {0}", "Posición inalcanzable: {0}", "Position impossible : {0}"],
+    InvalidMoveIndex => [
+        "Move index {0} is out of range",
+        "El índice de jugada {0} está fuera de rango",
+        "L'indice de coup {0} est hors limites",
+    ],
+    VariationNotFound => ["Variation {0} not found", "No se encontró la variante {0}", "Variante {0} introuvable"],
+    InvalidPlayerType => ["Invalid player type: {0}", "Tipo de jugador no válido: {0}", "Type de joueur invalide : {0}"],
+    InvalidCursor => ["Invalid cursor: {0}", "Cursor no válido: {0}", "Curseur invalide : {0}"],
+    InvalidConfig => ["Invalid configuration: {0}", "Configuración no válida: {0}", "Configuration invalide : {0}"],
+    InvalidMatchLength => [
+        "Invalid match length: best of {0}",
+        "Duración de encuentro no válida: al mejor de {0}",
+        "Durée de match invalide : au meilleur des {0}",
+    ],
+    MatchNotFound => ["Match not found: {0}", "No se encontró el encuentro: {0}", "Match introuvable : {0}"],
+    MatchAlreadyFinished => ["Match is already finished", "El encuentro ya ha terminado", "Le match est déjà terminé"],
+    ConcurrentUpdate => [
+        "Game {0} was changed by another server",
+        "Otro servidor cambió la partida {0}",
+        "La partie {0} a été modifiée par un autre serveur",
+    ],
+    AiTimedOut => [
+        "The AI took longer than {0} ms to choose a move",
+        "La IA tardó más de {0} ms en elegir una jugada",
+        "L'IA a mis plus de {0} ms à choisir un coup",
+    ],
+    AiFailed => [
+        "The AI failed to choose a move: {0}",
+        "La IA no pudo elegir una jugada: {0}",
+        "L'IA n'a pas réussi à choisir un coup : {0}",
+    ],
+    GameAlreadyExists => [
+        "Game with ID {0} already exists",
+        "Ya existe una partida con ID {0}",
+        "Une partie avec l'ID {0} existe déjà",
+    ],
+    Forbidden => ["Forbidden: {0}", "Prohibido: {0}", "Interdit : {0}"],
+    InvalidNotation => ["Invalid notation: {0}", "Notación no válida: {0}", "Notation invalide : {0}"],
+    IllegalHistoryMove => [
+        "Illegal move at ply {0}: {1}",
+        "Jugada ilegal en el medio movimiento {0}: {1}",
+        "Coup illégal au demi-coup {0} : {1}",
+    ],
+    UnsupportedSchemaVersion => [
+        "Save file schema version {0} is newer than this release supports",
+        "La versión {0} del esquema de la partida guardada es más nueva de lo que admite esta versión",
+        "La version {0} du schéma de la sauvegarde est plus récente que ce que cette version prend en charge",
+    ],
+    CorruptSave => ["Corrupt save: {0}", "Partida guardada dañada: {0}", "Sauvegarde corrompue : {0}"],
+    InvalidImport => ["Invalid import: {0}", "Importación no válida: {0}", "Importation invalide : {0}"],
+}
+
+impl Text {
+    /// The text in `locale`, with `{0}`, `{1}` and so on replaced by `args`
+    pub fn format(self, locale: Locale, args: &[&dyn fmt::Display]) -> String {
+        let mut text = self.templates()[locale.index()].to_string();
+        for (index, arg) in args.iter().enumerate() {
+            text = text.replace(&format!("{{{}}}", index), &arg.to_string());
+        }
+        text
+    }
+}
+
+/// The text in the current locale, with `{0}`, `{1}` and so on replaced by `args`
+///
+/// # Examples
+///
+/// ```
+/// use my_game_project::i18n::{tr, Locale, Text};
+///
+/// assert_eq!(tr(Text::PlayerTurn, &[&"X"]), "Player X's turn");
+/// assert_eq!(Text::PlayerTurn.format(Locale::Spanish, &[&"X"]), "Turno del jugador X");
+/// ```
+pub fn tr(text: Text, args: &[&dyn fmt::Display]) -> String {
+    text.format(Locale::current(), args)
+}
+
+impl GameError {
+    /// The error's message in the current locale
+    ///
+    /// The error's [`Display`](fmt::Display) stays in English, for logs and the API.
+    pub fn localized(&self) -> String {
+        self.localized_in(Locale::current())
+    }
+
+    /// The error's message in `locale`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::error::GameError;
+    /// use my_game_project::i18n::Locale;
+    ///
+    /// let error = GameError::CellOccupied(1, 2);
+    /// assert_eq!(error.localized_in(Locale::French), "La case (1, 2) est déjà occupée");
+    /// assert_eq!(error.localized_in(Locale::English), error.to_string());
+    /// ```
+    pub fn localized_in(&self, locale: Locale) -> String {
+        let text = |text: Text, args: &[&dyn fmt::Display]| text.format(locale, args);
+        match self {
+            GameError::CellOccupied(row, col) => text(Text::CellOccupied, &[row, col]),
+            GameError::InvalidPosition(row, col) => text(Text::InvalidPosition, &[row, col]),
+            GameError::GameAlreadyFinished => text(Text::GameAlreadyFinished, &[]),
+            GameError::NotPlayerTurn => text(Text::NotPlayerTurn, &[]),
+            GameError::InvalidToken(player) => text(Text::InvalidToken, &[player]),
+            GameError::NoOpenSeat => text(Text::NoOpenSeat, &[]),
+            GameError::VersionConflict { expected, actual } => text(Text::VersionConflict, &[actual, expected]),
+            GameError::Unauthenticated(reason) => text(Text::Unauthenticated, &[reason]),
+            GameError::InvalidCredentials => text(Text::InvalidCredentials, &[]),
+            GameError::UsernameTaken(name) => text(Text::UsernameTaken, &[name]),
+            GameError::InvalidAccount(reason) => text(Text::InvalidAccount, &[reason]),
+            GameError::NoDrawOffer => text(Text::NoDrawOffer, &[]),
+            GameError::NothingToUndo => text(Text::NothingToUndo, &[]),
+            GameError::NoUndoRequest => text(Text::NoUndoRequest, &[]),
+            GameError::InvalidWebhook(reason) => text(Text::InvalidWebhook, &[reason]),
+            GameError::InvalidChatMessage(reason) => text(Text::InvalidChatMessage, &[reason]),
+            GameError::IoError(reason) => text(Text::IoError, &[reason]),
+            GameError::JsonError(e) => text(Text::JsonError, &[e]),
+            GameError::SerializationError(reason) => text(Text::SerializationError, &[reason]),
+            GameError::DeserializationError(reason) => text(Text::DeserializationError, &[reason]),
+            GameError::NetworkError(reason) => text(Text::NetworkError, &[reason]),
+            GameError::StorageError(reason) => text(Text::StorageError, &[reason]),
+            GameError::GameNotFound(id) => text(Text::GameNotFound, &[id]),
+            GameError::PlayerNotFound(id) => text(Text::PlayerNotFound, &[id]),
+            GameError::NoValidMoves => text(Text::NoValidMoves, &[]),
+            GameError::InvalidCoordinates(input) => text(Text::InvalidCoordinates, &[input]),
+            GameError::InvalidBoardSize(size, win_length) => text(Text::InvalidBoardSize, &[size, win_length]),
+            GameError::UnreachablePosition(reason) => text(Text::UnreachablePosition, &[reason]),
+            GameError::InvalidMoveIndex(index) => text(Text::InvalidMoveIndex, &[index]),
+            GameError::VariationNotFound(index) => text(Text::VariationNotFound, &[index]),
+            GameError::InvalidPlayerType(player) => text(Text::InvalidPlayerType, &[player]),
+            GameError::InvalidCursor(cursor) => text(Text::InvalidCursor, &[cursor]),
+            GameError::InvalidConfig(reason) => text(Text::InvalidConfig, &[reason]),
+            GameError::InvalidMatchLength(best_of) => text(Text::InvalidMatchLength, &[best_of]),
+            GameError::MatchNotFound(id) => text(Text::MatchNotFound, &[id]),
+            GameError::MatchAlreadyFinished => text(Text::MatchAlreadyFinished, &[]),
+            GameError::ConcurrentUpdate(id) => text(Text::ConcurrentUpdate, &[id]),
+            GameError::AiTimedOut(ms) => text(Text::AiTimedOut, &[ms]),
+            GameError::AiFailed(reason) => text(Text::AiFailed, &[reason]),
+            GameError::GameAlreadyExists(id) => text(Text::GameAlreadyExists, &[id]),
+            GameError::Forbidden(reason) => text(Text::Forbidden, &[reason]),
+            GameError::InvalidNotation(reason) => text(Text::InvalidNotation, &[reason]),
+            GameError::IllegalHistoryMove(ply, error) => {
+                text(Text::IllegalHistoryMove, &[ply, &error.localized_in(locale)])
+            }
+            GameError::UnsupportedSchemaVersion(version) => text(Text::UnsupportedSchemaVersion, &[version]),
+            GameError::CorruptSave(reason) => text(Text::CorruptSave, &[reason]),
+            GameError::InvalidImport(reason) => text(Text::InvalidImport, &[reason]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translations_use_the_same_arguments() {
+        for text in Text::ALL {
+            let [english, others @ ..] = text.templates();
+            let placeholders = |template: &str| -> Vec<usize> {
+                (0..10).filter(|index| template.contains(&format!("{{{}}}", index))).collect()
+            };
+            for other in others {
+                assert_eq!(placeholders(other), placeholders(english), "{:?}", text);
+            }
+        }
+
+        // English messages match the errors' own
+        for error in [
+            GameError::VersionConflict { expected: 3, actual: 4 },
+            GameError::IllegalHistoryMove(2, Box::new(GameError::CellOccupied(0, 0))),
+            GameError::InvalidCoordinates("z9".to_string()),
+        ] {
+            assert_eq!(error.localized_in(Locale::English), error.to_string());
+        }
+        assert_eq!("es_ES.UTF-8".parse::<Locale>().unwrap(), Locale::Spanish);
+        assert!("de_DE".parse::<Locale>().is_err());
+    }
+}
@@ -3,10 +3,63 @@
 //! This library provides the core functionality for a tic-tac-toe game,
 //! including game state management, move validation, and win condition checking.
 //! It also supports game history tracking, serialization, AI opponents, and a REST API.
+//!
+//! The rules engine is always built, along with what it uses itself to draw
+//! boards ([`render`]), describe saves ([`save`]), turn boards ([`symmetry`])
+//! and translate messages ([`i18n`]), and the [`rating`]s and best-of-N
+//! [`r#match`]es built on single games. The rest is behind cargo features,
+//! all on by default, so a program that only needs the rules can depend on
+//! the crate with `default-features = false`:
+//!
+//! - `ai`: the AI players, with [`analysis`], [`bench`], [`simulation`],
+//!   [`tournament`], [`puzzle`]s and the [`tutorial`]
+//! - `files`: saving to and loading from files, [`saves`], and the [`stats`]
+//!   and training [`dataset`]s drawn from saved games
+//! - `archive`: the SQLite [`archive`] of finished games
+//! - `server`: the [`server`] itself and its [`client`]
+//! - `cli`: the `tictactoe` command-line game, which needs `server` to play
+//!   over the network and `archive` to archive finished games
+//! - `ffi`: C functions in [`ffi`] for using the engine and its AI from other languages
 
 pub mod game;
 pub mod error;
+pub mod events;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 pub mod player;
+pub mod position;
 pub mod history;
+pub mod notation;
+#[cfg(feature = "ai")]
 pub mod ai;
+#[cfg(feature = "ai")]
+pub mod analysis;
+#[cfg(feature = "archive")]
+pub mod archive;
+#[cfg(feature = "ai")]
+pub mod bench;
+#[cfg(feature = "files")]
+pub mod dataset;
+pub mod i18n;
+#[cfg(feature = "ai")]
+pub mod puzzle;
+pub mod rating;
+pub mod render;
+pub mod rules;
+pub mod save;
+#[cfg(feature = "files")]
+pub mod saves;
+#[cfg(feature = "server")]
 pub mod server;
+#[cfg(feature = "ai")]
+pub mod simulation;
+#[cfg(feature = "files")]
+pub mod stats;
+pub mod symmetry;
+#[cfg(feature = "server")]
+pub mod client;
+#[cfg(feature = "ai")]
+pub mod tournament;
+#[cfg(feature = "ai")]
+pub mod tutorial;
+pub mod r#match;
@@ -1,102 +1,1343 @@
-use std::io::{self, Write};
-use std::path::Path;
+mod tui;
 
-use my_game_project::ai::{MinimaxAI, Difficulty};
-use my_game_project::game::{GameState, GameStatus};
+#[cfg(feature = "archive")]
+use std::future::Future;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use my_game_project::ai::{Difficulty, QLearningAI, QTable, Tuner};
+use my_game_project::analysis::{analyze_history, describe_score, review_history, MoveQuality, Review};
+#[cfg(feature = "archive")]
+use my_game_project::archive::{HistoryQuery, HistoryStore, Outcome};
+use my_game_project::bench;
+use my_game_project::game::{Cell, Game, GameState, GameStatus, DEFAULT_SIZE, DEFAULT_WIN_LENGTH};
+use my_game_project::dataset::{DatasetFormat, DatasetWriter};
 use my_game_project::error::{GameError, GameResult};
 use my_game_project::history::GameHistory;
-use my_game_project::player::{GamePlayer, HumanPlayer, Player};
+use my_game_project::i18n::{tr, Locale, Text};
+use my_game_project::player::{self, GamePlayer, Player, PlayerAction};
+#[cfg(feature = "server")]
+use my_game_project::player::{HumanPlayer, RemoteGame, RemotePlayer};
+use my_game_project::position::Position;
+use my_game_project::puzzle::{find_in_history, Puzzle, DEFAULT_PUZZLE_MOVES};
+use my_game_project::r#match::{Match, MatchStatus};
+use my_game_project::render::{self, BoardRenderer};
+use my_game_project::rules::Variant;
+use my_game_project::save::{self, PlayerInfo, SaveBundle, SaveFormat};
+use my_game_project::saves::{SaveSlot, SaveSlots, DEFAULT_SAVES_DIR};
+use my_game_project::simulation::{simulate, Bot};
+use my_game_project::stats::GameStats;
+use my_game_project::tournament::Tournament;
+use my_game_project::tutorial::LESSONS;
+#[cfg(feature = "archive")]
+use chrono::{DateTime, NaiveDate, NaiveTime};
+use chrono::Utc;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Settings file read when `--config` doesn't name another
+const CLI_CONFIG_FILE: &str = "tictactoe.toml";
+
+/// File in the saves directory finished games are archived in unless configured otherwise
+#[cfg(feature = "archive")]
+const DEFAULT_ARCHIVE_FILE: &str = "history.db";
+
+/// The game being played with plain prompts, saved if Ctrl-C interrupts it
+static UNFINISHED_GAME: Mutex<Option<UnfinishedGame>> = Mutex::new(None);
+
+/// A game in progress, with what's needed to save it into its slot
+struct UnfinishedGame {
+    game: Game,
+    players: Vec<PlayerInfo>,
+    saves: SaveSlots,
+}
+
+/// Settings for the CLI
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct CliConfig {
+    /// Directory games are saved in
+    saves_dir: Option<PathBuf>,
+    /// SQLite database finished games are archived in
+    #[cfg(feature = "archive")]
+    archive: Option<String>,
+    /// How boards are drawn
+    board: BoardRenderer,
+    /// The language prompts and messages are shown in, instead of the one the environment names
+    locale: Option<Locale>,
+}
+
+impl CliConfig {
+    /// Reads the config file, then applies `--saves-dir`, `--archive` and `--theme` over it
+    ///
+    /// The config file is the one named by `--config`, or `tictactoe.toml` if it exists.
+    fn load(args: &[String]) -> GameResult<Self> {
+        let config_file = match flag_value(args, "--config") {
+            Some(path) => Some(PathBuf::from(path)),
+            None => Some(PathBuf::from(CLI_CONFIG_FILE)).filter(|path| path.exists()),
+        };
+        let mut config = match config_file {
+            Some(path) => {
+                let text = std::fs::read_to_string(&path).map_err(|e| {
+                    GameError::InvalidConfig(format!("can't read {}: {}", path.display(), e))
+                })?;
+                toml::from_str::<CliConfig>(&text)
+                    .map_err(|e| GameError::InvalidConfig(format!("in {}: {}", path.display(), e)))?
+            }
+            None => CliConfig::default(),
+        };
+
+        if let Some(dir) = flag_value(args, "--saves-dir") {
+            config.saves_dir = Some(PathBuf::from(dir));
+        }
+        #[cfg(feature = "archive")]
+        if let Some(url) = flag_value(args, "--archive") {
+            config.archive = Some(url.to_string());
+        }
+        if let Some(theme) = flag_value(args, "--theme") {
+            config.board.theme = theme.parse()?;
+        }
+        Ok(config)
+    }
+
+    /// The saves directory, `saves` unless configured otherwise
+    fn save_slots(&self) -> SaveSlots {
+        SaveSlots::new(self.saves_dir.clone().unwrap_or_else(|| PathBuf::from(DEFAULT_SAVES_DIR)))
+    }
+
+    /// Opens the archive of finished games, `history.db` in the saves directory unless configured otherwise
+    #[cfg(feature = "archive")]
+    fn open_archive(&self) -> GameResult<HistoryStore> {
+        let url = match &self.archive {
+            Some(url) => url.clone(),
+            None => {
+                let saves = self.save_slots();
+                std::fs::create_dir_all(saves.dir())?;
+                format!("sqlite://{}", saves.dir().join(DEFAULT_ARCHIVE_FILE).display())
+            }
+        };
+        block_on(HistoryStore::connect(&url))?
+    }
+}
 
 fn main() -> GameResult<()> {
-    println!("Welcome to Tic-Tac-Toe in Rust!");
+    let args: Vec<String> = std::env::args().collect();
+    // A broken config file is reported by the command that reads the rest of it
+    let locale = CliConfig::load(&args).ok().and_then(|config| config.locale);
+    locale.unwrap_or_else(Locale::from_env).set_current();
+    ctrlc::set_handler(save_on_interrupt).map_err(|e| GameError::IoError(io::Error::other(e)))?;
+
+    match args.get(1).map(String::as_str) {
+        Some("analyze") => match args.get(2) {
+            Some(filename) => analyze(filename),
+            None => {
+                println!("Usage: tictactoe analyze <history.json>");
+                Ok(())
+            }
+        },
+        Some("replay") => match args.get(2) {
+            Some(filename) => replay(filename, args.iter().any(|arg| arg == "--eval"), &CliConfig::load(&args)?.board),
+            None => {
+                println!("Usage: tictactoe replay <history.json> [--eval]");
+                Ok(())
+            }
+        },
+        Some("notation") => {
+            match args.get(2) {
+                Some(filename) => print!("{}", GameHistory::load_from_file(filename)?.to_notation()?),
+                None => println!("Usage: tictactoe notation <history.json>"),
+            }
+            Ok(())
+        }
+        Some("convert") => match (args.get(2), args.get(3)) {
+            (Some(from), Some(to)) => convert(from, to),
+            _ => {
+                println!("Usage: tictactoe convert <saved file> <new file>");
+                Ok(())
+            }
+        },
+        Some("stats") => show_stats(&args),
+        Some("export-training") => export_training(&args),
+        Some("export-gif") => match (args.get(2), args.get(3)) {
+            (Some(from), Some(to)) => export_gif(from, to, &args),
+            _ => {
+                println!("Usage: tictactoe export-gif <history> <replay.gif> [--delay <ms>]");
+                Ok(())
+            }
+        },
+        Some("export-image") => match (args.get(2), args.get(3)) {
+            (Some(from), Some(to)) => export_image(from, to, &args),
+            _ => {
+                println!("Usage: tictactoe export-image <saved game or history> <image.svg|image.png> [--ply <n>]");
+                Ok(())
+            }
+        },
+        Some("puzzle") => solve_puzzle(&args),
+        Some("tutorial") => run_tutorial(&CliConfig::load(&args)?.board),
+        Some("simulate") => run_simulation(&args),
+        Some("bench") => run_benchmarks(&args),
+        Some("tournament") => run_tournament(&args),
+        Some("tune") => match flag_value(&args, "--out") {
+            Some(out) => tune(out, &args),
+            None => {
+                println!("Usage: tictactoe tune --out <weights.toml> [--size <n>] [--win-length <k>] [--generations <n>] [--population <n>] [--games <n>]");
+                Ok(())
+            }
+        },
+        Some("train") => match flag_value(&args, "--out") {
+            Some(out) => train(out, &args),
+            None => {
+                println!("Usage: tictactoe train --out <table.json> [--games <games>] [--from <table.json>]");
+                Ok(())
+            }
+        },
+        Some("match") => {
+            let plain = args.iter().any(|arg| arg == "--plain") || !io::stdout().is_terminal();
+            match flag_value(&args, "--best-of").unwrap_or("3").parse() {
+                Ok(best_of) => play_match(best_of, plain, &CliConfig::load(&args)?, &args),
+                Err(_) => {
+                    println!("Usage: tictactoe match --best-of <games> [--plain] [--x <player>] [--o <player>]");
+                    Ok(())
+                }
+            }
+        }
+        _ if args.iter().any(|arg| arg == "--moves") => play_moves(&args),
+        #[cfg(feature = "server")]
+        _ if flag_value(&args, "--server").is_some() => {
+            let server = flag_value(&args, "--server").unwrap_or_default();
+            play_remote(server, flag_value(&args, "--join"), &CliConfig::load(&args)?.board)
+        }
+        #[cfg(not(feature = "server"))]
+        _ if flag_value(&args, "--server").is_some() => Err(GameError::InvalidConfig(
+            "playing over the network needs the `server` feature; build with `--features server`".to_string(),
+        )),
+        _ => {
+            // Fall back to plain prompts when asked to, or when not attached to a terminal
+            let plain = args.iter().any(|arg| arg == "--plain") || !io::stdout().is_terminal();
+            play(plain, &CliConfig::load(&args)?, &args)
+        }
+    }
+}
+
+/// Print statistics over the games in a directory, or over the archived games
+///
+/// Usage: `tictactoe stats [<dir>] [--player <name>] [--result x|o|draw] [--from <date>] [--to <date>]`
+///
+/// The filters narrow down the archived games, and dates are either
+/// `YYYY-MM-DD` or RFC 3339 times.
+fn show_stats(args: &[String]) -> GameResult<()> {
+    if let Some(dir) = args.get(2).filter(|arg| !arg.starts_with("--")) {
+        println!("{}", GameStats::load_dir(dir)?);
+        return Ok(());
+    }
+
+    show_archived_stats(args)
+}
+
+/// Prints statistics over the archived games the filters pick out
+#[cfg(feature = "archive")]
+fn show_archived_stats(args: &[String]) -> GameResult<()> {
+    let Some(query) = history_query(args) else {
+        println!("Usage: tictactoe stats [<dir>] [--player <name>] [--result x|o|draw] [--from <date>] [--to <date>]");
+        return Ok(());
+    };
+    let archive = CliConfig::load(args)?.open_archive()?;
+    println!("{}", block_on(archive.stats(&query))??);
+    Ok(())
+}
+
+/// Statistics over archived games need the archive
+#[cfg(not(feature = "archive"))]
+fn show_archived_stats(_args: &[String]) -> GameResult<()> {
+    Err(archive_missing())
+}
+
+/// The histories of the archived games the filters pick out, or `None` if the filters can't be read
+#[cfg(feature = "archive")]
+fn archived_histories(args: &[String]) -> GameResult<Option<Vec<GameHistory>>> {
+    let Some(query) = history_query(args) else {
+        return Ok(None);
+    };
+    let archive = CliConfig::load(args)?.open_archive()?;
+    Ok(Some(block_on(archive.find(&query))??.into_iter().map(|game| game.history).collect()))
+}
+
+/// Archived games need the archive
+#[cfg(not(feature = "archive"))]
+fn archived_histories(_args: &[String]) -> GameResult<Option<Vec<GameHistory>>> {
+    Err(archive_missing())
+}
+
+/// The error for commands that read the archive in a build without it
+#[cfg(not(feature = "archive"))]
+fn archive_missing() -> GameError {
+    GameError::InvalidConfig("the archive needs the `archive` feature; build with `--features archive`".to_string())
+}
+
+/// Reads the `--player`, `--result`, `--from` and `--to` filters for archived games
+///
+/// Returns `None` if a result or date can't be read.
+#[cfg(feature = "archive")]
+fn history_query(args: &[String]) -> Option<HistoryQuery> {
+    let result = match flag_value(args, "--result") {
+        None => None,
+        Some("x") => Some(Outcome::XWon),
+        Some("o") => Some(Outcome::OWon),
+        Some("draw") => Some(Outcome::Draw),
+        Some(_) => return None,
+    };
+    let from = flag_value(args, "--from").map(parse_time).transpose().ok()?;
+    let to = flag_value(args, "--to").map(parse_time).transpose().ok()?;
+    Some(HistoryQuery { from, to, player: flag_value(args, "--player").map(str::to_string), result })
+}
+
+/// Export finished games as training data for machine learning
+///
+/// Usage: `tictactoe export-training [<dir>] --out <file> [--format ndjson|csv] [--augment] [--player <name>] [--result x|o|draw] [--from <date>] [--to <date>]`
+///
+/// Games come from the history files in `<dir>`, or from the archive, which
+/// the filters narrow down. `--augment` writes each move once for every
+/// symmetry of the board.
+fn export_training(args: &[String]) -> GameResult<()> {
+    let usage = "Usage: tictactoe export-training [<dir>] --out <file> [--format ndjson|csv] [--augment] \
+                 [--player <name>] [--result x|o|draw] [--from <date>] [--to <date>]";
+    let Some(out) = flag_value(args, "--out") else {
+        println!("{}", usage);
+        return Ok(());
+    };
+    let format = match flag_value(args, "--format") {
+        Some(format) => format.parse()?,
+        None if out.ends_with(".csv") => DatasetFormat::Csv,
+        None => DatasetFormat::Ndjson,
+    };
+    let augment = args.iter().any(|arg| arg == "--augment");
+
+    let histories = match args.get(2).filter(|arg| !arg.starts_with("--")) {
+        Some(dir) => load_histories(dir)?,
+        None => match archived_histories(args)? {
+            Some(histories) => histories,
+            None => {
+                println!("{}", usage);
+                return Ok(());
+            }
+        },
+    };
+
+    let file = std::fs::File::create(out)?;
+    let mut writer = DatasetWriter::new(io::BufWriter::new(file), format);
+    let mut records = 0;
+    for history in &histories {
+        records += writer.write_history(history, augment)?;
+    }
+    writer.finish()?;
+
+    println!("Wrote {} records from {} games to {}", records, histories.len(), out);
+    Ok(())
+}
+
+/// Draw a saved game or history as an SVG or PNG image, going by `to`'s extension
+///
+/// Usage: `tictactoe export-image <saved game or history> <image.svg|image.png> [--ply <n>]`
+///
+/// Histories are drawn as they finished, or after `--ply` moves. PNG images
+/// need the `png` feature.
+fn export_image(from: &str, to: &str, args: &[String]) -> GameResult<()> {
+    let ply = match flag_value(args, "--ply").map(str::parse::<usize>) {
+        Some(Ok(ply)) => Some(ply),
+        Some(Err(_)) => return Err(GameError::InvalidConfig("--ply must be a number of moves".to_string())),
+        None => None,
+    };
+
+    // A history has moves, which a game state doesn't, so try it first
+    let game = match GameHistory::load_from_file(from) {
+        Ok(history) => {
+            let mut positions = history.replay();
+            let position = match ply {
+                Some(ply) => positions.nth(ply),
+                None => positions.last(),
+            };
+            position.ok_or_else(|| GameError::InvalidConfig(format!("the game has fewer than {} moves", ply.unwrap_or(0))))??
+        }
+        Err(_) => GameState::load_from_file(from)?,
+    };
+
+    let image = match Path::new(to).extension().and_then(|extension| extension.to_str()) {
+        Some("svg") => render::svg(&game).into_bytes(),
+        Some("png") => render::png(&game)?,
+        _ => return Err(GameError::InvalidConfig(format!("can't tell the image format of {}; use .svg or .png", to))),
+    };
+    std::fs::write(to, image)?;
+    println!("Drew {} as {}", from, to);
+    Ok(())
+}
+
+/// Animate a saved history as a GIF, one frame per move
+///
+/// Usage: `tictactoe export-gif <history> <replay.gif> [--delay <ms>]`
+///
+/// Each frame is shown for `--delay` milliseconds, 800 by default. Needs the
+/// `gif` feature.
+fn export_gif(from: &str, to: &str, args: &[String]) -> GameResult<()> {
+    let delay = match flag_value(args, "--delay").map(str::parse::<u64>) {
+        Some(Ok(ms)) => Duration::from_millis(ms),
+        Some(Err(_)) => return Err(GameError::InvalidConfig("--delay must be a number of milliseconds".to_string())),
+        None => render::DEFAULT_FRAME_DELAY,
+    };
+    let history = GameHistory::load_from_file(from)?;
+
+    let mut out = Vec::new();
+    render::replay_gif(&history, delay, &mut out)?;
+    std::fs::write(to, out)?;
+    println!("Animated {} moves of {} as {}", history.moves.len(), from, to);
+    Ok(())
+}
+
+/// Loads every game history in `dir`, skipping files that aren't histories
+fn load_histories(dir: &str) -> GameResult<Vec<GameHistory>> {
+    let entries = std::fs::read_dir(dir)?;
+    let mut histories = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if SaveFormat::is_save_file(&path) {
+            if let Ok(history) = GameHistory::load_from_file(&path.to_string_lossy()) {
+                histories.push(history);
+            }
+        }
+    }
+    Ok(histories)
+}
+
+/// Parses a date as midnight UTC, or an RFC 3339 time
+#[cfg(feature = "archive")]
+fn parse_time(text: &str) -> Result<DateTime<Utc>, chrono::ParseError> {
+    match NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        Ok(date) => Ok(date.and_time(NaiveTime::MIN).and_utc()),
+        Err(_) => DateTime::parse_from_rfc3339(text).map(|time| time.with_timezone(&Utc)),
+    }
+}
+
+/// Runs a future, such as a query of the archive, to completion
+#[cfg(feature = "archive")]
+fn block_on<F: Future>(future: F) -> GameResult<F::Output> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    Ok(runtime.block_on(future))
+}
+
+/// Saves the unfinished game, if one is being played, and exits as Ctrl-C would have
+fn save_on_interrupt() {
+    let unfinished = UNFINISHED_GAME.lock().unwrap_or_else(PoisonError::into_inner).take();
+    if let Some(UnfinishedGame { game, players, saves }) = unfinished {
+        println!();
+        match saves.save(&game, &players) {
+            Ok(()) => {
+                let args: Vec<String> = std::env::args().collect();
+                println!("{}", tr(Text::InterruptedSaved, &[&resume_command(&args, game.state().id)]));
+            }
+            Err(e) => eprintln!("{}", tr(Text::ErrorMessage, &[&e.localized()])),
+        }
+    }
+    std::process::exit(130);
+}
+
+/// The command that carries on the saved game with this ID, with the flags that chose its players and saves
+fn resume_command(args: &[String], game_id: Uuid) -> String {
+    let mut command = vec!["tictactoe".to_string()];
+    if args.iter().any(|arg| arg == "--plain") {
+        command.push("--plain".to_string());
+    }
+    for flag in ["--x", "--o", "--config", "--saves-dir", "--theme"] {
+        if let Some(value) = flag_value(args, flag) {
+            command.push(flag.to_string());
+            command.push(match value.contains(char::is_whitespace) {
+                true => format!("{:?}", value),
+                false => value.to_string(),
+            });
+        }
+    }
+    command.push(format!("--resume {}", game_id));
+    command.join(" ")
+}
+
+/// Returns the value following `flag` in the command line arguments, if any
+fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|index| args.get(index + 1))
+        .map(String::as_str)
+}
+
+/// Play bots against each other and print the results
+///
+/// Usage: `tictactoe simulate --games 1000 --x rules --o minimax:hard`
+fn run_simulation(args: &[String]) -> GameResult<()> {
+    let games = flag_value(args, "--games").and_then(|games| games.parse().ok()).unwrap_or(100);
+    let bot = |flag| flag_value(args, flag).unwrap_or("minimax:hard").parse::<Bot>();
+
+    let (x, o) = match (bot("--x"), bot("--o")) {
+        (Ok(x), Ok(o)) => (x, o),
+        (Err(e), _) | (_, Err(e)) => {
+            println!("{}", e);
+            println!("Bots: minimax:easy, minimax:medium, minimax:hard, rules, perfect");
+            return Ok(());
+        }
+    };
+
+    println!("Playing {} games of {} (X) against {} (O)...", games, x, o);
+    let report = simulate(&GameState::new(), x, o, games)?;
+    println!("{}", report);
+
+    Ok(())
+}
+
+/// Measure how fast the AI searches at each difficulty and board size, and print a table
+///
+/// Usage: `tictactoe bench [--difficulties easy,medium,hard] [--sizes 3,4,5] [--win-length <k>] [--games <n>] [--json]`
+///
+/// Each difficulty plays itself `--games` times (1 by default) on each board
+/// size, needing `--win-length` in a row to win (3 by default). `--json`
+/// prints the results as JSON instead, to compare runs by script.
+fn run_benchmarks(args: &[String]) -> GameResult<()> {
+    let list = |flag| flag_value(args, flag).map(|value| value.split(',').map(str::trim).collect::<Vec<_>>());
+    let difficulties = match list("--difficulties") {
+        Some(names) => names.into_iter().map(str::parse).collect::<GameResult<Vec<Difficulty>>>()?,
+        None => vec![Difficulty::Easy, Difficulty::Medium, Difficulty::Hard],
+    };
+    let sizes = match list("--sizes") {
+        Some(sizes) => sizes
+            .into_iter()
+            .map(|size| size.parse().map_err(|_| GameError::InvalidConfig(format!("invalid board size {}", size))))
+            .collect::<GameResult<Vec<usize>>>()?,
+        None => bench::DEFAULT_SIZES.to_vec(),
+    };
+    let number = |flag, default| flag_value(args, flag).and_then(|value| value.parse().ok()).unwrap_or(default);
+    let (win_length, games) = (number("--win-length", bench::DEFAULT_WIN_LENGTH), number("--games", 1));
+
+    let report = bench::run_all(&difficulties, &sizes, win_length, games)?;
+    if args.iter().any(|arg| arg == "--json") {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!("{}", report);
+    }
+
+    Ok(())
+}
+
+/// Solve a puzzle: a position where the player to move can force a win
+///
+/// Usage: `tictactoe puzzle [<dir>] [--daily] [--moves <n>] [--seed <n>]`
+///
+/// Puzzles are found in the history files in `<dir>`, or by playing random
+/// moves on a standard board. `--daily` gives the day's puzzle, the same one
+/// the server gives at `/puzzles/daily`. The engine defends, and any move
+/// that keeps a forced win in time is accepted.
+fn solve_puzzle(args: &[String]) -> GameResult<()> {
+    let usage = "Usage: tictactoe puzzle [<dir>] [--daily] [--moves <n>] [--seed <n>]";
+    let Some(moves) = flag_value(args, "--moves").map_or(Some(DEFAULT_PUZZLE_MOVES), |moves| moves.parse().ok()) else {
+        println!("{}", usage);
+        return Ok(());
+    };
+    let style = CliConfig::load(args)?.board;
+    let mut rng = match flag_value(args, "--seed").and_then(|seed| seed.parse().ok()) {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+
+    let puzzle = if args.iter().any(|arg| arg == "--daily") {
+        Some(Puzzle::daily(Utc::now().date_naive())?)
+    } else if let Some(dir) = args.get(2).filter(|arg| !arg.starts_with("--")) {
+        let mut puzzles = Vec::new();
+        for history in load_histories(dir)? {
+            puzzles.extend(find_in_history(&history, moves)?);
+        }
+        println!("Found {} puzzles in {}", puzzles.len(), dir);
+        puzzles.choose(&mut rng).cloned()
+    } else {
+        Puzzle::random(&GameState::new(), moves, &mut rng)
+    };
+    let Some(puzzle) = puzzle else {
+        println!("No puzzle found");
+        return Ok(());
+    };
+
+    println!("{}", puzzle);
+    let mut game = puzzle.game();
+    let mut played = Vec::new();
+    loop {
+        print_board(&game, &style);
+        print!("Your move (row col, or q to give up): ");
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        let read = io::stdin().read_line(&mut input)?;
+        if read == 0 || input.trim() == "q" {
+            print_solutions(&puzzle);
+            return Ok(());
+        }
+        let position = match input.parse::<Position>() {
+            Ok(position) => position,
+            Err(e) => {
+                println!("{}", e);
+                continue;
+            }
+        };
+
+        played.push(position);
+        let attempt = match puzzle.attempt(&played) {
+            Ok(attempt) => attempt,
+            Err(e) => {
+                println!("{}", e);
+                played.pop();
+                continue;
+            }
+        };
+        if !attempt.correct {
+            println!("{} lets the win slip away.", position);
+            print_solutions(&puzzle);
+            return Ok(());
+        }
+
+        game.make_move(position)?;
+        if attempt.solved {
+            print_board(&game, &style);
+            println!("Solved!");
+            return Ok(());
+        }
+        if let Some(&reply) = attempt.replies.last() {
+            println!("{} replies {}", game.current_turn, reply);
+            game.make_move(reply)?;
+        }
+    }
+}
+
+/// Walk a new player through the rules, winning, blocking and forks
+///
+/// Usage: `tictactoe tutorial`
+///
+/// Each lesson shows a position and only moves meeting its goal are
+/// accepted; a wrong move gets a hint and another try.
+fn run_tutorial(style: &BoardRenderer) -> GameResult<()> {
+    println!("Welcome to the Tic-Tac-Toe tutorial!");
+    println!("Type a move as a cell name like b2, or as 'row col' counting from 0. Type q to stop.");
+
+    for (number, lesson) in LESSONS.iter().enumerate() {
+        println!("\nLesson {} of {}: {}", number + 1, LESSONS.len(), lesson.title);
+        println!("==========================");
+        println!("{}\n", lesson.text);
+
+        let game = lesson.game()?;
+        loop {
+            print!("{}", game.render(style));
+            print!("{} to move: ", lesson.player);
+            io::stdout().flush()?;
+
+            let mut input = String::new();
+            let read = io::stdin().read_line(&mut input)?;
+            if read == 0 || input.trim() == "q" {
+                println!("Come back any time to carry on.");
+                return Ok(());
+            }
+            let position = match input.parse::<Position>() {
+                Ok(position) => position,
+                Err(e) => {
+                    println!("{}", e);
+                    continue;
+                }
+            };
+
+            if lesson.accepts(position)? {
+                let mut after = game.clone();
+                after.make_move(position)?;
+                print!("{}", after.render(style));
+                println!("Well done!");
+                break;
+            }
+            match game.clone().make_move(position) {
+                Ok(()) => println!("Not quite. {}", lesson.hint),
+                Err(e) => println!("{}", e),
+            }
+        }
+    }
+
+    println!("\nThat's the tutorial finished. Start a game with `tictactoe` and try it out!");
+    Ok(())
+}
+
+/// Print the first moves that solve a puzzle
+fn print_solutions(puzzle: &Puzzle) {
+    let solutions: Vec<String> = puzzle.solutions().iter().map(Position::to_string).collect();
+    println!("Winning moves: {}", solutions.join(", "));
+}
+
+/// Play a tournament between bots and print the ranked results
+///
+/// Usage: `tictactoe tournament --bots rules,minimax:hard,minimax:easy --games 10 [--swiss 3] [--json | --csv]`
+///
+/// Besides the built-in bots, `qlearning:<table.json>` enters a Q-learning AI
+/// playing by a table saved by `tictactoe train`.
+fn run_tournament(args: &[String]) -> GameResult<()> {
+    let games = flag_value(args, "--games").and_then(|games| games.parse().ok()).unwrap_or(10);
+    let bots = flag_value(args, "--bots").unwrap_or("rules,minimax:hard,minimax:medium,minimax:easy");
+
+    let mut tournament = Tournament::new(GameState::new()).games_per_pairing(games);
+    for name in bots.split(',') {
+        if let Some(path) = name.trim().strip_prefix("qlearning:") {
+            let table = Arc::new(QTable::load_from_file(path)?);
+            tournament.register(name.trim(), move |player| Box::new(QLearningAI::new(player, Arc::clone(&table))));
+            continue;
+        }
+        match name.trim().parse::<Bot>() {
+            Ok(bot) => tournament.register_bot(bot),
+            Err(e) => {
+                println!("{}", e);
+                println!("Bots: minimax:easy, minimax:medium, minimax:hard, rules, perfect");
+                return Ok(());
+            }
+        };
+    }
+
+    let results = match flag_value(args, "--swiss").and_then(|rounds| rounds.parse().ok()) {
+        Some(rounds) => tournament.swiss(rounds)?,
+        None => tournament.round_robin()?,
+    };
+
+    if args.iter().any(|arg| arg == "--json") {
+        println!("{}", results.to_json()?);
+    } else if args.iter().any(|arg| arg == "--csv") {
+        println!("{}", results.standings_csv());
+        print!("{}", results.pairings_csv());
+    } else {
+        print!("{}", results);
+    }
+
+    Ok(())
+}
+
+/// Tune the minimax AI's evaluation weights by self-play and save the best
+///
+/// Usage: `tictactoe tune --out weights.toml --size 4 --win-length 3 --generations 5 --population 8 --games 2`
+fn tune(out: &str, args: &[String]) -> GameResult<()> {
+    let number = |flag, default| flag_value(args, flag).and_then(|value| value.parse().ok()).unwrap_or(default);
+    let start = GameState::with_size(number("--size", 4), number("--win-length", 3))?;
+    let tuner = Tuner::new(start)
+        .generations(number("--generations", 5))
+        .population(number("--population", 8))
+        .games_per_pairing(number("--games", 2));
+
+    println!("Tuning weights...");
+    let started = Instant::now();
+    let result = tuner.run()?;
+    for (generation, best) in result.generations.iter().enumerate() {
+        println!("Generation {}: {:?}", generation + 1, best);
+    }
+    result.best.save_to_file(out)?;
+    println!("Saved the best weights to {} in {:.1?}", out, started.elapsed());
+
+    Ok(())
+}
+
+/// Train a Q-learning AI by self-play and save its table
+///
+/// Usage: `tictactoe train --out qtable.json --games 50000 [--from qtable.json]`
+///
+/// Training continues from the table in `--from` if one is given.
+fn train(out: &str, args: &[String]) -> GameResult<()> {
+    let games = flag_value(args, "--games").and_then(|games| games.parse().ok()).unwrap_or(50_000);
+    let mut table = match flag_value(args, "--from") {
+        Some(from) => QTable::load_from_file(from)?,
+        None => QTable::new(),
+    };
+
+    println!("Training on {} games of self-play...", games);
+    let started = Instant::now();
+    table.train(games);
+    table.save_to_file(out)?;
+    println!(
+        "Saved {} positions learned from {} games to {} in {:.1?}",
+        table.positions(),
+        table.games(),
+        out,
+        started.elapsed()
+    );
+
+    Ok(())
+}
+
+/// Save a saved game or history again in the format `to`'s name calls for
+///
+/// Usage: `tictactoe convert history_<id>.json history_<id>.msgpack.gz`
+fn convert(from: &str, to: &str) -> GameResult<()> {
+    if SaveFormat::is_bundle(from) {
+        save::save_to_file(&save::load_from_file::<SaveBundle>(from)?, to)?;
+        println!("Saved {} as {}", from, to);
+        return Ok(());
+    }
+
+    // A history has moves, which a game state doesn't, so try it first
+    match GameHistory::load_from_file(from) {
+        Ok(history) => history.save_to_file(to)?,
+        Err(_) => GameState::load_from_file(from)?.save_to_file(to)?,
+    }
+    println!("Saved {} as {}", from, to);
+    Ok(())
+}
+
+/// Analyze a saved game history and print a report of every move
+fn analyze(filename: &str) -> GameResult<()> {
+    let history = GameHistory::load_from_file(filename)?;
+    let analysis = analyze_history(&history)?;
+
+    println!("Analysis of game {}", analysis.game_id);
+    println!("==========================");
+
+    for analysis in &analysis.moves {
+        let (row, col) = analysis.played;
+        print!("{}. {} plays ({}, {}): {:?}", analysis.ply + 1, analysis.player, row, col, analysis.quality);
+        if analysis.quality != MoveQuality::Best {
+            let (best_row, best_col) = analysis.best_move;
+            print!(" - ({}, {}) was better", best_row, best_col);
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+/// A command for stepping through a replay
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ReplayCommand {
+    /// Step forward a move
+    Next,
+    /// Step back a move
+    Previous,
+    /// Jump to the position after the given number of moves
+    Go(usize),
+    /// Stop the replay
+    Quit,
+}
+
+/// Get the next replay command from the user, or `None` if it isn't recognised
+fn get_replay_command() -> GameResult<Option<ReplayCommand>> {
+    print!("[n]ext, [p]revious, [g]o <ply>, [q]uit: ");
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    let read = io::stdin().read_line(&mut input)?;
+
+    // Stop at the end of the input
+    if read == 0 {
+        return Ok(Some(ReplayCommand::Quit));
+    }
+
+    let mut words = input.split_whitespace();
+    let command = match (words.next(), words.next()) {
+        (None | Some("n"), None) => Some(ReplayCommand::Next),
+        (Some("p"), None) => Some(ReplayCommand::Previous),
+        (Some("g"), Some(ply)) => ply.parse().ok().map(ReplayCommand::Go),
+        (Some("q"), None) => Some(ReplayCommand::Quit),
+        _ => None,
+    };
+    Ok(command)
+}
+
+/// Step through a saved game history, showing the board after each move
+///
+/// Usage: `tictactoe replay history_<id>.json [--eval]`
+fn replay(filename: &str, eval: bool, style: &BoardRenderer) -> GameResult<()> {
+    let history = GameHistory::load_from_file(filename)?;
+    let positions = history.replay().collect::<GameResult<Vec<_>>>()?;
+    let analysis = if eval { Some(analyze_history(&history)?) } else { None };
+
+    println!("Replay of game {}", history.game_id);
+    println!("Started at {}", history.started_at.format("%Y-%m-%d %H:%M:%S UTC"));
+    println!("==========================");
+
+    let last = positions.len() - 1;
+    let mut ply = 0;
+    loop {
+        let game = &positions[ply];
+        println!("\nPosition after {} of {} moves", ply, last);
+        if let Some(game_move) = ply.checked_sub(1).map(|index| &history.moves[index]) {
+            let at = game_move.timestamp.format("%H:%M:%S%.3f");
+            print!("{}. {} plays {} at {}", ply, game_move.player, game_move.position, at);
+            if let Some(timing) = game.move_timings.last() {
+                print!(" after {:.1}s", timing.duration_ms as f64 / 1000.0);
+            }
+            if let Some(tag) = game_move.tag {
+                print!(" {}", tag.symbol());
+            }
+            println!();
+            for comment in &game_move.comments {
+                println!("  {}", comment);
+            }
+
+            if let Some(analysis) = analysis.as_ref().map(|analysis| &analysis.moves[ply - 1]) {
+                let (best_row, best_col) = analysis.best_move;
+                println!(
+                    "Evaluation: {} ({:?}); best was ({}, {}) with {}",
+                    analysis.score, analysis.quality, best_row, best_col, analysis.best_score
+                );
+            }
+        }
+        print_board(game, style);
+        if ply == last {
+            if let Some(status) = history.final_status {
+                println!("Final result: {}", status);
+            }
+        }
+
+        ply = match get_replay_command()? {
+            Some(ReplayCommand::Next) if ply < last => ply + 1,
+            Some(ReplayCommand::Previous) if ply > 0 => ply - 1,
+            Some(ReplayCommand::Go(target)) if target <= last => target,
+            Some(ReplayCommand::Quit) => return Ok(()),
+            Some(ReplayCommand::Next) => {
+                println!("That was the last move.");
+                ply
+            }
+            Some(ReplayCommand::Previous) => {
+                println!("That is the start of the game.");
+                ply
+            }
+            Some(ReplayCommand::Go(_)) => {
+                println!("The game has {} moves.", last);
+                ply
+            }
+            None => {
+                println!("Unknown command.");
+                ply
+            }
+        };
+    }
+}
+
+/// Play an interactive game, in the terminal UI unless `plain` is set
+fn play(plain: bool, config: &CliConfig, args: &[String]) -> GameResult<()> {
+    println!("{}", tr(Text::Welcome, &[]));
     println!("==========================");
 
-    // Check if we should load a saved game
-    let mut game = if let Some(filename) = get_load_game_option()? {
-        load_game(&filename)?
+    let saves = &config.save_slots();
+    // Check if we should load a saved game, or create a new one and record its moves
+    let mut game = if let Some(id) = flag_value(args, "--resume") {
+        let id = Uuid::parse_str(id).map_err(|_| GameError::GameNotFound(id.to_string()))?;
+        let (game, players) = saves.load_id(id)?;
+        print_players(&players);
+        println!("{}", tr(Text::GameLoaded, &[]));
+        game
+    } else if let Some(game) = choose_saved_game(saves)? {
+        game
     } else {
         // Create a new game
-        let game = GameState::new();
-        println!("Game created with ID: {}", game.id);
-        println!("Player X goes first\n");
-        game
+        let game = match get_variant()? {
+            Variant::ConnectFour => GameState::connect_four(),
+            Variant::Gomoku => GameState::gomoku(),
+            variant => {
+                let (size, win_length) = get_board_size()?;
+                let mut game = GameState::with_size(size, win_length)?;
+                game.variant = variant;
+                game
+            }
+        };
+        println!("{}", tr(Text::GameCreated, &[&game.id]));
+        println!("{}\n", tr(Text::XGoesFirst, &[]));
+        Game::new(game)?
     };
 
-    // Create a history object
-    let mut history = game.create_history();
-
     // Set up players
-    let game_mode = get_game_mode()?;
-    let (player1, player2) = create_players(game_mode)?;
+    let (player1, player2) = choose_players(args)?;
+
+    println!("\n{}", tr(Text::PlayerNumber, &[&1, &player1.get_name()]));
+    println!("{}\n", tr(Text::PlayerNumber, &[&2, &player2.get_name()]));
+
+    if plain {
+        play_plain(&mut game, player1.as_ref(), player2.as_ref(), saves, &config.board)?;
+    } else {
+        tui::run(&mut game, player1.as_ref(), player2.as_ref(), saves)?;
+    }
+    archive_game(&game, player1.as_ref(), player2.as_ref(), config)?;
+    offer_review(&game, player1.as_ref(), player2.as_ref(), &config.board)?;
+
+    println!("{}", tr(Text::ThanksForPlaying, &[]));
+
+    Ok(())
+}
+
+/// Play a best-of-`best_of` match, in the terminal UI unless `plain` is set
+///
+/// Usage: `tictactoe match --best-of 5 [--plain] [--x <player>] [--o <player>]`
+fn play_match(best_of: usize, plain: bool, config: &CliConfig, args: &[String]) -> GameResult<()> {
+    println!("{}", tr(Text::Welcome, &[]));
+    println!("==========================");
+
+    let saves = &config.save_slots();
+    let first_game = match get_variant()? {
+        Variant::ConnectFour => GameState::connect_four(),
+        Variant::Gomoku => GameState::gomoku(),
+        variant => {
+            let (size, win_length) = get_board_size()?;
+            let mut game = GameState::with_size(size, win_length)?;
+            game.variant = variant;
+            game
+        }
+    };
+    let mut matchup = Match::new(first_game, best_of)?;
+
+    let (player1, player2) = choose_players(args)?;
+
+    println!("\n{}", tr(Text::PlayerNumberSide, &[&1, &"X", &player1.get_name()]));
+    println!("{}", tr(Text::PlayerNumberSide, &[&2, &"O", &player2.get_name()]));
+    println!("{}\n", tr(Text::BestOf, &[&best_of]));
+
+    loop {
+        let number = matchup.games.len();
+        let mut game = Game::new(matchup.current_game().clone())?;
+        println!("{}\n", tr(Text::MatchGameStarts, &[&number, &game.current_turn]));
+
+        if plain {
+            play_plain(&mut game, player1.as_ref(), player2.as_ref(), saves, &config.board)?;
+        } else {
+            tui::run(&mut game, player1.as_ref(), player2.as_ref(), saves)?;
+        }
+
+        // Quitting the terminal UI leaves the game unfinished, which ends the match early
+        if !game.is_over() {
+            println!("{}", tr(Text::MatchAbandoned, &[&matchup.score]));
+            return Ok(());
+        }
+        archive_game(&game, player1.as_ref(), player2.as_ref(), config)?;
+        offer_review(&game, player1.as_ref(), player2.as_ref(), &config.board)?;
+
+        *matchup.current_game_mut()? = game.state().clone();
+        matchup.advance();
+        println!("{}\n", tr(Text::AfterGame, &[&number, &matchup.score]));
+
+        if matchup.is_over() {
+            break;
+        }
+    }
 
-    println!("\nPlayer 1: {}", player1.get_name());
-    println!("Player 2: {}\n", player2.get_name());
+    match matchup.status {
+        MatchStatus::Won(Player::X) => println!("{}", tr(Text::WinsMatch, &[&player1.get_name()])),
+        MatchStatus::Won(Player::O) => println!("{}", tr(Text::WinsMatch, &[&player2.get_name()])),
+        _ => println!("{}", tr(Text::MatchDrawn, &[])),
+    }
+    println!("{}", tr(Text::ThanksForPlaying, &[]));
+
+    Ok(())
+}
+
+/// How a game played from a list of moves stands, printed as JSON by [`play_moves`]
+#[derive(Debug, Serialize)]
+struct MovesOutcome {
+    /// The board, one string per row from the top: `X`, `O`, or `.` for an empty cell
+    board: Vec<String>,
+    /// The status of the game after the moves
+    status: GameStatus,
+    /// The winner, if the game has been won
+    winner: Option<Player>,
+    /// The player to move next
+    current_turn: Player,
+    /// The number of moves played
+    moves: usize,
+    /// Why the move that stopped the game early couldn't be played, if one couldn't
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Play a game from a list of moves without prompting, and print how it ends as JSON
+///
+/// Usage: `tictactoe play --moves "0,0 1,1 0,1" [--size <n>] [--win-length <k>]`
+///
+/// Moves are separated by whitespace and written as `row,col` or in algebraic
+/// notation. With `--moves -`, or no list, they are read from stdin. Playing
+/// stops at the first move that can't be made, whose error is included in the
+/// JSON and returned, so the exit status shows whether every move was legal.
+fn play_moves(args: &[String]) -> GameResult<()> {
+    let moves = match flag_value(args, "--moves") {
+        Some(moves) if moves != "-" && !moves.starts_with("--") => moves.to_string(),
+        _ => io::read_to_string(io::stdin())?,
+    };
+    let number = |flag, default| flag_value(args, flag).and_then(|value| value.parse().ok()).unwrap_or(default);
+    let mut game = GameState::with_size(number("--size", DEFAULT_SIZE), number("--win-length", DEFAULT_WIN_LENGTH))?;
+
+    let result = moves
+        .split_whitespace()
+        .try_for_each(|cell| game.make_move(cell.parse::<Position>()?));
+
+    let outcome = MovesOutcome {
+        board: game.board.iter().map(|row| row.iter().map(Cell::to_string).collect()).collect(),
+        status: game.status,
+        winner: game.status.winner(),
+        current_turn: game.current_turn,
+        moves: game.move_count,
+        error: result.as_ref().err().map(GameError::to_string),
+    };
+    println!("{}", serde_json::to_string_pretty(&outcome)?);
+    result
+}
+
+/// Play against another client through the game server at `server`
+///
+/// Creates a new game for the opponent to join unless `join` names a game to join.
+#[cfg(feature = "server")]
+fn play_remote(server: &str, join: Option<&str>, style: &BoardRenderer) -> GameResult<()> {
+    println!("{}", tr(Text::Welcome, &[]));
+    println!("==========================");
+
+    let connection = match join {
+        Some(id) => {
+            let id = Uuid::parse_str(id).map_err(|_| GameError::GameNotFound(id.to_string()))?;
+            RemoteGame::join(server, id)?
+        }
+        None => {
+            let connection = RemoteGame::create(server)?;
+            println!("{}", tr(Text::GameCreated, &[&connection.game_id()]));
+            let command = format!("tictactoe play --server {} --join {}", server, connection.game_id());
+            println!("{}", tr(Text::ShareGame, &[&command]));
+            connection
+        }
+    };
 
+    let local = HumanPlayer::new(connection.player(), "You".to_string());
+    let remote = RemotePlayer::new(connection.clone());
+    println!("\n{}\n", tr(Text::YouArePlaying, &[&connection.player()]));
+
+    // The server holds the game, so fetch it afresh on every turn
+    let game = loop {
+        let game = connection.game()?;
+        print_board(&game, style);
+        if game.is_over() {
+            break game;
+        }
+
+        if game.draw_offer == Some(remote.get_player_type()) {
+            let result = if local.respond_to_draw_offer(&game)? {
+                connection.accept_draw()
+            } else {
+                connection.decline_draw()
+            };
+            if let Err(e) = result {
+                println!("{}\n", tr(Text::ErrorMessage, &[&e.localized()]));
+            }
+            continue;
+        }
+
+        if game.current_turn != connection.player() {
+            // The opponent's action is already on the server once this returns
+            match remote.get_action(&game) {
+                Ok(PlayerAction::OfferDraw) => println!("{}", tr(Text::OffersDraw, &[&remote.get_name()])),
+                Ok(_) | Err(GameError::GameAlreadyFinished) => {}
+                Err(e) => return Err(e),
+            }
+            continue;
+        }
+
+        let result = match local.get_action(&game)? {
+            PlayerAction::Move(position) => connection.make_move(position),
+            PlayerAction::Resign => connection.resign(),
+            PlayerAction::OfferDraw => connection.offer_draw().and_then(|game| {
+                if !remote.respond_to_draw_offer(&game)? {
+                    println!("{}\n", tr(Text::DeclinedDraw, &[&remote.get_name()]));
+                }
+                Ok(game)
+            }),
+        };
+        if let Err(e) = result {
+            println!("{}\n{}\n", tr(Text::ErrorMessage, &[&e.localized()]), tr(Text::TryAgain, &[]));
+        }
+    };
+
+    match game.status {
+        GameStatus::Draw => println!("{}", tr(Text::Draw, &[])),
+        status => match status.winner() {
+            Some(player) if player == connection.player() => println!("{}", tr(Text::YouWin, &[])),
+            _ => println!("{}", tr(Text::YouLose, &[])),
+        },
+    }
+    println!("{}", tr(Text::ThanksForPlaying, &[]));
+
+    Ok(())
+}
+
+/// Play a game with line-based prompts, suitable for scripting
+fn play_plain(
+    game: &mut Game,
+    player1: &dyn GamePlayer,
+    player2: &dyn GamePlayer,
+    saves: &SaveSlots,
+    style: &BoardRenderer,
+) -> GameResult<()> {
     // Main game loop
     loop {
+        // Keep the game to save if the player gives up on it with Ctrl-C
+        *UNFINISHED_GAME.lock().unwrap_or_else(PoisonError::into_inner) = Some(UnfinishedGame {
+            game: game.clone(),
+            players: player_infos(player1, player2),
+            saves: saves.clone(),
+        });
+
         // Display the current board
-        game.print_board();
+        print_board(game, style);
 
         // Check if the game is over
         match game.status {
-            GameStatus::Won(player) => {
-                println!("Player {:?} wins!", player);
-                history.finish(game.status);
-                break;
+            GameStatus::InProgress => {
+                println!("{}", tr(Text::PlayerTurn, &[&game.current_turn]));
             }
-            GameStatus::Draw => {
-                println!("It's a draw!");
-                history.finish(game.status);
+            status => {
+                println!("{}", describe_result(status));
                 break;
             }
-            GameStatus::InProgress => {
-                println!("Player {:?}'s turn", game.current_turn);
-            }
         }
 
-        // Get the current player
-        let current_player = if game.current_turn == Player::X {
-            &player1
+        // Get the current player and their opponent
+        let (current_player, opponent) = if game.current_turn == Player::X {
+            (player1, player2)
         } else {
-            &player2
+            (player2, player1)
         };
 
-        println!("{}'s turn", current_player.get_name());
+        println!("{}", tr(Text::NamedTurn, &[&current_player.get_name()]));
+
+        // Get the player's action, timing the AI's thinking
+        let started = Instant::now();
+        let action = current_player.get_action(game)?;
+        match (&action, current_player.search_stats()) {
+            (PlayerAction::Move(position), Some(stats)) => {
+                println!("{}", tr(Text::ChoseMove, &[&current_player.get_name(), position, &stats]));
+            }
+            _ if !current_player.is_human() => {
+                let elapsed = format!("{:.2?}", started.elapsed());
+                println!("{}", tr(Text::DecidedIn, &[&current_player.get_name(), &elapsed]));
+            }
+            _ => {}
+        }
 
-        // Get the player's move
-        let (row, col) = current_player.get_move(&game)?;
+        let position = match action {
+            PlayerAction::Move(position) => position,
+            PlayerAction::Resign => {
+                game.resign(game.current_turn)?;
+                continue;
+            }
+            PlayerAction::OfferDraw => {
+                game.offer_draw(game.current_turn)?;
+                if opponent.respond_to_draw_offer(game)? {
+                    game.accept_draw(game.current_turn.opponent())?;
+                } else {
+                    game.decline_draw(game.current_turn.opponent())?;
+                    println!("{}\n", tr(Text::DeclinedDraw, &[&opponent.get_name()]));
+                }
+                continue;
+            }
+        };
 
         // Make the move
-        match game.make_move(row, col) {
+        match game.make_move_as(current_player.get_player_type(), position) {
             Ok(()) => {
-                // Record the move in history
-                history.add_move(game.current_turn.opponent(), row, col);
-                println!("Move successful!\n");
+                println!("{}\n", tr(Text::MoveSuccessful, &[]));
 
                 // Save the game after each move
-                save_game_option(&game, &history)?;
+                save_game_option(game, player1, player2, saves)?;
             }
             Err(e) => {
-                println!("Error: {}\nPlease try again.\n", e);
+                println!("{}\n{}\n", tr(Text::ErrorMessage, &[&e.localized()]), tr(Text::TryAgain, &[]));
                 continue;
             }
         }
     }
 
+    UNFINISHED_GAME.lock().unwrap_or_else(PoisonError::into_inner).take();
+
     // Final board state
-    println!("\nFinal board state:");
-    game.print_board();
+    println!("\n{}", tr(Text::FinalBoard, &[]));
+    print_board(game, style);
 
     // Save the final game state and history
-    save_game_option(&game, &history)?;
+    save_game_option(game, player1, player2, saves)
 
-    println!("Thanks for playing!");
+}
 
-    Ok(())
+/// Describes how a finished game ended, like "Player X wins!"
+fn describe_result(status: GameStatus) -> String {
+    match status {
+        GameStatus::InProgress => String::new(),
+        GameStatus::Won(winner) => tr(Text::PlayerWins, &[&winner]),
+        GameStatus::Draw => tr(Text::Draw, &[]),
+        GameStatus::Resigned(loser) => tr(Text::PlayerResigns, &[&loser, &loser.opponent()]),
+        GameStatus::TimedOut(loser) => tr(Text::PlayerTimedOut, &[&loser, &loser.opponent()]),
+    }
+}
+
+/// Prints the board in `style` under a heading
+fn print_board(game: &GameState, style: &BoardRenderer) {
+    println!("{}", tr(Text::CurrentBoard, &[]));
+    print!("{}", game.render(style));
+}
+
+/// Get the variant of the game to play from the user
+fn get_variant() -> GameResult<Variant> {
+    println!("{}", tr(Text::SelectVariant, &[]));
+    println!("1. {}", tr(Text::VariantStandard, &[]));
+    println!("2. {}", tr(Text::VariantConnectFour, &[]));
+    println!("3. {}", tr(Text::VariantGomoku, &[]));
+    println!("4. {}", tr(Text::VariantMisere, &[]));
+
+    print!("{}", tr(Text::ChoiceDefaultFirst, &[]));
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+
+    match input.trim() {
+        "2" => Ok(Variant::ConnectFour),
+        "3" => Ok(Variant::Gomoku),
+        "4" => Ok(Variant::Misere),
+        _ => Ok(Variant::Standard),
+    }
+}
+
+/// Get the board size and win length from the user
+fn get_board_size() -> GameResult<(usize, usize)> {
+    print!("{}", tr(Text::BoardSizePrompt, &[&DEFAULT_SIZE]));
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+
+    let size = match input.trim() {
+        "" => return Ok((DEFAULT_SIZE, DEFAULT_WIN_LENGTH)),
+        value => match value.parse::<usize>() {
+            Ok(size) if size > 0 => size,
+            _ => {
+                println!("{}", tr(Text::InvalidSize, &[&DEFAULT_SIZE]));
+                return Ok((DEFAULT_SIZE, DEFAULT_WIN_LENGTH));
+            }
+        },
+    };
+
+    print!("{}", tr(Text::WinLengthPrompt, &[&size]));
+    io::stdout().flush()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+
+    let win_length = match input.trim().parse::<usize>() {
+        Ok(win_length) if win_length > 0 && win_length <= size => win_length,
+        _ => size,
+    };
+
+    Ok((size, win_length))
 }
 
 /// Game modes for the tic-tac-toe game
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(clippy::enum_variant_names)]
 enum GameMode {
     /// Human vs Human
     HumanVsHuman,
@@ -106,33 +1347,33 @@ enum GameMode {
     HumanVsAIMedium,
     /// Human vs AI (Hard)
     HumanVsAIHard,
+    /// Human vs the rule-based AI
+    HumanVsAIRules,
 }
 
 /// Get the game mode from the user
 fn get_game_mode() -> GameResult<GameMode> {
-    println!("Select game mode:");
-    println!("1. Human vs Human");
-    println!("2. Human vs AI (Easy)");
-    println!("3. Human vs AI (Medium)");
-    println!("4. Human vs AI (Hard)");
+    println!("{}", tr(Text::SelectGameMode, &[]));
+    println!("1. {}", tr(Text::ModeHumanVsHuman, &[]));
+    println!("2. {}", tr(Text::ModeEasy, &[]));
+    println!("3. {}", tr(Text::ModeMedium, &[]));
+    println!("4. {}", tr(Text::ModeHard, &[]));
+    println!("5. {}", tr(Text::ModeRules, &[]));
 
-    print!("Enter your choice (1-4): ");
-    io::stdout().flush().map_err(|e| {
-        GameError::IoError(e.to_string())
-    })?;
+    print!("{}", tr(Text::ChoiceOneToFive, &[]));
+    io::stdout().flush()?;
 
     let mut input = String::new();
-    io::stdin().read_line(&mut input).map_err(|e| {
-        GameError::IoError(e.to_string())
-    })?;
+    io::stdin().read_line(&mut input)?;
 
     match input.trim() {
         "1" => Ok(GameMode::HumanVsHuman),
         "2" => Ok(GameMode::HumanVsAIEasy),
         "3" => Ok(GameMode::HumanVsAIMedium),
         "4" => Ok(GameMode::HumanVsAIHard),
+        "5" => Ok(GameMode::HumanVsAIRules),
         _ => {
-            println!("Invalid choice. Defaulting to Human vs Human.");
+            println!("{}", tr(Text::InvalidModeChoice, &[]));
             Ok(GameMode::HumanVsHuman)
         }
     }
@@ -140,82 +1381,179 @@ fn get_game_mode() -> GameResult<GameMode> {
 
 /// Create players based on the selected game mode
 fn create_players(mode: GameMode) -> GameResult<(Box<dyn GamePlayer>, Box<dyn GamePlayer>)> {
+    let (x, o) = match mode {
+        GameMode::HumanVsHuman => ("human:Player 1", "human:Player 2"),
+        GameMode::HumanVsAIEasy => ("human:Player", "ai:easy"),
+        GameMode::HumanVsAIMedium => ("human:Player", "ai:medium"),
+        GameMode::HumanVsAIHard => ("human:Player", "ai:hard"),
+        GameMode::HumanVsAIRules => ("human:Player", "ai:rules"),
+    };
+    Ok((player::from_spec(x, Player::X)?, player::from_spec(o, Player::O)?))
+}
 
-    match mode {
-        GameMode::HumanVsHuman => {
-            let player1 = Box::new(HumanPlayer::new(Player::X, "Player 1".to_string()));
-            let player2 = Box::new(HumanPlayer::new(Player::O, "Player 2".to_string()));
-            Ok((player1, player2))
-        },
-        GameMode::HumanVsAIEasy => {
-            let player1 = Box::new(HumanPlayer::new(Player::X, "Player".to_string()));
-            let player2 = Box::new(MinimaxAI::new(Player::O, Difficulty::Easy));
-            Ok((player1, player2))
-        },
-        GameMode::HumanVsAIMedium => {
-            let player1 = Box::new(HumanPlayer::new(Player::X, "Player".to_string()));
-            let player2 = Box::new(MinimaxAI::new(Player::O, Difficulty::Medium));
-            Ok((player1, player2))
-        },
-        GameMode::HumanVsAIHard => {
-            let player1 = Box::new(HumanPlayer::new(Player::X, "Player".to_string()));
-            let player2 = Box::new(MinimaxAI::new(Player::O, Difficulty::Hard));
-            Ok((player1, player2))
-        },
+/// Create the players named by `--x` and `--o`, or ask for a game mode if neither is given
+///
+/// A side left out is played by a human.
+fn choose_players(args: &[String]) -> GameResult<(Box<dyn GamePlayer>, Box<dyn GamePlayer>)> {
+    match (flag_value(args, "--x"), flag_value(args, "--o")) {
+        (None, None) => create_players(get_game_mode()?),
+        (x, o) => Ok((
+            player::from_spec(x.unwrap_or("human"), Player::X)?,
+            player::from_spec(o.unwrap_or("human"), Player::O)?,
+        )),
     }
 }
 
-/// Asks the user if they want to load a saved game
-fn get_load_game_option() -> GameResult<Option<String>> {
-    print!("Do you want to load a saved game? (y/n): ");
-    io::stdout().flush().map_err(|e| {
-        GameError::IoError(e.to_string())
-    })?;
+/// Lists the saved games and lets the user load or delete one
+///
+/// Returns `None` when the user would rather start a new game.
+fn choose_saved_game(saves: &SaveSlots) -> GameResult<Option<Game>> {
+    loop {
+        let slots = saves.list()?;
+        if slots.is_empty() {
+            return Ok(None);
+        }
+
+        println!("{}", tr(Text::SavedGamesIn, &[&saves.dir().display()]));
+        for (number, slot) in slots.iter().enumerate() {
+            println!("{}. {}", number + 1, describe_slot(slot));
+        }
+        print!("{}", tr(Text::SavedGamePrompt, &[]));
+        io::stdout().flush()?;
 
-    let mut input = String::new();
-    io::stdin().read_line(&mut input).map_err(|e| {
-        GameError::IoError(e.to_string())
-    })?;
-
-    if input.trim().to_lowercase() == "y" {
-        print!("Enter the filename to load: ");
-        io::stdout().flush().map_err(|e| {
-            GameError::IoError(e.to_string())
-        })?;
-
-        let mut filename = String::new();
-        io::stdin().read_line(&mut filename).map_err(|e| {
-            GameError::IoError(e.to_string())
-        })?;
-
-        Ok(Some(filename.trim().to_string()))
-    } else {
-        Ok(None)
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+
+        let choice = |number: &str| number.parse::<usize>().ok().and_then(|number| slots.get(number.checked_sub(1)?));
+        match input.split_whitespace().collect::<Vec<_>>().as_slice() {
+            [] => return Ok(None),
+            ["d", number] => match choice(number) {
+                Some(slot) => {
+                    saves.delete(slot)?;
+                    println!("{}\n", tr(Text::DeletedGame, &[&slot.game_id]));
+                }
+                None => println!("{}\n", tr(Text::NoSavedGame, &[number])),
+            },
+            [number] => match choice(number) {
+                Some(slot) => {
+                    let (game, players) = saves.load(slot)?;
+                    print_players(&players);
+                    println!("{}", tr(Text::GameLoaded, &[]));
+                    return Ok(Some(game));
+                }
+                None => println!("{}\n", tr(Text::NoSavedGame, &[number])),
+            },
+            _ => println!("{}\n", tr(Text::InvalidChoice, &[])),
+        }
     }
 }
 
-/// Loads a game from a file
-fn load_game(filename: &str) -> GameResult<GameState> {
-    if !Path::new(filename).exists() {
-        return Err(GameError::IoError(format!("File '{}' not found", filename)));
+/// Prints who played each side of a saved game
+fn print_players(players: &[PlayerInfo]) {
+    for player in players {
+        println!("{}", tr(Text::PlayedBy, &[&player.player, &player.name]));
     }
+}
 
-    println!("Loading game from {}...", filename);
-    let game = GameState::load_from_file(filename)?;
-    println!("Game loaded successfully!");
+/// Describes a saved game for the list of saves
+fn describe_slot(slot: &SaveSlot) -> String {
+    let status = match slot.status {
+        GameStatus::InProgress => tr(Text::SlotInProgress, &[]),
+        GameStatus::Won(winner) => tr(Text::SlotWon, &[&winner]),
+        GameStatus::Draw => tr(Text::SlotDrawn, &[]),
+        GameStatus::Resigned(loser) => tr(Text::SlotResigned, &[&loser]),
+        GameStatus::TimedOut(loser) => tr(Text::SlotTimedOut, &[&loser]),
+    };
+    let saved_at = slot.saved_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M");
+    tr(Text::SlotSummary, &[&slot.matchup(), &status, &slot.moves, &saved_at])
+}
 
-    Ok(game)
+/// Auto-saves the game with its history and players into its slot in the saves directory
+fn save_game_option(game: &Game, player1: &dyn GamePlayer, player2: &dyn GamePlayer, saves: &SaveSlots) -> GameResult<()> {
+    saves.save(game, &player_infos(player1, player2))
 }
 
-/// Asks the user if they want to save the game
-fn save_game_option(game: &GameState, history: &GameHistory) -> GameResult<()> {
-    // Auto-save the game state
-    let game_filename = format!("game_{}.json", game.id);
-    game.save_to_file(&game_filename)?;
+/// Archives a finished game with its players, so that `tictactoe stats` counts it
+#[cfg(feature = "archive")]
+fn archive_game(game: &Game, player1: &dyn GamePlayer, player2: &dyn GamePlayer, config: &CliConfig) -> GameResult<()> {
+    if !game.is_over() {
+        return Ok(());
+    }
+    let archive = config.open_archive()?;
+    block_on(archive.record(game.history(), &player_infos(player1, player2)))?
+}
 
-    // Auto-save the history
-    let history_filename = format!("history_{}.json", game.id);
-    history.save_to_file(&history_filename)?;
+/// Finished games aren't archived in a build without the archive
+#[cfg(not(feature = "archive"))]
+fn archive_game(_game: &Game, _player1: &dyn GamePlayer, _player2: &dyn GamePlayer, _config: &CliConfig) -> GameResult<()> {
+    Ok(())
+}
 
+/// Offers to go over the human players' moves in a finished game with the engine
+fn offer_review(game: &Game, player1: &dyn GamePlayer, player2: &dyn GamePlayer, style: &BoardRenderer) -> GameResult<()> {
+    let humans: Vec<&dyn GamePlayer> = [player1, player2].into_iter().filter(|player| player.is_human()).collect();
+    if !game.is_over() || humans.is_empty() {
+        return Ok(());
+    }
+
+    print!("{}", tr(Text::ReviewPrompt, &[]));
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    if !Locale::current().is_yes(&input) {
+        return Ok(());
+    }
+
+    let history = game.history();
+    let positions = history.replay().collect::<GameResult<Vec<_>>>()?;
+    for human in humans {
+        let review = review_history(history, human.get_player_type())?;
+        print_review(&review, &human.get_name(), &positions, style);
+    }
     Ok(())
+}
+
+/// Prints a coach's review of a player's moves, showing the board before their first losing mistake
+fn print_review(review: &Review, name: &str, positions: &[GameState], style: &BoardRenderer) {
+    println!("\nCoach's review of {}", name);
+    println!("==========================");
+
+    for analysis in &review.moves {
+        print!("{}. {}: {}", analysis.ply + 1, Position::from(analysis.played), describe_score(analysis.score));
+        if analysis.quality != MoveQuality::Best {
+            print!(
+                " ({:?}) - {} was {}",
+                analysis.quality,
+                Position::from(analysis.best_move),
+                describe_score(analysis.best_score)
+            );
+        }
+        println!();
+    }
+
+    let Some(mistake) = &review.mistake else {
+        println!("No losing mistakes. Well played!");
+        return;
+    };
+    println!("\nThe first losing mistake was move {}, {}, from here:", mistake.ply + 1, Position::from(mistake.played));
+    if let Some(game) = positions.get(mistake.ply) {
+        print!("{}", game.render(style));
+    }
+    println!("{} would have held on.", Position::from(mistake.best_move));
+    if !review.refutation.is_empty() {
+        let line: Vec<String> = review.refutation.iter().map(Position::to_string).collect();
+        println!("After it, {} wins with {}", review.player.opponent(), line.join(" "));
+    }
+}
+
+/// Describes who is playing each side, for saves and the archive
+fn player_infos(player1: &dyn GamePlayer, player2: &dyn GamePlayer) -> Vec<PlayerInfo> {
+    [player1, player2]
+        .into_iter()
+        .map(|player| PlayerInfo {
+            player: player.get_player_type(),
+            name: player.get_name(),
+            human: player.is_human(),
+        })
+        .collect()
 }
\ No newline at end of file
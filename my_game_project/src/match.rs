@@ -0,0 +1,248 @@
+//! Best-of-N matches between two players
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::{GameError, GameResult};
+use crate::game::{Cell, GameState};
+use crate::player::Player;
+
+/// How a match stands
+///
+/// Serialized like a [`GameStatus`](crate::game::GameStatus), as
+/// `{"status": "won", "winner": "x"}` or `{"status": "in_progress"}`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", content = "winner", rename_all = "snake_case")]
+pub enum MatchStatus {
+    /// More games are needed to decide the match
+    InProgress,
+    /// The player won more games than the other can catch up with
+    Won(Player),
+    /// Every game was played and both players won as many
+    Drawn,
+}
+
+/// The number of games each player has won in a match
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MatchScore {
+    /// Games won by X
+    pub x: usize,
+    /// Games won by O
+    pub o: usize,
+    /// Games drawn
+    pub draws: usize,
+}
+
+impl MatchScore {
+    /// Games won by `player`
+    pub fn wins(&self, player: Player) -> usize {
+        match player {
+            Player::X => self.x,
+            Player::O => self.o,
+        }
+    }
+
+    /// Total number of finished games
+    pub fn played(&self) -> usize {
+        self.x + self.o + self.draws
+    }
+}
+
+impl fmt::Display for MatchScore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "X {} - {} O", self.x, self.o)?;
+        if self.draws > 0 {
+            write!(f, " ({} drawn)", self.draws)?;
+        }
+        Ok(())
+    }
+}
+
+/// A series of up to `best_of` games between the same two players
+///
+/// Each player keeps their symbol for the whole match, and the player who
+/// moves first alternates from game to game. The match ends as soon as one
+/// player has won more games than the other could catch up with in the games
+/// left to play.
+///
+/// # Examples
+///
+/// ```
+/// use my_game_project::game::GameState;
+/// use my_game_project::player::Player;
+/// use my_game_project::r#match::{Match, MatchStatus};
+///
+/// let mut best_of_three = Match::new(GameState::new(), 3).unwrap();
+/// best_of_three.current_game_mut().unwrap().resign(Player::O).unwrap();
+/// best_of_three.advance();
+///
+/// // O moves first in the second game
+/// assert_eq!(best_of_three.current_game().current_turn, Player::O);
+/// best_of_three.current_game_mut().unwrap().resign(Player::O).unwrap();
+/// best_of_three.advance();
+///
+/// assert_eq!(best_of_three.status, MatchStatus::Won(Player::X));
+/// assert_eq!(best_of_three.games.len(), 2);
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Match {
+    /// Unique identifier for the match
+    pub id: Uuid,
+    /// The most games the match can last
+    pub best_of: usize,
+    /// Every game started so far; the last is in progress unless the match is over
+    pub games: Vec<GameState>,
+    /// The number of games each player has won
+    pub score: MatchScore,
+    /// How the match stands
+    pub status: MatchStatus,
+    /// The player who moves first in odd-numbered games
+    pub first_player: Player,
+}
+
+impl Match {
+    /// Start a match whose games are set up like `first_game`
+    ///
+    /// `first_game` is the first game played, and its player to move starts
+    /// every odd-numbered game.
+    pub fn new(first_game: GameState, best_of: usize) -> GameResult<Self> {
+        if best_of == 0 {
+            return Err(GameError::InvalidMatchLength(best_of));
+        }
+
+        Ok(Self {
+            id: Uuid::new_v4(),
+            best_of,
+            first_player: first_game.current_turn,
+            games: vec![first_game],
+            score: MatchScore::default(),
+            status: MatchStatus::InProgress,
+        })
+    }
+
+    /// The game being played, or the last game played once the match is over
+    pub fn current_game(&self) -> &GameState {
+        self.games.last().expect("a match always has a game")
+    }
+
+    /// The game being played, to make moves in
+    pub fn current_game_mut(&mut self) -> GameResult<&mut GameState> {
+        if self.is_over() {
+            return Err(GameError::MatchAlreadyFinished);
+        }
+        Ok(self.games.last_mut().expect("a match always has a game"))
+    }
+
+    /// Returns true if the match has been decided
+    pub fn is_over(&self) -> bool {
+        self.status != MatchStatus::InProgress
+    }
+
+    /// Count the current game's result if it has finished, starting the next game if needed
+    ///
+    /// Returns true if a new game was started. Does nothing while the current
+    /// game is still being played.
+    pub fn advance(&mut self) -> bool {
+        if self.is_over() || !self.current_game().is_over() {
+            return false;
+        }
+
+        match self.current_game().status.winner() {
+            Some(Player::X) => self.score.x += 1,
+            Some(Player::O) => self.score.o += 1,
+            None => self.score.draws += 1,
+        }
+
+        self.status = self.decide();
+        if self.is_over() {
+            return false;
+        }
+
+        let next = self.next_game();
+        self.games.push(next);
+        true
+    }
+
+    /// The match's status given its score
+    fn decide(&self) -> MatchStatus {
+        let remaining = self.best_of.saturating_sub(self.score.played());
+        let (x, o) = (self.score.x, self.score.o);
+
+        if x > o + remaining {
+            MatchStatus::Won(Player::X)
+        } else if o > x + remaining {
+            MatchStatus::Won(Player::O)
+        } else if remaining == 0 {
+            MatchStatus::Drawn
+        } else {
+            MatchStatus::InProgress
+        }
+    }
+
+    /// A fresh game set up like the first, started by the player whose turn it is to go first
+    fn next_game(&self) -> GameState {
+        let first = &self.games[0];
+        let mut next = GameState::new();
+
+        next.board = vec![vec![Cell::Empty; first.cols()]; first.rows()];
+        next.win_length = first.win_length;
+        next.variant = first.variant;
+        if let Some(seconds) = first.move_time_limit {
+            next.set_move_time_limit(seconds);
+        }
+
+        // Alternate who moves first
+        next.current_turn = if self.games.len().is_multiple_of(2) {
+            self.first_player
+        } else {
+            self.first_player.opponent()
+        };
+        next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Play the current game to a draw
+    fn draw(m: &mut Match) {
+        let game = m.current_game_mut().unwrap();
+        let first = game.current_turn;
+        game.offer_draw(first).unwrap();
+        game.accept_draw(first.opponent()).unwrap();
+        m.advance();
+    }
+
+    #[test]
+    fn test_draws_and_alternation() {
+        let mut m = Match::new(GameState::with_size(4, 3).unwrap(), 2).unwrap();
+
+        draw(&mut m);
+        assert_eq!(m.current_game().current_turn, Player::O);
+        assert_eq!(m.current_game().size(), 4);
+        assert_eq!(m.status, MatchStatus::InProgress);
+
+        m.current_game_mut().unwrap().resign(Player::O).unwrap();
+        assert!(!m.advance());
+        assert_eq!(m.status, MatchStatus::Won(Player::X));
+        assert_eq!(m.score, MatchScore { x: 1, o: 0, draws: 1 });
+        assert!(matches!(m.current_game_mut(), Err(GameError::MatchAlreadyFinished)));
+    }
+
+    #[test]
+    fn test_drawn_match() {
+        let mut m = Match::new(GameState::new(), 3).unwrap();
+        draw(&mut m);
+        m.current_game_mut().unwrap().resign(Player::X).unwrap();
+        m.advance();
+        m.current_game_mut().unwrap().resign(Player::O).unwrap();
+        m.advance();
+
+        assert_eq!(m.status, MatchStatus::Drawn);
+        assert_eq!(m.score.to_string(), "X 1 - 1 O (1 drawn)");
+        assert!(Match::new(GameState::new(), 0).is_err());
+    }
+}
@@ -0,0 +1,265 @@
+//! Compact text notation for game histories
+//!
+//! A history is written as a few PGN-style headers followed by the moves,
+//! numbered from 1, with cells in algebraic notation (a letter for the
+//! column from `a` and a number for the row from 1):
+//!
+//! ```text
+//! [Game "7f1c2a3e-5d8b-4e1a-9c4f-2b6d8e0a1c3f"]
+//! [Variant "standard"]
+//! [Board "3x3"]
+//! [WinLength "3"]
+//! [First "X"]
+//! [Result "X wins"]
+//!
+//! 1. X b2 2. O a1 3. X a2 4. O c1 5. X c2
+//! ```
+//!
+//! Timestamps, variations and chat are left out, so games read back from
+//! the notation have their moves timestamped when they were parsed.
+
+use uuid::Uuid;
+
+use crate::error::{GameError, GameResult};
+use crate::game::GameStatus;
+use crate::history::{GameHistory, GameMove};
+use crate::player::Player;
+use crate::position::Position;
+use crate::rules::Variant;
+
+/// The widest board whose columns can be named by a single letter
+const MAX_NOTATION_COLS: usize = 26;
+
+impl GameHistory {
+    /// Writes the history in the compact text notation
+    ///
+    /// Fails for boards too wide to name every column with a letter.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::game::GameState;
+    /// use my_game_project::player::Player;
+    ///
+    /// let mut history = GameState::new().create_history();
+    /// history.add_move(Player::X, (1, 1));
+    /// history.add_move(Player::O, (0, 0));
+    ///
+    /// let notation = history.to_notation().unwrap();
+    /// assert!(notation.ends_with("1. X b2 2. O a1\n"));
+    /// ```
+    pub fn to_notation(&self) -> GameResult<String> {
+        let cols = self.board_cols.unwrap_or(self.board_size);
+        if cols > MAX_NOTATION_COLS {
+            return Err(GameError::InvalidNotation(format!(
+                "boards wider than {} columns can't be written",
+                MAX_NOTATION_COLS
+            )));
+        }
+
+        let mut notation = String::new();
+        let mut header = |name: &str, value: &str| {
+            notation.push_str(&format!("[{} {}]\n", name, serde_json::Value::from(value)));
+        };
+        header("Game", &self.game_id.to_string());
+        header("Variant", variant_name(self.variant));
+        header("Board", &format!("{}x{}", self.board_size, cols));
+        header("WinLength", &self.win_length.to_string());
+        header("First", &self.starting_player.to_string());
+        header("Result", &result_name(self.final_status));
+
+        let moves: Vec<String> = self
+            .moves
+            .iter()
+            .enumerate()
+            .map(|(ply, game_move)| {
+                format!("{}. {} {}", ply + 1, game_move.player, cell_name(game_move.position))
+            })
+            .collect();
+        notation.push('\n');
+        notation.push_str(&moves.join(" "));
+        notation.push('\n');
+
+        Ok(notation)
+    }
+
+    /// Reads a history written in the compact text notation
+    ///
+    /// Missing headers fall back to a standard 3x3 game started by the first
+    /// player to move, and unknown headers are ignored. The moves must be
+    /// legal and agree with the result if the board decides the game.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::history::GameHistory;
+    ///
+    /// let history = GameHistory::from_notation("1. X b2 2. O a1 3. X c3").unwrap();
+    /// assert_eq!(history.moves.len(), 3);
+    /// assert!(GameHistory::from_notation("1. X b2 2. O b2").is_err());
+    /// ```
+    pub fn from_notation(notation: &str) -> GameResult<Self> {
+        let invalid = |message: String| GameError::InvalidNotation(message);
+        let mut history = GameHistory::new(Uuid::new_v4());
+        let mut first = None;
+        let mut result = None;
+        let mut tokens = Vec::new();
+
+        for line in notation.lines().map(str::trim) {
+            if !line.starts_with('[') {
+                tokens.extend(line.split_whitespace());
+                continue;
+            }
+
+            let (name, value) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+                .and_then(|header| header.split_once(' '))
+                .and_then(|(name, value)| Some((name, serde_json::from_str::<String>(value).ok()?)))
+                .ok_or_else(|| invalid(format!("malformed header '{}'", line)))?;
+            let bad_value = || invalid(format!("bad {} header '{}'", name, value));
+            match name {
+                "Game" => history.game_id = Uuid::parse_str(&value).map_err(|_| bad_value())?,
+                "Variant" => history.variant = parse_variant(&value).ok_or_else(bad_value)?,
+                "Board" => {
+                    let (rows, cols) = value
+                        .split_once('x')
+                        .and_then(|(rows, cols)| Some((rows.parse().ok()?, cols.parse().ok()?)))
+                        .ok_or_else(bad_value)?;
+                    history.board_size = rows;
+                    history.board_cols = (cols != rows).then_some(cols);
+                }
+                "WinLength" => history.win_length = value.parse().map_err(|_| bad_value())?,
+                "First" => first = Some(value.parse().map_err(|_| bad_value())?),
+                "Result" => result = parse_result(&value).ok_or_else(bad_value)?,
+                _ => {}
+            }
+        }
+
+        for (ply, chunk) in tokens.chunks(3).enumerate() {
+            let [number, player, cell] = chunk else {
+                return Err(invalid(format!("move {} is incomplete", ply + 1)));
+            };
+            if number.strip_suffix('.') != Some(&(ply + 1).to_string()) {
+                return Err(invalid(format!("expected move {} but found '{}'", ply + 1, number)));
+            }
+            let player: Player = player
+                .parse()
+                .map_err(|_| invalid(format!("move {} has no player '{}'", ply + 1, player)))?;
+            let position: Position = cell.parse()?;
+            history.moves.push(GameMove::new(player, position));
+        }
+
+        history.starting_player = first
+            .or_else(|| history.moves.first().map(|game_move| game_move.player))
+            .unwrap_or(Player::X);
+
+        // Replaying checks every move, and the board has the last word on how the game ended
+        let game = history.reconstruct_game()?;
+        let status = match result {
+            Some(status) if game.is_over() && status != game.status => {
+                return Err(invalid(format!("the result disagrees with the board ({})", game.status)));
+            }
+            None if game.is_over() => Some(game.status),
+            status => status,
+        };
+        if let Some(status) = status {
+            history.finish(status);
+        }
+
+        Ok(history)
+    }
+}
+
+/// Returns the name of a cell in algebraic notation, or as `(row, col)` past the 26th column
+pub(crate) fn cell_name(position: Position) -> String {
+    if position.col >= MAX_NOTATION_COLS {
+        return position.to_string();
+    }
+    format!("{}{}", (b'a' + position.col as u8) as char, position.row + 1)
+}
+
+/// Returns the name of `variant` in the notation, which is also its serialized name
+fn variant_name(variant: Variant) -> &'static str {
+    match variant {
+        Variant::Standard => "standard",
+        Variant::ConnectFour => "connect_four",
+        Variant::Gomoku => "gomoku",
+        Variant::Misere => "misere",
+    }
+}
+
+/// Parses a variant named in the notation
+fn parse_variant(name: &str) -> Option<Variant> {
+    [Variant::Standard, Variant::ConnectFour, Variant::Gomoku, Variant::Misere]
+        .into_iter()
+        .find(|&variant| variant_name(variant) == name)
+}
+
+/// Returns the result header for a game that ended with `status`, or `*` if it hasn't ended
+fn result_name(status: Option<GameStatus>) -> String {
+    match status {
+        None | Some(GameStatus::InProgress) => "*".to_string(),
+        Some(status) => status.to_string(),
+    }
+}
+
+/// Parses a result header, which is `None` inside for a game that hasn't ended
+fn parse_result(result: &str) -> Option<Option<GameStatus>> {
+    let status = match result.split_once(' ') {
+        None if result == "*" => return Some(None),
+        None if result == "draw" => GameStatus::Draw,
+        Some((player, "wins")) => GameStatus::Won(player.parse().ok()?),
+        Some((player, "resigns")) => GameStatus::Resigned(player.parse().ok()?),
+        Some((player, "times out")) => GameStatus::TimedOut(player.parse().ok()?),
+        _ => return None,
+    };
+    Some(Some(status))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+
+    #[test]
+    fn test_notation_round_trip() {
+        let mut game = GameState::connect_four();
+        game.current_turn = Player::O;
+        let mut history = game.create_history();
+        history.add_move(Player::O, (5, 3));
+        history.add_move(Player::X, (5, 6));
+        history.finish(GameStatus::Resigned(Player::X));
+
+        let notation = history.to_notation().unwrap();
+        assert!(notation.contains("[Board \"6x7\"]\n"));
+        assert!(notation.contains("[Result \"X resigns\"]\n"));
+        assert!(notation.ends_with("\n1. O d6 2. X g6\n"));
+
+        let parsed = GameHistory::from_notation(&notation).unwrap();
+        assert_eq!(parsed.game_id, history.game_id);
+        assert_eq!((parsed.board_size, parsed.board_cols), (6, Some(7)));
+        assert_eq!(parsed.variant, Variant::ConnectFour);
+        assert_eq!(parsed.starting_player, Player::O);
+        assert_eq!(parsed.final_status, Some(GameStatus::Resigned(Player::X)));
+        assert_eq!(parsed.moves.len(), 2);
+        assert!(parsed.moves.iter().zip(&history.moves).all(|(a, b)| a.position == b.position));
+        assert_eq!(parsed.to_notation().unwrap(), notation);
+    }
+
+    #[test]
+    fn test_from_notation_checks_the_game() {
+        // The result is read off the board when it isn't given
+        let won = GameHistory::from_notation("1. X a1 2. O b1 3. X a2 4. O b2 5. X a3").unwrap();
+        assert_eq!(won.final_status, Some(GameStatus::Won(Player::X)));
+
+        let disagrees = "[Result \"draw\"]\n1. X a1 2. O b1 3. X a2 4. O b2 5. X a3";
+        assert!(matches!(GameHistory::from_notation(disagrees), Err(GameError::InvalidNotation(_))));
+        assert!(matches!(
+            GameHistory::from_notation("1. X a1 3. O b1"),
+            Err(GameError::InvalidNotation(_))
+        ));
+        assert!(matches!(GameHistory::from_notation("1. X a1 2. X b1"), Err(GameError::NotPlayerTurn)));
+        assert!(matches!(GameHistory::from_notation("[Board 3x3]"), Err(GameError::InvalidNotation(_))));
+    }
+}
@@ -0,0 +1,98 @@
+use crate::error::GameResult;
+use crate::game::GameState;
+use crate::player::{GamePlayer, Player, PlayerAction};
+use crate::position::Position;
+
+/// Chooses an action for the current position
+type ActionCallback = Box<dyn Fn(&GameState) -> GameResult<PlayerAction> + Send>;
+
+/// Decides whether to accept a draw offer in the current position
+type DrawCallback = Box<dyn Fn(&GameState) -> GameResult<bool> + Send>;
+
+/// A player whose actions come from a closure, such as one that asks a GUI
+///
+/// # Examples
+///
+/// ```
+/// use my_game_project::game::GameState;
+/// use my_game_project::player::{CallbackPlayer, GamePlayer, Player, PlayerAction};
+///
+/// // Always take the first free cell
+/// let player = CallbackPlayer::new(Player::X, "First free", |game: &GameState| {
+///     let (row, col) = game.legal_moves().next().unwrap();
+///     Ok(PlayerAction::Move((row, col).into()))
+/// });
+///
+/// assert_eq!(player.get_move(&GameState::new()).unwrap(), (0, 0).into());
+/// ```
+pub struct CallbackPlayer {
+    /// The player type (X or O)
+    player_type: Player,
+    /// The player's name
+    name: String,
+    /// Called for each of the player's actions
+    on_action: ActionCallback,
+    /// Called when the opponent offers a draw, if set
+    on_draw_offer: Option<DrawCallback>,
+}
+
+impl CallbackPlayer {
+    /// Create a player that calls `on_action` whenever it's their turn
+    ///
+    /// Draw offers are declined unless [`with_draw_callback`](Self::with_draw_callback) says otherwise.
+    pub fn new(
+        player_type: Player,
+        name: impl Into<String>,
+        on_action: impl Fn(&GameState) -> GameResult<PlayerAction> + Send + 'static,
+    ) -> Self {
+        Self {
+            player_type,
+            name: name.into(),
+            on_action: Box::new(on_action),
+            on_draw_offer: None,
+        }
+    }
+
+    /// Call `on_draw_offer` to decide whether to accept the opponent's draw offers
+    pub fn with_draw_callback(
+        mut self,
+        on_draw_offer: impl Fn(&GameState) -> GameResult<bool> + Send + 'static,
+    ) -> Self {
+        self.on_draw_offer = Some(Box::new(on_draw_offer));
+        self
+    }
+}
+
+impl GamePlayer for CallbackPlayer {
+    fn get_move(&self, game: &GameState) -> GameResult<Position> {
+        // Only a move will do, so ask again until the callback makes one
+        loop {
+            if let PlayerAction::Move(position) = self.get_action(game)? {
+                return Ok(position);
+            }
+        }
+    }
+
+    fn get_action(&self, game: &GameState) -> GameResult<PlayerAction> {
+        (self.on_action)(game)
+    }
+
+    fn respond_to_draw_offer(&self, game: &GameState) -> GameResult<bool> {
+        match &self.on_draw_offer {
+            Some(on_draw_offer) => on_draw_offer(game),
+            None => Ok(false),
+        }
+    }
+
+    fn is_human(&self) -> bool {
+        true
+    }
+
+    fn get_player_type(&self) -> Player {
+        self.player_type
+    }
+
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+}
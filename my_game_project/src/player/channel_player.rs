@@ -0,0 +1,113 @@
+use std::io;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use crate::error::{GameError, GameResult};
+use crate::game::GameState;
+use crate::player::{GamePlayer, Player, PlayerAction};
+use crate::position::Position;
+
+/// A player whose actions are sent to it from elsewhere, such as a GUI's event loop
+///
+/// The game loop blocks in [`GamePlayer::get_action`] until the
+/// [`ChannelPlayerHandle`] sends an action, so it can run on its own thread
+/// while the frontend sends clicks through the handle.
+///
+/// # Examples
+///
+/// ```
+/// use std::thread;
+///
+/// use my_game_project::game::GameState;
+/// use my_game_project::player::{ChannelPlayer, GamePlayer, Player, PlayerAction};
+///
+/// let (player, handle) = ChannelPlayer::new(Player::X, "Alice");
+/// thread::spawn(move || handle.play((1, 1)));
+///
+/// let action = player.get_action(&GameState::new()).unwrap();
+/// assert_eq!(action, PlayerAction::Move((1, 1).into()));
+/// ```
+pub struct ChannelPlayer {
+    /// The player type (X or O)
+    player_type: Player,
+    /// The player's name
+    name: String,
+    /// Actions sent by the handle, in the order they were sent
+    actions: Receiver<PlayerAction>,
+    /// Answers to draw offers sent by the handle
+    draw_answers: Receiver<bool>,
+}
+
+/// Sends actions to a [`ChannelPlayer`]
+///
+/// Every method fails once the player has been dropped, such as when its game is over.
+#[derive(Debug, Clone)]
+pub struct ChannelPlayerHandle {
+    /// Where actions are sent
+    actions: Sender<PlayerAction>,
+    /// Where answers to draw offers are sent
+    draw_answers: Sender<bool>,
+}
+
+impl ChannelPlayer {
+    /// Create a player along with the handle that sends its actions
+    pub fn new(player_type: Player, name: impl Into<String>) -> (Self, ChannelPlayerHandle) {
+        let (action_sender, actions) = mpsc::channel();
+        let (answer_sender, draw_answers) = mpsc::channel();
+        let player = Self { player_type, name: name.into(), actions, draw_answers };
+        let handle = ChannelPlayerHandle { actions: action_sender, draw_answers: answer_sender };
+        (player, handle)
+    }
+}
+
+impl ChannelPlayerHandle {
+    /// Make a move at `position`
+    pub fn play(&self, position: impl Into<Position>) -> GameResult<()> {
+        self.act(PlayerAction::Move(position.into()))
+    }
+
+    /// Send any action, such as resigning or offering a draw
+    pub fn act(&self, action: PlayerAction) -> GameResult<()> {
+        self.actions.send(action).map_err(|_| disconnected())
+    }
+
+    /// Accept or decline the opponent's draw offer
+    pub fn answer_draw(&self, accept: bool) -> GameResult<()> {
+        self.draw_answers.send(accept).map_err(|_| disconnected())
+    }
+}
+
+impl GamePlayer for ChannelPlayer {
+    fn get_move(&self, game: &GameState) -> GameResult<Position> {
+        // Only a move will do, so anything else sent is skipped
+        loop {
+            if let PlayerAction::Move(position) = self.get_action(game)? {
+                return Ok(position);
+            }
+        }
+    }
+
+    fn get_action(&self, _game: &GameState) -> GameResult<PlayerAction> {
+        self.actions.recv().map_err(|_| disconnected())
+    }
+
+    fn respond_to_draw_offer(&self, _game: &GameState) -> GameResult<bool> {
+        self.draw_answers.recv().map_err(|_| disconnected())
+    }
+
+    fn is_human(&self) -> bool {
+        true
+    }
+
+    fn get_player_type(&self) -> Player {
+        self.player_type
+    }
+
+    fn get_name(&self) -> String {
+        self.name.clone()
+    }
+}
+
+/// The error for a channel whose other end has been dropped
+fn disconnected() -> GameError {
+    GameError::IoError(io::Error::new(io::ErrorKind::BrokenPipe, "the player's channel was closed"))
+}
@@ -1,8 +1,10 @@
 use std::io::{self, Write};
 
-use crate::error::{GameError, GameResult};
+use crate::error::GameResult;
 use crate::game::GameState;
-use crate::player::{GamePlayer, Player};
+use crate::i18n::{tr, Locale, Text};
+use crate::player::{GamePlayer, Player, PlayerAction};
+use crate::position::Position;
 
 /// A human player that gets moves from stdin
 pub struct HumanPlayer {
@@ -18,45 +20,67 @@ impl HumanPlayer {
         Self { player_type, name }
     }
     
-    /// Get a move from the user via stdin
-    fn get_player_move_from_stdin(&self) -> GameResult<(usize, usize)> {
+    /// Print a prompt and read a line from stdin
+    fn prompt(&self, message: &str) -> GameResult<String> {
+        print!("{}", message);
+        io::stdout().flush()?;
+        
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        
+        Ok(input)
+    }
+    
+    /// Get an action from the user via stdin
+    ///
+    /// Besides a move, in any form [`Position`] parses, the user may type
+    /// `resign` or `draw` (to offer a draw).
+    fn get_player_action_from_stdin(&self, game: &GameState) -> GameResult<PlayerAction> {
+        let (max_row, max_col) = (game.rows() - 1, game.cols() - 1);
+
         loop {
-            print!("Enter your move as 'row col' (0-2): ");
-            io::stdout().flush().map_err(|e| {
-                GameError::IoError(e.to_string())
-            })?;
-            
-            let mut input = String::new();
-            io::stdin().read_line(&mut input).map_err(|e| {
-                GameError::IoError(e.to_string())
-            })?;
+            let input = self.prompt(&tr(Text::MovePrompt, &[&max_row, &max_col]))?;
             
-            // Parse the input
-            let coords: Vec<&str> = input.trim().split_whitespace().collect();
-            
-            if coords.len() != 2 {
-                println!("Please enter exactly two numbers separated by a space.");
-                continue;
+            // Check for commands
+            match input.trim().to_lowercase().as_str() {
+                "resign" => return Ok(PlayerAction::Resign),
+                "draw" => return Ok(PlayerAction::OfferDraw),
+                _ => {}
             }
             
-            // Try to parse the coordinates
-            match (coords[0].parse::<usize>(), coords[1].parse::<usize>()) {
-                (Ok(row), Ok(col)) => {
-                    if row <= 2 && col <= 2 {
-                        return Ok((row, col));
-                    } else {
-                        println!("Row and column must be between 0 and 2.");
-                    }
+            // Parse the coordinates
+            match input.parse::<Position>() {
+                Ok(position) if position.row <= max_row && position.col <= max_col => {
+                    return Ok(PlayerAction::Move(position));
                 }
-                _ => println!("Invalid input. Please enter numbers."),
+                Ok(_) => println!("{}", tr(Text::MoveOutOfRange, &[&max_row, &max_col])),
+                Err(e) => println!("{}", tr(Text::ErrorTryAgain, &[&e.localized()])),
             }
         }
     }
 }
 
 impl GamePlayer for HumanPlayer {
-    fn get_move(&self, _game: &GameState) -> GameResult<(usize, usize)> {
-        self.get_player_move_from_stdin()
+    fn get_move(&self, game: &GameState) -> GameResult<Position> {
+        loop {
+            match self.get_player_action_from_stdin(game)? {
+                PlayerAction::Move(position) => return Ok(position),
+                _ => println!("{}", tr(Text::EnterAMove, &[])),
+            }
+        }
+    }
+    
+    fn get_action(&self, game: &GameState) -> GameResult<PlayerAction> {
+        self.get_player_action_from_stdin(game)
+    }
+    
+    fn respond_to_draw_offer(&self, _game: &GameState) -> GameResult<bool> {
+        let input = self.prompt(&tr(Text::AcceptDrawPrompt, &[&self.name]))?;
+        Ok(Locale::current().is_yes(&input))
+    }
+    
+    fn is_human(&self) -> bool {
+        true
     }
     
     fn get_player_type(&self) -> Player {
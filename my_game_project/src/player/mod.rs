@@ -1,16 +1,66 @@
 mod player_type;
 mod human_player;
+mod channel_player;
+mod callback_player;
+#[cfg(feature = "server")]
+mod remote_player;
+#[cfg(all(feature = "ai", feature = "files"))]
+mod spec;
 
 pub use player_type::Player;
 pub use human_player::HumanPlayer;
+pub use channel_player::{ChannelPlayer, ChannelPlayerHandle};
+pub use callback_player::CallbackPlayer;
+#[cfg(feature = "server")]
+pub use remote_player::{RemoteGame, RemotePlayer};
+#[cfg(all(feature = "ai", feature = "files"))]
+pub use spec::{from_spec, PlayerSpec};
 
+#[cfg(feature = "ai")]
+use crate::ai::SearchStats;
 use crate::error::GameResult;
 use crate::game::GameState;
+use crate::position::Position;
+
+/// Something a player can do on their turn
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlayerAction {
+    /// Place a mark at a position
+    Move(Position),
+    /// Concede the game
+    Resign,
+    /// Offer the opponent a draw
+    OfferDraw,
+}
 
 /// A trait for entities that can make moves in the game
 pub trait GamePlayer {
     /// Get the next move from this player
-    fn get_move(&self, game: &GameState) -> GameResult<(usize, usize)>;
+    fn get_move(&self, game: &GameState) -> GameResult<Position>;
+
+    /// Get the next action from this player
+    ///
+    /// Players that never resign or offer draws can rely on the default,
+    /// which always makes a move.
+    fn get_action(&self, game: &GameState) -> GameResult<PlayerAction> {
+        Ok(PlayerAction::Move(self.get_move(game)?))
+    }
+
+    /// Decide whether to accept the opponent's draw offer (declines by default)
+    fn respond_to_draw_offer(&self, _game: &GameState) -> GameResult<bool> {
+        Ok(false)
+    }
+
+    /// Statistics from the search for this player's last move, if it searched
+    #[cfg(feature = "ai")]
+    fn search_stats(&self) -> Option<SearchStats> {
+        None
+    }
+
+    /// Whether this player is a person at the keyboard
+    fn is_human(&self) -> bool {
+        false
+    }
 
     /// Get the player type (X or O)
     fn get_player_type(&self) -> Player;
@@ -1,11 +1,21 @@
+use std::fmt;
+use std::str::FromStr;
+
 use serde::{Deserialize, Serialize};
 
+use crate::error::GameError;
+
 /// Represents a player in the game (X or O)
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+///
+/// Serialized as `"x"` or `"o"`, though the uppercase marks older releases
+/// wrote are still read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Player {
     /// The X player (usually goes first)
+    #[serde(rename = "x", alias = "X")]
     X,
     /// The O player
+    #[serde(rename = "o", alias = "O")]
     O,
 }
 
@@ -27,3 +37,36 @@ impl Player {
         }
     }
 }
+
+impl fmt::Display for Player {
+    /// Writes the player's mark, `X` or `O`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Player::X => write!(f, "X"),
+            Player::O => write!(f, "O"),
+        }
+    }
+}
+
+impl FromStr for Player {
+    type Err = GameError;
+
+    /// Parses a player's mark, in either case
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::player::Player;
+    ///
+    /// assert_eq!("o".parse::<Player>().unwrap(), Player::O);
+    /// assert_eq!(Player::X.to_string().parse::<Player>().unwrap(), Player::X);
+    /// assert!("Z".parse::<Player>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "X" | "x" => Ok(Player::X),
+            "O" | "o" => Ok(Player::O),
+            _ => Err(GameError::InvalidPlayerType(s.to_string())),
+        }
+    }
+}
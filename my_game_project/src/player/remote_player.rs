@@ -0,0 +1,249 @@
+use std::thread;
+use std::time::Duration;
+
+use reqwest::blocking::{Client, RequestBuilder};
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::client::{decode_response, network_error, CreateGame, CreatedGame, MakeMove};
+use crate::error::{GameError, GameResult};
+use crate::game::{Cell, GameState, GameStatus};
+use crate::history::GameHistory;
+use crate::i18n::{tr, Text};
+use crate::player::{GamePlayer, Player, PlayerAction};
+use crate::position::Position;
+use crate::server::Seat;
+
+/// How long to wait between checks for the opponent's move
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A game hosted by a server, seen from one of its seats
+///
+/// The server is the source of truth: every method returns the game as the
+/// server sees it after the request.
+#[derive(Debug, Clone)]
+pub struct RemoteGame {
+    /// The HTTP client used for every request
+    client: Client,
+    /// Base URL of the server, e.g. `http://localhost:3000`
+    server: String,
+    /// The game's ID
+    game_id: Uuid,
+    /// The seat this client plays
+    player: Player,
+    /// The secret token for this client's seat
+    token: String,
+}
+
+impl RemoteGame {
+    /// Create a game on `server`, playing X and leaving O open for an opponent to join
+    pub fn create(server: &str) -> GameResult<Self> {
+        let client = Client::new();
+        let server = server.trim_end_matches('/').to_string();
+
+        let request = CreateGame {
+            open_seat: Some(Player::O),
+            ..CreateGame::default()
+        };
+        let created: CreatedGame = send(client.post(format!("{}/games", server)).json(&request))?;
+
+        Ok(Self {
+            client,
+            server,
+            game_id: created.game.id,
+            player: Player::X,
+            token: created.tokens.x.unwrap_or_default(),
+        })
+    }
+
+    /// Join the game with ID `game_id` on `server`, taking its open seat
+    pub fn join(server: &str, game_id: Uuid) -> GameResult<Self> {
+        let client = Client::new();
+        let server = server.trim_end_matches('/').to_string();
+
+        let request = client.post(format!("{}/games/{}/join", server, game_id));
+        let seat: Seat = send(request)?;
+
+        Ok(Self {
+            client,
+            server,
+            game_id: seat.game_id,
+            player: seat.player,
+            token: seat.token,
+        })
+    }
+
+    /// Returns the game's ID
+    pub fn game_id(&self) -> Uuid {
+        self.game_id
+    }
+
+    /// Returns the seat this client plays
+    pub fn player(&self) -> Player {
+        self.player
+    }
+
+    /// Fetch the current state of the game
+    pub fn game(&self) -> GameResult<GameState> {
+        send(self.client.get(self.url("")))
+    }
+
+    /// Fetch the moves made so far
+    pub fn history(&self) -> GameResult<GameHistory> {
+        send(self.client.get(self.url("/history")))
+    }
+
+    /// Make a move for this client's seat
+    pub fn make_move(&self, position: impl Into<Position>) -> GameResult<GameState> {
+        let request = MakeMove {
+            position: position.into(),
+            player: self.player,
+            token: Some(self.token.clone()),
+            expected_version: None,
+        };
+        send(self.client.post(self.url("/move")).json(&request))
+    }
+
+    /// Resign the game
+    pub fn resign(&self) -> GameResult<GameState> {
+        self.act("/resign")
+    }
+
+    /// Offer the opponent a draw
+    pub fn offer_draw(&self) -> GameResult<GameState> {
+        self.act("/draw-offer")
+    }
+
+    /// Accept the opponent's draw offer
+    pub fn accept_draw(&self) -> GameResult<GameState> {
+        self.act("/draw-accept")
+    }
+
+    /// Decline the opponent's draw offer
+    pub fn decline_draw(&self) -> GameResult<GameState> {
+        self.act("/draw-decline")
+    }
+
+    /// Perform a non-move action for this client's seat
+    fn act(&self, path: &str) -> GameResult<GameState> {
+        let body = json!({ "player": self.player, "token": self.token });
+        send(self.client.post(self.url(path)).json(&body))
+    }
+
+    /// Returns the URL of a route under this game
+    fn url(&self, path: &str) -> String {
+        format!("{}/games/{}{}", self.server, self.game_id, path)
+    }
+}
+
+/// Send a request and decode its JSON response, turning server errors into `GameError`s
+fn send<T: DeserializeOwned>(request: RequestBuilder) -> GameResult<T> {
+    let response = request.send().map_err(network_error)?;
+    let status = response.status();
+    let body = response.bytes().map_err(network_error)?;
+
+    decode_response(status, &body)
+}
+
+/// An opponent playing from another client, whose actions arrive through the server
+///
+/// The local client's own actions must be sent with [`RemoteGame`]; this
+/// player only waits for the opponent's.
+pub struct RemotePlayer {
+    /// The game being played, from the local client's seat
+    game: RemoteGame,
+}
+
+impl RemotePlayer {
+    /// Create the opponent of the local client's seat in `game`
+    pub fn new(game: RemoteGame) -> Self {
+        Self { game }
+    }
+
+    /// Send the moves the local client made in `game` that the server hasn't seen yet
+    ///
+    /// This lets the opponent play in a local game loop, such as one set up
+    /// with [`from_spec`](crate::player::from_spec), that doesn't talk to the
+    /// server itself.
+    fn send_local_moves(&self, game: &GameState) -> GameResult<()> {
+        let local = Cell::Occupied(self.game.player());
+        let server = self.game.game()?;
+        for (row, cells) in game.board.iter().enumerate() {
+            for (col, &cell) in cells.iter().enumerate() {
+                if cell == local && server.board[row][col] == Cell::Empty {
+                    self.game.make_move((row, col))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Poll the server until `done` returns a result for the latest state
+    fn wait_for<T>(&self, mut done: impl FnMut(&GameState) -> GameResult<Option<T>>) -> GameResult<T> {
+        loop {
+            if let Some(result) = done(&self.game.game()?)? {
+                return Ok(result);
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+impl GamePlayer for RemotePlayer {
+    fn get_move(&self, game: &GameState) -> GameResult<Position> {
+        match self.get_action(game)? {
+            PlayerAction::Move(position) => Ok(position),
+            _ => Err(GameError::GameAlreadyFinished),
+        }
+    }
+
+    fn get_action(&self, game: &GameState) -> GameResult<PlayerAction> {
+        let opponent = self.get_player_type();
+        self.send_local_moves(game)?;
+        println!("{}", tr(Text::WaitingForOpponent, &[]));
+
+        // Wait until the opponent has moved, resigned or offered a draw
+        self.wait_for(|latest| {
+            if latest.status == GameStatus::Resigned(opponent) {
+                return Ok(Some(PlayerAction::Resign));
+            }
+            if latest.draw_offer == Some(opponent) && !latest.is_over() {
+                return Ok(Some(PlayerAction::OfferDraw));
+            }
+            if latest.current_turn == opponent && !latest.is_over() {
+                return Ok(None);
+            }
+
+            // Once the turn has passed, the opponent's move is the last one played
+            match self.game.history()?.moves.last() {
+                Some(game_move) if game_move.player == opponent => {
+                    Ok(Some(PlayerAction::Move(game_move.position)))
+                }
+                // The game ended some other way, e.g. on time, without the opponent acting
+                _ if latest.is_over() => Err(GameError::GameAlreadyFinished),
+                _ => Ok(None),
+            }
+        })
+    }
+
+    fn respond_to_draw_offer(&self, _game: &GameState) -> GameResult<bool> {
+        println!("{}", tr(Text::WaitingForDrawAnswer, &[]));
+
+        // The offer is answered once it's withdrawn from the game
+        self.wait_for(|latest| {
+            Ok(match latest.draw_offer {
+                Some(_) if !latest.is_over() => None,
+                _ => Some(latest.status == GameStatus::Draw),
+            })
+        })
+    }
+
+    fn get_player_type(&self) -> Player {
+        self.game.player().opponent()
+    }
+
+    fn get_name(&self) -> String {
+        format!("Remote player ({}) in game {}", self.get_player_type(), self.game.game_id())
+    }
+}
@@ -0,0 +1,114 @@
+use std::fmt;
+use std::str::FromStr;
+
+use std::sync::Arc;
+
+use crate::ai::{QLearningAI, QTable};
+use crate::error::{GameError, GameResult};
+use crate::player::{GamePlayer, HumanPlayer, Player};
+#[cfg(feature = "server")]
+use crate::player::{RemoteGame, RemotePlayer};
+use crate::simulation::Bot;
+
+/// A player described by a short string, such as on the command line
+///
+/// The forms are:
+///
+/// - `human` or `human:<name>`, a person at the keyboard
+/// - `ai:<bot>`, an AI written as for [`Bot`], or just `ai:easy`, `ai:medium` or `ai:hard`
+/// - `ai:qlearning:<file>`, a [`QLearningAI`] playing by the [`QTable`] saved in the file
+/// - `remote:<server>`, an opponent who joins a game created on the server,
+///   with the `server` feature
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlayerSpec {
+    /// A person at the keyboard, with the name they play under if one was given
+    Human(Option<String>),
+    /// An AI
+    Ai(Bot),
+    /// A Q-learning AI playing by the table saved in this file
+    QLearning(String),
+    /// An opponent playing through the server at this URL
+    #[cfg(feature = "server")]
+    Remote(String),
+}
+
+impl PlayerSpec {
+    /// Create the player to play `player`
+    ///
+    /// A remote opponent can only play O, since the server gives X to the
+    /// client that creates the game. The local client's moves are sent to the
+    /// server as the opponent waits for its turn.
+    pub fn create(&self, player: Player) -> GameResult<Box<dyn GamePlayer>> {
+        match self {
+            PlayerSpec::Human(name) => {
+                let name = name.clone().unwrap_or_else(|| format!("Player {}", player));
+                Ok(Box::new(HumanPlayer::new(player, name)))
+            }
+            PlayerSpec::Ai(bot) => Ok(bot.create(player)),
+            PlayerSpec::QLearning(path) => {
+                let table = QTable::load_from_file(path)?;
+                Ok(Box::new(QLearningAI::new(player, Arc::new(table))))
+            }
+            #[cfg(feature = "server")]
+            PlayerSpec::Remote(_) if player == Player::X => Err(GameError::InvalidPlayerType(
+                "a remote opponent can only play O".to_string(),
+            )),
+            #[cfg(feature = "server")]
+            PlayerSpec::Remote(server) => Ok(Box::new(RemotePlayer::new(RemoteGame::create(server)?))),
+        }
+    }
+}
+
+impl FromStr for PlayerSpec {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, rest) = s.split_once(':').unwrap_or((s, ""));
+        match kind.to_lowercase().as_str() {
+            "human" if rest.trim().is_empty() => Ok(PlayerSpec::Human(None)),
+            "human" => Ok(PlayerSpec::Human(Some(rest.trim().to_string()))),
+            "ai" => match rest.split_once(':') {
+                Some((bot, path)) if bot.eq_ignore_ascii_case("qlearning") && !path.is_empty() => {
+                    Ok(PlayerSpec::QLearning(path.to_string()))
+                }
+                _ => Ok(PlayerSpec::Ai(s.parse()?)),
+            },
+            #[cfg(feature = "server")]
+            "remote" if !rest.is_empty() => Ok(PlayerSpec::Remote(rest.to_string())),
+            _ => Err(GameError::InvalidPlayerType(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for PlayerSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlayerSpec::Human(None) => write!(f, "human"),
+            PlayerSpec::Human(Some(name)) => write!(f, "human:{}", name),
+            PlayerSpec::Ai(bot) => write!(f, "ai:{}", bot),
+            PlayerSpec::QLearning(path) => write!(f, "ai:qlearning:{}", path),
+            #[cfg(feature = "server")]
+            PlayerSpec::Remote(server) => write!(f, "remote:{}", server),
+        }
+    }
+}
+
+/// Create the player that `spec` describes to play `player`
+///
+/// # Examples
+///
+/// ```
+/// use my_game_project::player::{self, Player};
+///
+/// let human = player::from_spec("human:Alice", Player::X).unwrap();
+/// assert_eq!(human.get_name(), "Alice (Human)");
+///
+/// let ai = player::from_spec("ai:hard", Player::O).unwrap();
+/// assert_eq!(ai.get_name(), "AI (Hard)");
+/// assert!(!ai.is_human());
+///
+/// assert!(player::from_spec("ai:unknown", Player::O).is_err());
+/// ```
+pub fn from_spec(spec: &str, player: Player) -> GameResult<Box<dyn GamePlayer>> {
+    spec.parse::<PlayerSpec>()?.create(player)
+}
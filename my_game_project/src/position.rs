@@ -0,0 +1,91 @@
+//! Board coordinates
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::GameError;
+
+/// A cell on the board, counted from the top-left corner
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Position {
+    /// The row, counting down from 0
+    pub row: usize,
+    /// The column, counting right from 0
+    pub col: usize,
+}
+
+impl Position {
+    /// Creates a position from a row and column
+    pub fn new(row: usize, col: usize) -> Self {
+        Self { row, col }
+    }
+}
+
+impl From<(usize, usize)> for Position {
+    fn from((row, col): (usize, usize)) -> Self {
+        Self::new(row, col)
+    }
+}
+
+impl From<Position> for (usize, usize) {
+    fn from(position: Position) -> Self {
+        (position.row, position.col)
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {})", self.row, self.col)
+    }
+}
+
+impl FromStr for Position {
+    type Err = GameError;
+
+    /// Parses a position written as `row col`, `row,col` or in algebraic notation
+    ///
+    /// Rows and columns count from 0, except in algebraic notation, where a
+    /// letter names the column from `a` and a number the row from 1.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::position::Position;
+    ///
+    /// assert_eq!("1 2".parse::<Position>().unwrap(), Position::new(1, 2));
+    /// assert_eq!("1,2".parse::<Position>().unwrap(), Position::new(1, 2));
+    /// assert_eq!("b2".parse::<Position>().unwrap(), Position::new(1, 1));
+    /// assert!("2".parse::<Position>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || GameError::InvalidCoordinates(s.trim().to_string());
+        let parts: Vec<&str> = if s.contains(',') {
+            s.split(',').map(str::trim).collect()
+        } else {
+            s.split_whitespace().collect()
+        };
+
+        match parts.as_slice() {
+            [row, col] => Ok(Self::new(
+                row.parse().map_err(|_| invalid())?,
+                col.parse().map_err(|_| invalid())?,
+            )),
+            [algebraic] => {
+                let mut chars = algebraic.chars();
+                let col = match chars.next() {
+                    Some(letter) if letter.is_ascii_alphabetic() => {
+                        (letter.to_ascii_lowercase() as u8 - b'a') as usize
+                    }
+                    _ => return Err(invalid()),
+                };
+                match chars.as_str().parse::<usize>() {
+                    Ok(row) if row > 0 => Ok(Self::new(row - 1, col)),
+                    _ => Err(invalid()),
+                }
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
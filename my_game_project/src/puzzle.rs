@@ -0,0 +1,348 @@
+//! Tactical puzzles: positions where the player to move can force a win
+//!
+//! A [`Puzzle`] is a position where the player to move can win in exactly
+//! so many of their own moves, however their opponent defends, but not in
+//! fewer. Puzzles are found in the positions of stored games or by playing
+//! random moves, and each day has its own [`Puzzle::daily`]. Solutions are
+//! checked by searching every defence to the end, so any winning line counts,
+//! not just the one the puzzle was found with.
+
+use std::cmp::Reverse;
+use std::fmt;
+
+use chrono::{Datelike, NaiveDate};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::{GameError, GameResult};
+use crate::game::{Cell, GameState};
+use crate::history::GameHistory;
+use crate::player::Player;
+use crate::position::Position;
+use crate::rules::Variant;
+
+/// Number of moves to win in when a puzzle doesn't say otherwise, as in "X to move and win in 2"
+pub const DEFAULT_PUZZLE_MOVES: usize = 2;
+
+/// The most positions a puzzle search may visit; positions with more moves to consider are skipped
+const MAX_SEARCH_NODES: u64 = 1_000_000;
+
+/// Number of random games to try before giving up on finding a puzzle
+const RANDOM_ATTEMPTS: usize = 1000;
+
+/// A position where the player to move can force a win in exactly [`moves`](Self::moves) moves
+///
+/// # Examples
+///
+/// ```
+/// use my_game_project::game::GameState;
+/// use my_game_project::position::Position;
+/// use my_game_project::puzzle::Puzzle;
+///
+/// // X holds two corners and O has blocked the center, so X can fork
+/// let mut game = GameState::new();
+/// for cell in [(0, 0), (1, 1), (2, 2), (0, 2)] {
+///     game.make_move(cell).unwrap();
+/// }
+///
+/// let puzzle = Puzzle::from_game(&game, 2).unwrap();
+/// assert_eq!(puzzle.to_string(), "X to move and win in 2");
+/// assert_eq!(puzzle.solutions(), vec![Position::new(2, 0)]);
+/// assert!(Puzzle::from_game(&game, 1).is_none());
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Puzzle {
+    /// The board, indexed as `board[row][col]`
+    pub board: Vec<Vec<Cell>>,
+    /// The number of marks in a row needed to win
+    pub win_length: usize,
+    /// The variant the position is played under
+    pub variant: Variant,
+    /// The player to move, who can force the win
+    pub player: Player,
+    /// Number of moves the player has to win in, counting only their own
+    pub moves: usize,
+    /// The game the position was found in, if it came from a stored game
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<Uuid>,
+}
+
+/// How an attempt at a puzzle went
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attempt {
+    /// Whether every move kept a forced win within the moves left
+    pub correct: bool,
+    /// Whether the moves won the game
+    pub solved: bool,
+    /// The defence to each correct move that didn't end the game, in order
+    pub replies: Vec<Position>,
+}
+
+impl Puzzle {
+    /// Makes a puzzle of `game` if the player to move can win in exactly `moves` moves
+    ///
+    /// Returns `None` for finished games, for positions where the win takes
+    /// more or fewer moves or can't be forced, and for positions with too many
+    /// moves to search.
+    pub fn from_game(game: &GameState, moves: usize) -> Option<Self> {
+        if game.is_over() || search_cost(game, moves) > MAX_SEARCH_NODES {
+            return None;
+        }
+
+        let mut search = game.clone();
+        if win_distance(&mut search, moves) != Some(moves) {
+            return None;
+        }
+        Some(Self {
+            board: game.board.clone(),
+            win_length: game.win_length,
+            variant: game.variant,
+            player: game.current_turn,
+            moves,
+            source: None,
+        })
+    }
+
+    /// Finds a puzzle by playing random moves from `start`, if one turns up
+    ///
+    /// An even number of moves is played, so the player to move at `start`
+    /// is the one who solves the puzzle.
+    pub fn random(start: &GameState, moves: usize, rng: &mut impl Rng) -> Option<Self> {
+        let cells = start.rows() * start.cols();
+        for _ in 0..RANDOM_ATTEMPTS {
+            let mut game = start.clone();
+            for _ in 0..2 * rng.gen_range(0..=cells / 2) {
+                let legal: Vec<_> = game.legal_moves().collect();
+                let Some(&cell) = legal.choose(rng) else {
+                    break;
+                };
+                game.make_move(cell).ok()?;
+            }
+
+            if let Some(puzzle) = Self::from_game(&game, moves) {
+                return Some(puzzle);
+            }
+        }
+        None
+    }
+
+    /// The puzzle of the day: X to move and win in two on a standard board
+    ///
+    /// The same date always gives the same puzzle, on any machine.
+    pub fn daily(date: NaiveDate) -> GameResult<Self> {
+        let mut rng = StdRng::seed_from_u64(date.num_days_from_ce() as u64);
+        Self::random(&GameState::new(), DEFAULT_PUZZLE_MOVES, &mut rng)
+            .ok_or_else(|| GameError::InvalidConfig(format!("no puzzle found for {}", date)))
+    }
+
+    /// The puzzle's position, ready to play
+    pub fn game(&self) -> GameState {
+        let mut game = GameState::new();
+        game.board = self.board.clone();
+        game.win_length = self.win_length;
+        game.variant = self.variant;
+        game.current_turn = self.player;
+        game.move_count = self.board.iter().flatten().filter(|&&cell| cell != Cell::Empty).count();
+        game.version = game.move_count as u64;
+        game
+    }
+
+    /// Every first move that forces the win in time, in row-major order
+    pub fn solutions(&self) -> Vec<Position> {
+        let mut game = self.game();
+        legal_moves(&game)
+            .into_iter()
+            .filter(|&cell| wins_with(&mut game, cell, self.moves))
+            .map(Position::from)
+            .collect()
+    }
+
+    /// Checks the solver's moves, answering each with the strongest defence
+    ///
+    /// `moves` are the solver's moves only. Each must keep a forced win
+    /// within the moves left, and the defender answers each with the reply
+    /// that holds out longest. Checking stops at the first wrong move or
+    /// once the game is won, and an attempt that is correct so far can be
+    /// continued by passing more moves.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::game::GameState;
+    /// use my_game_project::position::Position;
+    /// use my_game_project::puzzle::Puzzle;
+    ///
+    /// let mut game = GameState::new();
+    /// for cell in [(0, 0), (1, 1), (2, 2), (0, 2)] {
+    ///     game.make_move(cell).unwrap();
+    /// }
+    /// let puzzle = Puzzle::from_game(&game, 2).unwrap();
+    ///
+    /// // X threatens two lines, and every reply loses as quickly
+    /// let attempt = puzzle.attempt(&[Position::new(2, 0)]).unwrap();
+    /// assert!(attempt.correct && !attempt.solved);
+    /// assert_eq!(attempt.replies, vec![Position::new(0, 1)]);
+    ///
+    /// let attempt = puzzle.attempt(&[Position::new(2, 0), Position::new(2, 1)]).unwrap();
+    /// assert!(attempt.solved);
+    ///
+    /// assert!(!puzzle.attempt(&[Position::new(1, 0)]).unwrap().correct);
+    /// ```
+    pub fn attempt(&self, moves: &[Position]) -> GameResult<Attempt> {
+        let mut game = self.game();
+        let mut attempt = Attempt { correct: true, solved: false, replies: Vec::new() };
+
+        for (index, &position) in moves.iter().enumerate() {
+            let left = self.moves.saturating_sub(index);
+            let mut after = game.clone();
+            after.make_move(position)?;
+            if !wins_with(&mut game, position.into(), left) {
+                attempt.correct = false;
+                return Ok(attempt);
+            }
+
+            game = after;
+            if game.status.winner() == Some(self.player) {
+                attempt.solved = true;
+                return Ok(attempt);
+            }
+
+            let reply = defend(&mut game, left - 1).ok_or(GameError::NoValidMoves)?;
+            game.make_move(reply)?;
+            attempt.replies.push(Position::from(reply));
+        }
+
+        Ok(attempt)
+    }
+}
+
+impl fmt::Display for Puzzle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} to move and win in {}", self.player, self.moves)
+    }
+}
+
+/// Finds every puzzle in a game: positions where the player to move could win in exactly `moves` moves
+///
+/// # Examples
+///
+/// ```
+/// use my_game_project::game::GameState;
+/// use my_game_project::player::Player;
+/// use my_game_project::puzzle::find_in_history;
+///
+/// let game = GameState::new();
+/// let mut history = game.create_history();
+/// for (player, cell) in [(Player::X, (0, 0)), (Player::O, (1, 1)), (Player::X, (2, 2)), (Player::O, (0, 2))] {
+///     history.add_move(player, cell);
+/// }
+///
+/// let puzzles = find_in_history(&history, 2).unwrap();
+/// assert_eq!(puzzles.len(), 1);
+/// assert_eq!(puzzles[0].source, Some(history.game_id));
+/// ```
+pub fn find_in_history(history: &GameHistory, moves: usize) -> GameResult<Vec<Puzzle>> {
+    let mut puzzles = Vec::new();
+    for game in history.replay() {
+        if let Some(mut puzzle) = Puzzle::from_game(&game?, moves) {
+            puzzle.source = Some(history.game_id);
+            puzzles.push(puzzle);
+        }
+    }
+    Ok(puzzles)
+}
+
+/// The legal moves of `game`, collected so moves can be made while going through them
+fn legal_moves(game: &GameState) -> Vec<(usize, usize)> {
+    game.legal_moves().collect()
+}
+
+/// A bound on the positions a search for a win in `moves` moves visits
+fn search_cost(game: &GameState, moves: usize) -> u64 {
+    let plies = u32::try_from(2 * moves.max(1) - 1).unwrap_or(u32::MAX);
+    (game.legal_moves().count() as u64).saturating_pow(plies)
+}
+
+/// The fewest moves the player to move needs to force a win, if they can within `limit`
+fn win_distance(game: &mut GameState, limit: usize) -> Option<usize> {
+    (1..=limit).find(|&moves| forces_win(game, moves))
+}
+
+/// Returns true if the player to move can force a win within `moves` of their own moves
+fn forces_win(game: &mut GameState, moves: usize) -> bool {
+    moves > 0 && legal_moves(game).into_iter().any(|cell| wins_with(game, cell, moves))
+}
+
+/// Returns true if playing `cell` forces a win within `moves` moves, this one included
+fn wins_with(game: &mut GameState, (row, col): (usize, usize), moves: usize) -> bool {
+    let player = game.current_turn;
+    let undo = game.make_move_unchecked(row, col);
+    let wins = match game.status.winner() {
+        Some(winner) => winner == player,
+        None => !game.is_over() && moves > 1 && every_reply_loses(game, player, moves - 1),
+    };
+    game.unmake_move(undo);
+    wins
+}
+
+/// Returns true if `player` can force a win within `moves` moves after any reply
+fn every_reply_loses(game: &mut GameState, player: Player, moves: usize) -> bool {
+    legal_moves(game).into_iter().all(|(row, col)| {
+        let undo = game.make_move_unchecked(row, col);
+        let loses = match game.status.winner() {
+            Some(winner) => winner == player,
+            None => forces_win(game, moves),
+        };
+        game.unmake_move(undo);
+        loses
+    })
+}
+
+/// The reply that puts off the opponent's win longest, or escapes it, with the opponent having `moves` moves left
+///
+/// Among equally good replies the first in row-major order is chosen.
+fn defend(game: &mut GameState, moves: usize) -> Option<(usize, usize)> {
+    let attacker = game.current_turn.opponent();
+    legal_moves(game).into_iter().min_by_key(|&(row, col)| {
+        let undo = game.make_move_unchecked(row, col);
+        let holds_out = match game.status.winner() {
+            Some(winner) if winner == attacker => 0,
+            None if !game.is_over() => win_distance(game, moves).unwrap_or(usize::MAX),
+            _ => usize::MAX,
+        };
+        game.unmake_move(undo);
+        Reverse(holds_out)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_random_and_daily_puzzles_are_wins_in_two() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..5 {
+            let puzzle = Puzzle::random(&GameState::new(), 2, &mut rng).unwrap();
+            assert_eq!(puzzle.player, Player::X);
+            assert!(!puzzle.solutions().is_empty());
+
+            // Playing the first solution and then finishing off always wins
+            let mut moves = vec![puzzle.solutions()[0]];
+            let attempt = puzzle.attempt(&moves).unwrap();
+            assert!(attempt.correct && !attempt.solved);
+
+            let mut game = puzzle.game();
+            game.make_move(moves[0]).unwrap();
+            game.make_move(attempt.replies[0]).unwrap();
+            moves.push(Puzzle::from_game(&game, 1).unwrap().solutions()[0]);
+            assert!(puzzle.attempt(&moves).unwrap().solved);
+        }
+
+        let date = NaiveDate::from_ymd_opt(2024, 3, 14).unwrap();
+        assert_eq!(Puzzle::daily(date).unwrap(), Puzzle::daily(date).unwrap());
+    }
+}
@@ -0,0 +1,130 @@
+//! ELO ratings for players identified by an ID
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::GameStatus;
+use crate::player::Player;
+
+/// The rating a player starts with
+pub const INITIAL_RATING: f64 = 1200.0;
+
+/// The most a rating can change by in a single game
+pub const K_FACTOR: f64 = 32.0;
+
+/// A player's rating and record of results
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerRating {
+    /// The player's ID
+    pub player_id: String,
+    /// The player's ELO rating
+    pub rating: f64,
+    /// Number of games won
+    pub wins: u32,
+    /// Number of games lost
+    pub losses: u32,
+    /// Number of games drawn
+    pub draws: u32,
+}
+
+impl PlayerRating {
+    /// Create a rating for a player who hasn't played yet
+    pub fn new(player_id: impl Into<String>) -> Self {
+        Self {
+            player_id: player_id.into(),
+            rating: INITIAL_RATING,
+            wins: 0,
+            losses: 0,
+            draws: 0,
+        }
+    }
+
+    /// Total number of rated games played
+    pub fn games_played(&self) -> u32 {
+        self.wins + self.losses + self.draws
+    }
+
+    /// The score this player is expected to get against `opponent`, from 0 (loss) to 1 (win)
+    pub fn expected_score(&self, opponent: &PlayerRating) -> f64 {
+        1.0 / (1.0 + 10f64.powf((opponent.rating - self.rating) / 400.0))
+    }
+
+    /// Adjust the rating for an actual `score` (0, 0.5 or 1) against an opponent's rating
+    fn update(&mut self, score: f64, expected: f64) {
+        self.rating += K_FACTOR * (score - expected);
+
+        if score == 1.0 {
+            self.wins += 1;
+        } else if score == 0.0 {
+            self.losses += 1;
+        } else {
+            self.draws += 1;
+        }
+    }
+}
+
+/// Update the ratings of the X and O players for a finished game
+///
+/// Games that are still in progress leave both ratings unchanged.
+///
+/// # Examples
+///
+/// ```
+/// use my_game_project::game::GameStatus;
+/// use my_game_project::player::Player;
+/// use my_game_project::rating::{rate_game, PlayerRating, INITIAL_RATING};
+///
+/// let mut alice = PlayerRating::new("alice");
+/// let mut bob = PlayerRating::new("bob");
+/// rate_game(&mut alice, &mut bob, GameStatus::Won(Player::X));
+///
+/// assert!(alice.rating > INITIAL_RATING);
+/// assert_eq!(alice.rating + bob.rating, 2.0 * INITIAL_RATING);
+/// ```
+pub fn rate_game(x: &mut PlayerRating, o: &mut PlayerRating, status: GameStatus) {
+    let x_score = match status {
+        GameStatus::InProgress => return,
+        GameStatus::Draw => 0.5,
+        _ if status.winner() == Some(Player::X) => 1.0,
+        _ => 0.0,
+    };
+
+    let x_expected = x.expected_score(o);
+    let o_expected = o.expected_score(x);
+    x.update(x_score, x_expected);
+    o.update(1.0 - x_score, o_expected);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upset_gains_more_than_expected_win() {
+        let mut favourite = PlayerRating::new("favourite");
+        favourite.rating = 1600.0;
+        let mut underdog = PlayerRating::new("underdog");
+
+        assert!(favourite.expected_score(&underdog) > 0.9);
+
+        // Beating a much weaker player gains little, losing to them costs a lot
+        let (mut x, mut o) = (favourite.clone(), underdog.clone());
+        rate_game(&mut x, &mut o, GameStatus::Won(Player::X));
+        let expected_gain = x.rating - favourite.rating;
+
+        let (mut x, mut o) = (favourite.clone(), underdog.clone());
+        rate_game(&mut x, &mut o, GameStatus::Resigned(Player::X));
+        let upset_gain = o.rating - underdog.rating;
+
+        assert!(upset_gain > 5.0 * expected_gain);
+        assert_eq!((x.losses, o.wins), (1, 1));
+
+        // A draw moves the ratings towards each other
+        rate_game(&mut favourite, &mut underdog, GameStatus::Draw);
+        assert!(favourite.rating < 1600.0 && underdog.rating > INITIAL_RATING);
+        assert_eq!((favourite.draws, underdog.draws), (1, 1));
+
+        // Unfinished games aren't rated
+        rate_game(&mut favourite, &mut underdog, GameStatus::InProgress);
+        assert_eq!(favourite.games_played(), 1);
+    }
+}
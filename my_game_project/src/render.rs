@@ -0,0 +1,450 @@
+//! Pictures of the board, for terminals, web pages and chat bots
+//!
+//! A [`BoardRenderer`] draws a game as text, in plain ASCII, with Unicode
+//! box-drawing borders or as emoji, small or large and optionally colored
+//! with ANSI escape codes. [`svg`] draws a game as a standalone SVG image: the grid, the marks, and
+//! a line through the winning cells once the game is won. With the `png`
+//! feature, [`png`] rasterizes the same picture, and with the `gif` feature,
+//! [`replay_gif`] animates a whole game a move at a time.
+
+use std::fmt::Write;
+use std::io;
+use std::str::FromStr;
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::error::{GameError, GameResult};
+use crate::game::{Cell, GameState};
+use crate::history::GameHistory;
+use crate::player::Player;
+
+/// The width and height of a cell, in pixels
+pub const CELL_SIZE: usize = 100;
+
+/// The space around the grid, in pixels
+const MARGIN: usize = 10;
+
+/// How long each frame of an animated replay is shown, unless told otherwise
+pub const DEFAULT_FRAME_DELAY: Duration = Duration::from_millis(800);
+
+/// The background color
+const BACKGROUND: &str = "#ffffff";
+/// The color of the grid lines
+const GRID_COLOR: &str = "#333333";
+/// The color of X's marks
+const X_COLOR: &str = "#d62828";
+/// The color of O's marks
+const O_COLOR: &str = "#1d4ed8";
+/// The color of the line through the winning cells
+const WIN_COLOR: &str = "#2a9d8f";
+
+/// ANSI escape code starting X's marks, in bold red
+const ANSI_X: &str = "\x1b[1;31m";
+/// ANSI escape code starting O's marks, in bold blue
+const ANSI_O: &str = "\x1b[1;34m";
+/// ANSI escape code ending a colored mark
+const ANSI_RESET: &str = "\x1b[0m";
+
+/// How the text board is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    /// Dashes and bars, which any terminal can show
+    #[default]
+    Ascii,
+    /// Unicode box-drawing borders
+    Unicode,
+    /// Emoji marks on empty squares, without borders
+    Emoji,
+}
+
+impl FromStr for Theme {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "ascii" => Ok(Theme::Ascii),
+            "unicode" => Ok(Theme::Unicode),
+            "emoji" => Ok(Theme::Emoji),
+            _ => Err(GameError::InvalidConfig(format!("unknown theme {}", s))),
+        }
+    }
+}
+
+/// How big the text board is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BoardSize {
+    /// A line per row
+    #[default]
+    Small,
+    /// Three lines per row, with wider cells
+    Large,
+}
+
+impl BoardSize {
+    /// The width and height of a cell inside its borders, in characters
+    fn cell(self) -> (usize, usize) {
+        match self {
+            BoardSize::Small => (3, 1),
+            BoardSize::Large => (7, 3),
+        }
+    }
+}
+
+/// The characters a theme draws borders with
+struct Borders {
+    /// The line along a row
+    horizontal: char,
+    /// The line between cells in a row
+    vertical: char,
+    /// The left end, crossings and right end of the top, middle and bottom rules
+    rules: [[char; 3]; 3],
+}
+
+/// ASCII borders, all dashes and bars
+const ASCII_BORDERS: Borders = Borders { horizontal: '-', vertical: '|', rules: [['-'; 3]; 3] };
+
+/// Unicode box-drawing borders
+const UNICODE_BORDERS: Borders = Borders {
+    horizontal: '─',
+    vertical: '│',
+    rules: [['┌', '┬', '┐'], ['├', '┼', '┤'], ['└', '┴', '┘']],
+};
+
+/// Draws the board as text, for terminals and anything else that shows text
+///
+/// The default draws the same small ASCII board as
+/// [`GameState::render_board`]. CLI players choose a style with the
+/// `[board]` table of their config file, or `--theme`.
+///
+/// # Examples
+///
+/// ```
+/// use my_game_project::game::GameState;
+/// use my_game_project::render::{BoardRenderer, Theme};
+///
+/// let mut game = GameState::new();
+/// game.make_move((0, 0)).unwrap();
+///
+/// let style = BoardRenderer::default().with_theme(Theme::Unicode);
+/// assert!(game.render(&style).starts_with("┌───┬───┬───┐\n│ X │   │   │\n├───┼"));
+///
+/// let style = BoardRenderer::default().with_theme(Theme::Emoji);
+/// assert!(game.render(&style).starts_with("❌ ⬜ ⬜\n"));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BoardRenderer {
+    /// How the board is drawn
+    pub theme: Theme,
+    /// How big the board is drawn
+    pub size: BoardSize,
+    /// Whether X's and O's marks are colored with ANSI escape codes
+    pub color: bool,
+}
+
+impl BoardRenderer {
+    /// Draw the board with `theme`
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Draw the board at `size`
+    pub fn with_size(mut self, size: BoardSize) -> Self {
+        self.size = size;
+        self
+    }
+
+    /// Color X's and O's marks with ANSI escape codes
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Draws the board, one or more lines per row, each ending in a newline
+    pub fn render(&self, game: &GameState) -> String {
+        match self.theme {
+            Theme::Ascii => self.render_bordered(game, &ASCII_BORDERS),
+            Theme::Unicode => self.render_bordered(game, &UNICODE_BORDERS),
+            Theme::Emoji => self.render_emoji(game),
+        }
+    }
+
+    /// Draws the board with each cell inside borders
+    fn render_bordered(&self, game: &GameState, borders: &Borders) -> String {
+        let (width, height) = self.size.cell();
+        let rule = |[left, crossing, right]: [char; 3]| {
+            let segment = borders.horizontal.to_string().repeat(width);
+            let mut line = left.to_string();
+            line.push_str(&vec![segment; game.cols()].join(&crossing.to_string()));
+            line.push(right);
+            line.push('\n');
+            line
+        };
+
+        let mut out = rule(borders.rules[0]);
+        for (row, cells) in game.board.iter().enumerate() {
+            for line in 0..height {
+                out.push(borders.vertical);
+                for cell in cells {
+                    // The mark goes in the middle of the cell
+                    let mark = if line == height / 2 { self.mark(*cell) } else { " ".to_string() };
+                    let padding = (width - 1) / 2;
+                    let _ = write!(out, "{:padding$}{}{:padding$}", "", mark, "", padding = padding);
+                    out.push(borders.vertical);
+                }
+                out.push('\n');
+            }
+            let last = row + 1 == game.rows();
+            out.push_str(&rule(borders.rules[if last { 2 } else { 1 }]));
+        }
+        out
+    }
+
+    /// Draws the board as rows of emoji
+    fn render_emoji(&self, game: &GameState) -> String {
+        let (gap, row_gap) = match self.size {
+            BoardSize::Small => (" ", ""),
+            BoardSize::Large => ("  ", "\n"),
+        };
+        let rows: Vec<String> = game
+            .board
+            .iter()
+            .map(|cells| {
+                let cells: Vec<&str> = cells
+                    .iter()
+                    .map(|cell| match cell {
+                        Cell::Occupied(Player::X) => "❌",
+                        Cell::Occupied(Player::O) => "⭕",
+                        Cell::Empty => "⬜",
+                    })
+                    .collect();
+                format!("{}\n", cells.join(gap))
+            })
+            .collect();
+        rows.join(row_gap)
+    }
+
+    /// A cell's mark, colored if asked to be
+    fn mark(&self, cell: Cell) -> String {
+        match (cell, self.color) {
+            (Cell::Empty, _) => " ".to_string(),
+            (Cell::Occupied(Player::X), false) => "X".to_string(),
+            (Cell::Occupied(Player::O), false) => "O".to_string(),
+            (Cell::Occupied(Player::X), true) => format!("{}X{}", ANSI_X, ANSI_RESET),
+            (Cell::Occupied(Player::O), true) => format!("{}O{}", ANSI_O, ANSI_RESET),
+        }
+    }
+}
+
+/// The width and height of the picture of `game`, in pixels
+pub fn image_size(game: &GameState) -> (usize, usize) {
+    (game.cols() * CELL_SIZE + 2 * MARGIN, game.rows() * CELL_SIZE + 2 * MARGIN)
+}
+
+/// Draws the board as an SVG image
+///
+/// # Examples
+///
+/// ```
+/// use my_game_project::game::GameState;
+/// use my_game_project::render::svg;
+///
+/// let mut game = GameState::new();
+/// game.make_move((1, 1)).unwrap();
+///
+/// let image = svg(&game);
+/// assert!(image.starts_with("<svg"));
+/// assert_eq!(image.matches("<line").count(), 4 + 2);
+/// ```
+pub fn svg(game: &GameState) -> String {
+    let (width, height) = image_size(game);
+    let mut out = format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    );
+    out.push('\n');
+    let _ = writeln!(out, r#"<rect width="{}" height="{}" fill="{}"/>"#, width, height, BACKGROUND);
+
+    // The grid lines between the cells
+    let (left, top) = (MARGIN, MARGIN);
+    let (right, bottom) = (width - MARGIN, height - MARGIN);
+    for col in 1..game.cols() {
+        let x = left + col * CELL_SIZE;
+        line(&mut out, (x, top), (x, bottom), GRID_COLOR, 4);
+    }
+    for row in 1..game.rows() {
+        let y = top + row * CELL_SIZE;
+        line(&mut out, (left, y), (right, y), GRID_COLOR, 4);
+    }
+
+    // The marks, inset from the edges of their cells
+    let inset = CELL_SIZE / 5;
+    for (row, cells) in game.board.iter().enumerate() {
+        for (col, cell) in cells.iter().enumerate() {
+            let (x, y) = (left + col * CELL_SIZE, top + row * CELL_SIZE);
+            match cell {
+                Cell::Occupied(Player::X) => {
+                    line(&mut out, (x + inset, y + inset), (x + CELL_SIZE - inset, y + CELL_SIZE - inset), X_COLOR, 10);
+                    line(&mut out, (x + CELL_SIZE - inset, y + inset), (x + inset, y + CELL_SIZE - inset), X_COLOR, 10);
+                }
+                Cell::Occupied(Player::O) => {
+                    let _ = writeln!(
+                        out,
+                        r#"<circle cx="{}" cy="{}" r="{}" fill="none" stroke="{}" stroke-width="10"/>"#,
+                        x + CELL_SIZE / 2,
+                        y + CELL_SIZE / 2,
+                        CELL_SIZE / 2 - inset,
+                        O_COLOR
+                    );
+                }
+                Cell::Empty => {}
+            }
+        }
+    }
+
+    // A line from the centre of the first winning cell to the centre of the last
+    if let Some(cells) = &game.winning_line {
+        if let (Some(&first), Some(&last)) = (cells.first(), cells.last()) {
+            let centre = |(row, col): (usize, usize)| (left + col * CELL_SIZE + CELL_SIZE / 2, top + row * CELL_SIZE + CELL_SIZE / 2);
+            line(&mut out, centre(first), centre(last), WIN_COLOR, 12);
+        }
+    }
+
+    out.push_str("</svg>\n");
+    out
+}
+
+/// Adds a line with round ends from `from` to `to`
+fn line(out: &mut String, from: (usize, usize), to: (usize, usize), color: &str, width: usize) {
+    let _ = writeln!(
+        out,
+        r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="{}" stroke-linecap="round"/>"#,
+        from.0, from.1, to.0, to.1, color, width
+    );
+}
+
+/// Draws the board as a PNG image, the same picture as [`svg`]
+#[cfg(feature = "png")]
+pub fn png(game: &GameState) -> GameResult<Vec<u8>> {
+    rasterize(game)?
+        .encode_png()
+        .map_err(|e| GameError::SerializationError(e.to_string()))
+}
+
+/// Draws the board into a pixmap
+#[cfg(feature = "png")]
+pub(crate) fn rasterize(game: &GameState) -> GameResult<resvg::tiny_skia::Pixmap> {
+    let tree = resvg::usvg::Tree::from_str(&svg(game), &resvg::usvg::Options::default())
+        .map_err(|e| GameError::SerializationError(e.to_string()))?;
+    let (width, height) = image_size(game);
+    let mut pixmap = resvg::tiny_skia::Pixmap::new(width as u32, height as u32)
+        .ok_or_else(|| GameError::SerializationError(format!("can't draw a {}x{} image", width, height)))?;
+    resvg::render(&tree, resvg::tiny_skia::Transform::default(), &mut pixmap.as_mut());
+    Ok(pixmap)
+}
+
+/// Draws the board as a PNG image, which needs the `png` feature
+#[cfg(not(feature = "png"))]
+pub fn png(_game: &GameState) -> GameResult<Vec<u8>> {
+    Err(GameError::InvalidConfig(
+        "PNG images need the `png` feature; build with `--features png`".to_string(),
+    ))
+}
+
+/// Animates a game a move at a time as a GIF that loops forever
+///
+/// Every position of the replay, from the empty board to the last move, is
+/// a frame shown for `delay`. The final position is held three times as
+/// long, so it can be seen before the animation starts again.
+#[cfg(feature = "gif")]
+pub fn replay_gif<W: io::Write>(history: &GameHistory, delay: Duration, out: W) -> GameResult<()> {
+    let positions = history.replay().collect::<GameResult<Vec<_>>>()?;
+    let (width, height) = image_size(&positions[0]);
+    let (Ok(width), Ok(height)) = (u16::try_from(width), u16::try_from(height)) else {
+        return Err(GameError::InvalidConfig(format!("a {}x{} board is too big for a GIF", width, height)));
+    };
+
+    let gif_error = |e: gif::EncodingError| GameError::IoError(io::Error::other(e));
+    let mut encoder = gif::Encoder::new(out, width, height, &[]).map_err(gif_error)?;
+    encoder.set_repeat(gif::Repeat::Infinite).map_err(gif_error)?;
+
+    // GIFs count delays in hundredths of a second
+    let delay = u16::try_from(delay.as_millis() / 10).unwrap_or(u16::MAX);
+    let last = positions.len() - 1;
+    for (ply, game) in positions.iter().enumerate() {
+        let mut pixels = rasterize(game)?.take();
+        let mut frame = gif::Frame::from_rgba_speed(width, height, &mut pixels, 10);
+        frame.delay = if ply == last { delay.saturating_mul(3) } else { delay };
+        encoder.write_frame(&frame).map_err(gif_error)?;
+    }
+    Ok(())
+}
+
+/// Animates a game a move at a time as a GIF, which needs the `gif` feature
+#[cfg(not(feature = "gif"))]
+pub fn replay_gif<W: io::Write>(_history: &GameHistory, _delay: Duration, _out: W) -> GameResult<()> {
+    Err(GameError::InvalidConfig(
+        "GIF replays need the `gif` feature; build with `--features gif`".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_svg_draws_marks_and_winning_line() {
+        let mut game = GameState::new();
+        for cell in [(0, 0), (1, 0), (0, 1), (1, 1), (0, 2)] {
+            game.make_move(cell).unwrap();
+        }
+
+        let image = svg(&game);
+        assert!(image.contains(r#"width="320" height="320""#));
+        // Two grid lines each way, two strokes for each X and the winning line
+        assert_eq!(image.matches("<line").count(), 4 + 3 * 2 + 1);
+        assert_eq!(image.matches("<circle").count(), 2);
+        // The winning line runs across the top row
+        assert!(image.contains(&format!(r#"<line x1="60" y1="60" x2="260" y2="60" stroke="{}""#, WIN_COLOR)));
+    }
+
+    #[test]
+    fn test_large_colored_board() {
+        let mut game = GameState::new();
+        game.make_move((1, 1)).unwrap();
+
+        let style = BoardRenderer::default().with_size(BoardSize::Large).with_color(true);
+        let board = game.render(&style);
+        let lines: Vec<&str> = board.lines().collect();
+        assert_eq!(lines.len(), 3 * 3 + 4);
+        assert_eq!(lines[0], "-".repeat(3 * 8 + 1));
+        // The middle row's three lines follow the second rule, with the mark halfway down
+        assert_eq!(lines[5], "|       |       |       |");
+        assert_eq!(lines[6], format!("|       |   {}X{}   |       |", ANSI_X, ANSI_RESET));
+    }
+
+    #[cfg(feature = "gif")]
+    #[test]
+    fn test_replay_gif_has_a_frame_per_position() {
+        let mut game = crate::game::Game::new(GameState::new()).unwrap();
+        for cell in [(0, 0), (1, 1), (2, 2)] {
+            game.make_move(cell).unwrap();
+        }
+
+        let mut out = Vec::new();
+        replay_gif(game.history(), Duration::from_millis(500), &mut out).unwrap();
+
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::RGBA);
+        let mut decoder = options.read_info(out.as_slice()).unwrap();
+        assert_eq!((decoder.width(), decoder.height()), (320, 320));
+        let mut delays = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            delays.push(frame.delay);
+        }
+        assert_eq!(delays, vec![50, 50, 50, 150]);
+    }
+}
@@ -0,0 +1,190 @@
+//! Rules deciding when a game is won or drawn
+//!
+//! Each variant of the game implements [`GameRules`]. A [`GameState`] names
+//! its variant, and consults the variant's rules after every move.
+
+use std::fmt::Debug;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::{Cell, GameState, GameStatus};
+
+/// The row, column and both diagonal directions a line can run in
+pub const LINE_DIRECTIONS: [(isize, isize); 4] = [(0, 1), (1, 0), (1, 1), (1, -1)];
+
+/// The status of a game after a move, along with the line that won it, if any
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    /// The new status of the game
+    pub status: GameStatus,
+    /// The cells forming the winning line, if the move ended the game with one
+    pub winning_line: Option<Vec<(usize, usize)>>,
+}
+
+impl Outcome {
+    /// The game carries on
+    pub fn in_progress() -> Self {
+        Self {
+            status: GameStatus::InProgress,
+            winning_line: None,
+        }
+    }
+}
+
+/// The rules of a variant of the game
+pub trait GameRules: Debug + Send + Sync {
+    /// Decide the status of the game after a mark was placed at (`row`, `col`)
+    ///
+    /// The mark is already on the board and `game.current_turn` is still the
+    /// player who placed it.
+    fn outcome(&self, game: &GameState, row: usize, col: usize) -> Outcome;
+
+    /// Decide the status of the game after a mark was placed, without finding the winning line
+    ///
+    /// Searches call this for every move they try, so it shouldn't allocate.
+    /// By default it's the status of [`outcome`](Self::outcome).
+    fn status(&self, game: &GameState, row: usize, col: usize) -> GameStatus {
+        self.outcome(game, row, col).status
+    }
+
+    /// Returns the cell a mark played at (`row`, `col`) ends up in
+    ///
+    /// By default marks stay where they are played.
+    fn landing_cell(&self, _game: &GameState, row: usize, col: usize) -> (usize, usize) {
+        (row, col)
+    }
+
+    /// Returns true if completing a line wins the game, or false if it loses it
+    fn lines_win(&self) -> bool {
+        true
+    }
+}
+
+/// Standard rules: the first player to get `win_length` marks in a row wins,
+/// and a full board is a draw
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StandardRules;
+
+impl GameRules for StandardRules {
+    fn outcome(&self, game: &GameState, row: usize, col: usize) -> Outcome {
+        let player = game.current_turn;
+
+        // Check the row, the column and both diagonals through the last move
+        if let Some(line) = LINE_DIRECTIONS
+            .iter()
+            .map(|&(dr, dc)| game.line_through(row, col, dr, dc, player))
+            .find(|line| line.len() >= game.win_length)
+        {
+            return Outcome {
+                status: GameStatus::Won(player),
+                winning_line: Some(line),
+            };
+        }
+
+        // Check for draw (all cells filled)
+        if game.is_full() {
+            return Outcome {
+                status: GameStatus::Draw,
+                winning_line: None,
+            };
+        }
+
+        Outcome::in_progress()
+    }
+
+    fn status(&self, game: &GameState, row: usize, col: usize) -> GameStatus {
+        let player = game.current_turn;
+
+        if LINE_DIRECTIONS
+            .iter()
+            .any(|&(dr, dc)| game.run_length(row, col, dr, dc, player) >= game.win_length)
+        {
+            GameStatus::Won(player)
+        } else if game.is_full() {
+            GameStatus::Draw
+        } else {
+            GameStatus::InProgress
+        }
+    }
+}
+
+/// Connect Four rules: marks fall to the lowest empty row of their column,
+/// otherwise the standard rules apply
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GravityRules;
+
+impl GameRules for GravityRules {
+    fn outcome(&self, game: &GameState, row: usize, col: usize) -> Outcome {
+        StandardRules.outcome(game, row, col)
+    }
+
+    fn status(&self, game: &GameState, row: usize, col: usize) -> GameStatus {
+        StandardRules.status(game, row, col)
+    }
+
+    fn landing_cell(&self, game: &GameState, _row: usize, col: usize) -> (usize, usize) {
+        // A full column leaves the mark on its occupied top cell, so the move is rejected
+        let row = (0..game.rows())
+            .rev()
+            .find(|&row| game.board[row][col] == Cell::Empty)
+            .unwrap_or(0);
+        (row, col)
+    }
+}
+
+/// Misère rules: the first player to get `win_length` marks in a row loses
+///
+/// The game is won by the completing player's opponent, and the winning line
+/// is the line the loser completed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MisereRules;
+
+impl GameRules for MisereRules {
+    fn outcome(&self, game: &GameState, row: usize, col: usize) -> Outcome {
+        let outcome = StandardRules.outcome(game, row, col);
+        match outcome.status {
+            GameStatus::Won(player) => Outcome {
+                status: GameStatus::Won(player.opponent()),
+                ..outcome
+            },
+            _ => outcome,
+        }
+    }
+
+    fn status(&self, game: &GameState, row: usize, col: usize) -> GameStatus {
+        match StandardRules.status(game, row, col) {
+            GameStatus::Won(player) => GameStatus::Won(player.opponent()),
+            status => status,
+        }
+    }
+
+    fn lines_win(&self) -> bool {
+        false
+    }
+}
+
+/// The variants of the game a `GameState` can be played with
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Variant {
+    /// Standard tic-tac-toe rules
+    #[default]
+    Standard,
+    /// Connect Four: a 7x6 board with gravity where four in a row wins
+    ConnectFour,
+    /// Gomoku: a 15x15 board where five in a row wins
+    Gomoku,
+    /// Misère: completing a line loses instead of winning
+    Misere,
+}
+
+impl Variant {
+    /// Returns the rules this variant is played by
+    pub fn rules(self) -> &'static dyn GameRules {
+        match self {
+            Variant::Standard | Variant::Gomoku => &StandardRules,
+            Variant::ConnectFour => &GravityRules,
+            Variant::Misere => &MisereRules,
+        }
+    }
+}
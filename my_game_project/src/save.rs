@@ -0,0 +1,563 @@
+//! Save file formats
+//!
+//! Games and histories are saved as JSON by default. Files named with a
+//! `.msgpack` extension are saved as MessagePack instead, and a trailing `.gz`
+//! compresses either with gzip, e.g. `history_<id>.msgpack.gz`. Loading
+//! detects the format from the file's contents, whatever it is called.
+//!
+//! A game can also be saved in one `.ttt` file, a [`SaveBundle`] holding its
+//! state, its history and who played it as compressed MessagePack.
+//!
+//! Every save records the `schema_version` of the type it holds. Loading a
+//! file written by an older release runs the type's migrations on it first,
+//! so renamed or restructured fields still load.
+//!
+//! Saves are written to a temporary file that replaces the target only once
+//! it is safely on disk, so a crash mid-save leaves the previous save intact.
+//! A [`Footer`] can also be appended to catch saves corrupted afterwards.
+//!
+//! Without the `files` feature only the schema versions and migrations are
+//! built, for reading values saved by older releases through serde.
+
+#[cfg(feature = "files")]
+use std::fs::File;
+#[cfg(feature = "files")]
+use std::io::{self, Read, Write};
+#[cfg(feature = "files")]
+use std::path::Path;
+
+#[cfg(feature = "files")]
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+#[cfg(feature = "files")]
+use hmac::{Hmac, Mac};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+#[cfg(feature = "files")]
+use sha2::{Digest, Sha256};
+#[cfg(feature = "files")]
+use uuid::Uuid;
+
+use crate::error::{GameError, GameResult};
+use crate::game::GameState;
+use crate::history::GameHistory;
+use crate::player::Player;
+
+/// The bytes every gzip file starts with
+#[cfg(feature = "files")]
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// How a checksum footer starts
+#[cfg(feature = "files")]
+const CHECKSUM_FOOTER: &[u8] = b"\n--ttt-sha256:";
+
+/// How an HMAC footer starts
+#[cfg(feature = "files")]
+const HMAC_FOOTER: &[u8] = b"\n--ttt-hmac-sha256:";
+
+/// A line appended to a save with a digest of its contents, checked when it loads
+#[cfg(feature = "files")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Footer<'a> {
+    /// A SHA-256 checksum, which catches accidental corruption
+    Checksum,
+    /// An HMAC-SHA256 signature with this key, which also catches tampering
+    Hmac(&'a [u8]),
+}
+
+#[cfg(feature = "files")]
+impl Footer<'_> {
+    /// Returns the footer line for `contents`, starting with a newline
+    fn line(self, contents: &[u8]) -> Vec<u8> {
+        let (start, digest) = match self {
+            Footer::Checksum => (CHECKSUM_FOOTER, Sha256::digest(contents).to_vec()),
+            Footer::Hmac(key) => (HMAC_FOOTER, hmac(key, contents).finalize().into_bytes().to_vec()),
+        };
+        let hex: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+        [start, hex.as_bytes(), b"\n"].concat()
+    }
+}
+
+/// Returns an HMAC-SHA256 of `contents` keyed with `key`
+#[cfg(feature = "files")]
+fn hmac(key: &[u8], contents: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(contents);
+    mac
+}
+
+/// Strips the footer off a save, if it has one, checking it against the contents
+///
+/// `key` is needed to check an HMAC footer.
+#[cfg(feature = "files")]
+fn verify_footer<'a>(bytes: &'a [u8], key: Option<&[u8]>) -> GameResult<&'a [u8]> {
+    let corrupt = |reason: &str| GameError::CorruptSave(reason.to_string());
+    let Some(body) = bytes.strip_suffix(b"\n") else {
+        return Ok(bytes);
+    };
+    let line_start = body.iter().rposition(|&byte| byte == b'\n').unwrap_or(0);
+    let (contents, line) = bytes.split_at(line_start);
+    let line = &line[..line.len() - 1];
+
+    let footer = if let Some(hex) = line.strip_prefix(CHECKSUM_FOOTER) {
+        (Footer::Checksum, hex)
+    } else if let Some(hex) = line.strip_prefix(HMAC_FOOTER) {
+        let key = key.ok_or_else(|| corrupt("the save is signed, but no key was given to check it"))?;
+        (Footer::Hmac(key), hex)
+    } else {
+        return Ok(bytes);
+    };
+
+    let digest: Option<Vec<u8>> = footer
+        .1
+        .chunks(2)
+        .map(|pair| std::str::from_utf8(pair).ok().and_then(|pair| u8::from_str_radix(pair, 16).ok()))
+        .collect();
+    let digest = digest.ok_or_else(|| corrupt("the footer isn't a valid digest"))?;
+    let matches = match footer.0 {
+        Footer::Checksum => Sha256::digest(contents).as_slice() == digest.as_slice(),
+        Footer::Hmac(key) => hmac(key, contents).verify_slice(&digest).is_ok(),
+    };
+    if !matches {
+        return Err(corrupt("the contents don't match the footer"));
+    }
+    Ok(contents)
+}
+
+/// Writes `bytes` to `filename` through a temporary file, so the file is never left half-written
+#[cfg(feature = "files")]
+fn write_atomically(filename: &str, bytes: &[u8]) -> GameResult<()> {
+    let path = Path::new(filename);
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("'{}' isn't a file", filename)))?;
+    let temp = path.with_file_name(format!(".{}.{}.tmp", name.to_string_lossy(), Uuid::new_v4().simple()));
+
+    let written = File::create(&temp)
+        .and_then(|mut file| {
+            file.write_all(bytes)?;
+            file.sync_all()
+        })
+        .and_then(|()| std::fs::rename(&temp, path));
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&temp);
+        return Err(e.into());
+    }
+
+    // Make the rename itself durable, where the platform allows syncing a directory
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        let _ = File::open(dir).and_then(|dir| dir.sync_all());
+    }
+    Ok(())
+}
+
+/// The field holding a save's schema version
+pub const SCHEMA_VERSION_FIELD: &str = "schema_version";
+
+/// Upgrades a saved value by one schema version
+pub type Migration = fn(&mut Value) -> GameResult<()>;
+
+/// A type that can be saved to a file, and loaded from files older releases saved
+///
+/// Files saved before schema versions were recorded count as version 0.
+/// Whenever a change to the type would stop older saves from loading, add a
+/// migration that rewrites a save of the previous version to match it.
+pub trait Saved: Serialize + DeserializeOwned {
+    /// The migrations in order, where the one at index `n` upgrades version `n` to `n + 1`
+    const MIGRATIONS: &'static [Migration];
+
+    /// The schema version saves are written with
+    fn schema_version() -> u32 {
+        Self::MIGRATIONS.len() as u32
+    }
+
+    /// Upgrades a save from its recorded schema version to the current one and reads it
+    fn migrate(mut value: Value) -> GameResult<Self> {
+        let version = match value.as_object_mut().and_then(|fields| fields.remove(SCHEMA_VERSION_FIELD)) {
+            Some(version) => version
+                .as_u64()
+                .and_then(|version| u32::try_from(version).ok())
+                .ok_or_else(|| GameError::DeserializationError(format!("invalid schema version {}", version)))?,
+            None => 0,
+        };
+        if version > Self::schema_version() {
+            return Err(GameError::UnsupportedSchemaVersion(version));
+        }
+        for migration in &Self::MIGRATIONS[version as usize..] {
+            migration(&mut value)?;
+        }
+        serde_json::from_value(value).map_err(GameError::from)
+    }
+}
+
+/// A value written out with its schema version alongside its own fields
+#[cfg(feature = "files")]
+#[derive(Serialize)]
+struct VersionedSave<'a, T> {
+    schema_version: u32,
+    #[serde(flatten)]
+    value: &'a T,
+}
+
+/// Upgrades a save made before schema versions were recorded to version 1
+///
+/// There's nothing to change, as every field added before then has a default.
+pub(crate) fn unversioned(_: &mut Value) -> GameResult<()> {
+    Ok(())
+}
+
+/// Upgrades a version 1 save to version 2, which writes players, cells and
+/// statuses in lowercase, like `{"status": "won", "winner": "x"}`
+///
+/// There's nothing to change, as the forms version 1 used are still read. The
+/// new version makes older releases report newer saves as unsupported rather
+/// than failing to read them.
+pub(crate) fn lowercase_enums(_: &mut Value) -> GameResult<()> {
+    Ok(())
+}
+
+/// The extension of game bundles
+#[cfg(feature = "files")]
+pub const BUNDLE_EXTENSION: &str = "ttt";
+
+/// Who played one side of a saved game
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerInfo {
+    /// The side they played
+    pub player: Player,
+    /// The name they played under
+    pub name: String,
+    /// Whether they are a person rather than an AI
+    pub human: bool,
+}
+
+/// A game saved in a single file along with its history and players
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveBundle {
+    /// The state of the game
+    pub game: GameState,
+    /// How the game was played
+    pub history: GameHistory,
+    /// Who played the game, if known
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub players: Vec<PlayerInfo>,
+}
+
+impl Saved for SaveBundle {
+    const MIGRATIONS: &'static [Migration] = &[unversioned, lowercase_enums];
+}
+
+/// How values are encoded in a save file
+#[cfg(feature = "files")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    /// Pretty-printed JSON, or compact JSON when compressed
+    #[default]
+    Json,
+    /// MessagePack, with fields stored by name
+    MessagePack,
+}
+
+/// The format of a save file
+#[cfg(feature = "files")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SaveFormat {
+    /// How the value is encoded
+    pub encoding: Encoding,
+    /// Whether the encoded value is compressed with gzip
+    pub compressed: bool,
+}
+
+#[cfg(feature = "files")]
+impl SaveFormat {
+    /// Returns the format for a file name: MessagePack for `.msgpack` files
+    /// and JSON otherwise, compressed if the name ends in `.gz`
+    ///
+    /// Bundles are always compressed MessagePack.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::save::{Encoding, SaveFormat};
+    ///
+    /// assert_eq!(SaveFormat::from_path("history.json"), SaveFormat::default());
+    /// let format = SaveFormat::from_path("history.msgpack.gz");
+    /// assert_eq!(format.encoding, Encoding::MessagePack);
+    /// assert!(format.compressed);
+    /// ```
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        if Self::is_bundle(path) {
+            return Self { encoding: Encoding::MessagePack, compressed: true };
+        }
+        let compressed = path.extension().is_some_and(|extension| extension == "gz");
+        let path = if compressed { Path::new(path.file_stem().unwrap_or_default()) } else { path };
+        let encoding = match path.extension() {
+            Some(extension) if extension == "msgpack" => Encoding::MessagePack,
+            _ => Encoding::Json,
+        };
+        Self { encoding, compressed }
+    }
+
+    /// Returns true if `path` is named like a game bundle
+    pub fn is_bundle(path: impl AsRef<Path>) -> bool {
+        path.as_ref().extension().is_some_and(|extension| extension == BUNDLE_EXTENSION)
+    }
+
+    /// Returns true if `path` is named like a save file: `.json` or `.msgpack`,
+    /// optionally gzipped, or a bundle
+    pub fn is_save_file(path: impl AsRef<Path>) -> bool {
+        let path = path.as_ref();
+        if Self::is_bundle(path) {
+            return true;
+        }
+        let path = match path.extension() {
+            Some(extension) if extension == "gz" => Path::new(path.file_stem().unwrap_or_default()),
+            _ => path,
+        };
+        path.extension().is_some_and(|extension| extension == "json" || extension == "msgpack")
+    }
+
+    /// Encodes `value` in this format, along with its schema version
+    pub fn encode<T: Saved>(self, value: &T) -> GameResult<Vec<u8>> {
+        let error = |e: String| GameError::SerializationError(e);
+        let value = &VersionedSave { schema_version: T::schema_version(), value };
+        let bytes = match (self.encoding, self.compressed) {
+            (Encoding::Json, false) => serde_json::to_vec_pretty(value)?,
+            (Encoding::Json, true) => serde_json::to_vec(value)?,
+            // Go through JSON's data model, which keeps flattened and optional fields intact
+            (Encoding::MessagePack, _) => {
+                let value = serde_json::to_value(value)?;
+                rmp_serde::to_vec(&value).map_err(|e| error(e.to_string()))?
+            }
+        };
+        if !self.compressed {
+            return Ok(bytes);
+        }
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&bytes).map_err(|e| error(e.to_string()))?;
+        encoder.finish().map_err(|e| error(e.to_string()))
+    }
+}
+
+/// Decodes a value saved in any [`SaveFormat`], detecting which from the
+/// bytes, and migrates it to the current schema version
+///
+/// # Examples
+///
+/// ```
+/// use my_game_project::game::GameState;
+/// use my_game_project::save::{decode, Encoding, SaveFormat};
+///
+/// let game = GameState::new();
+/// let format = SaveFormat { encoding: Encoding::MessagePack, compressed: true };
+/// let loaded: GameState = decode(&format.encode(&game).unwrap()).unwrap();
+/// assert_eq!(loaded.id, game.id);
+/// ```
+#[cfg(feature = "files")]
+pub fn decode<T: Saved>(bytes: &[u8]) -> GameResult<T> {
+    let error = |e: String| GameError::DeserializationError(e);
+    let mut decompressed = Vec::new();
+    let bytes = if bytes.starts_with(&GZIP_MAGIC) {
+        GzDecoder::new(bytes).read_to_end(&mut decompressed).map_err(|e| error(e.to_string()))?;
+        &decompressed[..]
+    } else {
+        bytes
+    };
+
+    // JSON saves are objects, which never start a MessagePack map
+    let value: Value = match bytes.iter().find(|byte| !byte.is_ascii_whitespace()) {
+        Some(b'{') => serde_json::from_slice(bytes)?,
+        _ => rmp_serde::from_slice(bytes).map_err(|e| error(e.to_string()))?,
+    };
+    T::migrate(value)
+}
+
+/// Saves `value` to `filename` in the format its name calls for
+#[cfg(feature = "files")]
+pub fn save_to_file<T: Saved>(value: &T, filename: &str) -> GameResult<()> {
+    write_atomically(filename, &SaveFormat::from_path(filename).encode(value)?)
+}
+
+/// Saves `value` to `filename` like [`save_to_file`], followed by `footer`
+///
+/// # Examples
+///
+/// ```no_run
+/// use my_game_project::game::GameState;
+/// use my_game_project::save::{load_from_file_with_key, save_to_file_with_footer, Footer};
+///
+/// let game = GameState::new();
+/// save_to_file_with_footer(&game, "game.json", Footer::Hmac(b"secret")).unwrap();
+/// let loaded: GameState = load_from_file_with_key("game.json", b"secret").unwrap();
+/// ```
+#[cfg(feature = "files")]
+pub fn save_to_file_with_footer<T: Saved>(value: &T, filename: &str, footer: Footer) -> GameResult<()> {
+    let mut bytes = SaveFormat::from_path(filename).encode(value)?;
+    bytes.extend(footer.line(&bytes));
+    write_atomically(filename, &bytes)
+}
+
+/// Loads a value from `filename`, whichever format it was saved in
+///
+/// A checksum footer is checked if the save has one, failing with
+/// [`GameError::CorruptSave`] if it doesn't match. Signed saves need
+/// [`load_from_file_with_key`].
+#[cfg(feature = "files")]
+pub fn load_from_file<T: Saved>(filename: &str) -> GameResult<T> {
+    load(filename, None)
+}
+
+/// Loads a value from `filename` like [`load_from_file`], checking an HMAC footer with `key`
+#[cfg(feature = "files")]
+pub fn load_from_file_with_key<T: Saved>(filename: &str, key: &[u8]) -> GameResult<T> {
+    load(filename, Some(key))
+}
+
+/// Loads a value from `filename`, checking any footer
+#[cfg(feature = "files")]
+fn load<T: Saved>(filename: &str, key: Option<&[u8]>) -> GameResult<T> {
+    let bytes = std::fs::read(filename)?;
+    decode(verify_footer(&bytes, key)?)
+}
+
+#[cfg(all(test, feature = "files"))]
+mod tests {
+    use super::*;
+    use crate::game::{Cell, Game, GameStatus};
+
+    #[test]
+    fn test_save_formats_round_trip() {
+        let mut game = GameState::new();
+        game.make_move((1, 1)).unwrap();
+        let mut history = game.create_history();
+        history.add_move(Player::X, (1, 1));
+        history.annotate(0, "the centre").unwrap();
+
+        let json = SaveFormat::default().encode(&history).unwrap();
+        for encoding in [Encoding::Json, Encoding::MessagePack] {
+            for compressed in [false, true] {
+                let format = SaveFormat { encoding, compressed };
+                let loaded: GameHistory = decode(&format.encode(&history).unwrap()).unwrap();
+                assert_eq!(loaded.moves, history.moves, "{:?}", format);
+
+                let loaded: GameState = decode(&format.encode(&game).unwrap()).unwrap();
+                assert_eq!(loaded.board, game.board, "{:?}", format);
+            }
+        }
+
+        let packed = SaveFormat { encoding: Encoding::MessagePack, compressed: true }.encode(&history).unwrap();
+        assert!(packed.len() < json.len());
+
+        // Errors keep what caused them, for matching on
+        assert!(matches!(decode::<GameHistory>(b"not a save"), Err(GameError::JsonError(e)) if e.is_data()));
+        let missing = load_from_file::<GameState>("no-such-save.json");
+        assert!(matches!(missing, Err(GameError::IoError(e)) if e.kind() == io::ErrorKind::NotFound));
+        assert!(matches!(decode::<GameHistory>(b"{ not json"), Err(GameError::JsonError(e)) if e.is_syntax()));
+    }
+
+    /// A type whose `name` field was renamed to `title` in version 2
+    #[derive(Debug, PartialEq, Serialize, serde::Deserialize)]
+    struct Renamed {
+        title: String,
+    }
+
+    impl Saved for Renamed {
+        const MIGRATIONS: &'static [Migration] = &[unversioned, |value| {
+            if let Some(fields) = value.as_object_mut() {
+                let name = fields.remove("name").unwrap_or_default();
+                fields.insert("title".to_string(), name);
+            }
+            Ok(())
+        }];
+    }
+
+    #[test]
+    fn test_footers() {
+        let dir = std::env::temp_dir().join(format!("ttt-footers-{}", Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+        let game = GameState::new();
+
+        save_to_file_with_footer(&game, &path("checked.json.gz"), Footer::Checksum).unwrap();
+        assert_eq!(load_from_file::<GameState>(&path("checked.json.gz")).unwrap().id, game.id);
+
+        save_to_file_with_footer(&game, &path("signed.json"), Footer::Hmac(b"key")).unwrap();
+        assert_eq!(load_from_file_with_key::<GameState>(&path("signed.json"), b"key").unwrap().id, game.id);
+        for result in [
+            load_from_file::<GameState>(&path("signed.json")),
+            load_from_file_with_key::<GameState>(&path("signed.json"), b"other key"),
+        ] {
+            assert!(matches!(result, Err(GameError::CorruptSave(_))));
+        }
+
+        // Flip a bit in the middle of the save
+        let mut bytes = std::fs::read(path("checked.json.gz")).unwrap();
+        let middle = bytes.len() / 2;
+        bytes[middle] ^= 1;
+        std::fs::write(path("checked.json.gz"), bytes).unwrap();
+        assert!(matches!(load_from_file::<GameState>(&path("checked.json.gz")), Err(GameError::CorruptSave(_))));
+
+        // Nothing is left behind but the saves themselves
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_bundles() {
+        let dir = std::env::temp_dir().join(format!("ttt-bundles-{}", Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        let path = dir.join("game.ttt").to_string_lossy().into_owned();
+
+        let mut game = Game::new(GameState::new()).unwrap();
+        game.make_move((1, 1)).unwrap();
+        let players = [PlayerInfo { player: Player::X, name: "Ada".to_string(), human: true }];
+        game.save_bundle(&path, &players).unwrap();
+
+        let (loaded, loaded_players) = Game::load_bundle(&path).unwrap();
+        assert_eq!(loaded.history().moves, game.history().moves);
+        assert_eq!(loaded_players, players);
+        assert_eq!(GameHistory::load_from_file(&path).unwrap().moves.len(), 1);
+        assert_eq!(GameState::load_from_file(&path).unwrap().board, game.board);
+
+        // A bundle whose history doesn't match its game is refused
+        let mut bundle: SaveBundle = load_from_file(&path).unwrap();
+        bundle.history.moves.clear();
+        save_to_file(&bundle, &path).unwrap();
+        assert!(matches!(Game::load_bundle(&path), Err(GameError::InvalidImport(_))));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_schema_migrations() {
+        let saved = SaveFormat::default().encode(&Renamed { title: "game".to_string() }).unwrap();
+        assert!(String::from_utf8(saved.clone()).unwrap().contains("\"schema_version\": 2"));
+        assert_eq!(decode::<Renamed>(&saved).unwrap().title, "game");
+
+        // Older saves are upgraded, whether or not they recorded a version
+        for old in [r#"{"name": "old"}"#, r#"{"schema_version": 1, "name": "old"}"#] {
+            assert_eq!(decode::<Renamed>(old.as_bytes()).unwrap().title, "old");
+        }
+        assert!(matches!(
+            decode::<Renamed>(br#"{"schema_version": 3, "title": "new"}"#),
+            Err(GameError::UnsupportedSchemaVersion(3))
+        ));
+
+        // Games saved before versions were recorded still load
+        let unversioned = serde_json::to_vec(&GameState::new()).unwrap();
+        assert!(decode::<GameState>(&unversioned).is_ok());
+
+        // As do games saved with uppercase players, cells and statuses
+        let mut old = serde_json::to_value(GameState::new()).unwrap();
+        old["schema_version"] = 1.into();
+        old["board"][1][1] = serde_json::json!({"Occupied": "X"});
+        old["board"][0][0] = "Empty".into();
+        old["status"] = serde_json::json!({"Resigned": "O"});
+        old["current_turn"] = "O".into();
+        let game = decode::<GameState>(&serde_json::to_vec(&old).unwrap()).unwrap();
+        assert_eq!(game.board[1][1], Cell::Occupied(Player::X));
+        assert_eq!(game.status, GameStatus::Resigned(Player::O));
+        assert_eq!(game.current_turn, Player::O);
+    }
+}
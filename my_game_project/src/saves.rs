@@ -0,0 +1,187 @@
+//! Save slots for the CLI
+//!
+//! Games are kept as bundles in one saves directory, named after the game's
+//! ID, so they can be listed, loaded and deleted without typing file names.
+
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::error::{GameError, GameResult};
+use crate::game::{Game, GameStatus};
+use crate::save::{self, PlayerInfo, SaveBundle, SaveFormat, BUNDLE_EXTENSION};
+
+/// Directory games are saved in unless configured otherwise
+pub const DEFAULT_SAVES_DIR: &str = "saves";
+
+/// A saved game, as listed by [`SaveSlots::list`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SaveSlot {
+    /// Where the game is saved
+    pub path: PathBuf,
+    /// The unique identifier of the game
+    pub game_id: Uuid,
+    /// How the game stood when it was saved
+    pub status: GameStatus,
+    /// The number of moves played
+    pub moves: usize,
+    /// Who played the game, if known
+    pub players: Vec<PlayerInfo>,
+    /// When the game was last saved
+    pub saved_at: DateTime<Utc>,
+}
+
+impl SaveSlot {
+    /// Returns the players' names as `X vs O`, or `unknown players` if they weren't saved
+    pub fn matchup(&self) -> String {
+        if self.players.is_empty() {
+            return "unknown players".to_string();
+        }
+        let names: Vec<&str> = self.players.iter().map(|player| player.name.as_str()).collect();
+        names.join(" vs ")
+    }
+}
+
+/// The games in a saves directory
+#[derive(Debug, Clone, PartialEq)]
+pub struct SaveSlots {
+    /// The directory the games are saved in
+    dir: PathBuf,
+}
+
+impl SaveSlots {
+    /// Manages the games saved in `dir`, which is created when the first game is saved
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Returns the directory the games are saved in
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the file the game with this ID is saved in
+    pub fn path_for(&self, game_id: Uuid) -> PathBuf {
+        self.dir.join(format!("game_{}.{}", game_id, BUNDLE_EXTENSION))
+    }
+
+    /// Saves a game into its slot, replacing any earlier save of it
+    pub fn save(&self, game: &Game, players: &[PlayerInfo]) -> GameResult<()> {
+        std::fs::create_dir_all(&self.dir)?;
+        game.save_bundle(&self.path_for(game.state().id).to_string_lossy(), players)
+    }
+
+    /// Lists the saved games, most recently saved first
+    ///
+    /// Files that can't be read as bundles are left out, and a directory that
+    /// doesn't exist yet has no games in it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::game::{Game, GameState};
+    /// use my_game_project::saves::SaveSlots;
+    ///
+    /// let slots = SaveSlots::new(std::env::temp_dir().join(uuid::Uuid::new_v4().to_string()));
+    /// assert!(slots.list().unwrap().is_empty());
+    ///
+    /// let game = Game::new(GameState::new()).unwrap();
+    /// slots.save(&game, &[]).unwrap();
+    /// assert_eq!(slots.list().unwrap()[0].game_id, game.state().id);
+    /// # std::fs::remove_dir_all(slots.dir()).unwrap();
+    /// ```
+    pub fn list(&self) -> GameResult<Vec<SaveSlot>> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut slots = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            let path = entry.path();
+            if !SaveFormat::is_bundle(&path) {
+                continue;
+            }
+            let Ok(bundle) = save::load_from_file::<SaveBundle>(&path.to_string_lossy()) else {
+                continue;
+            };
+            let saved_at = entry
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .map(DateTime::<Utc>::from)?;
+            slots.push(SaveSlot {
+                path,
+                game_id: bundle.game.id,
+                status: bundle.game.status,
+                moves: bundle.history.moves.len(),
+                players: bundle.players,
+                saved_at,
+            });
+        }
+
+        slots.sort_by(|a, b| b.saved_at.cmp(&a.saved_at).then_with(|| a.game_id.cmp(&b.game_id)));
+        Ok(slots)
+    }
+
+    /// Loads a saved game along with its players
+    pub fn load(&self, slot: &SaveSlot) -> GameResult<(Game, Vec<PlayerInfo>)> {
+        Game::load_bundle(&slot.path.to_string_lossy())
+    }
+
+    /// Loads the game with this ID along with its players
+    pub fn load_id(&self, game_id: Uuid) -> GameResult<(Game, Vec<PlayerInfo>)> {
+        let path = self.path_for(game_id);
+        if !path.exists() {
+            return Err(GameError::GameNotFound(game_id.to_string()));
+        }
+        Game::load_bundle(&path.to_string_lossy())
+    }
+
+    /// Deletes a saved game
+    pub fn delete(&self, slot: &SaveSlot) -> GameResult<()> {
+        std::fs::remove_file(&slot.path).map_err(GameError::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+    use crate::player::Player;
+
+    #[test]
+    fn test_save_slots() {
+        let slots = SaveSlots::new(std::env::temp_dir().join(format!("ttt-slots-{}", Uuid::new_v4())));
+        let mut first = Game::new(GameState::new()).unwrap();
+        first.make_move((1, 1)).unwrap();
+        let players = [
+            PlayerInfo { player: Player::X, name: "Ada".to_string(), human: true },
+            PlayerInfo { player: Player::O, name: "AI (Hard)".to_string(), human: false },
+        ];
+        slots.save(&first, &players).unwrap();
+        slots.save(&Game::new(GameState::new()).unwrap(), &[]).unwrap();
+
+        // Anything else in the directory is ignored
+        std::fs::write(slots.dir().join("notes.txt"), "not a save").unwrap();
+        std::fs::write(slots.dir().join("broken.ttt"), "not a bundle").unwrap();
+
+        let listed = slots.list().unwrap();
+        assert_eq!(listed.len(), 2);
+        let saved = listed.iter().find(|slot| slot.game_id == first.state().id).unwrap();
+        assert_eq!((saved.moves, saved.status), (1, GameStatus::InProgress));
+        assert_eq!(saved.matchup(), "Ada vs AI (Hard)");
+
+        let (loaded, _) = slots.load(saved).unwrap();
+        assert_eq!(loaded.state().board, first.state().board);
+        let (_, loaded_players) = slots.load_id(first.state().id).unwrap();
+        assert_eq!(loaded_players, players);
+
+        slots.delete(saved).unwrap();
+        assert_eq!(slots.list().unwrap().len(), 1);
+        assert!(matches!(slots.load_id(first.state().id), Err(GameError::GameNotFound(_))));
+        std::fs::remove_dir_all(slots.dir()).unwrap();
+    }
+}
@@ -0,0 +1,168 @@
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Path, State},
+    http::request::Parts,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::GameError;
+use crate::player::Player;
+use crate::server::auth::AuthUser;
+use crate::server::error::ApiError;
+use crate::server::metrics::{AiSearch, AiUsage, Metrics};
+use crate::server::state::{AppState, GameRecord};
+
+/// Number of AI searches listed in the statistics
+pub const SLOWEST_AI_SEARCHES: usize = 10;
+
+/// A logged-in user who is allowed to call the admin API
+///
+/// Extracting it rejects requests from anyone who isn't logged in as one of
+/// the configured admins.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdminUser(pub AuthUser);
+
+#[async_trait]
+impl FromRequestParts<AppState> for AdminUser {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let user = AuthUser::from_request_parts(parts, state).await?;
+        if !state.admins.contains(&user.username) {
+            return Err(GameError::Forbidden(format!("{} is not an admin", user.username)).into());
+        }
+        Ok(Self(user))
+    }
+}
+
+/// Memory taken by the games the server holds, measured as the size of their JSON
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct MemoryUsage {
+    /// Bytes taken by the games' states
+    pub games_bytes: usize,
+    /// Bytes taken by the games' histories
+    pub histories_bytes: usize,
+}
+
+/// Statistics about the running server
+#[derive(Debug, Serialize)]
+pub struct AdminStats {
+    /// Number of games the server holds
+    pub total_games: usize,
+    /// Number of those games still in progress
+    pub active_games: usize,
+    /// Number of best-of-N matches
+    pub matches: usize,
+    /// Roughly how much memory the games take up
+    pub memory: MemoryUsage,
+    /// The number of moves the AI has chosen at each difficulty
+    pub ai_moves: AiUsage,
+    /// The slowest of the most recent AI searches, slowest first
+    pub slowest_ai_searches: Vec<AiSearch>,
+}
+
+/// Request for ending a game
+#[derive(Debug, Default, Deserialize)]
+pub struct FinishGameRequest {
+    /// The player to declare the winner; the game is drawn if not given
+    #[serde(default)]
+    pub winner: Option<Player>,
+}
+
+/// Get statistics about the running server
+pub async fn get_stats(State(state): State<AppState>, _: AdminUser) -> Json<AdminStats> {
+    let games = state.games.read().await;
+    let total_games = games.len();
+    let active_games = games.values().filter(|record| !record.game.is_over()).count();
+    let games_bytes = games.values().map(json_size).sum();
+    drop(games);
+
+    let histories_bytes = state.histories.read().await.values().map(json_size).sum();
+    let metrics = Metrics::get();
+
+    Json(AdminStats {
+        total_games,
+        active_games,
+        matches: state.matches.read().await.len(),
+        memory: MemoryUsage { games_bytes, histories_bytes },
+        ai_moves: metrics.ai_usage(),
+        slowest_ai_searches: metrics.slowest_ai_searches(SLOWEST_AI_SEARCHES),
+    })
+}
+
+/// End a game straight away, whoever's turn it is
+pub async fn finish_game(
+    State(state): State<AppState>,
+    _: AdminUser,
+    Path(id): Path<Uuid>,
+    request: Option<Json<FinishGameRequest>>,
+) -> Result<Json<GameRecord>, ApiError> {
+    let request = request.map(|Json(request)| request).unwrap_or_default();
+    let record = state.force_finish(id, request.winner).await?;
+    Ok(Json(record.public()))
+}
+
+/// The size of a value's JSON, in bytes
+fn json_size(value: &impl Serialize) -> usize {
+    serde_json::to_vec(value).map_or(0, |json| json.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{GameState, GameStatus};
+    use crate::server::auth::{TokenIssuer, User};
+    use crate::server::handlers;
+    use axum::http::{header, Request, StatusCode};
+    use chrono::Utc;
+
+    /// The request parts of a call made by `username`
+    fn parts_for(state: &AppState, username: &str) -> Parts {
+        let user = User {
+            id: Uuid::new_v4(),
+            username: username.to_string(),
+            password_hash: String::new(),
+            created_at: Utc::now(),
+        };
+        let token = state.auth.issue(&user).unwrap();
+        let request = Request::get("/admin/stats")
+            .header(header::AUTHORIZATION, format!("Bearer {}", token))
+            .body(())
+            .unwrap();
+        request.into_parts().0
+    }
+
+    #[tokio::test]
+    async fn test_admin_only() {
+        let state = AppState::new()
+            .with_token_issuer(TokenIssuer::random(chrono::Duration::hours(1)))
+            .with_admins(["root".to_string()]);
+
+        let err = AdminUser::from_request_parts(&mut parts_for(&state, "guest"), &state).await;
+        assert_eq!(err.unwrap_err().code, "FORBIDDEN");
+        let admin = AdminUser::from_request_parts(&mut parts_for(&state, "root"), &state).await;
+        let admin = admin.unwrap();
+
+        let id = state.insert_game(GameRecord::new(GameState::new())).await.unwrap().game.id;
+        let Json(stats) = get_stats(State(state.clone()), admin.clone()).await;
+        assert_eq!((stats.total_games, stats.active_games), (1, 1));
+        assert!(stats.memory.games_bytes > 0);
+
+        let request = FinishGameRequest { winner: Some(Player::O) };
+        let Json(record) = finish_game(State(state.clone()), admin.clone(), Path(id), Some(Json(request)))
+            .await
+            .unwrap();
+        assert_eq!(record.game.status, GameStatus::Won(Player::O));
+        assert_eq!(record.game.version, 0);
+        assert!(state.histories.read().await[&id].final_status.is_some());
+
+        // Deleting games is for admins too, so anonymous requests can't
+        let mut anonymous = Request::delete("/games").body(()).unwrap().into_parts().0;
+        assert!(AdminUser::from_request_parts(&mut anonymous, &state).await.is_err());
+        let status = handlers::delete_game(State(state.clone()), admin, Path(id)).await.unwrap();
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert!(state.games.read().await.is_empty());
+    }
+}
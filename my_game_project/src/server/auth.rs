@@ -0,0 +1,393 @@
+use std::fmt;
+
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, State},
+    http::{header, request::Parts, StatusCode},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::{GameError, GameResult};
+use crate::server::error::ApiError;
+use crate::server::handlers::GameSummary;
+use crate::server::matchmaking::Seat;
+use crate::server::state::AppState;
+
+/// How long a login lasts unless the server is configured otherwise
+pub const DEFAULT_TOKEN_TTL: chrono::Duration = chrono::Duration::hours(24);
+
+/// Shortest password a user may register with
+const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// Longest username a user may register with
+const MAX_USERNAME_LENGTH: usize = 32;
+
+/// A registered account
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct User {
+    /// Unique ID of the user
+    pub id: Uuid,
+    /// The name the user logs in with
+    pub username: String,
+    /// Argon2 hash of the user's password
+    pub password_hash: String,
+    /// When the user registered
+    pub created_at: DateTime<Utc>,
+}
+
+/// The parts of a user that are safe to show to clients
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UserProfile {
+    /// Unique ID of the user
+    pub id: Uuid,
+    /// The name the user logs in with
+    pub username: String,
+    /// When the user registered
+    pub created_at: DateTime<Utc>,
+}
+
+impl User {
+    /// The user's public profile
+    pub fn profile(&self) -> UserProfile {
+        UserProfile {
+            id: self.id,
+            username: self.username.clone(),
+            created_at: self.created_at,
+        }
+    }
+}
+
+/// The claims carried by a login token
+#[derive(Debug, Serialize, Deserialize)]
+struct Claims {
+    /// ID of the logged-in user
+    sub: Uuid,
+    /// Username of the logged-in user
+    name: String,
+    /// When the token was issued, in seconds since the epoch
+    iat: i64,
+    /// When the token expires, in seconds since the epoch
+    exp: i64,
+}
+
+/// Signs and checks the JWTs given to logged-in users
+#[derive(Clone)]
+pub struct TokenIssuer {
+    /// Key tokens are signed with
+    encoding: EncodingKey,
+    /// Key signatures are checked with
+    decoding: DecodingKey,
+    /// How long each token lasts
+    ttl: chrono::Duration,
+}
+
+impl fmt::Debug for TokenIssuer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenIssuer").field("ttl", &self.ttl).finish_non_exhaustive()
+    }
+}
+
+impl TokenIssuer {
+    /// Sign tokens lasting `ttl` with the HMAC key `secret`
+    pub fn new(secret: &[u8], ttl: chrono::Duration) -> Self {
+        Self {
+            encoding: EncodingKey::from_secret(secret),
+            decoding: DecodingKey::from_secret(secret),
+            ttl,
+        }
+    }
+
+    /// Sign tokens with a random key, so they stop working when the server restarts
+    pub fn random(ttl: chrono::Duration) -> Self {
+        let mut secret = [0; 32];
+        rand::thread_rng().fill_bytes(&mut secret);
+        Self::new(&secret, ttl)
+    }
+
+    /// Issue a token logging in `user`
+    pub fn issue(&self, user: &User) -> GameResult<String> {
+        let now = Utc::now();
+        let claims = Claims {
+            sub: user.id,
+            name: user.username.clone(),
+            iat: now.timestamp(),
+            exp: (now + self.ttl).timestamp(),
+        };
+
+        jsonwebtoken::encode(&Header::default(), &claims, &self.encoding)
+            .map_err(|e| GameError::SerializationError(e.to_string()))
+    }
+
+    /// Check a token's signature and expiry, returning the user it logs in
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::server::{TokenIssuer, DEFAULT_TOKEN_TTL};
+    ///
+    /// let issuer = TokenIssuer::random(DEFAULT_TOKEN_TTL);
+    /// assert!(issuer.verify("not.a.token").is_err());
+    /// ```
+    pub fn verify(&self, token: &str) -> GameResult<AuthUser> {
+        let data = jsonwebtoken::decode::<Claims>(token, &self.decoding, &Validation::default())
+            .map_err(|e| GameError::Unauthenticated(e.to_string()))?;
+
+        Ok(AuthUser {
+            id: data.claims.sub,
+            username: data.claims.name,
+        })
+    }
+}
+
+/// The logged-in user a request was made by
+///
+/// Extracting it rejects requests without a valid `Authorization: Bearer`
+/// token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthUser {
+    /// ID of the user
+    pub id: Uuid,
+    /// Username of the user
+    pub username: String,
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for AuthUser {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        MaybeUser::from_request_parts(parts, state)
+            .await?
+            .0
+            .ok_or_else(|| GameError::Unauthenticated("missing bearer token".to_string()).into())
+    }
+}
+
+/// The logged-in user a request was made by, if it was made by one
+///
+/// Requests without an `Authorization` header are anonymous, but a token that
+/// doesn't verify is still rejected.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaybeUser(pub Option<AuthUser>);
+
+#[async_trait]
+impl FromRequestParts<AppState> for MaybeUser {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let header = match parts.headers.get(header::AUTHORIZATION) {
+            Some(header) => header,
+            None => return Ok(Self(None)),
+        };
+
+        let token = header
+            .to_str()
+            .ok()
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| GameError::Unauthenticated("expected a bearer token".to_string()))?;
+
+        Ok(Self(Some(state.auth.verify(token)?)))
+    }
+}
+
+/// Hash a password for storage
+async fn hash_password(password: String) -> GameResult<String> {
+    // Hashing is deliberately slow, so keep it off the async worker threads
+    tokio::task::spawn_blocking(move || {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|e| GameError::SerializationError(e.to_string()))
+    })
+    .await
+    .map_err(|e| GameError::SerializationError(e.to_string()))?
+}
+
+/// Returns true if `password` hashes to `hash`
+async fn verify_password(password: String, hash: String) -> bool {
+    tokio::task::spawn_blocking(move || {
+        PasswordHash::new(&hash)
+            .is_ok_and(|hash| Argon2::default().verify_password(password.as_bytes(), &hash).is_ok())
+    })
+    .await
+    .unwrap_or(false)
+}
+
+/// Check that a username and password can be registered
+fn validate_credentials(credentials: &Credentials) -> GameResult<()> {
+    let username = &credentials.username;
+    let allowed = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-';
+
+    if username.is_empty() || username.len() > MAX_USERNAME_LENGTH || !username.chars().all(allowed) {
+        return Err(GameError::InvalidAccount(format!(
+            "usernames are 1 to {} letters, digits, '_' or '-'",
+            MAX_USERNAME_LENGTH
+        )));
+    }
+    if credentials.password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(GameError::InvalidAccount(format!(
+            "passwords must be at least {} characters",
+            MIN_PASSWORD_LENGTH
+        )));
+    }
+    Ok(())
+}
+
+/// Request for registering or logging in
+#[derive(Debug, Deserialize)]
+pub struct Credentials {
+    /// The name to log in with
+    pub username: String,
+    /// The user's password
+    pub password: String,
+}
+
+/// Response for registering or logging in
+#[derive(Debug, Serialize)]
+pub struct AuthResponse {
+    /// The bearer token to send as `Authorization: Bearer <token>`
+    pub token: String,
+    /// The logged-in user
+    pub user: UserProfile,
+}
+
+/// A game the logged-in user has a seat in
+#[derive(Debug, Serialize)]
+pub struct UserGame {
+    /// Summary of the game
+    #[serde(flatten)]
+    pub summary: GameSummary,
+    /// The user's seats, with the tokens needed to keep playing them
+    pub seats: Vec<Seat>,
+}
+
+/// Response listing the logged-in user's games
+#[derive(Debug, Serialize)]
+pub struct UserGamesResponse {
+    /// The user's games, most recently changed first
+    pub games: Vec<UserGame>,
+}
+
+/// Register a new user and log them in
+pub async fn register(
+    State(state): State<AppState>,
+    Json(credentials): Json<Credentials>,
+) -> Result<(StatusCode, Json<AuthResponse>), ApiError> {
+    validate_credentials(&credentials)?;
+
+    let user = User {
+        id: Uuid::new_v4(),
+        username: credentials.username,
+        password_hash: hash_password(credentials.password).await?,
+        created_at: Utc::now(),
+    };
+    state.store.create_user(&user).await?;
+
+    let token = state.auth.issue(&user)?;
+    Ok((StatusCode::CREATED, Json(AuthResponse { token, user: user.profile() })))
+}
+
+/// Log in as an existing user
+pub async fn login(
+    State(state): State<AppState>,
+    Json(credentials): Json<Credentials>,
+) -> Result<Json<AuthResponse>, ApiError> {
+    let user = state
+        .store
+        .load_user(&credentials.username)
+        .await?
+        .ok_or(GameError::InvalidCredentials)?;
+
+    if !verify_password(credentials.password, user.password_hash.clone()).await {
+        return Err(GameError::InvalidCredentials.into());
+    }
+
+    let token = state.auth.issue(&user)?;
+    Ok(Json(AuthResponse { token, user: user.profile() }))
+}
+
+/// List the logged-in user's games, with the seat tokens to resume them
+pub async fn my_games(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<UserGamesResponse>, ApiError> {
+    let games = state.games.read().await;
+
+    let mut records: Vec<_> = games
+        .values()
+        .filter(|record| record.users.seats_of(user.id).next().is_some())
+        .collect();
+    records.sort_by_key(|record| std::cmp::Reverse(record.updated_at));
+
+    let games = records
+        .into_iter()
+        .map(|record| UserGame {
+            summary: GameSummary::new(record, &state),
+            seats: record
+                .users
+                .seats_of(user.id)
+                .filter_map(|player| {
+                    let token = record.seat_tokens.as_ref()?.for_player(player).to_string();
+                    Some(Seat { game_id: record.game.id, player, token })
+                })
+                .collect(),
+        })
+        .collect();
+
+    Ok(Json(UserGamesResponse { games }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user() -> User {
+        User {
+            id: Uuid::new_v4(),
+            username: "alice".to_string(),
+            password_hash: String::new(),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_tokens() {
+        let secret = b"an example secret that is long enough";
+        let issuer = TokenIssuer::new(secret, DEFAULT_TOKEN_TTL);
+        let user = user();
+
+        let token = issuer.issue(&user).unwrap();
+        let expected = AuthUser { id: user.id, username: user.username.clone() };
+        assert_eq!(issuer.verify(&token).unwrap(), expected);
+
+        let other = TokenIssuer::random(DEFAULT_TOKEN_TTL);
+        assert!(matches!(other.verify(&token), Err(GameError::Unauthenticated(_))));
+
+        let expired = TokenIssuer::new(secret, chrono::Duration::hours(-1));
+        assert!(issuer.verify(&expired.issue(&user).unwrap()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_passwords() {
+        let hash = hash_password("correct horse".to_string()).await.unwrap();
+        assert!(verify_password("correct horse".to_string(), hash.clone()).await);
+        assert!(!verify_password("wrong horse".to_string(), hash).await);
+
+        let credentials = |username: &str, password: &str| Credentials {
+            username: username.to_string(),
+            password: password.to_string(),
+        };
+        assert!(validate_credentials(&credentials("alice", "correct horse")).is_ok());
+        assert!(validate_credentials(&credentials("alice", "short")).is_err());
+        assert!(validate_credentials(&credentials("alice smith", "correct horse")).is_err());
+    }
+}
@@ -0,0 +1,506 @@
+use std::net::SocketAddr;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::http::HeaderValue;
+use serde::de::{DeserializeOwned, IntoDeserializer};
+use serde::Deserialize;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+use crate::ai::Difficulty;
+use crate::archive::HistoryStore;
+use crate::error::{GameError, GameResult};
+use crate::server::auth::{TokenIssuer, DEFAULT_TOKEN_TTL};
+use crate::server::event_store::EventStore;
+use crate::server::expiry::{ExpiryAction, ExpiryPolicy, DEFAULT_SWEEP_INTERVAL};
+use crate::server::state::DEFAULT_AI_TIMEOUT;
+#[cfg(feature = "redis")]
+use crate::server::redis::RedisStore;
+use crate::server::store::{GameStore, MemoryStore, SqliteStore};
+use crate::server::timers::DEFAULT_CHECK_INTERVAL;
+
+/// File the server reads its settings from when `SERVER_CONFIG` isn't set
+pub const DEFAULT_CONFIG_FILE: &str = "server.toml";
+
+/// Shortest secret login tokens may be signed with
+const MIN_SECRET_LENGTH: usize = 32;
+
+/// Settings for the HTTP server
+///
+/// Read from a TOML file, then overridden by environment variables. Every
+/// setting is optional; see `server.example.toml` for the defaults.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    /// Address to listen on (`BIND_ADDRESS`, or just the port with `PORT`)
+    pub bind_address: SocketAddr,
+    /// Address the gRPC API listens on, if it is served (`GRPC_ADDRESS`, with the `grpc` feature)
+    pub grpc_address: Option<SocketAddr>,
+    /// Origins browsers may call the API from, or `["*"]` for any (`ALLOWED_ORIGINS`, comma separated)
+    pub allowed_origins: Vec<String>,
+    /// Whether to serve the built-in web UI at `/` (`WEB_UI`)
+    pub web_ui: bool,
+    /// Where games are kept
+    pub storage: StorageConfig,
+    /// Defaults for AI opponents
+    pub ai: AiConfig,
+    /// When games time out and expire
+    pub timeouts: TimeoutConfig,
+    /// How users log in
+    pub auth: AuthConfig,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind_address: SocketAddr::from(([0, 0, 0, 0], 3000)),
+            grpc_address: None,
+            allowed_origins: vec!["*".to_string()],
+            web_ui: true,
+            storage: StorageConfig::default(),
+            ai: AiConfig::default(),
+            timeouts: TimeoutConfig::default(),
+            auth: AuthConfig::default(),
+        }
+    }
+}
+
+/// The backend games are stored in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    /// Keep games in memory, losing them when the server stops
+    #[default]
+    Memory,
+    /// Keep games in a SQLite database
+    Sqlite,
+    /// Keep each game in a SQLite database as the stream of events that made it
+    #[serde(rename = "event_log")]
+    EventLog,
+    /// Keep games in Redis, which several servers can share (needs the `redis` feature)
+    Redis,
+}
+
+/// Settings for where games are kept
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    /// The backend to use (`STORAGE_BACKEND`)
+    pub backend: StorageBackend,
+    /// The database to open with the SQLite and event log backends (`DATABASE_URL`)
+    pub database_url: String,
+    /// The server to connect to with the Redis backend (`REDIS_URL`)
+    pub redis_url: String,
+    /// The SQLite database finished games are archived in, if any (`HISTORY_DATABASE_URL`)
+    pub history_url: Option<String>,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            backend: StorageBackend::Memory,
+            database_url: "sqlite://games.db".to_string(),
+            redis_url: "redis://127.0.0.1/".to_string(),
+            history_url: None,
+        }
+    }
+}
+
+impl StorageConfig {
+    /// Open the configured store
+    pub async fn open(&self) -> GameResult<Arc<dyn GameStore>> {
+        Ok(match self.backend {
+            StorageBackend::Memory => Arc::new(MemoryStore::new()),
+            StorageBackend::Sqlite => Arc::new(SqliteStore::connect(&self.database_url).await?),
+            StorageBackend::EventLog => Arc::new(EventStore::connect(&self.database_url).await?),
+            #[cfg(feature = "redis")]
+            StorageBackend::Redis => Arc::new(RedisStore::connect(&self.redis_url).await?),
+            #[cfg(not(feature = "redis"))]
+            StorageBackend::Redis => {
+                return Err(GameError::InvalidConfig(
+                    "the server was built without the redis feature".to_string(),
+                ))
+            }
+        })
+    }
+
+    /// Open the configured archive of finished games, if there is one
+    pub async fn open_archive(&self) -> GameResult<Option<HistoryStore>> {
+        match &self.history_url {
+            Some(url) => Ok(Some(HistoryStore::connect(url).await?)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Defaults for AI opponents
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AiConfig {
+    /// Difficulty used when a request doesn't give one (`AI_DIFFICULTY`)
+    pub difficulty: Difficulty,
+    /// Longest the AI may search for a move, in milliseconds (`AI_SEARCH_TIMEOUT_MS`)
+    pub search_timeout_ms: u64,
+}
+
+impl Default for AiConfig {
+    fn default() -> Self {
+        Self {
+            difficulty: Difficulty::Medium,
+            search_timeout_ms: DEFAULT_AI_TIMEOUT.as_millis() as u64,
+        }
+    }
+}
+
+impl AiConfig {
+    /// Longest the AI may search for a move
+    pub fn search_timeout(&self) -> Duration {
+        Duration::from_millis(self.search_timeout_ms)
+    }
+}
+
+/// Settings for turn timers and game expiry
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimeoutConfig {
+    /// How often to check for players who have run out of time, in milliseconds
+    pub turn_check_interval_ms: u64,
+    /// How often to look for expired games, in seconds
+    pub expiry_sweep_interval_secs: u64,
+    /// Hours a finished game is kept (`FINISHED_GAME_TTL_HOURS`)
+    pub finished_game_ttl_hours: i64,
+    /// Hours an in-progress game is kept without a move (`IDLE_GAME_TTL_HOURS`)
+    pub idle_game_ttl_hours: i64,
+    /// What happens to expired games (`EXPIRED_GAMES`)
+    pub expired_games: ExpiryAction,
+}
+
+impl Default for TimeoutConfig {
+    fn default() -> Self {
+        let expiry = ExpiryPolicy::default();
+        Self {
+            turn_check_interval_ms: DEFAULT_CHECK_INTERVAL.as_millis() as u64,
+            expiry_sweep_interval_secs: DEFAULT_SWEEP_INTERVAL.as_secs(),
+            finished_game_ttl_hours: expiry.finished_ttl.num_hours(),
+            idle_game_ttl_hours: expiry.idle_ttl.num_hours(),
+            expired_games: expiry.action,
+        }
+    }
+}
+
+impl TimeoutConfig {
+    /// How often the turn timer runs
+    pub fn turn_check_interval(&self) -> Duration {
+        Duration::from_millis(self.turn_check_interval_ms)
+    }
+
+    /// How often the game collector runs
+    pub fn expiry_sweep_interval(&self) -> Duration {
+        Duration::from_secs(self.expiry_sweep_interval_secs)
+    }
+
+    /// The expiry policy these settings describe
+    pub fn expiry_policy(&self) -> ExpiryPolicy {
+        ExpiryPolicy {
+            finished_ttl: chrono::Duration::hours(self.finished_game_ttl_hours),
+            idle_ttl: chrono::Duration::hours(self.idle_game_ttl_hours),
+            action: self.expired_games,
+        }
+    }
+}
+
+/// Settings for user accounts
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    /// Secret login tokens are signed with (`JWT_SECRET`); a random one is used if unset
+    pub jwt_secret: Option<String>,
+    /// Hours a login lasts
+    pub token_ttl_hours: i64,
+    /// Usernames of the users allowed to call the admin API (`ADMINS`, comma separated)
+    pub admins: Vec<String>,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            jwt_secret: None,
+            token_ttl_hours: DEFAULT_TOKEN_TTL.num_hours(),
+            admins: Vec::new(),
+        }
+    }
+}
+
+impl AuthConfig {
+    /// The token issuer these settings describe
+    pub fn token_issuer(&self) -> TokenIssuer {
+        let ttl = chrono::Duration::hours(self.token_ttl_hours);
+        match &self.jwt_secret {
+            Some(secret) => TokenIssuer::new(secret.as_bytes(), ttl),
+            None => TokenIssuer::random(ttl),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Load the settings the server was started with
+    ///
+    /// Reads the file named by `SERVER_CONFIG`, or `server.toml` if it exists,
+    /// then applies environment overrides and validates the result.
+    pub fn from_env() -> GameResult<Self> {
+        let config = match std::env::var("SERVER_CONFIG") {
+            Ok(path) => Self::from_file(Path::new(&path))?,
+            Err(_) if Path::new(DEFAULT_CONFIG_FILE).exists() => {
+                Self::from_file(Path::new(DEFAULT_CONFIG_FILE))?
+            }
+            Err(_) => Self::default(),
+        };
+
+        let config = config.with_overrides(|name| std::env::var(name).ok())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Read settings from a TOML file
+    pub fn from_file(path: &Path) -> GameResult<Self> {
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            GameError::InvalidConfig(format!("can't read {}: {}", path.display(), e))
+        })?;
+        toml::from_str(&text)
+            .map_err(|e| GameError::InvalidConfig(format!("in {}: {}", path.display(), e)))
+    }
+
+    /// Parse settings from TOML text
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::ai::Difficulty;
+    /// use my_game_project::server::ServerConfig;
+    ///
+    /// let config = ServerConfig::from_toml("[ai]\ndifficulty = \"Hard\"").unwrap();
+    /// assert_eq!(config.ai.difficulty, Difficulty::Hard);
+    /// assert!(ServerConfig::from_toml("prot = 3000").is_err());
+    /// ```
+    pub fn from_toml(text: &str) -> GameResult<Self> {
+        toml::from_str(text).map_err(|e| GameError::InvalidConfig(e.to_string()))
+    }
+
+    /// Apply the environment variables `lookup` finds over these settings
+    pub fn with_overrides(mut self, lookup: impl Fn(&str) -> Option<String>) -> GameResult<Self> {
+        if let Some(address) = lookup("BIND_ADDRESS") {
+            self.bind_address = parse_var("BIND_ADDRESS", &address)?;
+        }
+        if let Some(port) = lookup("PORT") {
+            self.bind_address.set_port(parse_var("PORT", &port)?);
+        }
+        if let Some(address) = lookup("GRPC_ADDRESS") {
+            self.grpc_address = Some(parse_var("GRPC_ADDRESS", &address)?);
+        }
+        if let Some(origins) = lookup("ALLOWED_ORIGINS") {
+            self.allowed_origins = origins.split(',').map(|o| o.trim().to_string()).collect();
+        }
+        if let Some(web_ui) = lookup("WEB_UI") {
+            self.web_ui = parse_var("WEB_UI", &web_ui)?;
+        }
+        if let Some(backend) = lookup("STORAGE_BACKEND") {
+            self.storage.backend = deserialize_var("STORAGE_BACKEND", &backend)?;
+        }
+        if let Some(url) = lookup("DATABASE_URL") {
+            self.storage.database_url = url;
+        }
+        if let Some(url) = lookup("REDIS_URL") {
+            self.storage.redis_url = url;
+        }
+        if let Some(url) = lookup("HISTORY_DATABASE_URL") {
+            self.storage.history_url = Some(url);
+        }
+        if let Some(difficulty) = lookup("AI_DIFFICULTY") {
+            self.ai.difficulty = parse_var("AI_DIFFICULTY", &difficulty)?;
+        }
+        if let Some(timeout) = lookup("AI_SEARCH_TIMEOUT_MS") {
+            self.ai.search_timeout_ms = parse_var("AI_SEARCH_TIMEOUT_MS", &timeout)?;
+        }
+        if let Some(hours) = lookup("FINISHED_GAME_TTL_HOURS") {
+            self.timeouts.finished_game_ttl_hours = parse_var("FINISHED_GAME_TTL_HOURS", &hours)?;
+        }
+        if let Some(hours) = lookup("IDLE_GAME_TTL_HOURS") {
+            self.timeouts.idle_game_ttl_hours = parse_var("IDLE_GAME_TTL_HOURS", &hours)?;
+        }
+        if let Some(action) = lookup("EXPIRED_GAMES") {
+            self.timeouts.expired_games = deserialize_var("EXPIRED_GAMES", &action)?;
+        }
+        if let Some(secret) = lookup("JWT_SECRET") {
+            self.auth.jwt_secret = Some(secret);
+        }
+        if let Some(admins) = lookup("ADMINS") {
+            self.auth.admins = admins.split(',').map(|name| name.trim().to_string()).collect();
+        }
+        Ok(self)
+    }
+
+    /// Check that the settings can be used, explaining the first problem found
+    pub fn validate(&self) -> GameResult<()> {
+        let invalid = |message: String| Err(GameError::InvalidConfig(message));
+
+        if self.allowed_origins.is_empty() {
+            return invalid("allowed_origins is empty; use [\"*\"] to allow any origin".to_string());
+        }
+        let any = self.allowed_origins.iter().any(|origin| origin == "*");
+        if any && self.allowed_origins.len() > 1 {
+            return invalid("allowed_origins can't mix \"*\" with specific origins".to_string());
+        }
+        for origin in self.allowed_origins.iter().filter(|_| !any) {
+            let scheme = origin.starts_with("http://") || origin.starts_with("https://");
+            if !scheme || origin.ends_with('/') || HeaderValue::from_str(origin).is_err() {
+                return invalid(format!(
+                    "allowed origin '{}' should look like 'https://example.com', with no path",
+                    origin
+                ));
+            }
+        }
+
+        if matches!(self.storage.backend, StorageBackend::Sqlite | StorageBackend::EventLog)
+            && !self.storage.database_url.starts_with("sqlite:")
+        {
+            return invalid(format!(
+                "database_url '{}' should start with 'sqlite:' for the SQLite and event log backends",
+                self.storage.database_url
+            ));
+        }
+
+        if let Some(url) = self.storage.history_url.as_ref().filter(|url| !url.starts_with("sqlite:")) {
+            return invalid(format!("history_url '{}' should start with 'sqlite:'", url));
+        }
+
+        if self.storage.backend == StorageBackend::Redis
+            && !["redis://", "rediss://"].iter().any(|scheme| self.storage.redis_url.starts_with(scheme))
+        {
+            return invalid(format!(
+                "redis_url '{}' should start with 'redis://' or 'rediss://'",
+                self.storage.redis_url
+            ));
+        }
+
+        if self.ai.search_timeout_ms == 0 {
+            return invalid("search_timeout_ms must be above 0".to_string());
+        }
+
+        let timeouts = &self.timeouts;
+        if timeouts.turn_check_interval_ms == 0 || timeouts.expiry_sweep_interval_secs == 0 {
+            return invalid(
+                "turn_check_interval_ms and expiry_sweep_interval_secs must be above 0".to_string(),
+            );
+        }
+        if timeouts.finished_game_ttl_hours <= 0 || timeouts.idle_game_ttl_hours <= 0 {
+            return invalid(
+                "finished_game_ttl_hours and idle_game_ttl_hours must be above 0".to_string(),
+            );
+        }
+
+        if self.auth.jwt_secret.as_ref().is_some_and(|secret| secret.len() < MIN_SECRET_LENGTH) {
+            return invalid(format!("jwt_secret must be at least {} bytes long", MIN_SECRET_LENGTH));
+        }
+        if self.auth.token_ttl_hours <= 0 {
+            return invalid("token_ttl_hours must be above 0".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// The CORS layer allowing requests from the configured origins
+    pub fn cors_layer(&self) -> CorsLayer {
+        let origins = if self.allowed_origins.iter().any(|origin| origin == "*") {
+            AllowOrigin::from(Any)
+        } else {
+            AllowOrigin::list(
+                self.allowed_origins.iter().filter_map(|origin| origin.parse().ok()),
+            )
+        };
+
+        CorsLayer::new().allow_origin(origins).allow_methods(Any).allow_headers(Any)
+    }
+}
+
+/// Parse the environment variable `name` with [`FromStr`]
+fn parse_var<T: FromStr>(name: &str, value: &str) -> GameResult<T>
+where
+    T::Err: std::fmt::Display,
+{
+    value
+        .parse()
+        .map_err(|e| GameError::InvalidConfig(format!("{}='{}': {}", name, value, e)))
+}
+
+/// Parse the environment variable `name` as the name of an enum variant
+fn deserialize_var<T: DeserializeOwned>(name: &str, value: &str) -> GameResult<T> {
+    T::deserialize(value.into_deserializer())
+        .map_err(|e: serde::de::value::Error| {
+            GameError::InvalidConfig(format!("{}='{}': {}", name, value, e))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_then_environment() {
+        let config = ServerConfig::from_toml(
+            r#"
+            bind_address = "127.0.0.1:8080"
+            allowed_origins = ["https://example.com"]
+
+            [storage]
+            backend = "sqlite"
+
+            [timeouts]
+            idle_game_ttl_hours = 12
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.storage.database_url, "sqlite://games.db");
+        assert_eq!(config.timeouts.finished_game_ttl_hours, 24);
+        config.validate().unwrap();
+
+        let env = |name: &str| match name {
+            "PORT" => Some("9000".to_string()),
+            "AI_DIFFICULTY" => Some("Hard".to_string()),
+            "EXPIRED_GAMES" => Some("delete".to_string()),
+            "WEB_UI" => Some("false".to_string()),
+            _ => None,
+        };
+        let config = config.with_overrides(env).unwrap();
+        assert_eq!(config.bind_address, "127.0.0.1:9000".parse().unwrap());
+        assert_eq!(config.ai.difficulty, Difficulty::Hard);
+        assert!(!config.web_ui);
+        assert_eq!(config.timeouts.expiry_policy().action, ExpiryAction::Delete);
+        assert_eq!(config.timeouts.expiry_policy().idle_ttl, chrono::Duration::hours(12));
+    }
+
+    #[test]
+    fn test_invalid_settings() {
+        let env = |value: &'static str| move |name: &str| (name == "PORT").then(|| value.to_string());
+        assert!(ServerConfig::default().with_overrides(env("http")).is_err());
+
+        let mut config = ServerConfig {
+            allowed_origins: vec!["*".to_string(), "https://example.com".to_string()],
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+
+        config.allowed_origins = vec!["example.com".to_string()];
+        assert!(config.validate().is_err());
+
+        config.allowed_origins = vec!["https://example.com".to_string()];
+        config.storage.backend = StorageBackend::Redis;
+        config.storage.redis_url = "localhost:6379".to_string();
+        assert!(config.validate().is_err());
+
+        config.storage.redis_url = "redis://localhost:6379".to_string();
+        config.timeouts.idle_game_ttl_hours = 0;
+        assert!(config.validate().is_err());
+    }
+}
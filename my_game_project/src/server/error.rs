@@ -0,0 +1,132 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+
+use crate::error::GameError;
+
+/// An error returned by the REST API
+///
+/// Serialized as a JSON body of the form
+/// `{"code": "CELL_OCCUPIED", "message": "...", "position": [0, 0]}`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiError {
+    /// The HTTP status to respond with
+    #[serde(skip)]
+    pub status: StatusCode,
+    /// A stable, machine-readable error code
+    pub code: &'static str,
+    /// A human-readable description of the error
+    pub message: String,
+    /// The board position the error refers to, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<(usize, usize)>,
+    /// The expected and actual game versions, for a version conflict
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub versions: Option<(u64, u64)>,
+    /// The ply of the move the error refers to, counting from 0, for an illegal move in a history
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ply: Option<usize>,
+}
+
+impl ApiError {
+    /// Create an error that doesn't refer to a board position
+    pub fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code,
+            message: message.into(),
+            position: None,
+            versions: None,
+            ply: None,
+        }
+    }
+}
+
+impl From<&GameError> for StatusCode {
+    /// The HTTP status the server responds to an error with
+    fn from(error: &GameError) -> Self {
+        match error {
+            // Reading the JSON failed, rather than the JSON being wrong
+            GameError::JsonError(e) if e.is_io() => StatusCode::INTERNAL_SERVER_ERROR,
+            GameError::InvalidPosition(_, _)
+            | GameError::InvalidAccount(_)
+            | GameError::InvalidWebhook(_)
+            | GameError::InvalidChatMessage(_)
+            | GameError::DeserializationError(_)
+            | GameError::JsonError(_)
+            | GameError::InvalidCoordinates(_)
+            | GameError::InvalidBoardSize(_, _)
+            | GameError::UnreachablePosition(_)
+            | GameError::InvalidMoveIndex(_)
+            | GameError::InvalidPlayerType(_)
+            | GameError::InvalidCursor(_)
+            | GameError::InvalidMatchLength(_)
+            | GameError::InvalidImport(_)
+            | GameError::CorruptSave(_)
+            | GameError::UnsupportedSchemaVersion(_)
+            | GameError::IllegalHistoryMove(_, _)
+            | GameError::InvalidNotation(_) => StatusCode::BAD_REQUEST,
+            GameError::Unauthenticated(_) | GameError::InvalidCredentials => StatusCode::UNAUTHORIZED,
+            GameError::InvalidToken(_) | GameError::Forbidden(_) => StatusCode::FORBIDDEN,
+            GameError::GameNotFound(_)
+            | GameError::PlayerNotFound(_)
+            | GameError::VariationNotFound(_)
+            | GameError::MatchNotFound(_) => StatusCode::NOT_FOUND,
+            GameError::CellOccupied(_, _)
+            | GameError::GameAlreadyFinished
+            | GameError::NotPlayerTurn
+            | GameError::VersionConflict { .. }
+            | GameError::NoDrawOffer
+            | GameError::NothingToUndo
+            | GameError::NoUndoRequest
+            | GameError::NoOpenSeat
+            | GameError::UsernameTaken(_)
+            | GameError::NoValidMoves
+            | GameError::MatchAlreadyFinished
+            | GameError::GameAlreadyExists(_)
+            | GameError::ConcurrentUpdate(_) => StatusCode::CONFLICT,
+            GameError::NetworkError(_) => StatusCode::BAD_GATEWAY,
+            GameError::AiTimedOut(_) => StatusCode::SERVICE_UNAVAILABLE,
+            GameError::IoError(_)
+            | GameError::AiFailed(_)
+            | GameError::SerializationError(_)
+            | GameError::StorageError(_)
+            | GameError::InvalidConfig(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl From<GameError> for ApiError {
+    fn from(error: GameError) -> Self {
+        let position = match error {
+            GameError::CellOccupied(row, col) | GameError::InvalidPosition(row, col) => Some((row, col)),
+            _ => None,
+        };
+        let versions = match error {
+            GameError::VersionConflict { expected, actual } => Some((expected, actual)),
+            _ => None,
+        };
+        let ply = match error {
+            GameError::IllegalHistoryMove(ply, _) => Some(ply),
+            _ => None,
+        };
+
+        Self {
+            status: StatusCode::from(&error),
+            code: error.error_code(),
+            message: error.to_string(),
+            position,
+            versions,
+            ply,
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(self)).into_response()
+    }
+}
@@ -0,0 +1,587 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::{Row, SqlitePool};
+use tokio::sync::Mutex;
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::error::{GameError, GameResult};
+use crate::game::GameStatus;
+use crate::history::{ChatMessage, GameHistory, GameMove};
+use crate::rating::PlayerRating;
+use crate::server::auth::User;
+use crate::server::state::GameRecord;
+use crate::server::store::{from_json, storage_error, GameStore, SqliteStore};
+
+/// Number of events between snapshots of a game
+pub const SNAPSHOT_INTERVAL: u64 = 32;
+
+/// Something that happened to a stored game
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameEvent {
+    /// The game's record changed, given as a JSON merge patch (RFC 7396) to its last state
+    RecordChanged(Value),
+    /// The game's history was started, with no moves or messages yet
+    HistoryStarted(Box<GameHistory>),
+    /// A move was played
+    Moved(GameMove),
+    /// This many of the last moves were taken back
+    TakenBack(usize),
+    /// A chat message was sent
+    Chatted(ChatMessage),
+    /// The game ended, or an ended game was taken back to before its end
+    Ended {
+        /// How the game ended, or `None` if it is back in progress
+        status: Option<GameStatus>,
+        /// When the game ended
+        at: Option<DateTime<Utc>>,
+    },
+    /// The history changed some other way, such as by gaining a variation
+    HistoryReplaced(Box<GameHistory>),
+    /// The game was moved to the archive
+    Archived,
+}
+
+/// An event as it was stored
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordedEvent {
+    /// The event's position in its game's stream, starting from 1
+    pub sequence: u64,
+    /// When the event was stored
+    pub recorded_at: DateTime<Utc>,
+    /// What happened
+    pub event: GameEvent,
+}
+
+/// A game's state as rebuilt from its events
+#[derive(Debug, Clone, Default)]
+struct GameLog {
+    /// The sequence number of the last event applied
+    sequence: u64,
+    /// The game's record as JSON, or null before it is first saved
+    record: Value,
+    /// The game's history, once it has been started
+    history: Option<GameHistory>,
+    /// Whether the game has been archived
+    archived: bool,
+}
+
+impl GameLog {
+    /// Apply the next event in the game's stream
+    fn apply(&mut self, event: &GameEvent) -> GameResult<()> {
+        match event {
+            GameEvent::RecordChanged(patch) => apply_merge_patch(&mut self.record, patch),
+            GameEvent::HistoryStarted(started) | GameEvent::HistoryReplaced(started) => {
+                self.history = Some((**started).clone());
+            }
+            GameEvent::Moved(game_move) => self.history_mut()?.moves.push(game_move.clone()),
+            GameEvent::TakenBack(count) => {
+                let moves = &mut self.history_mut()?.moves;
+                moves.truncate(moves.len().saturating_sub(*count));
+            }
+            GameEvent::Chatted(message) => self.history_mut()?.chat.push(message.clone()),
+            GameEvent::Ended { status, at } => {
+                let history = self.history_mut()?;
+                history.final_status = *status;
+                history.ended_at = *at;
+            }
+            GameEvent::Archived => self.archived = true,
+        }
+        Ok(())
+    }
+
+    /// The game's history, which must have been started by an earlier event
+    fn history_mut(&mut self) -> GameResult<&mut GameHistory> {
+        self.history
+            .as_mut()
+            .ok_or_else(|| GameError::StorageError("event recorded before the history".to_string()))
+    }
+
+    /// The game's record, if it has been saved
+    fn game_record(&self) -> GameResult<Option<GameRecord>> {
+        if self.record.is_null() {
+            return Ok(None);
+        }
+        serde_json::from_value(self.record.clone())
+            .map(Some)
+            .map_err(|e| GameError::DeserializationError(e.to_string()))
+    }
+}
+
+/// A store that keeps each game as an append-only stream of events in SQLite
+///
+/// Saving a game or its history records only what changed since it was last
+/// saved: a patch to the record, and the moves, take-backs and messages that
+/// were added to the history. Loading a game replays its events on top of
+/// its latest snapshot, which is taken every [`SNAPSHOT_INTERVAL`] events.
+/// Ratings and users are kept as in [`SqliteStore`].
+#[derive(Debug)]
+pub struct EventStore {
+    /// The store ratings and users are kept in, sharing its database
+    tables: SqliteStore,
+    /// The state of each game that has been loaded or saved, as of its last event
+    logs: Mutex<HashMap<Uuid, GameLog>>,
+}
+
+impl EventStore {
+    /// Open (creating if necessary) the database at `url` and set up its tables
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # async fn example() -> my_game_project::error::GameResult<()> {
+    /// use my_game_project::server::EventStore;
+    ///
+    /// let store = EventStore::connect("sqlite://events.db").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn connect(url: &str) -> GameResult<Self> {
+        let tables = SqliteStore::connect(url).await?;
+        let pool = tables.pool();
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS game_streams \
+             (game_id TEXT PRIMARY KEY, archived INTEGER NOT NULL DEFAULT 0)",
+        )
+        .execute(pool)
+        .await
+        .map_err(storage_error)?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS game_events \
+             (game_id TEXT NOT NULL, sequence INTEGER NOT NULL, recorded_at TEXT NOT NULL, \
+             event TEXT NOT NULL, PRIMARY KEY (game_id, sequence))",
+        )
+        .execute(pool)
+        .await
+        .map_err(storage_error)?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS game_snapshots \
+             (game_id TEXT PRIMARY KEY, sequence INTEGER NOT NULL, record TEXT NOT NULL, history TEXT, \
+             archived INTEGER NOT NULL)",
+        )
+        .execute(pool)
+        .await
+        .map_err(storage_error)?;
+
+        Ok(Self {
+            tables,
+            logs: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// The database connection pool
+    fn pool(&self) -> &SqlitePool {
+        self.tables.pool()
+    }
+
+    /// Every event recorded for game `id`, oldest first
+    pub async fn events(&self, id: Uuid) -> GameResult<Vec<RecordedEvent>> {
+        self.events_after(id, 0).await
+    }
+
+    /// The events recorded for game `id` after the one numbered `sequence`
+    async fn events_after(&self, id: Uuid, sequence: u64) -> GameResult<Vec<RecordedEvent>> {
+        let rows = sqlx::query(
+            "SELECT sequence, recorded_at, event FROM game_events \
+             WHERE game_id = ? AND sequence > ? ORDER BY sequence",
+        )
+        .bind(id.to_string())
+        .bind(sequence as i64)
+        .fetch_all(self.pool())
+        .await
+        .map_err(storage_error)?;
+
+        rows.iter()
+            .map(|row| {
+                let recorded_at = DateTime::parse_from_rfc3339(row.get("recorded_at"))
+                    .map_err(|e| GameError::StorageError(e.to_string()))?;
+                Ok(RecordedEvent {
+                    sequence: row.get::<i64, _>("sequence") as u64,
+                    recorded_at: recorded_at.with_timezone(&Utc),
+                    event: from_json(row.get("event"))?,
+                })
+            })
+            .collect()
+    }
+
+    /// Rebuild game `id` from its latest snapshot and the events after it
+    ///
+    /// Returns `None` if nothing has been recorded for the game.
+    async fn replay(&self, id: Uuid) -> GameResult<Option<GameLog>> {
+        let snapshot =
+            sqlx::query("SELECT sequence, record, history, archived FROM game_snapshots WHERE game_id = ?")
+                .bind(id.to_string())
+                .fetch_optional(self.pool())
+                .await
+                .map_err(storage_error)?;
+
+        let mut log = match snapshot {
+            Some(row) => GameLog {
+                sequence: row.get::<i64, _>("sequence") as u64,
+                record: from_json(row.get("record"))?,
+                history: row.get::<Option<&str>, _>("history").map(from_json).transpose()?,
+                archived: row.get("archived"),
+            },
+            None => GameLog::default(),
+        };
+
+        let events = self.events_after(id, log.sequence).await?;
+        if log.sequence == 0 && events.is_empty() {
+            return Ok(None);
+        }
+        for recorded in events {
+            log.apply(&recorded.event)?;
+            log.sequence = recorded.sequence;
+        }
+        Ok(Some(log))
+    }
+
+    /// Game `id` as of its last event, from memory if it has been seen before
+    async fn log(&self, id: Uuid) -> GameResult<Option<GameLog>> {
+        if let Some(log) = self.logs.lock().await.get(&id) {
+            return Ok(Some(log.clone()));
+        }
+        self.replay(id).await
+    }
+
+    /// Record the events `changes` finds for game `id`, given its current state
+    async fn append(
+        &self,
+        id: Uuid,
+        changes: impl FnOnce(&GameLog) -> GameResult<Vec<GameEvent>>,
+    ) -> GameResult<()> {
+        // Holding the lock keeps each game's events in order
+        let mut logs = self.logs.lock().await;
+        let mut log = match logs.get(&id) {
+            Some(log) => log.clone(),
+            None => self.replay(id).await?.unwrap_or_default(),
+        };
+
+        let events = changes(&log)?;
+        if events.is_empty() {
+            return Ok(());
+        }
+
+        let mut transaction = self.pool().begin().await.map_err(storage_error)?;
+        sqlx::query("INSERT OR IGNORE INTO game_streams (game_id) VALUES (?)")
+            .bind(id.to_string())
+            .execute(&mut *transaction)
+            .await
+            .map_err(storage_error)?;
+
+        let mut snapshot_due = false;
+        for event in &events {
+            log.apply(event)?;
+            log.sequence += 1;
+            snapshot_due |= log.sequence % SNAPSHOT_INTERVAL == 0;
+
+            sqlx::query(
+                "INSERT INTO game_events (game_id, sequence, recorded_at, event) VALUES (?, ?, ?, ?)",
+            )
+            .bind(id.to_string())
+            .bind(log.sequence as i64)
+            .bind(Utc::now().to_rfc3339())
+            .bind(to_json(event)?)
+            .execute(&mut *transaction)
+            .await
+            .map_err(storage_error)?;
+        }
+
+        if snapshot_due {
+            let history = log.history.as_ref().map(to_json).transpose()?;
+            sqlx::query(
+                "INSERT OR REPLACE INTO game_snapshots (game_id, sequence, record, history, archived) \
+                 VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(id.to_string())
+            .bind(log.sequence as i64)
+            .bind(to_json(&log.record)?)
+            .bind(history)
+            .bind(log.archived)
+            .execute(&mut *transaction)
+            .await
+            .map_err(storage_error)?;
+        }
+
+        transaction.commit().await.map_err(storage_error)?;
+        logs.insert(id, log);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl GameStore for EventStore {
+    #[instrument(level = "debug", skip_all, fields(game_id = %record.game.id))]
+    async fn save_game(&self, record: &GameRecord) -> GameResult<()> {
+        let json = serde_json::to_value(record)?;
+        self.append(record.game.id, |log| {
+            let patch = merge_patch(&log.record, &json);
+            let unchanged = patch.as_object().is_some_and(Map::is_empty);
+            Ok(if unchanged { Vec::new() } else { vec![GameEvent::RecordChanged(patch)] })
+        })
+        .await
+    }
+
+    async fn load_game(&self, id: Uuid) -> GameResult<Option<GameRecord>> {
+        match self.log(id).await? {
+            Some(log) if !log.archived => log.game_record(),
+            _ => Ok(None),
+        }
+    }
+
+    async fn load_all_games(&self) -> GameResult<Vec<GameRecord>> {
+        let rows = sqlx::query("SELECT game_id FROM game_streams WHERE archived = 0")
+            .fetch_all(self.pool())
+            .await
+            .map_err(storage_error)?;
+
+        let mut records = Vec::with_capacity(rows.len());
+        for row in rows {
+            let id = parse_id(row.get("game_id"))?;
+            if let Some(record) = self.load_game(id).await? {
+                records.push(record);
+            }
+        }
+        Ok(records)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(game_id = %id))]
+    async fn delete_game(&self, id: Uuid) -> GameResult<()> {
+        let mut logs = self.logs.lock().await;
+        let mut transaction = self.pool().begin().await.map_err(storage_error)?;
+
+        for table in ["game_streams", "game_events", "game_snapshots"] {
+            sqlx::query(&format!("DELETE FROM {} WHERE game_id = ?", table))
+                .bind(id.to_string())
+                .execute(&mut *transaction)
+                .await
+                .map_err(storage_error)?;
+        }
+
+        transaction.commit().await.map_err(storage_error)?;
+        logs.remove(&id);
+        Ok(())
+    }
+
+    async fn archive_game(&self, id: Uuid) -> GameResult<()> {
+        if self.log(id).await?.is_none() {
+            return Ok(());
+        }
+
+        self.append(id, |_| Ok(vec![GameEvent::Archived])).await?;
+        sqlx::query("UPDATE game_streams SET archived = 1 WHERE game_id = ?")
+            .bind(id.to_string())
+            .execute(self.pool())
+            .await
+            .map_err(storage_error)?;
+
+        // Archived games are rarely looked at again, so there's no need to keep them in memory
+        self.logs.lock().await.remove(&id);
+        Ok(())
+    }
+
+    async fn load_archived_game(&self, id: Uuid) -> GameResult<Option<GameRecord>> {
+        match self.log(id).await? {
+            Some(log) if log.archived => log.game_record(),
+            _ => Ok(None),
+        }
+    }
+
+    #[instrument(level = "debug", skip_all, fields(game_id = %history.game_id))]
+    async fn save_history(&self, history: &GameHistory) -> GameResult<()> {
+        self.append(history.game_id, |log| history_events(log.history.as_ref(), history)).await
+    }
+
+    async fn load_history(&self, game_id: Uuid) -> GameResult<Option<GameHistory>> {
+        Ok(self.log(game_id).await?.filter(|log| !log.archived).and_then(|log| log.history))
+    }
+
+    async fn save_rating(&self, rating: &PlayerRating) -> GameResult<()> {
+        self.tables.save_rating(rating).await
+    }
+
+    async fn load_all_ratings(&self) -> GameResult<Vec<PlayerRating>> {
+        self.tables.load_all_ratings().await
+    }
+
+    async fn create_user(&self, user: &User) -> GameResult<()> {
+        self.tables.create_user(user).await
+    }
+
+    async fn load_user(&self, username: &str) -> GameResult<Option<User>> {
+        self.tables.load_user(username).await
+    }
+}
+
+/// The events that turn the stored history `old` into `new`
+fn history_events(old: Option<&GameHistory>, new: &GameHistory) -> GameResult<Vec<GameEvent>> {
+    let Some(old) = old else {
+        // Start with an empty history and add the moves and messages it already has
+        let started = GameHistory {
+            moves: Vec::new(),
+            chat: Vec::new(),
+            ended_at: None,
+            final_status: None,
+            ..new.clone()
+        };
+        let mut events = history_events(Some(&started), new)?;
+        events.insert(0, GameEvent::HistoryStarted(Box::new(started)));
+        return Ok(events);
+    };
+
+    // Anything other than moves, messages and the result is only changed by replacing the history
+    let header = |history: &GameHistory| {
+        let mut header = serde_json::to_value(history)?;
+        if let Some(fields) = header.as_object_mut() {
+            for field in ["moves", "chat", "ended_at", "final_status"] {
+                fields.remove(field);
+            }
+        }
+        Ok::<_, GameError>(header)
+    };
+    if header(old)? != header(new)? || !new.chat.starts_with(&old.chat) {
+        return Ok(vec![GameEvent::HistoryReplaced(Box::new(new.clone()))]);
+    }
+
+    let kept = old.moves.iter().zip(&new.moves).take_while(|(old, new)| old == new).count();
+    let mut events = Vec::new();
+    if old.moves.len() > kept {
+        events.push(GameEvent::TakenBack(old.moves.len() - kept));
+    }
+    events.extend(new.moves[kept..].iter().cloned().map(GameEvent::Moved));
+    events.extend(new.chat[old.chat.len()..].iter().cloned().map(GameEvent::Chatted));
+    if (old.final_status, old.ended_at) != (new.final_status, new.ended_at) {
+        events.push(GameEvent::Ended {
+            status: new.final_status,
+            at: new.ended_at,
+        });
+    }
+    Ok(events)
+}
+
+/// The JSON merge patch that turns `from` into `to`
+fn merge_patch(from: &Value, to: &Value) -> Value {
+    let (Value::Object(from), Value::Object(to)) = (from, to) else {
+        return to.clone();
+    };
+
+    let mut patch = Map::new();
+    for (key, value) in to {
+        match from.get(key) {
+            Some(old) if old == value => {}
+            Some(old) => {
+                patch.insert(key.clone(), merge_patch(old, value));
+            }
+            None => {
+                patch.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    for key in from.keys().filter(|key| !to.contains_key(*key)) {
+        patch.insert(key.clone(), Value::Null);
+    }
+    Value::Object(patch)
+}
+
+/// Apply a JSON merge patch to `target`
+fn apply_merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    if let Value::Object(fields) = target {
+        for (key, value) in patch {
+            if value.is_null() {
+                fields.remove(key);
+            } else {
+                apply_merge_patch(fields.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+/// Serialize a value into a JSON document for the database
+fn to_json(value: &impl Serialize) -> GameResult<String> {
+    serde_json::to_string(value).map_err(GameError::from)
+}
+
+/// Parse a game ID read from the database
+fn parse_id(id: &str) -> GameResult<Uuid> {
+    id.parse().map_err(|_| GameError::StorageError(format!("bad game ID '{}'", id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+    use crate::player::Player;
+
+    #[test]
+    fn test_merge_patch() {
+        let from = serde_json::json!({"a": 1, "b": {"c": 2, "d": 3}, "e": [1, 2]});
+        let to = serde_json::json!({"a": 1, "b": {"c": 4}, "e": [1, 2, 3], "f": "new"});
+        let patch = merge_patch(&from, &to);
+        assert_eq!(patch, serde_json::json!({"b": {"c": 4, "d": null}, "e": [1, 2, 3], "f": "new"}));
+
+        let mut patched = from;
+        apply_merge_patch(&mut patched, &patch);
+        assert_eq!(patched, to);
+    }
+
+    #[tokio::test]
+    async fn test_replay_events() {
+        let path = std::env::temp_dir().join(format!("events_{}.db", Uuid::new_v4()));
+        let url = format!("sqlite://{}", path.display());
+        let store = EventStore::connect(&url).await.unwrap();
+
+        let mut record = GameRecord::new(GameState::new());
+        let mut history = record.game.create_history();
+        store.save_game(&record).await.unwrap();
+        store.save_history(&history).await.unwrap();
+        for position in [(1, 1), (0, 0), (2, 2)] {
+            let player = record.game.current_turn;
+            record.game.make_move(position).unwrap();
+            record.touch(None);
+            history.add_move(player, position);
+            store.save_game(&record).await.unwrap();
+            store.save_history(&history).await.unwrap();
+        }
+        history.moves.pop();
+        history.chat.push(ChatMessage::new(Player::O, "undo that"));
+        store.save_history(&history).await.unwrap();
+
+        let events = store.events(record.game.id).await.unwrap();
+        let moves = events.iter().filter(|e| matches!(e.event, GameEvent::Moved(_))).count();
+        assert_eq!(moves, 3);
+        assert!(matches!(events.last().unwrap().event, GameEvent::Chatted(_)));
+        assert!(events.iter().any(|e| matches!(e.event, GameEvent::TakenBack(1))));
+
+        // A fresh store rebuilds the game from the events alone, and after a snapshot
+        for _ in 0..SNAPSHOT_INTERVAL {
+            record.touch(None);
+            store.save_game(&record).await.unwrap();
+        }
+        let reopened = EventStore::connect(&url).await.unwrap();
+        let loaded = reopened.load_game(record.game.id).await.unwrap().unwrap();
+        assert_eq!((loaded.game.board, loaded.revision), (record.game.board, record.revision));
+        let loaded = reopened.load_history(record.game.id).await.unwrap().unwrap();
+        assert_eq!(loaded.moves, history.moves);
+        assert_eq!(loaded.chat, history.chat);
+
+        reopened.archive_game(record.game.id).await.unwrap();
+        assert!(reopened.load_all_games().await.unwrap().is_empty());
+        assert!(reopened.load_archived_game(record.game.id).await.unwrap().is_some());
+    }
+}
@@ -0,0 +1,96 @@
+use std::convert::Infallible;
+
+use axum::{
+    extract::{Path, State},
+    response::sse::{Event, KeepAlive, Sse},
+};
+use futures_util::stream::{self, Stream, StreamExt};
+use serde::Serialize;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use uuid::Uuid;
+
+use crate::error::GameError;
+use crate::server::error::ApiError;
+use crate::server::state::{AppState, GameRecord, GameUpdate};
+
+/// Stream a game's updates as server-sent events
+///
+/// The current state is sent first as a `state` event. After that, every
+/// update is sent as a `move` event when the board changed and a `status`
+/// event when the game's status changed (both, for a move that ends the
+/// game), or an `update` event for anything else, like a draw offer. Each
+/// of these events' data is the game as JSON. Chat messages are sent as
+/// `chat` events whose data is the message as JSON.
+pub async fn game_events(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    Ok(Sse::new(event_stream(&state, id).await?).keep_alive(KeepAlive::default()))
+}
+
+/// The events describing a game, starting with its current state
+pub(crate) async fn event_stream(
+    state: &AppState,
+    id: Uuid,
+) -> Result<impl Stream<Item = Result<Event, Infallible>>, ApiError> {
+    if !state.games.read().await.contains_key(&id) {
+        return Err(GameError::GameNotFound(id.to_string()).into());
+    }
+
+    // Subscribe before reading the current state so no update is missed in between
+    let updates = state.subscribe(id).await;
+    let current = state
+        .games
+        .read()
+        .await
+        .get(&id)
+        .map(GameRecord::public)
+        .ok_or_else(|| GameError::GameNotFound(id.to_string()))?;
+
+    let first = event("state", &current);
+    let events = stream::unfold((updates, current), next_events)
+        .flat_map(stream::iter)
+        .map(Ok);
+
+    Ok(stream::once(async { Ok(first) }).chain(events))
+}
+
+/// Wait for the next update, returning the events describing it
+///
+/// Ends the stream once the game's channel closes.
+async fn next_events(
+    (mut updates, last): (Receiver<GameUpdate>, GameRecord),
+) -> Option<(Vec<Event>, (Receiver<GameUpdate>, GameRecord))> {
+    let record = loop {
+        match updates.recv().await {
+            Ok(GameUpdate::State(record)) => break record.public(),
+            Ok(GameUpdate::Chat(message)) => {
+                return Some((vec![event("chat", &message)], (updates, last)));
+            }
+            Ok(GameUpdate::Event(_)) => continue,
+            // Skip straight to the latest state if we fell behind
+            Err(RecvError::Lagged(_)) => continue,
+            Err(RecvError::Closed) => return None,
+        }
+    };
+
+    let mut events = Vec::new();
+    if record.game.board != last.game.board {
+        events.push(event("move", &record));
+    }
+    if record.game.status != last.game.status {
+        events.push(event("status", &record));
+    }
+    if events.is_empty() {
+        events.push(event("update", &record));
+    }
+
+    Some((events, (updates, record)))
+}
+
+/// An event named `name` carrying `value` as JSON
+fn event<T: Serialize>(name: &str, value: &T) -> Event {
+    Event::default()
+        .event(name)
+        .data(serde_json::to_string(value).unwrap_or_default())
+}
@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+use crate::server::state::{AppState, GameRecord};
+
+/// How often the collector looks for expired games
+pub const DEFAULT_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// What happens to a game once it expires
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExpiryAction {
+    /// Move the game into the store's archive
+    #[default]
+    Archive,
+    /// Delete the game and its history
+    Delete,
+}
+
+/// How long games are kept after they were last changed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExpiryPolicy {
+    /// How long a finished game is kept
+    pub finished_ttl: chrono::Duration,
+    /// How long an in-progress game is kept without a move or other change
+    pub idle_ttl: chrono::Duration,
+    /// What happens to expired games
+    pub action: ExpiryAction,
+}
+
+impl Default for ExpiryPolicy {
+    fn default() -> Self {
+        Self {
+            finished_ttl: chrono::Duration::hours(24),
+            idle_ttl: chrono::Duration::hours(72),
+            action: ExpiryAction::Archive,
+        }
+    }
+}
+
+impl ExpiryPolicy {
+    /// When `record` expires if it isn't changed again
+    pub fn expires_at(&self, record: &GameRecord) -> DateTime<Utc> {
+        let ttl = if record.game.is_over() { self.finished_ttl } else { self.idle_ttl };
+        record.updated_at + ttl
+    }
+}
+
+/// Spawn a background task that archives or deletes expired games
+///
+/// Does nothing useful unless the state has an expiry policy.
+pub fn spawn_game_collector(state: AppState, interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+
+            match state.expire_games(Utc::now()).await {
+                Ok(ids) => {
+                    for id in ids {
+                        info!("Game {} expired", id);
+                    }
+                }
+                Err(e) => error!("Failed to expire games: {}", e),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+    use crate::player::Player;
+
+    #[tokio::test]
+    async fn test_expire_games() {
+        let state = AppState::new().with_expiry(ExpiryPolicy::default()).await;
+
+        let idle = state.insert_game(GameRecord::new(GameState::new())).await.unwrap();
+        let mut finished = GameRecord::new(GameState::new());
+        finished.game.resign(Player::X).unwrap();
+        let finished = state.insert_game(finished).await.unwrap();
+
+        assert_eq!(idle.expires_at, Some(idle.updated_at + chrono::Duration::hours(72)));
+        assert_eq!(finished.expires_at, Some(finished.updated_at + chrono::Duration::hours(24)));
+
+        let now = Utc::now();
+        assert!(state.expire_games(now).await.unwrap().is_empty());
+        let later = |hours| now + chrono::Duration::hours(hours);
+        assert_eq!(state.expire_games(later(25)).await.unwrap(), [finished.game.id]);
+        assert_eq!(state.expire_games(later(73)).await.unwrap(), [idle.game.id]);
+
+        assert!(state.games.read().await.is_empty());
+        assert!(state.store.load_game(idle.game.id).await.unwrap().is_none());
+        assert!(state.store.load_archived_game(idle.game.id).await.unwrap().is_some());
+    }
+}
@@ -0,0 +1,458 @@
+use std::sync::OnceLock;
+
+use async_graphql::http::GraphiQLSource;
+use async_graphql::{
+    Context, Data, Enum, Error, ErrorExtensions, InputObject, Object, Result, Schema, SimpleObject,
+    Subscription,
+};
+use async_graphql_axum::{GraphQLProtocol, GraphQLRequest, GraphQLResponse, GraphQLWebSocket};
+use axum::{
+    extract::{ws::WebSocketUpgrade, State},
+    response::{Html, IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use futures_util::stream::{self, Stream};
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+use crate::error::GameError;
+use crate::game::{Cell, GameStatus};
+use crate::history::GameMove;
+use crate::server::error::ApiError;
+use crate::server::handlers::{self, CreateGameRequest, ListGamesQuery, Opponent, StatusFilter};
+use crate::server::state::{AppState, GameRecord, GameUpdate};
+
+/// The schema served at `/graphql`
+pub type GameSchema = Schema<QueryRoot, MutationRoot, SubscriptionRoot>;
+
+/// The schema, built the first time it is needed
+///
+/// The application state isn't part of the schema: it is attached to each
+/// request instead, so one schema serves every router.
+pub fn schema() -> GameSchema {
+    static SCHEMA: OnceLock<GameSchema> = OnceLock::new();
+    SCHEMA
+        .get_or_init(|| Schema::new(QueryRoot, MutationRoot, SubscriptionRoot))
+        .clone()
+}
+
+/// Execute a GraphQL query or mutation
+pub async fn graphql(State(state): State<AppState>, request: GraphQLRequest) -> GraphQLResponse {
+    schema().execute(request.into_inner().data(state)).await.into()
+}
+
+/// Serve GraphiQL for exploring the schema
+pub async fn graphiql() -> Html<String> {
+    Html(GraphiQLSource::build().endpoint("/graphql").subscription_endpoint("/graphql/ws").finish())
+}
+
+/// Upgrade to a WebSocket that serves GraphQL subscriptions
+pub async fn graphql_ws(
+    State(state): State<AppState>,
+    protocol: GraphQLProtocol,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    upgrade
+        .protocols(async_graphql::http::ALL_WEBSOCKET_PROTOCOLS)
+        .on_upgrade(move |socket| {
+            let mut data = Data::default();
+            data.insert(state);
+            GraphQLWebSocket::new(socket, schema(), protocol).with_data(data).serve()
+        })
+        .into_response()
+}
+
+/// A GraphQL error carrying the REST API's error code as its `code` extension
+fn graphql_error(error: GameError) -> Error {
+    let api = ApiError::from(error);
+    Error::new(api.message).extend_with(|_, extensions| extensions.set("code", api.code))
+}
+
+/// One of the two players
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+#[graphql(name = "Player", remote = "crate::player::Player")]
+pub enum PlayerValue {
+    X,
+    O,
+}
+
+/// Difficulty of an AI opponent
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+#[graphql(name = "Difficulty", remote = "crate::ai::Difficulty")]
+pub enum DifficultyValue {
+    Easy,
+    Medium,
+    Hard,
+}
+
+/// The rules a game is played by
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+#[graphql(name = "Variant", remote = "crate::rules::Variant")]
+pub enum VariantValue {
+    Standard,
+    ConnectFour,
+    Gomoku,
+    Misere,
+}
+
+/// How a game stands
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    /// The game is still being played
+    InProgress,
+    /// A player completed a line
+    Won,
+    /// The game ended without a winner
+    Draw,
+    /// A player resigned
+    Resigned,
+    /// A player ran out of time
+    TimedOut,
+}
+
+impl From<GameStatus> for Status {
+    fn from(status: GameStatus) -> Self {
+        match status {
+            GameStatus::InProgress => Status::InProgress,
+            GameStatus::Won(_) => Status::Won,
+            GameStatus::Draw => Status::Draw,
+            GameStatus::Resigned(_) => Status::Resigned,
+            GameStatus::TimedOut(_) => Status::TimedOut,
+        }
+    }
+}
+
+/// A cell of the board
+#[derive(SimpleObject)]
+pub struct Square {
+    /// Row of the cell, from the top
+    pub row: usize,
+    /// Column of the cell, from the left
+    pub col: usize,
+}
+
+/// A move made in a game
+#[derive(SimpleObject)]
+pub struct Move {
+    /// The player who made the move
+    pub player: PlayerValue,
+    /// Row of the cell played
+    pub row: usize,
+    /// Column of the cell played
+    pub col: usize,
+    /// When the move was made
+    pub timestamp: DateTime<Utc>,
+}
+
+impl From<&GameMove> for Move {
+    fn from(game_move: &GameMove) -> Self {
+        Self {
+            player: game_move.player.into(),
+            row: game_move.position.row,
+            col: game_move.position.col,
+            timestamp: game_move.timestamp,
+        }
+    }
+}
+
+/// A game, as any client may see it
+pub struct Game(GameRecord);
+
+#[Object]
+impl Game {
+    /// Unique identifier for the game
+    async fn id(&self) -> Uuid {
+        self.0.game.id
+    }
+
+    /// The board, row by row, with the player holding each cell
+    async fn board(&self) -> Vec<Vec<Option<PlayerValue>>> {
+        let cell = |cell: &Cell| match cell {
+            Cell::Occupied(player) => Some((*player).into()),
+            Cell::Empty => None,
+        };
+        self.0.game.board.iter().map(|row| row.iter().map(cell).collect()).collect()
+    }
+
+    /// The number of marks in a row needed to win
+    async fn win_length(&self) -> usize {
+        self.0.game.win_length
+    }
+
+    /// The rules the game is played by
+    async fn variant(&self) -> VariantValue {
+        self.0.game.variant.into()
+    }
+
+    /// The player whose turn it is
+    async fn current_turn(&self) -> PlayerValue {
+        self.0.game.current_turn.into()
+    }
+
+    /// How the game stands
+    async fn status(&self) -> Status {
+        self.0.game.status.into()
+    }
+
+    /// The player who won, if anyone has
+    async fn winner(&self) -> Option<PlayerValue> {
+        self.0.game.status.winner().map(Into::into)
+    }
+
+    /// The cells forming the winning line, if the game has been won
+    async fn winning_line(&self) -> Option<Vec<Square>> {
+        let line = self.0.game.winning_line.as_ref()?;
+        Some(line.iter().map(|&(row, col)| Square { row, col }).collect())
+    }
+
+    /// Counts moves made and taken back, so clients can tell whether their copy is stale
+    async fn version(&self) -> u64 {
+        self.0.game.version
+    }
+
+    /// The number of moves on the board
+    async fn move_count(&self) -> usize {
+        self.0.game.move_count
+    }
+
+    /// When the last move was made, if any have been
+    async fn last_move_at(&self) -> Option<DateTime<Utc>> {
+        self.0.game.last_move_at
+    }
+
+    /// When the game was created
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.0.created_at
+    }
+
+    /// When the game last changed
+    async fn updated_at(&self) -> DateTime<Utc> {
+        self.0.updated_at
+    }
+
+    /// The moves made so far, in order
+    async fn history(&self, ctx: &Context<'_>) -> Result<Vec<Move>> {
+        let state = ctx.data::<AppState>()?;
+        let histories = state.histories.read().await;
+
+        let moves = histories.get(&self.0.game.id).map(|history| history.moves.as_slice());
+        Ok(moves.unwrap_or_default().iter().map(Move::from).collect())
+    }
+}
+
+/// A page of games
+#[derive(SimpleObject)]
+pub struct GamePage {
+    /// The games on the page
+    pub games: Vec<Game>,
+    /// Pass as `after` to get the next page, if there is one
+    pub next_cursor: Option<String>,
+}
+
+/// The secret tokens to present when moving for each player
+#[derive(SimpleObject)]
+pub struct Tokens {
+    /// Token for the X player, unless the AI plays X
+    pub x: Option<String>,
+    /// Token for the O player, unless the AI plays O
+    pub o: Option<String>,
+}
+
+/// A newly created game
+#[derive(SimpleObject)]
+pub struct CreatedGame {
+    /// The game
+    pub game: Game,
+    /// The seat tokens, which are only ever shown here
+    pub tokens: Tokens,
+}
+
+/// Options for a new game; unset fields take the server's defaults
+#[derive(InputObject, Default)]
+pub struct CreateGameInput {
+    /// The rules to play by (standard tic-tac-toe by default)
+    pub variant: Option<VariantValue>,
+    /// The player who moves first (X by default)
+    pub starting_player: Option<PlayerValue>,
+    /// Board size for variants that don't fix it (3 by default)
+    pub size: Option<usize>,
+    /// Marks in a row needed to win (the board size by default)
+    pub win_length: Option<usize>,
+    /// Whether the server plays one side with an AI
+    pub against_ai: Option<bool>,
+    /// Difficulty of the AI opponent
+    pub difficulty: Option<DifficultyValue>,
+    /// Symbol the AI opponent plays as (O by default)
+    pub ai_player: Option<PlayerValue>,
+    /// Time each player has per move, in seconds (untimed by default)
+    pub move_time_limit: Option<u64>,
+}
+
+/// Read-only queries
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// The game with this ID, if there is one
+    async fn game(&self, ctx: &Context<'_>, id: Uuid) -> Result<Option<Game>> {
+        let state = ctx.data::<AppState>()?;
+        let games = state.games.read().await;
+        Ok(games.get(&id).map(|record| Game(record.public())))
+    }
+
+    /// Games a page at a time, oldest first
+    async fn games(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(desc = "Only finished (or only unfinished) games are listed")] finished: Option<bool>,
+        #[graphql(desc = "Most games to list (50 by default, at most 200)")] first: Option<usize>,
+        #[graphql(desc = "The `nextCursor` of the previous page")] after: Option<String>,
+    ) -> Result<GamePage> {
+        let state = ctx.data::<AppState>()?;
+        let query = ListGamesQuery {
+            status: finished.map(|finished| {
+                if finished { StatusFilter::Finished } else { StatusFilter::InProgress }
+            }),
+            limit: first,
+            cursor: after,
+            ..Default::default()
+        };
+
+        let games = state.games.read().await;
+        let (page, next_cursor) =
+            handlers::page_games(games.values(), &query).map_err(graphql_error)?;
+        Ok(GamePage {
+            games: page.into_iter().map(|record| Game(record.public())).collect(),
+            next_cursor,
+        })
+    }
+}
+
+/// Changes to games
+pub struct MutationRoot;
+
+#[Object]
+impl MutationRoot {
+    /// Create a new game
+    async fn create_game(
+        &self,
+        ctx: &Context<'_>,
+        input: Option<CreateGameInput>,
+    ) -> Result<CreatedGame> {
+        let state = ctx.data::<AppState>()?;
+        let input = input.unwrap_or_default();
+        let request = CreateGameRequest {
+            variant: input.variant.map(Into::into),
+            starting_player: input.starting_player.map(Into::into),
+            size: input.size,
+            win_length: input.win_length,
+            opponent: input.against_ai.map(|ai| if ai { Opponent::Ai } else { Opponent::Human }),
+            difficulty: input.difficulty.map(Into::into),
+            ai_player: input.ai_player.map(Into::into),
+            move_time_limit: input.move_time_limit,
+            ..Default::default()
+        };
+
+        let created = handlers::start_game(state, None, request).await.map_err(graphql_error)?;
+        Ok(CreatedGame {
+            game: Game(created.record),
+            tokens: Tokens {
+                x: created.tokens.x,
+                o: created.tokens.o,
+            },
+        })
+    }
+
+    /// Make a move for `player`, returning the game after it and any AI reply
+    #[allow(clippy::too_many_arguments)]
+    async fn make_move(
+        &self,
+        ctx: &Context<'_>,
+        game_id: Uuid,
+        player: PlayerValue,
+        row: usize,
+        col: usize,
+        #[graphql(desc = "The player's seat token")] token: Option<String>,
+        #[graphql(desc = "The version the move was chosen against")] expected_version: Option<u64>,
+    ) -> Result<Game> {
+        let state = ctx.data::<AppState>()?;
+        let record = state
+            .play_move(game_id, player.into(), token.as_deref(), (row, col).into(), expected_version)
+            .await
+            .map_err(graphql_error)?;
+        Ok(Game(record.public()))
+    }
+}
+
+/// Live updates
+pub struct SubscriptionRoot;
+
+#[Subscription]
+impl SubscriptionRoot {
+    /// The game after every move made in it, until it is removed
+    async fn moves(&self, ctx: &Context<'_>, game_id: Uuid) -> Result<impl Stream<Item = Game>> {
+        let state = ctx.data::<AppState>()?;
+        let updates = state.subscribe(game_id).await;
+        let current = state
+            .games
+            .read()
+            .await
+            .get(&game_id)
+            .map(GameRecord::public)
+            .ok_or_else(|| graphql_error(GameError::GameNotFound(game_id.to_string())))?;
+
+        Ok(stream::unfold((updates, current), |(mut updates, last)| async move {
+            loop {
+                match updates.recv().await {
+                    Ok(GameUpdate::State(record)) if record.game.board != last.game.board => {
+                        let record = *record;
+                        return Some((Game(record.clone()), (updates, record)));
+                    }
+                    Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_graphql::Response;
+
+    async fn execute(state: &AppState, query: String) -> Response {
+        schema().execute(async_graphql::Request::new(query).data(state.clone())).await
+    }
+
+    #[tokio::test]
+    async fn test_create_and_play() {
+        let state = AppState::new();
+        let execute = |query: String| execute(&state, query);
+
+        let created = execute("mutation { createGame { game { id } tokens { x } } }".into()).await;
+        assert!(created.errors.is_empty(), "{:?}", created.errors);
+        let created = created.data.into_json().unwrap();
+        let id = &created["createGame"]["game"]["id"];
+        let token = &created["createGame"]["tokens"]["x"];
+
+        let played = execute(format!(
+            "mutation {{ makeMove(gameId: {id}, player: X, row: 1, col: 1, token: {token}) \
+             {{ version }} }}"
+        ))
+        .await;
+        assert_eq!(played.data.into_json().unwrap()["makeMove"]["version"], 1);
+
+        let query = format!("{{ game(id: {id}) {{ currentTurn history {{ player row col }} }} }}");
+        let game = execute(query).await.data.into_json().unwrap();
+        assert_eq!(game["game"]["currentTurn"], "O");
+        assert_eq!(game["game"]["history"][0]["row"], 1);
+
+        let refused = execute(format!(
+            "mutation {{ makeMove(gameId: {id}, player: O, row: 1, col: 1) {{ version }} }}"
+        ))
+        .await;
+        let code = refused.errors[0].extensions.as_ref().unwrap().get("code");
+        assert_eq!(code, Some(&"INVALID_TOKEN".into()));
+    }
+}
@@ -0,0 +1,414 @@
+//! The gRPC API defined in `proto/game.proto`
+
+// Every call fails with tonic's `Status`, large as it is
+#![allow(clippy::result_large_err)]
+
+use std::net::SocketAddr;
+use std::pin::Pin;
+
+use axum::http::StatusCode;
+use futures_util::Stream;
+use tokio::sync::{broadcast::error::RecvError, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::metadata::MetadataValue;
+use tonic::{Request, Response, Status, Streaming};
+use uuid::Uuid;
+
+use crate::ai::Difficulty;
+use crate::error::GameError;
+use crate::game::{Cell, GameStatus};
+use crate::player::Player;
+use crate::position::Position;
+use crate::rules::Variant;
+use crate::server::error::ApiError;
+use crate::server::handlers::{self, CreateGameRequest, Opponent};
+use crate::server::state::{AppState, GameRecord, GameUpdate};
+
+/// The types and client generated from `proto/game.proto`
+pub mod proto {
+    tonic::include_proto!("tictactoe.v1");
+}
+
+use proto::games_server::{Games, GamesServer};
+use proto::{play_event, play_request, PlayEvent, PlayRequest};
+
+/// Metadata key carrying the REST API's error code on a failed call
+pub const ERROR_CODE_KEY: &str = "error-code";
+
+/// Events sent to a `Play` stream that the client hasn't read yet
+const PLAY_BUFFER: usize = 16;
+
+/// The `Games` gRPC service, backed by the same state as the REST API
+#[derive(Clone)]
+pub struct GrpcGames {
+    state: AppState,
+}
+
+impl GrpcGames {
+    /// Serve the games in `state`
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+}
+
+/// Serve the gRPC API on `address` until the server fails
+pub async fn serve_grpc(
+    state: AppState,
+    address: SocketAddr,
+) -> Result<(), tonic::transport::Error> {
+    tonic::transport::Server::builder()
+        .add_service(GamesServer::new(GrpcGames::new(state)))
+        .serve(address)
+        .await
+}
+
+/// A gRPC status for `error`, with the REST API's error code in its metadata
+fn status(error: GameError) -> Status {
+    let api = ApiError::from(error);
+    let code = match api.status {
+        StatusCode::BAD_REQUEST => tonic::Code::InvalidArgument,
+        StatusCode::UNAUTHORIZED => tonic::Code::Unauthenticated,
+        StatusCode::FORBIDDEN => tonic::Code::PermissionDenied,
+        StatusCode::NOT_FOUND => tonic::Code::NotFound,
+        StatusCode::CONFLICT => tonic::Code::FailedPrecondition,
+        StatusCode::SERVICE_UNAVAILABLE => tonic::Code::Unavailable,
+        _ => tonic::Code::Internal,
+    };
+
+    let mut status = Status::new(code, api.message);
+    status.metadata_mut().insert(ERROR_CODE_KEY, MetadataValue::from_static(api.code));
+    status
+}
+
+/// The game ID in a request
+fn parse_id(id: &str) -> Result<Uuid, Status> {
+    id.parse().map_err(|_| Status::invalid_argument(format!("'{}' is not a game ID", id)))
+}
+
+/// The status for a game that doesn't exist
+fn not_found(id: Uuid) -> Status {
+    status(GameError::GameNotFound(id.to_string()))
+}
+
+/// The player a field names, if it names one
+fn player(value: proto::Player) -> Option<Player> {
+    match value {
+        proto::Player::Unspecified => None,
+        proto::Player::X => Some(Player::X),
+        proto::Player::O => Some(Player::O),
+    }
+}
+
+/// The player a field names, which it must
+fn required_player(value: proto::Player) -> Result<Player, Status> {
+    player(value).ok_or_else(|| Status::invalid_argument("a player is required"))
+}
+
+/// The position a request must give
+fn position(value: Option<proto::Position>) -> Result<Position, Status> {
+    let value = value.ok_or_else(|| Status::invalid_argument("a position is required"))?;
+    Ok((value.row as usize, value.col as usize).into())
+}
+
+impl From<Player> for proto::Player {
+    fn from(player: Player) -> Self {
+        match player {
+            Player::X => proto::Player::X,
+            Player::O => proto::Player::O,
+        }
+    }
+}
+
+impl From<proto::Variant> for Variant {
+    fn from(variant: proto::Variant) -> Self {
+        match variant {
+            proto::Variant::Standard => Variant::Standard,
+            proto::Variant::ConnectFour => Variant::ConnectFour,
+            proto::Variant::Gomoku => Variant::Gomoku,
+            proto::Variant::Misere => Variant::Misere,
+        }
+    }
+}
+
+impl From<Variant> for proto::Variant {
+    fn from(variant: Variant) -> Self {
+        match variant {
+            Variant::Standard => proto::Variant::Standard,
+            Variant::ConnectFour => proto::Variant::ConnectFour,
+            Variant::Gomoku => proto::Variant::Gomoku,
+            Variant::Misere => proto::Variant::Misere,
+        }
+    }
+}
+
+impl From<&GameRecord> for proto::GameState {
+    fn from(record: &GameRecord) -> Self {
+        let game = &record.game;
+        let cell = |cell: &Cell| match cell {
+            Cell::Occupied(player) => proto::Player::from(*player) as i32,
+            Cell::Empty => proto::Player::Unspecified as i32,
+        };
+        let status = match game.status {
+            GameStatus::InProgress => proto::Status::InProgress,
+            GameStatus::Won(_) => proto::Status::Won,
+            GameStatus::Draw => proto::Status::Draw,
+            GameStatus::Resigned(_) => proto::Status::Resigned,
+            GameStatus::TimedOut(_) => proto::Status::TimedOut,
+        };
+        let winning_line = game.winning_line.iter().flatten().map(|&(row, col)| proto::Position {
+            row: row as u32,
+            col: col as u32,
+        });
+
+        Self {
+            id: game.id.to_string(),
+            rows: game.rows() as u32,
+            cols: game.cols() as u32,
+            cells: game.board.iter().flatten().map(cell).collect(),
+            win_length: game.win_length as u32,
+            variant: proto::Variant::from(game.variant) as i32,
+            current_turn: proto::Player::from(game.current_turn) as i32,
+            status: status as i32,
+            winner: game.status.winner().map_or(proto::Player::Unspecified, Into::into) as i32,
+            winning_line: winning_line.collect(),
+            version: game.version,
+            move_count: game.move_count as u32,
+            last_move_at_ms: game.last_move_at.map(|at| at.timestamp_millis()),
+        }
+    }
+}
+
+/// Make a move sent over a `Play` stream
+async fn play(
+    state: &AppState,
+    id: Uuid,
+    player: Player,
+    token: &str,
+    game_move: proto::Move,
+) -> Result<(), Status> {
+    let position = position(game_move.position)?;
+    state
+        .play_move(id, player, Some(token), position, game_move.expected_version)
+        .await
+        .map_err(status)?;
+    Ok(())
+}
+
+#[tonic::async_trait]
+impl Games for GrpcGames {
+    async fn create_game(
+        &self,
+        request: Request<proto::CreateGameRequest>,
+    ) -> Result<Response<proto::CreateGameResponse>, Status> {
+        let request = request.into_inner();
+        let ai_player = player(request.ai_player());
+        let difficulty = match request.difficulty() {
+            proto::Difficulty::Unspecified => None,
+            proto::Difficulty::Easy => Some(Difficulty::Easy),
+            proto::Difficulty::Medium => Some(Difficulty::Medium),
+            proto::Difficulty::Hard => Some(Difficulty::Hard),
+        };
+
+        let created = handlers::start_game(
+            &self.state,
+            None,
+            CreateGameRequest {
+                variant: Some(request.variant().into()),
+                starting_player: player(request.starting_player()),
+                size: request.size.map(|size| size as usize),
+                win_length: request.win_length.map(|length| length as usize),
+                opponent: ai_player.map(|_| Opponent::Ai),
+                difficulty,
+                ai_player,
+                move_time_limit: request.move_time_limit,
+                ..Default::default()
+            },
+        )
+        .await
+        .map_err(status)?;
+
+        Ok(Response::new(proto::CreateGameResponse {
+            game: Some((&created.record).into()),
+            x_token: created.tokens.x.unwrap_or_default(),
+            o_token: created.tokens.o.unwrap_or_default(),
+        }))
+    }
+
+    async fn get_game(
+        &self,
+        request: Request<proto::GetGameRequest>,
+    ) -> Result<Response<proto::GameState>, Status> {
+        let id = parse_id(&request.get_ref().id)?;
+        let games = self.state.games.read().await;
+
+        let record = games.get(&id).ok_or_else(|| not_found(id))?;
+        Ok(Response::new(record.into()))
+    }
+
+    async fn make_move(
+        &self,
+        request: Request<proto::MoveRequest>,
+    ) -> Result<Response<proto::GameState>, Status> {
+        let request = request.into_inner();
+        let id = parse_id(&request.game_id)?;
+        let player = required_player(request.player())?;
+
+        let position = position(request.position)?;
+        let record = self
+            .state
+            .play_move(id, player, Some(&request.token), position, request.expected_version)
+            .await
+            .map_err(status)?;
+        Ok(Response::new((&record).into()))
+    }
+
+    type PlayStream = Pin<Box<dyn Stream<Item = Result<PlayEvent, Status>> + Send>>;
+
+    async fn play(
+        &self,
+        request: Request<Streaming<PlayRequest>>,
+    ) -> Result<Response<Self::PlayStream>, Status> {
+        let mut requests = request.into_inner();
+
+        // The first message says which game and seat the stream plays
+        let join = match requests.message().await? {
+            Some(PlayRequest { request: Some(play_request::Request::Join(join)) }) => join,
+            _ => return Err(Status::invalid_argument("the first message must be a Join")),
+        };
+        let id = parse_id(&join.game_id)?;
+        let player = required_player(join.player())?;
+
+        // Check the seat and subscribe before sending the game, so no change is missed
+        let mut updates = self.state.subscribe(id).await;
+        let current = {
+            let games = self.state.games.read().await;
+            let record = games.get(&id).ok_or_else(|| not_found(id))?;
+            if let Some(tokens) = &record.seat_tokens {
+                tokens.verify(player, Some(&join.token)).map_err(status)?;
+            }
+            proto::GameState::from(record)
+        };
+
+        let (events, stream) = mpsc::channel(PLAY_BUFFER);
+        let state = self.state.clone();
+        tokio::spawn(async move {
+            let send = |event| events.send(Ok(PlayEvent { event: Some(event) }));
+            let mut over = current.status != proto::Status::InProgress as i32;
+            if send(play_event::Event::Game(current)).await.is_err() {
+                return;
+            }
+
+            while !over {
+                tokio::select! {
+                    update = updates.recv() => match update {
+                        Ok(GameUpdate::State(record)) => {
+                            over = record.game.is_over();
+                            if send(play_event::Event::Game((&*record).into())).await.is_err() {
+                                return;
+                            }
+                        }
+                        Ok(GameUpdate::Chat(_) | GameUpdate::Event(_)) | Err(RecvError::Lagged(_)) => {}
+                        Err(RecvError::Closed) => return,
+                    },
+                    request = requests.message() => {
+                        let Ok(Some(PlayRequest { request })) = request else {
+                            return;
+                        };
+
+                        // The game after a move arrives with the other updates
+                        let result = match request {
+                            Some(play_request::Request::Move(game_move)) => {
+                                play(&state, id, player, &join.token, game_move).await
+                            }
+                            _ => Err(Status::invalid_argument("only moves may follow the Join")),
+                        };
+
+                        if let Err(error) = result {
+                            let code = error.metadata().get(ERROR_CODE_KEY);
+                            let code = code.and_then(|code| code.to_str().ok());
+                            let error = proto::MoveError {
+                                code: code.unwrap_or("INVALID_REQUEST").to_string(),
+                                message: error.message().to_string(),
+                            };
+                            if send(play_event::Event::Error(error)).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(Response::new(Box::pin(ReceiverStream::new(stream))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proto::games_client::GamesClient;
+    use tokio::net::TcpListener;
+    use tokio_stream::wrappers::TcpListenerStream;
+
+    #[tokio::test]
+    async fn test_play_stream_against_ai() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let service = GamesServer::new(GrpcGames::new(AppState::new()));
+        tokio::spawn(
+            tonic::transport::Server::builder()
+                .add_service(service)
+                .serve_with_incoming(TcpListenerStream::new(listener)),
+        );
+
+        let mut client = GamesClient::connect(format!("http://{}", address)).await.unwrap();
+        let created = client
+            .create_game(proto::CreateGameRequest {
+                ai_player: proto::Player::O as i32,
+                difficulty: proto::Difficulty::Easy as i32,
+                ..Default::default()
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        let game_id = created.game.unwrap().id;
+
+        let (requests, outgoing) = mpsc::channel(4);
+        let request = |request| PlayRequest { request: Some(request) };
+        let play_move = |row, col| {
+            let position = Some(proto::Position { row, col });
+            request(play_request::Request::Move(proto::Move { position, expected_version: None }))
+        };
+        requests
+            .send(request(play_request::Request::Join(proto::Join {
+                game_id,
+                player: proto::Player::X as i32,
+                token: created.x_token,
+            })))
+            .await
+            .unwrap();
+        let mut events = client.play(ReceiverStream::new(outgoing)).await.unwrap().into_inner();
+
+        let next = |event: Option<PlayEvent>| event.unwrap().event.unwrap();
+        let play_event::Event::Game(game) = next(events.message().await.unwrap()) else {
+            panic!("expected the game");
+        };
+        assert_eq!((game.rows, game.cells.len(), game.version), (3, 9, 0));
+
+        // X's move arrives, followed by the AI's reply
+        requests.send(play_move(1, 1)).await.unwrap();
+        for version in [1, 2] {
+            let play_event::Event::Game(game) = next(events.message().await.unwrap()) else {
+                panic!("expected the game");
+            };
+            assert_eq!(game.version, version);
+            assert_eq!(game.cells[4], proto::Player::X as i32);
+        }
+
+        requests.send(play_move(1, 1)).await.unwrap();
+        let play_event::Event::Error(error) = next(events.message().await.unwrap()) else {
+            panic!("expected an error");
+        };
+        assert_eq!(error.code, "CELL_OCCUPIED");
+    }
+}
@@ -1,21 +1,50 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
-use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+use serde::{de, Deserialize, Deserializer, Serialize};
+use tracing::{field, instrument, Span};
 use uuid::Uuid;
 
-use crate::ai::{Difficulty, MinimaxAI};
-use crate::game::{GameState, GameStatus};
-use crate::player::{GamePlayer, Player};
-use crate::server::state::AppState;
+use crate::ai::{Difficulty, MinimaxAI, SearchStats};
+use crate::analysis::{analyze_history, GameAnalysis};
+use crate::error::GameError;
+use crate::game::{GameState, GameStatus, DEFAULT_SIZE};
+use crate::history::{ChatMessage, GameHistory};
+use crate::player::Player;
+use crate::position::Position;
+use crate::rules::Variant;
+use crate::server::admin::AdminUser;
+use crate::server::auth::{AuthUser, MaybeUser};
+use crate::server::error::ApiError;
+use crate::server::matchmaking::Seat;
+use crate::server::negotiate::Format;
+use crate::server::state::{AiOpponent, AppState, CreatorTokens, GameRecord, SeatTokens};
+use crate::server::webhooks::Webhook;
+
+/// Number of games listed per page unless the client asks for fewer
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// The most games listed on one page
+pub const MAX_PAGE_SIZE: usize = 200;
+
+/// Largest board, in cells, whose moves are all scored at Hard difficulty
+pub const MAX_HARD_EVALUATION_CELLS: usize = 25;
 
 /// Response for listing games
 #[derive(Debug, Serialize)]
 pub struct GamesListResponse {
     /// List of game IDs
     pub games: Vec<GameSummary>,
+    /// Cursor for the next page, if there are more games
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
 }
 
 /// Summary of a game
@@ -27,24 +56,234 @@ pub struct GameSummary {
     pub status: GameStatus,
     /// Current turn
     pub current_turn: Player,
+    /// Cells forming the winning line, if the game has been won
+    pub winning_line: Option<Vec<(usize, usize)>>,
+    /// When the game was created
+    pub created_at: DateTime<Utc>,
+    /// When the game last changed
+    pub updated_at: DateTime<Utc>,
+    /// When the game will be archived or deleted unless it changes again
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Number of spectators watching the game
+    pub spectators: usize,
+}
+
+impl GameSummary {
+    /// Summarize a game, counting its spectators in `state`
+    pub fn new(record: &GameRecord, state: &AppState) -> Self {
+        Self {
+            id: record.game.id,
+            status: record.game.status,
+            current_turn: record.game.current_turn,
+            winning_line: record.game.winning_line.clone(),
+            created_at: record.created_at,
+            updated_at: record.updated_at,
+            expires_at: record.expires_at,
+            spectators: state.spectators.count(record.game.id),
+        }
+    }
+}
+
+/// The timestamp games are listed in order of
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    /// Oldest game first
+    #[default]
+    CreatedAt,
+    /// Least recently changed game first
+    UpdatedAt,
+}
+
+impl SortKey {
+    /// The position of `record` in this order
+    ///
+    /// Games with the same timestamp are ordered by ID, so every game has a
+    /// distinct position for a cursor to point at.
+    fn key(&self, record: &GameRecord) -> (i64, Uuid) {
+        let timestamp = match self {
+            SortKey::CreatedAt => record.created_at,
+            SortKey::UpdatedAt => record.updated_at,
+        };
+        (timestamp.timestamp_micros(), record.game.id)
+    }
+}
+
+/// Query parameters for listing games
+#[derive(Debug, Default, Deserialize)]
+pub struct ListGamesQuery {
+    /// Only games with this status are listed
+    pub status: Option<StatusFilter>,
+    /// Most games to list (50 by default, at most 200)
+    pub limit: Option<usize>,
+    /// The `next_cursor` of the previous page
+    pub cursor: Option<String>,
+    /// Order to list games in (creation time by default)
+    #[serde(default)]
+    pub sort: SortKey,
+}
+
+/// Encode the position just after which the next page starts
+fn encode_cursor((timestamp, id): (i64, Uuid)) -> String {
+    format!("{timestamp}_{id}")
+}
+
+/// Decode a cursor made by [`encode_cursor`]
+fn decode_cursor(cursor: &str) -> Result<(i64, Uuid), GameError> {
+    let invalid = || GameError::InvalidCursor(cursor.to_string());
+    let (timestamp, id) = cursor.split_once('_').ok_or_else(invalid)?;
+    Ok((timestamp.parse().map_err(|_| invalid())?, id.parse().map_err(|_| invalid())?))
+}
+
+/// Select one page of `records` matching `query`, with the cursor for the page after it
+pub(crate) fn page_games<'a>(
+    records: impl Iterator<Item = &'a GameRecord>,
+    query: &ListGamesQuery,
+) -> Result<(Vec<&'a GameRecord>, Option<String>), GameError> {
+    let after = query.cursor.as_deref().map(decode_cursor).transpose()?;
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+
+    let mut matching: Vec<_> = records
+        .filter(|record| query.status.is_none_or(|status| status.matches(&record.game)))
+        .filter(|record| after.is_none_or(|after| query.sort.key(record) > after))
+        .collect();
+    matching.sort_by_key(|record| query.sort.key(record));
+
+    let next_cursor = (matching.len() > limit)
+        .then(|| encode_cursor(query.sort.key(matching[limit - 1])));
+    matching.truncate(limit);
+
+    Ok((matching, next_cursor))
+}
+
+/// Who a game is played against
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Opponent {
+    /// Another client makes the opponent's moves
+    Human,
+    /// The server makes the opponent's moves with an AI
+    Ai,
 }
 
 /// Request for creating a game
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 pub struct CreateGameRequest {
+    /// Optional variant of the game (defaults to standard tic-tac-toe)
+    pub variant: Option<Variant>,
     /// Optional player to start (defaults to X)
     pub starting_player: Option<Player>,
+    /// Optional board size (defaults to 3, ignored for Connect Four and Gomoku)
+    pub size: Option<usize>,
+    /// Optional number of marks in a row needed to win (defaults to the board size, ignored for Connect Four and Gomoku)
+    pub win_length: Option<usize>,
+    /// Optional opponent type (defaults to human)
+    pub opponent: Option<Opponent>,
+    /// Difficulty of the AI opponent (defaults to the server's configured difficulty)
+    pub difficulty: Option<Difficulty>,
+    /// Symbol the AI opponent plays as (defaults to O)
+    pub ai_player: Option<Player>,
+    /// Optional time each player has per move, in seconds (moves are untimed by default)
+    pub move_time_limit: Option<u64>,
+    /// Seat left open for a remote player to claim with `POST /games/:id/join`
+    pub open_seat: Option<Player>,
+    /// URL the game's events are POSTed to as they happen
+    pub webhook_url: Option<String>,
 }
 
 /// Request for making a move
 #[derive(Debug, Deserialize)]
 pub struct MakeMoveRequest {
-    /// Row index (0-2)
-    pub row: usize,
-    /// Column index (0-2)
-    pub col: usize,
+    /// The cell to play, given as `row` and `col` fields
+    #[serde(flatten)]
+    pub position: Position,
     /// Player making the move
     pub player: Player,
+    /// The player's seat token, issued when the game was created
+    pub token: Option<String>,
+    /// The game version the move was chosen against; the move is refused if the game has moved on
+    #[serde(default)]
+    pub expected_version: Option<u64>,
+}
+
+/// Which games a bulk operation applies to
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusFilter {
+    /// Games that have been won or drawn
+    Finished,
+    /// Games that are still being played
+    InProgress,
+}
+
+impl StatusFilter {
+    /// Returns true if the game matches this filter
+    pub fn matches(&self, game: &GameState) -> bool {
+        match self {
+            StatusFilter::Finished => game.is_over(),
+            StatusFilter::InProgress => !game.is_over(),
+        }
+    }
+}
+
+/// Query parameters for deleting games in bulk
+#[derive(Debug, Deserialize)]
+pub struct DeleteGamesQuery {
+    /// Only games with this status are deleted
+    pub status: StatusFilter,
+}
+
+/// Response for deleting games in bulk
+#[derive(Debug, Serialize)]
+pub struct DeleteGamesResponse {
+    /// IDs of the deleted games
+    pub deleted: Vec<Uuid>,
+}
+
+/// Request identifying the player performing an action such as resigning
+#[derive(Debug, Deserialize)]
+pub struct PlayerActionRequest {
+    /// Player performing the action
+    pub player: Player,
+    /// The player's seat token, issued when the game was created
+    pub token: Option<String>,
+}
+
+/// How a player takes part in an undo
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UndoAction {
+    /// Ask to take back the player's last move
+    #[default]
+    Request,
+    /// Accept the opponent's request, taking back their last move
+    Accept,
+    /// Decline the opponent's request
+    Decline,
+}
+
+/// Request for taking part in an undo
+#[derive(Debug, Deserialize)]
+pub struct UndoRequest {
+    /// Player taking part
+    pub player: Player,
+    /// The player's seat token, issued when the game was created
+    pub token: Option<String>,
+    /// Whether the player is asking for an undo or answering the opponent's request
+    #[serde(default)]
+    pub action: UndoAction,
+}
+
+/// Request for sending a chat message
+#[derive(Debug, Deserialize)]
+pub struct ChatRequest {
+    /// Player sending the message
+    pub player: Player,
+    /// The player's seat token, issued when the game was created
+    pub token: Option<String>,
+    /// What the player says
+    pub text: String,
 }
 
 /// Request for making an AI move
@@ -52,101 +291,842 @@ pub struct MakeMoveRequest {
 pub struct MakeAIMoveRequest {
     /// Difficulty level for the AI
     pub difficulty: Difficulty,
+    /// The seat token of the player the AI moves for
+    pub token: Option<String>,
+    /// Whether to include statistics about the AI's search in the response
+    #[serde(default)]
+    pub include_stats: bool,
+}
+
+/// Response for an AI move
+#[derive(Debug, Serialize)]
+pub struct AiMoveResponse {
+    /// The game after the move
+    #[serde(flatten)]
+    pub record: GameRecord,
+    /// How the AI searched for its move, if requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search: Option<SearchStats>,
+}
+
+/// Query parameters for move evaluations
+#[derive(Debug, Deserialize)]
+pub struct EvaluationsQuery {
+    /// How deeply to search, like `?difficulty=hard` (the server's configured difficulty by default)
+    #[serde(default, deserialize_with = "parse_optional")]
+    pub difficulty: Option<Difficulty>,
+}
+
+/// Deserialize an optional query parameter with its [`FromStr`] implementation
+fn parse_optional<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|value| value.parse().map_err(de::Error::custom))
+        .transpose()
+}
+
+/// The score of a single legal move
+#[derive(Debug, Serialize)]
+pub struct MoveEvaluation {
+    /// Where the move is played
+    #[serde(flatten)]
+    pub position: Position,
+    /// The move's score: positive is good for the player to move
+    pub score: i32,
+}
+
+/// Response for move evaluations
+#[derive(Debug, Serialize)]
+pub struct EvaluationsResponse {
+    /// The player to move, whose options are scored
+    pub player: Player,
+    /// The difficulty the moves were searched at
+    pub difficulty: Difficulty,
+    /// Every legal move, in row-major order
+    pub evaluations: Vec<MoveEvaluation>,
 }
 
-/// List all games
+/// Response for creating a game
+#[derive(Debug, Serialize)]
+pub struct CreateGameResponse {
+    /// The new game
+    #[serde(flatten)]
+    pub record: GameRecord,
+    /// The seat tokens to present when moving for each player, except for
+    /// a seat left open or played by the AI
+    pub tokens: CreatorTokens,
+    /// The key the webhook's deliveries are signed with, if the game has a webhook
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub webhook_secret: Option<String>,
+}
+
+/// List games a page at a time
+///
+/// Pass a page's `next_cursor` back as `cursor` to get the page after it.
 pub async fn list_games(
     State(state): State<AppState>,
-) -> Result<Json<GamesListResponse>, StatusCode> {
+    Query(query): Query<ListGamesQuery>,
+) -> Result<Json<GamesListResponse>, ApiError> {
     let games = state.games.read().await;
+    let (page, next_cursor) = page_games(games.values(), &query)?;
     
-    let game_summaries = games
-        .iter()
-        .map(|(id, game)| GameSummary {
-            id: *id,
-            status: game.status,
-            current_turn: game.current_turn,
-        })
-        .collect();
+    let game_summaries = page.into_iter().map(|record| GameSummary::new(record, &state)).collect();
     
     Ok(Json(GamesListResponse {
         games: game_summaries,
+        next_cursor,
     }))
 }
 
+/// Set up an empty board for `variant`, with the requested size for variants that have one
+pub(crate) fn new_game(
+    variant: Option<Variant>,
+    size: Option<usize>,
+    win_length: Option<usize>,
+) -> Result<GameState, GameError> {
+    Ok(match variant.unwrap_or_default() {
+        Variant::ConnectFour => GameState::connect_four(),
+        Variant::Gomoku => GameState::gomoku(),
+        variant => {
+            let size = size.unwrap_or(DEFAULT_SIZE);
+            let mut game = GameState::with_size(size, win_length.unwrap_or(size))?;
+            game.variant = variant;
+            game
+        }
+    })
+}
+
 /// Create a new game
+///
+/// The caller gets the tokens of every seat not taken by the AI or left
+/// open, and if they're logged in, those seats are theirs. An open seat's
+/// token is only handed out to whoever joins it.
 pub async fn create_game(
     State(state): State<AppState>,
+    MaybeUser(user): MaybeUser,
     Json(request): Json<CreateGameRequest>,
-) -> Result<Json<GameState>, StatusCode> {
-    let mut game = GameState::new();
+) -> Result<Json<CreateGameResponse>, ApiError> {
+    Ok(Json(start_game(&state, user.as_ref(), request).await?))
+}
+
+/// Set up and register the game described by `request`, seating `user` if they're logged in
+#[instrument(skip_all, fields(game_id = field::Empty))]
+pub(crate) async fn start_game(
+    state: &AppState,
+    user: Option<&AuthUser>,
+    request: CreateGameRequest,
+) -> Result<CreateGameResponse, GameError> {
+    let mut game = new_game(request.variant, request.size, request.win_length)?;
     
     // Set the starting player if specified
     if let Some(starting_player) = request.starting_player {
         game.current_turn = starting_player;
     }
     
+    // Start the clock if moves are timed
+    if let Some(seconds) = request.move_time_limit {
+        game.set_move_time_limit(seconds);
+    }
+    
+    // Set up the seat tokens and the AI opponent if requested
+    let mut record = GameRecord::new(game);
+    Span::current().record("game_id", field::display(record.game.id));
+    let tokens = SeatTokens::generate();
+    record.seat_tokens = Some(tokens.clone());
+    if request.opponent == Some(Opponent::Ai) {
+        record.ai_opponent = Some(AiOpponent {
+            player: request.ai_player.unwrap_or(Player::O),
+            difficulty: request.difficulty.unwrap_or(state.ai_difficulty),
+        });
+    }
+    
+    // Send the game's events to its webhook
+    if let Some(url) = &request.webhook_url {
+        record.webhook = Some(Webhook::new(url)?);
+    }
+    
+    // Leave a seat for a remote player, unless the AI is sitting in it
+    if request.open_seat != record.ai_opponent.map(|opponent| opponent.player) {
+        record.open_seat = request.open_seat;
+    }
+    
+    // Seat the logged-in creator in the seats nobody else plays
+    let taken = [record.ai_opponent.map(|opponent| opponent.player), record.open_seat];
+    if let Some(user) = user {
+        for player in [Player::X, Player::O] {
+            if !taken.contains(&Some(player)) {
+                record.users.seat(player, user);
+            }
+        }
+    }
+    
     // Add the game to the state
-    let game_id = game.id;
-    state.games.write().await.insert(game_id, game.clone());
+    let record = state.insert_game(record).await?;
     
-    Ok(Json(game))
+    Ok(CreateGameResponse {
+        record: record.public(),
+        tokens: tokens.except(&taken),
+        webhook_secret: record.webhook.map(|webhook| webhook.secret),
+    })
 }
 
 /// Get a game by ID
+///
+/// Clients that accept `text/plain` get the board drawn in ASCII instead.
+#[instrument(skip_all, fields(game_id = %id))]
 pub async fn get_game(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
-) -> Result<Json<GameState>, StatusCode> {
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
     let games = state.games.read().await;
     
-    let game = games.get(&id).ok_or(StatusCode::NOT_FOUND)?;
+    let record = games.get(&id).ok_or_else(|| GameError::GameNotFound(id.to_string()))?;
     
-    Ok(Json(game.clone()))
+    if Format::preferred(&headers) == Format::PlainText {
+        return Ok(plain_text(&record.game).into_response());
+    }
+    Ok(Json(record.public()).into_response())
 }
 
-/// Make a move in a game
-pub async fn make_move(
+/// The game as text for a terminal: its board and how it stands
+fn plain_text(game: &GameState) -> String {
+    let status = match game.status {
+        GameStatus::InProgress => format!("{} to move", game.current_turn),
+        GameStatus::Won(player) => format!("{} wins", player),
+        GameStatus::Draw => "Draw".to_string(),
+        GameStatus::Resigned(player) => {
+            format!("{} resigned, {} wins", player, player.opponent())
+        }
+        GameStatus::TimedOut(player) => {
+            format!("{} ran out of time, {} wins", player, player.opponent())
+        }
+    };
+    format!("Game {}\n{}{}\n", game.id, game.render_board(), status)
+}
+
+/// Claim a game's open seat, receiving the token needed to play it
+#[instrument(skip_all, fields(game_id = %id))]
+pub async fn join_game(
     State(state): State<AppState>,
+    MaybeUser(user): MaybeUser,
     Path(id): Path<Uuid>,
-    Json(request): Json<MakeMoveRequest>,
-) -> Result<Json<GameState>, StatusCode> {
-    // Get the game
-    let mut games = state.games.write().await;
-    let game = games.get_mut(&id).ok_or(StatusCode::NOT_FOUND)?;
+) -> Result<Json<Seat>, ApiError> {
+    let seat = state.claim_open_seat(id, user.as_ref()).await?;
+    
+    Ok(Json(seat))
+}
+
+/// Delete a game and its history, which only admins may do
+#[instrument(skip_all, fields(game_id = %id))]
+pub async fn delete_game(
+    State(state): State<AppState>,
+    _: AdminUser,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    state.remove_game(id).await?;
+    
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Delete every game matching a status filter, e.g. `DELETE /games?status=finished`
+///
+/// Only admins may do this.
+pub async fn delete_games(
+    State(state): State<AppState>,
+    _: AdminUser,
+    Query(query): Query<DeleteGamesQuery>,
+) -> Result<Json<DeleteGamesResponse>, ApiError> {
+    let ids: Vec<Uuid> = state
+        .games
+        .read()
+        .await
+        .values()
+        .filter(|record| query.status.matches(&record.game))
+        .map(|record| record.game.id)
+        .collect();
+    
+    let mut deleted = Vec::new();
+    for id in ids {
+        match state.remove_game(id).await {
+            Ok(_) => deleted.push(id),
+            // Another request removed it first
+            Err(GameError::GameNotFound(_)) => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    
+    Ok(Json(DeleteGamesResponse { deleted }))
+}
+
+/// Get the move history of a game
+#[instrument(skip_all, fields(game_id = %id))]
+pub async fn get_history(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<GameHistory>, ApiError> {
+    let histories = state.histories.read().await;
+    
+    let history = histories.get(&id).ok_or_else(|| GameError::GameNotFound(id.to_string()))?;
+    
+    Ok(Json(history.clone()))
+}
+
+/// A game and its history, as the CLI saves them to `game_<id>.json` and `history_<id>.json`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GameExport {
+    /// The state of the game
+    pub game: GameState,
+    /// How the game was played
+    pub history: GameHistory,
+}
+
+/// Host a game saved by the CLI, to carry on playing it on the server
+///
+/// The game keeps its ID, and new seat tokens are issued for it.
+#[instrument(skip_all, fields(game_id = %export.game.id))]
+pub async fn import_game(
+    State(state): State<AppState>,
+    Json(export): Json<GameExport>,
+) -> Result<Json<CreateGameResponse>, ApiError> {
+    let mut record = GameRecord::new(export.game);
+    let tokens = SeatTokens::generate();
+    record.seat_tokens = Some(tokens.clone());
+
+    let record = state.import_game(record, export.history).await?;
+
+    Ok(Json(CreateGameResponse {
+        record: record.public(),
+        tokens: tokens.except(&[]),
+        webhook_secret: None,
+    }))
+}
+
+/// Get a game with its history, in the form `POST /games/import` takes
+#[instrument(skip_all, fields(game_id = %id))]
+pub async fn export_game(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<GameExport>, ApiError> {
+    let game = state
+        .games
+        .read()
+        .await
+        .get(&id)
+        .map(|record| record.game.clone())
+        .ok_or_else(|| GameError::GameNotFound(id.to_string()))?;
+    let history = state
+        .histories
+        .read()
+        .await
+        .get(&id)
+        .cloned()
+        .unwrap_or_else(|| game.create_history());
+
+    Ok(Json(GameExport { game, history }))
+}
+
+/// Analyze every move made so far in a game
+///
+/// Gives up with `AI_TIMEOUT` if the analysis takes longer than the server's
+/// AI timeout.
+#[instrument(skip_all, fields(game_id = %id))]
+pub async fn get_analysis(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<GameAnalysis>, ApiError> {
+    let history = state
+        .histories
+        .read()
+        .await
+        .get(&id)
+        .cloned()
+        .ok_or_else(|| GameError::GameNotFound(id.to_string()))?;
+    
+    let analysis = state.run_search(move || analyze_history(&history)).await??;
     
-    // Verify it's the correct player's turn
-    if game.current_turn != request.player {
-        return Err(StatusCode::BAD_REQUEST);
+    Ok(Json(analysis))
+}
+
+/// Score every legal move for the player to move, e.g. `GET /games/:id/evaluations?difficulty=Hard`
+///
+/// Positive scores are good for the player to move. Searches at the server's
+/// configured difficulty unless another is given, though boards with more
+/// than [`MAX_HARD_EVALUATION_CELLS`] cells are searched at Medium at most.
+/// Gives up with `AI_TIMEOUT` if the search takes longer than the server's
+/// AI timeout.
+#[instrument(skip_all, fields(game_id = %id))]
+pub async fn get_evaluations(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<EvaluationsQuery>,
+) -> Result<Json<EvaluationsResponse>, ApiError> {
+    let game = state
+        .games
+        .read()
+        .await
+        .get(&id)
+        .map(|record| record.game.clone())
+        .ok_or_else(|| GameError::GameNotFound(id.to_string()))?;
+    let player = game.current_turn;
+    let mut difficulty = query.difficulty.unwrap_or(state.ai_difficulty);
+    if difficulty == Difficulty::Hard && game.rows() * game.cols() > MAX_HARD_EVALUATION_CELLS {
+        difficulty = Difficulty::Medium;
     }
+    let ai = MinimaxAI::new(player, difficulty);
     
-    // Make the move
-    game.make_move(request.row, request.col)
-        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let scores = state.run_search(move || ai.evaluate_all(&game)).await?;
     
-    Ok(Json(game.clone()))
+    let mut evaluations: Vec<MoveEvaluation> = scores
+        .into_iter()
+        .map(|(position, score)| MoveEvaluation { position, score })
+        .collect();
+    evaluations.sort_by_key(|evaluation| (evaluation.position.row, evaluation.position.col));
+    
+    Ok(Json(EvaluationsResponse { player, difficulty, evaluations }))
+}
+
+/// Make a move in a game
+///
+/// If the game has an AI opponent, its reply is included in the returned state.
+/// The version the move was chosen against can be given as `expected_version`
+/// or an `If-Match` header, and a stale version is refused with 409 Conflict.
+#[instrument(skip_all, fields(game_id = %id))]
+pub async fn make_move(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+    Json(request): Json<MakeMoveRequest>,
+) -> Result<Json<GameRecord>, ApiError> {
+    let expected_version = match request.expected_version {
+        Some(version) => Some(version),
+        None => if_match_version(&headers)?,
+    };
+
+    let record = state
+        .play_move(id, request.player, request.token.as_deref(), request.position, expected_version)
+        .await?;
+    
+    Ok(Json(record.public()))
+}
+
+/// Parse the game version from an `If-Match` header, if there is one
+///
+/// The version may be quoted like an ETag, as in `If-Match: "3"`.
+fn if_match_version(headers: &HeaderMap) -> Result<Option<u64>, GameError> {
+    let Some(value) = headers.get(header::IF_MATCH) else {
+        return Ok(None);
+    };
+
+    value
+        .to_str()
+        .ok()
+        .and_then(|value| value.trim().trim_matches('"').parse().ok())
+        .map(Some)
+        .ok_or_else(|| GameError::DeserializationError("If-Match must be a game version".to_string()))
 }
 
 /// Make an AI move in a game
+///
+/// When it's the turn of the game's own AI opponent, the opponent moves at
+/// its own difficulty and no token is needed, which lets a client ask it
+/// again after it failed to reply.
+#[instrument(skip_all, fields(game_id = %id))]
 pub async fn make_ai_move(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
     Json(request): Json<MakeAIMoveRequest>,
-) -> Result<Json<GameState>, StatusCode> {
-    // Get the game
-    let mut games = state.games.write().await;
-    let game = games.get_mut(&id).ok_or(StatusCode::NOT_FOUND)?;
-    
-    // Create an AI player
-    let ai = MinimaxAI::new(game.current_turn, request.difficulty);
+) -> Result<Json<AiMoveResponse>, ApiError> {
+    // Take a copy of the game, so the lock isn't held while the AI thinks
+    let record = state
+        .games
+        .read()
+        .await
+        .get(&id)
+        .cloned()
+        .ok_or_else(|| GameError::GameNotFound(id.to_string()))?;
+    let game = record.game.clone();
+    let player = game.current_turn;
+
+    if record.ai_opponent.is_some_and(|opponent| opponent.player == player) {
+        let (record, stats) = state.play_ai_turn(record).await?;
+        return Ok(Json(AiMoveResponse {
+            record: record.public(),
+            search: stats.filter(|_| request.include_stats),
+        }));
+    }
     
     // Get the AI's move
-    let (row, col) = ai.get_move(game)
-        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let (position, stats) = state.search_ai_move(&game, player, request.difficulty).await?;
+    
+    // Make the move, checked again, unless the game changed while the AI was thinking
+    let record = state
+        .play_move(id, player, request.token.as_deref(), position, Some(game.version))
+        .await?;
+    
+    Ok(Json(AiMoveResponse {
+        record: record.public(),
+        search: request.include_stats.then_some(stats),
+    }))
+}
+
+/// Resign a game on behalf of a player
+#[instrument(skip_all, fields(game_id = %id))]
+pub async fn resign(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<PlayerActionRequest>,
+) -> Result<Json<GameRecord>, ApiError> {
+    let player = request.player;
+    let record = state
+        .act(id, player, request.token.as_deref(), |game| game.resign(player))
+        .await?;
+    
+    Ok(Json(record.public()))
+}
+
+/// Ask to take back a player's last move, or answer the opponent's request
+///
+/// The player's last move, and any reply to it, is only taken back once the
+/// opponent accepts. An AI opponent always accepts.
+#[instrument(skip_all, fields(game_id = %id))]
+pub async fn undo(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<UndoRequest>,
+) -> Result<Json<GameRecord>, ApiError> {
+    let (player, token) = (request.player, request.token.as_deref());
+    let record = match request.action {
+        UndoAction::Request => state.request_undo(id, player, token).await?,
+        UndoAction::Accept => state.answer_undo(id, player, token, true).await?,
+        UndoAction::Decline => state.answer_undo(id, player, token, false).await?,
+    };
+
+    Ok(Json(record.public()))
+}
+
+/// Send a chat message to the game's other player and spectators
+///
+/// The message is relayed over the game's WebSocket and event streams, and
+/// kept with its history.
+#[instrument(skip_all, fields(game_id = %id))]
+pub async fn post_chat(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<ChatRequest>,
+) -> Result<Json<ChatMessage>, ApiError> {
+    let message = state
+        .post_chat(id, request.player, request.token.as_deref(), &request.text)
+        .await?;
+
+    Ok(Json(message))
+}
+
+/// Offer the opponent a draw
+#[instrument(skip_all, fields(game_id = %id))]
+pub async fn offer_draw(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<PlayerActionRequest>,
+) -> Result<Json<GameRecord>, ApiError> {
+    let player = request.player;
+    let record = state
+        .act(id, player, request.token.as_deref(), |game| game.offer_draw(player))
+        .await?;
     
-    // Make the move
-    game.make_move(row, col)
-        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    Ok(Json(record.public()))
+}
+
+/// Accept the opponent's draw offer
+#[instrument(skip_all, fields(game_id = %id))]
+pub async fn accept_draw(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<PlayerActionRequest>,
+) -> Result<Json<GameRecord>, ApiError> {
+    let player = request.player;
+    let record = state
+        .act(id, player, request.token.as_deref(), |game| game.accept_draw(player))
+        .await?;
     
-    Ok(Json(game.clone()))
+    Ok(Json(record.public()))
+}
+
+/// Decline the opponent's draw offer
+#[instrument(skip_all, fields(game_id = %id))]
+pub async fn decline_draw(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<PlayerActionRequest>,
+) -> Result<Json<GameRecord>, ApiError> {
+    let player = request.player;
+    let record = state
+        .act(id, player, request.token.as_deref(), |game| game.decline_draw(player))
+        .await?;
+    
+    Ok(Json(record.public()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use crate::server::state::GameUpdate;
+    use chrono::Duration;
+
+    /// A game created `minutes` after the first one
+    fn record_at(minutes: i64) -> GameRecord {
+        let mut record = GameRecord::new(GameState::new());
+        record.created_at = DateTime::UNIX_EPOCH + Duration::minutes(minutes);
+        record.updated_at = DateTime::UNIX_EPOCH + Duration::minutes(10 - minutes);
+        record
+    }
+
+    #[test]
+    fn test_page_games() {
+        let mut records: Vec<_> = (0..5).map(record_at).collect();
+        records[1].game.resign(Player::X).unwrap();
+        let ids = |page: &[&GameRecord]| page.iter().map(|record| record.game.id).collect::<Vec<_>>();
+
+        let mut query = ListGamesQuery { limit: Some(2), ..Default::default() };
+        let (page, cursor) = page_games(records.iter(), &query).unwrap();
+        assert_eq!(ids(&page), [records[0].game.id, records[1].game.id]);
+
+        query.cursor = cursor;
+        let (page, cursor) = page_games(records.iter(), &query).unwrap();
+        assert_eq!(ids(&page), [records[2].game.id, records[3].game.id]);
+
+        query.cursor = cursor;
+        let (page, cursor) = page_games(records.iter(), &query).unwrap();
+        assert_eq!(ids(&page), [records[4].game.id]);
+        assert!(cursor.is_none());
+
+        let query = ListGamesQuery {
+            status: Some(StatusFilter::InProgress),
+            sort: SortKey::UpdatedAt,
+            ..Default::default()
+        };
+        let (page, _) = page_games(records.iter(), &query).unwrap();
+        let newest_change_last = [4, 3, 2, 0].map(|i| records[i].game.id);
+        assert_eq!(ids(&page), newest_change_last);
+
+        let query = ListGamesQuery { cursor: Some("not-a-cursor".to_string()), ..Default::default() };
+        assert!(matches!(page_games(records.iter(), &query), Err(GameError::InvalidCursor(_))));
+    }
+
+    #[tokio::test]
+    async fn test_undo_needs_consent() {
+        let state = AppState::new();
+        let id = state.insert_game(GameRecord::new(GameState::new())).await.unwrap().game.id;
+        for (player, position) in [(Player::X, (1, 1)), (Player::O, (0, 0))] {
+            state.play_move(id, player, None, position.into(), None).await.unwrap();
+        }
+
+        // X takes back their move and O's reply once O accepts
+        let record = state.request_undo(id, Player::X, None).await.unwrap();
+        assert_eq!((record.undo_request, record.game.version), (Some(Player::X), 2));
+        let err = state.answer_undo(id, Player::X, None, true).await.unwrap_err();
+        assert!(matches!(err, GameError::NoUndoRequest));
+
+        let record = state.answer_undo(id, Player::O, None, true).await.unwrap();
+        assert_eq!((record.undo_request, record.game.current_turn), (None, Player::X));
+        assert_eq!(record.game.board, GameState::new().board);
+        assert!(state.histories.read().await[&id].moves.is_empty());
+
+        let err = state.request_undo(id, Player::X, None).await.unwrap_err();
+        assert!(matches!(err, GameError::NothingToUndo));
+    }
+
+    #[tokio::test]
+    async fn test_post_chat() {
+        let state = AppState::new();
+        let mut record = GameRecord::new(GameState::new());
+        let tokens = SeatTokens::generate();
+        record.seat_tokens = Some(tokens.clone());
+        let id = state.insert_game(record).await.unwrap().game.id;
+        let mut updates = state.subscribe(id).await;
+
+        let message = state.post_chat(id, Player::O, Some(&tokens.o), " gg ").await.unwrap();
+        assert_eq!(message.text, "gg");
+        assert!(matches!(updates.recv().await, Ok(GameUpdate::Chat(sent)) if sent == message));
+        assert_eq!(state.histories.read().await[&id].chat, [message]);
+
+        let err = state.post_chat(id, Player::O, Some(&tokens.x), "hi").await.unwrap_err();
+        assert!(matches!(err, GameError::InvalidToken(_)));
+        let err = state.post_chat(id, Player::O, Some(&tokens.o), "  ").await.unwrap_err();
+        assert!(matches!(err, GameError::InvalidChatMessage(_)));
+    }
+
+    #[tokio::test]
+    async fn test_import_and_export() {
+        let mut played = Game::new(GameState::new()).unwrap();
+        played.make_move((1, 1)).unwrap();
+        played.make_move((0, 0)).unwrap();
+        let export = || GameExport {
+            game: played.state().clone(),
+            history: played.history().clone(),
+        };
+
+        let state = AppState::new();
+        let Json(imported) = import_game(State(state.clone()), Json(export())).await.unwrap();
+        assert_eq!(imported.record.game.id, played.id);
+        let id = imported.record.game.id;
+        state.play_move(id, Player::X, imported.tokens.x.as_deref(), (2, 2).into(), None).await.unwrap();
+
+        let Json(exported) = export_game(State(state.clone()), Path(id)).await.unwrap();
+        assert_eq!(exported.history.moves.len(), 3);
+        assert_eq!(exported.history.reconstruct_game().unwrap().board, exported.game.board);
+
+        let err = import_game(State(state.clone()), Json(export())).await.unwrap_err();
+        assert_eq!(err.code, "GAME_EXISTS");
+
+        // Only the history counts, whatever the client says about the game
+        let mut claimed = export();
+        claimed.game.id = Uuid::new_v4();
+        claimed.history.game_id = claimed.game.id;
+        claimed.game.status = GameStatus::Won(Player::O);
+        claimed.game.current_turn = Player::O;
+        claimed.game.version = 99;
+        let Json(imported) = import_game(State(state.clone()), Json(claimed)).await.unwrap();
+        assert_eq!(imported.record.game.status, GameStatus::InProgress);
+        assert_eq!(imported.record.game.current_turn, Player::X);
+        assert_eq!(imported.record.game.move_count, 2);
+        let mut resigned = export();
+        resigned.game.id = Uuid::new_v4();
+        resigned.history.game_id = resigned.game.id;
+        resigned.history.finish(GameStatus::Resigned(Player::X));
+        let Json(imported) = import_game(State(state.clone()), Json(resigned)).await.unwrap();
+        assert_eq!(imported.record.game.status, GameStatus::Resigned(Player::X));
+        let mut tampered = export();
+        tampered.game.id = Uuid::new_v4();
+        tampered.history.game_id = tampered.game.id;
+        tampered.history.moves.pop();
+        let err = import_game(State(state.clone()), Json(tampered)).await.unwrap_err();
+        assert_eq!(err.code, "INVALID_IMPORT");
+
+        // The first illegal move in the history is pointed out
+        let mut illegal = export();
+        illegal.game.id = Uuid::new_v4();
+        illegal.history.game_id = illegal.game.id;
+        illegal.history.moves[1].position = (1, 1).into();
+        let err = import_game(State(state), Json(illegal)).await.unwrap_err();
+        assert_eq!(err.code, "ILLEGAL_HISTORY_MOVE");
+        assert_eq!(err.ply, Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_open_seat_token_only_goes_to_the_joiner() {
+        let state = AppState::new();
+        let request = CreateGameRequest { open_seat: Some(Player::O), ..Default::default() };
+        let created = start_game(&state, None, request).await.unwrap();
+        let id = created.record.game.id;
+        assert!(created.tokens.o.is_none());
+        state.play_move(id, Player::X, created.tokens.x.as_deref(), (1, 1).into(), None).await.unwrap();
+
+        // The creator can't move for the seat they left open, even with their own token
+        let err = state.play_move(id, Player::O, created.tokens.x.as_deref(), (0, 0).into(), None).await;
+        assert!(matches!(err, Err(GameError::InvalidToken(_))));
+
+        let seat = state.claim_open_seat(id, None).await.unwrap();
+        assert_eq!(seat.player, Player::O);
+        assert_ne!(Some(&seat.token), created.tokens.x.as_ref());
+        state.play_move(id, Player::O, Some(&seat.token), (0, 0).into(), None).await.unwrap();
+
+        // The AI's seat is left out too
+        let request = CreateGameRequest { opponent: Some(Opponent::Ai), ..Default::default() };
+        let created = start_game(&state, None, request).await.unwrap();
+        assert!(created.tokens.x.is_some() && created.tokens.o.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_only_games_between_logged_in_users_are_rated() {
+        let user = |name: &str| AuthUser { id: Uuid::new_v4(), username: name.to_string() };
+        let (alice, bob) = (user("alice"), user("bob"));
+        let state = AppState::new();
+
+        for joiner in [None, Some(&alice), Some(&bob)] {
+            let request = CreateGameRequest { open_seat: Some(Player::O), ..Default::default() };
+            let created = start_game(&state, Some(&alice), request).await.unwrap();
+            let id = created.record.game.id;
+            let seat = state.claim_open_seat(id, joiner).await.unwrap();
+            for (player, token, cell) in [
+                (Player::X, created.tokens.x.as_deref(), (0, 0)),
+                (Player::O, Some(seat.token.as_str()), (1, 0)),
+                (Player::X, created.tokens.x.as_deref(), (0, 1)),
+                (Player::O, Some(seat.token.as_str()), (1, 1)),
+                (Player::X, created.tokens.x.as_deref(), (0, 2)),
+            ] {
+                state.play_move(id, player, token, cell.into(), None).await.unwrap();
+            }
+        }
+
+        // Neither the anonymous joiner's game nor alice's game against herself counts
+        let leaderboard = state.leaderboard().await;
+        let players: Vec<_> = leaderboard.iter().map(|rating| rating.player_id.as_str()).collect();
+        assert_eq!(players, ["alice", "bob"]);
+        assert!(leaderboard.iter().all(|rating| rating.wins + rating.losses == 1));
+    }
+
+    #[tokio::test]
+    async fn test_ai_move_timeout() {
+        let request = || MakeAIMoveRequest { difficulty: Difficulty::Hard, token: None, include_stats: true };
+        let state = AppState::new();
+        let id = state.insert_game(GameRecord::new(GameState::new())).await.unwrap().game.id;
+        let Json(response) = make_ai_move(State(state.clone()), Path(id), Json(request())).await.unwrap();
+        assert_eq!(response.record.game.version, 1);
+        assert!(response.search.is_some());
+
+        let state = AppState::new().with_ai_timeout(std::time::Duration::ZERO);
+        let game = GameState::with_size(4, 4).unwrap();
+        let id = state.insert_game(GameRecord::new(game)).await.unwrap().game.id;
+        let err = make_ai_move(State(state.clone()), Path(id), Json(request())).await.unwrap_err();
+        assert_eq!(err.code, "AI_TIMEOUT");
+        assert_eq!(state.games.read().await[&id].game.version, 0);
+
+        // A player's move stands when the AI opponent fails to reply to it
+        let mut record = GameRecord::new(GameState::with_size(4, 4).unwrap());
+        record.ai_opponent = Some(AiOpponent { player: Player::O, difficulty: Difficulty::Hard });
+        let id = state.insert_game(record).await.unwrap().game.id;
+        let record = state.play_move(id, Player::X, None, (0, 0).into(), None).await.unwrap();
+        assert_eq!(record.game.version, 1);
+        assert_eq!(record.game.current_turn, Player::O);
+        assert_eq!(state.games.read().await[&id].game.version, 1);
+
+        // Analysis and evaluations give up the same way
+        let err = get_analysis(State(state.clone()), Path(id)).await.unwrap_err();
+        assert_eq!(err.code, "AI_TIMEOUT");
+        let query = Query(EvaluationsQuery { difficulty: Some(Difficulty::Hard) });
+        let err = get_evaluations(State(state.clone()), Path(id), query).await.unwrap_err();
+        assert_eq!(err.code, "AI_TIMEOUT");
+    }
+
+    #[tokio::test]
+    async fn test_hard_evaluations_are_limited_to_small_boards() {
+        let state = AppState::new();
+        let query = || Query(EvaluationsQuery { difficulty: Some(Difficulty::Hard) });
+        let id = state.insert_game(GameRecord::new(GameState::new())).await.unwrap().game.id;
+        let Json(response) = get_evaluations(State(state.clone()), Path(id), query()).await.unwrap();
+        assert_eq!(response.difficulty, Difficulty::Hard);
+        assert_eq!(response.evaluations.len(), 9);
+
+        let game = GameState::with_size(6, 4).unwrap();
+        let id = state.insert_game(GameRecord::new(game)).await.unwrap().game.id;
+        let Json(response) = get_evaluations(State(state.clone()), Path(id), query()).await.unwrap();
+        assert_eq!(response.difficulty, Difficulty::Medium);
+        assert_eq!(response.evaluations.len(), 36);
+    }
+
+    #[test]
+    fn test_difficulty_query() {
+        let query = |uri: &str| Query::<EvaluationsQuery>::try_from_uri(&uri.parse().unwrap()).map(|q| q.0.difficulty);
+
+        assert_eq!(query("/games/1/evaluations?difficulty=hard").unwrap(), Some(Difficulty::Hard));
+        assert_eq!(query("/games/1/evaluations?difficulty=Easy").unwrap(), Some(Difficulty::Easy));
+        assert_eq!(query("/games/1/evaluations").unwrap(), None);
+        assert!(query("/games/1/evaluations?difficulty=impossible").is_err());
+    }
 }
@@ -0,0 +1,40 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use uuid::Uuid;
+
+use crate::error::GameError;
+use crate::game::GameState;
+use crate::render;
+use crate::server::error::ApiError;
+use crate::server::state::AppState;
+
+/// A copy of a game's current state
+async fn game_state(state: &AppState, id: Uuid) -> Result<GameState, ApiError> {
+    let games = state.games.read().await;
+    let record = games.get(&id).ok_or_else(|| GameError::GameNotFound(id.to_string()))?;
+    Ok(record.game.clone())
+}
+
+/// Get a picture of a game's board, e.g. `GET /games/:id/board.svg`
+pub async fn get_board_svg(State(state): State<AppState>, Path(id): Path<Uuid>) -> Result<impl IntoResponse, ApiError> {
+    let game = game_state(&state, id).await?;
+    Ok(([(header::CONTENT_TYPE, "image/svg+xml")], render::svg(&game)))
+}
+
+/// Get a picture of a game's board as a PNG image, e.g. `GET /games/:id/board.png`
+///
+/// Answers 501 Not Implemented unless the server was built with the `png` feature.
+pub async fn get_board_png(State(state): State<AppState>, Path(id): Path<Uuid>) -> Result<impl IntoResponse, ApiError> {
+    let game = game_state(&state, id).await?;
+    if !cfg!(feature = "png") {
+        return Err(ApiError::new(
+            StatusCode::NOT_IMPLEMENTED,
+            "PNG_UNSUPPORTED",
+            "This server was built without PNG images",
+        ));
+    }
+    Ok(([(header::CONTENT_TYPE, "image/png")], render::png(&game)?))
+}
@@ -0,0 +1,122 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::archive::HistoryQuery;
+use crate::error::GameError;
+use crate::rating::PlayerRating;
+use crate::server::error::ApiError;
+use crate::server::state::AppState;
+use crate::stats::{GameStats, REPORTED_OPENINGS};
+
+/// Query parameters for the leaderboard
+#[derive(Debug, Deserialize)]
+pub struct LeaderboardQuery {
+    /// Maximum number of players to return (all players by default)
+    pub limit: Option<usize>,
+}
+
+/// A player's position on the leaderboard
+#[derive(Debug, Serialize)]
+pub struct LeaderboardEntry {
+    /// Position on the leaderboard, starting from 1
+    pub rank: usize,
+    /// The player's rating and results
+    #[serde(flatten)]
+    pub rating: PlayerRating,
+    /// Total number of rated games played
+    pub games_played: u32,
+}
+
+impl LeaderboardEntry {
+    /// Create an entry for the player at `index` in the sorted leaderboard
+    fn new(index: usize, rating: PlayerRating) -> Self {
+        Self {
+            rank: index + 1,
+            games_played: rating.games_played(),
+            rating,
+        }
+    }
+}
+
+/// Response for the leaderboard
+#[derive(Debug, Serialize)]
+pub struct LeaderboardResponse {
+    /// Players ordered from highest to lowest rating
+    pub players: Vec<LeaderboardEntry>,
+}
+
+/// List rated players from highest to lowest rating, e.g. `GET /leaderboard?limit=10`
+pub async fn get_leaderboard(
+    State(state): State<AppState>,
+    Query(query): Query<LeaderboardQuery>,
+) -> Json<LeaderboardResponse> {
+    let players = state
+        .leaderboard()
+        .await
+        .into_iter()
+        .take(query.limit.unwrap_or(usize::MAX))
+        .enumerate()
+        .map(|(index, rating)| LeaderboardEntry::new(index, rating))
+        .collect();
+
+    Json(LeaderboardResponse { players })
+}
+
+/// Get a player's rating, results and leaderboard rank
+pub async fn get_player(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<LeaderboardEntry>, ApiError> {
+    let entry = state
+        .leaderboard()
+        .await
+        .into_iter()
+        .enumerate()
+        .find(|(_, rating)| rating.player_id == id)
+        .map(|(index, rating)| LeaderboardEntry::new(index, rating))
+        .ok_or(GameError::PlayerNotFound(id))?;
+
+    Ok(Json(entry))
+}
+
+/// An opening and how many games began with it
+#[derive(Debug, Serialize)]
+pub struct OpeningEntry {
+    /// The opening moves in algebraic notation
+    pub moves: String,
+    /// Number of games that began with them
+    pub games: usize,
+}
+
+/// Response for the game statistics
+#[derive(Debug, Serialize)]
+pub struct StatsResponse {
+    /// The statistics collected from the finished games
+    #[serde(flatten)]
+    pub stats: GameStats,
+    /// The average number of moves in a game
+    pub average_length: f64,
+    /// The most played openings, most played first
+    pub top_openings: Vec<OpeningEntry>,
+}
+
+/// Get statistics over the finished games, e.g. `GET /stats?player=alice&result=draw`
+///
+/// Games can be narrowed down by `from` and `to` start times, `player` and
+/// `result` (`x`, `o` or `draw`).
+pub async fn get_stats(
+    State(state): State<AppState>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<StatsResponse>, ApiError> {
+    let stats = state.game_stats(&query).await?;
+    let top_openings = stats
+        .top_openings(REPORTED_OPENINGS)
+        .into_iter()
+        .map(|(moves, games)| OpeningEntry { moves: moves.to_string(), games })
+        .collect();
+
+    Ok(Json(StatsResponse { average_length: stats.average_length(), top_openings, stats }))
+}
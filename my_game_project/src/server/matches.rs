@@ -0,0 +1,266 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::ai::{Difficulty, MinimaxAI};
+use crate::error::{GameError, GameResult};
+use crate::player::{GamePlayer, Player};
+use crate::r#match::{Match, MatchScore, MatchStatus};
+use crate::rules::Variant;
+use crate::server::error::ApiError;
+use crate::server::handlers::{new_game, MakeMoveRequest, Opponent, PlayerActionRequest};
+use crate::server::metrics::Metrics;
+use crate::server::state::{AiOpponent, AppState, CreatorTokens, SeatTokens};
+
+/// A best-of-N match being played on the server
+#[derive(Debug, Clone, Serialize)]
+pub struct MatchRecord {
+    /// The match's games and score
+    #[serde(flatten)]
+    pub r#match: Match,
+    /// The AI opponent that replies automatically, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ai_opponent: Option<AiOpponent>,
+    /// The seat tokens, which stay the same for every game of the match
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seat_tokens: Option<SeatTokens>,
+}
+
+impl MatchRecord {
+    /// Returns a copy that is safe to show to any client, with secrets removed
+    pub fn public(&self) -> Self {
+        Self {
+            seat_tokens: None,
+            ..self.clone()
+        }
+    }
+
+    /// Count any finished game and let the AI opponent move, across as many games as it takes
+    fn play_on(&mut self) -> GameResult<()> {
+        loop {
+            self.r#match.advance();
+
+            let Some(opponent) = self.ai_opponent else {
+                return Ok(());
+            };
+            if self.r#match.is_over() || self.r#match.current_game().current_turn != opponent.player {
+                return Ok(());
+            }
+
+            let game = self.r#match.current_game_mut()?;
+            let ai = MinimaxAI::new(opponent.player, opponent.difficulty);
+            let position =
+                Metrics::get().time_ai_move(game.id, opponent.difficulty, || ai.get_move(game))?;
+            game.make_move_as(opponent.player, position)?;
+            Metrics::get().record(&game.take_events());
+        }
+    }
+}
+
+/// Request for creating a match
+#[derive(Debug, Deserialize)]
+pub struct CreateMatchRequest {
+    /// The most games the match can last
+    pub best_of: usize,
+    /// Optional variant of every game (defaults to standard tic-tac-toe)
+    pub variant: Option<Variant>,
+    /// Optional player to start the first game (defaults to X)
+    pub starting_player: Option<Player>,
+    /// Optional board size (defaults to 3, ignored for Connect Four and Gomoku)
+    pub size: Option<usize>,
+    /// Optional number of marks in a row needed to win (defaults to the board size)
+    pub win_length: Option<usize>,
+    /// Optional opponent type (defaults to human)
+    pub opponent: Option<Opponent>,
+    /// Difficulty of the AI opponent (defaults to the server's configured difficulty)
+    pub difficulty: Option<Difficulty>,
+    /// Symbol the AI opponent plays as (defaults to O)
+    pub ai_player: Option<Player>,
+}
+
+/// Response for creating a match
+#[derive(Debug, Serialize)]
+pub struct CreateMatchResponse {
+    /// The new match
+    #[serde(flatten)]
+    pub record: MatchRecord,
+    /// The seat tokens to present when moving for each player, except the AI's
+    pub tokens: CreatorTokens,
+}
+
+/// Summary of a match
+#[derive(Debug, Serialize)]
+pub struct MatchSummary {
+    /// Match ID
+    pub id: Uuid,
+    /// The most games the match can last
+    pub best_of: usize,
+    /// The number of games each player has won
+    pub score: MatchScore,
+    /// How the match stands
+    pub status: MatchStatus,
+    /// Number of games started so far
+    pub games_played: usize,
+}
+
+/// Response for listing matches
+#[derive(Debug, Serialize)]
+pub struct MatchesListResponse {
+    /// Every match
+    pub matches: Vec<MatchSummary>,
+}
+
+/// Create a new match
+pub async fn create_match(
+    State(state): State<AppState>,
+    Json(request): Json<CreateMatchRequest>,
+) -> Result<Json<CreateMatchResponse>, ApiError> {
+    let mut game = new_game(request.variant, request.size, request.win_length)?;
+    if let Some(starting_player) = request.starting_player {
+        game.current_turn = starting_player;
+    }
+
+    let tokens = SeatTokens::generate();
+    let mut record = MatchRecord {
+        r#match: Match::new(game, request.best_of)?,
+        ai_opponent: (request.opponent == Some(Opponent::Ai)).then(|| AiOpponent {
+            player: request.ai_player.unwrap_or(Player::O),
+            difficulty: request.difficulty.unwrap_or(state.ai_difficulty),
+        }),
+        seat_tokens: Some(tokens.clone()),
+    };
+    record.play_on()?;
+
+    state.matches.write().await.insert(record.r#match.id, record.clone());
+
+    let ai_seat = record.ai_opponent.map(|opponent| opponent.player);
+    Ok(Json(CreateMatchResponse {
+        record: record.public(),
+        tokens: tokens.except(&[ai_seat]),
+    }))
+}
+
+/// List every match
+pub async fn list_matches(State(state): State<AppState>) -> Json<MatchesListResponse> {
+    let matches = state.matches.read().await;
+
+    let mut matches: Vec<_> = matches
+        .values()
+        .map(|record| MatchSummary {
+            id: record.r#match.id,
+            best_of: record.r#match.best_of,
+            score: record.r#match.score,
+            status: record.r#match.status,
+            games_played: record.r#match.games.len(),
+        })
+        .collect();
+    matches.sort_by_key(|summary| summary.id);
+
+    Json(MatchesListResponse { matches })
+}
+
+/// Get a match by ID
+pub async fn get_match(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<MatchRecord>, ApiError> {
+    let matches = state.matches.read().await;
+
+    let record = matches.get(&id).ok_or_else(|| GameError::MatchNotFound(id.to_string()))?;
+    Ok(Json(record.public()))
+}
+
+/// Make a move in the match's current game
+///
+/// When the move finishes a game, the score is updated and the next game
+/// started. If the match has an AI opponent, its replies are included.
+pub async fn make_match_move(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<MakeMoveRequest>,
+) -> Result<Json<MatchRecord>, ApiError> {
+    update_match(&state, id, request.player, request.token.as_deref(), |record| {
+        let game = record.r#match.current_game_mut()?;
+
+        // Refuse moves made on a state the caller hasn't seen
+        match request.expected_version {
+            Some(expected) if expected != game.version => {
+                return Err(GameError::VersionConflict { expected, actual: game.version });
+            }
+            _ => {}
+        }
+
+        game.make_move_as(request.player, request.position)?;
+        Metrics::get().record(&game.take_events());
+        Ok(())
+    })
+    .await
+}
+
+/// Resign the match's current game on behalf of a player
+pub async fn resign_match_game(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(request): Json<PlayerActionRequest>,
+) -> Result<Json<MatchRecord>, ApiError> {
+    update_match(&state, id, request.player, request.token.as_deref(), |record| {
+        let game = record.r#match.current_game_mut()?;
+        game.resign(request.player)?;
+        Metrics::get().record(&game.take_events());
+        Ok(())
+    })
+    .await
+}
+
+/// Apply `action` to match `id` for the holder of `player`'s seat, then play on
+async fn update_match(
+    state: &AppState,
+    id: Uuid,
+    player: Player,
+    token: Option<&str>,
+    action: impl FnOnce(&mut MatchRecord) -> GameResult<()>,
+) -> Result<Json<MatchRecord>, ApiError> {
+    let mut matches = state.matches.write().await;
+    let record = matches.get_mut(&id).ok_or_else(|| GameError::MatchNotFound(id.to_string()))?;
+
+    if let Some(tokens) = &record.seat_tokens {
+        tokens.verify(player, token)?;
+    }
+    action(record)?;
+    record.play_on()?;
+
+    Ok(Json(record.public()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+
+    #[test]
+    fn test_ai_plays_on_across_games() {
+        let mut record = MatchRecord {
+            r#match: Match::new(GameState::new(), 3).unwrap(),
+            ai_opponent: Some(AiOpponent { player: Player::X, difficulty: Difficulty::Easy }),
+            seat_tokens: None,
+        };
+
+        // The AI opens the first game
+        record.play_on().unwrap();
+        assert_eq!(record.r#match.current_game().version, 1);
+
+        // O starts the second game, so the AI waits for them
+        record.r#match.current_game_mut().unwrap().resign(Player::O).unwrap();
+        record.play_on().unwrap();
+        assert_eq!(record.r#match.games.len(), 2);
+        assert_eq!(record.r#match.current_game().version, 0);
+
+        record.r#match.current_game_mut().unwrap().resign(Player::O).unwrap();
+        record.play_on().unwrap();
+        assert_eq!(record.r#match.status, MatchStatus::Won(Player::X));
+        assert!(record.public().seat_tokens.is_none());
+    }
+}
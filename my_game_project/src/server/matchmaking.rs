@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::game::GameState;
+use crate::player::Player;
+use crate::server::auth::{AuthUser, MaybeUser};
+use crate::server::error::ApiError;
+use crate::server::state::{AppState, GameRecord, SeatTokens};
+
+/// The seat a matched player was given
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Seat {
+    /// The game the player was placed in
+    pub game_id: Uuid,
+    /// The symbol the player plays as
+    pub player: Player,
+    /// The secret token the player must present to move
+    pub token: String,
+}
+
+/// How long a ticket lasts without being polled before it is dropped
+pub const TICKET_TTL: Duration = Duration::from_secs(30);
+
+/// A player in the matchmaking queue
+#[derive(Debug, Clone, PartialEq)]
+pub struct Waiting {
+    /// The ticket the player polls with
+    pub ticket: Uuid,
+    /// The logged-in user, if the player is one
+    pub user: Option<AuthUser>,
+    /// When the player last joined or polled
+    pub last_seen: Instant,
+}
+
+/// Players waiting for an opponent, and seats not yet collected by their owners
+#[derive(Debug)]
+pub struct Matchmaker {
+    /// The player waiting for an opponent, if any
+    waiting: Option<Waiting>,
+    /// Seats assigned to waiting players who haven't polled since being
+    /// matched, with when they were matched
+    matched: HashMap<Uuid, (Seat, Instant)>,
+    /// How long a ticket lasts without being polled
+    ttl: Duration,
+}
+
+impl Default for Matchmaker {
+    fn default() -> Self {
+        Self::new(TICKET_TTL)
+    }
+}
+
+impl Matchmaker {
+    /// Create an empty queue whose tickets last `ttl` without being polled
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            waiting: None,
+            matched: HashMap::new(),
+            ttl,
+        }
+    }
+
+    /// Add a player to the queue
+    ///
+    /// If another player is already waiting, both are paired into `game` and
+    /// the joining player's seat is returned. Otherwise the joining player
+    /// must wait, and `None` is returned.
+    pub fn join(&mut self, player: Waiting, game: &GameRecord) -> Option<Seat> {
+        self.prune();
+        let waiting = match self.waiting.take() {
+            Some(waiting) => waiting.ticket,
+            None => {
+                self.waiting = Some(player);
+                return None;
+            }
+        };
+
+        let tokens = game.seat_tokens.as_ref()?;
+        let seat = |player| Seat {
+            game_id: game.game.id,
+            player,
+            token: tokens.for_player(player).to_string(),
+        };
+
+        // The player who waited longest moves first
+        self.matched.insert(waiting, (seat(Player::X), Instant::now()));
+        Some(seat(Player::O))
+    }
+
+    /// Returns the player waiting for an opponent, if any
+    pub fn waiting(&mut self) -> Option<&Waiting> {
+        self.prune();
+        self.waiting.as_ref()
+    }
+
+    /// Check on a ticket, collecting its seat if it has been matched
+    ///
+    /// Polling keeps a waiting player's ticket alive.
+    pub fn poll(&mut self, ticket: Uuid) -> Option<MatchmakingResponse> {
+        self.prune();
+        if let Some((seat, _)) = self.matched.remove(&ticket) {
+            return Some(MatchmakingResponse::Matched { ticket, seat });
+        }
+
+        let waiting = self.waiting.as_mut().filter(|waiting| waiting.ticket == ticket)?;
+        waiting.last_seen = Instant::now();
+        Some(MatchmakingResponse::Waiting { ticket })
+    }
+
+    /// Remove a waiting player from the queue
+    pub fn leave(&mut self, ticket: Uuid) -> bool {
+        self.prune();
+        if self.is_waiting(ticket) {
+            self.waiting = None;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Drop the waiting player and uncollected seats whose tickets haven't
+    /// been polled within the TTL
+    fn prune(&mut self) {
+        let ttl = self.ttl;
+        self.waiting = self.waiting.take().filter(|waiting| waiting.last_seen.elapsed() < ttl);
+        self.matched.retain(|_, (_, matched_at)| matched_at.elapsed() < ttl);
+    }
+
+    /// Returns true if `ticket` belongs to the waiting player
+    fn is_waiting(&self, ticket: Uuid) -> bool {
+        matches!(&self.waiting, Some(waiting) if waiting.ticket == ticket)
+    }
+}
+
+/// Response describing a player's place in the matchmaking queue
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum MatchmakingResponse {
+    /// The player is waiting for an opponent and should poll with the ticket
+    Waiting {
+        /// The ticket to poll with
+        ticket: Uuid,
+    },
+    /// The player has been placed in a game
+    Matched {
+        /// The ticket the player joined with
+        ticket: Uuid,
+        /// The seat the player was given
+        #[serde(flatten)]
+        seat: Seat,
+    },
+}
+
+/// Join the matchmaking queue
+///
+/// Pairs the caller with a waiting player if there is one, otherwise returns
+/// a ticket to poll `GET /matchmaking/:ticket` with until an opponent joins.
+/// Tickets not polled for [`TICKET_TTL`] are dropped. Games between two
+/// logged-in users are rated under their usernames.
+pub async fn join(
+    State(state): State<AppState>,
+    MaybeUser(user): MaybeUser,
+) -> Result<Json<MatchmakingResponse>, ApiError> {
+    let player = Waiting {
+        ticket: Uuid::new_v4(),
+        user,
+        last_seen: Instant::now(),
+    };
+    let ticket = player.ticket;
+    let mut matchmaker = state.matchmaker.lock().await;
+
+    let mut record = GameRecord::new(GameState::new());
+    record.seat_tokens = Some(SeatTokens::generate());
+
+    // Only register the game when there is someone to pair with
+    if let Some(waiting) = matchmaker.waiting() {
+        // The player who waited longest plays X
+        if let Some(user) = &waiting.user {
+            record.users.seat(Player::X, user);
+        }
+        if let Some(user) = &player.user {
+            record.users.seat(Player::O, user);
+        }
+        record = state.insert_game(record).await?;
+    }
+
+    let response = match matchmaker.join(player, &record) {
+        Some(seat) => MatchmakingResponse::Matched { ticket, seat },
+        None => MatchmakingResponse::Waiting { ticket },
+    };
+
+    Ok(Json(response))
+}
+
+/// Check whether a waiting player has been matched
+pub async fn poll(
+    State(state): State<AppState>,
+    Path(ticket): Path<Uuid>,
+) -> Result<Json<MatchmakingResponse>, ApiError> {
+    state
+        .matchmaker
+        .lock()
+        .await
+        .poll(ticket)
+        .map(Json)
+        .ok_or_else(ticket_not_found)
+}
+
+/// Leave the matchmaking queue
+pub async fn leave(
+    State(state): State<AppState>,
+    Path(ticket): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    if state.matchmaker.lock().await.leave(ticket) {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(ticket_not_found())
+    }
+}
+
+/// Error for a ticket that isn't waiting or awaiting collection
+fn ticket_not_found() -> ApiError {
+    ApiError::new(StatusCode::NOT_FOUND, "TICKET_NOT_FOUND", "Matchmaking ticket not found")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn waiting() -> Waiting {
+        Waiting {
+            ticket: Uuid::new_v4(),
+            user: None,
+            last_seen: Instant::now(),
+        }
+    }
+
+    fn game() -> GameRecord {
+        let mut record = GameRecord::new(GameState::new());
+        record.seat_tokens = Some(SeatTokens::generate());
+        record
+    }
+
+    #[test]
+    fn test_pairs_players_and_hands_out_seats_once() {
+        let mut matchmaker = Matchmaker::default();
+        let (first, second) = (waiting(), waiting());
+        let game = game();
+
+        assert_eq!(matchmaker.join(first.clone(), &game), None);
+        assert_eq!(matchmaker.poll(first.ticket), Some(MatchmakingResponse::Waiting { ticket: first.ticket }));
+
+        let seat = matchmaker.join(second, &game).unwrap();
+        assert_eq!(seat.player, Player::O);
+        match matchmaker.poll(first.ticket) {
+            Some(MatchmakingResponse::Matched { seat, .. }) => assert_eq!(seat.player, Player::X),
+            other => panic!("expected a match, got {:?}", other),
+        }
+        assert_eq!(matchmaker.poll(first.ticket), None);
+    }
+
+    #[test]
+    fn test_drops_tickets_that_stop_polling() {
+        let mut matchmaker = Matchmaker::new(Duration::ZERO);
+        let first = waiting();
+        let game = game();
+
+        // A player who never polls again isn't paired with the next one
+        assert_eq!(matchmaker.join(first.clone(), &game), None);
+        assert_eq!(matchmaker.poll(first.ticket), None);
+        assert_eq!(matchmaker.join(waiting(), &game), None);
+        assert!(!matchmaker.leave(first.ticket));
+
+        // Nor is a seat kept for a matched player who never collects it
+        let mut matchmaker = Matchmaker::default();
+        matchmaker.join(first.clone(), &game);
+        matchmaker.join(waiting(), &game).unwrap();
+        matchmaker.ttl = Duration::ZERO;
+        assert_eq!(matchmaker.poll(first.ticket), None);
+        assert!(matchmaker.matched.is_empty());
+    }
+}
@@ -0,0 +1,325 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{MatchedPath, State},
+    http::{header, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::ai::Difficulty;
+use crate::events::GameEvent;
+use crate::server::state::AppState;
+
+/// Upper bounds, in seconds, of the buckets every duration histogram uses
+const BUCKETS: [f64; 12] = [
+    0.0001, 0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0,
+];
+
+/// Number of recent AI searches kept for finding the slowest
+pub const RECENT_AI_SEARCHES: usize = 100;
+
+/// Content type of the Prometheus text exposition format
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// A histogram of durations with fixed buckets
+#[derive(Debug, Default)]
+struct Histogram {
+    /// Number of observations at or below each bucket's bound
+    buckets: [AtomicU64; BUCKETS.len()],
+    /// Total number of observations
+    count: AtomicU64,
+    /// Sum of every observation, in microseconds
+    sum_micros: AtomicU64,
+}
+
+impl Histogram {
+    /// Record one observation
+    fn observe(&self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        for (bound, bucket) in BUCKETS.iter().zip(&self.buckets) {
+            if seconds <= *bound {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_micros.fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// Write the histogram's samples, each labelled with `labels`
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let separator = if labels.is_empty() { "" } else { "," };
+        for (bound, bucket) in BUCKETS.iter().zip(&self.buckets) {
+            let count = bucket.load(Ordering::Relaxed);
+            let _ = writeln!(out, "{name}_bucket{{{labels}{separator}le=\"{bound}\"}} {count}");
+        }
+
+        let count = self.count.load(Ordering::Relaxed);
+        let sum = self.sum_micros.load(Ordering::Relaxed) as f64 / 1e6;
+        let _ = writeln!(out, "{name}_bucket{{{labels}{separator}le=\"+Inf\"}} {count}");
+        let labels = if labels.is_empty() { String::new() } else { format!("{{{labels}}}") };
+        let _ = writeln!(out, "{name}_sum{labels} {sum}");
+        let _ = writeln!(out, "{name}_count{labels} {count}");
+    }
+}
+
+/// One move chosen by the AI
+#[derive(Debug, Clone, Serialize)]
+pub struct AiSearch {
+    /// The game the move was chosen for
+    pub game_id: Uuid,
+    /// The difficulty the AI played at
+    pub difficulty: Difficulty,
+    /// Time the search took, in milliseconds
+    pub duration_ms: f64,
+    /// When the search finished
+    pub finished_at: DateTime<Utc>,
+}
+
+/// The number of moves the AI has chosen at each difficulty
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct AiUsage {
+    /// Moves chosen on Easy
+    pub easy: u64,
+    /// Moves chosen on Medium
+    pub medium: u64,
+    /// Moves chosen on Hard
+    pub hard: u64,
+}
+
+/// Counters and histograms describing what the server has done
+///
+/// There's one set of metrics per process, shared by every router, the way a
+/// Prometheus scraper expects.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    /// Number of games created
+    games_created: AtomicU64,
+    /// Number of moves made, by players and AI opponents alike
+    moves_made: AtomicU64,
+    /// Number of games that have ended, however they ended
+    games_finished: AtomicU64,
+    /// Time the AI took to choose each move
+    ai_move_duration: Histogram,
+    /// Number of moves the AI chose, by difficulty
+    ai_moves: [AtomicU64; 3],
+    /// The most recent AI searches, oldest first
+    ai_searches: Mutex<VecDeque<AiSearch>>,
+    /// Time taken to handle each request, by method and route
+    request_duration: Mutex<BTreeMap<(String, String), Histogram>>,
+}
+
+impl Metrics {
+    /// The process-wide metrics
+    pub fn get() -> &'static Self {
+        static METRICS: OnceLock<Metrics> = OnceLock::new();
+        METRICS.get_or_init(Metrics::default)
+    }
+
+    /// Count a newly created game
+    pub fn game_created(&self) {
+        self.games_created.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count the moves made and games finished among `events`
+    pub fn record(&self, events: &[GameEvent]) {
+        for event in events {
+            let counter = match event {
+                GameEvent::MoveMade { .. } => &self.moves_made,
+                GameEvent::GameFinished { .. } => &self.games_finished,
+                GameEvent::TurnChanged { .. } => continue,
+            };
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Run `choose`, recording how long the AI took to choose its move in `game_id`
+    pub fn time_ai_move<T>(
+        &self,
+        game_id: Uuid,
+        difficulty: Difficulty,
+        choose: impl FnOnce() -> T,
+    ) -> T {
+        let start = Instant::now();
+        let result = choose();
+        let duration = start.elapsed();
+
+        self.ai_move_duration.observe(duration);
+        self.ai_moves[difficulty as usize].fetch_add(1, Ordering::Relaxed);
+        let mut searches = self.ai_searches.lock().unwrap_or_else(|e| e.into_inner());
+        if searches.len() == RECENT_AI_SEARCHES {
+            searches.pop_front();
+        }
+        searches.push_back(AiSearch {
+            game_id,
+            difficulty,
+            duration_ms: duration.as_secs_f64() * 1000.0,
+            finished_at: Utc::now(),
+        });
+        result
+    }
+
+    /// The number of moves the AI has chosen at each difficulty
+    pub fn ai_usage(&self) -> AiUsage {
+        let count =
+            |difficulty: Difficulty| self.ai_moves[difficulty as usize].load(Ordering::Relaxed);
+        AiUsage {
+            easy: count(Difficulty::Easy),
+            medium: count(Difficulty::Medium),
+            hard: count(Difficulty::Hard),
+        }
+    }
+
+    /// The `limit` slowest of the last [`RECENT_AI_SEARCHES`] AI searches, slowest first
+    pub fn slowest_ai_searches(&self, limit: usize) -> Vec<AiSearch> {
+        let mut searches: Vec<_> = self
+            .ai_searches
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .cloned()
+            .collect();
+        searches.sort_by(|a, b| b.duration_ms.total_cmp(&a.duration_ms));
+        searches.truncate(limit);
+        searches
+    }
+
+    /// Record how long a request to `route` took
+    pub fn request_handled(&self, method: &str, route: &str, duration: Duration) {
+        let mut requests = self.request_duration.lock().unwrap_or_else(|e| e.into_inner());
+        requests
+            .entry((method.to_string(), route.to_string()))
+            .or_default()
+            .observe(duration);
+    }
+
+    /// Render every metric in the Prometheus text format
+    ///
+    /// `active_games` is the number of games still in progress.
+    pub fn render(&self, active_games: usize) -> String {
+        let mut out = String::new();
+
+        let counters = [
+            ("tictactoe_games_created_total", "Number of games created", &self.games_created),
+            ("tictactoe_moves_made_total", "Number of moves made", &self.moves_made),
+            ("tictactoe_games_finished_total", "Number of games finished", &self.games_finished),
+        ];
+        for (name, help, counter) in counters {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} counter");
+            let _ = writeln!(out, "{name} {}", counter.load(Ordering::Relaxed));
+        }
+
+        let _ = writeln!(out, "# HELP tictactoe_active_games Number of games in progress");
+        let _ = writeln!(out, "# TYPE tictactoe_active_games gauge");
+        let _ = writeln!(out, "tictactoe_active_games {active_games}");
+
+        let name = "tictactoe_ai_move_duration_seconds";
+        let _ = writeln!(out, "# HELP {name} Time the AI took to choose a move");
+        let _ = writeln!(out, "# TYPE {name} histogram");
+        self.ai_move_duration.render(&mut out, name, "");
+
+        let name = "tictactoe_http_request_duration_seconds";
+        let _ = writeln!(out, "# HELP {name} Time taken to handle a request");
+        let _ = writeln!(out, "# TYPE {name} histogram");
+        let requests = self.request_duration.lock().unwrap_or_else(|e| e.into_inner());
+        for ((method, route), histogram) in requests.iter() {
+            let labels = format!("method=\"{method}\",route=\"{route}\"");
+            histogram.render(&mut out, name, &labels);
+        }
+
+        out
+    }
+}
+
+/// Expose the server's metrics for Prometheus to scrape
+pub async fn get_metrics(State(state): State<AppState>) -> impl IntoResponse {
+    let active_games = state
+        .games
+        .read()
+        .await
+        .values()
+        .filter(|record| !record.game.is_over())
+        .count();
+
+    ([(header::CONTENT_TYPE, CONTENT_TYPE)], Metrics::get().render(active_games))
+}
+
+/// Middleware recording how long each request took, labelled by its route
+///
+/// The route is the path pattern it matched, like `/games/:id`, so requests
+/// for different games share a histogram.
+pub async fn track_requests<B>(request: Request<B>, next: Next<B>) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("unmatched", MatchedPath::as_str)
+        .to_string();
+
+    let start = Instant::now();
+    let response = next.run(request).await;
+    Metrics::get().request_handled(&method, &route, start.elapsed());
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+    use crate::player::Player;
+
+    #[test]
+    fn test_histogram_buckets_are_cumulative() {
+        let histogram = Histogram::default();
+        histogram.observe(Duration::from_micros(300));
+        histogram.observe(Duration::from_millis(20));
+        histogram.observe(Duration::from_secs(2));
+
+        let mut out = String::new();
+        histogram.render(&mut out, "latency", "route=\"/games\"");
+
+        assert!(out.contains("latency_bucket{route=\"/games\",le=\"0.0001\"} 0\n"));
+        assert!(out.contains("latency_bucket{route=\"/games\",le=\"0.0005\"} 1\n"));
+        assert!(out.contains("latency_bucket{route=\"/games\",le=\"0.025\"} 2\n"));
+        assert!(out.contains("latency_bucket{route=\"/games\",le=\"1\"} 2\n"));
+        assert!(out.contains("latency_bucket{route=\"/games\",le=\"+Inf\"} 3\n"));
+        assert!(out.contains("latency_sum{route=\"/games\"} 2.0203\n"));
+        assert!(out.contains("latency_count{route=\"/games\"} 3\n"));
+    }
+
+    #[test]
+    fn test_render() {
+        let metrics = Metrics::default();
+        let mut game = GameState::new();
+        game.make_move((1, 1)).unwrap();
+        game.make_move((0, 0)).unwrap();
+        game.resign(Player::X).unwrap();
+
+        metrics.game_created();
+        metrics.record(&game.take_events());
+        metrics.time_ai_move(Uuid::new_v4(), Difficulty::Hard, || ());
+        metrics.request_handled("POST", "/games/:id/move", Duration::from_millis(3));
+
+        let out = metrics.render(4);
+        assert!(out.contains("# TYPE tictactoe_games_created_total counter\n"));
+        assert!(out.contains("tictactoe_games_created_total 1\n"));
+        assert!(out.contains("tictactoe_moves_made_total 2\n"));
+        assert!(out.contains("tictactoe_games_finished_total 1\n"));
+        assert!(out.contains("tictactoe_active_games 4\n"));
+        assert!(out.contains("tictactoe_ai_move_duration_seconds_count 1\n"));
+        assert_eq!(metrics.ai_usage(), AiUsage { easy: 0, medium: 0, hard: 1 });
+        assert_eq!(metrics.slowest_ai_searches(5).len(), 1);
+        assert!(out.contains(
+            "tictactoe_http_request_duration_seconds_count{method=\"POST\",route=\"/games/:id/move\"} 1\n"
+        ));
+    }
+}
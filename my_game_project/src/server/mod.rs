@@ -1,10 +1,68 @@
 //! HTTP server for the tic-tac-toe game
 //!
-//! This module provides a REST API for playing tic-tac-toe over HTTP.
+//! This module provides a REST API for playing tic-tac-toe over HTTP, and a
+//! gRPC API when built with the `grpc` feature. With the `redis` feature,
+//! several servers can share their games through Redis.
 
+mod admin;
+mod auth;
+mod config;
+mod error;
+mod event_store;
+mod events;
+mod expiry;
+mod graphql;
+#[cfg(feature = "grpc")]
+mod grpc;
+#[cfg(feature = "redis")]
+mod redis;
 mod routes;
 mod state;
+mod store;
+mod sync;
+mod timers;
+mod trace;
 mod handlers;
+mod images;
+mod matches;
+mod matchmaking;
+mod leaderboard;
+mod spectate;
+mod ui;
+mod webhooks;
+mod metrics;
+mod negotiate;
+mod puzzles;
+mod ws;
 
-pub use routes::create_router;
-pub use state::AppState;
+pub use admin::{AdminStats, AdminUser, FinishGameRequest, MemoryUsage, SLOWEST_AI_SEARCHES};
+pub use auth::{AuthUser, MaybeUser, TokenIssuer, User, UserProfile, DEFAULT_TOKEN_TTL};
+pub use config::{AiConfig, AuthConfig, ServerConfig, StorageBackend, StorageConfig, TimeoutConfig};
+pub use error::ApiError;
+pub use event_store::{EventStore, GameEvent, RecordedEvent, SNAPSHOT_INTERVAL};
+pub use expiry::{spawn_game_collector, ExpiryAction, ExpiryPolicy, DEFAULT_SWEEP_INTERVAL};
+pub use graphql::{schema, GameSchema};
+#[cfg(feature = "grpc")]
+pub use grpc::{proto, serve_grpc, GrpcGames, ERROR_CODE_KEY};
+pub use handlers::Opponent;
+pub use matches::MatchRecord;
+pub use matchmaking::Seat;
+pub use metrics::{AiSearch, AiUsage, Metrics, RECENT_AI_SEARCHES};
+pub use negotiate::{Format, MSGPACK};
+pub use puzzles::DailyPuzzleResponse;
+#[cfg(feature = "redis")]
+pub use redis::RedisStore;
+pub use routes::{create_router, create_router_with_cors};
+pub use spectate::{SpectatorGuard, Spectators};
+pub use state::{
+    AiOpponent, AppState, CreatorTokens, GameRecord, GameUpdate, SeatTokens, SeatUser, SeatUsers,
+    DEFAULT_AI_TIMEOUT, MAX_CHAT_LENGTH,
+};
+pub use store::{GameStore, MemoryStore, SqliteStore, StoreChange};
+pub use sync::spawn_store_sync;
+pub use timers::{spawn_turn_timer, DEFAULT_CHECK_INTERVAL};
+pub use trace::REQUEST_ID_HEADER;
+pub use ui::web_ui;
+pub use webhooks::{
+    Webhook, WebhookEvent, WebhookEventKind, EVENT_HEADER, MAX_DELIVERY_ATTEMPTS, SIGNATURE_HEADER,
+};
@@ -0,0 +1,167 @@
+use axum::{
+    body::{boxed, Body},
+    http::{header, HeaderMap, HeaderValue, Request},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+
+use crate::error::GameError;
+use crate::server::error::ApiError;
+
+/// Media type of MessagePack bodies
+pub const MSGPACK: &str = "application/msgpack";
+
+/// A format the API can respond in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// JSON, the default
+    Json,
+    /// MessagePack, for binary clients
+    MessagePack,
+    /// Plain text for people, where the endpoint has a text form
+    PlainText,
+}
+
+impl Format {
+    /// The format the client prefers, going by its `Accept` header
+    ///
+    /// Formats are weighed by their `q` values, with ties going to the one
+    /// listed first. JSON is used when the header names none of them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use axum::http::{header, HeaderMap};
+    /// use my_game_project::server::Format;
+    ///
+    /// let mut headers = HeaderMap::new();
+    /// headers.insert(header::ACCEPT, "application/json;q=0.5, text/plain".parse().unwrap());
+    /// assert_eq!(Format::preferred(&headers), Format::PlainText);
+    /// ```
+    pub fn preferred(headers: &HeaderMap) -> Self {
+        let Some(accept) = headers.get(header::ACCEPT).and_then(|value| value.to_str().ok()) else {
+            return Format::Json;
+        };
+
+        let mut best = (Format::Json, 0.0);
+        for range in accept.split(',') {
+            let mut params = range.split(';');
+            let format = match media_type(params.next().unwrap_or_default()).as_str() {
+                "application/json" | "application/*" | "*/*" => Format::Json,
+                MSGPACK | "application/x-msgpack" | "application/vnd.msgpack" => Format::MessagePack,
+                "text/plain" | "text/*" => Format::PlainText,
+                _ => continue,
+            };
+            let q = params
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .and_then(|q| q.parse().ok())
+                .unwrap_or(1.0);
+
+            if q > best.1 {
+                best = (format, q);
+            }
+        }
+        best.0
+    }
+}
+
+/// A media type with its parameters removed, in lowercase
+fn media_type(value: &str) -> String {
+    value.split(';').next().unwrap_or_default().trim().to_ascii_lowercase()
+}
+
+/// Returns true if a `Content-Type` header names `expected`
+fn has_type(value: Option<&HeaderValue>, expected: &[&str]) -> bool {
+    value
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| expected.contains(&media_type(value).as_str()))
+}
+
+/// Let clients speak MessagePack to the JSON API
+///
+/// MessagePack request bodies are turned into JSON before they reach the
+/// handlers, and JSON responses are sent as MessagePack to clients that
+/// prefer it.
+pub async fn negotiate_content(mut request: Request<Body>, next: Next<Body>) -> Response {
+    let msgpack_types = [MSGPACK, "application/x-msgpack", "application/vnd.msgpack"];
+    if has_type(request.headers().get(header::CONTENT_TYPE), &msgpack_types) {
+        let body = std::mem::take(request.body_mut());
+        let json = match msgpack_to_json(body).await {
+            Ok(json) => json,
+            Err(e) => return ApiError::from(e).into_response(),
+        };
+
+        let headers = request.headers_mut();
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        headers.remove(header::CONTENT_LENGTH);
+        *request.body_mut() = Body::from(json);
+    }
+
+    let format = Format::preferred(request.headers());
+    let response = next.run(request).await;
+    if format != Format::MessagePack
+        || !has_type(response.headers().get(header::CONTENT_TYPE), &["application/json"])
+    {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let packed = match hyper::body::to_bytes(body).await {
+        Ok(json) => json_to_msgpack(&json),
+        Err(e) => Err(GameError::SerializationError(e.to_string())),
+    };
+    match packed {
+        Ok(packed) => {
+            parts.headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(MSGPACK));
+            parts.headers.remove(header::CONTENT_LENGTH);
+            Response::from_parts(parts, boxed(Body::from(packed)))
+        }
+        Err(e) => ApiError::from(e).into_response(),
+    }
+}
+
+/// Read a MessagePack body and encode it as JSON
+async fn msgpack_to_json(body: Body) -> Result<Vec<u8>, GameError> {
+    let invalid = |e: String| GameError::DeserializationError(format!("invalid MessagePack: {}", e));
+    let bytes = hyper::body::to_bytes(body).await.map_err(|e| invalid(e.to_string()))?;
+    let value: serde_json::Value = rmp_serde::from_slice(&bytes).map_err(|e| invalid(e.to_string()))?;
+    serde_json::to_vec(&value).map_err(|e| invalid(e.to_string()))
+}
+
+/// Encode a JSON body as MessagePack
+fn json_to_msgpack(json: &[u8]) -> Result<Vec<u8>, GameError> {
+    let value: serde_json::Value = serde_json::from_slice(json)
+        .map_err(|e| GameError::SerializationError(e.to_string()))?;
+    rmp_serde::to_vec(&value).map_err(|e| GameError::SerializationError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preferred_format() {
+        let preferred = |accept: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::ACCEPT, accept.parse().unwrap());
+            Format::preferred(&headers)
+        };
+
+        assert_eq!(Format::preferred(&HeaderMap::new()), Format::Json);
+        assert_eq!(preferred("*/*"), Format::Json);
+        assert_eq!(preferred("Application/MsgPack"), Format::MessagePack);
+        assert_eq!(preferred("text/plain;q=0.4, application/x-msgpack;q=0.6"), Format::MessagePack);
+        assert_eq!(preferred("text/html, */*;q=0.8"), Format::Json);
+        assert_eq!(preferred("image/png"), Format::Json);
+    }
+
+    #[tokio::test]
+    async fn test_round_trip() {
+        let packed = json_to_msgpack(br#"{"row":1,"col":2,"player":"X"}"#).unwrap();
+        let json = msgpack_to_json(Body::from(packed)).await.unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(value, serde_json::json!({"row": 1, "col": 2, "player": "X"}));
+
+        assert!(msgpack_to_json(Body::from(vec![0xc1])).await.is_err());
+    }
+}
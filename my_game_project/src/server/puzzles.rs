@@ -0,0 +1,116 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::{NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::GameError;
+use crate::position::Position;
+use crate::puzzle::{Attempt, Puzzle};
+use crate::server::error::ApiError;
+use crate::server::state::AppState;
+
+/// Most days whose puzzles are kept once found, since any date can be asked for
+const CACHED_PUZZLES: usize = 64;
+
+/// Query parameters for the daily puzzle
+#[derive(Debug, Deserialize)]
+pub struct DailyPuzzleQuery {
+    /// The day whose puzzle to get (today, in UTC, by default)
+    pub date: Option<NaiveDate>,
+    /// The solver's moves to check, separated by commas, e.g. `a3,b3`
+    pub moves: Option<String>,
+}
+
+/// Response for the daily puzzle
+#[derive(Debug, Serialize)]
+pub struct DailyPuzzleResponse {
+    /// The day the puzzle is for
+    pub date: NaiveDate,
+    /// The puzzle
+    #[serde(flatten)]
+    pub puzzle: Puzzle,
+    /// How the submitted moves went, if any were submitted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attempt: Option<Attempt>,
+}
+
+/// Get the puzzle of the day, checking a solution if one is given, e.g. `GET /puzzles/daily?moves=a3,b3`
+///
+/// Moves are the solver's only, in algebraic notation. Each is checked
+/// against every defence, and the strongest defence to each correct move
+/// comes back in the attempt's `replies`, so a solution can be submitted a
+/// move at a time.
+pub async fn get_daily_puzzle(
+    State(state): State<AppState>,
+    Query(query): Query<DailyPuzzleQuery>,
+) -> Result<Json<DailyPuzzleResponse>, ApiError> {
+    let date = query.date.unwrap_or_else(|| Utc::now().date_naive());
+    let puzzle = daily_puzzle(&state, date).await?;
+
+    let attempt = match query.moves {
+        Some(moves) => {
+            let moves = moves
+                .split(',')
+                .map(|cell| {
+                    // Only algebraic notation, since commas separate the moves
+                    let cell = cell.trim();
+                    match cell.contains(char::is_whitespace) {
+                        true => Err(GameError::InvalidCoordinates(cell.to_string())),
+                        false => cell.parse::<Position>(),
+                    }
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            Some(puzzle.attempt(&moves)?)
+        }
+        None => None,
+    };
+
+    Ok(Json(DailyPuzzleResponse { date, puzzle, attempt }))
+}
+
+/// The puzzle for `date`, found the first time it's asked for and kept after that
+async fn daily_puzzle(state: &AppState, date: NaiveDate) -> Result<Puzzle, ApiError> {
+    if let Some(puzzle) = state.puzzles.read().await.get(&date) {
+        return Ok(puzzle.clone());
+    }
+
+    // Finding a puzzle searches many random games, so keep it off the async worker threads
+    let puzzle = tokio::task::spawn_blocking(move || Puzzle::daily(date))
+        .await
+        .map_err(|e| ApiError::new(StatusCode::INTERNAL_SERVER_ERROR, "PUZZLE_FAILED", e.to_string()))??;
+
+    // Make room by forgetting the earliest day
+    let mut puzzles = state.puzzles.write().await;
+    if puzzles.len() >= CACHED_PUZZLES {
+        if let Some(earliest) = puzzles.keys().min().copied() {
+            puzzles.remove(&earliest);
+        }
+    }
+    puzzles.insert(date, puzzle.clone());
+    Ok(puzzle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_daily_puzzle_is_kept() {
+        let state = AppState::new();
+        let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let puzzle = daily_puzzle(&state, date).await.unwrap();
+        assert_eq!(puzzle, Puzzle::daily(date).unwrap());
+        assert_eq!(state.puzzles.read().await.get(&date), Some(&puzzle));
+
+        // Only the latest days are kept
+        for day in 1..=CACHED_PUZZLES as i64 {
+            daily_puzzle(&state, date + chrono::Duration::days(day)).await.unwrap();
+        }
+        let puzzles = state.puzzles.read().await;
+        assert_eq!(puzzles.len(), CACHED_PUZZLES);
+        assert!(!puzzles.contains_key(&date));
+    }
+}
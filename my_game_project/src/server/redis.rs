@@ -0,0 +1,372 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use redis::aio::{ConnectionManager, PubSub};
+use redis::{AsyncCommands, Client, Script};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tracing::{instrument, warn};
+use uuid::Uuid;
+
+use crate::error::{GameError, GameResult};
+use crate::history::GameHistory;
+use crate::rating::PlayerRating;
+use crate::server::auth::User;
+use crate::server::state::GameRecord;
+use crate::server::store::{GameStore, StoreChange};
+
+/// Prefix of every key the store uses, so it can share a Redis database
+const PREFIX: &str = "tictactoe";
+
+/// Number of changes from other servers that may wait to be applied
+const CHANGE_CHANNEL_CAPACITY: usize = 256;
+
+/// How long to wait before subscribing again after losing the subscription
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(1);
+
+/// Save a game unless the stored copy is as new, and announce it
+///
+/// KEYS: the game, the set of live game IDs and the changes channel.
+/// ARGV: the revision, the record, the game ID and the announcement.
+const SAVE_GAME: &str = r"
+local stored = redis.call('HGET', KEYS[1], 'revision')
+if stored and tonumber(stored) >= tonumber(ARGV[1]) then
+    return 0
+end
+redis.call('HSET', KEYS[1], 'record', ARGV[2], 'revision', ARGV[1])
+redis.call('SADD', KEYS[2], ARGV[3])
+redis.call('PUBLISH', KEYS[3], ARGV[4])
+return 1
+";
+
+/// Move a game and its history into the archive, and announce its removal
+///
+/// KEYS: the game, its history, its archive entry, the set of live game IDs
+/// and the changes channel. ARGV: the game ID and the announcement.
+const ARCHIVE_GAME: &str = r"
+local record = redis.call('HGET', KEYS[1], 'record')
+if not record then
+    return 0
+end
+redis.call('HSET', KEYS[3], 'record', record)
+local history = redis.call('GET', KEYS[2])
+if history then
+    redis.call('HSET', KEYS[3], 'history', history)
+end
+redis.call('DEL', KEYS[1], KEYS[2])
+redis.call('SREM', KEYS[4], ARGV[1])
+redis.call('PUBLISH', KEYS[5], ARGV[2])
+return 1
+";
+
+/// A change published for the other servers sharing the store
+#[derive(Debug, Serialize, Deserialize)]
+struct Announcement {
+    /// The server that made the change
+    source: Uuid,
+    /// What changed
+    change: StoreChange,
+}
+
+/// A store backed by Redis, which several servers can share
+///
+/// Games, histories, ratings and users are stored as JSON documents. Every
+/// change is published on a channel, so each server can keep its games in
+/// memory up to date with the changes the others make.
+#[derive(Clone)]
+pub struct RedisStore {
+    /// The client, for opening subscriptions
+    client: Client,
+    /// The connection commands are sent on
+    connection: ConnectionManager,
+    /// The ID this server's announcements are sent with
+    source: Uuid,
+}
+
+impl std::fmt::Debug for RedisStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisStore").field("source", &self.source).finish_non_exhaustive()
+    }
+}
+
+impl RedisStore {
+    /// Connect to the Redis server at `url`
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # async fn example() -> my_game_project::error::GameResult<()> {
+    /// use my_game_project::server::RedisStore;
+    ///
+    /// let store = RedisStore::connect("redis://127.0.0.1/").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn connect(url: &str) -> GameResult<Self> {
+        let client = Client::open(url).map_err(storage_error)?;
+        let connection = client.get_connection_manager().await.map_err(storage_error)?;
+        Ok(Self {
+            client,
+            connection,
+            source: Uuid::new_v4(),
+        })
+    }
+
+    /// The JSON announcing `change` to the other servers
+    fn announcement(&self, change: StoreChange) -> GameResult<String> {
+        to_json(&Announcement { source: self.source, change })
+    }
+
+    /// Save `value` as JSON under `key` and announce `change`, in one transaction
+    async fn set_and_announce(&self, key: String, value: &str, change: StoreChange) -> GameResult<()> {
+        let announcement = self.announcement(change)?;
+        redis::pipe()
+            .atomic()
+            .set(key, value)
+            .ignore()
+            .publish(channel(), announcement)
+            .ignore()
+            .query_async(&mut self.connection.clone())
+            .await
+            .map_err(storage_error)
+    }
+}
+
+/// The key of the game with ID `id`
+fn game_key(id: Uuid) -> String {
+    format!("{}:game:{}", PREFIX, id)
+}
+
+/// The key of the history of the game with ID `id`
+fn history_key(id: Uuid) -> String {
+    format!("{}:history:{}", PREFIX, id)
+}
+
+/// The key of the archived game with ID `id`
+fn archived_key(id: Uuid) -> String {
+    format!("{}:archived:{}", PREFIX, id)
+}
+
+/// The key of the set of live game IDs
+fn games_key() -> String {
+    format!("{}:games", PREFIX)
+}
+
+/// The key of the hash of player ID to rating
+fn ratings_key() -> String {
+    format!("{}:ratings", PREFIX)
+}
+
+/// The key of the hash of username to user
+fn users_key() -> String {
+    format!("{}:users", PREFIX)
+}
+
+/// The channel changes are announced on
+fn channel() -> String {
+    format!("{}:changes", PREFIX)
+}
+
+#[async_trait]
+impl GameStore for RedisStore {
+    #[instrument(level = "debug", skip_all, fields(game_id = %record.game.id))]
+    async fn save_game(&self, record: &GameRecord) -> GameResult<()> {
+        let id = record.game.id;
+        let json = to_json(record)?;
+        let announcement = self.announcement(StoreChange::Game(Box::new(record.clone())))?;
+
+        let saved: bool = Script::new(SAVE_GAME)
+            .key(game_key(id))
+            .key(games_key())
+            .key(channel())
+            .arg(record.revision)
+            .arg(json)
+            .arg(id.to_string())
+            .arg(announcement)
+            .invoke_async(&mut self.connection.clone())
+            .await
+            .map_err(storage_error)?;
+
+        if !saved {
+            return Err(GameError::ConcurrentUpdate(id.to_string()));
+        }
+        Ok(())
+    }
+
+    async fn load_game(&self, id: Uuid) -> GameResult<Option<GameRecord>> {
+        let json: Option<String> =
+            self.connection.clone().hget(game_key(id), "record").await.map_err(storage_error)?;
+        json.map(|json| from_json(&json)).transpose()
+    }
+
+    async fn load_all_games(&self) -> GameResult<Vec<GameRecord>> {
+        let ids: Vec<String> = self.connection.clone().smembers(games_key()).await.map_err(storage_error)?;
+
+        let mut records = Vec::with_capacity(ids.len());
+        for id in ids {
+            let id = id.parse().map_err(|_| GameError::StorageError(format!("bad game ID '{}'", id)))?;
+            // A game archived since the IDs were read is skipped
+            if let Some(record) = self.load_game(id).await? {
+                records.push(record);
+            }
+        }
+        Ok(records)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(game_id = %id))]
+    async fn delete_game(&self, id: Uuid) -> GameResult<()> {
+        let announcement = self.announcement(StoreChange::Removed(id))?;
+        redis::pipe()
+            .atomic()
+            .del(&[game_key(id), history_key(id)])
+            .ignore()
+            .srem(games_key(), id.to_string())
+            .ignore()
+            .publish(channel(), announcement)
+            .ignore()
+            .query_async(&mut self.connection.clone())
+            .await
+            .map_err(storage_error)
+    }
+
+    async fn archive_game(&self, id: Uuid) -> GameResult<()> {
+        let announcement = self.announcement(StoreChange::Removed(id))?;
+        let _: bool = Script::new(ARCHIVE_GAME)
+            .key(game_key(id))
+            .key(history_key(id))
+            .key(archived_key(id))
+            .key(games_key())
+            .key(channel())
+            .arg(id.to_string())
+            .arg(announcement)
+            .invoke_async(&mut self.connection.clone())
+            .await
+            .map_err(storage_error)?;
+        Ok(())
+    }
+
+    async fn load_archived_game(&self, id: Uuid) -> GameResult<Option<GameRecord>> {
+        let json: Option<String> =
+            self.connection.clone().hget(archived_key(id), "record").await.map_err(storage_error)?;
+        json.map(|json| from_json(&json)).transpose()
+    }
+
+    #[instrument(level = "debug", skip_all, fields(game_id = %history.game_id))]
+    async fn save_history(&self, history: &GameHistory) -> GameResult<()> {
+        let json = to_json(history)?;
+        let change = StoreChange::History(Box::new(history.clone()));
+        self.set_and_announce(history_key(history.game_id), &json, change).await
+    }
+
+    async fn load_history(&self, game_id: Uuid) -> GameResult<Option<GameHistory>> {
+        let json: Option<String> =
+            self.connection.clone().get(history_key(game_id)).await.map_err(storage_error)?;
+        json.map(|json| from_json(&json)).transpose()
+    }
+
+    async fn save_rating(&self, rating: &PlayerRating) -> GameResult<()> {
+        let announcement = self.announcement(StoreChange::Rating(rating.clone()))?;
+        redis::pipe()
+            .atomic()
+            .hset(ratings_key(), &rating.player_id, to_json(rating)?)
+            .ignore()
+            .publish(channel(), announcement)
+            .ignore()
+            .query_async(&mut self.connection.clone())
+            .await
+            .map_err(storage_error)
+    }
+
+    async fn load_all_ratings(&self) -> GameResult<Vec<PlayerRating>> {
+        let ratings: Vec<String> = self.connection.clone().hvals(ratings_key()).await.map_err(storage_error)?;
+        ratings.iter().map(|json| from_json(json)).collect()
+    }
+
+    async fn create_user(&self, user: &User) -> GameResult<()> {
+        let created: bool = self
+            .connection
+            .clone()
+            .hset_nx(users_key(), &user.username, to_json(user)?)
+            .await
+            .map_err(storage_error)?;
+
+        if !created {
+            return Err(GameError::UsernameTaken(user.username.clone()));
+        }
+        Ok(())
+    }
+
+    async fn load_user(&self, username: &str) -> GameResult<Option<User>> {
+        let json: Option<String> =
+            self.connection.clone().hget(users_key(), username).await.map_err(storage_error)?;
+        json.map(|json| from_json(&json)).transpose()
+    }
+
+    async fn changes(&self) -> GameResult<Option<mpsc::Receiver<StoreChange>>> {
+        let (sender, receiver) = mpsc::channel(CHANGE_CHANNEL_CAPACITY);
+        let (client, source) = (self.client.clone(), self.source);
+
+        // Fail straight away if the first subscription can't be made
+        let mut pubsub = subscribe(&client).await?;
+
+        tokio::spawn(async move {
+            loop {
+                let mut messages = pubsub.into_on_message();
+                while let Some(message) = messages.next().await {
+                    let announcement = message
+                        .get_payload::<String>()
+                        .map_err(storage_error)
+                        .and_then(|json| from_json::<Announcement>(&json));
+                    match announcement {
+                        Ok(announcement) if announcement.source == source => {}
+                        Ok(announcement) => {
+                            if sender.send(announcement.change).await.is_err() {
+                                return;
+                            }
+                        }
+                        Err(e) => warn!("Ignoring a change announced by another server: {}", e),
+                    }
+                }
+
+                // Changes announced until the subscription is back are missed, but
+                // saving over a game changed meanwhile still fails and reloads it
+                warn!("Lost the subscription to changes made by other servers; resubscribing");
+                pubsub = loop {
+                    tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+                    if sender.is_closed() {
+                        return;
+                    }
+                    match subscribe(&client).await {
+                        Ok(pubsub) => break pubsub,
+                        Err(e) => warn!("Failed to resubscribe to changes: {}", e),
+                    }
+                };
+            }
+        });
+
+        Ok(Some(receiver))
+    }
+}
+
+/// Subscribe to the channel changes are announced on
+async fn subscribe(client: &Client) -> GameResult<PubSub> {
+    let mut pubsub = client.get_async_pubsub().await.map_err(storage_error)?;
+    pubsub.subscribe(channel()).await.map_err(storage_error)?;
+    Ok(pubsub)
+}
+
+/// Convert a Redis error into a storage error
+fn storage_error(error: redis::RedisError) -> GameError {
+    GameError::StorageError(error.to_string())
+}
+
+/// Serialize a value into a JSON document for Redis
+fn to_json(value: &impl Serialize) -> GameResult<String> {
+    serde_json::to_string(value).map_err(GameError::from)
+}
+
+/// Deserialize a JSON document read from Redis
+fn from_json<T: serde::de::DeserializeOwned>(json: &str) -> GameResult<T> {
+    serde_json::from_str(json).map_err(GameError::from)
+}
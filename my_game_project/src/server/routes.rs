@@ -1,28 +1,88 @@
 use axum::{
-    routing::{get, post},
+    middleware,
+    routing::{delete, get, post},
     Router,
 };
-use tower_http::cors::{Any, CorsLayer};
+use tower_http::cors::CorsLayer;
 
-use crate::server::handlers;
+use crate::server::{
+    admin, auth, events, graphql, handlers, images, leaderboard, matches, matchmaking, metrics, negotiate, puzzles,
+    spectate, trace, ws,
+};
+use crate::server::config::ServerConfig;
 use crate::server::state::AppState;
 
-/// Create the router for the HTTP server
+/// Create the router for the HTTP server, allowing requests from any origin
 pub fn create_router() -> Router<AppState> {
-    // Create a CORS layer
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+    create_router_with_cors(ServerConfig::default().cors_layer())
+}
 
+/// Create the router for the HTTP server with the given CORS policy
+pub fn create_router_with_cors(cors: CorsLayer) -> Router<AppState> {
     // Create the router
     Router::new()
         // Game routes
         .route("/games", get(handlers::list_games))
         .route("/games", post(handlers::create_game))
+        .route("/games", delete(handlers::delete_games))
+        .route("/games/import", post(handlers::import_game))
         .route("/games/:id", get(handlers::get_game))
+        .route("/games/:id", delete(handlers::delete_game))
+        .route("/games/:id/join", post(handlers::join_game))
+        .route("/games/:id/history", get(handlers::get_history))
+        .route("/games/:id/export", get(handlers::export_game))
+        .route("/games/:id/analysis", get(handlers::get_analysis))
+        .route("/games/:id/evaluations", get(handlers::get_evaluations))
         .route("/games/:id/move", post(handlers::make_move))
         .route("/games/:id/ai-move", post(handlers::make_ai_move))
+        .route("/games/:id/resign", post(handlers::resign))
+        .route("/games/:id/undo", post(handlers::undo))
+        .route("/games/:id/chat", post(handlers::post_chat))
+        .route("/games/:id/draw-offer", post(handlers::offer_draw))
+        .route("/games/:id/draw-accept", post(handlers::accept_draw))
+        .route("/games/:id/draw-decline", post(handlers::decline_draw))
+        .route("/games/:id/ws", get(ws::game_socket))
+        .route("/games/:id/events", get(events::game_events))
+        .route("/games/:id/spectate", get(spectate::spectate))
+        // Board image routes
+        .route("/games/:id/board.svg", get(images::get_board_svg))
+        .route("/games/:id/board.png", get(images::get_board_png))
+        // Match routes
+        .route("/matches", get(matches::list_matches))
+        .route("/matches", post(matches::create_match))
+        .route("/matches/:id", get(matches::get_match))
+        .route("/matches/:id/move", post(matches::make_match_move))
+        .route("/matches/:id/resign", post(matches::resign_match_game))
+        // GraphQL routes
+        .route("/graphql", get(graphql::graphiql).post(graphql::graphql))
+        .route("/graphql/ws", get(graphql::graphql_ws))
+        // Matchmaking routes
+        .route("/matchmaking/join", post(matchmaking::join))
+        .route("/matchmaking/:ticket", get(matchmaking::poll))
+        .route("/matchmaking/:ticket", delete(matchmaking::leave))
+        // Account routes
+        .route("/auth/register", post(auth::register))
+        .route("/auth/login", post(auth::login))
+        .route("/me/games", get(auth::my_games))
+        // Rating routes
+        .route("/leaderboard", get(leaderboard::get_leaderboard))
+        .route("/players/:id", get(leaderboard::get_player))
+        // Statistics route
+        .route("/stats", get(leaderboard::get_stats))
+        // Puzzle route
+        .route("/puzzles/daily", get(puzzles::get_daily_puzzle))
+        // Admin routes
+        .route("/admin/stats", get(admin::get_stats))
+        .route("/admin/games/:id", delete(handlers::delete_game))
+        .route("/admin/games/:id/finish", post(admin::finish_game))
+        // Metrics route
+        .route("/metrics", get(metrics::get_metrics))
+        // Time every request that matched a route
+        .route_layer(middleware::from_fn(metrics::track_requests))
+        // Speak MessagePack to clients that ask for it
+        .layer(middleware::from_fn(negotiate::negotiate_content))
         // Add the CORS layer
         .layer(cors)
+        // Log each request in a span carrying its request ID
+        .layer(middleware::from_fn(trace::trace_requests))
 }
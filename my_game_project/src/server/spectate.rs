@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use axum::{
+    extract::{ws::WebSocketUpgrade, Path, State},
+    response::{
+        sse::{KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+};
+use futures_util::StreamExt;
+use uuid::Uuid;
+
+use crate::error::GameError;
+use crate::server::error::ApiError;
+use crate::server::state::AppState;
+use crate::server::{events, ws};
+
+/// The number of spectators watching each game
+#[derive(Debug, Clone, Default)]
+pub struct Spectators(Arc<Mutex<HashMap<Uuid, usize>>>);
+
+impl Spectators {
+    /// Count a new spectator of game `id` until the returned guard is dropped
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::server::Spectators;
+    /// use uuid::Uuid;
+    ///
+    /// let spectators = Spectators::default();
+    /// let id = Uuid::new_v4();
+    ///
+    /// let guard = spectators.watch(id);
+    /// assert_eq!(spectators.count(id), 1);
+    /// drop(guard);
+    /// assert_eq!(spectators.count(id), 0);
+    /// ```
+    pub fn watch(&self, id: Uuid) -> SpectatorGuard {
+        *self.lock().entry(id).or_default() += 1;
+        SpectatorGuard {
+            spectators: self.clone(),
+            id,
+        }
+    }
+
+    /// The number of spectators watching game `id`
+    pub fn count(&self, id: Uuid) -> usize {
+        self.lock().get(&id).copied().unwrap_or(0)
+    }
+
+    /// Lock the counts, ignoring poisoning since they're always left consistent
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<Uuid, usize>> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A spectator watching a game, who stops being counted when this is dropped
+#[derive(Debug)]
+pub struct SpectatorGuard {
+    /// The counts to remove the spectator from
+    spectators: Spectators,
+    /// The game being watched
+    id: Uuid,
+}
+
+impl Drop for SpectatorGuard {
+    fn drop(&mut self) {
+        let mut counts = self.spectators.lock();
+        if let Some(count) = counts.get_mut(&self.id) {
+            *count -= 1;
+            if *count == 0 {
+                counts.remove(&self.id);
+            }
+        }
+    }
+}
+
+/// Watch a game without being able to play in it
+///
+/// Upgrades to a read-only WebSocket if the client asks for one, and
+/// otherwise streams server-sent events like `GET /games/:id/events`. The
+/// spectator is counted in the game's summary while connected.
+pub async fn spectate(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    ws: Option<WebSocketUpgrade>,
+) -> Result<Response, ApiError> {
+    if !state.games.read().await.contains_key(&id) {
+        return Err(GameError::GameNotFound(id.to_string()).into());
+    }
+
+    let guard = state.spectators.watch(id);
+    match ws {
+        Some(ws) => Ok(ws.on_upgrade(move |socket| ws::handle_socket(socket, state, id, Some(guard)))),
+        None => {
+            // The guard lives as long as the stream, which is dropped when the client leaves
+            let events = events::event_stream(&state, id).await?.map(move |event| {
+                let _watching = &guard;
+                event
+            });
+            Ok(Sse::new(events).keep_alive(KeepAlive::default()).into_response())
+        }
+    }
+}
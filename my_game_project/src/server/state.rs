@@ -1,23 +1,1180 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::Duration;
 
-use tokio::sync::RwLock;
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, Mutex, RwLock};
+use tracing::{instrument, warn, Span};
 use uuid::Uuid;
 
-use crate::game::GameState;
+use crate::ai::{Difficulty, MinimaxAI, SearchStats};
+use crate::archive::{HistoryQuery, HistoryStore};
+use crate::error::{GameError, GameResult};
+use crate::events::GameEvent;
+use crate::game::{GameState, GameStatus};
+use crate::history::{ChatMessage, GameHistory};
+use crate::player::Player;
+use crate::position::Position;
+use crate::puzzle::Puzzle;
+use crate::rating::{rate_game, PlayerRating};
+use crate::save::PlayerInfo;
+use crate::server::auth::{AuthUser, TokenIssuer, DEFAULT_TOKEN_TTL};
+use crate::server::expiry::{ExpiryAction, ExpiryPolicy};
+use crate::server::matches::MatchRecord;
+use crate::server::matchmaking::{Matchmaker, Seat};
+use crate::server::spectate::Spectators;
+use crate::server::metrics::Metrics;
+use crate::server::store::{GameStore, MemoryStore, StoreChange};
+use crate::server::webhooks::{self, Webhook};
+use crate::stats::GameStats;
+
+/// Number of unread updates a slow subscriber may fall behind by
+const UPDATE_CHANNEL_CAPACITY: usize = 32;
+
+/// The longest chat message a player may send, in characters
+pub const MAX_CHAT_LENGTH: usize = 500;
+
+/// Longest the AI may search for a move unless configured otherwise
+pub const DEFAULT_AI_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An AI opponent that the server plays on behalf of
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AiOpponent {
+    /// The symbol the AI plays as
+    pub player: Player,
+    /// The difficulty level of the AI
+    pub difficulty: Difficulty,
+}
+
+/// Secret tokens identifying the client playing each seat of a game
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SeatTokens {
+    /// Token for the X player
+    pub x: String,
+    /// Token for the O player
+    pub o: String,
+}
+
+impl SeatTokens {
+    /// Generate a fresh, random token for each seat
+    pub fn generate() -> Self {
+        Self {
+            x: Uuid::new_v4().simple().to_string(),
+            o: Uuid::new_v4().simple().to_string(),
+        }
+    }
+
+    /// Returns the token for a player's seat
+    pub fn for_player(&self, player: Player) -> &str {
+        match player {
+            Player::X => &self.x,
+            Player::O => &self.o,
+        }
+    }
+
+    /// The tokens for every seat except `others`, the seats someone else plays
+    pub fn except(&self, others: &[Option<Player>]) -> CreatorTokens {
+        let held = |player| (!others.contains(&Some(player))).then(|| self.for_player(player).to_string());
+        CreatorTokens {
+            x: held(Player::X),
+            o: held(Player::O),
+        }
+    }
+
+    /// Check that `token` is the token for a player's seat
+    pub fn verify(&self, player: Player, token: Option<&str>) -> GameResult<()> {
+        if token == Some(self.for_player(player)) {
+            Ok(())
+        } else {
+            Err(GameError::InvalidToken(format!("{:?}", player)))
+        }
+    }
+}
+
+/// The seat tokens handed to a game's creator, leaving out the seats someone else plays
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CreatorTokens {
+    /// Token for the X player, unless X is left open or played by the AI
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub x: Option<String>,
+    /// Token for the O player, unless O is left open or played by the AI
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub o: Option<String>,
+}
+
+/// A registered user holding a seat
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SeatUser {
+    /// ID of the user
+    pub id: Uuid,
+    /// Username of the user, which their ratings are kept under
+    pub username: String,
+}
+
+impl From<&AuthUser> for SeatUser {
+    fn from(user: &AuthUser) -> Self {
+        Self {
+            id: user.id,
+            username: user.username.clone(),
+        }
+    }
+}
+
+/// The registered users sitting in each seat of a game
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SeatUsers {
+    /// The user playing X, if a logged-in user holds the seat
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub x: Option<SeatUser>,
+    /// The user playing O, if a logged-in user holds the seat
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub o: Option<SeatUser>,
+}
+
+impl SeatUsers {
+    /// Returns true if no user holds either seat
+    pub fn is_empty(&self) -> bool {
+        self.x.is_none() && self.o.is_none()
+    }
+
+    /// Give a player's seat to `user`
+    pub fn seat(&mut self, player: Player, user: &AuthUser) {
+        let holder = Some(SeatUser::from(user));
+        match player {
+            Player::X => self.x = holder,
+            Player::O => self.o = holder,
+        }
+    }
+
+    /// The user holding a player's seat, if any
+    pub fn holder(&self, player: Player) -> Option<&SeatUser> {
+        match player {
+            Player::X => self.x.as_ref(),
+            Player::O => self.o.as_ref(),
+        }
+    }
+
+    /// The seats `user` holds
+    pub fn seats_of(&self, user: Uuid) -> impl Iterator<Item = Player> + '_ {
+        [Player::X, Player::O]
+            .into_iter()
+            .filter(move |&player| self.holder(player).is_some_and(|holder| holder.id == user))
+    }
+
+    /// The usernames of the X and O players, if two different users hold the
+    /// seats and the game counts towards their ratings
+    pub fn rated_players(&self) -> Option<(&str, &str)> {
+        match (&self.x, &self.o) {
+            (Some(x), Some(o)) if x.id != o.id => Some((&x.username, &o.username)),
+            _ => None,
+        }
+    }
+}
+
+/// A game hosted by the server along with its server-side settings
+///
+/// Serializes as the game state with the settings as extra fields.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameRecord {
+    /// The state of the game
+    #[serde(flatten)]
+    pub game: GameState,
+    /// The AI opponent that replies automatically, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ai_opponent: Option<AiOpponent>,
+    /// The seat tokens, if the game's seats are assigned to specific clients
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seat_tokens: Option<SeatTokens>,
+    /// Whether the result has already been applied to the players' ratings
+    #[serde(default)]
+    pub rated: bool,
+    /// A seat waiting for a remote player to claim it with `POST /games/:id/join`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub open_seat: Option<Player>,
+    /// The logged-in users holding the game's seats
+    #[serde(default, skip_serializing_if = "SeatUsers::is_empty")]
+    pub users: SeatUsers,
+    /// Where the game's events are sent, if anywhere
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook: Option<Webhook>,
+    /// The player asking to take back their last move, until the opponent answers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub undo_request: Option<Player>,
+    /// When the game was created
+    #[serde(default = "Utc::now")]
+    pub created_at: DateTime<Utc>,
+    /// When the game last changed
+    #[serde(default = "Utc::now")]
+    pub updated_at: DateTime<Utc>,
+    /// When the game will be archived or deleted unless it changes again
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// The number of times the record has changed, so servers sharing a store can spot conflicts
+    #[serde(default)]
+    pub revision: u64,
+}
+
+impl GameRecord {
+    /// Create a record for a game between two clients
+    pub fn new(game: GameState) -> Self {
+        let now = Utc::now();
+        Self {
+            game,
+            ai_opponent: None,
+            seat_tokens: None,
+            rated: false,
+            open_seat: None,
+            users: SeatUsers::default(),
+            webhook: None,
+            undo_request: None,
+            created_at: now,
+            updated_at: now,
+            expires_at: None,
+            revision: 0,
+        }
+    }
+
+    /// Mark the game as changed just now, moving back its expiry under `expiry`
+    pub fn touch(&mut self, expiry: Option<&ExpiryPolicy>) {
+        self.updated_at = Utc::now();
+        self.expires_at = expiry.map(|policy| policy.expires_at(self));
+        self.revision += 1;
+    }
+
+    /// Returns who played the game, as far as the server knows
+    ///
+    /// Players of rated games are named by their usernames, and AI opponents
+    /// by their difficulty.
+    pub fn player_infos(&self) -> Vec<PlayerInfo> {
+        [Player::X, Player::O]
+            .into_iter()
+            .filter_map(|player| match (self.ai_opponent, self.users.rated_players()) {
+                (Some(opponent), _) if opponent.player == player => Some(PlayerInfo {
+                    player,
+                    name: format!("AI ({:?})", opponent.difficulty),
+                    human: false,
+                }),
+                (_, Some((x, o))) => Some(PlayerInfo {
+                    player,
+                    name: match player {
+                        Player::X => x.to_string(),
+                        Player::O => o.to_string(),
+                    },
+                    human: true,
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns a copy that is safe to show to any client, with secrets removed
+    pub fn public(&self) -> Self {
+        Self {
+            seat_tokens: None,
+            webhook: None,
+            ..self.clone()
+        }
+    }
+}
+
+/// Something that happened in a game, broadcast to the game's subscribers
+#[derive(Debug, Clone)]
+pub enum GameUpdate {
+    /// The game's new state, with secrets removed
+    State(Box<GameRecord>),
+    /// Something that happened in the game, sent before the state it led to
+    Event(GameEvent),
+    /// A message one of the players sent
+    Chat(ChatMessage),
+}
 
 /// Shared application state for the HTTP server
 #[derive(Debug, Clone)]
 pub struct AppState {
-    /// Map of game ID to game state
-    pub games: Arc<RwLock<HashMap<Uuid, GameState>>>,
+    /// Map of game ID to game record
+    pub games: Arc<RwLock<HashMap<Uuid, GameRecord>>>,
+    /// Map of game ID to the history of moves made in it
+    pub histories: Arc<RwLock<HashMap<Uuid, GameHistory>>>,
+    /// Map of game ID to the channel that broadcasts its updates
+    pub updates: Arc<RwLock<HashMap<Uuid, broadcast::Sender<GameUpdate>>>>,
+    /// The backend that every change is written through to
+    pub store: Arc<dyn GameStore>,
+    /// The queue of players waiting for an opponent
+    pub matchmaker: Arc<Mutex<Matchmaker>>,
+    /// Map of player ID to rating
+    pub ratings: Arc<RwLock<HashMap<String, PlayerRating>>>,
+    /// When idle and finished games expire, if they ever do
+    pub expiry: Option<ExpiryPolicy>,
+    /// Difficulty of AI opponents when a request doesn't give one
+    pub ai_difficulty: Difficulty,
+    /// Issues and checks the tokens of logged-in users
+    pub auth: TokenIssuer,
+    /// The number of spectators watching each game
+    pub spectators: Spectators,
+    /// Map of match ID to the best-of-N match being played
+    pub matches: Arc<RwLock<HashMap<Uuid, MatchRecord>>>,
+    /// Usernames of the users allowed to call the admin API
+    pub admins: Arc<HashSet<String>>,
+    /// Longest the AI may search for a move
+    pub ai_timeout: Duration,
+    /// Where finished games are archived, if anywhere
+    pub archive: Option<HistoryStore>,
+    /// Map of date to that day's puzzle, once it has been found
+    pub puzzles: Arc<RwLock<HashMap<NaiveDate, Puzzle>>>,
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl AppState {
-    /// Create a new application state
+    /// Create a new application state backed by an in-memory store
     pub fn new() -> Self {
         Self {
             games: Arc::new(RwLock::new(HashMap::new())),
+            histories: Arc::new(RwLock::new(HashMap::new())),
+            updates: Arc::new(RwLock::new(HashMap::new())),
+            store: Arc::new(MemoryStore::new()),
+            matchmaker: Arc::new(Mutex::new(Matchmaker::default())),
+            ratings: Arc::new(RwLock::new(HashMap::new())),
+            expiry: None,
+            ai_difficulty: Difficulty::Medium,
+            auth: TokenIssuer::random(DEFAULT_TOKEN_TTL),
+            spectators: Spectators::default(),
+            matches: Arc::new(RwLock::new(HashMap::new())),
+            admins: Arc::default(),
+            ai_timeout: DEFAULT_AI_TIMEOUT,
+            archive: None,
+            puzzles: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Create an application state backed by `store`, loading every game and rating it holds
+    pub async fn with_store(store: Arc<dyn GameStore>) -> GameResult<Self> {
+        let mut games = HashMap::new();
+        let mut histories = HashMap::new();
+
+        for record in store.load_all_games().await? {
+            let id = record.game.id;
+            let history = match store.load_history(id).await? {
+                Some(history) => history,
+                None => record.game.create_history(),
+            };
+            histories.insert(id, history);
+            games.insert(id, record);
+        }
+
+        let ratings = store
+            .load_all_ratings()
+            .await?
+            .into_iter()
+            .map(|rating| (rating.player_id.clone(), rating))
+            .collect();
+
+        let state = Self {
+            games: Arc::new(RwLock::new(games)),
+            histories: Arc::new(RwLock::new(histories)),
+            updates: Arc::new(RwLock::new(HashMap::new())),
+            store,
+            matchmaker: Arc::new(Mutex::new(Matchmaker::default())),
+            ratings: Arc::new(RwLock::new(ratings)),
+            expiry: None,
+            ai_difficulty: Difficulty::Medium,
+            auth: TokenIssuer::random(DEFAULT_TOKEN_TTL),
+            spectators: Spectators::default(),
+            matches: Arc::new(RwLock::new(HashMap::new())),
+            admins: Arc::default(),
+            ai_timeout: DEFAULT_AI_TIMEOUT,
+            archive: None,
+            puzzles: Arc::new(RwLock::new(HashMap::new())),
+        };
+
+        // Carry on sending the events of unfinished games to their webhooks
+        for record in state.games.read().await.values() {
+            webhooks::watch(&state, record).await;
+        }
+
+        Ok(state)
+    }
+
+    /// Expire games under `policy`, recalculating when every existing game expires
+    pub async fn with_expiry(mut self, policy: ExpiryPolicy) -> Self {
+        for record in self.games.write().await.values_mut() {
+            record.expires_at = Some(policy.expires_at(record));
+        }
+        self.expiry = Some(policy);
+        self
+    }
+
+    /// Issue and check login tokens with `auth`
+    pub fn with_token_issuer(mut self, auth: TokenIssuer) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Let the users named in `admins` call the admin API
+    pub fn with_admins(mut self, admins: impl IntoIterator<Item = String>) -> Self {
+        self.admins = Arc::new(admins.into_iter().collect());
+        self
+    }
+
+    /// Use `difficulty` for AI opponents when a request doesn't give one
+    pub fn with_ai_difficulty(mut self, difficulty: Difficulty) -> Self {
+        self.ai_difficulty = difficulty;
+        self
+    }
+
+    /// Give up on AI searches that take longer than `timeout`
+    pub fn with_ai_timeout(mut self, timeout: Duration) -> Self {
+        self.ai_timeout = timeout;
+        self
+    }
+
+    /// Archive finished games in `archive`, and collect statistics from it
+    pub fn with_archive(mut self, archive: HistoryStore) -> Self {
+        self.archive = Some(archive);
+        self
+    }
+
+    /// Choose `player`'s move in `game` at `difficulty`, away from the async runtime
+    ///
+    /// The search runs on the blocking thread pool, so it doesn't hold up
+    /// other requests. It fails with [`GameError::AiTimedOut`] if it takes
+    /// longer than the state's AI timeout, though the search itself can't be
+    /// interrupted and runs on to the end in the background.
+    pub async fn search_ai_move(
+        &self,
+        game: &GameState,
+        player: Player,
+        difficulty: Difficulty,
+    ) -> GameResult<(Position, SearchStats)> {
+        let game = game.clone();
+        self.run_search(move || {
+            let ai = MinimaxAI::new(player, difficulty);
+            Metrics::get().time_ai_move(game.id, difficulty, || ai.choose_move(&game))
+        })
+        .await?
+    }
+
+    /// Run an AI search off the async worker threads, giving up on it once
+    /// the AI timeout has passed
+    pub async fn run_search<T, F>(&self, search: F) -> GameResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let span = Span::current();
+        let search = tokio::task::spawn_blocking(move || span.in_scope(search));
+
+        match tokio::time::timeout(self.ai_timeout, search).await {
+            Ok(Ok(result)) => Ok(result),
+            Ok(Err(e)) => Err(GameError::AiFailed(e.to_string())),
+            Err(_) => {
+                warn!(timeout = ?self.ai_timeout, "AI search timed out");
+                Err(GameError::AiTimedOut(self.ai_timeout.as_millis() as u64))
+            }
+        }
+    }
+
+    /// Register a newly created game with an empty history
+    ///
+    /// If the game has an AI opponent that moves first, it makes its move
+    /// straight away, once the new game is saved.
+    #[instrument(skip_all, fields(game_id = %record.game.id))]
+    pub async fn insert_game(&self, mut record: GameRecord) -> GameResult<GameRecord> {
+        let id = record.game.id;
+        // Whatever happened before the game got here isn't part of its history
+        record.game.take_events();
+        webhooks::watch(self, &record).await;
+        let mut games = self.games.write().await;
+
+        self.histories.write().await.insert(id, record.game.create_history());
+        record.touch(self.expiry.as_ref());
+        games.insert(id, record.clone());
+        drop(games);
+
+        self.commit(&record).await?;
+        Metrics::get().game_created();
+        self.reply_as_ai(record).await
+    }
+
+    /// Host a game played elsewhere, keeping the history it comes with
+    ///
+    /// The history must replay to the game's board, and its first illegal
+    /// move is reported as [`GameError::IllegalHistoryMove`]. The game is
+    /// stored as the replay leaves it, so nothing but its move time limit is
+    /// taken from the client's copy. Timed games get a fresh clock, since the
+    /// save may be old.
+    #[instrument(skip_all, fields(game_id = %record.game.id))]
+    pub async fn import_game(
+        &self,
+        mut record: GameRecord,
+        history: GameHistory,
+    ) -> GameResult<GameRecord> {
+        let id = record.game.id;
+        if history.game_id != id {
+            return Err(GameError::InvalidImport(format!(
+                "the history is of game {}, not {}",
+                history.game_id, id
+            )));
+        }
+        let mut game = history.validate()?;
+        if game.board != record.game.board {
+            return Err(GameError::InvalidImport(
+                "the history doesn't lead to the game's board".to_string(),
+            ));
+        }
+        // Endings that aren't moves, like resigning, only show in the history's result
+        match history.final_status {
+            None | Some(GameStatus::InProgress) => {}
+            Some(status) if status == game.status => {}
+            Some(status @ (GameStatus::Resigned(_) | GameStatus::TimedOut(_) | GameStatus::Draw)) if !game.is_over() => {
+                game.finish(status);
+            }
+            Some(status) => {
+                return Err(GameError::InvalidImport(format!(
+                    "the history ends in {} but its moves don't",
+                    status
+                )));
+            }
+        }
+        if let Some(seconds) = record.game.move_time_limit {
+            game.set_move_time_limit(seconds);
+        }
+        // The history already holds everything that happened elsewhere
+        game.take_events();
+        record.game = game;
+
+        let mut games = self.games.write().await;
+        if games.contains_key(&id) {
+            return Err(GameError::GameAlreadyExists(id.to_string()));
+        }
+        self.histories.write().await.insert(id, history);
+        record.touch(self.expiry.as_ref());
+        games.insert(id, record.clone());
+        drop(games);
+
+        self.commit(&record).await?;
+        Metrics::get().game_created();
+        Ok(record)
+    }
+
+    /// Remove a game along with its history and update channel
+    ///
+    /// Dropping the update channel closes any open streams for the game.
+    #[instrument(skip_all, fields(game_id = %id))]
+    pub async fn remove_game(&self, id: Uuid) -> GameResult<GameRecord> {
+        let record = self
+            .games
+            .write()
+            .await
+            .remove(&id)
+            .ok_or_else(|| GameError::GameNotFound(id.to_string()))?;
+
+        self.histories.write().await.remove(&id);
+        self.updates.write().await.remove(&id);
+        self.store.delete_game(id).await?;
+
+        Ok(record)
+    }
+
+    /// Hand the game's open seat to the caller, along with the token to play it
+    ///
+    /// Each open seat can only be claimed once. If the caller is logged in as
+    /// `user`, the seat is theirs, and the game is rated if another logged-in
+    /// user holds the other seat.
+    #[instrument(skip_all, fields(game_id = %id))]
+    pub async fn claim_open_seat(&self, id: Uuid, user: Option<&AuthUser>) -> GameResult<Seat> {
+        let mut games = self.games.write().await;
+        let record = games.get_mut(&id).ok_or_else(|| GameError::GameNotFound(id.to_string()))?;
+
+        let player = record.open_seat.take().ok_or(GameError::NoOpenSeat)?;
+        let token = match &record.seat_tokens {
+            Some(tokens) => tokens.for_player(player).to_string(),
+            None => return Err(GameError::NoOpenSeat),
+        };
+        if let Some(user) = user {
+            record.users.seat(player, user);
+        }
+
+        record.touch(self.expiry.as_ref());
+        let record = record.clone();
+        drop(games);
+        self.commit(&record).await?;
+
+        Ok(Seat {
+            game_id: id,
+            player,
+            token,
+        })
+    }
+
+    /// Make a move for `player`, followed by the AI opponent's reply if the game has one
+    ///
+    /// If the game's seats are assigned, `token` must be the player's seat
+    /// token. If `expected_version` is given, the move is only made if the
+    /// game is still at that version. The move is saved before the AI
+    /// replies, so it stands even if the AI fails to.
+    #[instrument(skip(self, id, token), fields(game_id = %id), err(level = "debug"))]
+    pub async fn play_move(
+        &self,
+        id: Uuid,
+        player: Player,
+        token: Option<&str>,
+        position: Position,
+        expected_version: Option<u64>,
+    ) -> GameResult<GameRecord> {
+        let mut games = self.games.write().await;
+        let record = games.get_mut(&id).ok_or_else(|| GameError::GameNotFound(id.to_string()))?;
+
+        // Verify the caller holds the player's seat
+        if let Some(tokens) = &record.seat_tokens {
+            tokens.verify(player, token)?;
+        }
+
+        // Refuse moves made on a state the caller hasn't seen
+        match expected_version {
+            Some(expected) if expected != record.game.version => {
+                return Err(GameError::VersionConflict { expected, actual: record.game.version });
+            }
+            _ => {}
+        }
+
+        // A move made after the player's time ran out forfeits the game instead
+        if record.game.check_timeout(Utc::now()) {
+            self.record_events(&mut record.game).await;
+            self.rate_finished_game(record).await?;
+            record.touch(self.expiry.as_ref());
+            let record = record.clone();
+            drop(games);
+            self.commit(&record).await?;
+            return Err(GameError::GameAlreadyFinished);
+        }
+
+        // Make the move if it's the player's turn, which lets any pending undo request
+        // lapse
+        record.game.make_move_as(player, position)?;
+        record.undo_request = None;
+        self.record_events(&mut record.game).await;
+        self.rate_finished_game(record).await?;
+
+        record.touch(self.expiry.as_ref());
+        let record = record.clone();
+        drop(games);
+        self.commit(&record).await?;
+
+        self.reply_as_ai(record).await
+    }
+
+    /// Perform a non-move action for `player`, such as resigning or offering a draw
+    ///
+    /// If the game's seats are assigned, `token` must be the player's seat
+    /// token. An AI opponent answers draw offers straight away.
+    #[instrument(skip(self, id, token, action), fields(game_id = %id), err(level = "debug"))]
+    pub async fn act<F>(
+        &self,
+        id: Uuid,
+        player: Player,
+        token: Option<&str>,
+        action: F,
+    ) -> GameResult<GameRecord>
+    where
+        F: FnOnce(&mut GameState) -> GameResult<()>,
+    {
+        let mut games = self.games.write().await;
+        let record = games.get_mut(&id).ok_or_else(|| GameError::GameNotFound(id.to_string()))?;
+
+        // Verify the caller holds the player's seat
+        if let Some(tokens) = &record.seat_tokens {
+            tokens.verify(player, token)?;
+        }
+
+        action(&mut record.game)?;
+
+        // Let the AI opponent answer a draw offer
+        if let Some(opponent) = record.ai_opponent {
+            if record.game.draw_offer == Some(opponent.player.opponent()) {
+                let ai = MinimaxAI::new(opponent.player, opponent.difficulty);
+                if ai.accepts_draw(&record.game) {
+                    record.game.accept_draw(opponent.player)?;
+                } else {
+                    record.game.decline_draw(opponent.player)?;
+                }
+            }
+        }
+
+        self.record_events(&mut record.game).await;
+        if record.game.is_over() {
+            self.rate_finished_game(record).await?;
+        }
+
+        record.touch(self.expiry.as_ref());
+        let record = record.clone();
+        drop(games);
+        self.commit(&record).await?;
+
+        Ok(record)
+    }
+
+    /// Ask to take back `player`'s last move, and any reply made to it
+    ///
+    /// The opponent must accept with [`answer_undo`](Self::answer_undo)
+    /// before the moves are taken back, except an AI opponent, which always
+    /// accepts.
+    #[instrument(skip(self, id, token), fields(game_id = %id))]
+    pub async fn request_undo(
+        &self,
+        id: Uuid,
+        player: Player,
+        token: Option<&str>,
+    ) -> GameResult<GameRecord> {
+        let mut games = self.games.write().await;
+        let record = games.get_mut(&id).ok_or_else(|| GameError::GameNotFound(id.to_string()))?;
+
+        // Verify the caller holds the player's seat
+        if let Some(tokens) = &record.seat_tokens {
+            tokens.verify(player, token)?;
+        }
+        if record.game.is_over() {
+            return Err(GameError::GameAlreadyFinished);
+        }
+        self.plies_to_undo(&record.game, player).await?;
+
+        record.undo_request = Some(player);
+        if record.ai_opponent.is_some_and(|opponent| opponent.player == player.opponent()) {
+            self.take_back(record, player).await?;
+        }
+
+        record.touch(self.expiry.as_ref());
+        let record = record.clone();
+        drop(games);
+        self.commit(&record).await?;
+
+        Ok(record)
+    }
+
+    /// Accept or decline the opponent's request to undo their last move, on behalf of `player`
+    #[instrument(skip(self, id, token), fields(game_id = %id))]
+    pub async fn answer_undo(
+        &self,
+        id: Uuid,
+        player: Player,
+        token: Option<&str>,
+        accept: bool,
+    ) -> GameResult<GameRecord> {
+        let mut games = self.games.write().await;
+        let record = games.get_mut(&id).ok_or_else(|| GameError::GameNotFound(id.to_string()))?;
+
+        // Verify the caller holds the player's seat
+        if let Some(tokens) = &record.seat_tokens {
+            tokens.verify(player, token)?;
+        }
+        if record.undo_request != Some(player.opponent()) {
+            return Err(GameError::NoUndoRequest);
+        }
+
+        if accept {
+            self.take_back(record, player.opponent()).await?;
+        } else {
+            record.undo_request = None;
+        }
+
+        record.touch(self.expiry.as_ref());
+        let record = record.clone();
+        drop(games);
+        self.commit(&record).await?;
+
+        Ok(record)
+    }
+
+    /// The number of moves to take back so that `player` can play their last move again
+    async fn plies_to_undo(&self, game: &GameState, player: Player) -> GameResult<usize> {
+        let histories = self.histories.read().await;
+        let moves = histories.get(&game.id).map(|history| history.moves.as_slice()).unwrap_or_default();
+
+        moves
+            .iter()
+            .rposition(|game_move| game_move.player == player)
+            .map(|index| moves.len() - index)
+            .ok_or(GameError::NothingToUndo)
+    }
+
+    /// Take back `player`'s last move, and any reply made to it, settling the undo request
+    async fn take_back(&self, record: &mut GameRecord, player: Player) -> GameResult<()> {
+        let plies = self.plies_to_undo(&record.game, player).await?;
+        let mut histories = self.histories.write().await;
+        let history = histories.entry(record.game.id).or_insert_with(|| record.game.create_history());
+
+        record.game.undo(history, plies)?;
+        drop(histories);
+        record.undo_request = None;
+        self.record_events(&mut record.game).await;
+        Ok(())
+    }
+
+    /// Let the AI opponent reply to the move just committed in `record`
+    ///
+    /// The move stands even if the AI fails to reply. The game is then left
+    /// waiting for the AI, which can be asked to move again with
+    /// `POST /games/:id/ai-move`.
+    async fn reply_as_ai(&self, record: GameRecord) -> GameResult<GameRecord> {
+        match self.play_ai_turn(record.clone()).await {
+            Err(e @ (GameError::AiTimedOut(_) | GameError::AiFailed(_))) => {
+                warn!(error = %e, "AI opponent failed to move");
+                Ok(record)
+            }
+            result => result.map(|(record, _)| record),
+        }
+    }
+
+    /// Let the game's AI opponent move if it is its turn, returning the game as it then stands
+    ///
+    /// `record` must already be committed. The AI searches without holding
+    /// the `games` lock, so other games carry on meanwhile, and its move is
+    /// dropped if the game changed in the meantime. The search's statistics
+    /// come back if the AI searched.
+    #[instrument(skip_all, fields(game_id = %record.game.id))]
+    pub async fn play_ai_turn(&self, record: GameRecord) -> GameResult<(GameRecord, Option<SearchStats>)> {
+        let opponent = match record.ai_opponent {
+            Some(opponent) if !record.game.is_over() && record.game.current_turn == opponent.player => {
+                opponent
+            }
+            _ => return Ok((record, None)),
+        };
+
+        let (position, stats) =
+            self.search_ai_move(&record.game, opponent.player, opponent.difficulty).await?;
+
+        let id = record.game.id;
+        let mut games = self.games.write().await;
+        let current = match games.get_mut(&id) {
+            Some(current) if current.game.version == record.game.version => current,
+            Some(current) => return Ok((current.clone(), None)),
+            None => return Ok((record, None)),
+        };
+
+        let result = current.game.make_move_as(opponent.player, position);
+        // Record the forfeit even if the AI took longer than the move time limit
+        self.record_events(&mut current.game).await;
+        match result {
+            Err(_) if current.game.is_over() => {}
+            result => result?,
+        }
+        self.rate_finished_game(current).await?;
+
+        current.touch(self.expiry.as_ref());
+        let record = current.clone();
+        drop(games);
+        self.commit(&record).await?;
+
+        Ok((record, Some(stats)))
+    }
+
+    /// Take the events of the changes just made to `game`, recording them in
+    /// its history and the metrics and sending them to its subscribers
+    ///
+    /// Callers should hold the `games` lock so events are recorded in the
+    /// order they happened.
+    pub async fn record_events(&self, game: &mut GameState) {
+        let events = game.take_events();
+        if events.is_empty() {
+            return;
+        }
+
+        let mut histories = self.histories.write().await;
+        let history = histories.entry(game.id).or_insert_with(|| game.create_history());
+        for event in &events {
+            history.record(event);
+        }
+        drop(histories);
+
+        Metrics::get().record(&events);
+        for event in events {
+            self.broadcast(game.id, GameUpdate::Event(event)).await;
+        }
+    }
+
+    /// Forfeit every in-progress game whose current player has run out of time
+    ///
+    /// Returns the IDs of the forfeited games.
+    pub async fn forfeit_timed_out_games(&self) -> GameResult<Vec<Uuid>> {
+        let now = Utc::now();
+        let mut forfeited = Vec::new();
+
+        let mut games = self.games.write().await;
+        for record in games.values_mut() {
+            if record.game.check_timeout(now) {
+                record.touch(self.expiry.as_ref());
+                self.record_events(&mut record.game).await;
+                self.rate_finished_game(record).await?;
+                forfeited.push(record.clone());
+            }
+        }
+        drop(games);
+
+        for record in &forfeited {
+            self.commit(record).await?;
+        }
+
+        Ok(forfeited.iter().map(|record| record.game.id).collect())
+    }
+
+    /// End game `id` straight away, won by `winner` or drawn if there is none
+    ///
+    /// Like resigning, this leaves the game's version alone, as no move was made.
+    #[instrument(skip(self, id), fields(game_id = %id))]
+    pub async fn force_finish(&self, id: Uuid, winner: Option<Player>) -> GameResult<GameRecord> {
+        let mut games = self.games.write().await;
+        let record = games.get_mut(&id).ok_or_else(|| GameError::GameNotFound(id.to_string()))?;
+        if record.game.is_over() {
+            return Err(GameError::GameAlreadyFinished);
+        }
+
+        record.game.finish(winner.map_or(GameStatus::Draw, GameStatus::Won));
+        record.undo_request = None;
+        self.record_events(&mut record.game).await;
+        self.rate_finished_game(record).await?;
+
+        record.touch(self.expiry.as_ref());
+        let record = record.clone();
+        drop(games);
+        self.commit(&record).await?;
+
+        Ok(record)
+    }
+
+    /// Archive or delete every game that has expired by `now`, as the expiry policy says
+    ///
+    /// Returns the IDs of the expired games.
+    pub async fn expire_games(&self, now: DateTime<Utc>) -> GameResult<Vec<Uuid>> {
+        let policy = match self.expiry {
+            Some(policy) => policy,
+            None => return Ok(Vec::new()),
+        };
+
+        let mut games = self.games.write().await;
+        let expired: Vec<Uuid> = games
+            .values()
+            .filter(|record| policy.expires_at(record) <= now)
+            .map(|record| record.game.id)
+            .collect();
+
+        for id in &expired {
+            games.remove(id);
+            self.histories.write().await.remove(id);
+            self.updates.write().await.remove(id);
+        }
+        drop(games);
+
+        for &id in &expired {
+            match policy.action {
+                ExpiryAction::Archive => self.store.archive_game(id).await?,
+                ExpiryAction::Delete => self.store.delete_game(id).await?,
+            }
+        }
+
+        Ok(expired)
+    }
+
+    /// Apply the result of a finished rated game to its players' ratings
+    ///
+    /// Only games between two different logged-in users are rated, under
+    /// their usernames, and each game is only rated once however many times
+    /// this is called.
+    async fn rate_finished_game(&self, record: &mut GameRecord) -> GameResult<()> {
+        let (x, o) = match record.users.rated_players() {
+            Some(players) if record.game.is_over() && !record.rated && record.ai_opponent.is_none() => players,
+            _ => return Ok(()),
+        };
+
+        let mut ratings = self.ratings.write().await;
+        let mut x = ratings.get(x).cloned().unwrap_or_else(|| PlayerRating::new(x));
+        let mut o = ratings.get(o).cloned().unwrap_or_else(|| PlayerRating::new(o));
+        rate_game(&mut x, &mut o, record.game.status);
+
+        self.store.save_rating(&x).await?;
+        self.store.save_rating(&o).await?;
+        ratings.insert(x.player_id.clone(), x);
+        ratings.insert(o.player_id.clone(), o);
+        record.rated = true;
+
+        Ok(())
+    }
+
+    /// Returns every rated player, highest rating first
+    pub async fn leaderboard(&self) -> Vec<PlayerRating> {
+        let mut ratings: Vec<PlayerRating> = self.ratings.read().await.values().cloned().collect();
+        ratings.sort_by(|a, b| b.rating.total_cmp(&a.rating).then_with(|| a.player_id.cmp(&b.player_id)));
+        ratings
+    }
+
+    /// Collects statistics over the finished games that match `query`
+    ///
+    /// The games come from the archive if there is one, and from the games
+    /// the server holds otherwise. Players in rated games are named by their
+    /// usernames, and by their mark otherwise.
+    pub async fn game_stats(&self, query: &HistoryQuery) -> GameResult<GameStats> {
+        if let Some(archive) = &self.archive {
+            return archive.stats(query).await;
+        }
+
+        let games = self.games.read().await;
+        let histories = self.histories.read().await;
+        let mut stats = GameStats::default();
+        for (id, history) in histories.iter() {
+            let players = games.get(id).map(GameRecord::player_infos).unwrap_or_default();
+            if !query.matches(history, &players) {
+                continue;
+            }
+            match games.get(id).and_then(|record| record.users.rated_players()) {
+                Some((x, o)) => stats.add_with_players(history, x, o),
+                None => stats.add(history),
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Persist a game's new state and history, and broadcast it to its subscribers
+    #[instrument(skip_all, fields(game_id = %record.game.id))]
+    pub async fn commit(&self, record: &GameRecord) -> GameResult<()> {
+        if let Err(e) = self.store.save_game(record).await {
+            // Another server changed the game first, so pick up its copy instead
+            if matches!(e, GameError::ConcurrentUpdate(_)) {
+                self.reload_game(record.game.id).await?;
+            }
+            return Err(e);
+        }
+
+        let history = self.histories.read().await.get(&record.game.id).cloned();
+        if let Some(history) = history {
+            self.store.save_history(&history).await?;
+            if let Some(archive) = self.archive.as_ref().filter(|_| record.game.is_over()) {
+                archive.record(&history, &record.player_infos()).await?;
+            }
+        }
+
+        self.publish(record).await;
+        Ok(())
+    }
+
+    /// Replace game `id` and its history with the copies in the store
+    async fn reload_game(&self, id: Uuid) -> GameResult<()> {
+        let Some(record) = self.store.load_game(id).await? else {
+            self.apply_store_change(StoreChange::Removed(id)).await;
+            return Ok(());
+        };
+
+        self.apply_store_change(StoreChange::Game(Box::new(record))).await;
+        if let Some(history) = self.store.load_history(id).await? {
+            self.apply_store_change(StoreChange::History(Box::new(history))).await;
+        }
+        Ok(())
+    }
+
+    /// Bring the games in memory up to date with a change made to a shared store
+    ///
+    /// Subscribers to the game hear about the change as if it had been made
+    /// here. A copy of a game older than the one held already is ignored.
+    pub async fn apply_store_change(&self, change: StoreChange) {
+        match change {
+            StoreChange::Game(record) => {
+                let mut games = self.games.write().await;
+                let id = record.game.id;
+                if games.get(&id).is_some_and(|current| current.revision > record.revision) {
+                    return;
+                }
+                games.insert(id, (*record).clone());
+                drop(games);
+                self.publish(&record).await;
+            }
+            StoreChange::History(history) => {
+                let id = history.game_id;
+                let mut histories = self.histories.write().await;
+                let seen = histories.get(&id).map_or(0, |current| current.chat.len());
+                let chat = history.chat.get(seen..).unwrap_or_default().to_vec();
+                histories.insert(id, *history);
+                drop(histories);
+
+                for message in chat {
+                    self.broadcast(id, GameUpdate::Chat(message)).await;
+                }
+            }
+            StoreChange::Rating(rating) => {
+                self.ratings.write().await.insert(rating.player_id.clone(), rating);
+            }
+            StoreChange::Removed(id) => {
+                self.games.write().await.remove(&id);
+                self.histories.write().await.remove(&id);
+                self.updates.write().await.remove(&id);
+            }
+        }
+    }
+
+    /// Send a chat message from `player` to everyone watching game `id`
+    ///
+    /// If the game's seats are assigned, `token` must be the player's seat
+    /// token. The message is kept with the game's history.
+    #[instrument(skip(self, id, token, text), fields(game_id = %id))]
+    pub async fn post_chat(
+        &self,
+        id: Uuid,
+        player: Player,
+        token: Option<&str>,
+        text: &str,
+    ) -> GameResult<ChatMessage> {
+        let text = text.trim();
+        if text.is_empty() || text.chars().count() > MAX_CHAT_LENGTH {
+            return Err(GameError::InvalidChatMessage(format!(
+                "messages must be 1 to {} characters long",
+                MAX_CHAT_LENGTH
+            )));
+        }
+
+        let games = self.games.read().await;
+        let record = games.get(&id).ok_or_else(|| GameError::GameNotFound(id.to_string()))?;
+
+        // Verify the caller holds the player's seat
+        if let Some(tokens) = &record.seat_tokens {
+            tokens.verify(player, token)?;
+        }
+
+        let message = ChatMessage::new(player, text);
+        let history = {
+            let mut histories = self.histories.write().await;
+            let history = histories.entry(id).or_insert_with(|| record.game.create_history());
+            history.chat.push(message.clone());
+            history.clone()
+        };
+        drop(games);
+
+        self.store.save_history(&history).await?;
+        self.broadcast(id, GameUpdate::Chat(message.clone())).await;
+        Ok(message)
+    }
+
+    /// Subscribe to updates for a game
+    pub async fn subscribe(&self, id: Uuid) -> broadcast::Receiver<GameUpdate> {
+        self.updates
+            .write()
+            .await
+            .entry(id)
+            .or_insert_with(|| broadcast::channel(UPDATE_CHANNEL_CAPACITY).0)
+            .subscribe()
+    }
+
+    /// Broadcast a game's new state to all of its subscribers
+    pub async fn publish(&self, record: &GameRecord) {
+        self.broadcast(record.game.id, GameUpdate::State(Box::new(record.public()))).await;
+    }
+
+    /// Send an update to all of game `id`'s subscribers
+    async fn broadcast(&self, id: Uuid, update: GameUpdate) {
+        if let Some(sender) = self.updates.read().await.get(&id) {
+            // Sending only fails when nobody is subscribed, which is fine
+            let _ = sender.send(update);
         }
     }
 }
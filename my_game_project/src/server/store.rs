@@ -0,0 +1,479 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use sqlx::Row;
+use tokio::sync::{mpsc, RwLock};
+use tracing::instrument;
+use uuid::Uuid;
+
+use crate::error::{GameError, GameResult};
+use crate::server::auth::User;
+use crate::server::state::GameRecord;
+use crate::history::GameHistory;
+use crate::rating::PlayerRating;
+
+/// A change that another server made to a store they share
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum StoreChange {
+    /// A game was created or changed
+    Game(Box<GameRecord>),
+    /// A game's history changed, such as when a move was made or a chat message sent
+    History(Box<GameHistory>),
+    /// A player's rating changed
+    Rating(PlayerRating),
+    /// A game was deleted or archived
+    Removed(Uuid),
+}
+
+/// Persistent storage for games, their histories, player ratings and users
+///
+/// The server keeps its working set of games in memory and writes every
+/// change through to the store, so the store only needs to support simple
+/// whole-record reads and writes.
+///
+/// Stores that several servers share must refuse to save a game over a copy
+/// with the same or a later [`GameRecord::revision`], failing with
+/// [`GameError::ConcurrentUpdate`], and report the changes each server makes
+/// to the others through [`GameStore::changes`].
+#[async_trait]
+pub trait GameStore: Debug + Send + Sync {
+    /// Insert or replace a game
+    async fn save_game(&self, record: &GameRecord) -> GameResult<()>;
+
+    /// Load a game by ID
+    async fn load_game(&self, id: Uuid) -> GameResult<Option<GameRecord>>;
+
+    /// Load every stored game
+    async fn load_all_games(&self) -> GameResult<Vec<GameRecord>>;
+
+    /// Delete a game and its history
+    async fn delete_game(&self, id: Uuid) -> GameResult<()>;
+
+    /// Move a game and its history out of the live games into the archive
+    ///
+    /// Archived games aren't loaded when the server starts.
+    async fn archive_game(&self, id: Uuid) -> GameResult<()>;
+
+    /// Load an archived game by ID
+    async fn load_archived_game(&self, id: Uuid) -> GameResult<Option<GameRecord>>;
+
+    /// Insert or replace a game's history
+    async fn save_history(&self, history: &GameHistory) -> GameResult<()>;
+
+    /// Load the history of a game
+    async fn load_history(&self, game_id: Uuid) -> GameResult<Option<GameHistory>>;
+
+    /// Insert or replace a player's rating
+    async fn save_rating(&self, rating: &PlayerRating) -> GameResult<()>;
+
+    /// Load every stored player rating
+    async fn load_all_ratings(&self) -> GameResult<Vec<PlayerRating>>;
+
+    /// Register a user, failing if the username is taken
+    async fn create_user(&self, user: &User) -> GameResult<()>;
+
+    /// Load a user by username
+    async fn load_user(&self, username: &str) -> GameResult<Option<User>>;
+
+    /// Start following the changes other servers make to the store
+    ///
+    /// Returns `None` for stores that only one server uses.
+    async fn changes(&self) -> GameResult<Option<mpsc::Receiver<StoreChange>>> {
+        Ok(None)
+    }
+}
+
+/// A store that keeps everything in memory and is lost when the server stops
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    /// Map of game ID to game record
+    games: RwLock<HashMap<Uuid, GameRecord>>,
+    /// Map of game ID to game history
+    histories: RwLock<HashMap<Uuid, GameHistory>>,
+    /// Map of game ID to archived game record and history
+    archived: RwLock<HashMap<Uuid, (GameRecord, Option<GameHistory>)>>,
+    /// Map of player ID to rating
+    ratings: RwLock<HashMap<String, PlayerRating>>,
+    /// Map of username to user
+    users: RwLock<HashMap<String, User>>,
+}
+
+impl MemoryStore {
+    /// Create an empty in-memory store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl GameStore for MemoryStore {
+    #[instrument(level = "debug", skip_all, fields(game_id = %record.game.id))]
+    async fn save_game(&self, record: &GameRecord) -> GameResult<()> {
+        self.games.write().await.insert(record.game.id, record.clone());
+        Ok(())
+    }
+
+    async fn load_game(&self, id: Uuid) -> GameResult<Option<GameRecord>> {
+        Ok(self.games.read().await.get(&id).cloned())
+    }
+
+    async fn load_all_games(&self) -> GameResult<Vec<GameRecord>> {
+        Ok(self.games.read().await.values().cloned().collect())
+    }
+
+    #[instrument(level = "debug", skip_all, fields(game_id = %id))]
+    async fn delete_game(&self, id: Uuid) -> GameResult<()> {
+        self.games.write().await.remove(&id);
+        self.histories.write().await.remove(&id);
+        Ok(())
+    }
+
+    async fn archive_game(&self, id: Uuid) -> GameResult<()> {
+        if let Some(record) = self.games.write().await.remove(&id) {
+            let history = self.histories.write().await.remove(&id);
+            self.archived.write().await.insert(id, (record, history));
+        }
+        Ok(())
+    }
+
+    async fn load_archived_game(&self, id: Uuid) -> GameResult<Option<GameRecord>> {
+        Ok(self.archived.read().await.get(&id).map(|(record, _)| record.clone()))
+    }
+
+    #[instrument(level = "debug", skip_all, fields(game_id = %history.game_id))]
+    async fn save_history(&self, history: &GameHistory) -> GameResult<()> {
+        self.histories.write().await.insert(history.game_id, history.clone());
+        Ok(())
+    }
+
+    async fn load_history(&self, game_id: Uuid) -> GameResult<Option<GameHistory>> {
+        Ok(self.histories.read().await.get(&game_id).cloned())
+    }
+
+    async fn save_rating(&self, rating: &PlayerRating) -> GameResult<()> {
+        self.ratings.write().await.insert(rating.player_id.clone(), rating.clone());
+        Ok(())
+    }
+
+    async fn load_all_ratings(&self) -> GameResult<Vec<PlayerRating>> {
+        Ok(self.ratings.read().await.values().cloned().collect())
+    }
+
+    async fn create_user(&self, user: &User) -> GameResult<()> {
+        let mut users = self.users.write().await;
+        if users.contains_key(&user.username) {
+            return Err(GameError::UsernameTaken(user.username.clone()));
+        }
+        users.insert(user.username.clone(), user.clone());
+        Ok(())
+    }
+
+    async fn load_user(&self, username: &str) -> GameResult<Option<User>> {
+        Ok(self.users.read().await.get(username).cloned())
+    }
+}
+
+/// A store backed by a SQLite database
+///
+/// Games and histories are stored as JSON documents keyed by game ID,
+/// ratings as JSON documents keyed by player ID, and users as JSON documents
+/// keyed by username.
+#[derive(Debug, Clone)]
+pub struct SqliteStore {
+    /// The connection pool
+    pool: SqlitePool,
+}
+
+impl SqliteStore {
+    /// The connection pool, for stores that keep more tables in the same database
+    pub(crate) fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+
+    /// Open (creating if necessary) the database at `url` and set up its tables
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # async fn example() -> my_game_project::error::GameResult<()> {
+    /// use my_game_project::server::SqliteStore;
+    ///
+    /// let store = SqliteStore::connect("sqlite://games.db").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn connect(url: &str) -> GameResult<Self> {
+        let options = url
+            .parse::<SqliteConnectOptions>()
+            .map_err(storage_error)?
+            .create_if_missing(true);
+
+        let pool = SqlitePoolOptions::new()
+            .connect_with(options)
+            .await
+            .map_err(storage_error)?;
+
+        sqlx::query("CREATE TABLE IF NOT EXISTS games (id TEXT PRIMARY KEY, state TEXT NOT NULL)")
+            .execute(&pool)
+            .await
+            .map_err(storage_error)?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS histories (game_id TEXT PRIMARY KEY, history TEXT NOT NULL)",
+        )
+        .execute(&pool)
+        .await
+        .map_err(storage_error)?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS ratings (player_id TEXT PRIMARY KEY, rating TEXT NOT NULL)",
+        )
+        .execute(&pool)
+        .await
+        .map_err(storage_error)?;
+
+        sqlx::query("CREATE TABLE IF NOT EXISTS users (username TEXT PRIMARY KEY, user TEXT NOT NULL)")
+            .execute(&pool)
+            .await
+            .map_err(storage_error)?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS archived_games \
+             (id TEXT PRIMARY KEY, state TEXT NOT NULL, history TEXT)",
+        )
+        .execute(&pool)
+        .await
+        .map_err(storage_error)?;
+
+        Ok(Self { pool })
+    }
+}
+
+#[async_trait]
+impl GameStore for SqliteStore {
+    #[instrument(level = "debug", skip_all, fields(game_id = %record.game.id))]
+    async fn save_game(&self, record: &GameRecord) -> GameResult<()> {
+        let json = serde_json::to_string(record)?;
+
+        sqlx::query("INSERT OR REPLACE INTO games (id, state) VALUES (?, ?)")
+            .bind(record.game.id.to_string())
+            .bind(json)
+            .execute(&self.pool)
+            .await
+            .map_err(storage_error)?;
+
+        Ok(())
+    }
+
+    async fn load_game(&self, id: Uuid) -> GameResult<Option<GameRecord>> {
+        let row = sqlx::query("SELECT state FROM games WHERE id = ?")
+            .bind(id.to_string())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(storage_error)?;
+
+        row.map(|row| from_json(row.get("state"))).transpose()
+    }
+
+    async fn load_all_games(&self) -> GameResult<Vec<GameRecord>> {
+        let rows = sqlx::query("SELECT state FROM games")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(storage_error)?;
+
+        rows.iter().map(|row| from_json(row.get("state"))).collect()
+    }
+
+    #[instrument(level = "debug", skip_all, fields(game_id = %id))]
+    async fn delete_game(&self, id: Uuid) -> GameResult<()> {
+        let mut transaction = self.pool.begin().await.map_err(storage_error)?;
+
+        sqlx::query("DELETE FROM games WHERE id = ?")
+            .bind(id.to_string())
+            .execute(&mut *transaction)
+            .await
+            .map_err(storage_error)?;
+
+        sqlx::query("DELETE FROM histories WHERE game_id = ?")
+            .bind(id.to_string())
+            .execute(&mut *transaction)
+            .await
+            .map_err(storage_error)?;
+
+        transaction.commit().await.map_err(storage_error)
+    }
+
+    async fn archive_game(&self, id: Uuid) -> GameResult<()> {
+        let mut transaction = self.pool.begin().await.map_err(storage_error)?;
+
+        sqlx::query(
+            "INSERT OR REPLACE INTO archived_games (id, state, history) \
+             SELECT games.id, games.state, histories.history FROM games \
+             LEFT JOIN histories ON histories.game_id = games.id WHERE games.id = ?",
+        )
+        .bind(id.to_string())
+        .execute(&mut *transaction)
+        .await
+        .map_err(storage_error)?;
+
+        for statement in ["DELETE FROM games WHERE id = ?", "DELETE FROM histories WHERE game_id = ?"] {
+            sqlx::query(statement)
+                .bind(id.to_string())
+                .execute(&mut *transaction)
+                .await
+                .map_err(storage_error)?;
+        }
+
+        transaction.commit().await.map_err(storage_error)
+    }
+
+    async fn load_archived_game(&self, id: Uuid) -> GameResult<Option<GameRecord>> {
+        let row = sqlx::query("SELECT state FROM archived_games WHERE id = ?")
+            .bind(id.to_string())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(storage_error)?;
+
+        row.map(|row| from_json(row.get("state"))).transpose()
+    }
+
+    #[instrument(level = "debug", skip_all, fields(game_id = %history.game_id))]
+    async fn save_history(&self, history: &GameHistory) -> GameResult<()> {
+        let json = serde_json::to_string(history)?;
+
+        sqlx::query("INSERT OR REPLACE INTO histories (game_id, history) VALUES (?, ?)")
+            .bind(history.game_id.to_string())
+            .bind(json)
+            .execute(&self.pool)
+            .await
+            .map_err(storage_error)?;
+
+        Ok(())
+    }
+
+    async fn load_history(&self, game_id: Uuid) -> GameResult<Option<GameHistory>> {
+        let row = sqlx::query("SELECT history FROM histories WHERE game_id = ?")
+            .bind(game_id.to_string())
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(storage_error)?;
+
+        row.map(|row| from_json(row.get("history"))).transpose()
+    }
+
+    async fn save_rating(&self, rating: &PlayerRating) -> GameResult<()> {
+        let json = serde_json::to_string(rating)?;
+
+        sqlx::query("INSERT OR REPLACE INTO ratings (player_id, rating) VALUES (?, ?)")
+            .bind(&rating.player_id)
+            .bind(json)
+            .execute(&self.pool)
+            .await
+            .map_err(storage_error)?;
+
+        Ok(())
+    }
+
+    async fn load_all_ratings(&self) -> GameResult<Vec<PlayerRating>> {
+        let rows = sqlx::query("SELECT rating FROM ratings")
+            .fetch_all(&self.pool)
+            .await
+            .map_err(storage_error)?;
+
+        rows.iter().map(|row| from_json(row.get("rating"))).collect()
+    }
+
+    async fn create_user(&self, user: &User) -> GameResult<()> {
+        let json = serde_json::to_string(user)?;
+
+        let result = sqlx::query("INSERT INTO users (username, user) VALUES (?, ?)")
+            .bind(&user.username)
+            .bind(json)
+            .execute(&self.pool)
+            .await;
+
+        match result {
+            Ok(_) => Ok(()),
+            Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+                Err(GameError::UsernameTaken(user.username.clone()))
+            }
+            Err(e) => Err(storage_error(e)),
+        }
+    }
+
+    async fn load_user(&self, username: &str) -> GameResult<Option<User>> {
+        let row = sqlx::query("SELECT user FROM users WHERE username = ?")
+            .bind(username)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(storage_error)?;
+
+        row.map(|row| from_json(row.get("user"))).transpose()
+    }
+}
+
+/// Convert a database error into a `GameError`
+pub(crate) fn storage_error(error: sqlx::Error) -> GameError {
+    GameError::StorageError(error.to_string())
+}
+
+/// Deserialize a JSON document read from the database
+pub(crate) fn from_json<T: serde::de::DeserializeOwned>(json: &str) -> GameResult<T> {
+    serde_json::from_str(json).map_err(GameError::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+
+    #[tokio::test]
+    async fn test_sqlite_round_trip() {
+        let path = std::env::temp_dir().join(format!("games_{}.db", Uuid::new_v4()));
+        let store = SqliteStore::connect(&format!("sqlite://{}", path.display())).await.unwrap();
+
+        let mut game = GameState::new();
+        game.make_move((1, 1)).unwrap();
+        store.save_game(&GameRecord::new(game.clone())).await.unwrap();
+
+        let mut history = game.create_history();
+        history.add_move(game.current_turn.opponent(), (1, 1));
+        store.save_history(&history).await.unwrap();
+
+        let loaded = store.load_game(game.id).await.unwrap().unwrap();
+        assert_eq!(loaded.game.board, game.board);
+        assert_eq!(store.load_all_games().await.unwrap().len(), 1);
+        assert_eq!(store.load_history(game.id).await.unwrap().unwrap().moves.len(), 1);
+        assert!(store.load_game(Uuid::new_v4()).await.unwrap().is_none());
+
+        store.archive_game(game.id).await.unwrap();
+        assert!(store.load_game(game.id).await.unwrap().is_none());
+        assert!(store.load_history(game.id).await.unwrap().is_none());
+        assert_eq!(store.load_archived_game(game.id).await.unwrap().unwrap().game.board, game.board);
+
+        store.save_game(&GameRecord::new(game.clone())).await.unwrap();
+        store.delete_game(game.id).await.unwrap();
+        assert!(store.load_game(game.id).await.unwrap().is_none());
+        assert!(store.load_history(game.id).await.unwrap().is_none());
+
+        store.save_rating(&PlayerRating::new("alice")).await.unwrap();
+        store.save_rating(&PlayerRating::new("alice")).await.unwrap();
+        assert_eq!(store.load_all_ratings().await.unwrap(), vec![PlayerRating::new("alice")]);
+
+        let user = User {
+            id: Uuid::new_v4(),
+            username: "alice".to_string(),
+            password_hash: "hash".to_string(),
+            created_at: chrono::Utc::now(),
+        };
+        store.create_user(&user).await.unwrap();
+        assert!(matches!(store.create_user(&user).await, Err(GameError::UsernameTaken(_))));
+        assert_eq!(store.load_user("alice").await.unwrap(), Some(user));
+        assert!(store.load_user("bob").await.unwrap().is_none());
+
+        let _ = std::fs::remove_file(path);
+    }
+}
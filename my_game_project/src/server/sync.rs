@@ -0,0 +1,57 @@
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+use crate::error::GameResult;
+use crate::server::state::AppState;
+
+/// Spawn a background task that applies the changes other servers make to a shared store
+///
+/// Returns `None` if the store isn't shared, as there is nothing to follow.
+pub async fn spawn_store_sync(state: AppState) -> GameResult<Option<JoinHandle<()>>> {
+    let Some(mut changes) = state.store.changes().await? else {
+        return Ok(None);
+    };
+
+    Ok(Some(tokio::spawn(async move {
+        while let Some(change) = changes.recv().await {
+            state.apply_store_change(change).await;
+        }
+        warn!("Stopped following changes made by other servers");
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::GameState;
+    use crate::history::ChatMessage;
+    use crate::player::Player;
+    use crate::server::state::{AppState, GameRecord, GameUpdate};
+    use crate::server::store::StoreChange;
+
+    #[tokio::test]
+    async fn test_apply_store_change() {
+        let state = AppState::new();
+        let record = state.insert_game(GameRecord::new(GameState::new())).await.unwrap();
+        let id = record.game.id;
+        let mut updates = state.subscribe(id).await;
+
+        // A move made on another server reaches this server's subscribers
+        let mut moved = record.clone();
+        moved.game.make_move((1, 1)).unwrap();
+        moved.touch(None);
+        state.apply_store_change(StoreChange::Game(Box::new(moved))).await;
+        assert!(matches!(updates.recv().await, Ok(GameUpdate::State(update)) if update.game.version == 1));
+
+        // A stale copy doesn't undo it
+        state.apply_store_change(StoreChange::Game(Box::new(record))).await;
+        assert_eq!(state.games.read().await[&id].game.version, 1);
+
+        let mut history = state.histories.read().await[&id].clone();
+        history.chat.push(ChatMessage::new(Player::O, "hi"));
+        state.apply_store_change(StoreChange::History(Box::new(history))).await;
+        assert!(matches!(updates.recv().await, Ok(GameUpdate::Chat(message)) if message.text == "hi"));
+
+        state.apply_store_change(StoreChange::Removed(id)).await;
+        assert!(!state.games.read().await.contains_key(&id));
+    }
+}
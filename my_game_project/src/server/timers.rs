@@ -0,0 +1,31 @@
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+use crate::server::state::AppState;
+
+/// How often the turn timer checks for players who have run out of time
+pub const DEFAULT_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Spawn a background task that forfeits games whose current player has run out of time
+///
+/// Only games created with a move time limit are affected.
+pub fn spawn_turn_timer(state: AppState, interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+
+            match state.forfeit_timed_out_games().await {
+                Ok(ids) => {
+                    for id in ids {
+                        info!("Game {} forfeited on time", id);
+                    }
+                }
+                Err(e) => error!("Failed to forfeit timed out games: {}", e),
+            }
+        }
+    })
+}
@@ -0,0 +1,66 @@
+use std::time::Instant;
+
+use axum::{
+    http::{HeaderValue, Request},
+    middleware::Next,
+    response::Response,
+};
+use tracing::{info, info_span, Instrument};
+use uuid::Uuid;
+
+/// Header carrying the ID that ties together everything logged for a request
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest request ID taken from a client; longer ones are replaced
+const MAX_REQUEST_ID_LENGTH: usize = 128;
+
+/// The request ID the client sent, or a new one if it didn't send a usable one
+fn request_id<B>(request: &Request<B>) -> String {
+    request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| !id.is_empty() && id.len() <= MAX_REQUEST_ID_LENGTH)
+        .map_or_else(|| Uuid::new_v4().to_string(), str::to_string)
+}
+
+/// Middleware running each request in a span labelled with its request ID
+///
+/// Everything logged while handling the request, down to the AI search and
+/// storage, is logged in that span. The ID is echoed in the response's
+/// `X-Request-Id` header, so a client can quote it when reporting a problem.
+pub async fn trace_requests<B>(request: Request<B>, next: Next<B>) -> Response {
+    let id = request_id(&request);
+    let span = info_span!(
+        "request",
+        request_id = %id,
+        method = %request.method(),
+        path = %request.uri().path(),
+    );
+
+    let start = Instant::now();
+    let mut response = next.run(request).instrument(span.clone()).await;
+    span.in_scope(|| {
+        info!(status = response.status().as_u16(), elapsed = ?start.elapsed(), "finished");
+    });
+
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_id() {
+        let request = |id: &str| Request::get("/").header(REQUEST_ID_HEADER, id).body(()).unwrap();
+
+        assert_eq!(request_id(&request("abc-123")), "abc-123");
+        let generated = request_id(&request(""));
+        assert!(Uuid::parse_str(&generated).is_ok());
+        assert_ne!(request_id(&request(&"x".repeat(200))).len(), 200);
+    }
+}
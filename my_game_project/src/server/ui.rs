@@ -0,0 +1,19 @@
+use axum::{response::Html, routing::get, Router};
+
+use crate::server::state::AppState;
+
+/// The built-in board page, which plays through the REST API and its events
+const PAGE: &str = include_str!("ui.html");
+
+/// Routes serving the built-in web UI at `/`
+///
+/// Merged into the router unless the server is configured without it.
+pub fn web_ui() -> Router<AppState> {
+    Router::new().route("/", get(index))
+}
+
+/// Serve the board page
+pub async fn index() -> Html<&'static str> {
+    Html(PAGE)
+}
+
@@ -0,0 +1,362 @@
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use reqwest::{redirect, Client, Url};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::error::{GameError, GameResult};
+use crate::game::GameStatus;
+use crate::server::state::{AppState, GameRecord, GameUpdate};
+
+/// Number of times an event is sent before giving up on it
+pub const MAX_DELIVERY_ATTEMPTS: u32 = 4;
+
+/// How long to wait before the first retry, doubling for every retry after it
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// How long the receiver has to answer each attempt
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Header carrying the `sha256=`-prefixed HMAC of the request body
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+
+/// Header naming the event being delivered
+pub const EVENT_HEADER: &str = "X-Webhook-Event";
+
+/// A URL that is sent the events of a game
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Webhook {
+    /// Where events are POSTed
+    pub url: String,
+    /// The key every delivery is signed with
+    pub secret: String,
+}
+
+impl Webhook {
+    /// Send events to `url`, signed with a fresh random secret
+    ///
+    /// Only `http` and `https` URLs are accepted, and not ones naming a
+    /// loopback, link-local or private address. Host names are checked the
+    /// same way each time they are resolved for a delivery.
+    pub fn new(url: &str) -> GameResult<Self> {
+        let invalid = |reason: String| GameError::InvalidWebhook(format!("{}: {}", url, reason));
+        let parsed = Url::parse(url).map_err(|e| invalid(e.to_string()))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(invalid("must be an http or https URL".to_string()));
+        }
+        let host = parsed.host_str().unwrap_or_default();
+        let internal = match host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() {
+            Ok(ip) => !is_public(ip),
+            Err(_) => host.is_empty() || host == "localhost" || host.ends_with(".localhost"),
+        };
+        if internal {
+            return Err(invalid("must point outside the server's network".to_string()));
+        }
+
+        Ok(Self {
+            url: url.to_string(),
+            secret: Uuid::new_v4().simple().to_string(),
+        })
+    }
+
+    /// The value of the signature header for a request with `body`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::server::Webhook;
+    ///
+    /// let webhook = Webhook { url: "http://localhost".to_string(), secret: "Jefe".to_string() };
+    /// assert_eq!(
+    ///     webhook.sign(b"what do ya want for nothing?"),
+    ///     "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+    /// );
+    /// ```
+    pub fn sign(&self, body: &[u8]) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.secret.as_bytes())
+            .expect("HMAC takes keys of any length");
+        mac.update(body);
+
+        let digest = mac.finalize().into_bytes();
+        let hex: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+        format!("sha256={}", hex)
+    }
+}
+
+/// Returns true if `ip` is reachable on the public internet, rather than
+/// being a loopback, link-local, private or otherwise reserved address
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            // 100.64.0.0/10 is shared by carrier-grade NATs
+            let shared = a == 100 && (b & 0xc0) == 64;
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || shared)
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(mapped) => is_public(mapped.into()),
+            None => {
+                let first = ip.segments()[0];
+                // fc00::/7 is unique local, and fe80::/10 link-local
+                let unique_local = (first & 0xfe00) == 0xfc00;
+                let link_local = (first & 0xffc0) == 0xfe80;
+                !(ip.is_loopback() || ip.is_unspecified() || ip.is_multicast() || unique_local || link_local)
+            }
+        },
+    }
+}
+
+/// Resolves webhook hosts to their public addresses only, so a host name
+/// can't be pointed at the server's own network after the webhook is set
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|addr| is_public(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("{} has no public address", name.as_str()).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Something that happened in a game that its webhook is told about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEventKind {
+    /// A move was made
+    MoveMade,
+    /// The player to move ran out of time; sent just before `GameFinished`
+    Timeout,
+    /// The game ended, however it ended
+    GameFinished,
+}
+
+impl WebhookEventKind {
+    /// The name of the event, as sent in the event header
+    fn name(&self) -> &'static str {
+        match self {
+            WebhookEventKind::MoveMade => "move_made",
+            WebhookEventKind::Timeout => "timeout",
+            WebhookEventKind::GameFinished => "game_finished",
+        }
+    }
+}
+
+/// The body of a webhook delivery
+#[derive(Debug, Serialize)]
+pub struct WebhookEvent<'a> {
+    /// What happened
+    pub event: WebhookEventKind,
+    /// Unique identifier for the delivery, the same for every attempt
+    pub delivery_id: Uuid,
+    /// When the event happened
+    pub sent_at: DateTime<Utc>,
+    /// The game after the event
+    pub game: &'a GameRecord,
+}
+
+/// The events describing the change from `last` to `record`
+fn events_between(last: &GameRecord, record: &GameRecord) -> Vec<WebhookEventKind> {
+    let mut events = Vec::new();
+    if record.game.board != last.game.board {
+        events.push(WebhookEventKind::MoveMade);
+    }
+    if record.game.is_over() && !last.game.is_over() {
+        if matches!(record.game.status, GameStatus::TimedOut(_)) {
+            events.push(WebhookEventKind::Timeout);
+        }
+        events.push(WebhookEventKind::GameFinished);
+    }
+    events
+}
+
+/// Start sending the events of `record`'s game to its webhook, if it has one
+///
+/// Events are sent in order from a background task, which stops once the
+/// game is over or removed. Changes are reported relative to `record`.
+pub(crate) async fn watch(state: &AppState, record: &GameRecord) {
+    let Some(webhook) = record.webhook.clone() else {
+        return;
+    };
+    if record.game.is_over() {
+        return;
+    }
+
+    // Subscribe before returning so the caller's next update isn't missed
+    let mut updates = state.subscribe(record.game.id).await;
+    let mut last = record.public();
+
+    tokio::spawn(async move {
+        loop {
+            let record = match updates.recv().await {
+                Ok(GameUpdate::State(record)) => *record,
+                Ok(GameUpdate::Chat(_) | GameUpdate::Event(_)) => continue,
+                // Report the latest state if we fell behind
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => return,
+            };
+
+            for kind in events_between(&last, &record) {
+                deliver(&webhook, kind, &record).await;
+            }
+            if record.game.is_over() {
+                return;
+            }
+            last = record;
+        }
+    });
+}
+
+/// POST an event to the webhook, retrying with backoff until it is accepted
+async fn deliver(webhook: &Webhook, kind: WebhookEventKind, record: &GameRecord) {
+    static CLIENT: OnceLock<Client> = OnceLock::new();
+    let client = CLIENT.get_or_init(|| {
+        // Redirects aren't followed, as they could lead anywhere
+        Client::builder()
+            .timeout(DELIVERY_TIMEOUT)
+            .redirect(redirect::Policy::none())
+            .dns_resolver(Arc::new(PublicResolver))
+            .build()
+            .unwrap_or_default()
+    });
+
+    let event = WebhookEvent {
+        event: kind,
+        delivery_id: Uuid::new_v4(),
+        sent_at: Utc::now(),
+        game: record,
+    };
+    let body = serde_json::to_vec(&event).unwrap_or_default();
+    let signature = webhook.sign(&body);
+
+    let mut delay = RETRY_DELAY;
+    for attempt in 1..=MAX_DELIVERY_ATTEMPTS {
+        let result = client
+            .post(&webhook.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, kind.name())
+            .header(SIGNATURE_HEADER, &signature)
+            .body(body.clone())
+            .send()
+            .await;
+
+        let error = match result {
+            Ok(response) if response.status().is_success() => return,
+            Ok(response) => format!("status {}", response.status()),
+            Err(e) => e.to_string(),
+        };
+        warn!(
+            "Webhook {} for game {} failed (attempt {} of {}): {}",
+            kind.name(),
+            record.game.id,
+            attempt,
+            MAX_DELIVERY_ATTEMPTS,
+            error
+        );
+
+        if attempt < MAX_DELIVERY_ATTEMPTS {
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+    use crate::player::Player;
+    use axum::{extract::State, http::HeaderMap, http::StatusCode, routing::post, Router};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::sync::mpsc;
+
+    /// A webhook receiver that refuses every other attempt and passes on the rest
+    #[derive(Clone)]
+    struct Receiver {
+        deliveries: mpsc::UnboundedSender<(String, String)>,
+        attempts: Arc<AtomicUsize>,
+    }
+
+    async fn receive(State(receiver): State<Receiver>, headers: HeaderMap, body: String) -> StatusCode {
+        if receiver.attempts.fetch_add(1, Ordering::SeqCst) % 2 == 0 {
+            return StatusCode::SERVICE_UNAVAILABLE;
+        }
+        let signature = headers[SIGNATURE_HEADER].to_str().unwrap().to_string();
+        receiver.deliveries.send((signature, body)).unwrap();
+        StatusCode::OK
+    }
+
+    #[tokio::test]
+    async fn test_deliveries_are_signed_and_retried() {
+        let (deliveries, mut received) = mpsc::unbounded_channel();
+        let receiver = Receiver { deliveries, attempts: Arc::default() };
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let app = Router::new().route("/", post(receive)).with_state(receiver.clone());
+        tokio::spawn(axum::Server::from_tcp(listener).unwrap().serve(app.into_make_service()));
+
+        let state = AppState::new();
+        let mut record = GameRecord::new(GameState::new());
+        // Local receivers are refused by `Webhook::new`, so set this one up by hand
+        let webhook = Webhook { url, secret: Uuid::new_v4().simple().to_string() };
+        record.webhook = Some(webhook.clone());
+        let id = state.insert_game(record).await.unwrap().game.id;
+        state.play_move(id, Player::X, None, (1, 1).into(), None).await.unwrap();
+        state.act(id, Player::O, None, |game| game.resign(Player::O)).await.unwrap();
+
+        let mut events = Vec::new();
+        for _ in 0..2 {
+            let (signature, body) = received.recv().await.unwrap();
+            assert_eq!(signature, webhook.sign(body.as_bytes()));
+            let event: serde_json::Value = serde_json::from_str(&body).unwrap();
+            assert!(event["game"].get("webhook").is_none());
+            events.push(event["event"].as_str().unwrap().to_string());
+        }
+        assert_eq!(events, ["move_made", "game_finished"]);
+        assert_eq!(receiver.attempts.load(Ordering::SeqCst), 4);
+
+        assert!(Webhook::new("ftp://example.com").is_err());
+    }
+
+    #[test]
+    fn test_internal_addresses_are_refused() {
+        for url in [
+            "http://127.0.0.1/",
+            "http://localhost:8080/",
+            "http://10.0.0.5/",
+            "http://192.168.1.1/",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]/",
+            "http://[fd00::1]/",
+            "http://[::ffff:127.0.0.1]/",
+        ] {
+            assert!(Webhook::new(url).is_err(), "{} was accepted", url);
+        }
+        assert!(Webhook::new("https://example.com/hooks").is_ok());
+        assert!(Webhook::new("http://93.184.216.34/").is_ok());
+    }
+}
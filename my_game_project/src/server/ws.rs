@@ -0,0 +1,127 @@
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, State,
+    },
+    http::StatusCode,
+    response::Response,
+};
+use serde::Serialize;
+use tokio::sync::broadcast::error::RecvError;
+use uuid::Uuid;
+
+use crate::error::{GameError, GameResult};
+use crate::server::error::ApiError;
+use crate::server::handlers::MakeMoveRequest;
+use crate::server::spectate::SpectatorGuard;
+use crate::history::ChatMessage;
+use crate::server::state::{AppState, GameRecord, GameUpdate};
+
+/// Upgrade to a WebSocket that streams a game's state and accepts moves
+///
+/// The current state is sent as soon as the socket opens, followed by the
+/// new state after every move. Each state is preceded by the
+/// [`GameEvent`](crate::events::GameEvent)s that led to it, told apart by
+/// their `event` field. Chat messages are sent as `{"chat": message}`.
+/// Clients make moves by sending a JSON `MakeMoveRequest`; rejected moves
+/// are answered with an `ApiError` body.
+pub async fn game_socket(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, ApiError> {
+    if !state.games.read().await.contains_key(&id) {
+        return Err(GameError::GameNotFound(id.to_string()).into());
+    }
+
+    Ok(ws.on_upgrade(move |socket| handle_socket(socket, state, id, None)))
+}
+
+/// Relay updates to the socket and apply moves received from it
+///
+/// A spectator's socket is read-only: every message it sends is refused.
+pub(crate) async fn handle_socket(
+    mut socket: WebSocket,
+    state: AppState,
+    id: Uuid,
+    spectator: Option<SpectatorGuard>,
+) {
+    let mut updates = state.subscribe(id).await;
+
+    // Send the current state so the client doesn't have to fetch it first
+    let current = state.games.read().await.get(&id).map(GameRecord::public);
+    match current {
+        Some(record) => {
+            if send_json(&mut socket, &record).await.is_err() {
+                return;
+            }
+        }
+        None => return,
+    }
+
+    loop {
+        tokio::select! {
+            update = updates.recv() => {
+                let sent = match update {
+                    Ok(GameUpdate::State(record)) => send_json(&mut socket, &record).await,
+                    Ok(GameUpdate::Event(event)) => send_json(&mut socket, &event).await,
+                    Ok(GameUpdate::Chat(message)) => {
+                        send_json(&mut socket, &ChatEvent { chat: &message }).await
+                    }
+                    // Skip straight to the latest state if we fell behind
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                };
+
+                if sent.is_err() {
+                    break;
+                }
+            }
+            message = socket.recv() => {
+                let text = match message {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                };
+
+                let result = match spectator {
+                    Some(_) => Err(spectator_error()),
+                    None => apply_move(&state, id, &text).await.map_err(ApiError::from),
+                };
+                if let Err(error) = result {
+                    if send_json(&mut socket, &error).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A chat message as sent over the socket, told apart from game states by its `chat` field
+#[derive(Serialize)]
+struct ChatEvent<'a> {
+    /// The message
+    chat: &'a ChatMessage,
+}
+
+/// Apply a move received over the socket and broadcast the result
+async fn apply_move(state: &AppState, id: Uuid, text: &str) -> GameResult<()> {
+    let request: MakeMoveRequest = serde_json::from_str(text)?;
+
+    state
+        .play_move(id, request.player, request.token.as_deref(), request.position, request.expected_version)
+        .await?;
+    Ok(())
+}
+
+/// Error sent in reply to anything a spectator sends
+fn spectator_error() -> ApiError {
+    ApiError::new(StatusCode::FORBIDDEN, "SPECTATOR", "Spectators can't make moves")
+}
+
+/// Serialize a value and send it as a text message
+async fn send_json<T: Serialize>(socket: &mut WebSocket, value: &T) -> Result<(), axum::Error> {
+    let json = serde_json::to_string(value).unwrap_or_default();
+    socket.send(Message::Text(json)).await
+}
@@ -1,36 +1,102 @@
-use std::net::SocketAddr;
-
 use axum::Server;
-use tracing::info;
-use tracing_subscriber::FmtSubscriber;
+use tracing::{error, info, warn};
+use tracing_subscriber::EnvFilter;
 
-use my_game_project::server::{create_router, AppState};
+use my_game_project::server::{
+    create_router_with_cors, spawn_game_collector, spawn_store_sync, spawn_turn_timer, web_ui, AppState,
+    ServerConfig, StorageBackend,
+};
 
 #[tokio::main]
 async fn main() {
-    // Initialize tracing
-    tracing_subscriber::fmt()
-        .with_max_level(tracing::Level::INFO)
-        .init();
+    // Initialize tracing, at the levels in RUST_LOG if it is set
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    tracing_subscriber::fmt().with_env_filter(filter).init();
+
+    // Read the settings from server.toml (or SERVER_CONFIG) and the environment
+    let config = ServerConfig::from_env().unwrap_or_else(|e| {
+        error!("{}", e);
+        std::process::exit(1);
+    });
+
+    // Open the storage backend
+    match config.storage.backend {
+        StorageBackend::Sqlite => info!("Using SQLite storage at {}", config.storage.database_url),
+        StorageBackend::EventLog => info!("Using event log storage at {}", config.storage.database_url),
+        StorageBackend::Redis => info!("Using Redis storage at {}", config.storage.redis_url),
+        StorageBackend::Memory => {}
+    }
+    let store = config.storage.open().await.unwrap_or_else(|e| {
+        error!("Failed to open {:?} storage: {}", config.storage.backend, e);
+        std::process::exit(1);
+    });
+
+    // Open the archive of finished games
+    let archive = config.storage.open_archive().await.unwrap_or_else(|e| {
+        error!("Failed to open the game archive: {}", e);
+        std::process::exit(1);
+    });
 
     // Create the application state
-    let state = AppState::new();
+    let mut state = AppState::with_store(store)
+        .await
+        .expect("Failed to load games from storage")
+        .with_expiry(config.timeouts.expiry_policy())
+        .await
+        .with_ai_difficulty(config.ai.difficulty)
+        .with_ai_timeout(config.ai.search_timeout())
+        .with_token_issuer(config.auth.token_issuer())
+        .with_admins(config.auth.admins.clone());
+    if let Some(archive) = archive {
+        info!("Archiving finished games in {}", config.storage.history_url.as_deref().unwrap_or_default());
+        state = state.with_archive(archive);
+    }
+    if config.auth.jwt_secret.is_none() {
+        warn!("No jwt_secret is configured, so logins won't survive a restart");
+    }
 
-    // Create the router
-    let app = create_router().with_state(state);
+    // Keep up with the changes other servers sharing the store make
+    match spawn_store_sync(state.clone()).await {
+        Ok(Some(_)) => info!("Following changes made by other servers"),
+        Ok(None) => {}
+        Err(e) => {
+            error!("Failed to follow changes made by other servers: {}", e);
+            std::process::exit(1);
+        }
+    }
+
+    // Forfeit games whose players run out of time
+    spawn_turn_timer(state.clone(), config.timeouts.turn_check_interval());
 
-    // Get the port from the environment variable or use 3000 as default
-    let port = std::env::var("PORT")
-        .unwrap_or_else(|_| "3000".to_string())
-        .parse::<u16>()
-        .expect("PORT must be a number");
+    // Archive or delete games that have expired
+    spawn_game_collector(state.clone(), config.timeouts.expiry_sweep_interval());
 
-    // Define the address to listen on all interfaces
-    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    // Serve the gRPC API alongside the REST API
+    if let Some(address) = config.grpc_address {
+        #[cfg(feature = "grpc")]
+        {
+            info!("Serving gRPC on {}", address);
+            let state = state.clone();
+            tokio::spawn(async move {
+                if let Err(e) = my_game_project::server::serve_grpc(state, address).await {
+                    error!("gRPC server failed: {}", e);
+                }
+            });
+        }
+        #[cfg(not(feature = "grpc"))]
+        warn!("Ignoring grpc_address {}: the server was built without the grpc feature", address);
+    }
+
+    // Create the router
+    let mut app = create_router_with_cors(config.cors_layer());
+    if config.web_ui {
+        app = app.merge(web_ui());
+    }
+    let app = app.with_state(state);
 
     // Start the server
-    info!("Starting server on {}", addr);
-    Server::bind(&addr)
+    info!("Starting server on {}", config.bind_address);
+    Server::bind(&config.bind_address)
         .serve(app.into_make_service())
         .await
         .unwrap();
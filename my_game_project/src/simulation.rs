@@ -0,0 +1,246 @@
+//! Bots playing each other in bulk, for validating AI changes
+//!
+//! Games are played in parallel, and the results summed into a
+//! [`SimulationReport`] of wins, draws and time spent thinking.
+
+use std::fmt;
+use std::ops::Add;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use rayon::prelude::*;
+
+use crate::ai::{Difficulty, FallibleAI, HeuristicAI, MinimaxAI, PerfectAI};
+use crate::error::{GameError, GameResult};
+use crate::game::{GameState, GameStatus};
+use crate::player::{GamePlayer, Player, PlayerAction};
+
+/// A bot that can take part in simulated games
+///
+/// Bots are named like `minimax:hard`, `rules`, `perfect` or `fallible:0.8` on the command line,
+/// or as players are named by [`PlayerSpec`](crate::player::PlayerSpec), like `ai:hard`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bot {
+    /// A [`MinimaxAI`] at the given difficulty
+    Minimax(Difficulty),
+    /// A [`HeuristicAI`]
+    Rules,
+    /// A [`PerfectAI`]
+    Perfect,
+    /// A [`FallibleAI`] around a hard [`MinimaxAI`], playing its move with the given probability
+    Fallible(f64),
+}
+
+impl Bot {
+    /// Create the bot to play `player`
+    pub fn create(self, player: Player) -> Box<dyn GamePlayer> {
+        match self {
+            Bot::Minimax(difficulty) => Box::new(MinimaxAI::new(player, difficulty)),
+            Bot::Rules => Box::new(HeuristicAI::new(player)),
+            Bot::Perfect => Box::new(PerfectAI::new(player)),
+            Bot::Fallible(accuracy) => {
+                Box::new(FallibleAI::new(MinimaxAI::new(player, Difficulty::Hard), accuracy))
+            }
+        }
+    }
+}
+
+impl FromStr for Bot {
+    type Err = GameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.to_lowercase();
+        match name.strip_prefix("ai:").unwrap_or(&name) {
+            "minimax" => Ok(Bot::Minimax(Difficulty::Medium)),
+            "rules" => Ok(Bot::Rules),
+            "perfect" => Ok(Bot::Perfect),
+            other => {
+                if let Ok(difficulty) = other.strip_prefix("minimax:").unwrap_or(other).parse() {
+                    return Ok(Bot::Minimax(difficulty));
+                }
+                match other.strip_prefix("fallible:").map(str::parse::<f64>) {
+                    Some(Ok(accuracy)) if (0.0..=1.0).contains(&accuracy) => Ok(Bot::Fallible(accuracy)),
+                    _ => Err(GameError::InvalidPlayerType(s.to_string())),
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Bot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Bot::Minimax(difficulty) => write!(f, "minimax:{}", difficulty),
+            Bot::Rules => write!(f, "rules"),
+            Bot::Perfect => write!(f, "perfect"),
+            Bot::Fallible(accuracy) => write!(f, "fallible:{}", accuracy),
+        }
+    }
+}
+
+/// Results for one side of a simulation
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SideStats {
+    /// Number of games won
+    pub wins: usize,
+    /// Number of actions taken
+    pub moves: usize,
+    /// Total time spent deciding on actions
+    pub thinking_time: Duration,
+}
+
+impl SideStats {
+    /// Average time spent deciding on each action
+    pub fn average_move_time(&self) -> Duration {
+        match self.moves {
+            0 => Duration::ZERO,
+            moves => self.thinking_time / moves as u32,
+        }
+    }
+}
+
+impl Add for SideStats {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            wins: self.wins + other.wins,
+            moves: self.moves + other.moves,
+            thinking_time: self.thinking_time + other.thinking_time,
+        }
+    }
+}
+
+/// Combined results of simulated games
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SimulationReport {
+    /// Number of games played
+    pub games: usize,
+    /// Number of games drawn
+    pub draws: usize,
+    /// Results for the X player
+    pub x: SideStats,
+    /// Results for the O player
+    pub o: SideStats,
+}
+
+impl Add for SimulationReport {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            games: self.games + other.games,
+            draws: self.draws + other.draws,
+            x: self.x + other.x,
+            o: self.o + other.o,
+        }
+    }
+}
+
+impl fmt::Display for SimulationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let percent = |count: usize| 100.0 * count as f64 / self.games.max(1) as f64;
+
+        writeln!(f, "Games: {}", self.games)?;
+        for (player, stats) in [(Player::X, &self.x), (Player::O, &self.o)] {
+            writeln!(
+                f,
+                "{} wins: {} ({:.1}%), average move time {:.2?}",
+                player,
+                stats.wins,
+                percent(stats.wins),
+                stats.average_move_time()
+            )?;
+        }
+        write!(f, "Draws: {} ({:.1}%)", self.draws, percent(self.draws))
+    }
+}
+
+/// Play `games` games of `x` against `o` in parallel, each starting from `start`
+///
+/// # Examples
+///
+/// ```
+/// use my_game_project::game::GameState;
+/// use my_game_project::simulation::{simulate, Bot};
+///
+/// let report = simulate(&GameState::new(), Bot::Rules, "minimax:easy".parse().unwrap(), 10).unwrap();
+/// assert_eq!(report.games, 10);
+/// assert_eq!(report.o.wins, 0);
+/// ```
+pub fn simulate(start: &GameState, x: Bot, o: Bot, games: usize) -> GameResult<SimulationReport> {
+    (0..games)
+        .into_par_iter()
+        .map(|_| {
+            let (x, o) = (x.create(Player::X), o.create(Player::O));
+            play_game(start, x.as_ref(), o.as_ref()).map(|(_, report)| report)
+        })
+        .try_reduce(SimulationReport::default, |a, b| Ok(a + b))
+}
+
+/// Play a single game between `x` and `o`, returning how it ended and its report
+pub(crate) fn play_game(
+    start: &GameState,
+    x: &dyn GamePlayer,
+    o: &dyn GamePlayer,
+) -> GameResult<(GameStatus, SimulationReport)> {
+    let bot = |player| if player == Player::X { x } else { o };
+    let mut game = start.clone();
+    let mut report = SimulationReport {
+        games: 1,
+        ..SimulationReport::default()
+    };
+
+    while !game.is_over() {
+        let player = game.current_turn;
+        let started = Instant::now();
+        let action = bot(player).get_action(&game)?;
+
+        let stats = if player == Player::X { &mut report.x } else { &mut report.o };
+        stats.thinking_time += started.elapsed();
+        stats.moves += 1;
+
+        match action {
+            PlayerAction::Move(position) => game.make_move_as(player, position)?,
+            PlayerAction::Resign => game.resign(player)?,
+            PlayerAction::OfferDraw => {
+                game.offer_draw(player)?;
+                if bot(player.opponent()).respond_to_draw_offer(&game)? {
+                    game.accept_draw(player.opponent())?;
+                } else {
+                    game.decline_draw(player.opponent())?;
+                }
+            }
+        }
+    }
+
+    match game.status.winner() {
+        Some(Player::X) => report.x.wins += 1,
+        Some(Player::O) => report.o.wins += 1,
+        None => report.draws += 1,
+    }
+
+    Ok((game.status, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_perfect_players_always_draw() {
+        let hard = "minimax:hard".parse().unwrap();
+        let report = simulate(&GameState::new(), Bot::Rules, hard, 4).unwrap();
+
+        assert_eq!(report.games, 4);
+        assert_eq!(report.draws, 4);
+        // X moves first, so makes the last move of a drawn 3x3 game
+        assert_eq!((report.x.moves, report.o.moves), (20, 16));
+        assert!("mcts".parse::<Bot>().is_err());
+        assert_eq!("ai:hard".parse::<Bot>().unwrap(), hard);
+        assert_eq!(hard.to_string(), "minimax:hard");
+        assert_eq!("fallible:0.75".parse::<Bot>().unwrap(), Bot::Fallible(0.75));
+        assert_eq!(Bot::Fallible(0.75).to_string(), "fallible:0.75");
+        assert!("fallible:1.5".parse::<Bot>().is_err());
+    }
+}
@@ -0,0 +1,281 @@
+//! Aggregate statistics over finished games
+//!
+//! Collects the results of many game histories, from a directory of saved
+//! history files or the games a server holds, into win rates by first move,
+//! game lengths, per-player records and how often each opening is played.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::GameResult;
+use crate::game::GameStatus;
+use crate::history::GameHistory;
+use crate::notation::cell_name;
+use crate::player::Player;
+use crate::position::Position;
+use crate::save::SaveFormat;
+
+/// Number of moves that make up an opening
+pub const OPENING_PLIES: usize = 3;
+
+/// Number of openings listed in the report
+pub const REPORTED_OPENINGS: usize = 10;
+
+/// How games that started with a particular move turned out
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FirstMoveStats {
+    /// Number of games that started with the move
+    pub games: usize,
+    /// Games won by the player who made the move
+    pub wins: usize,
+    /// Games won by their opponent
+    pub losses: usize,
+    /// Games drawn
+    pub draws: usize,
+}
+
+impl FirstMoveStats {
+    /// Returns the fraction of games won by the player who made the move
+    pub fn win_rate(&self) -> f64 {
+        self.wins as f64 / self.games.max(1) as f64
+    }
+}
+
+/// A player's results across the games
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlayerRecord {
+    /// Games the player won
+    pub wins: usize,
+    /// Games the player lost
+    pub losses: usize,
+    /// Games the player drew
+    pub draws: usize,
+}
+
+/// Statistics collected from finished games
+///
+/// Games that haven't finished are skipped. Players are named by their mark
+/// unless [`GameStats::add_with_players`] names them.
+///
+/// # Examples
+///
+/// ```
+/// use my_game_project::game::{GameState, GameStatus};
+/// use my_game_project::player::Player;
+/// use my_game_project::stats::GameStats;
+///
+/// let mut history = GameState::new().create_history();
+/// history.add_move(Player::X, (1, 1));
+/// history.finish(GameStatus::Resigned(Player::O));
+///
+/// let mut stats = GameStats::default();
+/// stats.add(&history);
+/// assert_eq!(stats.games, 1);
+/// assert_eq!(stats.first_moves["b2"].wins, 1);
+/// assert_eq!(stats.players["O"].losses, 1);
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GameStats {
+    /// Number of finished games
+    pub games: usize,
+    /// Number of moves played across the games
+    pub total_moves: usize,
+    /// Games won by X
+    pub x_wins: usize,
+    /// Games won by O
+    pub o_wins: usize,
+    /// Games drawn
+    pub draws: usize,
+    /// Results of the games by their first move, in algebraic notation
+    pub first_moves: BTreeMap<String, FirstMoveStats>,
+    /// Each player's record
+    pub players: BTreeMap<String, PlayerRecord>,
+    /// Number of games that began with each sequence of [`OPENING_PLIES`] moves
+    ///
+    /// Openings that are rotations or reflections of each other are counted
+    /// together, under whichever of them comes first in board order.
+    pub openings: BTreeMap<String, usize>,
+}
+
+impl GameStats {
+    /// Collects statistics from every history file in `dir`
+    ///
+    /// Files that aren't game histories, such as saved game states, are skipped.
+    pub fn load_dir(dir: impl AsRef<Path>) -> GameResult<Self> {
+        let mut stats = Self::default();
+        let entries = std::fs::read_dir(dir)?;
+        for entry in entries {
+            let path = entry?.path();
+            if !SaveFormat::is_save_file(&path) {
+                continue;
+            }
+            if let Ok(history) = GameHistory::load_from_file(&path.to_string_lossy()) {
+                stats.add(&history);
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Adds a game, naming its players `X` and `O`
+    pub fn add(&mut self, history: &GameHistory) {
+        self.add_with_players(history, "X", "O");
+    }
+
+    /// Adds a game between the players named `x` and `o`
+    pub fn add_with_players(&mut self, history: &GameHistory, x: &str, o: &str) {
+        let winner = match history.final_status {
+            None | Some(GameStatus::InProgress) => return,
+            Some(status) => status.winner(),
+        };
+
+        self.games += 1;
+        self.total_moves += history.moves.len();
+        match winner {
+            Some(Player::X) => self.x_wins += 1,
+            Some(Player::O) => self.o_wins += 1,
+            None => self.draws += 1,
+        }
+
+        for (name, player) in [(x, Player::X), (o, Player::O)] {
+            let record = self.players.entry(name.to_string()).or_default();
+            match winner {
+                Some(winner) if winner == player => record.wins += 1,
+                Some(_) => record.losses += 1,
+                None => record.draws += 1,
+            }
+        }
+
+        if let Some(first) = history.moves.first() {
+            let stats = self.first_moves.entry(cell_name(first.position)).or_default();
+            stats.games += 1;
+            match winner {
+                Some(winner) if winner == first.player => stats.wins += 1,
+                Some(_) => stats.losses += 1,
+                None => stats.draws += 1,
+            }
+        }
+
+        if let Some(opening) = canonical_opening(history) {
+            *self.openings.entry(opening).or_default() += 1;
+        }
+    }
+
+    /// Returns the average number of moves in a game
+    pub fn average_length(&self) -> f64 {
+        self.total_moves as f64 / self.games.max(1) as f64
+    }
+
+    /// Returns the `count` most played openings, most played first
+    pub fn top_openings(&self, count: usize) -> Vec<(&str, usize)> {
+        let mut openings: Vec<(&str, usize)> =
+            self.openings.iter().map(|(opening, &games)| (opening.as_str(), games)).collect();
+        openings.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        openings.truncate(count);
+        openings
+    }
+}
+
+impl fmt::Display for GameStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let percent = |count: usize, games: usize| 100.0 * count as f64 / games.max(1) as f64;
+
+        writeln!(f, "Games: {}, averaging {:.1} moves", self.games, self.average_length())?;
+        writeln!(f, "X wins: {} ({:.1}%)", self.x_wins, percent(self.x_wins, self.games))?;
+        writeln!(f, "O wins: {} ({:.1}%)", self.o_wins, percent(self.o_wins, self.games))?;
+        writeln!(f, "Draws: {} ({:.1}%)", self.draws, percent(self.draws, self.games))?;
+
+        writeln!(f, "\nFirst moves:")?;
+        for (cell, stats) in &self.first_moves {
+            writeln!(
+                f,
+                "  {}: {} games, {:.1}% won, {:.1}% drawn",
+                cell,
+                stats.games,
+                100.0 * stats.win_rate(),
+                percent(stats.draws, stats.games)
+            )?;
+        }
+
+        writeln!(f, "\nPlayers:")?;
+        for (name, record) in &self.players {
+            writeln!(f, "  {}: {} won, {} lost, {} drawn", name, record.wins, record.losses, record.draws)?;
+        }
+
+        write!(f, "\nMost played openings:")?;
+        for (opening, games) in self.top_openings(REPORTED_OPENINGS) {
+            write!(f, "\n  {}: {} games", opening, games)?;
+        }
+        Ok(())
+    }
+}
+
+/// The game's first [`OPENING_PLIES`] moves in algebraic notation, turned to their canonical orientation
+///
+/// Of every image of the moves under the board's symmetries, the one that
+/// comes first in row-major order is used, so a symmetric opening keeps the
+/// cells it was played on.
+fn canonical_opening(history: &GameHistory) -> Option<String> {
+    let opening = history.moves.get(..OPENING_PLIES)?;
+    let game = history.initial_state().ok()?;
+    let (rows, cols) = (game.rows(), game.cols());
+
+    let cells = game
+        .symmetries()
+        .into_iter()
+        .map(|symmetry| {
+            opening
+                .iter()
+                .map(|game_move| symmetry.apply((game_move.position.row, game_move.position.col), rows, cols))
+                .collect::<Vec<_>>()
+        })
+        .min()?;
+    let names: Vec<String> = cells.into_iter().map(|cell| cell_name(Position::from(cell))).collect();
+    Some(names.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameState;
+
+    fn finished_game(moves: &[(usize, usize)]) -> GameHistory {
+        let mut game = GameState::new();
+        let mut history = game.create_history();
+        for &position in moves {
+            history.add_move(game.current_turn, position);
+            game.make_move(position).unwrap();
+        }
+        history.finish(game.status);
+        history
+    }
+
+    #[test]
+    fn test_game_stats() {
+        let mut stats = GameStats::default();
+        let x_wins = finished_game(&[(1, 1), (0, 0), (0, 1), (2, 2), (2, 1)]);
+        stats.add_with_players(&x_wins, "alice", "bob");
+        stats.add_with_players(&x_wins, "bob", "alice");
+        stats.add(&finished_game(&[(1, 1), (0, 0), (2, 2), (0, 2), (0, 1), (2, 1), (1, 0), (1, 2), (2, 0)]));
+        stats.add(&finished_game(&[(0, 0), (1, 1), (0, 1), (0, 2), (2, 0), (1, 0), (1, 2), (2, 1)]));
+
+        // The unfinished game isn't counted
+        assert_eq!(stats.games, 3);
+        assert_eq!((stats.x_wins, stats.o_wins, stats.draws), (2, 0, 1));
+        assert!((stats.average_length() - 19.0 / 3.0).abs() < 1e-9);
+
+        let centre = &stats.first_moves["b2"];
+        assert_eq!((centre.games, centre.wins, centre.draws), (3, 2, 1));
+        assert!(!stats.first_moves.contains_key("a1"));
+
+        assert_eq!(stats.players["alice"], PlayerRecord { wins: 1, losses: 1, draws: 0 });
+        assert_eq!(stats.players["X"].draws, 1);
+        assert_eq!(stats.top_openings(1), vec![("b2 a1 b1", 2)]);
+
+        // The same opening turned a half turn counts as the same
+        stats.add(&finished_game(&[(1, 1), (2, 2), (2, 1), (0, 0), (0, 1)]));
+        assert_eq!(stats.top_openings(1), vec![("b2 a1 b1", 3)]);
+    }
+}
@@ -0,0 +1,148 @@
+//! Rotations and reflections of the board
+//!
+//! Turning or mirroring the board doesn't change how a position plays, so a
+//! position and its images under each [`Symmetry`] are equally good for the
+//! same player. Only square boards can be given a quarter turn or mirrored on
+//! a diagonal, and Connect Four's gravity only survives a left-right mirror.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::rules::Variant;
+
+/// A rotation or reflection of the board
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Symmetry {
+    /// The board as it is
+    Identity,
+    /// A quarter turn clockwise
+    Rotate90,
+    /// A half turn
+    Rotate180,
+    /// A quarter turn anticlockwise
+    Rotate270,
+    /// Mirrored left to right
+    FlipHorizontal,
+    /// Mirrored top to bottom
+    FlipVertical,
+    /// Mirrored on the diagonal from the top-left corner
+    Transpose,
+    /// Mirrored on the diagonal from the top-right corner
+    AntiTranspose,
+}
+
+impl Symmetry {
+    /// Every symmetry of a square board, starting with the identity
+    pub const ALL: [Symmetry; 8] = [
+        Symmetry::Identity,
+        Symmetry::Rotate90,
+        Symmetry::Rotate180,
+        Symmetry::Rotate270,
+        Symmetry::FlipHorizontal,
+        Symmetry::FlipVertical,
+        Symmetry::Transpose,
+        Symmetry::AntiTranspose,
+    ];
+
+    /// The symmetries of a `rows` by `cols` board played as `variant`, starting with the identity
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::rules::Variant;
+    /// use my_game_project::symmetry::Symmetry;
+    ///
+    /// assert_eq!(Symmetry::of_board(3, 3, Variant::Standard).len(), 8);
+    /// assert_eq!(Symmetry::of_board(3, 4, Variant::Standard).len(), 4);
+    /// assert_eq!(
+    ///     Symmetry::of_board(6, 7, Variant::ConnectFour),
+    ///     vec![Symmetry::Identity, Symmetry::FlipHorizontal],
+    /// );
+    /// ```
+    pub fn of_board(rows: usize, cols: usize, variant: Variant) -> Vec<Symmetry> {
+        Self::ALL.into_iter().filter(|symmetry| symmetry.fits(rows, cols, variant)).collect()
+    }
+
+    /// Returns true if this is a symmetry of a `rows` by `cols` board played as `variant`
+    pub fn fits(self, rows: usize, cols: usize, variant: Variant) -> bool {
+        match self {
+            _ if variant == Variant::ConnectFour => matches!(self, Symmetry::Identity | Symmetry::FlipHorizontal),
+            Symmetry::Rotate90 | Symmetry::Rotate270 | Symmetry::Transpose | Symmetry::AntiTranspose => rows == cols,
+            _ => true,
+        }
+    }
+
+    /// Where the cell (`row`, `col`) of a `rows` by `cols` board ends up
+    ///
+    /// Quarter turns and diagonal mirrors are only defined for square boards.
+    pub fn apply(self, (row, col): (usize, usize), rows: usize, cols: usize) -> (usize, usize) {
+        let (last_row, last_col) = (rows - 1, cols - 1);
+        match self {
+            Symmetry::Identity => (row, col),
+            Symmetry::Rotate90 => (col, last_row - row),
+            Symmetry::Rotate180 => (last_row - row, last_col - col),
+            Symmetry::Rotate270 => (last_col - col, row),
+            Symmetry::FlipHorizontal => (row, last_col - col),
+            Symmetry::FlipVertical => (last_row - row, col),
+            Symmetry::Transpose => (col, row),
+            Symmetry::AntiTranspose => (last_col - col, last_row - row),
+        }
+    }
+
+    /// The symmetry that undoes this one
+    pub fn inverse(self) -> Symmetry {
+        match self {
+            Symmetry::Rotate90 => Symmetry::Rotate270,
+            Symmetry::Rotate270 => Symmetry::Rotate90,
+            other => other,
+        }
+    }
+
+    /// The symmetry's name, as it is serialized
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Symmetry::Identity => "identity",
+            Symmetry::Rotate90 => "rotate90",
+            Symmetry::Rotate180 => "rotate180",
+            Symmetry::Rotate270 => "rotate270",
+            Symmetry::FlipHorizontal => "flip_horizontal",
+            Symmetry::FlipVertical => "flip_vertical",
+            Symmetry::Transpose => "transpose",
+            Symmetry::AntiTranspose => "anti_transpose",
+        }
+    }
+}
+
+impl fmt::Display for Symmetry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symmetries_are_permutations_undone_by_their_inverse() {
+        for symmetry in Symmetry::ALL {
+            let mut images: Vec<_> = (0..4)
+                .flat_map(|row| (0..4).map(move |col| (row, col)))
+                .map(|cell| {
+                    let image = symmetry.apply(cell, 4, 4);
+                    assert_eq!(symmetry.inverse().apply(image, 4, 4), cell, "{}", symmetry);
+                    image
+                })
+                .collect();
+            images.sort();
+            images.dedup();
+            assert_eq!(images.len(), 16);
+        }
+
+        // The top-left corner visits every corner
+        let corners: Vec<_> = Symmetry::ALL.iter().map(|symmetry| symmetry.apply((0, 0), 3, 3)).collect();
+        assert_eq!(&corners[..4], &[(0, 0), (0, 2), (2, 2), (2, 0)]);
+    }
+}
@@ -0,0 +1,423 @@
+//! Tournaments between many players, ranked by points and ELO rating
+//!
+//! Players are registered with a [`Tournament`], which plays every game of
+//! a round-robin or Swiss tournament and returns [`TournamentResults`]: a
+//! ranked table and the results of every pairing, which serialize to JSON
+//! or CSV.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::error::{GameError, GameResult};
+use crate::game::{GameState, GameStatus};
+use crate::player::{GamePlayer, Player};
+use crate::rating::{rate_game, PlayerRating};
+use crate::simulation::{play_game, Bot};
+
+/// Creates a player for one side of a game
+type PlayerFactory = Box<dyn Fn(Player) -> Box<dyn GamePlayer> + Send + Sync>;
+
+/// A player registered in a tournament
+struct Entrant {
+    name: String,
+    create: PlayerFactory,
+}
+
+/// A finished game: the indexes of the X and O entrants and how it ended
+type GameRecord = (usize, usize, GameStatus);
+
+/// A tournament between registered players
+///
+/// Each pairing plays `games_per_pairing` games, with the players taking
+/// turns to play X.
+pub struct Tournament {
+    /// The position every game starts from
+    start: GameState,
+    /// The players taking part, in registration order
+    entrants: Vec<Entrant>,
+    /// Number of games each pairing plays
+    games_per_pairing: usize,
+}
+
+impl Tournament {
+    /// Create a tournament whose games start from `start`, two games per pairing
+    pub fn new(start: GameState) -> Self {
+        Self {
+            start,
+            entrants: Vec::new(),
+            games_per_pairing: 2,
+        }
+    }
+
+    /// Set the number of games each pairing plays
+    pub fn games_per_pairing(mut self, games: usize) -> Self {
+        self.games_per_pairing = games;
+        self
+    }
+
+    /// Register a player under `name`, created for each game by `create`
+    pub fn register(
+        &mut self,
+        name: impl Into<String>,
+        create: impl Fn(Player) -> Box<dyn GamePlayer> + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.entrants.push(Entrant {
+            name: name.into(),
+            create: Box::new(create),
+        });
+        self
+    }
+
+    /// Register one of the built-in bots, named as on the command line
+    pub fn register_bot(&mut self, bot: Bot) -> &mut Self {
+        self.register(bot.to_string(), move |player| bot.create(player))
+    }
+
+    /// Play every player against every other player
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::game::GameState;
+    /// use my_game_project::simulation::Bot;
+    /// use my_game_project::tournament::Tournament;
+    ///
+    /// let mut tournament = Tournament::new(GameState::new()).games_per_pairing(4);
+    /// tournament.register_bot(Bot::Rules);
+    /// tournament.register_bot("minimax:easy".parse().unwrap());
+    ///
+    /// let results = tournament.round_robin().unwrap();
+    /// assert_eq!(results.standings[0].rating.player_id, "rules");
+    /// assert_eq!(results.pairings.len(), 1);
+    /// ```
+    pub fn round_robin(&self) -> GameResult<TournamentResults> {
+        self.check_entrants()?;
+        let count = self.entrants.len();
+        let pairs: Vec<_> = (0..count)
+            .flat_map(|first| (first + 1..count).map(move |second| (first, second)))
+            .collect();
+
+        let mut results = self.empty_results();
+        let games = self.play_pairings(&pairs)?;
+        results.record(&pairs, &games);
+        Ok(results.ranked())
+    }
+
+    /// Play `rounds` rounds of a Swiss tournament
+    ///
+    /// Each round pairs players with similar scores who haven't met yet. With
+    /// an odd number of players, the lowest placed player who hasn't yet had
+    /// a bye sits the round out and gets a point for it.
+    pub fn swiss(&self, rounds: usize) -> GameResult<TournamentResults> {
+        self.check_entrants()?;
+        let mut results = self.empty_results();
+        let mut played = HashSet::new();
+        let mut byes = HashSet::new();
+
+        for _ in 0..rounds {
+            // Standings are in registration order until ranked, so sort indexes instead
+            let mut order: Vec<usize> = (0..self.entrants.len()).collect();
+            order.sort_by(|&a, &b| results.standings[b].cmp_score(&results.standings[a]));
+
+            let (pairs, bye) = swiss_pairs(&order, &played, &byes);
+            if let Some(index) = bye {
+                results.standings[index].points += 1.0;
+                byes.insert(index);
+            }
+            played.extend(pairs.iter().copied());
+
+            let games = self.play_pairings(&pairs)?;
+            results.record(&pairs, &games);
+        }
+
+        Ok(results.ranked())
+    }
+
+    /// Make sure there are enough players for a tournament
+    fn check_entrants(&self) -> GameResult<()> {
+        match self.entrants.len() {
+            0 | 1 => Err(GameError::InvalidPlayerType("a tournament needs at least two players".to_string())),
+            _ => Ok(()),
+        }
+    }
+
+    /// Results with every player unranked and nothing played
+    fn empty_results(&self) -> TournamentResults {
+        TournamentResults {
+            standings: self
+                .entrants
+                .iter()
+                .map(|entrant| Standing::new(&entrant.name))
+                .collect(),
+            pairings: Vec::new(),
+        }
+    }
+
+    /// Play every game of the given pairings in parallel, returned in a fixed order
+    fn play_pairings(&self, pairs: &[(usize, usize)]) -> GameResult<Vec<GameRecord>> {
+        let games: Vec<_> = pairs
+            .iter()
+            .flat_map(|&(first, second)| {
+                (0..self.games_per_pairing)
+                    .map(move |game| if game % 2 == 0 { (first, second) } else { (second, first) })
+            })
+            .collect();
+
+        games
+            .into_par_iter()
+            .map(|(x, o)| {
+                let x_player = (self.entrants[x].create)(Player::X);
+                let o_player = (self.entrants[o].create)(Player::O);
+                let (status, _) = play_game(&self.start, x_player.as_ref(), o_player.as_ref())?;
+                Ok((x, o, status))
+            })
+            .collect()
+    }
+}
+
+/// Pair players in `order` with the next player they haven't played, if possible
+///
+/// Returns the pairs and the player left without an opponent, if any.
+fn swiss_pairs(
+    order: &[usize],
+    played: &HashSet<(usize, usize)>,
+    byes: &HashSet<usize>,
+) -> (Vec<(usize, usize)>, Option<usize>) {
+    let met = |a: usize, b: usize| played.contains(&(a.min(b), a.max(b)));
+    let mut unpaired = order.to_vec();
+    let mut pairs = Vec::new();
+
+    // With an odd number, the lowest placed player without a bye sits out
+    let bye = (unpaired.len() % 2 == 1).then(|| {
+        let index = unpaired.iter().rposition(|player| !byes.contains(player)).unwrap_or(unpaired.len() - 1);
+        unpaired.remove(index)
+    });
+
+    while !unpaired.is_empty() {
+        let first = unpaired.remove(0);
+        let opponent = unpaired.iter().position(|&other| !met(first, other)).unwrap_or(0);
+        let second = unpaired.remove(opponent);
+        pairs.push((first.min(second), first.max(second)));
+    }
+
+    (pairs, bye)
+}
+
+/// A player's place in the final table
+#[derive(Debug, Clone, Serialize)]
+pub struct Standing {
+    /// Position in the table, starting from 1
+    pub rank: usize,
+    /// The player's name, rating and results
+    #[serde(flatten)]
+    pub rating: PlayerRating,
+    /// Tournament points: 1 for a win or a bye, a half for a draw
+    pub points: f64,
+}
+
+impl Standing {
+    /// An unranked player who hasn't played
+    fn new(name: &str) -> Self {
+        Self {
+            rank: 0,
+            rating: PlayerRating::new(name),
+            points: 0.0,
+        }
+    }
+
+    /// Compare by points, then by rating
+    fn cmp_score(&self, other: &Self) -> std::cmp::Ordering {
+        self.points
+            .total_cmp(&other.points)
+            .then(self.rating.rating.total_cmp(&other.rating.rating))
+    }
+}
+
+/// The results of every game between two players
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PairingResult {
+    /// The player registered first
+    pub first: String,
+    /// The player registered second
+    pub second: String,
+    /// Games won by the first player
+    pub first_wins: u32,
+    /// Games won by the second player
+    pub second_wins: u32,
+    /// Games drawn
+    pub draws: u32,
+}
+
+/// The outcome of a tournament
+#[derive(Debug, Clone, Serialize)]
+pub struct TournamentResults {
+    /// Players from first place to last
+    pub standings: Vec<Standing>,
+    /// The results of every pairing, in the order they were played
+    pub pairings: Vec<PairingResult>,
+}
+
+impl TournamentResults {
+    /// Serialize the results to JSON
+    pub fn to_json(&self) -> GameResult<String> {
+        serde_json::to_string_pretty(self).map_err(GameError::from)
+    }
+
+    /// The standings as CSV, with a header row
+    pub fn standings_csv(&self) -> String {
+        let mut csv = String::from("rank,player,rating,points,wins,draws,losses\n");
+        for standing in &self.standings {
+            csv.push_str(&format!(
+                "{},{},{:.1},{},{},{},{}\n",
+                standing.rank,
+                standing.rating.player_id,
+                standing.rating.rating,
+                standing.points,
+                standing.rating.wins,
+                standing.rating.draws,
+                standing.rating.losses
+            ));
+        }
+        csv
+    }
+
+    /// The pairing results as CSV, with a header row
+    pub fn pairings_csv(&self) -> String {
+        let mut csv = String::from("first,second,first_wins,second_wins,draws\n");
+        for pairing in &self.pairings {
+            csv.push_str(&format!(
+                "{},{},{},{},{}\n",
+                pairing.first, pairing.second, pairing.first_wins, pairing.second_wins, pairing.draws
+            ));
+        }
+        csv
+    }
+
+    /// Score the games played by `pairs`, updating points and ratings in order
+    fn record(&mut self, pairs: &[(usize, usize)], games: &[GameRecord]) {
+        for &(first, second) in pairs {
+            self.pairings.push(PairingResult {
+                first: self.standings[first].rating.player_id.clone(),
+                second: self.standings[second].rating.player_id.clone(),
+                first_wins: 0,
+                second_wins: 0,
+                draws: 0,
+            });
+            let pairing = self.pairings.len() - 1;
+
+            for &(x, o, status) in games.iter().filter(|&&(x, o, _)| (x.min(o), x.max(o)) == (first, second)) {
+                let mut x_rating = self.standings[x].rating.clone();
+                let mut o_rating = self.standings[o].rating.clone();
+                rate_game(&mut x_rating, &mut o_rating, status);
+                self.standings[x].rating = x_rating;
+                self.standings[o].rating = o_rating;
+
+                let result = &mut self.pairings[pairing];
+                match status.winner() {
+                    Some(winner) => {
+                        let winner = if winner == Player::X { x } else { o };
+                        self.standings[winner].points += 1.0;
+                        if winner == first {
+                            result.first_wins += 1;
+                        } else {
+                            result.second_wins += 1;
+                        }
+                    }
+                    None => {
+                        self.standings[x].points += 0.5;
+                        self.standings[o].points += 0.5;
+                        result.draws += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Sort the standings from first to last and number them
+    fn ranked(mut self) -> Self {
+        self.standings.sort_by(|a, b| b.cmp_score(a));
+        for (index, standing) in self.standings.iter_mut().enumerate() {
+            standing.rank = index + 1;
+        }
+        self
+    }
+}
+
+impl fmt::Display for TournamentResults {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:>4}  {:<16} {:>7} {:>6}  {:>4} {:>4} {:>4}", "Rank", "Player", "Rating", "Points", "W", "D", "L")?;
+        for standing in &self.standings {
+            writeln!(
+                f,
+                "{:>4}  {:<16} {:>7.1} {:>6.1}  {:>4} {:>4} {:>4}",
+                standing.rank,
+                standing.rating.player_id,
+                standing.rating.rating,
+                standing.points,
+                standing.rating.wins,
+                standing.rating.draws,
+                standing.rating.losses
+            )?;
+        }
+
+        writeln!(f)?;
+        for pairing in &self.pairings {
+            writeln!(
+                f,
+                "{} vs {}: {}-{} with {} draws",
+                pairing.first, pairing.second, pairing.first_wins, pairing.second_wins, pairing.draws
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_robin_ranks_strongest_first() {
+        let mut tournament = Tournament::new(GameState::new()).games_per_pairing(6);
+        for bot in ["minimax:easy", "rules", "minimax:hard"] {
+            tournament.register_bot(bot.parse().unwrap());
+        }
+
+        let results = tournament.round_robin().unwrap();
+        assert_eq!(results.pairings.len(), 3);
+        assert_eq!(results.standings.last().unwrap().rating.player_id, "minimax:easy");
+        assert_eq!(results.standings.iter().map(|s| s.rank).collect::<Vec<_>>(), [1, 2, 3]);
+
+        // Perfect players only draw against each other
+        let perfect = results.pairings.iter().find(|p| p.first == "rules").unwrap();
+        assert_eq!((perfect.second.as_str(), perfect.draws), ("minimax:hard", 6));
+
+        assert!(results.standings_csv().starts_with("rank,player,rating"));
+        assert_eq!(results.pairings_csv().lines().count(), 4);
+        assert!(results.to_json().unwrap().contains("\"first_wins\""));
+    }
+
+    #[test]
+    fn test_swiss_gives_byes_and_avoids_rematches() {
+        let mut tournament = Tournament::new(GameState::new());
+        for bot in ["minimax:easy", "rules", "minimax:hard"] {
+            tournament.register_bot(bot.parse().unwrap());
+        }
+
+        let results = tournament.swiss(3).unwrap();
+        assert_eq!(results.pairings.len(), 3);
+        let mut met: Vec<_> = results.pairings.iter().map(|p| (p.first.clone(), p.second.clone())).collect();
+        met.sort();
+        met.dedup();
+        assert_eq!(met.len(), 3);
+
+        // Two points from games each round, plus one for the bye
+        let points: f64 = results.standings.iter().map(|s| s.points).sum();
+        assert_eq!(points, 3.0 * (2.0 + 1.0));
+
+        assert!(Tournament::new(GameState::new()).round_robin().is_err());
+    }
+}
@@ -0,0 +1,267 @@
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Alignment, Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use my_game_project::error::GameResult;
+use my_game_project::events::GameEvent;
+use my_game_project::game::{Cell, Game, GameStatus};
+use my_game_project::i18n::{tr, Locale, Text};
+use my_game_project::player::{GamePlayer, Player, PlayerAction};
+use my_game_project::saves::SaveSlots;
+
+use crate::{describe_result, save_game_option};
+
+/// Play a game in a full-screen terminal UI
+///
+/// Human players pick cells with the arrow keys; AI players move on their own.
+pub fn run(game: &mut Game, player1: &dyn GamePlayer, player2: &dyn GamePlayer, saves: &SaveSlots) -> GameResult<()> {
+    let mut terminal = ratatui::init();
+    let mut app = App {
+        game,
+        players: [player1, player2],
+        saves,
+        cursor: (0, 0),
+        message: String::new(),
+        draw_offered: false,
+    };
+
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+    result
+}
+
+/// State of the terminal UI while a game is running
+struct App<'a> {
+    game: &'a mut Game,
+    /// Players for X and O, in that order
+    players: [&'a dyn GamePlayer; 2],
+    /// Where the game is saved
+    saves: &'a SaveSlots,
+    /// Currently selected (row, col)
+    cursor: (usize, usize),
+    /// Feedback about the last action
+    message: String,
+    /// Whether the player to move is being asked to accept a draw
+    draw_offered: bool,
+}
+
+impl<'a> App<'a> {
+    /// Run the event loop until the game ends or the user quits
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> GameResult<()> {
+        loop {
+            terminal.draw(|frame| self.render(frame))?;
+
+            if self.game.is_over() {
+                self.read_key()?;
+                return Ok(());
+            }
+
+            let player = self.player(self.game.current_turn);
+            if !player.is_human() && !self.draw_offered {
+                let action = player.get_action(self.game)?;
+                self.apply(action)?;
+                // The AI may have printed while thinking, so redraw from scratch
+                terminal.clear()?;
+                continue;
+            }
+
+            match self.read_key()? {
+                KeyCode::Char('q') | KeyCode::Esc => {
+                    save_game_option(self.game, self.players[0], self.players[1], self.saves)?;
+                    return Ok(());
+                }
+                key if self.draw_offered => self.answer_draw(key)?,
+                KeyCode::Up => self.move_cursor(-1, 0),
+                KeyCode::Down => self.move_cursor(1, 0),
+                KeyCode::Left => self.move_cursor(0, -1),
+                KeyCode::Right => self.move_cursor(0, 1),
+                KeyCode::Enter | KeyCode::Char(' ') => {
+                    self.apply(PlayerAction::Move(self.cursor.into()))?;
+                }
+                KeyCode::Char('r') => self.apply(PlayerAction::Resign)?,
+                KeyCode::Char('d') => self.apply(PlayerAction::OfferDraw)?,
+                _ => {}
+            }
+        }
+    }
+
+    /// Wait for the next key press
+    fn read_key(&self) -> GameResult<KeyCode> {
+        loop {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    return Ok(key.code);
+                }
+            }
+        }
+    }
+
+    /// Get the player controlling the given side
+    fn player(&self, player: Player) -> &'a dyn GamePlayer {
+        match player {
+            Player::X => self.players[0],
+            Player::O => self.players[1],
+        }
+    }
+
+    /// Move the cursor, clamped to the board
+    fn move_cursor(&mut self, d_row: isize, d_col: isize) {
+        let (max_row, max_col) = (self.game.rows() as isize - 1, self.game.cols() as isize - 1);
+        let (row, col) = self.cursor;
+        self.cursor = (
+            (row as isize + d_row).clamp(0, max_row) as usize,
+            (col as isize + d_col).clamp(0, max_col) as usize,
+        );
+    }
+
+    /// Carry out an action for the player whose turn it is
+    fn apply(&mut self, action: PlayerAction) -> GameResult<()> {
+        let player = self.game.current_turn;
+
+        match action {
+            PlayerAction::Move(position) => {
+                if let Err(e) = self.game.make_move_as(player, position) {
+                    self.message = tr(Text::ErrorMessage, &[&e.localized()]);
+                    return Ok(());
+                }
+            }
+            PlayerAction::Resign => self.game.resign(player)?,
+            PlayerAction::OfferDraw => {
+                self.game.offer_draw(player)?;
+                let opponent = self.player(player.opponent());
+                if opponent.is_human() {
+                    // Ask the opponent in the status bar on the next key press
+                    self.draw_offered = true;
+                    self.message = tr(Text::OffersDraw, &[&player]);
+                } else if opponent.respond_to_draw_offer(self.game)? {
+                    self.game.accept_draw(player.opponent())?;
+                } else {
+                    self.game.decline_draw(player.opponent())?;
+                    self.message = tr(Text::DeclinedDraw, &[&opponent.get_name()]);
+                }
+            }
+        }
+
+        for event in self.game.take_events() {
+            if let GameEvent::MoveMade { player, position } = event {
+                self.message = tr(Text::Played, &[&player, &position]);
+            }
+        }
+        self.finish_turn()
+    }
+
+    /// Accept or decline a pending draw offer from a key press
+    fn answer_draw(&mut self, key: KeyCode) -> GameResult<()> {
+        let responder = self.game.current_turn.opponent();
+        match key {
+            KeyCode::Char(answer) if Locale::current().is_yes(&answer.to_string()) => self.game.accept_draw(responder)?,
+            KeyCode::Char('n') => {
+                self.game.decline_draw(responder)?;
+                self.message = tr(Text::DeclinedDraw, &[&responder]);
+            }
+            _ => return Ok(()),
+        }
+
+        self.draw_offered = false;
+        self.finish_turn()
+    }
+
+    /// Save progress after a turn
+    fn finish_turn(&mut self) -> GameResult<()> {
+        save_game_option(self.game, self.players[0], self.players[1], self.saves)
+    }
+
+    /// Draw the whole screen
+    fn render(&self, frame: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(4)]).areas(frame.area());
+        let [board, sidebar] =
+            Layout::horizontal([Constraint::Min(0), Constraint::Length(26)]).areas(main);
+
+        self.render_board(frame, board);
+        self.render_history(frame, sidebar);
+        self.render_status(frame, status);
+    }
+
+    /// Draw the board with the cursor and any winning line highlighted
+    fn render_board(&self, frame: &mut Frame, area: Rect) {
+        let (rows, cols) = (self.game.rows(), self.game.cols());
+        let show_cursor = !self.game.is_over()
+            && self.player(self.game.current_turn).is_human();
+        let winning_line = self.game.winning_line.as_deref().unwrap_or(&[]);
+
+        let mut lines = Vec::new();
+        for row in 0..rows {
+            if row > 0 {
+                lines.push(Line::from(vec!["───"; cols].join("┼")));
+            }
+
+            let mut spans = Vec::new();
+            for col in 0..cols {
+                if col > 0 {
+                    spans.push(Span::raw("│"));
+                }
+
+                let (symbol, mut style) = match self.game.board[row][col] {
+                    Cell::Empty => (" · ", Style::default().fg(Color::DarkGray)),
+                    Cell::Occupied(Player::X) => (" X ", Style::default().fg(Color::Cyan)),
+                    Cell::Occupied(Player::O) => (" O ", Style::default().fg(Color::Magenta)),
+                };
+                if winning_line.contains(&(row, col)) {
+                    style = style.fg(Color::Green).add_modifier(Modifier::BOLD);
+                }
+                if show_cursor && self.cursor == (row, col) {
+                    style = style.add_modifier(Modifier::REVERSED);
+                }
+                spans.push(Span::styled(symbol, style));
+            }
+            lines.push(Line::from(spans));
+        }
+
+        let title = format!(" Game {} ", self.game.id);
+        let board = Paragraph::new(lines)
+            .alignment(Alignment::Center)
+            .block(Block::bordered().title(title));
+        frame.render_widget(board, area);
+    }
+
+    /// Draw the list of moves played so far
+    fn render_history(&self, frame: &mut Frame, area: Rect) {
+        let items: Vec<ListItem> = self
+            .game
+            .history()
+            .moves
+            .iter()
+            .enumerate()
+            .map(|(i, m)| ListItem::new(format!("{:>3}. {} {}", i + 1, m.player, m.position)))
+            .collect();
+
+        frame.render_widget(List::new(items).block(Block::bordered().title(" Moves ")), area);
+    }
+
+    /// Draw whose turn it is, the last message and the key bindings
+    fn render_status(&self, frame: &mut Frame, area: Rect) {
+        let current = self.game.current_turn;
+        let headline = match self.game.status {
+            GameStatus::InProgress if self.draw_offered => tr(
+                Text::OffersDrawAccept,
+                &[&current, &self.player(current.opponent()).get_name()],
+            ),
+            GameStatus::InProgress => format!(
+                "{} - {}",
+                tr(Text::PlayerTurn, &[&current]),
+                self.player(current).get_name()
+            ),
+            status => format!("{} {}", describe_result(status), tr(Text::PressAnyKey, &[])),
+        };
+
+        let lines = vec![
+            Line::from(Span::styled(headline, Style::default().add_modifier(Modifier::BOLD))),
+            Line::from(format!("{}  |  {}", self.message, tr(Text::TerminalHelp, &[]))),
+        ];
+        frame.render_widget(Paragraph::new(lines).block(Block::bordered()), area);
+    }
+}
@@ -0,0 +1,183 @@
+//! An interactive tutorial for new players
+//!
+//! Each [`Lesson`] sets up a position with [`GameState::from_board`] and asks
+//! for a move that meets its [`Goal`]: a particular cell, a win, a block or a
+//! fork. The moves that meet it are worked out by the engine rather than
+//! listed by hand, so only correct moves are accepted, and new lessons are
+//! just a board, a goal and some text.
+
+use crate::error::{GameError, GameResult};
+use crate::game::{Cell, GameState};
+use crate::player::Player;
+use crate::position::Position;
+use crate::puzzle::Puzzle;
+
+/// What a lesson's move must achieve
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Goal {
+    /// Play in this cell
+    Play(usize, usize),
+    /// Complete a line
+    Win,
+    /// Stop the opponent completing a line on their next move
+    Block,
+    /// Threaten two lines at once, so the opponent can't block both
+    Fork,
+}
+
+/// A guided position, with the player to move asked to find a move that meets the goal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lesson {
+    /// The lesson's title
+    pub title: &'static str,
+    /// What the lesson teaches, shown before the position
+    pub text: &'static str,
+    /// The board, one string per row from the top: `X`, `O`, or `.` for an empty cell
+    pub board: &'static [&'static str],
+    /// The player to move
+    pub player: Player,
+    /// What the move must achieve
+    pub goal: Goal,
+    /// Shown after a wrong move
+    pub hint: &'static str,
+}
+
+/// The tutorial's lessons, in the order they are taught
+pub const LESSONS: &[Lesson] = &[
+    Lesson {
+        title: "Taking turns",
+        text: "Two players, X and O, take turns putting their mark in an empty cell. \
+               X always goes first. Cells are named by column (a to c, from the left) \
+               and row (1 to 3, from the top), so the center is b2. Put an X in the center.",
+        board: &["...", "...", "..."],
+        player: Player::X,
+        goal: Goal::Play(1, 1),
+        hint: "The center is the middle column, b, and the middle row, 2.",
+    },
+    Lesson {
+        title: "Winning",
+        text: "The first player to get three marks in a row wins. Rows, columns and both \
+               diagonals all count. X has two marks down the left column. Finish the line.",
+        board: &["XO.", "X..", "..O"],
+        player: Player::X,
+        goal: Goal::Win,
+        hint: "Look down the a column: which cell would make it three X's?",
+    },
+    Lesson {
+        title: "Blocking",
+        text: "When your opponent has two in a row and the third cell is empty, they will \
+               win on their next move unless you put your mark there first. \
+               O is about to complete the middle row. Block it.",
+        board: &["X..", "OO.", "X.."],
+        player: Player::X,
+        goal: Goal::Block,
+        hint: "Which empty cell would give O three in the middle row?",
+    },
+    Lesson {
+        title: "Win before you block",
+        text: "If you can win, win! Blocking only matters if the game goes on. \
+               Both players have two in a row, and it is X's move.",
+        board: &["XX.", "OO.", "..."],
+        player: Player::X,
+        goal: Goal::Win,
+        hint: "Don't block O's row: finish your own line on the top row.",
+    },
+    Lesson {
+        title: "Forks",
+        text: "A fork threatens to win in two different ways at once. Your opponent can \
+               only block one, so you win with the other. Find a move that gives X two \
+               threats at once.",
+        board: &["XO.", ".X.", "..O"],
+        player: Player::X,
+        goal: Goal::Fork,
+        hint: "Look for an empty cell that lines up with two of your X's along two different lines.",
+    },
+];
+
+impl Lesson {
+    /// The lesson's position
+    pub fn game(&self) -> GameResult<GameState> {
+        let board = self
+            .board
+            .iter()
+            .map(|row| {
+                row.chars()
+                    .map(|mark| match mark {
+                        'X' => Ok(Cell::Occupied(Player::X)),
+                        'O' => Ok(Cell::Occupied(Player::O)),
+                        '.' => Ok(Cell::Empty),
+                        _ => Err(GameError::InvalidConfig(format!("unknown mark {:?} in {}", mark, self.title))),
+                    })
+                    .collect()
+            })
+            .collect::<GameResult<_>>()?;
+        GameState::from_board(board, self.player)
+    }
+
+    /// Every move that meets the lesson's goal, in row-major order
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use my_game_project::position::Position;
+    /// use my_game_project::tutorial::LESSONS;
+    ///
+    /// let forks = LESSONS.iter().find(|lesson| lesson.title == "Forks").unwrap();
+    /// assert_eq!(forks.answers().unwrap(), vec![Position::new(1, 0), Position::new(2, 0)]);
+    /// ```
+    pub fn answers(&self) -> GameResult<Vec<Position>> {
+        let game = self.game()?;
+        let fork_moves = match self.goal {
+            Goal::Fork => Puzzle::from_game(&game, 2).map(|puzzle| puzzle.solutions()).unwrap_or_default(),
+            _ => Vec::new(),
+        };
+
+        let mut answers = Vec::new();
+        for cell in game.legal_moves() {
+            let mut after = game.clone();
+            after.make_move(cell)?;
+            let meets_goal = match self.goal {
+                Goal::Play(row, col) => cell == (row, col),
+                Goal::Win => after.status.winner() == Some(self.player),
+                // The opponent can't win straight away
+                Goal::Block => !after.is_over() && Puzzle::from_game(&after, 1).is_none(),
+                Goal::Fork => fork_moves.contains(&Position::from(cell)),
+            };
+            if meets_goal {
+                answers.push(Position::from(cell));
+            }
+        }
+        Ok(answers)
+    }
+
+    /// Returns true if `position` meets the lesson's goal
+    pub fn accepts(&self, position: Position) -> GameResult<bool> {
+        Ok(self.answers()?.contains(&position))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_lesson_has_a_right_and_a_wrong_answer() {
+        for lesson in LESSONS {
+            let game = lesson.game().unwrap();
+            assert_eq!(game.current_turn, lesson.player, "{}", lesson.title);
+
+            let answers = lesson.answers().unwrap();
+            assert!(!answers.is_empty(), "{}", lesson.title);
+            assert!(answers.len() < game.legal_moves().count(), "{}", lesson.title);
+        }
+
+        // Blocking is the only move that doesn't lose on the spot
+        let blocking = &LESSONS[2];
+        assert_eq!(blocking.answers().unwrap(), vec![Position::new(1, 2)]);
+        assert!(!blocking.accepts(Position::new(0, 1)).unwrap());
+
+        // Blocking O's row is a fine move, but not the winning one
+        let win_first = &LESSONS[3];
+        assert_eq!(win_first.answers().unwrap(), vec![Position::new(0, 2)]);
+    }
+}
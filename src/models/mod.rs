@@ -1,2 +0,0 @@
-pub mod game_state;
-pub mod errors;
\ No newline at end of file